@@ -0,0 +1,43 @@
+//! Pdu::parse() against SmView::parse() for a typical submit_sm.  Run with
+//! `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use smpp::pdu_view::SmView;
+use smpp::pdu_write::write_pdu;
+use smpp::submit_sm_builder::SubmitSmBuilder;
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv};
+use smpp_pdu::pdu::Pdu;
+use std::io::Cursor;
+
+fn submit_sm_frame() -> Vec<u8> {
+    let submit_sm = SubmitSmBuilder::new()
+        .source_addr("MyCompany")
+        .destination_addr("447700900123")
+        .registered_delivery(1)
+        .short_message(&[b'a'; 160])
+        .tlv(Tlv::new(KnownTlvTag::user_message_reference, &[0, 7]))
+        .build()
+        .unwrap();
+    let mut bytes = Vec::new();
+    futures::executor::block_on(write_pdu(
+        &Pdu::new(0, 1, submit_sm.into()).unwrap(),
+        &mut bytes,
+    ))
+    .unwrap();
+    bytes
+}
+
+fn parse(c: &mut Criterion) {
+    let frame = submit_sm_frame();
+    let mut group = c.benchmark_group("submit_sm");
+    group.bench_function("Pdu::parse", |b| {
+        b.iter(|| Pdu::parse(&mut Cursor::new(black_box(&frame))).unwrap())
+    });
+    group.bench_function("SmView::parse", |b| {
+        b.iter(|| SmView::parse(black_box(&frame)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
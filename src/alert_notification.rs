@@ -0,0 +1,157 @@
+//! alert_notification, with which an SMSC tells a receiver that a handset
+//! it could not deliver to earlier is reachable again.  It has no response.
+//!
+//! Like query_sm, it is missing from smpp_pdu, so it is a standalone type
+//! that reads and writes a whole frame, header included.
+
+use smpp_pdu::pdu::formats::{COctetString, WriteStream};
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody};
+use std::convert::TryFrom;
+use std::io::{self, Cursor};
+
+use crate::command_id::CommandId;
+use crate::frame_body::{
+    c_octet_string, c_octet_string_at, command_id, read_bytes, write_frame,
+    Header, HEADER_LENGTH,
+};
+
+pub const ALERT_NOTIFICATION: u32 = CommandId::AlertNotification as u32;
+
+const MAX_LENGTH_ADDR: usize = 65;
+
+/// The ms_availability_status TLV, per section 5.3.2.30 of the spec.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MsAvailabilityStatus {
+    Available = 0,
+    Denied = 1,
+    Unavailable = 2,
+}
+
+impl TryFrom<u8> for MsAvailabilityStatus {
+    type Error = u8;
+
+    /// The value itself is the error if it is not a known status.
+    fn try_from(value: u8) -> Result<Self, u8> {
+        Ok(match value {
+            0 => Self::Available,
+            1 => Self::Denied,
+            2 => Self::Unavailable,
+            _ => return Err(value),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct AlertNotificationPdu {
+    pub sequence_number: u32,
+    pub source_addr_ton: u8,
+    pub source_addr_npi: u8,
+    /// The handset that is reachable again
+    pub source_addr: COctetString,
+    pub esme_addr_ton: u8,
+    pub esme_addr_npi: u8,
+    /// The ESME that asked to be told, by setting set_dpf in a submit_sm
+    pub esme_addr: COctetString,
+    pub tlvs: Tlvs,
+}
+
+impl AlertNotificationPdu {
+    /// An alert that source_addr can be reached by esme_addr, both with TON
+    /// and NPI 0, and with ms_availability_status if given.  Set those
+    /// directly if they matter.
+    pub fn new(
+        sequence_number: u32,
+        source_addr: &str,
+        esme_addr: &str,
+        ms_availability_status: Option<MsAvailabilityStatus>,
+    ) -> Result<Self, PduParseError> {
+        let tlvs: Vec<Tlv> = ms_availability_status
+            .map(|status| {
+                Tlv::new(KnownTlvTag::ms_availability_status, &[status as u8])
+            })
+            .into_iter()
+            .collect();
+        Ok(Self {
+            sequence_number,
+            source_addr_ton: 0,
+            source_addr_npi: 0,
+            source_addr: c_octet_string(
+                "source_addr",
+                source_addr,
+                MAX_LENGTH_ADDR,
+            )?,
+            esme_addr_ton: 0,
+            esme_addr_npi: 0,
+            esme_addr: c_octet_string("esme_addr", esme_addr, MAX_LENGTH_ADDR)?,
+            tlvs: Tlvs::from(&tlvs[..]),
+        })
+    }
+
+    /// Does this complete frame (as accepted by Pdu::check) hold an
+    /// alert_notification?
+    pub fn is_alert_notification(frame: &[u8]) -> bool {
+        command_id(frame) == Some(ALERT_NOTIFICATION)
+    }
+
+    /// The ms_availability_status TLV, if present, as an
+    /// MsAvailabilityStatus, or the raw value if it is not one.
+    pub fn ms_availability_status(
+        &self,
+    ) -> Option<Result<MsAvailabilityStatus, u8>> {
+        let tlv = self.tlvs.get(KnownTlvTag::ms_availability_status)?;
+        tlv.value
+            .first()
+            .map(|v| MsAvailabilityStatus::try_from(*v))
+    }
+
+    pub fn parse(frame: &[u8]) -> Result<Self, PduParseError> {
+        let header = Header::parse(frame, ALERT_NOTIFICATION)?;
+        if header.command_status != 0 {
+            return Err(header.error(PduParseError::new(
+                PduParseErrorBody::StatusIsNotZero,
+            )));
+        }
+        let mut body = Cursor::new(&frame[HEADER_LENGTH..]);
+        let [source_addr_ton, source_addr_npi] =
+            read_bytes(&mut body, &header, "source_addr_npi")?;
+        let source_addr = c_octet_string_at(
+            &mut body,
+            &header,
+            "source_addr",
+            MAX_LENGTH_ADDR,
+        )?;
+        let [esme_addr_ton, esme_addr_npi] =
+            read_bytes(&mut body, &header, "esme_addr_npi")?;
+        let esme_addr = c_octet_string_at(
+            &mut body,
+            &header,
+            "esme_addr",
+            MAX_LENGTH_ADDR,
+        )?;
+        let tlvs = Tlvs::read(&mut body).map_err(|e| {
+            header.error(PduParseError::from(e).into_with_field_name("tlvs"))
+        })?;
+        Ok(Self {
+            sequence_number: header.sequence_number,
+            source_addr_ton,
+            source_addr_npi,
+            source_addr,
+            esme_addr_ton,
+            esme_addr_npi,
+            esme_addr,
+            tlvs,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        let mut body: Vec<u8> = Vec::new();
+        body.extend([self.source_addr_ton, self.source_addr_npi]);
+        self.source_addr.write(&mut body).await?;
+        body.extend([self.esme_addr_ton, self.esme_addr_npi]);
+        self.esme_addr.write(&mut body).await?;
+        self.tlvs.write(&mut body).await?;
+        write_frame(stream, ALERT_NOTIFICATION, 0, self.sequence_number, &body)
+            .await
+    }
+}
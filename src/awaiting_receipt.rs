@@ -0,0 +1,72 @@
+//! Something to remember about each message until its delivery receipt
+//! comes, keyed by message_id.  Receipts may never come, so the oldest
+//! are forgotten once there are too many, or once they are too old.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, TokioClock};
+
+/// How many messages to remember at most
+pub const MAX_AWAITING_RECEIPTS: usize = 100_000;
+
+/// How long to remember a message.  SMSCs usually give up on a message,
+/// and send its receipt, within a couple of days.
+pub const MAX_RECEIPT_WAIT: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+
+pub(crate) struct AwaitingReceipt<V> {
+    entries: HashMap<String, (Instant, V)>,
+    /// When each message_id was inserted, oldest first.  May also hold
+    /// message_ids that have since been taken or inserted again.
+    order: VecDeque<(Instant, String)>,
+    capacity: usize,
+    max_age: Duration,
+}
+
+impl<V> AwaitingReceipt<V> {
+    pub fn new(capacity: usize, max_age: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+            max_age,
+        }
+    }
+
+    pub fn insert(&mut self, message_id: &str, value: V) {
+        let now = TokioClock.now();
+        self.entries.insert(String::from(message_id), (now, value));
+        self.order.push_back((now, String::from(message_id)));
+        self.expire(now);
+    }
+
+    /// What was inserted for message_id, which is then forgotten.
+    pub fn take(&mut self, message_id: &str) -> Option<V> {
+        self.expire(TokioClock.now());
+        self.entries.remove(message_id).map(|(_, value)| value)
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some((inserted, message_id)) = self.order.front() {
+            let too_old = now.duration_since(*inserted) > self.max_age;
+            if !too_old && self.entries.len() <= self.capacity {
+                break;
+            }
+            if self.is_current(*inserted, message_id) {
+                self.entries.remove(message_id);
+            }
+            self.order.pop_front();
+        }
+        if self.order.len() > 2 * self.capacity.max(1) {
+            let entries = &self.entries;
+            self.order.retain(|(inserted, message_id)| {
+                matches!(entries.get(message_id), Some((at, _)) if at == inserted)
+            });
+        }
+    }
+
+    /// Whether message_id was last inserted at inserted, and is still here
+    fn is_current(&self, inserted: Instant, message_id: &str) -> bool {
+        matches!(self.entries.get(message_id), Some((at, _)) if *at == inserted)
+    }
+}
@@ -0,0 +1,172 @@
+use clap::Clap;
+use std::fs;
+use std::io::{self, Read};
+use std::process;
+
+use smpp::parse_error::{ErrorSeverity, RecommendedStatus, Severity};
+use smpp::pdu_dump::{hex_dump, PduDump};
+use smpp::pdu_split::{PduSplitter, StreamBytes};
+use smpp::pdu_status::StatusName;
+use smpp::redact::frame_bytes;
+use smpp::smpp_connection::Frame;
+
+/// Print each SMPP PDU in captured traffic, and why any would not parse
+#[derive(Clap)]
+#[clap(name = "smpp-dump")]
+struct Args {
+    /// File holding the PDUs one after another as sent, or - for stdin
+    #[clap(default_value = "-")]
+    file: String,
+
+    /// FILE is a pcap capture: print the PDUs in each TCP stream in it.
+    /// Needs the pcap feature.
+    #[clap(long)]
+    pcap: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+    let bytes = match read_input(&args.file) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("smpp-dump: {}: {}", args.file, e);
+            process::exit(2);
+        }
+    };
+    let result = if args.pcap {
+        dump_pcap(&bytes)
+    } else {
+        let mut splitter = PduSplitter::new();
+        splitter.push(&bytes);
+        let mut dumper = Dumper::default();
+        dumper.dump_stream(&mut splitter, |offset| {
+            format!("at offset {} ({:#x})", offset, offset)
+        });
+        dumper.dump_leftover(splitter.finish());
+        Ok(!dumper.any_failed)
+    };
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("smpp-dump: {}: {}", args.file, e);
+            process::exit(2);
+        }
+    }
+}
+
+fn read_input(file: &str) -> io::Result<Vec<u8>> {
+    if file == "-" {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        Ok(bytes)
+    } else {
+        fs::read(file)
+    }
+}
+
+#[cfg(feature = "pcap")]
+fn dump_pcap(bytes: &[u8]) -> io::Result<bool> {
+    use smpp::pcap::tcp_segments;
+    use std::collections::HashMap;
+
+    let segments = tcp_segments(bytes)?;
+    let start = segments.first().map(|s| s.timestamp).unwrap_or_default();
+    let mut streams = HashMap::new();
+    let mut dumper = Dumper::default();
+    for segment in segments {
+        let key = (segment.source, segment.destination);
+        let splitter = streams.entry(key).or_insert_with(PduSplitter::new);
+        splitter.push(&segment.payload);
+        let elapsed = segment.timestamp.saturating_sub(start);
+        dumper.dump_stream(splitter, |offset| {
+            format!(
+                "{} -> {} at {}.{:06}s, stream offset {}",
+                key.0,
+                key.1,
+                elapsed.as_secs(),
+                elapsed.subsec_micros(),
+                offset
+            )
+        });
+    }
+    for ((source, destination), splitter) in streams {
+        if let Some(leftover) = splitter.finish() {
+            println!("{} -> {}:", source, destination);
+            dumper.dump_leftover(Some(leftover));
+        }
+    }
+    Ok(!dumper.any_failed)
+}
+
+#[cfg(not(feature = "pcap"))]
+fn dump_pcap(_bytes: &[u8]) -> io::Result<bool> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "smpp-dump was built without the pcap feature",
+    ))
+}
+
+#[derive(Default)]
+struct Dumper {
+    count: usize,
+    any_failed: bool,
+}
+
+impl Dumper {
+    /// Print every whole PDU splitter has, each headed by location(offset).
+    fn dump_stream(
+        &mut self,
+        splitter: &mut PduSplitter,
+        location: impl Fn(usize) -> String,
+    ) {
+        while let Some(next) = splitter.next_pdu() {
+            match next {
+                Ok(StreamBytes { offset, bytes }) => {
+                    self.count += 1;
+                    println!("PDU {} {}:", self.count, location(offset));
+                    print!("{}", PduDump::from_bytes(&bytes));
+                    if let Err(e) = Frame::parse(&bytes) {
+                        self.any_failed = true;
+                        println!("Parse error: {}", e);
+                        println!(
+                            "An SMSC would respond with {}, then {}",
+                            StatusName(e.recommended_status() as u32),
+                            match e.severity() {
+                                ErrorSeverity::RequestRecoverable => {
+                                    "carry on"
+                                }
+                                ErrorSeverity::SessionFatal => {
+                                    "close the connection"
+                                }
+                            }
+                        );
+                    }
+                }
+                Err(e) => {
+                    self.any_failed = true;
+                    println!(
+                        "Cannot find any more PDUs {}: {}",
+                        location(e.rest.offset),
+                        e.error
+                    );
+                    print!("{}", hex_dump(frame_bytes(&e.rest.bytes)));
+                }
+            }
+            println!();
+        }
+    }
+
+    fn dump_leftover(&mut self, leftover: Option<StreamBytes>) {
+        if let Some(StreamBytes { offset, bytes }) = leftover {
+            self.any_failed = true;
+            println!(
+                "Incomplete PDU at offset {} ({:#x}): only {} bytes",
+                offset,
+                offset,
+                bytes.len()
+            );
+            print!("{}", hex_dump(frame_bytes(&bytes)));
+        }
+    }
+}
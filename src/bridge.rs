@@ -0,0 +1,375 @@
+//! Passing accepted submits on to an upstream SMSC, and its delivery
+//! receipts back to the ESME that sent each message.  The core of an
+//! aggregator: an SmscLogic decides who may bind and which Bridge each
+//! submit_sm goes to, and the Bridge does the rest.  GatewayBuilder puts
+//! this together with a routing table.
+//!
+//! ESMEs never see an upstream's message_ids.  Each message is given one
+//! of ours, and receipts are rewritten to carry it before being passed on.
+//!
+//! A session that closes, or fails to send, is reconnected and bound
+//! again.  The submit_sm that found it closed fails, rather than risk
+//! sending it twice.
+
+use log::*;
+use smpp_pdu::pdu::{Pdu, PduStatus, SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+use crate::async_result::AsyncResult;
+use crate::awaiting_receipt::{
+    AwaitingReceipt, MAX_AWAITING_RECEIPTS, MAX_RECEIPT_WAIT,
+};
+use crate::client::{BindMode, Client, ClientError};
+use crate::clock::{Clock, TokioClock};
+use crate::delivery_receipt::DeliveryReceipt;
+use crate::message_unique_key::MessageUniqueKey;
+use crate::pdu_accessors::SmAccessors;
+use crate::pdu_clone::PduClone;
+use crate::smsc::message_id_map::replace_receipted_message_id;
+use crate::smsc::{Smsc, SubmitSmError};
+use crate::unique_id::MessageUid;
+
+/// registered_delivery bits asking for a final delivery receipt
+const RECEIPT_REQUESTED: u8 = 0x03;
+
+/// A Bridge is unhealthy after this many failed submits in a row...
+pub const MAX_CONSECUTIVE_FAILURES: usize = 3;
+
+/// ...until this long after the last of them, when it gets another chance.
+pub const RETRY_UNHEALTHY_AFTER: Duration = Duration::from_secs(30);
+
+/// How long to wait before trying again to reconnect a closed session
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// An SMSC we pass messages on to
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Upstream {
+    /// Used in logs, and as the namespace_id of its messages
+    pub name: String,
+    pub address: String,
+    pub system_id: String,
+    pub password: String,
+    /// How many sessions to bind and use in turn, e.g. where the upstream
+    /// limits the rate of each.  new() makes 1.
+    pub connections: usize,
+}
+
+impl Upstream {
+    pub fn new(
+        name: &str,
+        address: &str,
+        system_id: &str,
+        password: &str,
+    ) -> Self {
+        Self {
+            name: String::from(name),
+            address: String::from(address),
+            system_id: String::from(system_id),
+            password: String::from(password),
+            connections: 1,
+        }
+    }
+}
+
+/// Told of each delivery receipt from an upstream before it is passed on,
+/// e.g. to POST it to a webhook.  The receipt still has the upstream's
+/// message_id.
+pub trait ReceiptSink {
+    fn receipt(&self, upstream: &str, receipt: &DeliveryReceipt);
+}
+
+/// Sessions bound as transceivers to one upstream, used in turn
+pub struct ClientPool {
+    upstream: Upstream,
+    /// Each replaced by a new session when it closes
+    clients: Vec<std::sync::Mutex<Arc<Client>>>,
+    next: AtomicUsize,
+    /// Held while a session is being replaced, so that it is only replaced
+    /// once
+    reconnecting: Mutex<()>,
+}
+
+impl ClientPool {
+    /// Connect and bind upstream.connections sessions, or at least one.
+    pub async fn connect(upstream: &Upstream) -> Result<Self, ClientError> {
+        let mut clients = Vec::new();
+        for _ in 0..upstream.connections.max(1) {
+            let client = Client::connect(&upstream.address).await?;
+            bind(&client, upstream).await?;
+            clients.push(std::sync::Mutex::new(Arc::new(client)));
+        }
+        info!(
+            "Bound {} sessions to upstream {} at {}",
+            clients.len(),
+            upstream.name,
+            upstream.address
+        );
+        Ok(Self {
+            upstream: upstream.clone(),
+            clients,
+            next: AtomicUsize::new(0),
+            reconnecting: Mutex::new(()),
+        })
+    }
+
+    /// The session after the one next() returned last time
+    pub fn next(&self) -> Arc<Client> {
+        self.client(self.next_slot())
+    }
+
+    /// The sessions, as they are now
+    pub fn clients(&self) -> Vec<Arc<Client>> {
+        (0..self.clients.len())
+            .map(|slot| self.client(slot))
+            .collect()
+    }
+
+    fn next_slot(&self) -> usize {
+        self.next.fetch_add(1, Ordering::Relaxed) % self.clients.len()
+    }
+
+    fn client(&self, slot: usize) -> Arc<Client> {
+        Arc::clone(&self.clients[slot].lock().unwrap())
+    }
+
+    /// Replace failed, the session in slot, with a new one bound as it
+    /// was, unless that has been done already.  Returns the session in
+    /// slot.
+    async fn reconnect(
+        &self,
+        slot: usize,
+        failed: &Arc<Client>,
+    ) -> Result<Arc<Client>, ClientError> {
+        let _reconnecting = self.reconnecting.lock().await;
+        let current = self.client(slot);
+        if !Arc::ptr_eq(&current, failed) {
+            return Ok(current);
+        }
+        info!(
+            "Upstream {} - reconnecting to {}",
+            self.upstream.name, self.upstream.address
+        );
+        let client = failed.reconnect(&self.upstream.address).await?;
+        bind(&client, &self.upstream).await?;
+        let client = Arc::new(client);
+        *self.clients[slot].lock().unwrap() = Arc::clone(&client);
+        Ok(client)
+    }
+}
+
+async fn bind(client: &Client, upstream: &Upstream) -> Result<(), ClientError> {
+    client
+        .bind(
+            BindMode::Transceiver,
+            &upstream.system_id,
+            &upstream.password,
+            "",
+        )
+        .await
+}
+
+/// Forwards submits to one upstream.  Call relay_receipts() once the Smsc
+/// has started.
+pub struct Bridge {
+    name: String,
+    pool: ClientPool,
+    /// Our message_id for each of the upstream's, until its receipt comes
+    message_ids: std::sync::Mutex<AwaitingReceipt<String>>,
+    failures: std::sync::Mutex<Failures>,
+}
+
+/// Submits that failed since the last one that did not
+#[derive(Default)]
+struct Failures {
+    count: usize,
+    last: Option<Instant>,
+}
+
+impl Bridge {
+    pub fn new(name: &str, pool: ClientPool) -> Self {
+        Self {
+            name: String::from(name),
+            pool,
+            message_ids: std::sync::Mutex::new(AwaitingReceipt::new(
+                MAX_AWAITING_RECEIPTS,
+                MAX_RECEIPT_WAIT,
+            )),
+            failures: std::sync::Mutex::new(Failures::default()),
+        }
+    }
+
+    pub async fn connect(upstream: &Upstream) -> Result<Self, ClientError> {
+        Ok(Self::new(
+            &upstream.name,
+            ClientPool::connect(upstream).await?,
+        ))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// False after MAX_CONSECUTIVE_FAILURES submits in a row failed with
+    /// SubmitSmError::InternalError, i.e. the upstream could not be
+    /// reached or answered ESME_RSYSERR or similar, until
+    /// RETRY_UNHEALTHY_AFTER has passed.
+    pub fn is_healthy(&self) -> bool {
+        let failures = self.failures.lock().unwrap();
+        failures.count < MAX_CONSECUTIVE_FAILURES
+            || match failures.last {
+                Some(last) => {
+                    TokioClock.now().duration_since(last)
+                        >= RETRY_UNHEALTHY_AFTER
+                }
+                None => true,
+            }
+    }
+
+    /// Pass pdu on, and return what SmscLogic::submit_sm should.  The
+    /// upstream's rejections are passed back where SubmitSmError can say
+    /// the same thing.
+    pub async fn submit_sm(
+        &self,
+        pdu: &SubmitSmPdu,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        let result = self.forward(pdu).await;
+        let mut failures = self.failures.lock().unwrap();
+        if matches!(result, Err(SubmitSmError::InternalError)) {
+            failures.count += 1;
+            failures.last = Some(TokioClock.now());
+        } else {
+            failures.count = 0;
+        }
+        result
+    }
+
+    async fn forward(
+        &self,
+        pdu: &SubmitSmPdu,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        let slot = self.pool.next_slot();
+        let client = self.pool.client(slot);
+        let resp = match client.submit_sm(pdu.pdu_clone()).await {
+            Ok(resp) => resp,
+            Err(e) => {
+                warn!("Upstream {} - submit_sm failed: {}", self.name, e);
+                if let ClientError::Closed | ClientError::Io(_) = e {
+                    if let Err(e) = self.pool.reconnect(slot, &client).await {
+                        warn!(
+                            "Upstream {} - could not reconnect: {}",
+                            self.name, e
+                        );
+                    }
+                }
+                return Err(SubmitSmError::InternalError);
+            }
+        };
+        let upstream_id = match (resp.pdu_status(), resp.message_id) {
+            (Some(PduStatus::ESME_ROK), Some(message_id)) => message_id,
+            (Some(PduStatus::ESME_RTHROTTLED), _) => {
+                return Err(SubmitSmError::Throttled)
+            }
+            (Some(PduStatus::ESME_RINVDSTADR), _) => {
+                return Err(SubmitSmError::InvalidDestination)
+            }
+            _ => {
+                warn!(
+                    "Upstream {} - submit_sm rejected with command_status \
+                    {:#010x}",
+                    self.name, resp.command_status
+                );
+                return Err(SubmitSmError::InternalError);
+            }
+        };
+        let message_id = MessageUid::next().0.to_string();
+        if pdu.registered_delivery() & RECEIPT_REQUESTED != 0 {
+            self.message_ids
+                .lock()
+                .unwrap()
+                .insert(&upstream_id, message_id.clone());
+        }
+        Ok((
+            SubmitSmRespPdu::new(&message_id)
+                .map_err(|_| SubmitSmError::InternalError)?,
+            MessageUniqueKey::new(
+                self.name.clone(),
+                message_id,
+                String::from(SmAccessors::destination_addr(pdu)),
+            ),
+        ))
+    }
+
+    /// Start passing each session's receipts to smsc, one task per
+    /// session, which also reconnects the session when it closes.  Abort
+    /// the tasks to stop.
+    pub fn relay_receipts(
+        self: &Arc<Self>,
+        smsc: &Arc<Mutex<Smsc>>,
+        sink: Option<Arc<dyn ReceiptSink + Send + Sync>>,
+    ) -> Vec<JoinHandle<()>> {
+        (0..self.pool.clients.len())
+            .map(|slot| {
+                tokio::spawn(Arc::clone(self).relay(
+                    slot,
+                    Arc::clone(smsc),
+                    sink.clone(),
+                ))
+            })
+            .collect()
+    }
+
+    async fn relay(
+        self: Arc<Self>,
+        slot: usize,
+        smsc: Arc<Mutex<Smsc>>,
+        sink: Option<Arc<dyn ReceiptSink + Send + Sync>>,
+    ) {
+        let mut sequence_number: u32 = 0;
+        loop {
+            let client = self.pool.client(slot);
+            while let Some(receipt) = client.next_receipt().await {
+                if let Some(sink) = &sink {
+                    sink.receipt(&self.name, &receipt);
+                }
+                sequence_number = sequence_number % 0x7fff_ffff + 1;
+                if let Err(e) =
+                    self.pass_on(receipt, sequence_number, &smsc).await
+                {
+                    warn!(
+                        "Upstream {} - could not pass on receipt: {}",
+                        self.name, e
+                    );
+                }
+            }
+            info!("Upstream {} closed", self.name);
+            if let Err(e) = self.pool.reconnect(slot, &client).await {
+                warn!("Upstream {} - could not reconnect: {}", self.name, e);
+                sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+
+    async fn pass_on(
+        &self,
+        receipt: DeliveryReceipt,
+        sequence_number: u32,
+        smsc: &Mutex<Smsc>,
+    ) -> AsyncResult<()> {
+        let upstream_id =
+            receipt.message_id.ok_or("Receipt has no message_id")?;
+        let message_id = self
+            .message_ids
+            .lock()
+            .unwrap()
+            .take(&upstream_id)
+            .ok_or_else(|| format!("Unknown message_id {}", upstream_id))?;
+        let pdu = Pdu::new(0, sequence_number, receipt.pdu.into())?;
+        let pdu = replace_receipted_message_id(&pdu, &upstream_id, &message_id);
+        smsc.lock().await.receive_pdu(&self.name, pdu).await
+    }
+}
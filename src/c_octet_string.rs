@@ -0,0 +1,119 @@
+//! Validation for the C-Octet String (Decimal) and C-Octet String (Hex)
+//! variants defined in https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 3.1
+
+use smpp_pdu::pdu::formats::COctetString;
+use smpp_pdu::pdu::OctetStringCreationError;
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum COctetStringFormat {
+    /// Any ASCII characters
+    Any,
+    /// Only the digits 0-9
+    Decimal,
+    /// Only the digits 0-9 and the letters a-f and A-F
+    Hex,
+}
+
+impl COctetStringFormat {
+    fn allows(&self, ch: char) -> bool {
+        match self {
+            COctetStringFormat::Any => true,
+            COctetStringFormat::Decimal => ch.is_ascii_digit(),
+            COctetStringFormat::Hex => ch.is_ascii_hexdigit(),
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            COctetStringFormat::Any => "C-Octet String",
+            COctetStringFormat::Decimal => "C-Octet String (Decimal)",
+            COctetStringFormat::Hex => "C-Octet String (Hex)",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum COctetStringFormatError {
+    OctetStringCreationError(String, OctetStringCreationError),
+    WrongFormat {
+        field_name: String,
+        format: COctetStringFormat,
+        position: usize,
+        character: char,
+    },
+}
+
+impl Display for COctetStringFormatError {
+    fn fmt(
+        &self,
+        formatter: &mut Formatter,
+    ) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            COctetStringFormatError::OctetStringCreationError(
+                field_name,
+                e,
+            ) => write!(formatter, "Error reading {}: {}", field_name, e),
+            COctetStringFormatError::WrongFormat {
+                field_name,
+                format,
+                position,
+                character,
+            } => write!(
+                formatter,
+                "{} must be a {}, but it contains {:?} at position {}.",
+                field_name,
+                format.description(),
+                character,
+                position
+            ),
+        }
+    }
+}
+
+impl error::Error for COctetStringFormatError {}
+
+/// Check that every character of value is allowed by format.
+pub fn validate(
+    field_name: &str,
+    value: &COctetString,
+    format: COctetStringFormat,
+) -> Result<(), COctetStringFormatError> {
+    match value
+        .value
+        .chars()
+        .map(|ch| ch.as_char())
+        .enumerate()
+        .find(|(_, ch)| !format.allows(*ch))
+    {
+        Some((position, character)) => {
+            Err(COctetStringFormatError::WrongFormat {
+                field_name: String::from(field_name),
+                format,
+                position,
+                character,
+            })
+        }
+        None => Ok(()),
+    }
+}
+
+/// Read a COctetString as COctetString::read does, and then check it
+/// matches format.
+pub fn read(
+    field_name: &str,
+    bytes: &mut dyn io::BufRead,
+    max_len: usize,
+    format: COctetStringFormat,
+) -> Result<COctetString, COctetStringFormatError> {
+    let value = COctetString::read(bytes, max_len).map_err(|e| {
+        COctetStringFormatError::OctetStringCreationError(
+            String::from(field_name),
+            e,
+        )
+    })?;
+    validate(field_name, &value, format)?;
+    Ok(value)
+}
@@ -0,0 +1,191 @@
+//! cancel_sm and cancel_sm_resp, with which an ESME withdraws messages it
+//! submitted earlier that have yet to be delivered.
+//!
+//! Like query_sm, these are missing from smpp_pdu, so they are standalone
+//! types that read and write a whole frame, header included.
+
+use smpp_pdu::pdu::formats::WriteStream;
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody};
+use std::io::{self, Cursor};
+
+use crate::command_id::CommandId;
+use crate::frame_body::{
+    c_octet_string, command_id, pdu_fields, write_frame, Header, HEADER_LENGTH,
+};
+
+pub const CANCEL_SM: u32 = CommandId::CancelSm as u32;
+pub const CANCEL_SM_RESP: u32 = CommandId::CancelSmResp as u32;
+
+const MAX_LENGTH_SERVICE_TYPE: usize = 6;
+const MAX_LENGTH_MESSAGE_ID: usize = 65;
+const MAX_LENGTH_ADDR: usize = 21;
+
+pdu_fields! {
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct CancelSmPdu {
+        /// With message_id empty, every message from source_addr to
+        /// destination_addr with this service_type is cancelled
+        pub service_type: COctetString(MAX_LENGTH_SERVICE_TYPE),
+        /// The message to cancel, or empty to cancel by address
+        pub message_id: COctetString(MAX_LENGTH_MESSAGE_ID),
+        pub source_addr_ton: u8,
+        pub source_addr_npi: u8,
+        /// Must match the source_addr the message was submitted with
+        pub source_addr: COctetString(MAX_LENGTH_ADDR),
+        pub dest_addr_ton: u8,
+        pub dest_addr_npi: u8,
+        /// Required when message_id is empty
+        pub destination_addr: COctetString(MAX_LENGTH_ADDR),
+    }
+}
+
+/// Just a header: whether the cancel worked is in command_status.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CancelSmRespPdu {
+    pub command_status: u32,
+    pub sequence_number: u32,
+}
+
+impl CancelSmPdu {
+    /// Cancel message_id, submitted from source_addr with TON and NPI 0.
+    /// Set those directly if they matter.
+    pub fn new(
+        sequence_number: u32,
+        message_id: &str,
+        source_addr: &str,
+    ) -> Result<Self, PduParseError> {
+        if message_id.is_empty() {
+            return Err(missing("message_id", "name the message to cancel"));
+        }
+        Self::build(sequence_number, "", message_id, source_addr, "")
+    }
+
+    /// Cancel every undelivered message with service_type from source_addr
+    /// to destination_addr.
+    pub fn new_by_address(
+        sequence_number: u32,
+        service_type: &str,
+        source_addr: &str,
+        destination_addr: &str,
+    ) -> Result<Self, PduParseError> {
+        let pdu = Self::build(
+            sequence_number,
+            service_type,
+            "",
+            source_addr,
+            destination_addr,
+        )?;
+        pdu.validate()?;
+        Ok(pdu)
+    }
+
+    fn build(
+        sequence_number: u32,
+        service_type: &str,
+        message_id: &str,
+        source_addr: &str,
+        destination_addr: &str,
+    ) -> Result<Self, PduParseError> {
+        Ok(Self {
+            sequence_number,
+            service_type: c_octet_string(
+                "service_type",
+                service_type,
+                MAX_LENGTH_SERVICE_TYPE,
+            )?,
+            message_id: c_octet_string(
+                "message_id",
+                message_id,
+                MAX_LENGTH_MESSAGE_ID,
+            )?,
+            source_addr_ton: 0,
+            source_addr_npi: 0,
+            source_addr: c_octet_string(
+                "source_addr",
+                source_addr,
+                MAX_LENGTH_ADDR,
+            )?,
+            dest_addr_ton: 0,
+            dest_addr_npi: 0,
+            destination_addr: c_octet_string(
+                "destination_addr",
+                destination_addr,
+                MAX_LENGTH_ADDR,
+            )?,
+        })
+    }
+
+    /// Does this complete frame (as accepted by Pdu::check) hold a
+    /// cancel_sm?
+    pub fn is_cancel_sm(frame: &[u8]) -> bool {
+        command_id(frame) == Some(CANCEL_SM)
+    }
+
+    /// Does this name the messages to cancel?  Without a message_id, it
+    /// must have a destination_addr.
+    pub fn validate(&self) -> Result<(), PduParseError> {
+        if self.message_id.value.is_empty()
+            && self.destination_addr.value.is_empty()
+        {
+            return Err(missing(
+                "destination_addr",
+                "destination_addr is required without message_id",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn parse(frame: &[u8]) -> Result<Self, PduParseError> {
+        let header = Header::parse(frame, CANCEL_SM)?;
+        if header.command_status != 0 {
+            return Err(header.error(PduParseError::new(
+                PduParseErrorBody::StatusIsNotZero,
+            )));
+        }
+        let pdu = Self::read_body(
+            &header,
+            &mut Cursor::new(&frame[HEADER_LENGTH..]),
+        )?;
+        pdu.validate().map_err(|e| header.error(e))?;
+        Ok(pdu)
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        let body = self.write_body().await?;
+        write_frame(stream, CANCEL_SM, 0, self.sequence_number, &body).await
+    }
+}
+
+impl CancelSmRespPdu {
+    pub fn new(command_status: u32, sequence_number: u32) -> Self {
+        Self {
+            command_status,
+            sequence_number,
+        }
+    }
+
+    pub fn is_cancel_sm_resp(frame: &[u8]) -> bool {
+        command_id(frame) == Some(CANCEL_SM_RESP)
+    }
+
+    pub fn parse(frame: &[u8]) -> Result<Self, PduParseError> {
+        let header = Header::parse(frame, CANCEL_SM_RESP)?;
+        Ok(Self::new(header.command_status, header.sequence_number))
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        write_frame(
+            stream,
+            CANCEL_SM_RESP,
+            self.command_status,
+            self.sequence_number,
+            &[],
+        )
+        .await
+    }
+}
+
+fn missing(name: &str, why: &str) -> PduParseError {
+    PduParseError::new(PduParseErrorBody::IncorrectLength(0, String::from(why)))
+        .into_with_field_name(name)
+}
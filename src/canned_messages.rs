@@ -0,0 +1,149 @@
+//! Canned (predefined) messages, which a submit_sm names by its
+//! sm_default_msg_id instead of carrying text.  SMPP 3.4 (5.2.23) allows
+//! ids from 1 to 254, with 0 meaning the submit_sm carries its own message.
+
+use smpp_pdu::pdu::formats::OctetString;
+use smpp_pdu::pdu::tlvs::KnownTlvTag;
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody, SubmitSmPdu};
+use std::collections::HashMap;
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::pdu_clone::PduClone;
+
+const MAX_LENGTH_SHORT_MESSAGE: usize = 255;
+
+/// Fails unless id is 0, for no canned message, or from 1 to 254.
+pub fn check_sm_default_msg_id(id: u8) -> Result<(), PduParseError> {
+    if id == 0xFF {
+        Err(out_of_range(id))
+    } else {
+        Ok(())
+    }
+}
+
+fn out_of_range(id: u8) -> PduParseError {
+    PduParseError::new(PduParseErrorBody::IncorrectLength(
+        u32::from(id),
+        String::from("sm_default_msg_id must be from 1 to 254, or 0"),
+    ))
+    .into_with_field_name("sm_default_msg_id")
+}
+
+/// The message sent when a submit_sm names id.  Written as ID=TEXT, e.g.
+/// "1=Running late, call you soon".
+#[derive(Clone, Debug, PartialEq)]
+pub struct CannedMessage {
+    pub id: u8,
+    pub short_message: Vec<u8>,
+}
+
+impl CannedMessage {
+    /// Fails if id is not from 1 to 254 or short_message is longer than
+    /// 254 bytes.
+    pub fn new(id: u8, short_message: &[u8]) -> Result<Self, PduParseError> {
+        if id == 0 || id == 0xFF {
+            return Err(out_of_range(id));
+        }
+        OctetString::from_bytes(short_message, MAX_LENGTH_SHORT_MESSAGE)
+            .map_err(|e| {
+                PduParseError::from(e).into_with_field_name("short_message")
+            })?;
+        Ok(Self {
+            id,
+            short_message: Vec::from(short_message),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseCannedMessageError(String);
+
+impl Display for ParseCannedMessageError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "Invalid canned message '{}': expected ID=TEXT, with ID from 1 \
+            to 254 and TEXT at most 254 bytes",
+            self.0
+        )
+    }
+}
+
+impl error::Error for ParseCannedMessageError {}
+
+impl FromStr for CannedMessage {
+    type Err = ParseCannedMessageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseCannedMessageError(String::from(s));
+        let (id, text) = s.split_once('=').ok_or_else(err)?;
+        let id = id.parse().map_err(|_| err())?;
+        Self::new(id, text.as_bytes()).map_err(|_| err())
+    }
+}
+
+/// A submit_sm named a canned message we do not have, which should be
+/// rejected with ESME_RINVDFTMSGID.
+#[derive(Debug, PartialEq)]
+pub struct UnknownCannedMessage(pub u8);
+
+impl Display for UnknownCannedMessage {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "No canned message with sm_default_msg_id {}",
+            self.0
+        )
+    }
+}
+
+impl error::Error for UnknownCannedMessage {}
+
+/// The canned messages an SMSC can send, keyed by id.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CannedMessages {
+    messages: HashMap<u8, Vec<u8>>,
+}
+
+impl CannedMessages {
+    pub fn new(messages: &[CannedMessage]) -> Self {
+        let mut canned_messages = Self::default();
+        for message in messages {
+            canned_messages.insert(message.clone());
+        }
+        canned_messages
+    }
+
+    /// Add message, replacing any with the same id.
+    pub fn insert(&mut self, message: CannedMessage) {
+        self.messages.insert(message.id, message.short_message);
+    }
+
+    pub fn get(&self, id: u8) -> Option<&[u8]> {
+        self.messages.get(&id).map(Vec::as_slice)
+    }
+
+    /// A copy of submit_sm with the canned message it names as its
+    /// short_message, or None if it carries its own message: it names one
+    /// only with a non-zero sm_default_msg_id, an empty short_message and
+    /// no message_payload TLV.  The copy keeps its sm_default_msg_id.
+    pub fn expand(
+        &self,
+        submit_sm: &SubmitSmPdu,
+    ) -> Result<Option<SubmitSmPdu>, UnknownCannedMessage> {
+        let sm = &submit_sm.0;
+        let id = sm.sm_default_msg_id.value;
+        if id == 0
+            || !sm.short_message.value.is_empty()
+            || sm.tlvs.get(KnownTlvTag::message_payload).is_some()
+        {
+            return Ok(None);
+        }
+        let short_message = self.get(id).ok_or(UnknownCannedMessage(id))?;
+        let mut expanded = submit_sm.pdu_clone();
+        expanded.0.short_message.value = Vec::from(short_message);
+        Ok(Some(expanded))
+    }
+}
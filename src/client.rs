@@ -0,0 +1,1606 @@
+//! The ESME side of a session: connect to an SMSC, bind, and send requests
+//! whose responses come back in whatever order the SMSC likes.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! use smpp::client::{BindMode, Client};
+//! use smpp::sender::Sender;
+//! use smpp::submit_sm_builder::SubmitSmBuilder;
+//!
+//! let client = Client::connect("127.0.0.1:2775").await?;
+//! client.bind(BindMode::Transmitter, "esme1", "secret", "").await?;
+//! let submit_sm = SubmitSmBuilder::new()
+//!     .sender(&Sender::Alphanumeric(String::from("MyCompany")))
+//!     .destination("447700900123")
+//!     .registered_delivery(1)
+//!     .short_message(b"hello")
+//!     .build()?;
+//! let resp = client.submit_sm(submit_sm).await?;
+//! println!("{:?}", resp.message_id);
+//! client.unbind().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A task reads everything the SMSC sends.  It answers enquire_link and
+//! deliver_sm itself, and hands responses to the request with the same
+//! sequence_number.  Each deliver_sm is queued by its esm_class: messages
+//! for next_message(), delivery receipts for next_receipt(), and
+//! intermediate notifications for next_notification().  alert_notification,
+//! which has no response, is queued for next_alert_notification().
+
+use log::*;
+use smpp_pdu::pdu::data::bind_data::BindData;
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{
+    BindReceiverPdu, BindTransceiverPdu, BindTransmitterPdu, DeliverSmPdu,
+    EnquireLinkPdu, EnquireLinkRespPdu, Pdu, PduBody, PduParseError, PduStatus,
+    SubmitSmPdu,
+};
+use std::collections::HashMap;
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio::sync::{mpsc, watch, Notify, Semaphore};
+use tokio::task::JoinHandle;
+
+use crate::alert_notification::AlertNotificationPdu;
+use crate::awaiting_receipt::{
+    AwaitingReceipt, MAX_AWAITING_RECEIPTS, MAX_RECEIPT_WAIT,
+};
+use crate::cancel_sm::{CancelSmPdu, CancelSmRespPdu};
+use crate::canned_messages::check_sm_default_msg_id;
+use crate::command_id::{CommandId, CommandName, RESPONSE_BIT};
+use crate::data_sm::{DataSmPdu, DataSmRespPdu};
+use crate::deliver_sm_resp::DeliverSmRespPdu;
+use crate::delivery_receipt::{DeliveryKind, DeliveryReceipt};
+use crate::health::{ClientHealth, RequestOutcomes};
+use crate::in_flight::{InFlight, InFlightError, Stray};
+use crate::parse_error::{ErrorSeverity, Severity};
+use crate::parse_options::ParseOptions;
+use crate::pdu_clone::PduClone;
+use crate::pdu_status::{pdu_status, StatusDescription};
+use crate::profile::{Compatibility, Profile};
+use crate::query_sm::{QuerySmPdu, QuerySmRespPdu};
+use crate::replace_sm::{ReplaceSmPdu, ReplaceSmRespPdu};
+use crate::session_info::SessionInfo;
+use crate::smpp_connection::{Frame, SmppConnection};
+use crate::submit_multi::{SubmitMultiPdu, SubmitMultiRespPdu};
+use crate::submit_sm_builder::SubmitSmBuilder;
+use crate::submit_sm_defaults::SubmitSmDefaults;
+use crate::typed_tlvs::TypedTlvs;
+use crate::unbind::{UnbindPdu, UnbindRespPdu};
+
+/// How long to wait for a response if set_response_timeout() is not called
+pub const DEFAULT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub use crate::session_info::BindMode;
+
+/// Whatever the caller wants to know about a message when its response and
+/// receipt come back, e.g. a campaign id or tenant.  The SMSC never sees
+/// it.
+pub type Metadata = HashMap<String, String>;
+
+#[derive(Debug)]
+pub enum ClientError {
+    Io(io::Error),
+    /// A PDU could not be built, e.g. because a field was too long, or the
+    /// SMSC's response could not be parsed
+    Pdu(PduParseError),
+    /// The SMSC answered with this non-zero command_status
+    Status(u32),
+    /// The SMSC answered with a PDU of the wrong type
+    UnexpectedResponse(CommandId),
+    /// No response arrived within the response timeout
+    Timeout,
+    /// The connection closed before a response arrived
+    Closed,
+    /// quiesce() has been called, so no more messages may be submitted
+    Quiescing,
+}
+
+impl Display for ClientError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(formatter),
+            Self::Pdu(e) => e.fmt(formatter),
+            Self::Status(command_status) => write!(
+                formatter,
+                "SMSC responded with {}",
+                StatusDescription(*command_status)
+            ),
+            Self::UnexpectedResponse(command_id) => write!(
+                formatter,
+                "SMSC responded with unexpected {}",
+                CommandName((*command_id).into())
+            ),
+            Self::Timeout => formatter.write_str("No response from SMSC"),
+            Self::Closed => {
+                formatter.write_str("Connection closed before a response")
+            }
+            Self::Quiescing => {
+                formatter.write_str("Not accepting messages while quiescing")
+            }
+        }
+    }
+}
+
+impl error::Error for ClientError {}
+
+impl ClientError {
+    /// The command_status the SMSC answered with, if it is one the spec
+    /// names.
+    pub fn pdu_status(&self) -> Option<PduStatus> {
+        match self {
+            Self::Status(command_status) => pdu_status(*command_status),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<InFlightError> for ClientError {
+    fn from(e: InFlightError) -> Self {
+        match e {
+            InFlightError::Timeout => Self::Timeout,
+            InFlightError::Closed => Self::Closed,
+        }
+    }
+}
+
+impl From<PduParseError> for ClientError {
+    fn from(e: PduParseError) -> Self {
+        Self::Pdu(e)
+    }
+}
+
+/// The response to a submit_sm.  A non-zero command_status (e.g.
+/// ESME_RTHROTTLED) is an answer rather than an error, so it comes back
+/// here instead of as ClientError::Status.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SubmitSmResp {
+    pub command_status: u32,
+    pub sequence_number: u32,
+    /// None unless command_status is 0
+    pub message_id: Option<String>,
+    /// What submit_sm_with_metadata() was given, or empty
+    pub metadata: Metadata,
+}
+
+impl SubmitSmResp {
+    /// command_status, if it is one the spec names.
+    pub fn pdu_status(&self) -> Option<PduStatus> {
+        pdu_status(self.command_status)
+    }
+}
+
+/// What a client's session is doing.  See Client::state_changes().
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ClientState {
+    /// Connected, but not bound yet
+    Connecting,
+    Bound,
+    /// Bound, but the SMSC did not answer a request in time.  Back to Bound
+    /// when it answers one.
+    Degraded,
+    /// Closed, and being replaced by a new connection.  A Client never
+    /// reconnects by itself.
+    Reconnecting,
+    Closed,
+}
+
+/// A ClientState, when the client entered it, and why.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateChange {
+    pub state: ClientState,
+    pub since: SystemTime,
+    /// e.g. "No response from SMSC", or None for expected changes like
+    /// binding
+    pub cause: Option<String>,
+}
+
+impl StateChange {
+    fn new(state: ClientState, cause: Option<String>) -> Self {
+        Self {
+            state,
+            since: SystemTime::now(),
+            cause,
+        }
+    }
+}
+
+/// Tell anyone watching that the client is now in state, unless it already
+/// was.  Only reconnecting leaves Closed.
+fn set_state(
+    states: &watch::Sender<StateChange>,
+    state: ClientState,
+    cause: Option<String>,
+) {
+    let current = states.borrow().state;
+    if current == state
+        || (current == ClientState::Closed
+            && state != ClientState::Reconnecting)
+    {
+        return;
+    }
+    info!("Client is now {:?}{}", state, Cause(&cause));
+    // Client keeps a receiver, so this only fails once it has gone
+    let _ = states.send(StateChange::new(state, cause));
+}
+
+struct Cause<'a>(&'a Option<String>);
+
+impl<'a> Display for Cause<'a> {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self.0 {
+            Some(cause) => write!(formatter, ": {}", cause),
+            None => Ok(()),
+        }
+    }
+}
+
+enum Response {
+    Pdu(Pdu),
+    UnbindResp {
+        command_status: u32,
+    },
+    DataSmResp(DataSmRespPdu),
+    QuerySmResp(QuerySmRespPdu),
+    CancelSmResp(CancelSmRespPdu),
+    ReplaceSmResp(ReplaceSmRespPdu),
+    SubmitMultiResp(SubmitMultiRespPdu),
+    /// A response we could not parse, but whose sequence_number we read
+    Unparseable(PduParseError),
+}
+
+pub struct Client {
+    connection: Arc<SmppConnection>,
+    in_flight: Arc<InFlight<Response>>,
+    messages: tokio::sync::Mutex<mpsc::UnboundedReceiver<DeliverSmPdu>>,
+    receipts: tokio::sync::Mutex<mpsc::UnboundedReceiver<DeliveryReceipt>>,
+    notifications: tokio::sync::Mutex<mpsc::UnboundedReceiver<DeliverSmPdu>>,
+    alert_notifications:
+        tokio::sync::Mutex<mpsc::UnboundedReceiver<AlertNotificationPdu>>,
+    window: Option<usize>,
+    submit_sm_defaults: SubmitSmDefaults,
+    states: Arc<watch::Sender<StateChange>>,
+    state_changes: watch::Receiver<StateChange>,
+    queued_deliveries: Arc<AtomicUsize>,
+    outstanding: Outstanding,
+    quiescing: AtomicBool,
+    outcomes: std::sync::Mutex<RequestOutcomes>,
+    last_enquire_link_rtt: std::sync::Mutex<Option<Duration>>,
+    /// For each message submitted with metadata, until its receipt comes
+    metadata: Arc<std::sync::Mutex<AwaitingReceipt<Metadata>>>,
+    /// For each submit_sm with metadata awaiting a response, by
+    /// sequence_number, until read_loop() moves it to metadata
+    submitted: Arc<std::sync::Mutex<HashMap<u32, Metadata>>>,
+    compatibility: Arc<std::sync::Mutex<Compatibility>>,
+    strays: Arc<StrayResponses>,
+    producer_window: Option<usize>,
+    /// Each producer's share of the window, by name
+    producers: std::sync::Mutex<HashMap<String, Arc<Semaphore>>>,
+    destination_order: Option<DestinationOrder>,
+    expiry_policy: ExpiryPolicy,
+    /// Whose turn it is to submit to each destination with a submit_sm
+    /// waiting or in flight, while destination_order is set
+    destinations:
+        std::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    reader: JoinHandle<()>,
+}
+
+impl Client {
+    /// Connect over TCP.  Call bind() next.
+    pub async fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let stream = TcpStream::connect(address).await?;
+        let peer_addr = stream.peer_addr()?;
+        Ok(Self::from_connection(SmppConnection::new(
+            stream, peer_addr,
+        )))
+    }
+
+    /// Run a session over an existing connection, e.g. one made with
+    /// SmppConnection::connect_unix() or from_stream().
+    pub fn from_connection(connection: SmppConnection) -> Self {
+        let in_flight = InFlight::new(DEFAULT_RESPONSE_TIMEOUT);
+        Self::start(connection, in_flight, None)
+    }
+
+    /// Wait for an SMSC that has connected to us to send outbind, then bind
+    /// as a receiver with the system_id it asks for.  A connection whose
+    /// first PDU is not outbind fails with UnexpectedResponse.
+    pub async fn accept_outbind(
+        connection: SmppConnection,
+        password: &str,
+        system_type: &str,
+    ) -> Result<Self, ClientError> {
+        let outbind = match connection.read_frame().await? {
+            Some(Frame::Outbind(outbind)) => outbind,
+            Some(frame) => {
+                return Err(ClientError::UnexpectedResponse(frame.command_id()))
+            }
+            None => return Err(ClientError::Closed),
+        };
+        info!("<= {} outbind for {}", connection, outbind.system_id.value);
+        let client = Self::from_connection(connection);
+        client
+            .bind(
+                BindMode::Receiver,
+                outbind.system_id.value.as_str(),
+                password,
+                system_type,
+            )
+            .await?;
+        Ok(client)
+    }
+
+    /// Like from_connection(), but with at most max_outstanding requests
+    /// awaiting a response at once.  Further requests wait their turn.
+    pub fn from_connection_with_window(
+        connection: SmppConnection,
+        max_outstanding: usize,
+    ) -> Self {
+        let in_flight =
+            InFlight::with_window(DEFAULT_RESPONSE_TIMEOUT, max_outstanding);
+        Self::start(connection, in_flight, Some(max_outstanding))
+    }
+
+    fn start(
+        connection: SmppConnection,
+        in_flight: InFlight<Response>,
+        window: Option<usize>,
+    ) -> Self {
+        let (states, state_changes) =
+            watch::channel(StateChange::new(ClientState::Connecting, None));
+        let metadata = Arc::new(std::sync::Mutex::new(AwaitingReceipt::new(
+            MAX_AWAITING_RECEIPTS,
+            MAX_RECEIPT_WAIT,
+        )));
+        Self::start_with(
+            connection,
+            in_flight,
+            window,
+            (Arc::new(states), state_changes),
+            metadata,
+        )
+    }
+
+    /// Like start(), but reporting states to, and remembering metadata in,
+    /// those of an earlier session.
+    fn start_with(
+        connection: SmppConnection,
+        in_flight: InFlight<Response>,
+        window: Option<usize>,
+        (states, state_changes): (
+            Arc<watch::Sender<StateChange>>,
+            watch::Receiver<StateChange>,
+        ),
+        metadata: Arc<std::sync::Mutex<AwaitingReceipt<Metadata>>>,
+    ) -> Self {
+        let connection = Arc::new(connection);
+        let in_flight = Arc::new(in_flight);
+        let (messages_tx, messages) = mpsc::unbounded_channel();
+        let (receipts_tx, receipts) = mpsc::unbounded_channel();
+        let (notifications_tx, notifications) = mpsc::unbounded_channel();
+        let (alert_notifications_tx, alert_notifications) =
+            mpsc::unbounded_channel();
+        let queued_deliveries = Arc::new(AtomicUsize::new(0));
+        let compatibility =
+            Arc::new(std::sync::Mutex::new(Compatibility::default()));
+        let submitted = Arc::new(std::sync::Mutex::new(HashMap::new()));
+        let strays = Arc::new(StrayResponses::default());
+        let deliveries = Deliveries {
+            messages: messages_tx,
+            receipts: receipts_tx,
+            notifications: notifications_tx,
+            alert_notifications: alert_notifications_tx,
+            queued: queued_deliveries.clone(),
+            metadata: metadata.clone(),
+            submitted: submitted.clone(),
+            compatibility: compatibility.clone(),
+        };
+        let reader = tokio::spawn(read_loop(
+            connection.clone(),
+            in_flight.clone(),
+            deliveries,
+            strays.clone(),
+            states.clone(),
+        ));
+        Self {
+            connection,
+            in_flight,
+            messages: tokio::sync::Mutex::new(messages),
+            receipts: tokio::sync::Mutex::new(receipts),
+            notifications: tokio::sync::Mutex::new(notifications),
+            alert_notifications: tokio::sync::Mutex::new(alert_notifications),
+            window,
+            submit_sm_defaults: SubmitSmDefaults::default(),
+            states,
+            state_changes,
+            queued_deliveries,
+            outstanding: Outstanding::default(),
+            quiescing: AtomicBool::new(false),
+            outcomes: std::sync::Mutex::new(RequestOutcomes::default()),
+            last_enquire_link_rtt: std::sync::Mutex::new(None),
+            metadata,
+            submitted,
+            compatibility,
+            strays,
+            producer_window: None,
+            producers: std::sync::Mutex::new(HashMap::new()),
+            destination_order: None,
+            expiry_policy: ExpiryPolicy::default(),
+            destinations: std::sync::Mutex::new(HashMap::new()),
+            reader,
+        }
+    }
+
+    /// Connect to address again, over TCP, to take over from this session,
+    /// e.g. once it has closed.  This one stops reading, and is
+    /// Reconnecting meanwhile; whoever watches its state_changes() sees the
+    /// new session's states from then on.  Settings such as the window and
+    /// profile carry over, and receipts for messages submitted with
+    /// metadata still get it.  Call bind() next.
+    pub async fn reconnect<A: ToSocketAddrs>(
+        &self,
+        address: A,
+    ) -> io::Result<Self> {
+        self.reader.abort();
+        self.in_flight.clear();
+        set_state(&self.states, ClientState::Reconnecting, None);
+        let connection = match TcpStream::connect(address).await {
+            Ok(stream) => {
+                let peer_addr = stream.peer_addr()?;
+                SmppConnection::new(stream, peer_addr)
+            }
+            Err(e) => {
+                set_state(
+                    &self.states,
+                    ClientState::Closed,
+                    Some(e.to_string()),
+                );
+                return Err(e);
+            }
+        };
+        let response_timeout = self.in_flight.response_timeout();
+        let in_flight = match self.window {
+            Some(max_outstanding) => {
+                InFlight::with_window(response_timeout, max_outstanding)
+            }
+            None => InFlight::new(response_timeout),
+        };
+        let mut client = Self::start_with(
+            connection,
+            in_flight,
+            self.window,
+            (Arc::clone(&self.states), self.state_changes.clone()),
+            Arc::clone(&self.metadata),
+        );
+        set_state(&self.states, ClientState::Connecting, None);
+        client.set_compatibility(self.compatibility());
+        client.set_stray_response_policy(self.stray_response_policy());
+        client.submit_sm_defaults = self.submit_sm_defaults.clone();
+        client.producer_window = self.producer_window;
+        client.destination_order = self.destination_order;
+        client.expiry_policy = self.expiry_policy.clone();
+        Ok(client)
+    }
+
+    pub fn set_response_timeout(&mut self, response_timeout: Duration) {
+        self.in_flight.set_response_timeout(response_timeout);
+    }
+
+    /// What to do, from now on, about requests that are not answered
+    /// within the response timeout.  By default they fail.
+    pub fn set_expiry_policy(&mut self, policy: ExpiryPolicy) {
+        self.expiry_policy = policy;
+    }
+
+    /// What to do, from now on, with responses no request is awaiting.
+    /// Each is counted in health() whatever the policy.
+    pub fn set_stray_response_policy(&self, policy: StrayResponsePolicy) {
+        *self.strays.policy.lock().unwrap() = policy;
+    }
+
+    pub fn stray_response_policy(&self) -> StrayResponsePolicy {
+        *self.strays.policy.lock().unwrap()
+    }
+
+    /// The sequence numbers of the requests awaiting a response, lowest
+    /// first, e.g. to see which ones an SMSC is sitting on.
+    pub fn outstanding_sequence_numbers(&self) -> Vec<u32> {
+        self.in_flight.sequence_numbers()
+    }
+
+    /// Let each producer named in as_producer() have at most max_in_flight
+    /// requests outstanding at once, so that none can take the whole
+    /// window from the others.
+    pub fn set_producer_window(&mut self, max_in_flight: usize) {
+        self.producer_window = Some(max_in_flight);
+    }
+
+    /// Make request, e.g. client.submit_sm(pdu), on behalf of producer,
+    /// waiting first until producer has fewer than the producer window
+    /// outstanding.  Without set_producer_window() the request is just
+    /// made.
+    pub async fn as_producer<F: Future>(
+        &self,
+        producer: &str,
+        request: F,
+    ) -> F::Output {
+        let share = match self.producer_window {
+            Some(max_in_flight) => Arc::clone(
+                self.producers
+                    .lock()
+                    .unwrap()
+                    .entry(String::from(producer))
+                    .or_insert_with(|| Arc::new(Semaphore::new(max_in_flight))),
+            ),
+            None => return request.await,
+        };
+        let _permit = share.acquire().await;
+        request.await
+    }
+
+    /// How many requests producer has outstanding through as_producer()
+    pub fn producer_in_flight(&self, producer: &str) -> usize {
+        match (
+            self.producer_window,
+            self.producers.lock().unwrap().get(producer),
+        ) {
+            (Some(max_in_flight), Some(share)) => {
+                max_in_flight - share.available_permits()
+            }
+            _ => 0,
+        }
+    }
+
+    /// Submit messages to the same destination_addr in the order
+    /// submit_sm() is called, each only once the one before has been
+    /// answered, and retried if need be, e.g. so that a follow-up never
+    /// overtakes a one-time password.  None submits them all at once.
+    pub fn set_destination_order(&mut self, order: Option<DestinationOrder>) {
+        self.destination_order = order;
+    }
+
+    /// Fill in what submit_sm() and submit_text() are not given from
+    /// defaults, from now on.
+    pub fn set_submit_sm_defaults(&mut self, defaults: SubmitSmDefaults) {
+        self.submit_sm_defaults = defaults;
+    }
+
+    /// Bend the spec as the SMSC does, from now on.  Choose the profile
+    /// before bind(), which it may change.
+    pub fn with_profile(self, profile: Profile) -> Self {
+        self.set_compatibility(profile.compatibility());
+        self
+    }
+
+    /// Set each switch a Profile would, separately.
+    pub fn set_compatibility(&self, compatibility: Compatibility) {
+        self.connection.set_parse_options(ParseOptions {
+            mode: compatibility.parse_mode,
+            ..self.connection.parse_options()
+        });
+        *self.compatibility.lock().unwrap() = compatibility;
+    }
+
+    pub fn compatibility(&self) -> Compatibility {
+        *self.compatibility.lock().unwrap()
+    }
+
+    pub fn connection(&self) -> &SmppConnection {
+        &self.connection
+    }
+
+    /// What was agreed at bind(), or None before a successful bind().
+    pub fn session_info(&self) -> Option<SessionInfo> {
+        self.connection.session_info()
+    }
+
+    pub fn state(&self) -> ClientState {
+        self.state_changes.borrow().state
+    }
+
+    /// How the session is doing, e.g. for a readiness probe.
+    pub fn health(&self) -> ClientHealth {
+        let mut health = ClientHealth {
+            state: self.state(),
+            last_enquire_link_rtt: *self.last_enquire_link_rtt.lock().unwrap(),
+            pending_requests: self.in_flight.len(),
+            queued_deliveries: self.queued_deliveries.load(Ordering::Relaxed),
+            recent_requests: 0,
+            recent_errors: 0,
+            recent_system_errors: 0,
+            recent_timeouts: 0,
+            late_responses: self.strays.late.load(Ordering::Relaxed),
+            duplicate_responses: self.strays.duplicate.load(Ordering::Relaxed),
+        };
+        self.outcomes.lock().unwrap().report(&mut health);
+        health
+    }
+
+    /// Bound, with the SMSC answering most requests in time.  See
+    /// ClientHealth::is_healthy().
+    pub fn is_healthy(&self) -> bool {
+        self.health().is_healthy()
+    }
+
+    /// Watch for changes of state, e.g. to stop sending while Degraded, or
+    /// to report health.  The receiver holds the current state, and its
+    /// changed() waits for the next one.
+    pub fn state_changes(&self) -> watch::Receiver<StateChange> {
+        let mut state_changes = self.state_changes.clone();
+        state_changes.borrow_and_update();
+        state_changes
+    }
+
+    pub async fn bind(
+        &self,
+        mode: BindMode,
+        system_id: &str,
+        password: &str,
+        system_type: &str,
+    ) -> Result<(), ClientError> {
+        let interface_version = self.compatibility().interface_version;
+        let data = BindData::new(
+            system_id,
+            password,
+            system_type,
+            interface_version,
+            0,
+            0,
+            "",
+        )?;
+        let body: PduBody = match mode {
+            BindMode::Transmitter => BindTransmitterPdu(data).into(),
+            BindMode::Receiver => BindReceiverPdu(data).into(),
+            BindMode::Transceiver => BindTransceiverPdu(data).into(),
+        };
+        let response = self.request(body).await?;
+        let matches = matches!(
+            (mode, response.body()),
+            (BindMode::Transmitter, PduBody::BindTransmitterResp(_))
+                | (BindMode::Receiver, PduBody::BindReceiverResp(_))
+                | (BindMode::Transceiver, PduBody::BindTransceiverResp(_))
+        );
+        expect(&response, matches)?;
+        self.connection.set_session_info(SessionInfo {
+            bind_mode: mode,
+            interface_version,
+            window: self.window,
+        });
+        set_state(&self.states, ClientState::Bound, None);
+        Ok(())
+    }
+
+    pub async fn submit_sm(
+        &self,
+        submit_sm: SubmitSmPdu,
+    ) -> Result<SubmitSmResp, ClientError> {
+        self.submit_sm_with_metadata(submit_sm, Metadata::new())
+            .await
+    }
+
+    /// Like submit_sm(), but the response carries metadata, and so does
+    /// the delivery receipt for the message_id it gives, so that callers
+    /// need not keep their own table keyed by message_id.  Messages whose
+    /// receipt has not come are forgotten once there are
+    /// MAX_AWAITING_RECEIPTS of them, or after MAX_RECEIPT_WAIT.
+    pub async fn submit_sm_with_metadata(
+        &self,
+        mut submit_sm: SubmitSmPdu,
+        metadata: Metadata,
+    ) -> Result<SubmitSmResp, ClientError> {
+        self.check_not_quiescing()?;
+        self.submit_sm_defaults.apply(&mut submit_sm)?;
+        self.leave_out_unsupported_tlvs(&mut submit_sm);
+        check_sm_default_msg_id(submit_sm.0.sm_default_msg_id.value)?;
+        let response = match self.destination_order {
+            Some(order) => {
+                self.submit_in_turn(submit_sm, &metadata, order).await?
+            }
+            None => self.submit(submit_sm, &metadata).await?,
+        };
+        let body = match response.body() {
+            PduBody::SubmitSmResp(body) => body,
+            _ => return Err(unexpected(&response)),
+        };
+        let message_id = body
+            .message_id
+            .as_ref()
+            .map(|message_id| message_id.value.to_string());
+        Ok(SubmitSmResp {
+            command_status: response.command_status.value,
+            sequence_number: response.sequence_number.value,
+            message_id,
+            metadata,
+        })
+    }
+
+    /// Submit once every submit_sm to the same destination_addr before it
+    /// has been answered, resubmitting while the SMSC is too busy to take
+    /// it, as order allows.
+    async fn submit_in_turn(
+        &self,
+        submit_sm: SubmitSmPdu,
+        metadata: &Metadata,
+        order: DestinationOrder,
+    ) -> Result<Pdu, ClientError> {
+        let destination_addr = submit_sm.destination_addr();
+        let turn = Arc::clone(
+            self.destinations
+                .lock()
+                .unwrap()
+                .entry(destination_addr.clone())
+                .or_default(),
+        );
+        let response = {
+            let _turn = turn.lock().await;
+            let mut retries = 0;
+            loop {
+                let response =
+                    self.submit(submit_sm.pdu_clone(), metadata).await;
+                match &response {
+                    Ok(pdu)
+                        if is_busy(pdu.command_status.value)
+                            && retries < order.retries =>
+                    {
+                        retries += 1;
+                        tokio::time::sleep(order.retry_delay).await;
+                    }
+                    _ => break response,
+                }
+            }
+        };
+        // Forget the destination unless another submit_sm is waiting
+        let mut destinations = self.destinations.lock().unwrap();
+        if Arc::strong_count(&turn) == 2 {
+            destinations.remove(&destination_addr);
+        }
+        response
+    }
+
+    /// Submit short_message to destination_addr, with everything else from
+    /// the submit_sm defaults.
+    pub async fn submit_text(
+        &self,
+        destination_addr: &str,
+        short_message: &[u8],
+    ) -> Result<SubmitSmResp, ClientError> {
+        let submit_sm = SubmitSmBuilder::new()
+            .destination(destination_addr)
+            .short_message(short_message)
+            .build()?;
+        self.submit_sm(submit_sm).await
+    }
+
+    pub async fn enquire_link(&self) -> Result<(), ClientError> {
+        let sent = Instant::now();
+        let response = self.request(EnquireLinkPdu::new().into()).await?;
+        *self.last_enquire_link_rtt.lock().unwrap() = Some(sent.elapsed());
+        let matches = matches!(response.body(), PduBody::EnquireLinkResp(_));
+        expect(&response, matches)
+    }
+
+    /// End the session, closing the connection once the SMSC agrees.
+    pub async fn unbind(&self) -> Result<(), ClientError> {
+        let sequence_number = self.connection.next_sequence_number();
+        let unbind = Frame::Unbind(UnbindPdu::new(sequence_number));
+        let response = self.send(unbind).await?;
+        self.connection.disconnect().await;
+        set_state(
+            &self.states,
+            ClientState::Closed,
+            Some(String::from("Unbound")),
+        );
+        match response {
+            Response::UnbindResp { command_status: 0 } => Ok(()),
+            Response::UnbindResp { command_status } => {
+                Err(ClientError::Status(command_status))
+            }
+            response => Err(unexpected_response(&response)),
+        }
+    }
+
+    /// Wind the session down without losing messages, e.g. before a
+    /// deploy.  From now on submit_sm(), data_sm() and submit_multi() fail
+    /// with ClientError::Quiescing.  Requests already sent, or waiting for
+    /// room in the window, get up to drain_timeout to be answered, and
+    /// then we unbind.  If they are not all answered in time we unbind
+    /// anyway and return ClientError::Timeout.  Any the SMSC does not
+    /// answer before its unbind_resp fail with ClientError::Closed.
+    pub async fn quiesce(
+        &self,
+        drain_timeout: Duration,
+    ) -> Result<(), ClientError> {
+        self.quiescing.store(true, Ordering::SeqCst);
+        let drained =
+            tokio::time::timeout(drain_timeout, self.outstanding.drained())
+                .await
+                .is_ok();
+        if !drained {
+            warn!(
+                "=> {} unbinding with {} requests unanswered",
+                self.connection,
+                self.outstanding.count.load(Ordering::SeqCst)
+            );
+        }
+        self.unbind().await?;
+        if drained {
+            Ok(())
+        } else {
+            Err(ClientError::Timeout)
+        }
+    }
+
+    /// SMPP 3.3 has no TLVs, so they are not sent on a session bound with
+    /// it unless the SMSC is known to accept them.
+    fn leave_out_unsupported_tlvs(&self, submit_sm: &mut SubmitSmPdu) {
+        let supports_tlvs = match self.session_info() {
+            Some(session) => session.supports_tlvs(),
+            None => true,
+        } || self.compatibility().tlvs_on_smpp_3_3;
+        if !supports_tlvs && !submit_sm.0.tlvs.to_vec().is_empty() {
+            debug!("=> {} leaving TLVs out for SMPP 3.3", self.connection);
+            submit_sm.0.tlvs = Tlvs::new();
+        }
+    }
+
+    fn check_not_quiescing(&self) -> Result<(), ClientError> {
+        if self.quiescing.load(Ordering::SeqCst) {
+            Err(ClientError::Quiescing)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Send data_sm, under a sequence number of our choosing rather than
+    /// the one in data_sm.  As with submit_sm, a non-zero command_status
+    /// comes back in the response rather than as an error.
+    pub async fn data_sm(
+        &self,
+        mut data_sm: DataSmPdu,
+    ) -> Result<DataSmRespPdu, ClientError> {
+        self.check_not_quiescing()?;
+        let sequence_number = self.connection.next_sequence_number();
+        data_sm.sequence_number = sequence_number;
+        match self.send(Frame::DataSm(data_sm)).await? {
+            Response::DataSmResp(resp) => Ok(resp),
+            response => Err(unexpected_response(&response)),
+        }
+    }
+
+    /// Ask after a message submitted earlier, under a sequence number of
+    /// our choosing rather than the one in query_sm.  A non-zero
+    /// command_status (e.g. ESME_RQUERYFAIL for an unknown message) comes
+    /// back in the response rather than as an error.
+    pub async fn query_sm(
+        &self,
+        mut query_sm: QuerySmPdu,
+    ) -> Result<QuerySmRespPdu, ClientError> {
+        let sequence_number = self.connection.next_sequence_number();
+        query_sm.sequence_number = sequence_number;
+        match self.send(Frame::QuerySm(query_sm)).await? {
+            Response::QuerySmResp(resp) => Ok(resp),
+            response => Err(unexpected_response(&response)),
+        }
+    }
+
+    /// Withdraw messages that have yet to be delivered, under a sequence
+    /// number of our choosing rather than the one in cancel_sm.  Whether
+    /// it worked (e.g. ESME_RCANCELFAIL) is in the response's
+    /// command_status.
+    pub async fn cancel_sm(
+        &self,
+        mut cancel_sm: CancelSmPdu,
+    ) -> Result<CancelSmRespPdu, ClientError> {
+        let sequence_number = self.connection.next_sequence_number();
+        cancel_sm.sequence_number = sequence_number;
+        let frame = Frame::CancelSm(cancel_sm);
+        match self.send(frame).await? {
+            Response::CancelSmResp(resp) => Ok(resp),
+            response => Err(unexpected_response(&response)),
+        }
+    }
+
+    /// Change a message that has yet to be delivered, under a sequence
+    /// number of our choosing rather than the one in replace_sm.  Whether
+    /// it worked (e.g. ESME_RREPLACEFAIL) is in the response's
+    /// command_status.
+    pub async fn replace_sm(
+        &self,
+        mut replace_sm: ReplaceSmPdu,
+    ) -> Result<ReplaceSmRespPdu, ClientError> {
+        check_sm_default_msg_id(replace_sm.sm_default_msg_id)?;
+        let sequence_number = self.connection.next_sequence_number();
+        replace_sm.sequence_number = sequence_number;
+        let frame = Frame::ReplaceSm(replace_sm);
+        match self.send(frame).await? {
+            Response::ReplaceSmResp(resp) => Ok(resp),
+            response => Err(unexpected_response(&response)),
+        }
+    }
+
+    /// Send one message to several destinations, under a sequence number
+    /// of our choosing rather than the one in submit_multi.  As with
+    /// submit_sm, a non-zero command_status comes back in the response
+    /// rather than as an error, and so do the destinations that failed.
+    pub async fn submit_multi(
+        &self,
+        mut submit_multi: SubmitMultiPdu,
+    ) -> Result<SubmitMultiRespPdu, ClientError> {
+        self.check_not_quiescing()?;
+        check_sm_default_msg_id(submit_multi.sm_default_msg_id)?;
+        let sequence_number = self.connection.next_sequence_number();
+        submit_multi.sequence_number = sequence_number;
+        let frame = Frame::SubmitMulti(submit_multi);
+        match self.send(frame).await? {
+            Response::SubmitMultiResp(resp) => Ok(resp),
+            response => Err(unexpected_response(&response)),
+        }
+    }
+
+    /// The next deliver_sm from the SMSC that is neither a delivery
+    /// receipt nor a notification, e.g. a message from a handset.  It has
+    /// already been answered with a deliver_sm_resp.  None once the
+    /// connection has closed and every one has been returned.
+    pub async fn next_message(&self) -> Option<DeliverSmPdu> {
+        let delivery = self.messages.lock().await.recv().await;
+        if delivery.is_some() {
+            self.queued_deliveries.fetch_sub(1, Ordering::Relaxed);
+        }
+        delivery
+    }
+
+    /// The next delivery receipt, as for next_message().
+    pub async fn next_receipt(&self) -> Option<DeliveryReceipt> {
+        let delivery = self.receipts.lock().await.recv().await;
+        if delivery.is_some() {
+            self.queued_deliveries.fetch_sub(1, Ordering::Relaxed);
+        }
+        delivery
+    }
+
+    /// The next intermediate delivery notification, as for next_message().
+    pub async fn next_notification(&self) -> Option<DeliverSmPdu> {
+        let delivery = self.notifications.lock().await.recv().await;
+        if delivery.is_some() {
+            self.queued_deliveries.fetch_sub(1, Ordering::Relaxed);
+        }
+        delivery
+    }
+
+    /// The next alert_notification, saying that a handset we could not
+    /// reach earlier is available again, as for next_message().
+    pub async fn next_alert_notification(
+        &self,
+    ) -> Option<AlertNotificationPdu> {
+        let delivery = self.alert_notifications.lock().await.recv().await;
+        if delivery.is_some() {
+            self.queued_deliveries.fetch_sub(1, Ordering::Relaxed);
+        }
+        delivery
+    }
+
+    async fn request(&self, body: PduBody) -> Result<Pdu, ClientError> {
+        let sequence_number = self.connection.next_sequence_number();
+        let pdu = Pdu::new(0, sequence_number, body)?;
+        match self.send(Frame::Pdu(pdu)).await? {
+            Response::Pdu(pdu) => Ok(pdu),
+            response => Err(unexpected_response(&response)),
+        }
+    }
+
+    /// Like request(), but with metadata registered under the
+    /// sequence_number before sending, for read_loop() to file under the
+    /// message_id of the response before it reads anything after it, such
+    /// as the receipt.
+    async fn submit(
+        &self,
+        submit_sm: SubmitSmPdu,
+        metadata: &Metadata,
+    ) -> Result<Pdu, ClientError> {
+        let sequence_number = self.connection.next_sequence_number();
+        let pdu = Pdu::new(0, sequence_number, submit_sm.into())?;
+        match self.send_with(Frame::Pdu(pdu), metadata).await? {
+            Response::Pdu(pdu) => Ok(pdu),
+            response => Err(unexpected_response(&response)),
+        }
+    }
+
+    async fn send(&self, frame: Frame) -> Result<Response, ClientError> {
+        self.send_with(frame, &Metadata::new()).await
+    }
+
+    /// Send frame, and again under a fresh sequence number each time the
+    /// expiry policy says to, registering metadata under each sequence
+    /// number as submit() needs.
+    async fn send_with(
+        &self,
+        mut frame: Frame,
+        metadata: &Metadata,
+    ) -> Result<Response, ClientError> {
+        let mut resent = 0;
+        loop {
+            let sequence_number = frame.sequence_number();
+            let _submitted = (!metadata.is_empty()).then(|| {
+                Submitted::register(&self.submitted, sequence_number, metadata)
+            });
+            let response = self.send_once(&frame).await;
+            if !matches!(response, Err(ClientError::Timeout)) {
+                return response;
+            }
+            let expired = ExpiredRequest {
+                command_id: frame.command_id(),
+                sequence_number,
+                resent,
+            };
+            match self.expiry_policy.action(&expired) {
+                ExpiryAction::Fail => return response,
+                ExpiryAction::Resend => {
+                    frame.set_sequence_number(
+                        self.connection.next_sequence_number(),
+                    );
+                    warn!(
+                        "=> {} resending unanswered {} {} as {}",
+                        self.connection,
+                        expired.command_id.name(),
+                        sequence_number,
+                        frame.sequence_number()
+                    );
+                    resent += 1;
+                }
+            }
+        }
+    }
+
+    async fn send_once(&self, frame: &Frame) -> Result<Response, ClientError> {
+        let _outstanding = self.outstanding.start();
+        let pending = self.in_flight.start(frame.sequence_number()).await;
+        self.connection.write_frame(frame).await?;
+        let response = match pending.response().await {
+            Ok(Response::Unparseable(e)) => Err(ClientError::Pdu(e)),
+            response => response.map_err(ClientError::from),
+        };
+        match &response {
+            Ok(response) => self
+                .outcomes
+                .lock()
+                .unwrap()
+                .record(Some(response.status())),
+            Err(ClientError::Timeout) => {
+                self.outcomes.lock().unwrap().record(None)
+            }
+            Err(_) => {}
+        }
+        match (&response, self.state()) {
+            (Err(e @ ClientError::Timeout), ClientState::Bound) => set_state(
+                &self.states,
+                ClientState::Degraded,
+                Some(e.to_string()),
+            ),
+            (Ok(_), ClientState::Degraded) => {
+                set_state(&self.states, ClientState::Bound, None)
+            }
+            _ => {}
+        }
+        response
+    }
+}
+
+/// How submit_sm() keeps messages to each destination in order.  See
+/// Client::set_destination_order().
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DestinationOrder {
+    /// How many times to resubmit a message the SMSC answers with
+    /// ESME_RTHROTTLED or ESME_RMSGQFUL before giving the next message to
+    /// its destination a turn
+    pub retries: u32,
+    pub retry_delay: Duration,
+}
+
+/// What a Client does about a request that is not answered within the
+/// response timeout.  See Client::set_expiry_policy().
+#[derive(Clone, Default)]
+pub enum ExpiryPolicy {
+    /// Fail it with ClientError::Timeout
+    #[default]
+    Fail,
+    /// Send it once more, under a fresh sequence number, and fail it if
+    /// that is not answered in time either
+    ResendOnce,
+    /// Ask this, each time the request goes unanswered
+    Callback(Arc<dyn Fn(&ExpiredRequest) -> ExpiryAction + Send + Sync>),
+}
+
+impl ExpiryPolicy {
+    fn action(&self, expired: &ExpiredRequest) -> ExpiryAction {
+        match self {
+            Self::Fail => ExpiryAction::Fail,
+            Self::ResendOnce if expired.resent == 0 => ExpiryAction::Resend,
+            Self::ResendOnce => ExpiryAction::Fail,
+            Self::Callback(decide) => decide(expired),
+        }
+    }
+}
+
+impl std::fmt::Debug for ExpiryPolicy {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Self::Fail => formatter.write_str("Fail"),
+            Self::ResendOnce => formatter.write_str("ResendOnce"),
+            Self::Callback(_) => formatter.write_str("Callback"),
+        }
+    }
+}
+
+/// What a Client does with a response that no request is awaiting, e.g.
+/// a second response to the same request, or one that comes after its
+/// request timed out.  See Client::set_stray_response_policy().
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum StrayResponsePolicy {
+    /// Just count it
+    Ignore,
+    /// Count it, and log a warning
+    #[default]
+    Log,
+    /// Count it, and close the session with it as the cause, as a
+    /// protocol error
+    Error,
+}
+
+/// The stray response policy, shared with read_loop(), and how many stray
+/// responses it has had
+#[derive(Default)]
+struct StrayResponses {
+    policy: std::sync::Mutex<StrayResponsePolicy>,
+    late: AtomicUsize,
+    duplicate: AtomicUsize,
+}
+
+impl StrayResponses {
+    /// Count a response to sequence_number that no request was awaiting.
+    /// The cause to close the session with, if the policy says to.
+    fn record(
+        &self,
+        connection: &SmppConnection,
+        stray: Stray,
+        sequence_number: u32,
+    ) -> Option<String> {
+        let (count, kind) = match stray {
+            Stray::Late => (&self.late, "Late"),
+            Stray::Duplicate => (&self.duplicate, "Duplicate"),
+        };
+        count.fetch_add(1, Ordering::Relaxed);
+        let cause =
+            format!("{} response to sequence_number {}", kind, sequence_number);
+        match *self.policy.lock().unwrap() {
+            StrayResponsePolicy::Ignore => None,
+            StrayResponsePolicy::Log => {
+                warn!("<= {} {}", connection, cause);
+                None
+            }
+            StrayResponsePolicy::Error => {
+                error!("<= {} {}", connection, cause);
+                Some(cause)
+            }
+        }
+    }
+}
+
+/// A request that was not answered within the response timeout, as an
+/// ExpiryPolicy::Callback sees it
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ExpiredRequest {
+    pub command_id: CommandId,
+    pub sequence_number: u32,
+    /// How many times it had been sent again before this
+    pub resent: u32,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExpiryAction {
+    /// Fail the request with ClientError::Timeout
+    Fail,
+    /// Send it again under a fresh sequence number
+    Resend,
+}
+
+/// Whether command_status says to try again later
+fn is_busy(command_status: u32) -> bool {
+    command_status == PduStatus::ESME_RTHROTTLED as u32
+        || command_status == PduStatus::ESME_RMSGQFUL as u32
+}
+
+/// How many requests are waiting for room in the window or a response,
+/// so that quiesce() can wait for them.
+#[derive(Default)]
+struct Outstanding {
+    count: AtomicUsize,
+    none: Notify,
+}
+
+impl Outstanding {
+    fn start(&self) -> OutstandingRequest<'_> {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        OutstandingRequest(self)
+    }
+
+    async fn drained(&self) {
+        while self.count.load(Ordering::SeqCst) > 0 {
+            self.none.notified().await;
+        }
+    }
+}
+
+/// Counts as outstanding until dropped.
+struct OutstandingRequest<'a>(&'a Outstanding);
+
+impl<'a> Drop for OutstandingRequest<'a> {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            // Stored if nobody is waiting yet, so drained() cannot miss it
+            self.0.none.notify_one();
+        }
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+impl Response {
+    fn status(&self) -> u32 {
+        match self {
+            Self::Pdu(pdu) => pdu.command_status.value,
+            Self::UnbindResp { command_status } => *command_status,
+            Self::DataSmResp(resp) => resp.command_status,
+            Self::QuerySmResp(resp) => resp.command_status,
+            Self::CancelSmResp(resp) => resp.command_status,
+            Self::ReplaceSmResp(resp) => resp.command_status,
+            Self::SubmitMultiResp(resp) => resp.command_status,
+            Self::Unparseable(e) => e.status(),
+        }
+    }
+}
+
+/// Ok if response is of the expected type with command_status 0.
+fn expect(response: &Pdu, expected_type: bool) -> Result<(), ClientError> {
+    match response.command_status.value {
+        _ if !expected_type => Err(unexpected(response)),
+        0 => Ok(()),
+        command_status => Err(ClientError::Status(command_status)),
+    }
+}
+
+/// The error for a response of the wrong type.  A generic_nack means the
+/// SMSC could not handle our request, and says why.
+fn unexpected(response: &Pdu) -> ClientError {
+    match response.body() {
+        PduBody::GenericNack(_) => {
+            ClientError::Status(response.command_status.value)
+        }
+        body => ClientError::UnexpectedResponse(CommandId::from(body)),
+    }
+}
+
+fn unexpected_response(response: &Response) -> ClientError {
+    match response {
+        Response::Pdu(pdu) => unexpected(pdu),
+        Response::UnbindResp { .. } => {
+            ClientError::UnexpectedResponse(CommandId::UnbindResp)
+        }
+        Response::DataSmResp(_) => {
+            ClientError::UnexpectedResponse(CommandId::DataSmResp)
+        }
+        Response::QuerySmResp(_) => {
+            ClientError::UnexpectedResponse(CommandId::QuerySmResp)
+        }
+        Response::CancelSmResp(_) => {
+            ClientError::UnexpectedResponse(CommandId::CancelSmResp)
+        }
+        Response::ReplaceSmResp(_) => {
+            ClientError::UnexpectedResponse(CommandId::ReplaceSmResp)
+        }
+        Response::SubmitMultiResp(_) => {
+            ClientError::UnexpectedResponse(CommandId::SubmitMultiResp)
+        }
+        Response::Unparseable(_) => {
+            unreachable!("send() turns Unparseable into ClientError::Pdu")
+        }
+    }
+}
+
+/// A submit_sm's metadata, registered by sequence_number while it awaits a
+/// response, and forgotten if none comes
+struct Submitted<'a> {
+    submitted: &'a std::sync::Mutex<HashMap<u32, Metadata>>,
+    sequence_number: u32,
+}
+
+impl<'a> Submitted<'a> {
+    fn register(
+        submitted: &'a std::sync::Mutex<HashMap<u32, Metadata>>,
+        sequence_number: u32,
+        metadata: &Metadata,
+    ) -> Self {
+        submitted
+            .lock()
+            .unwrap()
+            .insert(sequence_number, metadata.clone());
+        Self {
+            submitted,
+            sequence_number,
+        }
+    }
+}
+
+impl Drop for Submitted<'_> {
+    fn drop(&mut self) {
+        self.submitted.lock().unwrap().remove(&self.sequence_number);
+    }
+}
+
+/// Where read_loop() queues each kind of deliver_sm, and alert_notification
+struct Deliveries {
+    messages: mpsc::UnboundedSender<DeliverSmPdu>,
+    receipts: mpsc::UnboundedSender<DeliveryReceipt>,
+    notifications: mpsc::UnboundedSender<DeliverSmPdu>,
+    alert_notifications: mpsc::UnboundedSender<AlertNotificationPdu>,
+    /// How many are waiting in the queues, for ClientHealth
+    queued: Arc<AtomicUsize>,
+    metadata: Arc<std::sync::Mutex<AwaitingReceipt<Metadata>>>,
+    submitted: Arc<std::sync::Mutex<HashMap<u32, Metadata>>>,
+    compatibility: Arc<std::sync::Mutex<Compatibility>>,
+}
+
+impl Deliveries {
+    /// Move the metadata of the submit_sm that resp answers to where its
+    /// receipt will look for it, under the message_id resp gives
+    fn file_metadata(&self, resp: &Pdu) {
+        let message_id = match resp.body() {
+            PduBody::SubmitSmResp(body) => body.message_id.as_ref(),
+            _ => return,
+        };
+        let metadata = self
+            .submitted
+            .lock()
+            .unwrap()
+            .remove(&resp.sequence_number.value);
+        if let (Some(metadata), Some(message_id)) = (metadata, message_id) {
+            self.metadata
+                .lock()
+                .unwrap()
+                .insert(message_id.value.as_ref(), metadata);
+        }
+    }
+
+    fn send(&self, pdu: DeliverSmPdu) {
+        self.queued.fetch_add(1, Ordering::Relaxed);
+        // Nobody may be reading a queue any more, which is fine
+        let _ = match DeliveryKind::of(&pdu) {
+            DeliveryKind::Message => self.messages.send(pdu).map_err(drop),
+            DeliveryKind::Receipt => {
+                let mut receipt = DeliveryReceipt::new(pdu);
+                let compatibility = *self.compatibility.lock().unwrap();
+                receipt.submit_date = receipt
+                    .submit_date
+                    .map(|date| compatibility.receipt_date(date));
+                receipt.done_date = receipt
+                    .done_date
+                    .map(|date| compatibility.receipt_date(date));
+                if let Some(message_id) = &receipt.message_id {
+                    let metadata =
+                        self.metadata.lock().unwrap().take(message_id);
+                    receipt.metadata = metadata.unwrap_or_default();
+                }
+                self.receipts.send(receipt).map_err(drop)
+            }
+            DeliveryKind::Notification => {
+                self.notifications.send(pdu).map_err(drop)
+            }
+        };
+    }
+}
+
+async fn read_loop(
+    connection: Arc<SmppConnection>,
+    in_flight: Arc<InFlight<Response>>,
+    deliveries: Deliveries,
+    strays: Arc<StrayResponses>,
+    states: Arc<watch::Sender<StateChange>>,
+) {
+    // Hand the response to its request, giving the cause to close the
+    // session with if there is none and the stray response policy says so
+    let respond = |sequence_number: u32, response: Response| {
+        if in_flight.respond(sequence_number, response) {
+            None
+        } else {
+            let stray = in_flight.stray(sequence_number);
+            strays.record(&connection, stray, sequence_number)
+        }
+    };
+    let cause = loop {
+        let frame = match connection.read_frame().await {
+            Ok(Some(frame)) => frame,
+            Ok(None) => break String::from("Closed by the SMSC"),
+            Err(e) => match (e.severity(), e.sequence_number) {
+                (ErrorSeverity::RequestRecoverable, Some(sequence_number)) => {
+                    // Fail the request it answers rather than leave it to
+                    // time out
+                    let is_response = !matches!(
+                        e.command_id,
+                        Some(command_id) if command_id & RESPONSE_BIT == 0
+                    );
+                    if is_response {
+                        let response = Response::Unparseable(e);
+                        if let Some(cause) = respond(sequence_number, response)
+                        {
+                            break cause;
+                        }
+                    }
+                    continue;
+                }
+                _ => break e.to_string(),
+            },
+        };
+        let mut stray_cause = None;
+        let written = match frame {
+            Frame::Pdu(pdu) => {
+                let sequence_number = pdu.sequence_number.value;
+                match pdu.body() {
+                    PduBody::EnquireLink(_) => {
+                        let resp = Pdu::new(
+                            0,
+                            sequence_number,
+                            EnquireLinkRespPdu::new().into(),
+                        )
+                        .expect("enquire_link_resp should be valid");
+                        connection.write_pdu(&resp).await
+                    }
+                    PduBody::DeliverSm(body) => {
+                        deliveries.send(body.pdu_clone());
+                        let resp = DeliverSmRespPdu::new(0, sequence_number);
+                        connection
+                            .write_frame(&Frame::DeliverSmResp(resp))
+                            .await
+                    }
+                    _ => {
+                        deliveries.file_metadata(&pdu);
+                        stray_cause =
+                            respond(sequence_number, Response::Pdu(pdu));
+                        Ok(())
+                    }
+                }
+            }
+            Frame::UnbindResp(resp) => {
+                // Before the SMSC closes the connection, which is expected
+                if resp.command_status == 0 {
+                    set_state(
+                        &states,
+                        ClientState::Closed,
+                        Some(String::from("Unbound")),
+                    );
+                }
+                stray_cause = respond(
+                    resp.sequence_number,
+                    Response::UnbindResp {
+                        command_status: resp.command_status,
+                    },
+                );
+                Ok(())
+            }
+            Frame::Unbind(unbind) => {
+                let resp = UnbindRespPdu::new(0, unbind.sequence_number);
+                let _ = connection.write_unbind_resp(&resp).await;
+                break String::from("Unbound by the SMSC");
+            }
+            Frame::DataSmResp(resp) => {
+                stray_cause =
+                    respond(resp.sequence_number, Response::DataSmResp(resp));
+                Ok(())
+            }
+            Frame::DataSm(data_sm) => {
+                warn!("<= {} data_sm, which we do not accept", connection);
+                let resp = DataSmRespPdu::new_error(
+                    PduStatus::ESME_RINVCMDID as u32,
+                    data_sm.sequence_number,
+                );
+                connection.write_frame(&Frame::DataSmResp(resp)).await
+            }
+            Frame::QuerySmResp(resp) => {
+                stray_cause =
+                    respond(resp.sequence_number, Response::QuerySmResp(resp));
+                Ok(())
+            }
+            Frame::QuerySm(query_sm) => {
+                warn!("<= {} query_sm, which we do not accept", connection);
+                let resp = QuerySmRespPdu::new_error(
+                    PduStatus::ESME_RINVCMDID as u32,
+                    query_sm.sequence_number,
+                );
+                connection.write_frame(&Frame::QuerySmResp(resp)).await
+            }
+            Frame::CancelSmResp(resp) => {
+                stray_cause =
+                    respond(resp.sequence_number, Response::CancelSmResp(resp));
+                Ok(())
+            }
+            Frame::CancelSm(cancel_sm) => {
+                warn!("<= {} cancel_sm, which we do not accept", connection);
+                let resp = CancelSmRespPdu::new(
+                    PduStatus::ESME_RINVCMDID as u32,
+                    cancel_sm.sequence_number,
+                );
+                connection.write_frame(&Frame::CancelSmResp(resp)).await
+            }
+            Frame::ReplaceSmResp(resp) => {
+                stray_cause = respond(
+                    resp.sequence_number,
+                    Response::ReplaceSmResp(resp),
+                );
+                Ok(())
+            }
+            Frame::ReplaceSm(replace_sm) => {
+                warn!("<= {} replace_sm, which we do not accept", connection);
+                let resp = ReplaceSmRespPdu::new(
+                    PduStatus::ESME_RINVCMDID as u32,
+                    replace_sm.sequence_number,
+                );
+                connection.write_frame(&Frame::ReplaceSmResp(resp)).await
+            }
+            Frame::SubmitMultiResp(resp) => {
+                stray_cause = respond(
+                    resp.sequence_number,
+                    Response::SubmitMultiResp(resp),
+                );
+                Ok(())
+            }
+            Frame::SubmitMulti(submit_multi) => {
+                warn!("<= {} submit_multi, which we do not accept", connection);
+                let resp = SubmitMultiRespPdu::new_error(
+                    PduStatus::ESME_RINVCMDID as u32,
+                    submit_multi.sequence_number,
+                );
+                connection.write_frame(&Frame::SubmitMultiResp(resp)).await
+            }
+            Frame::AlertNotification(alert) => {
+                // Nobody may be reading the queue any more, which is fine
+                deliveries.queued.fetch_add(1, Ordering::Relaxed);
+                let _ = deliveries.alert_notifications.send(alert);
+                Ok(())
+            }
+            Frame::Outbind(_) => {
+                warn!(
+                    "<= {} outbind, but we are already connected",
+                    connection
+                );
+                Ok(())
+            }
+            Frame::DeliverSmResp(_) => Ok(()),
+        };
+        if let Err(e) = written {
+            error!("=> {} failed to respond: {}", connection, e);
+            break e.to_string();
+        }
+        if let Some(cause) = stray_cause {
+            break cause;
+        }
+    };
+    connection.disconnect().await;
+    in_flight.clear();
+    set_state(&states, ClientState::Closed, Some(cause));
+}
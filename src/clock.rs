@@ -0,0 +1,57 @@
+//! Where time-based code gets the current time from, so that it can be
+//! tested without waiting.
+//!
+//! Keepalives and idle timeouts have to agree with tokio's sleeps and
+//! timeouts, so they, and the session statistics and destination limits
+//! next to them, read TokioClock.  Tests can stop and move that with
+//! tokio::time::pause() and advance().  DlrBatches never sleeps, so it
+//! takes any Clock, and tests can hand it a ManualClock instead.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// tokio's idea of the current time, which is the real time unless a test
+/// has paused it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        tokio::time::Instant::now().into_std()
+    }
+}
+
+/// A clock that only moves when told to.
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<Instant>,
+}
+
+impl ManualClock {
+    /// A clock reading the real time now, until it is moved.
+    pub fn new() -> Self {
+        Self {
+            now: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+}
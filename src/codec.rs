@@ -0,0 +1,174 @@
+//! A tokio_util codec for SMPP, so PDUs can be read and written through
+//! Framed, FramedRead and FramedWrite.  Enabled with the codec feature.
+//!
+//! Like SmppConnection::read_frame, decoding yields a Frame, because
+//! smpp_pdu cannot parse deliver_sm_resp, unbind or unbind_resp.  Pdu and
+//! Frame can both be encoded.
+
+use bytes::{Buf, BytesMut};
+use futures::FutureExt;
+use smpp_pdu::pdu::{CheckOutcome, Pdu, PduParseError, PduParseErrorBody};
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::Cursor;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::parse_options::ParseOptions;
+use crate::pdu_write::write_pdu;
+use crate::smpp_connection::Frame;
+
+#[derive(Debug)]
+pub enum SmppCodecError {
+    Io(io::Error),
+    /// A frame could not be parsed.  If Pdu::check found its end, its bytes
+    /// have been consumed, so decode() can carry on with the next one,
+    /// though Framed ends the stream after any error.  A frame longer than
+    /// the codec's max_pdu_length is skipped, without being buffered,
+    /// however many of its bytes have arrived so far.
+    Pdu(PduParseError),
+}
+
+impl Display for SmppCodecError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(formatter),
+            Self::Pdu(e) => e.fmt(formatter),
+        }
+    }
+}
+
+impl error::Error for SmppCodecError {}
+
+impl From<io::Error> for SmppCodecError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<PduParseError> for SmppCodecError {
+    fn from(e: PduParseError) -> Self {
+        Self::Pdu(e)
+    }
+}
+
+/// Splits bytes into frames using ParseOptions::check, and writes PDUs.
+#[derive(Clone, Copy, Debug)]
+pub struct SmppCodec {
+    options: ParseOptions,
+    /// How many more bytes of a too-long frame to throw away
+    skipping: usize,
+}
+
+impl SmppCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject frames whose command_length is over max_frame_length as
+    /// soon as it arrives, rather than the default MAX_PDU_LENGTH.
+    pub fn with_max_frame_length(max_frame_length: usize) -> Self {
+        Self::with_parse_options(ParseOptions {
+            max_pdu_length: max_frame_length,
+            ..ParseOptions::default()
+        })
+    }
+
+    pub fn with_parse_options(options: ParseOptions) -> Self {
+        Self {
+            options,
+            skipping: 0,
+        }
+    }
+
+    /// Throw away what has arrived of a frame we are skipping, returning
+    /// whether there is more of it to come.
+    fn skip(&mut self, src: &mut BytesMut) -> bool {
+        let len = self.skipping.min(src.len());
+        src.advance(len);
+        self.skipping -= len;
+        self.skipping > 0
+    }
+}
+
+impl Default for SmppCodec {
+    fn default() -> Self {
+        Self::with_parse_options(ParseOptions::default())
+    }
+}
+
+impl Decoder for SmppCodec {
+    type Item = Frame;
+    type Error = SmppCodecError;
+
+    fn decode(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<Frame>, SmppCodecError> {
+        if self.skip(src) {
+            return Ok(None);
+        }
+        if src.len() >= 4 {
+            let command_length =
+                u32::from_be_bytes([src[0], src[1], src[2], src[3]]);
+            if command_length as usize > self.options.max_pdu_length {
+                self.skipping = command_length as usize;
+                self.skip(src);
+                return Err(PduParseError::new(
+                    PduParseErrorBody::LengthTooLong(command_length),
+                )
+                .into());
+            }
+        }
+
+        let mut buf = Cursor::new(&src[..]);
+        match self.options.check(&mut buf) {
+            Ok(CheckOutcome::Ready) => {
+                // check moved us to the end, so position is length
+                let len = buf.position() as usize;
+                let frame = self.options.parse_frame(&src[..len]);
+                src.advance(len);
+                Ok(Some(frame?))
+            }
+            Ok(CheckOutcome::Incomplete) => Ok(None),
+            // We cannot tell where this frame ends, so there is no
+            // carrying on after it.
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl Encoder<Frame> for SmppCodec {
+    type Error = SmppCodecError;
+
+    fn encode(
+        &mut self,
+        frame: Frame,
+        dst: &mut BytesMut,
+    ) -> Result<(), SmppCodecError> {
+        let mut buf = Vec::new();
+        frame
+            .write(&mut buf)
+            .now_or_never()
+            .expect("Writing to a Vec should never wait")?;
+        dst.extend_from_slice(&buf);
+        Ok(())
+    }
+}
+
+impl Encoder<Pdu> for SmppCodec {
+    type Error = SmppCodecError;
+
+    fn encode(
+        &mut self,
+        pdu: Pdu,
+        dst: &mut BytesMut,
+    ) -> Result<(), SmppCodecError> {
+        let mut buf = Vec::new();
+        write_pdu(&pdu, &mut buf)
+            .now_or_never()
+            .expect("Writing to a Vec should never wait")?;
+        dst.extend_from_slice(&buf);
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+//! The command_id of every SMPP 3.4 operation, to match on instead of
+//! remembering hex.
+
+use smpp_pdu::pdu::PduBody;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+/// Set in the command_id of every response
+pub const RESPONSE_BIT: u32 = 0x80000000;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(u32)]
+pub enum CommandId {
+    GenericNack = 0x80000000,
+    BindReceiver = 0x00000001,
+    BindReceiverResp = 0x80000001,
+    BindTransmitter = 0x00000002,
+    BindTransmitterResp = 0x80000002,
+    QuerySm = 0x00000003,
+    QuerySmResp = 0x80000003,
+    SubmitSm = 0x00000004,
+    SubmitSmResp = 0x80000004,
+    DeliverSm = 0x00000005,
+    DeliverSmResp = 0x80000005,
+    Unbind = 0x00000006,
+    UnbindResp = 0x80000006,
+    ReplaceSm = 0x00000007,
+    ReplaceSmResp = 0x80000007,
+    CancelSm = 0x00000008,
+    CancelSmResp = 0x80000008,
+    BindTransceiver = 0x00000009,
+    BindTransceiverResp = 0x80000009,
+    Outbind = 0x0000000B,
+    EnquireLink = 0x00000015,
+    EnquireLinkResp = 0x80000015,
+    SubmitMulti = 0x00000021,
+    SubmitMultiResp = 0x80000021,
+    AlertNotification = 0x00000102,
+    DataSm = 0x00000103,
+    DataSmResp = 0x80000103,
+}
+
+const ALL: [CommandId; 27] = [
+    CommandId::GenericNack,
+    CommandId::BindReceiver,
+    CommandId::BindReceiverResp,
+    CommandId::BindTransmitter,
+    CommandId::BindTransmitterResp,
+    CommandId::QuerySm,
+    CommandId::QuerySmResp,
+    CommandId::SubmitSm,
+    CommandId::SubmitSmResp,
+    CommandId::DeliverSm,
+    CommandId::DeliverSmResp,
+    CommandId::Unbind,
+    CommandId::UnbindResp,
+    CommandId::ReplaceSm,
+    CommandId::ReplaceSmResp,
+    CommandId::CancelSm,
+    CommandId::CancelSmResp,
+    CommandId::BindTransceiver,
+    CommandId::BindTransceiverResp,
+    CommandId::Outbind,
+    CommandId::EnquireLink,
+    CommandId::EnquireLinkResp,
+    CommandId::SubmitMulti,
+    CommandId::SubmitMultiResp,
+    CommandId::AlertNotification,
+    CommandId::DataSm,
+    CommandId::DataSmResp,
+];
+
+impl CommandId {
+    /// The name the spec uses, e.g. "submit_sm_resp"
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::GenericNack => "generic_nack",
+            Self::BindReceiver => "bind_receiver",
+            Self::BindReceiverResp => "bind_receiver_resp",
+            Self::BindTransmitter => "bind_transmitter",
+            Self::BindTransmitterResp => "bind_transmitter_resp",
+            Self::QuerySm => "query_sm",
+            Self::QuerySmResp => "query_sm_resp",
+            Self::SubmitSm => "submit_sm",
+            Self::SubmitSmResp => "submit_sm_resp",
+            Self::DeliverSm => "deliver_sm",
+            Self::DeliverSmResp => "deliver_sm_resp",
+            Self::Unbind => "unbind",
+            Self::UnbindResp => "unbind_resp",
+            Self::ReplaceSm => "replace_sm",
+            Self::ReplaceSmResp => "replace_sm_resp",
+            Self::CancelSm => "cancel_sm",
+            Self::CancelSmResp => "cancel_sm_resp",
+            Self::BindTransceiver => "bind_transceiver",
+            Self::BindTransceiverResp => "bind_transceiver_resp",
+            Self::Outbind => "outbind",
+            Self::EnquireLink => "enquire_link",
+            Self::EnquireLinkResp => "enquire_link_resp",
+            Self::SubmitMulti => "submit_multi",
+            Self::SubmitMultiResp => "submit_multi_resp",
+            Self::AlertNotification => "alert_notification",
+            Self::DataSm => "data_sm",
+            Self::DataSmResp => "data_sm_resp",
+        }
+    }
+
+    pub fn is_response(self) -> bool {
+        u32::from(self) & RESPONSE_BIT != 0
+    }
+
+    /// submit_sm, data_sm or submit_multi, which each hand us a message to
+    /// deliver
+    pub fn is_submit(self) -> bool {
+        matches!(self, Self::SubmitSm | Self::DataSm | Self::SubmitMulti)
+    }
+}
+
+impl From<CommandId> for u32 {
+    fn from(command_id: CommandId) -> Self {
+        command_id as u32
+    }
+}
+
+impl From<&PduBody> for CommandId {
+    fn from(body: &PduBody) -> Self {
+        match body {
+            PduBody::BindReceiver(_) => Self::BindReceiver,
+            PduBody::BindReceiverResp(_) => Self::BindReceiverResp,
+            PduBody::BindTransceiver(_) => Self::BindTransceiver,
+            PduBody::BindTransceiverResp(_) => Self::BindTransceiverResp,
+            PduBody::BindTransmitter(_) => Self::BindTransmitter,
+            PduBody::BindTransmitterResp(_) => Self::BindTransmitterResp,
+            PduBody::DeliverSm(_) => Self::DeliverSm,
+            PduBody::EnquireLink(_) => Self::EnquireLink,
+            PduBody::EnquireLinkResp(_) => Self::EnquireLinkResp,
+            PduBody::GenericNack(_) => Self::GenericNack,
+            PduBody::SubmitSm(_) => Self::SubmitSm,
+            PduBody::SubmitSmResp(_) => Self::SubmitSmResp,
+        }
+    }
+}
+
+impl TryFrom<u32> for CommandId {
+    type Error = u32;
+
+    /// The value itself is the error if it is not an SMPP 3.4 command_id.
+    fn try_from(value: u32) -> Result<Self, u32> {
+        ALL.iter()
+            .copied()
+            .find(|command_id| u32::from(*command_id) == value)
+            .ok_or(value)
+    }
+}
+
+impl Display for CommandId {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str(self.name())
+    }
+}
+
+/// Displays a command_id as its name followed by its value in hex, e.g.
+/// "submit_sm_resp (0x80000004)", or just the hex if it has no name.
+pub struct CommandName(pub u32);
+
+impl Display for CommandName {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match CommandId::try_from(self.0) {
+            Ok(command_id) => {
+                write!(formatter, "{} ({:#010X})", command_id, self.0)
+            }
+            Err(_) => write!(formatter, "{:#010X}", self.0),
+        }
+    }
+}
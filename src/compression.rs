@@ -0,0 +1,135 @@
+//! zlib compression of the bytes of an SMPP session, for slow links such as
+//! satellite.  Nothing on the wire says whether it is in use, so both ends
+//! must be configured to use it, e.g. with the SMSC's --compress option.
+//!
+//! Whatever SMPP bytes are ready to send are compressed together as one
+//! frame: a 4-byte big-endian length, then a zlib stream (RFC 1950) of at
+//! most MAX_FRAME_CONTENT bytes.  Under bulk traffic, many PDUs share a
+//! frame, and their repeated fields compress well.
+//!
+//! compressed() returns the other end of an in-memory pipe carrying the
+//! uncompressed bytes, which can be used anywhere a TCP stream would be,
+//! e.g. SmppConnection::from_stream.  Enabled with the compression
+//! feature.
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use log::*;
+use std::io::{self, Read, Write};
+use tokio::io::{
+    duplex, split, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+    DuplexStream, ReadHalf, WriteHalf,
+};
+
+/// The most uncompressed bytes one frame may carry.
+pub const MAX_FRAME_CONTENT: usize = 64 * 1024;
+
+/// Even incompressible content only grows by a few bytes per 64K, so
+/// anything longer than this is not a frame we sent.
+const MAX_FRAME_LENGTH: usize = 2 * MAX_FRAME_CONTENT;
+
+/// Compress what is written to the returned stream before sending it over
+/// stream, and decompress what arrives.
+pub fn compressed<S>(stream: S) -> DuplexStream
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (read, write) = split(stream);
+    let (ours, theirs) = duplex(MAX_FRAME_CONTENT);
+    let (pipe_read, pipe_write) = split(theirs);
+    tokio::spawn(receive_loop(read, pipe_write));
+    tokio::spawn(send_loop(pipe_read, write));
+    ours
+}
+
+/// Decompress each frame into the pipe, until the peer closes the stream.
+async fn receive_loop<S>(
+    mut read: ReadHalf<S>,
+    mut pipe: WriteHalf<DuplexStream>,
+) where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    loop {
+        let result = match read_frame(&mut read).await {
+            Ok(frame) => pipe.write_all(&frame).await,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            error!("Compressed receive failed: {}", e);
+            break;
+        }
+    }
+    let _ = pipe.shutdown().await;
+}
+
+/// Compress whatever is in the pipe each time something arrives, and close
+/// the stream when the pipe is closed.
+async fn send_loop<S>(mut pipe: ReadHalf<DuplexStream>, mut write: WriteHalf<S>)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let mut buffer = vec![0; MAX_FRAME_CONTENT];
+    loop {
+        let length = match pipe.read(&mut buffer).await {
+            Ok(0) => break,
+            Ok(length) => length,
+            Err(e) => {
+                error!("Compressed send failed: {}", e);
+                break;
+            }
+        };
+        let frame = compress(&buffer[..length]);
+        let mut bytes = Vec::from((frame.len() as u32).to_be_bytes());
+        bytes.extend(frame);
+        if let Err(e) = write.write_all(&bytes).await {
+            error!("Compressed send failed: {}", e);
+            return;
+        }
+    }
+    let _ = write.shutdown().await;
+}
+
+async fn read_frame<R>(read: &mut R) -> io::Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin,
+{
+    let length = read.read_u32().await? as usize;
+    if length > MAX_FRAME_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Compressed frame of {} bytes is too long", length),
+        ));
+    }
+    let mut frame = vec![0; length];
+    read.read_exact(&mut frame).await?;
+    decompress(&frame)
+}
+
+fn compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .and_then(|_| encoder.finish())
+        .expect("writing to a Vec should not fail")
+}
+
+/// Decompress a frame, failing if it would come to more than
+/// MAX_FRAME_CONTENT bytes.
+fn decompress(frame: &[u8]) -> io::Result<Vec<u8>> {
+    let mut content = Vec::new();
+    ZlibDecoder::new(frame)
+        .take(MAX_FRAME_CONTENT as u64 + 1)
+        .read_to_end(&mut content)?;
+    if content.len() > MAX_FRAME_CONTENT {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Compressed frame holds more than {} bytes",
+                MAX_FRAME_CONTENT
+            ),
+        ));
+    }
+    Ok(content)
+}
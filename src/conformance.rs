@@ -0,0 +1,419 @@
+//! A checklist of SMPP 3.4 behaviours to run against any SMSC, for people
+//! building on this crate and for checking third-party SMSCs.  Enabled
+//! with the conformance feature.
+//!
+//! Each check opens its own connection, so one failure cannot upset the
+//! next:
+//!
+//! ```no_run
+//! # async fn example() {
+//! use smpp::conformance::{run, ConformanceTarget};
+//!
+//! let target = ConformanceTarget::new("127.0.0.1:2775", "esme1", "secret");
+//! let report = run(&target).await;
+//! println!("{}", report.to_json());
+//! # }
+//! ```
+
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{
+    BindTransmitterPdu, EnquireLinkPdu, Pdu, PduStatus, SubmitSmPdu,
+};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+use crate::command_id::CommandId;
+use crate::frame_body::{self, HEADER_LENGTH};
+use crate::json::json_string;
+use crate::pdu_status::status_name;
+use crate::pdu_write::write_pdu;
+
+const GENERIC_NACK: u32 = CommandId::GenericNack as u32;
+const BIND_TRANSMITTER_RESP: u32 = CommandId::BindTransmitterResp as u32;
+const ENQUIRE_LINK: u32 = CommandId::EnquireLink as u32;
+const ENQUIRE_LINK_RESP: u32 = CommandId::EnquireLinkResp as u32;
+/// Not assigned to any operation in SMPP 3.4
+const UNKNOWN_COMMAND_ID: u32 = 0x00000022;
+
+/// The SMSC to check, and the account to bind with.
+#[derive(Clone, Debug)]
+pub struct ConformanceTarget {
+    /// host:port
+    pub address: String,
+    pub system_id: String,
+    pub password: String,
+    pub system_type: String,
+    /// How long to wait for each response
+    pub timeout: Duration,
+}
+
+impl ConformanceTarget {
+    pub fn new(address: &str, system_id: &str, password: &str) -> Self {
+        Self {
+            address: String::from(address),
+            system_id: String::from(system_id),
+            password: String::from(password),
+            system_type: String::new(),
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CheckResult {
+    /// e.g. unknown_command_id
+    pub name: &'static str,
+    /// What the spec requires
+    pub description: &'static str,
+    /// Ok, or what happened instead
+    pub outcome: Result<(), String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConformanceReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl ConformanceReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.outcome.is_ok())
+    }
+
+    pub fn failures(&self) -> Vec<&CheckResult> {
+        self.checks
+            .iter()
+            .filter(|check| check.outcome.is_err())
+            .collect()
+    }
+
+    /// {"passed":BOOL,"checks":[{"name":..,"description":..,"passed":BOOL,
+    /// "detail":STRING_OR_NULL},..]}
+    pub fn to_json(&self) -> String {
+        let checks: Vec<String> = self
+            .checks
+            .iter()
+            .map(|check| {
+                format!(
+                    r#"{{"name":{},"description":{},"passed":{},"detail":{}}}"#,
+                    json_string(check.name),
+                    json_string(check.description),
+                    check.outcome.is_ok(),
+                    match &check.outcome {
+                        Ok(()) => String::from("null"),
+                        Err(detail) => json_string(detail),
+                    }
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"passed":{},"checks":[{}]}}"#,
+            self.passed(),
+            checks.join(",")
+        )
+    }
+}
+
+/// Run every check against target, one after another.
+pub async fn run(target: &ConformanceTarget) -> ConformanceReport {
+    let checks = vec![
+        CheckResult {
+            name: "bind_transmitter",
+            description: "bind_transmitter with valid credentials is \
+                answered by bind_transmitter_resp with ESME_ROK",
+            outcome: bind_transmitter(target).await,
+        },
+        CheckResult {
+            name: "enquire_link_when_bound",
+            description: "enquire_link is answered by enquire_link_resp \
+                with ESME_ROK",
+            outcome: enquire_link_when_bound(target).await,
+        },
+        CheckResult {
+            name: "enquire_link_when_open",
+            description: "enquire_link is allowed before binding",
+            outcome: enquire_link_when_open(target).await,
+        },
+        CheckResult {
+            name: "submit_sm_when_open",
+            description: "submit_sm before binding is rejected with \
+                ESME_RINVBNDSTS",
+            outcome: submit_sm_when_open(target).await,
+        },
+        CheckResult {
+            name: "unknown_command_id",
+            description: "an unknown command_id is answered by \
+                generic_nack with ESME_RINVCMDID",
+            outcome: unknown_command_id(target).await,
+        },
+        CheckResult {
+            name: "short_command_length",
+            description: "a command_length shorter than the header is \
+                answered by generic_nack with ESME_RINVCMDLEN",
+            outcome: short_command_length(target).await,
+        },
+    ];
+    ConformanceReport { checks }
+}
+
+async fn bind_transmitter(target: &ConformanceTarget) -> Result<(), String> {
+    let mut session = Session::connect(target).await?;
+    session.bind(target, 1).await
+}
+
+async fn enquire_link_when_bound(
+    target: &ConformanceTarget,
+) -> Result<(), String> {
+    let mut session = Session::connect(target).await?;
+    session.bind(target, 1).await?;
+    session.send(&enquire_link(2).await?).await?;
+    session.receive().await?.expect(
+        ENQUIRE_LINK_RESP,
+        PduStatus::ESME_ROK as u32,
+        2,
+    )
+}
+
+async fn enquire_link_when_open(
+    target: &ConformanceTarget,
+) -> Result<(), String> {
+    let mut session = Session::connect(target).await?;
+    session.send(&enquire_link(1).await?).await?;
+    session.receive().await?.expect(
+        ENQUIRE_LINK_RESP,
+        PduStatus::ESME_ROK as u32,
+        1,
+    )
+}
+
+async fn submit_sm_when_open(target: &ConformanceTarget) -> Result<(), String> {
+    let mut session = Session::connect(target).await?;
+    let submit_sm = SubmitSmPdu::new(
+        "",
+        0,
+        0,
+        "447700900123",
+        0,
+        0,
+        "447700900124",
+        0,
+        0,
+        0,
+        "",
+        "",
+        0,
+        0,
+        0,
+        0,
+        b"conformance",
+        Tlvs::new(),
+    )
+    .map_err(|e| e.to_string())?;
+    session
+        .send(&bytes(Pdu::new(0, 1, submit_sm.into())).await?)
+        .await?;
+    // Either submit_sm_resp or generic_nack is a reasonable way to say it
+    let header = session.receive().await?;
+    header.expect_status(PduStatus::ESME_RINVBNDSTS as u32, 1)
+}
+
+async fn unknown_command_id(target: &ConformanceTarget) -> Result<(), String> {
+    let mut session = Session::connect(target).await?;
+    session.bind(target, 1).await?;
+    session.send(&header(16, UNKNOWN_COMMAND_ID, 2)).await?;
+    session.receive().await?.expect(
+        GENERIC_NACK,
+        PduStatus::ESME_RINVCMDID as u32,
+        2,
+    )
+}
+
+async fn short_command_length(
+    target: &ConformanceTarget,
+) -> Result<(), String> {
+    let mut session = Session::connect(target).await?;
+    session.bind(target, 1).await?;
+    session.send(&header(12, ENQUIRE_LINK, 2)).await?;
+    // The SMSC cannot trust the rest of the header, so any sequence_number
+    // will do.
+    let header = session.receive().await?;
+    if header.command_id == GENERIC_NACK
+        && header.command_status == PduStatus::ESME_RINVCMDLEN as u32
+    {
+        Ok(())
+    } else {
+        Err(format!(
+            "expected generic_nack with ESME_RINVCMDLEN, got {}",
+            header
+        ))
+    }
+}
+
+/// The first 16 bytes of a PDU
+struct Header {
+    command_id: u32,
+    command_status: u32,
+    sequence_number: u32,
+}
+
+impl std::fmt::Display for Header {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "command_id 0x{:08x} with {} and sequence_number {}",
+            self.command_id,
+            status_name(self.command_status)
+                .map(String::from)
+                .unwrap_or_else(|| format!(
+                    "command_status 0x{:08x}",
+                    self.command_status
+                )),
+            self.sequence_number
+        )
+    }
+}
+
+impl Header {
+    fn expect(
+        &self,
+        command_id: u32,
+        command_status: u32,
+        sequence_number: u32,
+    ) -> Result<(), String> {
+        if self.command_id == command_id {
+            self.expect_status(command_status, sequence_number)
+        } else {
+            Err(format!(
+                "expected command_id 0x{:08x}, got {}",
+                command_id, self
+            ))
+        }
+    }
+
+    fn expect_status(
+        &self,
+        command_status: u32,
+        sequence_number: u32,
+    ) -> Result<(), String> {
+        if self.command_status == command_status
+            && self.sequence_number == sequence_number
+        {
+            Ok(())
+        } else {
+            Err(format!(
+                "expected {} and sequence_number {}, got {}",
+                status_name(command_status).unwrap_or("?"),
+                sequence_number,
+                self
+            ))
+        }
+    }
+}
+
+struct Session {
+    stream: TcpStream,
+    timeout: Duration,
+}
+
+impl Session {
+    async fn connect(target: &ConformanceTarget) -> Result<Self, String> {
+        let stream =
+            timeout(target.timeout, TcpStream::connect(&target.address))
+                .await
+                .map_err(|_| String::from("timed out connecting"))?
+                .map_err(|e| format!("failed to connect: {}", e))?;
+        Ok(Self {
+            stream,
+            timeout: target.timeout,
+        })
+    }
+
+    async fn bind(
+        &mut self,
+        target: &ConformanceTarget,
+        sequence_number: u32,
+    ) -> Result<(), String> {
+        self.send(&bind_transmitter_bytes(target, sequence_number).await?)
+            .await?;
+        self.receive()
+            .await?
+            .expect(
+                BIND_TRANSMITTER_RESP,
+                PduStatus::ESME_ROK as u32,
+                sequence_number,
+            )
+            .map_err(|e| format!("bind failed: {}", e))
+    }
+
+    async fn send(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.stream
+            .write_all(bytes)
+            .await
+            .map_err(|e| format!("failed to send: {}", e))
+    }
+
+    /// Read one whole PDU, returning its header
+    async fn receive(&mut self) -> Result<Header, String> {
+        let limit = self.timeout;
+        let read = async {
+            let mut header = [0u8; HEADER_LENGTH];
+            self.stream.read_exact(&mut header).await?;
+            let header = frame_body::Header::from_bytes(&header);
+            let body_length =
+                (header.command_length as usize).saturating_sub(HEADER_LENGTH);
+            let mut body = vec![0u8; body_length];
+            self.stream.read_exact(&mut body).await?;
+            Ok::<Header, std::io::Error>(Header {
+                command_id: header.command_id,
+                command_status: header.command_status,
+                sequence_number: header.sequence_number,
+            })
+        };
+        timeout(limit, read)
+            .await
+            .map_err(|_| String::from("timed out waiting for a response"))?
+            .map_err(|e| format!("failed to read a response: {}", e))
+    }
+}
+
+async fn bind_transmitter_bytes(
+    target: &ConformanceTarget,
+    sequence_number: u32,
+) -> Result<Vec<u8>, String> {
+    let body = BindTransmitterPdu::new(
+        &target.system_id,
+        &target.password,
+        &target.system_type,
+        0x34,
+        0,
+        0,
+        "",
+    )
+    .map_err(|e| e.to_string())?;
+    bytes(Pdu::new(0, sequence_number, body.into())).await
+}
+
+async fn enquire_link(sequence_number: u32) -> Result<Vec<u8>, String> {
+    bytes(Pdu::new(0, sequence_number, EnquireLinkPdu::new().into())).await
+}
+
+fn header(
+    command_length: u32,
+    command_id: u32,
+    sequence_number: u32,
+) -> Vec<u8> {
+    [command_length, command_id, 0, sequence_number]
+        .iter()
+        .flat_map(|value| value.to_be_bytes())
+        .collect()
+}
+
+async fn bytes(
+    pdu: Result<Pdu, smpp_pdu::pdu::PduParseError>,
+) -> Result<Vec<u8>, String> {
+    let pdu = pdu.map_err(|e| e.to_string())?;
+    let mut ret = Vec::new();
+    write_pdu(&pdu, &mut ret).await.map_err(|e| e.to_string())?;
+    Ok(ret)
+}
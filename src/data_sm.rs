@@ -0,0 +1,299 @@
+//! data_sm and data_sm_resp, which some carriers prefer to submit_sm and
+//! deliver_sm.  data_sm has no short_message field, so its content travels
+//! in a message_payload TLV.
+//!
+//! Like deliver_sm_resp, these are missing from smpp_pdu, so they are
+//! standalone types that read and write a whole frame, header included.
+
+use smpp_pdu::pdu::formats::{COctetString, WriteStream};
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody, SubmitSmPdu};
+use std::fmt::{Debug, Formatter};
+use std::io::{self, Cursor};
+
+use crate::command_id::CommandId;
+use crate::frame_body::{
+    c_octet_string_at, command_id, read_bytes, write_frame, Header,
+    HEADER_LENGTH,
+};
+use crate::pdu_clone::PduClone;
+
+pub const DATA_SM: u32 = CommandId::DataSm as u32;
+pub const DATA_SM_RESP: u32 = CommandId::DataSmResp as u32;
+
+const MAX_LENGTH_SERVICE_TYPE: usize = 6;
+const MAX_LENGTH_ADDR: usize = 65;
+const MAX_LENGTH_MESSAGE_ID: usize = 65;
+
+#[derive(PartialEq)]
+pub struct DataSmPdu {
+    pub sequence_number: u32,
+    pub service_type: COctetString,
+    pub source_addr_ton: u8,
+    pub source_addr_npi: u8,
+    pub source_addr: COctetString,
+    pub dest_addr_ton: u8,
+    pub dest_addr_npi: u8,
+    pub destination_addr: COctetString,
+    pub esm_class: u8,
+    pub registered_delivery: u8,
+    pub data_coding: u8,
+    pub tlvs: Tlvs,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DataSmRespPdu {
+    pub command_status: u32,
+    pub sequence_number: u32,
+    pub message_id: COctetString,
+    pub tlvs: Tlvs,
+}
+
+impl DataSmPdu {
+    /// A data_sm carrying message_payload, with every field not given here
+    /// zero or empty.  Set those directly if they matter.
+    pub fn new(
+        sequence_number: u32,
+        source_addr: &str,
+        destination_addr: &str,
+        data_coding: u8,
+        message_payload: &[u8],
+    ) -> Result<Self, PduParseError> {
+        if message_payload.len() > u16::MAX as usize {
+            return Err(PduParseError::new(
+                PduParseErrorBody::IncorrectLength(
+                    message_payload.len() as u32,
+                    String::from("message_payload must fit in a TLV"),
+                ),
+            ));
+        }
+        let addr = |name, value| {
+            COctetString::from_str(value, MAX_LENGTH_ADDR)
+                .map_err(|e| PduParseError::from(e).into_with_field_name(name))
+        };
+        Ok(Self {
+            sequence_number,
+            service_type: COctetString::new(),
+            source_addr_ton: 0,
+            source_addr_npi: 0,
+            source_addr: addr("source_addr", source_addr)?,
+            dest_addr_ton: 0,
+            dest_addr_npi: 0,
+            destination_addr: addr("destination_addr", destination_addr)?,
+            esm_class: 0,
+            registered_delivery: 0,
+            data_coding,
+            tlvs: Tlvs::from(&[Tlv::new(
+                KnownTlvTag::message_payload,
+                message_payload,
+            )]),
+        })
+    }
+
+    /// Does this complete frame (as accepted by Pdu::check) hold a data_sm?
+    pub fn is_data_sm(frame: &[u8]) -> bool {
+        command_id(frame) == Some(DATA_SM)
+    }
+
+    /// The message, which data_sm can only carry in a TLV.  None if the
+    /// TLV is missing, which the receiver should answer with
+    /// ESME_RMISSINGOPTPARAM.
+    pub fn message_payload(&self) -> Option<Vec<u8>> {
+        self.tlvs.get(KnownTlvTag::message_payload).map(|t| t.value)
+    }
+
+    /// The submit_sm that asks for the same thing, with message_payload in
+    /// place of short_message.  Fails where data_sm allows longer fields
+    /// than submit_sm does.
+    pub fn to_submit_sm(&self) -> Result<SubmitSmPdu, PduParseError> {
+        SubmitSmPdu::new(
+            self.service_type.value.as_str(),
+            self.source_addr_ton,
+            self.source_addr_npi,
+            self.source_addr.value.as_str(),
+            self.dest_addr_ton,
+            self.dest_addr_npi,
+            self.destination_addr.value.as_str(),
+            self.esm_class,
+            0,
+            0,
+            "",
+            "",
+            self.registered_delivery,
+            0,
+            self.data_coding,
+            0,
+            b"",
+            self.tlvs.pdu_clone(),
+        )
+    }
+
+    pub fn parse(frame: &[u8]) -> Result<Self, PduParseError> {
+        let header = Header::parse(frame, DATA_SM)?;
+        if header.command_status != 0 {
+            return Err(header.error(PduParseError::new(
+                PduParseErrorBody::StatusIsNotZero,
+            )));
+        }
+        let mut body = Cursor::new(&frame[HEADER_LENGTH..]);
+        let service_type = c_octet_string_at(
+            &mut body,
+            &header,
+            "service_type",
+            MAX_LENGTH_SERVICE_TYPE,
+        )?;
+        let [source_addr_ton, source_addr_npi] =
+            read_bytes(&mut body, &header, "source_addr_npi")?;
+        let source_addr = c_octet_string_at(
+            &mut body,
+            &header,
+            "source_addr",
+            MAX_LENGTH_ADDR,
+        )?;
+        let [dest_addr_ton, dest_addr_npi] =
+            read_bytes(&mut body, &header, "dest_addr_npi")?;
+        let destination_addr = c_octet_string_at(
+            &mut body,
+            &header,
+            "destination_addr",
+            MAX_LENGTH_ADDR,
+        )?;
+        let [esm_class, registered_delivery, data_coding] =
+            read_bytes(&mut body, &header, "data_coding")?;
+        let tlvs = Tlvs::read(&mut body).map_err(|e| {
+            header.error(PduParseError::from(e).into_with_field_name("tlvs"))
+        })?;
+
+        Ok(Self {
+            sequence_number: header.sequence_number,
+            service_type,
+            source_addr_ton,
+            source_addr_npi,
+            source_addr,
+            dest_addr_ton,
+            dest_addr_npi,
+            destination_addr,
+            esm_class,
+            registered_delivery,
+            data_coding,
+            tlvs,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        let mut body: Vec<u8> = Vec::new();
+        self.service_type.write(&mut body).await?;
+        body.extend([self.source_addr_ton, self.source_addr_npi]);
+        self.source_addr.write(&mut body).await?;
+        body.extend([self.dest_addr_ton, self.dest_addr_npi]);
+        self.destination_addr.write(&mut body).await?;
+        body.extend([
+            self.esm_class,
+            self.registered_delivery,
+            self.data_coding,
+        ]);
+        self.tlvs.write(&mut body).await?;
+        write_frame(stream, DATA_SM, 0, self.sequence_number, &body).await
+    }
+}
+
+/// message_payload is left out when building with the
+/// redact-message-content feature, like short_message in crate::redact.
+impl Debug for DataSmPdu {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        let tlvs: &dyn Debug = if cfg!(feature = "redact-message-content") {
+            &format_args!("<redacted>")
+        } else {
+            &self.tlvs
+        };
+        formatter
+            .debug_struct("DataSmPdu")
+            .field("sequence_number", &self.sequence_number)
+            .field("service_type", &self.service_type)
+            .field("source_addr_ton", &self.source_addr_ton)
+            .field("source_addr_npi", &self.source_addr_npi)
+            .field("source_addr", &self.source_addr)
+            .field("dest_addr_ton", &self.dest_addr_ton)
+            .field("dest_addr_npi", &self.dest_addr_npi)
+            .field("destination_addr", &self.destination_addr)
+            .field("esm_class", &self.esm_class)
+            .field("registered_delivery", &self.registered_delivery)
+            .field("data_coding", &self.data_coding)
+            .field("tlvs", tlvs)
+            .finish()
+    }
+}
+
+impl DataSmRespPdu {
+    pub fn new(
+        sequence_number: u32,
+        message_id: &str,
+    ) -> Result<Self, PduParseError> {
+        Ok(Self {
+            command_status: 0,
+            sequence_number,
+            message_id: COctetString::from_str(
+                message_id,
+                MAX_LENGTH_MESSAGE_ID,
+            )
+            .map_err(|e| {
+                PduParseError::from(e).into_with_field_name("message_id")
+            })?,
+            tlvs: Tlvs::new(),
+        })
+    }
+
+    /// A rejection of the data_sm with sequence_number.
+    pub fn new_error(command_status: u32, sequence_number: u32) -> Self {
+        Self {
+            command_status,
+            sequence_number,
+            message_id: COctetString::new(),
+            tlvs: Tlvs::new(),
+        }
+    }
+
+    pub fn is_data_sm_resp(frame: &[u8]) -> bool {
+        command_id(frame) == Some(DATA_SM_RESP)
+    }
+
+    /// Parse one complete frame.  Like deliver_sm_resp, a rejection may
+    /// leave the body out entirely, which is read as an empty message_id.
+    pub fn parse(frame: &[u8]) -> Result<Self, PduParseError> {
+        let header = Header::parse(frame, DATA_SM_RESP)?;
+        let mut body = Cursor::new(&frame[HEADER_LENGTH..]);
+        let message_id = if body.get_ref().is_empty() {
+            COctetString::new()
+        } else {
+            c_octet_string_at(
+                &mut body,
+                &header,
+                "message_id",
+                MAX_LENGTH_MESSAGE_ID,
+            )?
+        };
+        let tlvs = Tlvs::read(&mut body).map_err(|e| {
+            header.error(PduParseError::from(e).into_with_field_name("tlvs"))
+        })?;
+        Ok(Self {
+            command_status: header.command_status,
+            sequence_number: header.sequence_number,
+            message_id,
+            tlvs,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        let mut body: Vec<u8> = Vec::new();
+        self.message_id.write(&mut body).await?;
+        self.tlvs.write(&mut body).await?;
+        write_frame(
+            stream,
+            DATA_SM_RESP,
+            self.command_status,
+            self.sequence_number,
+            &body,
+        )
+        .await
+    }
+}
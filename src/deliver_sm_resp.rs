@@ -0,0 +1,106 @@
+//! deliver_sm_resp, the response to a deliver_sm.
+//!
+//! smpp_pdu parses and writes deliver_sm but has no deliver_sm_resp, and
+//! its PduBody cannot be extended from here, so this is a standalone type
+//! that reads and writes a whole frame, header included.
+
+use smpp_pdu::pdu::formats::{COctetString, WriteStream};
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody};
+use std::io::{self, Cursor};
+use tokio::io::AsyncWriteExt;
+
+use crate::command_id::CommandId;
+use crate::frame_body::{command_id, Header, HEADER_LENGTH};
+
+pub const DELIVER_SM_RESP: u32 = CommandId::DeliverSmResp as u32;
+
+// 4.6.2 says message_id is unused and set to NULL, but it is still a
+// C-Octet String, so accept anything that would fit in a submit_sm_resp.
+const MAX_LENGTH_MESSAGE_ID: usize = 65;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeliverSmRespPdu {
+    pub command_status: u32,
+    pub sequence_number: u32,
+    pub message_id: COctetString,
+}
+
+impl DeliverSmRespPdu {
+    /// A response to the deliver_sm with sequence_number, with the empty
+    /// message_id the spec asks for.
+    pub fn new(command_status: u32, sequence_number: u32) -> Self {
+        Self {
+            command_status,
+            sequence_number,
+            message_id: COctetString::new(),
+        }
+    }
+
+    /// Does this complete frame (as accepted by Pdu::check) hold a
+    /// deliver_sm_resp?
+    pub fn is_deliver_sm_resp(frame: &[u8]) -> bool {
+        command_id(frame) == Some(DELIVER_SM_RESP)
+    }
+
+    /// Parse one complete frame.  Many ESMEs leave the body out entirely
+    /// when rejecting a deliver_sm, so an empty body is read as an empty
+    /// message_id.
+    pub fn parse(frame: &[u8]) -> Result<Self, PduParseError> {
+        let header = Header::peek(frame).ok_or_else(|| {
+            PduParseError::new(PduParseErrorBody::NotEnoughBytes)
+        })?;
+        if header.command_id != DELIVER_SM_RESP {
+            return Err(header.error(PduParseError::new(
+                PduParseErrorBody::UnknownCommandId,
+            )));
+        }
+        if header.command_length as usize != frame.len() {
+            return Err(header.error(PduParseError::new(
+                PduParseErrorBody::LengthLongerThanPdu(header.command_length),
+            )));
+        }
+
+        let mut body = Cursor::new(&frame[HEADER_LENGTH..]);
+        let message_id = if body.get_ref().is_empty() {
+            COctetString::new()
+        } else {
+            COctetString::read(&mut body, MAX_LENGTH_MESSAGE_ID).map_err(
+                |e| {
+                    header.error(
+                        PduParseError::from(e)
+                            .into_with_field_name("message_id"),
+                    )
+                },
+            )?
+        };
+        if (body.position() as usize) < body.get_ref().len() {
+            return Err(header.error(PduParseError::new(
+                PduParseErrorBody::IncorrectLength(
+                    header.command_length,
+                    String::from("Bytes left over after message_id"),
+                ),
+            )));
+        }
+
+        Ok(Self {
+            command_status: header.command_status,
+            sequence_number: header.sequence_number,
+            message_id,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        let mut body: Vec<u8> = Vec::new();
+        self.message_id.write(&mut body).await?;
+        let command_length = (HEADER_LENGTH + body.len()) as u32;
+        for value in &[
+            command_length,
+            DELIVER_SM_RESP,
+            self.command_status,
+            self.sequence_number,
+        ] {
+            stream.write_all(&value.to_be_bytes()).await?;
+        }
+        stream.write_all(&body).await
+    }
+}
@@ -0,0 +1,242 @@
+//! Telling delivery receipts and intermediate notifications apart from
+//! ordinary (MO) messages, and reading what a receipt says.
+//!
+//! Which a deliver_sm is comes from the message type bits of its
+//! esm_class.  A receipt's details come from its TLVs where present, and
+//! otherwise from the de facto standard text format, e.g.
+//! "id:123 sub:001 dlvrd:001 submit date:2101020304 done date:2101020305
+//! stat:DELIVRD err:000 text:hello".
+
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv};
+use smpp_pdu::pdu::DeliverSmPdu;
+use std::error;
+use std::fmt::{Display, Formatter};
+
+use crate::client::Metadata;
+use crate::dlr_batch::DlrOutcome;
+use crate::dlr_errors::{receipt_field, DlrErrorMap};
+use crate::query_sm::MessageState;
+
+/// Bits 2 to 5 of esm_class, per section 5.2.12 of the spec
+const MESSAGE_TYPE_MASK: u8 = 0b0011_1100;
+const SMSC_DELIVERY_RECEIPT: u8 = 0b0000_0100;
+const INTERMEDIATE_DELIVERY_NOTIFICATION: u8 = 0b0010_0000;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DeliveryKind {
+    /// A message from a handset (MO), or anything else that is not a
+    /// receipt or notification, such as an SME acknowledgement
+    Message,
+    /// The final outcome of a message we submitted
+    Receipt,
+    /// Progress of a message we submitted that is still on its way
+    Notification,
+}
+
+impl DeliveryKind {
+    pub fn of(pdu: &DeliverSmPdu) -> Self {
+        Self::from_esm_class(pdu.0.esm_class.value)
+    }
+
+    pub fn from_esm_class(esm_class: u8) -> Self {
+        match esm_class & MESSAGE_TYPE_MASK {
+            SMSC_DELIVERY_RECEIPT => Self::Receipt,
+            INTERMEDIATE_DELIVERY_NOTIFICATION => Self::Notification,
+            _ => Self::Message,
+        }
+    }
+}
+
+/// A deliver_sm holding a delivery receipt, with its details read out.
+/// Fields the receipt does not give are None.
+#[derive(Debug)]
+pub struct DeliveryReceipt {
+    /// The receipted_message_id TLV, or else the id: field of the text
+    pub message_id: Option<String>,
+    /// The message_state TLV, which SMPP 3.4 SMSCs may send instead of,
+    /// or as well as, stat:
+    pub message_state: Option<u8>,
+    /// e.g. "DELIVRD" or "UNDELIV"
+    pub stat: Option<String>,
+    pub err: Option<String>,
+    /// "YYMMDDhhmm"
+    pub submit_date: Option<String>,
+    /// "YYMMDDhhmm"
+    pub done_date: Option<String>,
+    /// The deliver_sm it came in
+    pub pdu: DeliverSmPdu,
+    /// What the message was submitted with by
+    /// Client::submit_sm_with_metadata(), or empty
+    pub metadata: Metadata,
+}
+
+impl DeliveryReceipt {
+    pub fn new(pdu: DeliverSmPdu) -> Self {
+        let text =
+            ReceiptText::parse(&pdu.0.short_message.value).unwrap_or_default();
+        let receipted_message_id = pdu
+            .0
+            .tlvs
+            .get(KnownTlvTag::receipted_message_id)
+            .map(|tlv| {
+                let value = tlv.value.split(|b| *b == 0).next().unwrap();
+                String::from_utf8_lossy(value).into_owned()
+            });
+        Self {
+            message_id: receipted_message_id.or(text.id),
+            message_state: pdu
+                .0
+                .tlvs
+                .get(KnownTlvTag::message_state)
+                .and_then(|tlv| tlv.value.first().copied()),
+            stat: text.stat,
+            err: text.err,
+            submit_date: text.submit_date,
+            done_date: text.done_date,
+            pdu,
+            metadata: Metadata::new(),
+        }
+    }
+
+    /// The final outcome, as for DlrOutcome::from_receipt().
+    pub fn outcome(&self, errors: &DlrErrorMap) -> Option<DlrOutcome> {
+        DlrOutcome::from_receipt(&self.pdu, errors)
+    }
+}
+
+/// The text of a delivery receipt, in the format of SMPP 3.4 Appendix B:
+/// "id:IIIIIIIIII sub:SSS dlvrd:DDD submit date:YYMMDDhhmm done
+/// date:YYMMDDhhmm stat:DDDDDDD err:E text:...".  Fields the text does not
+/// give are None, and are left out when it is written.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReceiptText {
+    pub id: Option<String>,
+    /// How many messages were submitted, e.g. "001"
+    pub sub: Option<String>,
+    /// How many were delivered
+    pub dlvrd: Option<String>,
+    /// "YYMMDDhhmm"
+    pub submit_date: Option<String>,
+    /// "YYMMDDhhmm"
+    pub done_date: Option<String>,
+    /// e.g. "DELIVRD" or "UNDELIV"
+    pub stat: Option<String>,
+    pub err: Option<String>,
+    /// The start of the message, which may contain spaces and so is always
+    /// the last field
+    pub text: Option<String>,
+}
+
+/// A short_message had none of the fields of a delivery receipt.
+#[derive(Debug, PartialEq)]
+pub struct NotAReceipt;
+
+impl Display for NotAReceipt {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("short_message is not a delivery receipt")
+    }
+}
+
+impl error::Error for NotAReceipt {}
+
+impl ReceiptText {
+    /// Read the fields of short_message, which may come in any order and
+    /// with names in any case.  Everything after "text:" is the text.
+    pub fn parse(short_message: &[u8]) -> Result<Self, NotAReceipt> {
+        let message = String::from_utf8_lossy(short_message);
+        let (fields, text) = match text_start(&message) {
+            Some(start) => (&message[..start], Some(&message[start + 5..])),
+            None => (&message[..], None),
+        };
+        let field = |name| receipt_field(fields, name).map(String::from);
+        let ret = Self {
+            id: field("id"),
+            sub: field("sub"),
+            dlvrd: field("dlvrd"),
+            submit_date: date_field(fields, "submit").map(String::from),
+            done_date: date_field(fields, "done").map(String::from),
+            stat: field("stat"),
+            err: field("err"),
+            text: text.map(String::from),
+        };
+        if ret == Self::default() {
+            Err(NotAReceipt)
+        } else {
+            Ok(ret)
+        }
+    }
+
+    /// The state stat: names, if it is one of those in SMPP 3.4
+    pub fn message_state(&self) -> Option<MessageState> {
+        MessageState::from_stat(self.stat.as_deref()?)
+    }
+
+    /// The receipted_message_id and message_state TLVs that say the same
+    /// as this text, for those of id: and stat: that it has.
+    pub fn tlvs(&self) -> Vec<Tlv> {
+        let mut tlvs = Vec::new();
+        if let Some(id) = &self.id {
+            let mut value = Vec::from(id.as_bytes());
+            value.push(0);
+            tlvs.push(Tlv::new(KnownTlvTag::receipted_message_id, &value));
+        }
+        if let Some(state) = self.message_state() {
+            tlvs.push(Tlv::new(KnownTlvTag::message_state, &[state as u8]));
+        }
+        tlvs
+    }
+}
+
+impl Display for ReceiptText {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        let fields = [
+            ("id:", &self.id),
+            ("sub:", &self.sub),
+            ("dlvrd:", &self.dlvrd),
+            ("submit date:", &self.submit_date),
+            ("done date:", &self.done_date),
+            ("stat:", &self.stat),
+            ("err:", &self.err),
+            ("text:", &self.text),
+        ];
+        let mut separator = "";
+        for (name, value) in &fields {
+            if let Some(value) = value {
+                write!(formatter, "{}{}{}", separator, name, value)?;
+                separator = " ";
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where the "text:" field starts, at the start of a word
+fn text_start(message: &str) -> Option<usize> {
+    message.char_indices().find_map(|(i, _)| {
+        let at_word_start = message[..i]
+            .chars()
+            .next_back()
+            .map_or(true, char::is_whitespace);
+        let name = message.get(i..i + 5)?;
+        if at_word_start && name.eq_ignore_ascii_case("text:") {
+            Some(i)
+        } else {
+            None
+        }
+    })
+}
+
+/// The value of a two-word field such as "submit date:2101020304"
+fn date_field<'a>(text: &'a str, first_word: &str) -> Option<&'a str> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    words.windows(2).find_map(|pair| {
+        let (name, value) = pair[1].split_once(':')?;
+        if pair[0].eq_ignore_ascii_case(first_word)
+            && name.eq_ignore_ascii_case("date")
+        {
+            Some(value)
+        } else {
+            None
+        }
+    })
+}
@@ -0,0 +1,178 @@
+//! Summaries of delivery outcomes for groups of messages, such as the
+//! parts of one multipart message or every message in a bulk campaign.
+//!
+//! Start a batch under a key of your choosing with the number of outcomes
+//! to expect, record each delivery receipt against that key, and a
+//! BatchSummary comes back once every outcome is in.  Call expire()
+//! periodically to give up on batches whose receipts never all arrive.
+
+use smpp_pdu::pdu::DeliverSmPdu;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, TokioClock};
+use crate::dlr_errors::{receipt_field, DeliveryError, DlrErrorMap};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DlrOutcome {
+    Delivered,
+    /// Failed, and why if the receipt said
+    Failed(Option<DeliveryError>),
+}
+
+impl DlrOutcome {
+    /// The final outcome a delivery receipt reports, or None if its stat:
+    /// is missing or only says the message is still on its way (ENROUTE,
+    /// ACCEPTD).
+    pub fn from_receipt(
+        pdu: &DeliverSmPdu,
+        errors: &DlrErrorMap,
+    ) -> Option<Self> {
+        let text = String::from_utf8_lossy(&pdu.0.short_message.value);
+        let stat = receipt_field(&text, "stat")?.to_ascii_uppercase();
+        match stat.as_str() {
+            "DELIVRD" => Some(Self::Delivered),
+            "ENROUTE" | "ACCEPTD" => None,
+            _ => Some(Self::Failed(errors.classify(pdu))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchSummary<K> {
+    pub key: K,
+    pub delivered: usize,
+    pub failed: usize,
+    /// Outcomes that never arrived, if the batch timed out
+    pub missing: usize,
+    /// Why the first failure failed, if it said
+    pub first_failure: Option<DeliveryError>,
+    pub timed_out: bool,
+}
+
+struct Batch {
+    expected: usize,
+    delivered: usize,
+    failed: usize,
+    first_failure: Option<DeliveryError>,
+    started: Instant,
+}
+
+pub struct DlrBatches<K> {
+    timeout: Duration,
+    batches: HashMap<K, Batch>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<K: Clone + Eq + Hash> DlrBatches<K> {
+    /// Batches still incomplete timeout after they started are summarised
+    /// by expire().
+    pub fn new(timeout: Duration) -> Self {
+        Self::with_clock(timeout, Arc::new(TokioClock))
+    }
+
+    /// Like new(), timing batches by clock.
+    pub fn with_clock(timeout: Duration, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            timeout,
+            batches: HashMap::new(),
+            clock,
+        }
+    }
+
+    /// Start waiting for expected outcomes under key, replacing any batch
+    /// already using it.
+    pub fn start(&mut self, key: K, expected: usize) {
+        self.batches.insert(
+            key,
+            Batch {
+                expected,
+                delivered: 0,
+                failed: 0,
+                first_failure: None,
+                started: self.clock.now(),
+            },
+        );
+    }
+
+    /// How many batches are still waiting for outcomes
+    pub fn len(&self) -> usize {
+        self.batches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.batches.is_empty()
+    }
+
+    /// Count outcome against key's batch, returning its summary if that
+    /// was the last outcome it was waiting for.  Outcomes for keys with no
+    /// batch (e.g. one that already timed out) are ignored.
+    pub fn record(
+        &mut self,
+        key: &K,
+        outcome: DlrOutcome,
+    ) -> Option<BatchSummary<K>> {
+        let batch = self.batches.get_mut(key)?;
+        match outcome {
+            DlrOutcome::Delivered => batch.delivered += 1,
+            DlrOutcome::Failed(error) => {
+                if batch.failed == 0 {
+                    batch.first_failure = error;
+                }
+                batch.failed += 1;
+            }
+        }
+        if batch.delivered + batch.failed < batch.expected {
+            return None;
+        }
+        let batch = self.batches.remove(key)?;
+        Some(summary(key.clone(), &batch, false))
+    }
+
+    /// Like record(), for the outcome a delivery receipt reports.
+    /// Receipts that report no final outcome are not counted.
+    pub fn record_receipt(
+        &mut self,
+        key: &K,
+        pdu: &DeliverSmPdu,
+        errors: &DlrErrorMap,
+    ) -> Option<BatchSummary<K>> {
+        self.record(key, DlrOutcome::from_receipt(pdu, errors)?)
+    }
+
+    /// Remove every batch started more than timeout before now, returning
+    /// their summaries so far.
+    pub fn expire(&mut self, now: Instant) -> Vec<BatchSummary<K>> {
+        let timeout = self.timeout;
+        let expired: Vec<K> = self
+            .batches
+            .iter()
+            .filter(|(_, batch)| {
+                now.saturating_duration_since(batch.started) >= timeout
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|key| {
+                let batch = self.batches.remove(&key)?;
+                Some(summary(key, &batch, true))
+            })
+            .collect()
+    }
+}
+
+fn summary<K>(key: K, batch: &Batch, timed_out: bool) -> BatchSummary<K> {
+    BatchSummary {
+        key,
+        delivered: batch.delivered,
+        failed: batch.failed,
+        missing: batch
+            .expected
+            .saturating_sub(batch.delivered + batch.failed),
+        first_failure: batch.first_failure,
+        timed_out,
+    }
+}
@@ -0,0 +1,223 @@
+//! Why a message was not delivered, in terms that do not depend on which
+//! carrier reported it.  Delivery receipts say why in an err: field in
+//! their text, a network_error_code TLV, or both, and carriers use
+//! different codes, so DlrErrorMap starts from the common GSM MAP meanings
+//! and can be given each carrier's overrides.
+
+use smpp_pdu::pdu::DeliverSmPdu;
+use std::collections::HashMap;
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::typed_tlvs::{NetworkErrorCode, TypedTlvs, NETWORK_TYPE_GSM};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DeliveryError {
+    /// Switched off or out of coverage
+    AbsentSubscriber,
+    HandsetMemoryFull,
+    /// Barred by the operator, or not allowed to receive SMS
+    Barred,
+    /// Not delivered before the validity period ran out
+    Expired,
+    /// The number does not exist
+    UnknownSubscriber,
+    /// A temporary problem in the network
+    NetworkFailure,
+    /// A code we have no mapping for
+    Unknown,
+}
+
+impl DeliveryError {
+    const NAMES: &'static [(&'static str, DeliveryError)] = &[
+        ("absent_subscriber", Self::AbsentSubscriber),
+        ("handset_memory_full", Self::HandsetMemoryFull),
+        ("barred", Self::Barred),
+        ("expired", Self::Expired),
+        ("unknown_subscriber", Self::UnknownSubscriber),
+        ("network_failure", Self::NetworkFailure),
+        ("unknown", Self::Unknown),
+    ];
+}
+
+impl Display for DeliveryError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        let (name, _) = Self::NAMES.iter().find(|(_, e)| e == self).unwrap();
+        formatter.write_str(name)
+    }
+}
+
+impl FromStr for DeliveryError {
+    type Err = ParseDlrErrorOverrideError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::NAMES
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, e)| *e)
+            .ok_or_else(|| ParseDlrErrorOverrideError(String::from(s)))
+    }
+}
+
+/// GSM MAP error codes, which most SMSCs use for err: as well as for GSM
+/// network_error_codes
+const GSM_MAP_ERRORS: &[(u16, DeliveryError)] = &[
+    (1, DeliveryError::UnknownSubscriber),
+    (5, DeliveryError::UnknownSubscriber),
+    (9, DeliveryError::Barred),
+    (11, DeliveryError::Barred),
+    (12, DeliveryError::Barred),
+    (13, DeliveryError::Barred),
+    (27, DeliveryError::AbsentSubscriber),
+    (31, DeliveryError::NetworkFailure),
+    // SM delivery failure, which carriers use for a full handset
+    (32, DeliveryError::HandsetMemoryFull),
+    (34, DeliveryError::NetworkFailure),
+];
+
+#[derive(Debug)]
+pub struct ParseDlrErrorOverrideError(String);
+
+impl Display for ParseDlrErrorOverrideError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "Invalid DLR error override '{}': expected err:CODE=ERROR or \
+            network:TYPE:CODE=ERROR, e.g. err:027=absent_subscriber",
+            self.0
+        )
+    }
+}
+
+impl error::Error for ParseDlrErrorOverrideError {}
+
+#[derive(Clone, Debug)]
+pub struct DlrErrorMap {
+    err: HashMap<String, DeliveryError>,
+    network: HashMap<(u8, u16), DeliveryError>,
+}
+
+impl Default for DlrErrorMap {
+    /// The GSM MAP meanings, for err: and GSM network_error_codes
+    fn default() -> Self {
+        let mut ret = Self {
+            err: HashMap::new(),
+            network: HashMap::new(),
+        };
+        for (code, error) in GSM_MAP_ERRORS {
+            ret.set_err(&code.to_string(), *error);
+            ret.set_network(NETWORK_TYPE_GSM, *code, *error);
+        }
+        ret
+    }
+}
+
+impl DlrErrorMap {
+    /// Map err:code to error.  Numeric codes match however many leading
+    /// zeros they are written with.
+    pub fn set_err(&mut self, code: &str, error: DeliveryError) {
+        self.err.insert(err_key(code), error);
+    }
+
+    pub fn set_network(
+        &mut self,
+        network_type: u8,
+        error_code: u16,
+        error: DeliveryError,
+    ) {
+        self.network.insert((network_type, error_code), error);
+    }
+
+    /// Apply a carrier's overrides, one per line, written
+    /// err:CODE=ERROR or network:TYPE:CODE=ERROR, where ERROR is e.g.
+    /// absent_subscriber.  Blank lines and lines starting with # are
+    /// ignored.
+    pub fn load_overrides(
+        &mut self,
+        overrides: &str,
+    ) -> Result<(), ParseDlrErrorOverrideError> {
+        for line in overrides.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = || ParseDlrErrorOverrideError(String::from(line));
+            let (key, error) = line.split_once('=').ok_or_else(err)?;
+            let error: DeliveryError =
+                error.trim().parse().map_err(|_| err())?;
+            let key: Vec<&str> = key.trim().split(':').collect();
+            match key[..] {
+                ["err", code] if !code.is_empty() => self.set_err(code, error),
+                ["network", network_type, code] => self.set_network(
+                    network_type.parse().map_err(|_| err())?,
+                    code.parse().map_err(|_| err())?,
+                    error,
+                ),
+                _ => return Err(err()),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn from_err(&self, code: &str) -> DeliveryError {
+        self.err
+            .get(&err_key(code))
+            .copied()
+            .unwrap_or(DeliveryError::Unknown)
+    }
+
+    pub fn from_network_error_code(
+        &self,
+        code: NetworkErrorCode,
+    ) -> DeliveryError {
+        self.network
+            .get(&(code.network_type, code.error_code))
+            .copied()
+            .unwrap_or(DeliveryError::Unknown)
+    }
+
+    /// Why the message a delivery receipt is about failed, or None if the
+    /// receipt gives no error (or only 0).  network_error_code is preferred over err:,
+    /// and stat:EXPIRED is used if neither gives a known error.
+    pub fn classify(&self, pdu: &DeliverSmPdu) -> Option<DeliveryError> {
+        let text = String::from_utf8_lossy(&pdu.0.short_message.value);
+        let candidates = [
+            pdu.0
+                .tlvs
+                .network_error_code()
+                .filter(|code| code.error_code != 0)
+                .map(|code| self.from_network_error_code(code)),
+            receipt_field(&text, "err")
+                .filter(|code| err_key(code) != "0")
+                .map(|code| self.from_err(code)),
+        ];
+        let found = candidates.iter().flatten();
+        let known = found.clone().find(|e| **e != DeliveryError::Unknown);
+        let expired = receipt_field(&text, "stat")
+            .filter(|stat| stat.eq_ignore_ascii_case("EXPIRED"))
+            .map(|_| DeliveryError::Expired);
+        known.copied().or(expired).or_else(|| found.copied().next())
+    }
+}
+
+/// The value of field in delivery receipt text like
+/// "id:123 sub:001 dlvrd:000 ... stat:UNDELIV err:027 text:..."
+pub(crate) fn receipt_field<'a>(text: &'a str, field: &str) -> Option<&'a str> {
+    text.split_whitespace().find_map(|word| {
+        let (name, value) = word.split_once(':')?;
+        if name.eq_ignore_ascii_case(field) {
+            Some(value)
+        } else {
+            None
+        }
+    })
+}
+
+fn err_key(code: &str) -> String {
+    if !code.is_empty() && code.chars().all(|c| c.is_ascii_digit()) {
+        let trimmed = code.trim_start_matches('0');
+        String::from(if trimmed.is_empty() { "0" } else { trimmed })
+    } else {
+        code.to_ascii_uppercase()
+    }
+}
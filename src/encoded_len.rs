@@ -0,0 +1,204 @@
+//! Working out how many bytes a PDU will occupy on the wire, without
+//! writing it out.
+
+use smpp_pdu::pdu::data::bind_data::BindData;
+use smpp_pdu::pdu::data::bind_resp_data::BindRespData;
+use smpp_pdu::pdu::data::sm_data::SmData;
+use smpp_pdu::pdu::formats::{COctetString, Integer1, Integer4, OctetString};
+use smpp_pdu::pdu::tlvs::{Tlv, Tlvs};
+use smpp_pdu::pdu::{
+    BindReceiverPdu, BindReceiverRespPdu, BindTransceiverPdu,
+    BindTransceiverRespPdu, BindTransmitterPdu, BindTransmitterRespPdu,
+    DeliverSmPdu, EnquireLinkPdu, EnquireLinkRespPdu, GenericNackPdu, Pdu,
+    PduBody, SubmitSmPdu, SubmitSmRespPdu,
+};
+
+use crate::pdu_accessors::BindRespAccessors;
+use crate::typed_tlvs::TypedTlvs;
+
+/// command_length, command_id, command_status and sequence_number
+const HEADER_LENGTH: usize = 16;
+
+pub trait EncodedLen {
+    /// The number of octets this will take up when written.  For a Pdu,
+    /// this is the value that will be sent as its command_length.
+    fn encoded_len(&self) -> usize;
+}
+
+impl EncodedLen for Pdu {
+    fn encoded_len(&self) -> usize {
+        HEADER_LENGTH + self.body().encoded_len()
+    }
+}
+
+impl EncodedLen for PduBody {
+    fn encoded_len(&self) -> usize {
+        match self {
+            PduBody::BindReceiver(body) => body.encoded_len(),
+            PduBody::BindReceiverResp(body) => body.encoded_len(),
+            PduBody::BindTransceiver(body) => body.encoded_len(),
+            PduBody::BindTransceiverResp(body) => body.encoded_len(),
+            PduBody::BindTransmitter(body) => body.encoded_len(),
+            PduBody::BindTransmitterResp(body) => body.encoded_len(),
+            PduBody::DeliverSm(body) => body.encoded_len(),
+            PduBody::EnquireLink(body) => body.encoded_len(),
+            PduBody::EnquireLinkResp(body) => body.encoded_len(),
+            PduBody::GenericNack(body) => body.encoded_len(),
+            PduBody::SubmitSm(body) => body.encoded_len(),
+            PduBody::SubmitSmResp(body) => body.encoded_len(),
+        }
+    }
+}
+
+impl EncodedLen for BindReceiverPdu {
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+
+impl EncodedLen for BindReceiverRespPdu {
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+
+impl EncodedLen for BindTransceiverPdu {
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+
+impl EncodedLen for BindTransceiverRespPdu {
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+
+impl EncodedLen for BindTransmitterPdu {
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+
+impl EncodedLen for BindTransmitterRespPdu {
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+
+impl EncodedLen for DeliverSmPdu {
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+
+impl EncodedLen for EnquireLinkPdu {
+    fn encoded_len(&self) -> usize {
+        0
+    }
+}
+
+impl EncodedLen for EnquireLinkRespPdu {
+    fn encoded_len(&self) -> usize {
+        0
+    }
+}
+
+impl EncodedLen for GenericNackPdu {
+    fn encoded_len(&self) -> usize {
+        0
+    }
+}
+
+impl EncodedLen for SubmitSmPdu {
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+
+impl EncodedLen for SubmitSmRespPdu {
+    fn encoded_len(&self) -> usize {
+        self.message_id.as_ref().map_or(0, |m| m.encoded_len())
+    }
+}
+
+impl EncodedLen for BindData {
+    fn encoded_len(&self) -> usize {
+        self.system_id.encoded_len()
+            + self.password.encoded_len()
+            + self.system_type.encoded_len()
+            + self.interface_version.encoded_len()
+            + self.addr_ton.encoded_len()
+            + self.addr_npi.encoded_len()
+            + self.address_range.encoded_len()
+    }
+}
+
+impl EncodedLen for BindRespData {
+    fn encoded_len(&self) -> usize {
+        // A failed bind's response has no body
+        self.system_id().map_or(0, |system_id| system_id.len() + 1)
+    }
+}
+
+impl EncodedLen for SmData {
+    fn encoded_len(&self) -> usize {
+        self.service_type.encoded_len()
+            + self.source_addr_ton.encoded_len()
+            + self.source_addr_npi.encoded_len()
+            + self.source_addr.encoded_len()
+            + self.dest_addr_ton.encoded_len()
+            + self.dest_addr_npi.encoded_len()
+            + self.destination_addr.encoded_len()
+            + self.esm_class.encoded_len()
+            + self.protocol_id.encoded_len()
+            + self.priority_flag.encoded_len()
+            + self.schedule_delivery_time.encoded_len()
+            + self.validity_period.encoded_len()
+            + self.registered_delivery.encoded_len()
+            + self.replace_if_present_flag.encoded_len()
+            + self.data_coding.encoded_len()
+            + self.sm_default_msg_id.encoded_len()
+            + 1 // sm_length
+            + self.short_message.encoded_len()
+            + self.tlvs.encoded_len()
+    }
+}
+
+impl EncodedLen for Tlvs {
+    fn encoded_len(&self) -> usize {
+        self.to_vec().iter().map(Tlv::encoded_len).sum()
+    }
+}
+
+impl EncodedLen for Tlv {
+    fn encoded_len(&self) -> usize {
+        // Tag and length, then the value
+        4 + self.value.len()
+    }
+}
+
+impl EncodedLen for Integer1 {
+    fn encoded_len(&self) -> usize {
+        1
+    }
+}
+
+impl EncodedLen for Integer4 {
+    fn encoded_len(&self) -> usize {
+        4
+    }
+}
+
+impl EncodedLen for COctetString {
+    fn encoded_len(&self) -> usize {
+        // Includes the NULL terminator
+        self.len() + 1
+    }
+}
+
+impl EncodedLen for OctetString {
+    fn encoded_len(&self) -> usize {
+        self.len()
+    }
+}
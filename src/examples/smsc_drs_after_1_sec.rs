@@ -22,6 +22,12 @@ impl DrsAfter1Sec {
     }
 }
 
+impl Default for DrsAfter1Sec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait]
 impl SmscLogic for DrsAfter1Sec {
     async fn bind(&mut self, bind_data: &BindData) -> Result<(), BindError> {
@@ -48,7 +54,7 @@ impl SmscLogic for DrsAfter1Sec {
                 .await
         });
         Ok((
-            SubmitSmRespPdu::new(message_id).unwrap().into(),
+            SubmitSmRespPdu::new(message_id).unwrap(),
             MessageUniqueKey::new(
                 String::from("MySupplier"),
                 String::from(message_id),
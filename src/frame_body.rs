@@ -0,0 +1,279 @@
+//! Reading and writing whole frames for the PDUs that smpp_pdu lacks, such
+//! as data_sm, which are standalone types rather than PduBody variants.
+
+use smpp_pdu::pdu::formats::{COctetString, WriteStream};
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody};
+use std::convert::TryFrom;
+use std::io::{self, Cursor, Read};
+use tokio::io::AsyncWriteExt;
+
+/// command_length, command_id, command_status and sequence_number
+pub(crate) const HEADER_LENGTH: usize = 16;
+
+// "YYMMDDhhmmsstnnp" and its NULL terminator
+const MAX_LENGTH_TIME: usize = 17;
+
+pub(crate) struct Header {
+    pub command_length: u32,
+    pub command_id: u32,
+    pub command_status: u32,
+    pub sequence_number: u32,
+}
+
+impl Header {
+    /// All four fields, decoded in one go and not checked.
+    pub(crate) fn from_bytes(bytes: &[u8; HEADER_LENGTH]) -> Self {
+        let all = u128::from_be_bytes(*bytes);
+        Self {
+            command_length: (all >> 96) as u32,
+            command_id: (all >> 64) as u32,
+            command_status: (all >> 32) as u32,
+            sequence_number: all as u32,
+        }
+    }
+
+    /// The unchecked header at the start of frame, or None if frame is too
+    /// short to have one.
+    pub(crate) fn peek(frame: &[u8]) -> Option<Self> {
+        let bytes = frame.get(..HEADER_LENGTH)?;
+        Some(Self::from_bytes(
+            <&[u8; HEADER_LENGTH]>::try_from(bytes).ok()?,
+        ))
+    }
+
+    /// The header of a complete frame that should have command_id
+    /// expected_command_id.
+    pub(crate) fn parse(
+        frame: &[u8],
+        expected_command_id: u32,
+    ) -> Result<Self, PduParseError> {
+        let header = Self::peek(frame).ok_or_else(|| {
+            PduParseError::new(PduParseErrorBody::NotEnoughBytes)
+        })?;
+        if header.command_id != expected_command_id {
+            return Err(header.error(PduParseError::new(
+                PduParseErrorBody::UnknownCommandId,
+            )));
+        }
+        if header.command_length as usize != frame.len() {
+            return Err(header.error(PduParseError::new(
+                PduParseErrorBody::LengthLongerThanPdu(header.command_length),
+            )));
+        }
+        Ok(header)
+    }
+
+    pub(crate) fn error(&self, e: PduParseError) -> PduParseError {
+        e.into_with_header(
+            Some(self.command_id),
+            Some(self.command_status),
+            Some(self.sequence_number),
+        )
+    }
+}
+
+pub(crate) fn c_octet_string_at(
+    body: &mut Cursor<&[u8]>,
+    header: &Header,
+    name: &str,
+    max_length: usize,
+) -> Result<COctetString, PduParseError> {
+    COctetString::read(body, max_length).map_err(|e| {
+        header.error(PduParseError::from(e).into_with_field_name(name))
+    })
+}
+
+/// An absolute or relative time such as schedule_delivery_time, which is
+/// either empty or 16 characters, "YYMMDDhhmmsstnnp".
+pub(crate) fn time_at(
+    body: &mut Cursor<&[u8]>,
+    header: &Header,
+    name: &str,
+) -> Result<COctetString, PduParseError> {
+    let time = c_octet_string_at(body, header, name, MAX_LENGTH_TIME)?;
+    check_time(name, time.value.as_str()).map_err(|e| header.error(e))?;
+    Ok(time)
+}
+
+pub(crate) fn c_octet_string(
+    name: &str,
+    value: &str,
+    max_length: usize,
+) -> Result<COctetString, PduParseError> {
+    COctetString::from_str(value, max_length)
+        .map_err(|e| PduParseError::from(e).into_with_field_name(name))
+}
+
+/// A time field with value, which must be empty or 16 characters.
+pub(crate) fn time(
+    name: &str,
+    value: &str,
+) -> Result<COctetString, PduParseError> {
+    check_time(name, value)?;
+    c_octet_string(name, value, MAX_LENGTH_TIME)
+}
+
+fn check_time(name: &str, value: &str) -> Result<(), PduParseError> {
+    let bytes = value.as_bytes();
+    let valid = bytes.is_empty()
+        || (bytes.len() == MAX_LENGTH_TIME - 1
+            && bytes[..15].iter().all(u8::is_ascii_digit)
+            && b"+-R".contains(&bytes[15]));
+    if valid {
+        Ok(())
+    } else {
+        Err(PduParseError::new(PduParseErrorBody::IncorrectLength(
+            bytes.len() as u32,
+            format!("{} must be empty or YYMMDDhhmmsstnnp", name),
+        ))
+        .into_with_field_name(name))
+    }
+}
+
+/// The next N one-byte fields.  Running out is blamed on name, the last.
+pub(crate) fn read_bytes<const N: usize>(
+    body: &mut Cursor<&[u8]>,
+    header: &Header,
+    name: &str,
+) -> Result<[u8; N], PduParseError> {
+    let mut bytes = [0; N];
+    body.read_exact(&mut bytes).map_err(|e| {
+        header.error(PduParseError::from(e).into_with_field_name(name))
+    })?;
+    Ok(bytes)
+}
+
+/// A four-byte field such as error_status_code.
+pub(crate) fn u32_at(
+    body: &mut Cursor<&[u8]>,
+    header: &Header,
+    name: &str,
+) -> Result<u32, PduParseError> {
+    read_bytes::<4>(body, header, name).map(u32::from_be_bytes)
+}
+
+/// count entries of a repeated field such as submit_multi's dest_address,
+/// each read by read_one.  count comes from the one-byte field before.
+pub(crate) fn repeated_at<T>(
+    body: &mut Cursor<&[u8]>,
+    header: &Header,
+    count: u8,
+    mut read_one: impl FnMut(
+        &mut Cursor<&[u8]>,
+        &Header,
+    ) -> Result<T, PduParseError>,
+) -> Result<Vec<T>, PduParseError> {
+    (0..count).map(|_| read_one(body, header)).collect()
+}
+
+/// Declares a PDU type whose body is a fixed list of fields, along with
+/// read_body() and write_body(), which go between those fields and the
+/// bytes after the header.  Each field is declared as one of
+///
+/// - `u8`
+/// - `COctetString(max_length)`, max_length counting the NULL
+/// - `Time`, a COctetString that must be empty or "YYMMDDhhmmsstnnp"
+///
+/// and errors name the field that failed.  The type gets a sequence_number
+/// first.  parse() and write() are still written by hand, since what they
+/// do with the header and after the body varies.
+macro_rules! pdu_fields {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                pub $field:ident: $kind:ident $(($max:expr))?,
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        pub struct $name {
+            pub sequence_number: u32,
+            $(
+                $(#[$field_meta])*
+                pub $field: $crate::frame_body::pdu_fields!(@type $kind),
+            )*
+        }
+
+        impl $name {
+            /// The fields of a body, in order, from header onwards.
+            fn read_body(
+                header: &$crate::frame_body::Header,
+                body: &mut ::std::io::Cursor<&[u8]>,
+            ) -> Result<Self, ::smpp_pdu::pdu::PduParseError> {
+                Ok(Self {
+                    sequence_number: header.sequence_number,
+                    $(
+                        $field: $crate::frame_body::pdu_fields!(
+                            @read body, header, $field, $kind $(($max))?
+                        ),
+                    )*
+                })
+            }
+
+            /// The bytes after the header.
+            async fn write_body(&self) -> ::std::io::Result<Vec<u8>> {
+                let mut body: Vec<u8> = Vec::new();
+                $(
+                    $crate::frame_body::pdu_fields!(
+                        @write body, self.$field, $kind
+                    );
+                )*
+                Ok(body)
+            }
+        }
+    };
+    (@type u8) => { u8 };
+    (@type COctetString) => { ::smpp_pdu::pdu::formats::COctetString };
+    (@type Time) => { ::smpp_pdu::pdu::formats::COctetString };
+    (@read $body:ident, $header:ident, $field:ident, u8) => {
+        $crate::frame_body::read_bytes::<1>(
+            $body,
+            $header,
+            stringify!($field),
+        )?[0]
+    };
+    (@read $body:ident, $header:ident, $field:ident, COctetString($max:expr)) => {
+        $crate::frame_body::c_octet_string_at(
+            $body,
+            $header,
+            stringify!($field),
+            $max,
+        )?
+    };
+    (@read $body:ident, $header:ident, $field:ident, Time) => {
+        $crate::frame_body::time_at($body, $header, stringify!($field))?
+    };
+    (@write $body:ident, $value:expr, u8) => {
+        $body.push($value)
+    };
+    (@write $body:ident, $value:expr, $kind:ident) => {
+        $value.write(&mut $body).await?
+    };
+}
+
+pub(crate) use pdu_fields;
+
+pub(crate) async fn write_frame(
+    stream: &mut WriteStream,
+    command_id: u32,
+    command_status: u32,
+    sequence_number: u32,
+    body: &[u8],
+) -> io::Result<()> {
+    let command_length = (HEADER_LENGTH + body.len()) as u32;
+    for value in &[command_length, command_id, command_status, sequence_number]
+    {
+        stream.write_all(&value.to_be_bytes()).await?;
+    }
+    stream.write_all(body).await
+}
+
+pub(crate) fn command_id(frame: &[u8]) -> Option<u32> {
+    frame.get(4..8).map(read_u32)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
@@ -0,0 +1,338 @@
+//! A ready-made SMPP aggregator.  ESMEs bind to us as to any SMSC, each
+//! submit_sm is passed on to the cheapest healthy upstream SMSC with a
+//! route to its destination_addr, and the delivery receipts upstreams send
+//! come back to the ESME that submitted the message.
+//!
+//! ```no_run
+//! # async fn example() -> smpp::async_result::AsyncResult<()> {
+//! use clap::Clap;
+//! use smpp::gateway::{GatewayBuilder, Upstream};
+//! use smpp::smsc::SmscConfig;
+//!
+//! let gateway = GatewayBuilder::new(SmscConfig::parse())
+//!     .account("esme1", "secret")
+//!     .upstream(Upstream::new("uk", "10.0.0.1:2775", "gateway", "pw1"))
+//!     .upstream(Upstream::new("world", "10.0.0.2:2775", "gateway", "pw2"))
+//!     .route("44", "uk")
+//!     .route_with_cost("", "world", 5)
+//!     .start()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Everything SmscConfig turns on, such as source quotas or duplicate
+//! suppression, applies to ESMEs as usual.  Upstreams are connected and
+//! bound as transceivers by start(), and reconnected if they close.
+//! Messages (MOs) from upstreams are not passed on.
+
+use async_trait::async_trait;
+use log::*;
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+use crate::async_result::AsyncResult;
+use crate::bridge::Bridge;
+use crate::message_unique_key::MessageUniqueKey;
+use crate::pdu_accessors::{BindAccessors, SmAccessors};
+use crate::smsc::{
+    BindData, BindError, MessageIdMap, Smsc, SmscConfig, SmscLogic,
+    SubmitSmArchive, SubmitSmError,
+};
+
+pub use crate::bridge::{ReceiptSink, Upstream};
+
+/// A route names an upstream that was never added
+#[derive(Debug)]
+pub struct UnknownUpstream(pub String);
+
+impl Display for UnknownUpstream {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "No upstream called '{}'", self.0)
+    }
+}
+
+impl error::Error for UnknownUpstream {}
+
+/// A call detail record: where one accepted message went
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Cdr {
+    /// As given to the ESME
+    pub message_id: String,
+    pub destination_addr: String,
+    /// The upstream that accepted it
+    pub upstream: String,
+    /// Of the route to that upstream
+    pub cost: u32,
+    /// Routes tried, including the one that accepted it
+    pub attempts: usize,
+}
+
+/// Told of each message an upstream accepts, e.g. to store CDRs for
+/// billing.  Register with GatewayBuilder::cdr_sink().
+pub trait CdrSink {
+    fn record(&self, cdr: &Cdr);
+}
+
+/// Nothing is connected until start().  With no accounts, no ESME can
+/// bind; with no routes, every submit_sm is rejected with ESME_RINVDSTADR.
+pub struct GatewayBuilder {
+    config: SmscConfig,
+    accounts: HashMap<String, String>,
+    upstreams: Vec<Upstream>,
+    routes: Vec<(String, String, u32)>,
+    archive: Option<Arc<dyn SubmitSmArchive + Send + Sync>>,
+    message_id_map: Option<Arc<dyn MessageIdMap + Send + Sync>>,
+    receipt_sink: Option<Arc<dyn ReceiptSink + Send + Sync>>,
+    cdr_sink: Option<Arc<dyn CdrSink + Send + Sync>>,
+}
+
+impl GatewayBuilder {
+    pub fn new(config: SmscConfig) -> Self {
+        Self {
+            config,
+            accounts: HashMap::new(),
+            upstreams: Vec::new(),
+            routes: Vec::new(),
+            archive: None,
+            message_id_map: None,
+            receipt_sink: None,
+            cdr_sink: None,
+        }
+    }
+
+    /// Let ESMEs bind with this system_id and password.
+    pub fn account(mut self, system_id: &str, password: &str) -> Self {
+        self.accounts
+            .insert(String::from(system_id), String::from(password));
+        self
+    }
+
+    pub fn upstream(mut self, upstream: Upstream) -> Self {
+        self.upstreams.push(upstream);
+        self
+    }
+
+    /// route_with_cost() with a cost of 0
+    pub fn route(self, prefix: &str, name: &str) -> Self {
+        self.route_with_cost(prefix, name, 0)
+    }
+
+    /// Let messages for destination_addrs starting with prefix go to the
+    /// upstream called name, at cost.  "" matches every destination.
+    ///
+    /// Each upstream's cost for a message is that of its longest matching
+    /// prefix.  Upstreams are tried cheapest first, with unhealthy ones
+    /// (see Bridge::is_healthy()) last, until one accepts the message.
+    /// Ties go to the upstream with the longer prefix, then to the route
+    /// added first.
+    pub fn route_with_cost(
+        mut self,
+        prefix: &str,
+        name: &str,
+        cost: u32,
+    ) -> Self {
+        self.routes
+            .push((String::from(prefix), String::from(name), cost));
+        self
+    }
+
+    /// As for Smsc::set_archive()
+    pub fn archive(
+        mut self,
+        archive: Arc<dyn SubmitSmArchive + Send + Sync>,
+    ) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    /// As for Smsc::set_message_id_map()
+    pub fn message_id_map(
+        mut self,
+        map: Arc<dyn MessageIdMap + Send + Sync>,
+    ) -> Self {
+        self.message_id_map = Some(map);
+        self
+    }
+
+    pub fn receipt_sink(
+        mut self,
+        sink: Arc<dyn ReceiptSink + Send + Sync>,
+    ) -> Self {
+        self.receipt_sink = Some(sink);
+        self
+    }
+
+    pub fn cdr_sink(mut self, sink: Arc<dyn CdrSink + Send + Sync>) -> Self {
+        self.cdr_sink = Some(sink);
+        self
+    }
+
+    /// Connect and bind to every upstream, then start listening for ESMEs.
+    pub async fn start(self) -> AsyncResult<Gateway> {
+        if let Some((_, name, _)) = self.routes.iter().find(|(_, name, _)| {
+            !self.upstreams.iter().any(|u| &u.name == name)
+        }) {
+            return Err(UnknownUpstream(name.clone()).into());
+        }
+
+        let mut bridges = HashMap::new();
+        for upstream in &self.upstreams {
+            let bridge = Bridge::connect(upstream).await?;
+            bridges.insert(upstream.name.clone(), Arc::new(bridge));
+        }
+
+        let mut routes: Vec<Route> = self
+            .routes
+            .into_iter()
+            .map(|(prefix, name, cost)| Route {
+                prefix,
+                bridge: Arc::clone(&bridges[&name]),
+                cost,
+            })
+            .collect();
+        routes.sort_by_key(|route| Reverse(route.prefix.len()));
+
+        let logic = GatewayLogic {
+            accounts: self.accounts,
+            routes,
+            cdr_sink: self.cdr_sink,
+        };
+        let smsc = Smsc::start(self.config, logic).await?;
+        {
+            let mut smsc = smsc.lock().await;
+            if let Some(archive) = self.archive {
+                smsc.set_archive(archive);
+            }
+            if let Some(map) = self.message_id_map {
+                smsc.set_message_id_map(map);
+            }
+        }
+
+        let receipt_sink = self.receipt_sink;
+        let relays = bridges
+            .values()
+            .flat_map(|bridge| {
+                bridge.relay_receipts(&smsc, receipt_sink.clone())
+            })
+            .collect();
+
+        Ok(Gateway { smsc, relays })
+    }
+
+    /// start(), then serve until the process is killed.
+    pub fn run(self) -> AsyncResult<()> {
+        let rt = tokio::runtime::Runtime::new()?;
+        rt.block_on(async move {
+            let _gateway = self.start().await?;
+            futures::future::pending().await
+        })
+    }
+}
+
+/// A running gateway.  Dropping it stops passing on receipts.
+pub struct Gateway {
+    smsc: Arc<Mutex<Smsc>>,
+    relays: Vec<JoinHandle<()>>,
+}
+
+impl Gateway {
+    /// The SMSC ESMEs bind to, e.g. to see who is connected.
+    pub fn smsc(&self) -> Arc<Mutex<Smsc>> {
+        Arc::clone(&self.smsc)
+    }
+}
+
+impl Drop for Gateway {
+    fn drop(&mut self) {
+        for relay in &self.relays {
+            relay.abort();
+        }
+    }
+}
+
+struct Route {
+    prefix: String,
+    bridge: Arc<Bridge>,
+    cost: u32,
+}
+
+/// Submits are passed on one at a time, since the Smsc calls SmscLogic
+/// under a lock.
+struct GatewayLogic {
+    accounts: HashMap<String, String>,
+    /// Longest prefix first
+    routes: Vec<Route>,
+    cdr_sink: Option<Arc<dyn CdrSink + Send + Sync>>,
+}
+
+impl GatewayLogic {
+    /// Each upstream's most specific route to destination_addr, in the
+    /// order to try them.
+    fn candidates(&self, destination_addr: &str) -> Vec<&Route> {
+        let mut candidates: Vec<&Route> = Vec::new();
+        for route in &self.routes {
+            if destination_addr.starts_with(&route.prefix)
+                && !candidates
+                    .iter()
+                    .any(|c| Arc::ptr_eq(&c.bridge, &route.bridge))
+            {
+                candidates.push(route);
+            }
+        }
+        candidates
+            .sort_by_key(|route| (!route.bridge.is_healthy(), route.cost));
+        candidates
+    }
+}
+
+#[async_trait]
+impl SmscLogic for GatewayLogic {
+    async fn bind(&mut self, bind_data: &BindData) -> Result<(), BindError> {
+        match self.accounts.get(bind_data.system_id()) {
+            Some(password) if password == bind_data.password() => Ok(()),
+            _ => Err(BindError::IncorrectPassword),
+        }
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        let destination_addr = SmAccessors::destination_addr(pdu);
+        let mut last_error = SubmitSmError::InvalidDestination;
+        for (i, route) in self.candidates(destination_addr).iter().enumerate() {
+            match route.bridge.submit_sm(pdu).await {
+                Ok((resp, key)) => {
+                    if let Some(sink) = &self.cdr_sink {
+                        sink.record(&Cdr {
+                            message_id: key.message_id.clone(),
+                            destination_addr: String::from(destination_addr),
+                            upstream: String::from(route.bridge.name()),
+                            cost: route.cost,
+                            attempts: i + 1,
+                        });
+                    }
+                    return Ok((resp, key));
+                }
+                Err(e) => {
+                    info!(
+                        "Upstream {} did not take message for {}",
+                        route.bridge.name(),
+                        destination_addr
+                    );
+                    last_error = e;
+                }
+            }
+        }
+        Err(last_error)
+    }
+}
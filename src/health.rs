@@ -0,0 +1,190 @@
+//! Health reports, for wiring into liveness and readiness probes.
+
+use smpp_pdu::pdu::PduStatus;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::client::ClientState;
+use crate::clock::{Clock, TokioClock};
+use crate::session_stats::{SessionStats, RECENT};
+use crate::smpp_connection::EsmeId;
+
+/// An SMSC is unhealthy when more than this fraction of its recent
+/// responses were ESME_RSYSERR, i.e. failures on our side rather than the
+/// ESME's.  A client is unhealthy when more than this fraction of its
+/// recent requests got ESME_RSYSERR or no response.
+pub const MAX_HEALTHY_SYSTEM_ERROR_RATE: f64 = 0.5;
+
+/// The health of one bound session, over the last minute where it says
+/// "recent".
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionHealth {
+    pub system_id: String,
+    pub system_type: String,
+    /// How long the ESME took to answer the last enquire_link we sent
+    pub last_enquire_link_rtt: Option<Duration>,
+    /// Requests received but not yet responded to
+    pub pending_responses: usize,
+    pub recent_responses: usize,
+    /// Recent responses with a non-zero command_status
+    pub recent_errors: usize,
+    /// Recent responses with ESME_RSYSERR
+    pub recent_system_errors: usize,
+}
+
+impl SessionHealth {
+    pub fn new(esme_id: &EsmeId, stats: &SessionStats) -> Self {
+        let recent = stats.recent_responses();
+        Self {
+            system_id: esme_id.system_id.to_string(),
+            system_type: esme_id.system_type.to_string(),
+            last_enquire_link_rtt: stats.last_enquire_link_rtt,
+            pending_responses: stats.pending_responses(),
+            recent_responses: recent.len(),
+            recent_errors: recent.iter().filter(|s| **s != 0).count(),
+            recent_system_errors: recent
+                .iter()
+                .filter(|s| **s == PduStatus::ESME_RSYSERR as u32)
+                .count(),
+        }
+    }
+
+    /// The fraction of recent responses that were errors, or 0 if there
+    /// were none.
+    pub fn recent_error_rate(&self) -> f64 {
+        rate(self.recent_errors, self.recent_responses)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SmscHealth {
+    /// Every bound session, ordered by system_id
+    pub sessions: Vec<SessionHealth>,
+}
+
+impl SmscHealth {
+    pub fn bound_sessions(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn pending_responses(&self) -> usize {
+        self.sessions.iter().map(|s| s.pending_responses).sum()
+    }
+
+    pub fn recent_error_rate(&self) -> f64 {
+        rate(
+            self.sessions.iter().map(|s| s.recent_errors).sum(),
+            self.sessions.iter().map(|s| s.recent_responses).sum(),
+        )
+    }
+
+    /// The fraction of recent responses that were ESME_RSYSERR
+    pub fn recent_system_error_rate(&self) -> f64 {
+        rate(
+            self.sessions.iter().map(|s| s.recent_system_errors).sum(),
+            self.sessions.iter().map(|s| s.recent_responses).sum(),
+        )
+    }
+
+    /// Errors the ESMEs cause (e.g. bad PDUs or throttling) do not count
+    /// against us, so only ESME_RSYSERR can make us unhealthy.
+    pub fn is_healthy(&self) -> bool {
+        self.recent_system_error_rate() <= MAX_HEALTHY_SYSTEM_ERROR_RATE
+    }
+}
+
+/// The health of a Client, over the last minute where it says "recent".
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClientHealth {
+    pub state: ClientState,
+    /// How long the SMSC took to answer our last enquire_link
+    pub last_enquire_link_rtt: Option<Duration>,
+    /// Requests sent but not yet answered
+    pub pending_requests: usize,
+    /// deliver_sm and alert_notification PDUs received, but not yet taken
+    /// with next_message() and the like
+    pub queued_deliveries: usize,
+    pub recent_requests: usize,
+    /// Recent requests answered with a non-zero command_status
+    pub recent_errors: usize,
+    /// Recent requests answered with ESME_RSYSERR
+    pub recent_system_errors: usize,
+    /// Recent requests that got no response in time
+    pub recent_timeouts: usize,
+    /// Responses to requests that had timed out or been given up, this
+    /// session.  See Client::set_stray_response_policy().
+    pub late_responses: usize,
+    /// Second responses to the same request, this session
+    pub duplicate_responses: usize,
+}
+
+impl ClientHealth {
+    /// The fraction of recent requests that were answered with an error or
+    /// not at all, or 0 if there were none.
+    pub fn recent_error_rate(&self) -> f64 {
+        rate(
+            self.recent_errors + self.recent_timeouts,
+            self.recent_requests,
+        )
+    }
+
+    /// Bound, and the SMSC is answering.  Errors our requests cause (e.g.
+    /// bad addresses or throttling) do not count against it.
+    pub fn is_healthy(&self) -> bool {
+        self.state == ClientState::Bound
+            && rate(
+                self.recent_system_errors + self.recent_timeouts,
+                self.recent_requests,
+            ) <= MAX_HEALTHY_SYSTEM_ERROR_RATE
+    }
+}
+
+/// What happened to each request a client sent in the last RECENT, for
+/// ClientHealth.
+#[derive(Debug, Default)]
+pub(crate) struct RequestOutcomes {
+    /// When each request finished, and its command_status, or None if it
+    /// timed out
+    recent: VecDeque<(Instant, Option<u32>)>,
+}
+
+impl RequestOutcomes {
+    pub(crate) fn record(&mut self, command_status: Option<u32>) {
+        let now = TokioClock.now();
+        self.recent.push_back((now, command_status));
+        while self.recent.front().is_some_and(|(finished, _)| {
+            now.saturating_duration_since(*finished) > RECENT
+        }) {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Fill in the recent_ fields of health.
+    pub(crate) fn report(&self, health: &mut ClientHealth) {
+        let now = TokioClock.now();
+        let recent: Vec<Option<u32>> = self
+            .recent
+            .iter()
+            .filter(|(finished, _)| {
+                now.saturating_duration_since(*finished) <= RECENT
+            })
+            .map(|(_, status)| *status)
+            .collect();
+        health.recent_requests = recent.len();
+        health.recent_errors =
+            recent.iter().filter(|s| s.is_some_and(|s| s != 0)).count();
+        health.recent_system_errors = recent
+            .iter()
+            .filter(|s| **s == Some(PduStatus::ESME_RSYSERR as u32))
+            .count();
+        health.recent_timeouts = recent.iter().filter(|s| s.is_none()).count();
+    }
+}
+
+fn rate(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
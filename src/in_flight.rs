@@ -0,0 +1,228 @@
+//! Matching responses to the requests we originated.
+//!
+//! Each request gets a sequence number from SequenceNumbers, and is
+//! registered with InFlight before it is written.  Whoever reads the
+//! connection hands each response to InFlight::respond(), which wakes the
+//! request with the same sequence number.  InFlight can also limit how
+//! many requests are outstanding at once (the window size), making further
+//! requests wait for a response.
+
+use std::collections::{HashMap, VecDeque};
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{oneshot, Semaphore, SemaphorePermit};
+use tokio::time::timeout;
+
+/// The highest sequence number SMPP allows.
+pub const MAX_SEQUENCE_NUMBER: u32 = 0x7FFFFFFF;
+
+/// How many answered requests InFlight remembers, to tell a duplicate
+/// response from a late one.
+const REMEMBERED_ANSWERS: usize = 1024;
+
+/// Sequence numbers for requests we originate.  Counts up from 1, wrapping
+/// back to 1 after MAX_SEQUENCE_NUMBER.
+#[derive(Debug, Default)]
+pub struct SequenceNumbers {
+    last: AtomicU32,
+}
+
+impl SequenceNumbers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn next(&self) -> u32 {
+        let next = |n: u32| if n >= MAX_SEQUENCE_NUMBER { 1 } else { n + 1 };
+        let previous = self
+            .last
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                Some(next(n))
+            })
+            .unwrap();
+        next(previous)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum InFlightError {
+    /// No response arrived within the response timeout
+    Timeout,
+    /// clear() was called, e.g. because the connection closed
+    Closed,
+}
+
+impl Display for InFlightError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Self::Timeout => formatter.write_str("No response in time"),
+            Self::Closed => {
+                formatter.write_str("Connection closed before a response")
+            }
+        }
+    }
+}
+
+impl error::Error for InFlightError {}
+
+/// Why no request was awaiting a response.  See InFlight::stray().
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Stray {
+    /// Its request had been answered already
+    Duplicate,
+    /// Its request had timed out or been given up, or was never sent
+    Late,
+}
+
+/// Requests awaiting a response of type T, by sequence number.
+pub struct InFlight<T> {
+    pending: Mutex<HashMap<u32, oneshot::Sender<T>>>,
+    /// The sequence numbers of the last REMEMBERED_ANSWERS requests
+    /// answered, oldest first
+    answered: Mutex<VecDeque<u32>>,
+    response_timeout: Mutex<Duration>,
+    window: Option<Semaphore>,
+}
+
+impl<T> InFlight<T> {
+    /// Any number of requests may be outstanding.
+    pub fn new(response_timeout: Duration) -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            answered: Mutex::new(VecDeque::new()),
+            response_timeout: Mutex::new(response_timeout),
+            window: None,
+        }
+    }
+
+    /// At most max_outstanding requests may be outstanding.
+    pub fn with_window(
+        response_timeout: Duration,
+        max_outstanding: usize,
+    ) -> Self {
+        Self {
+            window: Some(Semaphore::new(max_outstanding)),
+            ..Self::new(response_timeout)
+        }
+    }
+
+    pub fn response_timeout(&self) -> Duration {
+        *self.response_timeout.lock().unwrap()
+    }
+
+    /// Applies to requests that start waiting from now on.
+    pub fn set_response_timeout(&self, response_timeout: Duration) {
+        *self.response_timeout.lock().unwrap() = response_timeout;
+    }
+
+    /// Register a request with sequence_number, first waiting for room in
+    /// the window if there is one.  Call this before writing the request,
+    /// so that its response cannot arrive first.
+    pub async fn start(&self, sequence_number: u32) -> Pending<'_, T> {
+        let permit = match &self.window {
+            Some(window) => Some(
+                window
+                    .acquire()
+                    .await
+                    .expect("The window semaphore is never closed"),
+            ),
+            None => None,
+        };
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(sequence_number, tx);
+        Pending {
+            in_flight: self,
+            sequence_number,
+            rx,
+            _permit: permit,
+        }
+    }
+
+    /// Hand response to the request with sequence_number.  False if there
+    /// is none, e.g. because it timed out.
+    pub fn respond(&self, sequence_number: u32, response: T) -> bool {
+        match self.pending.lock().unwrap().remove(&sequence_number) {
+            // The requester may have given up since, which is fine
+            Some(tx) => {
+                let _ = tx.send(response);
+                let mut answered = self.answered.lock().unwrap();
+                if answered.len() == REMEMBERED_ANSWERS {
+                    answered.pop_front();
+                }
+                answered.push_back(sequence_number);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Why respond() found no request with sequence_number, as far as we
+    /// remember.
+    pub fn stray(&self, sequence_number: u32) -> Stray {
+        if self.answered.lock().unwrap().contains(&sequence_number) {
+            Stray::Duplicate
+        } else {
+            Stray::Late
+        }
+    }
+
+    /// Fail every outstanding request with InFlightError::Closed.
+    pub fn clear(&self) {
+        self.pending.lock().unwrap().clear();
+    }
+
+    /// How many requests are outstanding.
+    pub fn len(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The sequence numbers of the outstanding requests, lowest first.
+    pub fn sequence_numbers(&self) -> Vec<u32> {
+        let mut sequence_numbers: Vec<u32> =
+            self.pending.lock().unwrap().keys().copied().collect();
+        sequence_numbers.sort_unstable();
+        sequence_numbers
+    }
+}
+
+/// A request registered with InFlight.  Dropping it forgets the request,
+/// and frees its place in the window.
+pub struct Pending<'a, T> {
+    in_flight: &'a InFlight<T>,
+    sequence_number: u32,
+    rx: oneshot::Receiver<T>,
+    _permit: Option<SemaphorePermit<'a>>,
+}
+
+impl<'a, T> Pending<'a, T> {
+    pub fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+
+    /// Wait up to the response timeout for the response.
+    pub async fn response(mut self) -> Result<T, InFlightError> {
+        let response_timeout = *self.in_flight.response_timeout.lock().unwrap();
+        match timeout(response_timeout, &mut self.rx).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Err(InFlightError::Closed),
+            Err(_) => Err(InFlightError::Timeout),
+        }
+    }
+}
+
+impl<'a, T> Drop for Pending<'a, T> {
+    fn drop(&mut self) {
+        self.in_flight
+            .pending
+            .lock()
+            .unwrap()
+            .remove(&self.sequence_number);
+    }
+}
@@ -0,0 +1,21 @@
+//! Just enough JSON writing for the admin endpoint and conformance reports,
+//! without a serialization dependency.
+
+use std::fmt::Write;
+
+/// s as a quoted JSON string
+pub(crate) fn json_string(s: &str) -> String {
+    let mut ret = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(ret, "\\u{:04x}", c as u32);
+            }
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
@@ -0,0 +1,221 @@
+//! Acting on messages from handsets (MO) by their first word, e.g. STOP,
+//! HELP or a campaign's own JOIN.  Keywords match case-insensitively,
+//! ignoring punctuation after them, so "Stop." is STOP.
+//!
+//! ```no_run
+//! # use async_trait::async_trait;
+//! # use smpp::client::Client;
+//! # use smpp::keywords::{
+//! #     KeywordHandler, KeywordRouter, MemoryStopList, MoMessage,
+//! # };
+//! # use std::sync::Arc;
+//! # struct SendHelpText;
+//! # #[async_trait]
+//! # impl KeywordHandler for SendHelpText {
+//! #     async fn handle(&self, _message: &MoMessage) {}
+//! # }
+//! # async fn example(client: Client) {
+//! let router = KeywordRouter::new()
+//!     .with_stop_list(Arc::new(MemoryStopList::new()))
+//!     .on("HELP", Arc::new(SendHelpText));
+//! let map = client.connection().data_coding_map();
+//! while let Some(message) = client.next_message().await {
+//!     router.route(&message, &map).await;
+//! }
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use smpp_pdu::pdu::DeliverSmPdu;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use crate::msisdn::normalize_msisdn;
+use crate::pdu_accessors::SmAccessors;
+use crate::text::{DataCodingMap, DecodeTextError, MessageText};
+
+/// Keywords that add the sender to the stop list, as carriers expect
+pub const STOP_KEYWORDS: &[&str] =
+    &["STOP", "STOPALL", "UNSUBSCRIBE", "CANCEL", "END", "QUIT"];
+
+/// Keywords that take the sender off the stop list again
+pub const START_KEYWORDS: &[&str] = &["START", "UNSTOP"];
+
+/// A message from a handset, split into its keyword and the rest
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoMessage {
+    pub source_addr: String,
+    pub destination_addr: String,
+    /// Upper case, as registered
+    pub keyword: String,
+    /// Whatever followed the keyword, trimmed
+    pub rest: String,
+    /// The whole message, as sent
+    pub text: String,
+}
+
+/// Called for each message whose first word is the keyword it was
+/// registered for with KeywordRouter::on().
+#[async_trait]
+pub trait KeywordHandler {
+    async fn handle(&self, message: &MoMessage);
+}
+
+/// Numbers that have asked us to stop sending to them.  Check it before
+/// sending marketing traffic.  KeywordRouter adds and removes numbers in
+/// international form, as normalize_msisdn() writes them.
+#[async_trait]
+pub trait StopList {
+    async fn add(&self, msisdn: &str);
+    async fn remove(&self, msisdn: &str);
+    async fn contains(&self, msisdn: &str) -> bool;
+}
+
+/// A StopList that lasts as long as the process.  Numbers are kept in
+/// international form, so "+44 7700 900123" and "447700900123" are the
+/// same.
+#[derive(Default)]
+pub struct MemoryStopList {
+    msisdns: Mutex<HashSet<String>>,
+}
+
+impl MemoryStopList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl StopList for MemoryStopList {
+    async fn add(&self, msisdn: &str) {
+        let msisdn = normalize_msisdn(msisdn, None);
+        self.msisdns.lock().unwrap().insert(msisdn);
+    }
+
+    async fn remove(&self, msisdn: &str) {
+        let msisdn = normalize_msisdn(msisdn, None);
+        self.msisdns.lock().unwrap().remove(&msisdn);
+    }
+
+    async fn contains(&self, msisdn: &str) -> bool {
+        let msisdn = normalize_msisdn(msisdn, None);
+        self.msisdns.lock().unwrap().contains(&msisdn)
+    }
+}
+
+/// What KeywordRouter::route() did with a message
+#[derive(Debug, PartialEq)]
+pub enum Routed {
+    /// It started with this keyword
+    Keyword(String),
+    /// It matched no keyword, and went to the fallback handler if any
+    Unmatched,
+    /// It was not text, so could not be routed
+    NotText(DecodeTextError),
+}
+
+type Handler = Arc<dyn KeywordHandler + Send + Sync>;
+
+#[derive(Default)]
+pub struct KeywordRouter {
+    handlers: HashMap<String, Handler>,
+    fallback: Option<Handler>,
+    stop_list: Option<Arc<dyn StopList + Send + Sync>>,
+}
+
+impl KeywordRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call handler for messages starting with keyword.  For a STOP or
+    /// START keyword, it is called after the stop list is updated, e.g.
+    /// to confirm to the sender.
+    pub fn on(mut self, keyword: &str, handler: Handler) -> Self {
+        self.handlers.insert(keyword.to_uppercase(), handler);
+        self
+    }
+
+    /// Call handler for messages that match no keyword.
+    pub fn otherwise(mut self, handler: Handler) -> Self {
+        self.fallback = Some(handler);
+        self
+    }
+
+    /// Add the senders of STOP_KEYWORDS to stop_list, and remove the
+    /// senders of START_KEYWORDS.
+    pub fn with_stop_list(
+        mut self,
+        stop_list: Arc<dyn StopList + Send + Sync>,
+    ) -> Self {
+        self.stop_list = Some(stop_list);
+        self
+    }
+
+    /// Decode deliver_sm's text using map, then route_text() it.
+    pub async fn route(
+        &self,
+        deliver_sm: &DeliverSmPdu,
+        map: &DataCodingMap,
+    ) -> Routed {
+        match deliver_sm.decode_text(map) {
+            Ok(text) => {
+                self.route_text(
+                    SmAccessors::source_addr(deliver_sm),
+                    SmAccessors::destination_addr(deliver_sm),
+                    &text,
+                )
+                .await
+            }
+            Err(e) => Routed::NotText(e),
+        }
+    }
+
+    /// Update the stop list if text is a STOP or START keyword, then call
+    /// the handler for its keyword, if any.
+    pub async fn route_text(
+        &self,
+        source_addr: &str,
+        destination_addr: &str,
+        text: &str,
+    ) -> Routed {
+        let trimmed = text.trim_start();
+        let (first_word, rest) = trimmed
+            .split_once(char::is_whitespace)
+            .unwrap_or((trimmed, ""));
+        let keyword = first_word
+            .trim_end_matches(|c: char| c.is_ascii_punctuation())
+            .to_uppercase();
+
+        let is_stop = STOP_KEYWORDS.contains(&keyword.as_str());
+        let is_start = START_KEYWORDS.contains(&keyword.as_str());
+        let stop_list = self.stop_list.as_ref().filter(|_| is_stop || is_start);
+        if let Some(stop_list) = stop_list {
+            let msisdn = normalize_msisdn(source_addr, None);
+            if is_stop {
+                stop_list.add(&msisdn).await;
+            } else {
+                stop_list.remove(&msisdn).await;
+            }
+        }
+
+        let message = MoMessage {
+            source_addr: String::from(source_addr),
+            destination_addr: String::from(destination_addr),
+            keyword: keyword.clone(),
+            rest: String::from(rest.trim()),
+            text: String::from(text),
+        };
+        match self.handlers.get(&keyword) {
+            Some(handler) => handler.handle(&message).await,
+            None if stop_list.is_some() => {}
+            None => {
+                if let Some(fallback) = &self.fallback {
+                    fallback.handle(&message).await;
+                }
+                return Routed::Unmatched;
+            }
+        }
+        Routed::Keyword(keyword)
+    }
+}
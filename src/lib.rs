@@ -1,6 +1,75 @@
+pub mod alert_notification;
 pub mod async_result;
+mod awaiting_receipt;
+pub mod bridge;
+pub mod c_octet_string;
+pub mod cancel_sm;
+pub mod canned_messages;
+pub mod client;
+pub mod clock;
+#[cfg(feature = "codec")]
+pub mod codec;
+pub mod command_id;
+#[cfg(feature = "compression")]
+pub mod compression;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+pub mod data_sm;
+pub mod deliver_sm_resp;
+pub mod delivery_receipt;
+pub mod dlr_batch;
+pub mod dlr_errors;
+pub mod encoded_len;
 pub mod examples;
+mod frame_body;
+pub mod gateway;
+pub mod health;
+pub mod in_flight;
+#[cfg(any(feature = "admin-http", feature = "conformance"))]
+mod json;
+pub mod keywords;
+pub mod long_message;
+pub mod message_payload;
 pub mod message_unique_key;
+pub mod msisdn;
+pub mod outbind;
+pub mod parse_error;
+pub mod parse_options;
+#[cfg(feature = "pcap")]
+pub mod pcap;
+pub mod pdu_accessors;
+pub mod pdu_clone;
+pub mod pdu_diff;
+pub mod pdu_dump;
+pub mod pdu_read;
+#[cfg(feature = "serde")]
+pub mod pdu_serde;
+pub mod pdu_split;
+pub mod pdu_status;
+pub mod pdu_view;
+pub mod pdu_write;
+pub mod profile;
+pub mod protocol_id;
+pub mod query_sm;
+pub mod redact;
+pub mod replace_sm;
+pub mod scheduler;
+pub mod sender;
+pub mod sending_window;
+pub mod session_capture;
+pub mod session_info;
+pub mod session_memory;
+pub mod session_stats;
 pub mod smpp_connection;
 pub mod smsc;
+pub mod socket_activation;
+pub mod submit_multi;
+pub mod submit_sm_builder;
+pub mod submit_sm_defaults;
+pub mod text;
+pub mod typed_tlvs;
+pub mod unbind;
+pub mod unique_id;
 mod unittest_utils;
+#[cfg(feature = "websocket")]
+pub mod websocket;
@@ -0,0 +1,402 @@
+//! Messages too long for one short_message, sent as several submit_sms
+//! that the handset joins back together (3GPP TS 23.040 9.2.3.24.1), and
+//! joining received parts back together.
+//!
+//! Each part names the message it belongs to by a reference number, how
+//! many parts there are, and its own position, either in a User Data Header
+//! (UDH) at the start of short_message, with the UDHI bit of esm_class set,
+//! or in the sar_msg_ref_num, sar_total_segments and sar_segment_seqnum
+//! TLVs (SMPP 3.4 5.3.2.22 to 5.3.2.24).
+
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::{PduParseError, SubmitSmPdu};
+use std::collections::{BTreeMap, HashMap};
+use std::error;
+use std::fmt::{Display, Formatter};
+
+use crate::pdu_accessors::SmAccessors;
+use crate::pdu_clone::PduClone;
+use crate::submit_sm_builder::SubmitSmBuilder;
+use crate::text::DataCodingMap;
+use crate::typed_tlvs::TypedTlvs;
+
+/// The bit of esm_class saying short_message starts with a UDH
+pub const ESM_CLASS_UDHI: u8 = 0x40;
+
+/// What fits in one message on the air
+const MAX_OCTETS: usize = 140;
+const MAX_SEPTETS: usize = 160;
+
+const MAX_PARTS: usize = 255;
+
+// Information element identifiers for concatenated short messages
+const IEI_CONCATENATED_8_BIT: u8 = 0x00;
+const IEI_CONCATENATED_16_BIT: u8 = 0x08;
+
+/// How the parts of a long message say which message they belong to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Concatenation {
+    /// A UDH with an 8-bit reference number, taking 6 octets of each part
+    Udh8,
+    /// A UDH with a 16-bit reference number, taking 7 octets of each part
+    Udh16,
+    /// The sar TLVs, leaving all 140 octets for the message
+    Sar,
+}
+
+impl Concatenation {
+    fn udh_length(self) -> usize {
+        match self {
+            Self::Udh8 => 6,
+            Self::Udh16 => 7,
+            Self::Sar => 0,
+        }
+    }
+
+    fn udh(self, part: &Part) -> Vec<u8> {
+        let [reference_high, reference_low] = part.reference.to_be_bytes();
+        match self {
+            Self::Udh8 => vec![
+                5,
+                IEI_CONCATENATED_8_BIT,
+                3,
+                reference_low,
+                part.total,
+                part.seqnum,
+            ],
+            Self::Udh16 => vec![
+                6,
+                IEI_CONCATENATED_16_BIT,
+                4,
+                reference_high,
+                reference_low,
+                part.total,
+                part.seqnum,
+            ],
+            Self::Sar => Vec::new(),
+        }
+    }
+}
+
+/// Where a part belongs in a long message
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Part {
+    pub reference: u16,
+    /// How many parts the message has
+    pub total: u8,
+    /// This part's position, from 1
+    pub seqnum: u8,
+}
+
+impl Part {
+    /// Where sm belongs, and its message bytes after any UDH, or None if
+    /// sm is not a valid part of a long message.
+    pub fn of<Sm: SmAccessors + ?Sized>(sm: &Sm) -> Option<(Self, &[u8])> {
+        let short_message = sm.short_message();
+        let (part, bytes) = if sm.esm_class() & ESM_CLASS_UDHI != 0 {
+            Self::from_udh(short_message)?
+        } else {
+            (Self::from_tlvs(sm.tlvs())?, short_message)
+        };
+        if part.total == 0 || part.seqnum == 0 || part.seqnum > part.total {
+            return None;
+        }
+        Some((part, bytes))
+    }
+
+    fn from_udh(short_message: &[u8]) -> Option<(Self, &[u8])> {
+        let (&udh_length, rest) = short_message.split_first()?;
+        let udh_length = usize::from(udh_length);
+        let mut udh = rest.get(..udh_length)?;
+        let bytes = &rest[udh_length..];
+        while let [iei, length, rest @ ..] = udh {
+            let data = rest.get(..usize::from(*length))?;
+            match (*iei, data) {
+                (IEI_CONCATENATED_8_BIT, &[reference, total, seqnum]) => {
+                    return Some((
+                        Self {
+                            reference: u16::from(reference),
+                            total,
+                            seqnum,
+                        },
+                        bytes,
+                    ))
+                }
+                (
+                    IEI_CONCATENATED_16_BIT,
+                    &[reference_high, reference_low, total, seqnum],
+                ) => {
+                    return Some((
+                        Self {
+                            reference: u16::from_be_bytes([
+                                reference_high,
+                                reference_low,
+                            ]),
+                            total,
+                            seqnum,
+                        },
+                        bytes,
+                    ))
+                }
+                _ => udh = &rest[data.len()..],
+            }
+        }
+        None
+    }
+
+    fn from_tlvs(tlvs: &Tlvs) -> Option<Self> {
+        let reference = tlvs.get(KnownTlvTag::sar_msg_ref_num)?.value;
+        let total = tlvs.get(KnownTlvTag::sar_total_segments)?.value;
+        let seqnum = tlvs.get(KnownTlvTag::sar_segment_seqnum)?.value;
+        match (&reference[..], &total[..], &seqnum[..]) {
+            (&[reference_high, reference_low], &[total], &[seqnum]) => {
+                Some(Self {
+                    reference: u16::from_be_bytes([
+                        reference_high,
+                        reference_low,
+                    ]),
+                    total,
+                    seqnum,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn tlvs(&self) -> [Tlv; 3] {
+        [
+            Tlv::new(
+                KnownTlvTag::sar_msg_ref_num,
+                &self.reference.to_be_bytes(),
+            ),
+            Tlv::new(KnownTlvTag::sar_total_segments, &[self.total]),
+            Tlv::new(KnownTlvTag::sar_segment_seqnum, &[self.seqnum]),
+        ]
+    }
+}
+
+/// Why a message could not be split
+#[derive(Debug, PartialEq)]
+pub enum LongMessageError {
+    /// No alphabet the DataCodingMap prefers can write the text
+    Unencodable,
+    /// The message needs this many parts, but at most 255 are allowed
+    TooManyParts(usize),
+}
+
+impl Display for LongMessageError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Self::Unencodable => formatter.write_str(
+                "Text cannot be written in any preferred data_coding",
+            ),
+            Self::TooManyParts(parts) => write!(
+                formatter,
+                "Message needs {} parts, but at most {} are allowed",
+                parts, MAX_PARTS
+            ),
+        }
+    }
+}
+
+impl error::Error for LongMessageError {}
+
+/// A message split into parts that each fit in one short_message.  A
+/// message short enough for one is a single part, sent without a UDH or
+/// sar TLVs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LongMessage {
+    concatenation: Concatenation,
+    data_coding: u8,
+    parts: Vec<Vec<u8>>,
+}
+
+impl LongMessage {
+    /// text in the first alphabet map prefers that can write it, split
+    /// between characters, so that no part ends in half of a GSM escape
+    /// or UTF-16 surrogate pair.
+    pub fn from_text(
+        text: &str,
+        map: &DataCodingMap,
+        concatenation: Concatenation,
+    ) -> Result<Self, LongMessageError> {
+        let (data_coding, _) =
+            map.select(text).ok_or(LongMessageError::Unencodable)?;
+        let codec = map
+            .codec(data_coding)
+            .ok_or(LongMessageError::Unencodable)?;
+        let characters = text
+            .chars()
+            .map(|c| codec.encode(c.encode_utf8(&mut [0; 4])))
+            .collect::<Option<Vec<Vec<u8>>>>()
+            .ok_or(LongMessageError::Unencodable)?;
+        let (single, each) = if codec.septets() {
+            (
+                MAX_SEPTETS,
+                (MAX_OCTETS - concatenation.udh_length()) * 8 / 7,
+            )
+        } else {
+            (MAX_OCTETS, MAX_OCTETS - concatenation.udh_length())
+        };
+        Self::split(
+            concatenation,
+            data_coding,
+            characters.iter().map(Vec::as_slice),
+            single,
+            each,
+        )
+    }
+
+    /// bytes, e.g. binary data, split between any two octets.
+    pub fn from_bytes(
+        bytes: &[u8],
+        data_coding: u8,
+        concatenation: Concatenation,
+    ) -> Result<Self, LongMessageError> {
+        Self::split(
+            concatenation,
+            data_coding,
+            bytes.chunks(1),
+            MAX_OCTETS,
+            MAX_OCTETS - concatenation.udh_length(),
+        )
+    }
+
+    fn split<'a>(
+        concatenation: Concatenation,
+        data_coding: u8,
+        units: impl Iterator<Item = &'a [u8]> + Clone,
+        single: usize,
+        each: usize,
+    ) -> Result<Self, LongMessageError> {
+        let mut parts = vec![Vec::new()];
+        if units.clone().map(<[u8]>::len).sum::<usize>() <= single {
+            parts[0] = units.flatten().copied().collect();
+        } else {
+            for unit in units {
+                let part = parts.last_mut().expect("parts is never empty");
+                if part.len() + unit.len() > each {
+                    parts.push(Vec::from(unit));
+                } else {
+                    part.extend(unit);
+                }
+            }
+        }
+        if parts.len() > MAX_PARTS {
+            return Err(LongMessageError::TooManyParts(parts.len()));
+        }
+        Ok(Self {
+            concatenation,
+            data_coding,
+            parts,
+        })
+    }
+
+    pub fn data_coding(&self) -> u8 {
+        self.data_coding
+    }
+
+    /// The message bytes of each part, without any UDH
+    pub fn parts(&self) -> &[Vec<u8>] {
+        &self.parts
+    }
+
+    /// One submit_sm per part, with every other field from template.
+    /// reference should not be reused for the same destination until its
+    /// parts have been delivered; Udh8 sends only its low byte.
+    pub fn submit_sms(
+        &self,
+        template: &SubmitSmBuilder,
+        reference: u16,
+    ) -> Result<Vec<SubmitSmPdu>, PduParseError> {
+        let template = template.clone().short_message(b"").build()?;
+        let total = self.parts.len() as u8;
+        Ok(self
+            .parts
+            .iter()
+            .zip(1..)
+            .map(|(bytes, seqnum)| {
+                let mut pdu = template.pdu_clone();
+                let sm = &mut pdu.0;
+                sm.data_coding.value = self.data_coding;
+                let part = Part {
+                    reference,
+                    total,
+                    seqnum,
+                };
+                if total == 1 {
+                    sm.short_message.value = bytes.clone();
+                } else if self.concatenation == Concatenation::Sar {
+                    sm.short_message.value = bytes.clone();
+                    let mut tlvs = sm.tlvs.to_vec();
+                    tlvs.extend(part.tlvs());
+                    sm.tlvs = Tlvs::from(&tlvs);
+                } else {
+                    sm.esm_class.value |= ESM_CLASS_UDHI;
+                    let mut short_message = self.concatenation.udh(&part);
+                    short_message.extend(bytes);
+                    sm.short_message.value = short_message;
+                }
+                pdu
+            })
+            .collect())
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct MessageKey {
+    source_addr: String,
+    destination_addr: String,
+    reference: u16,
+    total: u8,
+}
+
+/// Collects the parts of long messages as they arrive, in any order.
+#[derive(Debug, Default)]
+pub struct Reassembler {
+    pending: HashMap<MessageKey, BTreeMap<u8, Vec<u8>>>,
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The whole message sm belongs to, once all its parts have arrived,
+    /// or sm's own short_message if it is not part of a long message.  A
+    /// part that has already arrived is ignored.
+    pub fn add<Sm: SmAccessors + ?Sized>(
+        &mut self,
+        sm: &Sm,
+    ) -> Option<Vec<u8>> {
+        let (part, bytes) = match Part::of(sm) {
+            Some(part) => part,
+            None => return Some(Vec::from(sm.short_message())),
+        };
+        let key = MessageKey {
+            source_addr: String::from(sm.source_addr()),
+            destination_addr: String::from(sm.destination_addr()),
+            reference: part.reference,
+            total: part.total,
+        };
+        let parts = self.pending.entry(key.clone()).or_default();
+        parts.entry(part.seqnum).or_insert_with(|| Vec::from(bytes));
+        if parts.len() < usize::from(part.total) {
+            return None;
+        }
+        self.pending
+            .remove(&key)
+            .map(|parts| parts.into_values().flatten().collect())
+    }
+
+    /// How many messages are still waiting for parts
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Forget the parts received so far, e.g. of messages whose remaining
+    /// parts are never going to arrive.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
@@ -0,0 +1,53 @@
+//! The message a submit_sm or deliver_sm carries.  Messages longer than
+//! 254 bytes go in a message_payload TLV, with sm_length 0, and SMPP 3.4
+//! (5.3.2.32) does not allow both to be used at once.
+
+use smpp_pdu::pdu::data::sm_data::SmData;
+use smpp_pdu::pdu::tlvs::KnownTlvTag;
+use smpp_pdu::pdu::{DeliverSmPdu, SubmitSmPdu};
+use std::error;
+use std::fmt::{Display, Formatter};
+
+/// A PDU had both a short_message and a message_payload TLV, which should
+/// be rejected with ESME_ROPTPARNOTALLWD.
+#[derive(Debug, PartialEq)]
+pub struct MessageInBothFields;
+
+impl Display for MessageInBothFields {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter
+            .write_str("PDU has both a short_message and a message_payload TLV")
+    }
+}
+
+impl error::Error for MessageInBothFields {}
+
+pub trait MessageBytes {
+    /// short_message, or the message_payload TLV's value if short_message
+    /// is empty.
+    fn message_bytes(&self) -> Result<Vec<u8>, MessageInBothFields>;
+}
+
+impl MessageBytes for SmData {
+    fn message_bytes(&self) -> Result<Vec<u8>, MessageInBothFields> {
+        match self.tlvs.get(KnownTlvTag::message_payload) {
+            None => Ok(self.short_message.value.clone()),
+            Some(_) if !self.short_message.value.is_empty() => {
+                Err(MessageInBothFields)
+            }
+            Some(payload) => Ok(payload.value),
+        }
+    }
+}
+
+impl MessageBytes for SubmitSmPdu {
+    fn message_bytes(&self) -> Result<Vec<u8>, MessageInBothFields> {
+        self.0.message_bytes()
+    }
+}
+
+impl MessageBytes for DeliverSmPdu {
+    fn message_bytes(&self) -> Result<Vec<u8>, MessageInBothFields> {
+        self.0.message_bytes()
+    }
+}
@@ -2,7 +2,7 @@ use smpp_pdu::pdu::DeliverSmPdu;
 
 /// A way to identify this message based on the message ID provided by
 /// some remove system.
-#[derive(Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct MessageUniqueKey {
     /// An identifier for the system which generated the message_id.  For
     /// systems that produce sufficiently unique IDs, this serves as a
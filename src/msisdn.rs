@@ -0,0 +1,67 @@
+//! Normalising phone numbers, so that the same number is always written the
+//! same way, whichever way an ESME or supplier wrote it.
+
+/// Short codes are this many digits long.
+pub const MIN_SHORT_CODE_LEN: usize = 3;
+pub const MAX_SHORT_CODE_LEN: usize = 6;
+
+/// Characters people use to make numbers readable, which we ignore.
+const SEPARATORS: &[char] = &[' ', '-', '.', '(', ')'];
+
+/// Is addr a short code, e.g. "88222"?  Short codes are only meaningful
+/// within one country, so they are never given a country code.
+pub fn is_short_code(addr: &str) -> bool {
+    let digits = without_separators(addr);
+    (MIN_SHORT_CODE_LEN..=MAX_SHORT_CODE_LEN).contains(&digits.len())
+        && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Is addr written with an international prefix, '+' or "00", so that we
+/// know which country it is in without being told?
+pub fn is_international(addr: &str) -> bool {
+    let number = without_separators(addr);
+    number.starts_with('+') || number.starts_with("00")
+}
+
+/// Write addr in E.164 form, as digits with no '+'.  "+44 7700 900123",
+/// "0044 7700900123" and (with default_country_code "44") "07700 900123"
+/// all become "447700900123".
+///
+/// Short codes and anything that is not a number (e.g. an alphanumeric
+/// sender) are returned unchanged.  So are national numbers like
+/// "07700900123" when there is no default_country_code, apart from losing
+/// their separators, because we cannot tell which country they are in.
+pub fn normalize_msisdn(
+    addr: &str,
+    default_country_code: Option<&str>,
+) -> String {
+    let number = without_separators(addr);
+    if is_short_code(&number) {
+        return number;
+    }
+    let digits = number.strip_prefix('+').unwrap_or(&number);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return String::from(addr);
+    }
+
+    if number.starts_with('+') {
+        String::from(digits)
+    } else if let Some(international) = digits.strip_prefix("00") {
+        String::from(international.trim_start_matches('0'))
+    } else if let Some(national) = digits.strip_prefix('0') {
+        match default_country_code {
+            Some(country_code) => format!(
+                "{}{}",
+                country_code.trim_start_matches('+'),
+                national.trim_start_matches('0')
+            ),
+            None => number,
+        }
+    } else {
+        String::from(digits)
+    }
+}
+
+fn without_separators(addr: &str) -> String {
+    addr.chars().filter(|c| !SEPARATORS.contains(c)).collect()
+}
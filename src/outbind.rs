@@ -0,0 +1,73 @@
+//! outbind, with which an SMSC that has connected to an ESME asks it to
+//! bind as a receiver, e.g. to deliver messages it has been holding.  It
+//! has no response: the ESME answers with bind_receiver.
+//!
+//! Like query_sm, it is missing from smpp_pdu, so it is a standalone type
+//! that reads and writes a whole frame, header included.
+
+use smpp_pdu::pdu::formats::WriteStream;
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody};
+use std::io::{self, Cursor};
+
+use crate::command_id::CommandId;
+use crate::frame_body::{
+    c_octet_string, command_id, pdu_fields, write_frame, Header, HEADER_LENGTH,
+};
+
+pub const OUTBIND: u32 = CommandId::Outbind as u32;
+
+const MAX_LENGTH_SYSTEM_ID: usize = 16;
+const MAX_LENGTH_PASSWORD: usize = 9;
+
+pdu_fields! {
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct OutbindPdu {
+        /// The system_id the ESME should bind with
+        pub system_id: COctetString(MAX_LENGTH_SYSTEM_ID),
+        /// Lets the ESME check that this is the SMSC it expects
+        pub password: COctetString(MAX_LENGTH_PASSWORD),
+    }
+}
+
+impl OutbindPdu {
+    pub fn new(
+        sequence_number: u32,
+        system_id: &str,
+        password: &str,
+    ) -> Result<Self, PduParseError> {
+        Ok(Self {
+            sequence_number,
+            system_id: c_octet_string(
+                "system_id",
+                system_id,
+                MAX_LENGTH_SYSTEM_ID,
+            )?,
+            password: c_octet_string(
+                "password",
+                password,
+                MAX_LENGTH_PASSWORD,
+            )?,
+        })
+    }
+
+    /// Does this complete frame (as accepted by Pdu::check) hold an
+    /// outbind?
+    pub fn is_outbind(frame: &[u8]) -> bool {
+        command_id(frame) == Some(OUTBIND)
+    }
+
+    pub fn parse(frame: &[u8]) -> Result<Self, PduParseError> {
+        let header = Header::parse(frame, OUTBIND)?;
+        if header.command_status != 0 {
+            return Err(header.error(PduParseError::new(
+                PduParseErrorBody::StatusIsNotZero,
+            )));
+        }
+        Self::read_body(&header, &mut Cursor::new(&frame[HEADER_LENGTH..]))
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        let body = self.write_body().await?;
+        write_frame(stream, OUTBIND, 0, self.sequence_number, &body).await
+    }
+}
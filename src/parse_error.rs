@@ -0,0 +1,93 @@
+//! Choosing how to respond to PDUs we could not parse.
+
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody, PduStatus};
+
+use crate::pdu_status::pdu_status;
+
+/// Whether an error means we must close the connection, or whether we can
+/// reject the PDU that caused it and carry on.
+#[derive(Debug, PartialEq)]
+pub enum ErrorSeverity {
+    SessionFatal,
+    RequestRecoverable,
+}
+
+pub trait Severity {
+    fn severity(&self) -> ErrorSeverity;
+}
+
+impl Severity for PduParseError {
+    fn severity(&self) -> ErrorSeverity {
+        // If we read the whole header, we know where this PDU ended and can
+        // respond to it specifically, unless its length was the problem.
+        // A body that ends early still ends where command_length says.
+        if self.sequence_number.is_some()
+            && pdu_status(self.status()) != Some(PduStatus::ESME_RINVCMDLEN)
+        {
+            ErrorSeverity::RequestRecoverable
+        } else {
+            ErrorSeverity::SessionFatal
+        }
+    }
+}
+
+pub trait RecommendedStatus {
+    /// The command_status we should send back to a peer who sent us a PDU
+    /// that failed to parse with this error.
+    fn recommended_status(&self) -> PduStatus;
+}
+
+impl RecommendedStatus for PduParseErrorBody {
+    fn recommended_status(&self) -> PduStatus {
+        match self {
+            // The length told us nothing useful about where the PDU ends
+            PduParseErrorBody::LengthLongerThanPdu(_)
+            | PduParseErrorBody::LengthTooLong(_)
+            | PduParseErrorBody::LengthTooShort(_)
+            | PduParseErrorBody::NotEnoughBytes => PduStatus::ESME_RINVCMDLEN,
+            // The PDU was the right length, but a field inside was invalid
+            PduParseErrorBody::IncorrectLength(_, _)
+            | PduParseErrorBody::OctetStringCreationError(_) => {
+                PduStatus::ESME_RINVMSGLEN
+            }
+            PduParseErrorBody::UnknownCommandId => PduStatus::ESME_RINVCMDID,
+            PduParseErrorBody::BodyNotAllowedWhenStatusIsNotZero
+            | PduParseErrorBody::BodyRequiredWhenStatusIsZero
+            | PduParseErrorBody::InvalidSequenceNumber
+            | PduParseErrorBody::OtherIoError(_)
+            | PduParseErrorBody::StatusIsNotZero
+            | PduParseErrorBody::StatusIsZero => PduStatus::ESME_RSYSERR,
+        }
+    }
+}
+
+impl RecommendedStatus for PduParseError {
+    fn recommended_status(&self) -> PduStatus {
+        // PduParseError does not give us access to its body, and the status
+        // it calculates for itself makes ESME_RSYSERR of bad C-Octet
+        // Strings and bodies that end early, so we tell those apart by the
+        // message it ends with.
+        let message = self.to_string();
+        if message.ends_with(NOT_ENOUGH_BYTES) {
+            PduStatus::ESME_RINVCMDLEN
+        } else if OCTET_STRING_ERRORS
+            .iter()
+            .any(|error| message.contains(error))
+        {
+            PduStatus::ESME_RINVMSGLEN
+        } else {
+            pdu_status(self.status()).unwrap_or(PduStatus::ESME_RSYSERR)
+        }
+    }
+}
+
+/// How PduParseError describes PduParseErrorBody::NotEnoughBytes
+const NOT_ENOUGH_BYTES: &str = "before finding all fields of the PDU.";
+
+/// How PduParseError describes each OctetStringCreationError
+const OCTET_STRING_ERRORS: [&str; 4] = [
+    "): C-Octet String does not end with the NULL character.",
+    "): Octet String is not ASCII",
+    "): Octet String is too long.",
+    "): IO error creating Octet String",
+];
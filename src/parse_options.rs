@@ -0,0 +1,284 @@
+//! Limits on the PDUs we read, chosen by the operator rather than fixed by
+//! smpp_pdu.  Tighten max_pdu_length to stop peers making us buffer large
+//! frames, or relax it for peers that send submit_sm or deliver_sm with a
+//! message_payload too large for smpp_pdu's MAX_PDU_LENGTH.
+//!
+//! Pdu::check() and Pdu::parse() always apply MAX_PDU_LENGTH, so check()
+//! and parse_frame() here stand in for them wherever we read frames.
+//!
+//! ParseMode::Lenient accepts frames from peers that break the spec in
+//! common ways, repairing them and reporting each repair as a
+//! ParseWarning instead of failing.
+
+use smpp_pdu::pdu::{
+    CheckOutcome, Pdu, PduParseError, PduParseErrorBody, MAX_PDU_LENGTH,
+    MIN_PDU_LENGTH,
+};
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::io::Cursor;
+
+use crate::command_id::CommandId;
+use crate::frame_body::{Header, HEADER_LENGTH};
+use crate::pdu_view::SmView;
+use crate::smpp_connection::Frame;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParseOptions {
+    /// The longest command_length we accept
+    pub max_pdu_length: usize,
+    pub mode: ParseMode,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            max_pdu_length: MAX_PDU_LENGTH,
+            mode: ParseMode::Strict,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParseMode {
+    /// Frames must follow the spec
+    Strict,
+    /// Frames that break it in the ways ParseWarning lists are repaired
+    /// and accepted
+    Lenient,
+}
+
+/// How a frame broke the spec, and was repaired, in ParseMode::Lenient
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseWarning {
+    /// field held bytes that are not ASCII, each now '?'
+    NonAscii { field: &'static str },
+    /// field ran to the end of the frame without its NULL terminator
+    MissingNull { field: &'static str },
+    /// A response with a non-zero command_status had a body, now ignored
+    BodyWithErrorStatus { command_status: u32 },
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Self::NonAscii { field } => {
+                write!(formatter, "{} is not ASCII", field)
+            }
+            Self::MissingNull { field } => {
+                write!(formatter, "{} has no NULL terminator", field)
+            }
+            Self::BodyWithErrorStatus { command_status } => write!(
+                formatter,
+                "Body given with command_status {:#010x}",
+                command_status
+            ),
+        }
+    }
+}
+
+/// A frame, and anything ParseMode::Lenient had to repair to read it
+#[derive(Debug)]
+pub struct Parsed {
+    pub frame: Frame,
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl ParseOptions {
+    /// As Pdu::check(), moving bytes to the end of the frame if it is
+    /// Ready, but rejecting a command_length over max_pdu_length as soon as
+    /// it arrives.
+    pub fn check(
+        &self,
+        bytes: &mut Cursor<&[u8]>,
+    ) -> Result<CheckOutcome, PduParseError> {
+        let start = bytes.position() as usize;
+        let rest = bytes.get_ref().get(start..).unwrap_or_default();
+        let command_length = match rest.get(..4) {
+            Some(b) => u32::from_be_bytes([b[0], b[1], b[2], b[3]]),
+            None => return Ok(CheckOutcome::Incomplete),
+        };
+        if command_length as usize > self.max_pdu_length {
+            Err(PduParseError::new(PduParseErrorBody::LengthTooLong(
+                command_length,
+            )))
+        } else if (command_length as usize) < MIN_PDU_LENGTH {
+            Err(PduParseError::new(PduParseErrorBody::LengthTooShort(
+                command_length,
+            )))
+        } else if rest.len() >= command_length as usize {
+            bytes.set_position((start + command_length as usize) as u64);
+            Ok(CheckOutcome::Ready)
+        } else {
+            Ok(CheckOutcome::Incomplete)
+        }
+    }
+
+    /// As Frame::parse(), for a frame that check() found Ready, dropping
+    /// any warnings.
+    pub fn parse_frame(&self, frame: &[u8]) -> Result<Frame, PduParseError> {
+        self.parse_frame_with_warnings(frame)
+            .map(|parsed| parsed.frame)
+    }
+
+    /// As parse_frame(), but in ParseMode::Lenient a frame that fails is
+    /// repaired and parsed again.  If that fails too, the error is the one
+    /// for the frame as it arrived.
+    pub fn parse_frame_with_warnings(
+        &self,
+        frame: &[u8],
+    ) -> Result<Parsed, PduParseError> {
+        let e = match self.parse_strict(frame) {
+            Ok(frame) => {
+                return Ok(Parsed {
+                    frame,
+                    warnings: Vec::new(),
+                })
+            }
+            Err(e) if self.mode == ParseMode::Lenient => e,
+            Err(e) => return Err(e),
+        };
+        let (repaired, warnings) = repair(frame);
+        if warnings.is_empty() {
+            return Err(e);
+        }
+        match self.parse_strict(&repaired) {
+            Ok(frame) => Ok(Parsed { frame, warnings }),
+            Err(_) => Err(e),
+        }
+    }
+
+    /// submit_sm and deliver_sm longer than MAX_PDU_LENGTH are read with
+    /// SmView, since Pdu::parse() would refuse them.
+    fn parse_strict(&self, frame: &[u8]) -> Result<Frame, PduParseError> {
+        let header = match Header::peek(frame) {
+            Some(header) if frame.len() > MAX_PDU_LENGTH => header,
+            _ => return Frame::parse(frame),
+        };
+        if header.command_id == CommandId::SubmitSm as u32 {
+            let view = SmView::parse(frame)?;
+            Pdu::new(0, view.sequence_number, view.to_submit_sm()?.into())
+                .map(Frame::Pdu)
+        } else if header.command_id == CommandId::DeliverSm as u32 {
+            let view = SmView::parse(frame)?;
+            Pdu::new(0, view.sequence_number, view.to_deliver_sm()?.into())
+                .map(Frame::Pdu)
+        } else {
+            Frame::parse(frame)
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    COctetString(&'static str),
+    U8,
+}
+
+use Field::{COctetString as S, U8};
+
+/// The fields at the start of each body, up to the last C-Octet String
+fn leading_fields(command_id: CommandId) -> &'static [Field] {
+    match command_id {
+        CommandId::BindReceiver
+        | CommandId::BindTransmitter
+        | CommandId::BindTransceiver => &[
+            S("system_id"),
+            S("password"),
+            S("system_type"),
+            U8,
+            U8,
+            U8,
+            S("address_range"),
+        ],
+        CommandId::BindReceiverResp
+        | CommandId::BindTransmitterResp
+        | CommandId::BindTransceiverResp => &[S("system_id")],
+        CommandId::SubmitSm | CommandId::DeliverSm => &[
+            S("service_type"),
+            U8,
+            U8,
+            S("source_addr"),
+            U8,
+            U8,
+            S("destination_addr"),
+            U8,
+            U8,
+            U8,
+            S("schedule_delivery_time"),
+            S("validity_period"),
+        ],
+        CommandId::DataSm => &[
+            S("service_type"),
+            U8,
+            U8,
+            S("source_addr"),
+            U8,
+            U8,
+            S("destination_addr"),
+        ],
+        CommandId::SubmitSmResp
+        | CommandId::DeliverSmResp
+        | CommandId::DataSmResp => &[S("message_id")],
+        _ => &[],
+    }
+}
+
+/// frame with every violation we know how to mend mended, and a warning
+/// for each.
+fn repair(frame: &[u8]) -> (Vec<u8>, Vec<ParseWarning>) {
+    let mut frame = frame.to_vec();
+    let mut warnings = Vec::new();
+    let header = match Header::peek(&frame) {
+        Some(header) => header,
+        None => return (frame, warnings),
+    };
+
+    let is_response = header.command_id & 0x80000000 != 0;
+    if is_response && header.command_status != 0 && frame.len() > HEADER_LENGTH
+    {
+        frame.truncate(HEADER_LENGTH);
+        warnings.push(ParseWarning::BodyWithErrorStatus {
+            command_status: header.command_status,
+        });
+    }
+
+    let fields = CommandId::try_from(header.command_id)
+        .map(leading_fields)
+        .unwrap_or_default();
+    let mut pos = HEADER_LENGTH;
+    for field in fields {
+        if pos >= frame.len() {
+            break;
+        }
+        let name = match field {
+            Field::U8 => {
+                pos += 1;
+                continue;
+            }
+            Field::COctetString(name) => *name,
+        };
+        let end = frame[pos..].iter().position(|b| *b == 0).map(|i| pos + i);
+        let end = match end {
+            Some(end) => end,
+            None => {
+                frame.push(0);
+                warnings.push(ParseWarning::MissingNull { field: name });
+                frame.len() - 1
+            }
+        };
+        if frame[pos..end].iter().any(|b| !b.is_ascii()) {
+            for b in &mut frame[pos..end] {
+                if !b.is_ascii() {
+                    *b = b'?';
+                }
+            }
+            warnings.push(ParseWarning::NonAscii { field: name });
+        }
+        pos = end + 1;
+    }
+
+    let command_length = frame.len() as u32;
+    frame[..4].copy_from_slice(&command_length.to_be_bytes());
+    (frame, warnings)
+}
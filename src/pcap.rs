@@ -0,0 +1,229 @@
+//! Reading the TCP payloads out of a capture file in the classic pcap
+//! format, as written by tcpdump and Wireshark's "Save As... pcap", so that
+//! they can be split into PDUs with pdu_split.  pcapng files are not read.
+//!
+//! Ethernet, Linux cooked, loopback and raw IP captures of IPv4 and IPv6
+//! are understood.  Segments are taken in the order they were captured,
+//! dropping bytes that were retransmitted; IP fragments are not
+//! reassembled.
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+const GLOBAL_HEADER_LENGTH: usize = 24;
+const RECORD_HEADER_LENGTH: usize = 16;
+const MAGIC_MICROS: u32 = 0xa1b2c3d4;
+const MAGIC_NANOS: u32 = 0xa1b23c4d;
+const MAGIC_PCAPNG: u32 = 0x0a0d0d0a;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const PROTOCOL_TCP: u8 = 6;
+const TCP_SYN: u8 = 0x02;
+
+/// Data carried one way on a TCP connection in one packet
+#[derive(Debug, PartialEq)]
+pub struct TcpSegment {
+    /// Since the Unix epoch, as recorded by the capture
+    pub timestamp: Duration,
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub payload: Vec<u8>,
+}
+
+/// Every TCP segment in pcap that carries data not already seen.  Packets
+/// that are not TCP are skipped.
+pub fn tcp_segments(pcap: &[u8]) -> io::Result<Vec<TcpSegment>> {
+    let header = pcap
+        .get(..GLOBAL_HEADER_LENGTH)
+        .ok_or_else(|| invalid("Too short to be a pcap file".into()))?;
+    let magic =
+        u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    let (big_endian, nanos) = match magic {
+        MAGIC_MICROS => (false, false),
+        MAGIC_NANOS => (false, true),
+        m if m.swap_bytes() == MAGIC_MICROS => (true, false),
+        m if m.swap_bytes() == MAGIC_NANOS => (true, true),
+        MAGIC_PCAPNG => {
+            return Err(invalid(
+                "pcapng is not supported: save the capture as pcap".into(),
+            ))
+        }
+        m => {
+            return Err(invalid(format!("Not a pcap file (magic {:#010x})", m)))
+        }
+    };
+    let u32_at = |bytes: &[u8], i: usize| {
+        let b = [bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]];
+        if big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        }
+    };
+    let linktype = u32_at(header, 20);
+
+    let mut next_seq: HashMap<(SocketAddr, SocketAddr), u32> = HashMap::new();
+    let mut ret = Vec::new();
+    let mut rest = &pcap[GLOBAL_HEADER_LENGTH..];
+    while !rest.is_empty() {
+        let record = rest.get(..RECORD_HEADER_LENGTH).ok_or_else(|| {
+            invalid("pcap file ends part way through a packet".into())
+        })?;
+        let seconds = u64::from(u32_at(record, 0));
+        let fraction = u32_at(record, 4);
+        let timestamp = Duration::from_secs(seconds)
+            + if nanos {
+                Duration::from_nanos(fraction.into())
+            } else {
+                Duration::from_micros(fraction.into())
+            };
+        let captured_length = u32_at(record, 8) as usize;
+        let packet = rest
+            .get(RECORD_HEADER_LENGTH..RECORD_HEADER_LENGTH + captured_length)
+            .ok_or_else(|| {
+                invalid("pcap file ends part way through a packet".into())
+            })?;
+        rest = &rest[RECORD_HEADER_LENGTH + captured_length..];
+
+        let tcp = match ip_packet(linktype, packet).and_then(tcp_packet) {
+            Some(tcp) => tcp,
+            None => continue,
+        };
+        let key = (tcp.source, tcp.destination);
+        if tcp.syn {
+            next_seq.insert(key, tcp.seq.wrapping_add(1));
+            continue;
+        }
+        let mut payload = tcp.payload;
+        if let Some(expected) = next_seq.get(&key) {
+            // Anything before the sequence number we expect was seen already
+            let seen = expected.wrapping_sub(tcp.seq) as i32;
+            if seen > 0 {
+                payload = payload.get(seen as usize..).unwrap_or_default();
+            }
+        }
+        let end = tcp.seq.wrapping_add(tcp.payload.len() as u32);
+        let expected = next_seq.entry(key).or_insert(end);
+        if (end.wrapping_sub(*expected) as i32) > 0 {
+            *expected = end;
+        }
+        if !payload.is_empty() {
+            ret.push(TcpSegment {
+                timestamp,
+                source: tcp.source,
+                destination: tcp.destination,
+                payload: payload.to_vec(),
+            });
+        }
+    }
+    Ok(ret)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct IpPacket<'a> {
+    source: IpAddr,
+    destination: IpAddr,
+    protocol: u8,
+    payload: &'a [u8],
+}
+
+struct TcpPacket<'a> {
+    source: SocketAddr,
+    destination: SocketAddr,
+    seq: u32,
+    syn: bool,
+    payload: &'a [u8],
+}
+
+fn ip_packet(linktype: u32, frame: &[u8]) -> Option<IpPacket<'_>> {
+    let u16_at = |i: usize| {
+        Some(u16::from_be_bytes([*frame.get(i)?, *frame.get(i + 1)?]))
+    };
+    let (ethertype, ip) = match linktype {
+        LINKTYPE_ETHERNET => match u16_at(12)? {
+            ETHERTYPE_VLAN => (u16_at(16)?, frame.get(18..)?),
+            ethertype => (ethertype, frame.get(14..)?),
+        },
+        LINKTYPE_LINUX_SLL => (u16_at(14)?, frame.get(16..)?),
+        // The address family, in the byte order of whoever captured it
+        LINKTYPE_NULL => match frame.get(..4)? {
+            [2, 0, 0, 0] | [0, 0, 0, 2] => (ETHERTYPE_IPV4, frame.get(4..)?),
+            _ => (ETHERTYPE_IPV6, frame.get(4..)?),
+        },
+        LINKTYPE_RAW if frame.first()? >> 4 == 6 => (ETHERTYPE_IPV6, frame),
+        LINKTYPE_RAW | LINKTYPE_IPV4 => (ETHERTYPE_IPV4, frame),
+        LINKTYPE_IPV6 => (ETHERTYPE_IPV6, frame),
+        _ => return None,
+    };
+    match ethertype {
+        ETHERTYPE_IPV4 => ipv4_packet(ip),
+        ETHERTYPE_IPV6 => ipv6_packet(ip),
+        _ => None,
+    }
+}
+
+fn ipv4_packet(ip: &[u8]) -> Option<IpPacket<'_>> {
+    let header_length = usize::from(ip.first()? & 0x0f) * 4;
+    let total_length =
+        usize::from(u16::from_be_bytes([*ip.get(2)?, *ip.get(3)?]));
+    let address = |i: usize| -> Option<IpAddr> {
+        let bytes: [u8; 4] = ip.get(i..i + 4)?.try_into().ok()?;
+        Some(Ipv4Addr::from(bytes).into())
+    };
+    Some(IpPacket {
+        source: address(12)?,
+        destination: address(16)?,
+        protocol: *ip.get(9)?,
+        // Ethernet may have padded a short packet
+        payload: ip.get(header_length..total_length.min(ip.len()))?,
+    })
+}
+
+fn ipv6_packet(ip: &[u8]) -> Option<IpPacket<'_>> {
+    let payload_length =
+        usize::from(u16::from_be_bytes([*ip.get(4)?, *ip.get(5)?]));
+    let address = |i: usize| -> Option<IpAddr> {
+        let bytes: [u8; 16] = ip.get(i..i + 16)?.try_into().ok()?;
+        Some(Ipv6Addr::from(bytes).into())
+    };
+    Some(IpPacket {
+        source: address(8)?,
+        destination: address(24)?,
+        // Extension headers are not followed, so packets with them are
+        // skipped as not being TCP.
+        protocol: *ip.get(6)?,
+        payload: ip.get(40..(40 + payload_length).min(ip.len()))?,
+    })
+}
+
+fn tcp_packet(ip: IpPacket<'_>) -> Option<TcpPacket<'_>> {
+    if ip.protocol != PROTOCOL_TCP {
+        return None;
+    }
+    let tcp = ip.payload;
+    let port =
+        |i: usize| Some(u16::from_be_bytes([*tcp.get(i)?, *tcp.get(i + 1)?]));
+    let header_length = usize::from(tcp.get(12)? >> 4) * 4;
+    Some(TcpPacket {
+        source: SocketAddr::new(ip.source, port(0)?),
+        destination: SocketAddr::new(ip.destination, port(2)?),
+        seq: u32::from_be_bytes(tcp.get(4..8)?.try_into().ok()?),
+        syn: tcp.get(13)? & TCP_SYN != 0,
+        payload: tcp.get(header_length..)?,
+    })
+}
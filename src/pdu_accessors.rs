@@ -0,0 +1,224 @@
+//! Plain &str, u8 and &[u8] accessors for the fields of smpp_pdu's PDU
+//! bodies, instead of reaching through `.0.system_id.value`.
+//!
+//! SubmitSmPdu and DeliverSmPdu have their own source_addr() and
+//! destination_addr(), which return a String and are found before these.
+//! Call `SmAccessors::source_addr(&pdu)` for a &str.
+
+use futures::FutureExt;
+use smpp_pdu::pdu::data::bind_data::BindData;
+use smpp_pdu::pdu::data::bind_resp_data::BindRespData;
+use smpp_pdu::pdu::data::sm_data::SmData;
+use smpp_pdu::pdu::formats::COctetString;
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{
+    BindReceiverPdu, BindReceiverRespPdu, BindTransceiverPdu,
+    BindTransceiverRespPdu, BindTransmitterPdu, BindTransmitterRespPdu,
+    DeliverSmPdu, SubmitSmPdu,
+};
+use std::io::Cursor;
+
+const MAX_LENGTH_SYSTEM_ID: usize = 16;
+
+/// The fields of bind_transmitter, bind_receiver and bind_transceiver
+pub trait BindAccessors {
+    fn bind_fields(&self) -> &BindData;
+
+    fn system_id(&self) -> &str {
+        self.bind_fields().system_id.value.as_str()
+    }
+
+    fn password(&self) -> &str {
+        self.bind_fields().password.value.as_str()
+    }
+
+    fn system_type(&self) -> &str {
+        self.bind_fields().system_type.value.as_str()
+    }
+
+    fn interface_version(&self) -> u8 {
+        self.bind_fields().interface_version.value
+    }
+
+    fn addr_ton(&self) -> u8 {
+        self.bind_fields().addr_ton.value
+    }
+
+    fn addr_npi(&self) -> u8 {
+        self.bind_fields().addr_npi.value
+    }
+
+    fn address_range(&self) -> &str {
+        self.bind_fields().address_range.value.as_str()
+    }
+}
+
+impl BindAccessors for BindData {
+    fn bind_fields(&self) -> &BindData {
+        self
+    }
+}
+
+impl BindAccessors for BindTransmitterPdu {
+    fn bind_fields(&self) -> &BindData {
+        &self.0
+    }
+}
+
+impl BindAccessors for BindReceiverPdu {
+    fn bind_fields(&self) -> &BindData {
+        &self.0
+    }
+}
+
+impl BindAccessors for BindTransceiverPdu {
+    fn bind_fields(&self) -> &BindData {
+        &self.0
+    }
+}
+
+/// The fields of the bind responses
+pub trait BindRespAccessors {
+    fn bind_resp_fields(&self) -> &BindRespData;
+
+    /// The SMSC's system_id, or None if the bind failed and the response
+    /// has no body.
+    fn system_id(&self) -> Option<String> {
+        // The system_id inside BindRespData is private, so we have no
+        // choice but to write it out and read it back.
+        let mut buf = Vec::new();
+        self.bind_resp_fields()
+            .write(&mut buf)
+            .now_or_never()
+            .expect("Writing to a Vec should never wait")
+            .expect("Writing to a Vec should never fail");
+        if buf.is_empty() {
+            return None;
+        }
+        let system_id = COctetString::read(
+            &mut Cursor::new(&buf[..]),
+            MAX_LENGTH_SYSTEM_ID,
+        )
+        .expect("A written system_id should be readable");
+        Some(system_id.value.to_string())
+    }
+}
+
+impl BindRespAccessors for BindRespData {
+    fn bind_resp_fields(&self) -> &BindRespData {
+        self
+    }
+}
+
+impl BindRespAccessors for BindTransmitterRespPdu {
+    fn bind_resp_fields(&self) -> &BindRespData {
+        &self.0
+    }
+}
+
+impl BindRespAccessors for BindReceiverRespPdu {
+    fn bind_resp_fields(&self) -> &BindRespData {
+        &self.0
+    }
+}
+
+impl BindRespAccessors for BindTransceiverRespPdu {
+    fn bind_resp_fields(&self) -> &BindRespData {
+        &self.0
+    }
+}
+
+/// The fields of submit_sm and deliver_sm
+pub trait SmAccessors {
+    fn sm_fields(&self) -> &SmData;
+
+    fn service_type(&self) -> &str {
+        self.sm_fields().service_type.value.as_str()
+    }
+
+    fn source_addr_ton(&self) -> u8 {
+        self.sm_fields().source_addr_ton.value
+    }
+
+    fn source_addr_npi(&self) -> u8 {
+        self.sm_fields().source_addr_npi.value
+    }
+
+    fn source_addr(&self) -> &str {
+        self.sm_fields().source_addr.value.as_str()
+    }
+
+    fn dest_addr_ton(&self) -> u8 {
+        self.sm_fields().dest_addr_ton.value
+    }
+
+    fn dest_addr_npi(&self) -> u8 {
+        self.sm_fields().dest_addr_npi.value
+    }
+
+    fn destination_addr(&self) -> &str {
+        self.sm_fields().destination_addr.value.as_str()
+    }
+
+    fn esm_class(&self) -> u8 {
+        self.sm_fields().esm_class.value
+    }
+
+    fn protocol_id(&self) -> u8 {
+        self.sm_fields().protocol_id.value
+    }
+
+    fn priority_flag(&self) -> u8 {
+        self.sm_fields().priority_flag.value
+    }
+
+    fn schedule_delivery_time(&self) -> &str {
+        self.sm_fields().schedule_delivery_time.value.as_str()
+    }
+
+    fn validity_period(&self) -> &str {
+        self.sm_fields().validity_period.value.as_str()
+    }
+
+    fn registered_delivery(&self) -> u8 {
+        self.sm_fields().registered_delivery.value
+    }
+
+    fn replace_if_present_flag(&self) -> u8 {
+        self.sm_fields().replace_if_present_flag.value
+    }
+
+    fn data_coding(&self) -> u8 {
+        self.sm_fields().data_coding.value
+    }
+
+    fn sm_default_msg_id(&self) -> u8 {
+        self.sm_fields().sm_default_msg_id.value
+    }
+
+    fn short_message(&self) -> &[u8] {
+        &self.sm_fields().short_message.value
+    }
+
+    fn tlvs(&self) -> &Tlvs {
+        &self.sm_fields().tlvs
+    }
+}
+
+impl SmAccessors for SmData {
+    fn sm_fields(&self) -> &SmData {
+        self
+    }
+}
+
+impl SmAccessors for SubmitSmPdu {
+    fn sm_fields(&self) -> &SmData {
+        &self.0
+    }
+}
+
+impl SmAccessors for DeliverSmPdu {
+    fn sm_fields(&self) -> &SmData {
+        &self.0
+    }
+}
@@ -0,0 +1,183 @@
+//! Copying PDUs, so they can be stored, queued and retried without writing
+//! them out and parsing them again.
+//!
+//! smpp_pdu only derives Clone on its format types (Integer1, COctetString
+//! etc.), so this builds a copy of each PDU type from its fields.
+
+use smpp_pdu::pdu::data::bind_data::BindData;
+use smpp_pdu::pdu::data::bind_resp_data::BindRespData;
+use smpp_pdu::pdu::data::sm_data::SmData;
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{
+    BindReceiverPdu, BindReceiverRespPdu, BindTransceiverPdu,
+    BindTransceiverRespPdu, BindTransmitterPdu, BindTransmitterRespPdu,
+    DeliverSmPdu, EnquireLinkPdu, EnquireLinkRespPdu, GenericNackPdu, Pdu,
+    PduBody, SubmitSmPdu, SubmitSmRespPdu,
+};
+
+use crate::pdu_accessors::BindRespAccessors;
+use crate::typed_tlvs::TypedTlvs;
+
+pub trait PduClone {
+    /// A copy of this that compares equal to it.
+    fn pdu_clone(&self) -> Self;
+}
+
+impl PduClone for Pdu {
+    fn pdu_clone(&self) -> Self {
+        Pdu::new(
+            self.command_status.value,
+            self.sequence_number.value,
+            self.body().pdu_clone(),
+        )
+        .expect("A copy of a valid PDU should be valid")
+    }
+}
+
+impl PduClone for PduBody {
+    fn pdu_clone(&self) -> Self {
+        match self {
+            PduBody::BindReceiver(body) => body.pdu_clone().into(),
+            PduBody::BindReceiverResp(body) => body.pdu_clone().into(),
+            PduBody::BindTransceiver(body) => body.pdu_clone().into(),
+            PduBody::BindTransceiverResp(body) => body.pdu_clone().into(),
+            PduBody::BindTransmitter(body) => body.pdu_clone().into(),
+            PduBody::BindTransmitterResp(body) => body.pdu_clone().into(),
+            PduBody::DeliverSm(body) => body.pdu_clone().into(),
+            PduBody::EnquireLink(body) => body.pdu_clone().into(),
+            PduBody::EnquireLinkResp(body) => body.pdu_clone().into(),
+            PduBody::GenericNack(body) => body.pdu_clone().into(),
+            PduBody::SubmitSm(body) => body.pdu_clone().into(),
+            PduBody::SubmitSmResp(body) => body.pdu_clone().into(),
+        }
+    }
+}
+
+impl PduClone for BindReceiverPdu {
+    fn pdu_clone(&self) -> Self {
+        Self(self.0.pdu_clone())
+    }
+}
+
+impl PduClone for BindReceiverRespPdu {
+    fn pdu_clone(&self) -> Self {
+        Self(self.0.pdu_clone())
+    }
+}
+
+impl PduClone for BindTransceiverPdu {
+    fn pdu_clone(&self) -> Self {
+        Self(self.0.pdu_clone())
+    }
+}
+
+impl PduClone for BindTransceiverRespPdu {
+    fn pdu_clone(&self) -> Self {
+        Self(self.0.pdu_clone())
+    }
+}
+
+impl PduClone for BindTransmitterPdu {
+    fn pdu_clone(&self) -> Self {
+        Self(self.0.pdu_clone())
+    }
+}
+
+impl PduClone for BindTransmitterRespPdu {
+    fn pdu_clone(&self) -> Self {
+        Self(self.0.pdu_clone())
+    }
+}
+
+impl PduClone for DeliverSmPdu {
+    fn pdu_clone(&self) -> Self {
+        Self(self.0.pdu_clone())
+    }
+}
+
+impl PduClone for EnquireLinkPdu {
+    fn pdu_clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl PduClone for EnquireLinkRespPdu {
+    fn pdu_clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl PduClone for GenericNackPdu {
+    fn pdu_clone(&self) -> Self {
+        Self::new_error()
+    }
+}
+
+impl PduClone for SubmitSmPdu {
+    fn pdu_clone(&self) -> Self {
+        Self(self.0.pdu_clone())
+    }
+}
+
+impl PduClone for SubmitSmRespPdu {
+    fn pdu_clone(&self) -> Self {
+        Self {
+            message_id: self.message_id.clone(),
+        }
+    }
+}
+
+impl PduClone for BindData {
+    fn pdu_clone(&self) -> Self {
+        Self {
+            system_id: self.system_id.clone(),
+            password: self.password.clone(),
+            system_type: self.system_type.clone(),
+            interface_version: self.interface_version.clone(),
+            addr_ton: self.addr_ton.clone(),
+            addr_npi: self.addr_npi.clone(),
+            address_range: self.address_range.clone(),
+        }
+    }
+}
+
+impl PduClone for BindRespData {
+    fn pdu_clone(&self) -> Self {
+        match self.system_id() {
+            Some(system_id) => Self::new(&system_id)
+                .expect("A written system_id should be valid"),
+            None => Self::new_error(),
+        }
+    }
+}
+
+impl PduClone for SmData {
+    fn pdu_clone(&self) -> Self {
+        Self {
+            service_type: self.service_type.clone(),
+            source_addr_ton: self.source_addr_ton.clone(),
+            source_addr_npi: self.source_addr_npi.clone(),
+            source_addr: self.source_addr.clone(),
+            dest_addr_ton: self.dest_addr_ton.clone(),
+            dest_addr_npi: self.dest_addr_npi.clone(),
+            destination_addr: self.destination_addr.clone(),
+            esm_class: self.esm_class.clone(),
+            protocol_id: self.protocol_id.clone(),
+            priority_flag: self.priority_flag.clone(),
+            schedule_delivery_time: self.schedule_delivery_time.clone(),
+            validity_period: self.validity_period.clone(),
+            registered_delivery: self.registered_delivery.clone(),
+            replace_if_present_flag: self.replace_if_present_flag.clone(),
+            data_coding: self.data_coding.clone(),
+            sm_default_msg_id: self.sm_default_msg_id.clone(),
+            short_message: self.short_message.clone(),
+            tlvs: self.tlvs.pdu_clone(),
+        }
+    }
+}
+
+impl PduClone for Tlvs {
+    fn pdu_clone(&self) -> Self {
+        Tlvs::from(&self.to_vec())
+    }
+}
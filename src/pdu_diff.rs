@@ -0,0 +1,333 @@
+//! Comparing two PDUs field by field, so that a failed assertion can say
+//! "dest_addr_npi: 1 != 0" instead of printing two whole PDUs.
+//!
+//! ```no_run
+//! # use smpp::pdu_diff::diff;
+//! # use smpp_pdu::pdu::{EnquireLinkPdu, Pdu};
+//! # let expected = Pdu::new(0, 1, EnquireLinkPdu::new().into()).unwrap();
+//! # let actual = Pdu::new(0, 2, EnquireLinkPdu::new().into()).unwrap();
+//! let differences = diff(&expected, &actual);
+//! assert!(differences.is_empty(), "PDUs differ:\n{}", differences);
+//! ```
+
+use futures::FutureExt;
+use smpp_pdu::pdu::data::bind_data::BindData;
+use smpp_pdu::pdu::data::sm_data::SmData;
+use smpp_pdu::pdu::formats::COctetString;
+use smpp_pdu::pdu::tlvs::{Tlv, Tlvs};
+use smpp_pdu::pdu::{Pdu, PduBody};
+use std::fmt::{Display, Formatter};
+use std::io::Cursor;
+
+use crate::frame_body::HEADER_LENGTH;
+use crate::pdu_status::status_name;
+use crate::pdu_write::write_pdu;
+use crate::session_capture::hex_bytes;
+use crate::typed_tlvs::TypedTlvs;
+
+/// One field whose value differs between two PDUs
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldDiff {
+    pub field: String,
+    pub left: String,
+    pub right: String,
+}
+
+impl Display for FieldDiff {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "{}: {} != {}", self.field, self.left, self.right)
+    }
+}
+
+/// Every field that differs between two PDUs, in the order they appear on
+/// the wire.  Displays as one difference per line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PduDiff(pub Vec<FieldDiff>);
+
+impl PduDiff {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn field(&mut self, field: &str, left: impl Display, right: impl Display) {
+        let left = left.to_string();
+        let right = right.to_string();
+        if left != right {
+            self.0.push(FieldDiff {
+                field: String::from(field),
+                left,
+                right,
+            });
+        }
+    }
+}
+
+impl Display for PduDiff {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        for field_diff in &self.0 {
+            writeln!(formatter, "{}", field_diff)?;
+        }
+        Ok(())
+    }
+}
+
+/// The fields that differ between left and right, including TLVs.  If the
+/// two are different operations, only the header is compared.
+pub fn diff(left: &Pdu, right: &Pdu) -> PduDiff {
+    let mut ret = PduDiff::default();
+    ret.field(
+        "command_id",
+        format!("{:#010x}", left.command_id().value),
+        format!("{:#010x}", right.command_id().value),
+    );
+    ret.field(
+        "command_status",
+        status(left.command_status.value),
+        status(right.command_status.value),
+    );
+    ret.field(
+        "sequence_number",
+        left.sequence_number.value,
+        right.sequence_number.value,
+    );
+
+    match (left.body(), right.body()) {
+        (PduBody::BindReceiver(l), PduBody::BindReceiver(r)) => {
+            bind_fields(&mut ret, l.bind_data(), r.bind_data())
+        }
+        (PduBody::BindTransceiver(l), PduBody::BindTransceiver(r)) => {
+            bind_fields(&mut ret, l.bind_data(), r.bind_data())
+        }
+        (PduBody::BindTransmitter(l), PduBody::BindTransmitter(r)) => {
+            bind_fields(&mut ret, l.bind_data(), r.bind_data())
+        }
+        (PduBody::BindReceiverResp(_), PduBody::BindReceiverResp(_))
+        | (PduBody::BindTransceiverResp(_), PduBody::BindTransceiverResp(_))
+        | (PduBody::BindTransmitterResp(_), PduBody::BindTransmitterResp(_)) => {
+            // The system_id is not accessible on these types, so read it
+            // from the bytes instead.
+            ret.field("system_id", resp_system_id(left), resp_system_id(right));
+        }
+        (PduBody::DeliverSm(l), PduBody::DeliverSm(r)) => {
+            sm_fields(&mut ret, &l.0, &r.0)
+        }
+        (PduBody::SubmitSm(l), PduBody::SubmitSm(r)) => {
+            sm_fields(&mut ret, &l.0, &r.0)
+        }
+        (PduBody::SubmitSmResp(l), PduBody::SubmitSmResp(r)) => {
+            ret.field(
+                "message_id",
+                optional(l.message_id.as_ref()),
+                optional(r.message_id.as_ref()),
+            );
+        }
+        _ => {}
+    }
+    ret
+}
+
+fn status(command_status: u32) -> String {
+    match status_name(command_status) {
+        Some(name) => format!("{} ({:#010x})", name, command_status),
+        None => format!("{:#010x}", command_status),
+    }
+}
+
+fn quoted(value: &COctetString) -> String {
+    format!("{:?}", value.value.as_str())
+}
+
+fn optional(value: Option<&COctetString>) -> String {
+    value.map_or_else(|| String::from("<absent>"), quoted)
+}
+
+fn written(pdu: &Pdu) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_pdu(pdu, &mut buf)
+        .now_or_never()
+        .expect("Writing to a Vec should never wait")
+        .expect("Writing to a Vec should never fail");
+    buf
+}
+
+fn resp_system_id(pdu: &Pdu) -> String {
+    let bytes = written(pdu);
+    let system_id =
+        COctetString::read(&mut Cursor::new(&bytes[HEADER_LENGTH..]), 16).ok();
+    optional(system_id.as_ref())
+}
+
+fn bind_fields(ret: &mut PduDiff, left: &BindData, right: &BindData) {
+    ret.field(
+        "system_id",
+        quoted(&left.system_id),
+        quoted(&right.system_id),
+    );
+    ret.field("password", quoted(&left.password), quoted(&right.password));
+    ret.field(
+        "system_type",
+        quoted(&left.system_type),
+        quoted(&right.system_type),
+    );
+    ret.field(
+        "interface_version",
+        format!("{:#04x}", left.interface_version.value),
+        format!("{:#04x}", right.interface_version.value),
+    );
+    ret.field("addr_ton", left.addr_ton.value, right.addr_ton.value);
+    ret.field("addr_npi", left.addr_npi.value, right.addr_npi.value);
+    ret.field(
+        "address_range",
+        quoted(&left.address_range),
+        quoted(&right.address_range),
+    );
+}
+
+fn sm_fields(ret: &mut PduDiff, left: &SmData, right: &SmData) {
+    ret.field(
+        "service_type",
+        quoted(&left.service_type),
+        quoted(&right.service_type),
+    );
+    ret.field(
+        "source_addr_ton",
+        left.source_addr_ton.value,
+        right.source_addr_ton.value,
+    );
+    ret.field(
+        "source_addr_npi",
+        left.source_addr_npi.value,
+        right.source_addr_npi.value,
+    );
+    ret.field(
+        "source_addr",
+        quoted(&left.source_addr),
+        quoted(&right.source_addr),
+    );
+    ret.field(
+        "dest_addr_ton",
+        left.dest_addr_ton.value,
+        right.dest_addr_ton.value,
+    );
+    ret.field(
+        "dest_addr_npi",
+        left.dest_addr_npi.value,
+        right.dest_addr_npi.value,
+    );
+    ret.field(
+        "destination_addr",
+        quoted(&left.destination_addr),
+        quoted(&right.destination_addr),
+    );
+    ret.field(
+        "esm_class",
+        format!("{:#04x}", left.esm_class.value),
+        format!("{:#04x}", right.esm_class.value),
+    );
+    ret.field(
+        "protocol_id",
+        left.protocol_id.value,
+        right.protocol_id.value,
+    );
+    ret.field(
+        "priority_flag",
+        left.priority_flag.value,
+        right.priority_flag.value,
+    );
+    ret.field(
+        "schedule_delivery_time",
+        quoted(&left.schedule_delivery_time),
+        quoted(&right.schedule_delivery_time),
+    );
+    ret.field(
+        "validity_period",
+        quoted(&left.validity_period),
+        quoted(&right.validity_period),
+    );
+    ret.field(
+        "registered_delivery",
+        format!("{:#04x}", left.registered_delivery.value),
+        format!("{:#04x}", right.registered_delivery.value),
+    );
+    ret.field(
+        "replace_if_present_flag",
+        left.replace_if_present_flag.value,
+        right.replace_if_present_flag.value,
+    );
+    ret.field(
+        "data_coding",
+        format!("{:#04x}", left.data_coding.value),
+        format!("{:#04x}", right.data_coding.value),
+    );
+    ret.field(
+        "sm_default_msg_id",
+        left.sm_default_msg_id.value,
+        right.sm_default_msg_id.value,
+    );
+    ret.field(
+        "short_message",
+        message_bytes(&left.short_message.value),
+        message_bytes(&right.short_message.value),
+    );
+    tlv_fields(ret, &left.tlvs, &right.tlvs);
+}
+
+fn message_bytes(bytes: &[u8]) -> String {
+    if cfg!(feature = "redact-message-content") {
+        format!("<{} bytes redacted>", bytes.len())
+    } else {
+        hex_bytes(bytes)
+    }
+}
+
+/// Compares TLVs by tag, so that the order they were added in does not
+/// matter.  If a tag appears more than once, its values are compared in
+/// order.
+fn tlv_fields(ret: &mut PduDiff, left: &Tlvs, right: &Tlvs) {
+    let left = left.to_vec();
+    let right = right.to_vec();
+
+    let mut tags: Vec<u16> = Vec::new();
+    for tlv in left.iter().chain(right.iter()) {
+        if !tags.contains(&tlv.raw_tag) {
+            tags.push(tlv.raw_tag);
+        }
+    }
+
+    for tag in tags {
+        let values = |tlvs: &[Tlv]| -> Vec<Vec<u8>> {
+            tlvs.iter()
+                .filter(|tlv| tlv.raw_tag == tag)
+                .map(|tlv| tlv.value.clone())
+                .collect()
+        };
+        let left_values = values(&left);
+        let right_values = values(&right);
+        let count = left_values.len().max(right_values.len());
+        for i in 0..count {
+            let mut name = tlv_name(tag);
+            if count > 1 {
+                name = format!("{}[{}]", name, i);
+            }
+            ret.field(
+                &name,
+                tlv_value(left_values.get(i)),
+                tlv_value(right_values.get(i)),
+            );
+        }
+    }
+}
+
+fn tlv_name(raw_tag: u16) -> String {
+    match Tlv::new_unknown(raw_tag, &[]).tag() {
+        Ok(tag) => format!("tlv {:?}", tag),
+        Err(_) => format!("tlv {:#06x}", raw_tag),
+    }
+}
+
+fn tlv_value(value: Option<&Vec<u8>>) -> String {
+    match value {
+        Some(value) => message_bytes(value),
+        None => String::from("<absent>"),
+    }
+}
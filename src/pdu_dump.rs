@@ -0,0 +1,96 @@
+//! Printing a PDU for a person to read, as Wireshark would: its header and
+//! body fields by name, then its bytes in hex and ASCII.  For comparing
+//! with a carrier's capture when debugging interop.
+//!
+//! ```no_run
+//! # use smpp_pdu::pdu::{EnquireLinkPdu, Pdu};
+//! use smpp::pdu_dump::Dump;
+//! # let pdu = Pdu::new(0, 1, EnquireLinkPdu::new().into()).unwrap();
+//! println!("{}", pdu.dump());
+//! ```
+
+use futures::FutureExt;
+use smpp_pdu::pdu::Pdu;
+use std::fmt::{Display, Formatter, Write};
+
+use crate::pdu_write::write_pdu;
+use crate::redact::frame_bytes;
+use crate::session_capture::wireshark_text;
+
+const BYTES_PER_LINE: usize = 16;
+
+pub trait Dump {
+    /// Displays as the fields of this PDU, then a hex dump of its bytes.
+    fn dump(&self) -> PduDump;
+}
+
+impl Dump for Pdu {
+    fn dump(&self) -> PduDump {
+        let mut bytes = Vec::new();
+        write_pdu(self, &mut bytes)
+            .now_or_never()
+            .expect("Writing to a Vec should never wait")
+            .expect("Writing to a Vec should never fail");
+        PduDump { bytes }
+    }
+}
+
+/// A PDU as written, ready to print
+pub struct PduDump {
+    bytes: Vec<u8>,
+}
+
+impl PduDump {
+    /// For bytes read from the wire, which need not be a valid PDU.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self {
+            bytes: bytes.to_vec(),
+        }
+    }
+}
+
+impl Display for PduDump {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str(&wireshark_text(&self.bytes))?;
+        formatter.write_str("\n")?;
+        let shown = frame_bytes(&self.bytes);
+        formatter.write_str(&hex_dump(shown))?;
+        if shown.len() < self.bytes.len() {
+            formatter.write_str("[Body redacted]\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// bytes in the layout of Wireshark's "Packet Bytes" view: the offset, 16
+/// bytes in hex, and the same bytes as ASCII with '.' for the rest.
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (line, chunk) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+        let _ = write!(out, "{:04x} ", line * BYTES_PER_LINE);
+        for i in 0..BYTES_PER_LINE {
+            if i == BYTES_PER_LINE / 2 {
+                out.push(' ');
+            }
+            match chunk.get(i) {
+                Some(b) => {
+                    let _ = write!(out, " {:02x}", b);
+                }
+                None => out.push_str("   "),
+            }
+        }
+        out.push_str("   ");
+        for (i, b) in chunk.iter().enumerate() {
+            if i == BYTES_PER_LINE / 2 {
+                out.push(' ');
+            }
+            out.push(if b.is_ascii_graphic() || *b == b' ' {
+                char::from(*b)
+            } else {
+                '.'
+            });
+        }
+        out.push('\n');
+    }
+    out
+}
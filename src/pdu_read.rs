@@ -0,0 +1,60 @@
+//! Reading PDUs from any async stream, to go with pdu_write.
+//!
+//! Pdu::parse() needs the whole PDU in a BufRead, so callers reading from
+//! a socket would otherwise have to buffer until one has arrived.
+//! read_pdu() awaits the whole frame first, using ParseOptions::check() on
+//! its command_length, then hands it to Pdu::parse().
+
+use smpp_pdu::pdu::{Pdu, PduParseError};
+use std::io::Cursor;
+use tokio::io::{AsyncBufRead, AsyncReadExt};
+
+use crate::parse_options::ParseOptions;
+use crate::smpp_connection::Frame;
+
+/// The next PDU from reader, or None if reader ended before one began.
+/// Ending part way through a PDU is PduParseErrorBody::NotEnoughBytes.
+pub async fn read_pdu<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> Result<Option<Pdu>, PduParseError> {
+    match read_frame_bytes(reader, &ParseOptions::default()).await? {
+        Some(bytes) => Pdu::parse(&mut Cursor::new(&bytes)).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// As read_pdu(), but for any frame, including those smpp_pdu cannot
+/// represent such as data_sm.
+pub async fn read_frame<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> Result<Option<Frame>, PduParseError> {
+    read_frame_with_options(reader, &ParseOptions::default()).await
+}
+
+/// As read_frame(), with a max_pdu_length of our choosing.
+pub async fn read_frame_with_options<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    options: &ParseOptions,
+) -> Result<Option<Frame>, PduParseError> {
+    match read_frame_bytes(reader, options).await? {
+        Some(bytes) => options.parse_frame(&bytes).map(Some),
+        None => Ok(None),
+    }
+}
+
+async fn read_frame_bytes<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    options: &ParseOptions,
+) -> Result<Option<Vec<u8>>, PduParseError> {
+    let mut command_length = [0; 4];
+    if reader.read(&mut command_length[..1]).await? == 0 {
+        return Ok(None);
+    }
+    reader.read_exact(&mut command_length[1..]).await?;
+    // With only command_length, check() either fails or wants more
+    options.check(&mut Cursor::new(&command_length[..]))?;
+    let mut bytes = vec![0; u32::from_be_bytes(command_length) as usize];
+    bytes[..4].copy_from_slice(&command_length);
+    reader.read_exact(&mut bytes[4..]).await?;
+    Ok(Some(bytes))
+}
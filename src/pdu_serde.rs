@@ -0,0 +1,374 @@
+//! Serde support for Pdu, so that PDUs can be logged as JSON or kept in
+//! test fixtures.  Needs the serde feature.
+//!
+//! Pdu belongs to smpp_pdu, so it cannot implement Serialize itself.  Wrap
+//! it in a SerdePdu, or mark a field #[serde(with = "smpp::pdu_serde")].
+//!
+//! command_id is a hex string and command_status the name of the status,
+//! or hex where it has none.  Bind, submit_sm, deliver_sm and
+//! submit_sm_resp bodies are written field by field, with short_message
+//! and TLV values in hex.  Other bodies are kept as hex:
+//!
+//! ```json
+//! {
+//!   "command_id": "0x00000004",
+//!   "command_status": "ESME_ROK",
+//!   "sequence_number": 1,
+//!   "body": {"sm": {"service_type": "", ..., "short_message": "6869"}}
+//! }
+//! ```
+//!
+//! Deserializing rebuilds the frame and hands it to Pdu::parse(), so a PDU
+//! that could not be parsed from the wire cannot be deserialized either.
+
+use futures::FutureExt;
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use smpp_pdu::pdu::data::bind_data::BindData;
+use smpp_pdu::pdu::{GenericNackPdu, Pdu, PduBody};
+use std::convert::TryFrom;
+use std::io::Cursor;
+
+use crate::command_id::CommandId;
+use crate::frame_body::HEADER_LENGTH;
+use crate::pdu_status::{parse_status, status_name};
+use crate::pdu_view::SmView;
+use crate::pdu_write::write_pdu;
+use crate::session_capture::hex_bytes;
+
+/// A Pdu that implements Serialize and Deserialize
+#[derive(Debug, PartialEq)]
+pub struct SerdePdu(pub Pdu);
+
+impl Serialize for SerdePdu {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SerdePdu {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(Self)
+    }
+}
+
+pub fn serialize<S: Serializer>(
+    pdu: &Pdu,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    PduRepr::new(pdu)
+        .map_err(S::Error::custom)?
+        .serialize(serializer)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Pdu, D::Error> {
+    PduRepr::deserialize(deserializer)?
+        .to_pdu()
+        .map_err(D::Error::custom)
+}
+
+/// For a command_status held as a u32, with
+/// #[serde(with = "smpp::pdu_serde::command_status")]: its name, such as
+/// "ESME_RTHROTTLED", or hex where it has none.
+pub mod command_status {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        command_status: &u32,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        status_string(*command_status).serialize(serializer)
+    }
+
+    /// Also accepts decimal, as --source-quota-status does.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<u32, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        parse_status(&s).ok_or_else(|| {
+            D::Error::custom(format!("Invalid command_status '{}'", s))
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct PduRepr {
+    command_id: String,
+    #[serde(with = "command_status")]
+    command_status: u32,
+    sequence_number: u32,
+    body: BodyRepr,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum BodyRepr {
+    Bind(BindRepr),
+    Sm(SmRepr),
+    SubmitSmResp {
+        message_id: Option<String>,
+    },
+    /// Hex
+    Raw(String),
+}
+
+#[derive(Deserialize, Serialize)]
+struct BindRepr {
+    system_id: String,
+    password: String,
+    system_type: String,
+    interface_version: u8,
+    addr_ton: u8,
+    addr_npi: u8,
+    address_range: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct SmRepr {
+    service_type: String,
+    source_addr_ton: u8,
+    source_addr_npi: u8,
+    source_addr: String,
+    dest_addr_ton: u8,
+    dest_addr_npi: u8,
+    destination_addr: String,
+    esm_class: u8,
+    protocol_id: u8,
+    priority_flag: u8,
+    schedule_delivery_time: String,
+    validity_period: String,
+    registered_delivery: u8,
+    replace_if_present_flag: u8,
+    data_coding: u8,
+    sm_default_msg_id: u8,
+    /// Hex
+    short_message: String,
+    tlvs: Vec<TlvRepr>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct TlvRepr {
+    /// Hex, e.g. "0x0204"
+    tag: String,
+    /// Hex
+    value: String,
+}
+
+impl PduRepr {
+    fn new(pdu: &Pdu) -> Result<Self, String> {
+        let mut bytes = Vec::new();
+        write_pdu(pdu, &mut bytes)
+            .now_or_never()
+            .expect("Writing to a Vec should never wait")
+            .map_err(|e| e.to_string())?;
+        let body = match pdu.body() {
+            PduBody::BindReceiver(body) => {
+                BodyRepr::Bind(BindRepr::new(body.bind_data()))
+            }
+            PduBody::BindTransceiver(body) => {
+                BodyRepr::Bind(BindRepr::new(body.bind_data()))
+            }
+            PduBody::BindTransmitter(body) => {
+                BodyRepr::Bind(BindRepr::new(body.bind_data()))
+            }
+            PduBody::SubmitSm(_) | PduBody::DeliverSm(_) => {
+                let view = SmView::parse(&bytes).map_err(|e| e.to_string())?;
+                BodyRepr::Sm(SmRepr::new(&view))
+            }
+            PduBody::SubmitSmResp(body) => BodyRepr::SubmitSmResp {
+                message_id: body
+                    .message_id
+                    .as_ref()
+                    .map(|id| String::from(id.value.as_str())),
+            },
+            _ => BodyRepr::Raw(hex_bytes(&bytes[HEADER_LENGTH..])),
+        };
+        Ok(Self {
+            command_id: format!("{:#010x}", pdu.command_id().value),
+            command_status: pdu.command_status.value,
+            sequence_number: pdu.sequence_number.value,
+            body,
+        })
+    }
+
+    fn to_pdu(&self) -> Result<Pdu, String> {
+        let command_id = self
+            .command_id
+            .strip_prefix("0x")
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| {
+                format!(
+                    "Invalid command_id '{}': expected hex like 0x00000004",
+                    self.command_id
+                )
+            })?;
+        let mut body = Vec::new();
+        match &self.body {
+            BodyRepr::Bind(bind) => bind.write(&mut body),
+            BodyRepr::Sm(sm) => sm.write(&mut body)?,
+            BodyRepr::SubmitSmResp { message_id } => {
+                if let Some(message_id) = message_id {
+                    write_c_octet_string(&mut body, message_id);
+                }
+            }
+            BodyRepr::Raw(hex) => body = from_hex("body", hex)?,
+        }
+        // Pdu::parse() does not know generic_nack
+        if command_id == CommandId::GenericNack as u32 && body.is_empty() {
+            return Pdu::new(
+                self.command_status,
+                self.sequence_number,
+                GenericNackPdu::new_error().into(),
+            )
+            .map_err(|e| e.to_string());
+        }
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + body.len());
+        for value in &[
+            (HEADER_LENGTH + body.len()) as u32,
+            command_id,
+            self.command_status,
+            self.sequence_number,
+        ] {
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+        bytes.extend_from_slice(&body);
+        Pdu::parse(&mut Cursor::new(&bytes)).map_err(|e| e.to_string())
+    }
+}
+
+impl BindRepr {
+    fn new(data: &BindData) -> Self {
+        Self {
+            system_id: String::from(data.system_id.value.as_str()),
+            password: String::from(data.password.value.as_str()),
+            system_type: String::from(data.system_type.value.as_str()),
+            interface_version: data.interface_version.value,
+            addr_ton: data.addr_ton.value,
+            addr_npi: data.addr_npi.value,
+            address_range: String::from(data.address_range.value.as_str()),
+        }
+    }
+
+    fn write(&self, body: &mut Vec<u8>) {
+        write_c_octet_string(body, &self.system_id);
+        write_c_octet_string(body, &self.password);
+        write_c_octet_string(body, &self.system_type);
+        body.extend_from_slice(&[
+            self.interface_version,
+            self.addr_ton,
+            self.addr_npi,
+        ]);
+        write_c_octet_string(body, &self.address_range);
+    }
+}
+
+impl SmRepr {
+    fn new(view: &SmView) -> Self {
+        Self {
+            service_type: String::from(view.service_type),
+            source_addr_ton: view.source_addr_ton,
+            source_addr_npi: view.source_addr_npi,
+            source_addr: String::from(view.source_addr),
+            dest_addr_ton: view.dest_addr_ton,
+            dest_addr_npi: view.dest_addr_npi,
+            destination_addr: String::from(view.destination_addr),
+            esm_class: view.esm_class,
+            protocol_id: view.protocol_id,
+            priority_flag: view.priority_flag,
+            schedule_delivery_time: String::from(view.schedule_delivery_time),
+            validity_period: String::from(view.validity_period),
+            registered_delivery: view.registered_delivery,
+            replace_if_present_flag: view.replace_if_present_flag,
+            data_coding: view.data_coding,
+            sm_default_msg_id: view.sm_default_msg_id,
+            short_message: hex_bytes(view.short_message),
+            tlvs: view
+                .tlvs
+                .iter()
+                .map(|(tag, value)| TlvRepr {
+                    tag: format!("{:#06x}", tag),
+                    value: hex_bytes(value),
+                })
+                .collect(),
+        }
+    }
+
+    fn write(&self, body: &mut Vec<u8>) -> Result<(), String> {
+        write_c_octet_string(body, &self.service_type);
+        body.extend_from_slice(&[self.source_addr_ton, self.source_addr_npi]);
+        write_c_octet_string(body, &self.source_addr);
+        body.extend_from_slice(&[self.dest_addr_ton, self.dest_addr_npi]);
+        write_c_octet_string(body, &self.destination_addr);
+        body.extend_from_slice(&[
+            self.esm_class,
+            self.protocol_id,
+            self.priority_flag,
+        ]);
+        write_c_octet_string(body, &self.schedule_delivery_time);
+        write_c_octet_string(body, &self.validity_period);
+        body.extend_from_slice(&[
+            self.registered_delivery,
+            self.replace_if_present_flag,
+            self.data_coding,
+            self.sm_default_msg_id,
+        ]);
+        let short_message = from_hex("short_message", &self.short_message)?;
+        let sm_length = u8::try_from(short_message.len()).map_err(|_| {
+            format!(
+                "short_message is {} bytes, more than an sm_length can say",
+                short_message.len()
+            )
+        })?;
+        body.push(sm_length);
+        body.extend_from_slice(&short_message);
+        for tlv in &self.tlvs {
+            let tag = tlv
+                .tag
+                .strip_prefix("0x")
+                .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| format!("Invalid TLV tag '{}'", tlv.tag))?;
+            let value = from_hex("TLV value", &tlv.value)?;
+            let length = u16::try_from(value.len())
+                .map_err(|_| String::from("TLV value is too long"))?;
+            body.extend_from_slice(&tag.to_be_bytes());
+            body.extend_from_slice(&length.to_be_bytes());
+            body.extend_from_slice(&value);
+        }
+        Ok(())
+    }
+}
+
+fn status_string(command_status: u32) -> String {
+    match status_name(command_status) {
+        Some(name) => String::from(name),
+        None => format!("{:#010x}", command_status),
+    }
+}
+
+fn write_c_octet_string(body: &mut Vec<u8>, value: &str) {
+    body.extend_from_slice(value.as_bytes());
+    body.push(0);
+}
+
+fn from_hex(name: &str, hex: &str) -> Result<Vec<u8>, String> {
+    let invalid = || format!("Invalid hex in {}: '{}'", name, hex);
+    if hex.len() % 2 != 0 {
+        return Err(invalid());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
@@ -0,0 +1,86 @@
+//! Cutting a stream of bytes into PDUs using each one's command_length,
+//! without parsing them, for reading captured traffic.  Bytes may be
+//! pushed a piece at a time, as they arrive in TCP segments.
+
+use smpp_pdu::pdu::{CheckOutcome, Pdu, PduParseError};
+use std::io::Cursor;
+
+/// Some bytes from the stream, and how far into it they started
+#[derive(Debug, PartialEq)]
+pub struct StreamBytes {
+    pub offset: usize,
+    pub bytes: Vec<u8>,
+}
+
+/// The stream could not be split any further because a command_length was
+/// invalid.  rest is everything from there to what had been pushed.
+#[derive(Debug)]
+pub struct SplitError {
+    pub rest: StreamBytes,
+    pub error: PduParseError,
+}
+
+#[derive(Default)]
+pub struct PduSplitter {
+    buffer: Vec<u8>,
+    /// Offset in the stream of buffer[0]
+    offset: usize,
+    /// Once we have lost track of where PDUs start, we ignore the rest.
+    failed: bool,
+}
+
+impl PduSplitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        if !self.failed {
+            self.buffer.extend_from_slice(bytes);
+        }
+    }
+
+    /// The next whole PDU, or None until more bytes are pushed.  After an
+    /// Err, always None.
+    pub fn next_pdu(&mut self) -> Option<Result<StreamBytes, SplitError>> {
+        if self.failed || self.buffer.is_empty() {
+            return None;
+        }
+        let mut cursor = Cursor::new(&self.buffer);
+        match Pdu::check(&mut cursor) {
+            Ok(CheckOutcome::Ready) => {
+                // Pdu::check moved us to the end, so position is length
+                let len = cursor.position() as usize;
+                let rest = self.buffer.split_off(len);
+                let bytes = std::mem::replace(&mut self.buffer, rest);
+                let offset = self.offset;
+                self.offset += bytes.len();
+                Some(Ok(StreamBytes { offset, bytes }))
+            }
+            Ok(CheckOutcome::Incomplete) => None,
+            Err(e) => {
+                self.failed = true;
+                Some(Err(SplitError {
+                    rest: StreamBytes {
+                        offset: self.offset,
+                        bytes: std::mem::take(&mut self.buffer),
+                    },
+                    error: e.into(),
+                }))
+            }
+        }
+    }
+
+    /// Whatever is left at the end of the stream: the start of a PDU whose
+    /// remaining bytes never arrived.
+    pub fn finish(self) -> Option<StreamBytes> {
+        if self.buffer.is_empty() {
+            None
+        } else {
+            Some(StreamBytes {
+                offset: self.offset,
+                bytes: self.buffer,
+            })
+        }
+    }
+}
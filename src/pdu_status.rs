@@ -0,0 +1,142 @@
+//! Helpers for describing command_status values.
+
+use smpp_pdu::pdu::PduStatus;
+use std::fmt::{Display, Formatter};
+
+macro_rules! statuses {
+    ($($name:ident = $value:expr => $description:expr,)*) => {
+        /// The name the spec gives to the supplied command_status (e.g.
+        /// "ESME_RINVCMDID"), or None if the value is reserved or
+        /// vendor-specific.
+        ///
+        /// https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 5.1.3
+        pub fn status_name(command_status: u32) -> Option<&'static str> {
+            match command_status {
+                $($value => Some(stringify!($name)),)*
+                _ => None,
+            }
+        }
+
+        /// What the spec says the supplied command_status means (e.g.
+        /// "Invalid Command ID"), or None if it has no name.
+        pub fn status_description(command_status: u32) -> Option<&'static str> {
+            match command_status {
+                $($value => Some($description),)*
+                _ => None,
+            }
+        }
+
+        /// The command_status value with the supplied name, e.g.
+        /// Some(0x00000058) for "ESME_RTHROTTLED".
+        pub fn status_value(name: &str) -> Option<u32> {
+            match name {
+                $(stringify!($name) => Some($value),)*
+                _ => None,
+            }
+        }
+
+        /// The PduStatus for the supplied command_status, or None if the
+        /// value is reserved or vendor-specific.
+        pub fn pdu_status(command_status: u32) -> Option<PduStatus> {
+            match command_status {
+                $($value => Some(PduStatus::$name),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+statuses! {
+    ESME_ROK = 0x00000000 => "No Error",
+    ESME_RINVMSGLEN = 0x00000001 => "Message Length is invalid",
+    ESME_RINVCMDLEN = 0x00000002 => "Command Length is invalid",
+    ESME_RINVCMDID = 0x00000003 => "Invalid Command ID",
+    ESME_RINVBNDSTS = 0x00000004 => "Incorrect BIND Status for given command",
+    ESME_RALYBND = 0x00000005 => "ESME Already in Bound State",
+    ESME_RINVPRTFLG = 0x00000006 => "Invalid Priority Flag",
+    ESME_RINVREGDLVFLG = 0x00000007 => "Invalid Registered Delivery Flag",
+    ESME_RSYSERR = 0x00000008 => "System Error",
+    ESME_RINVSRCADR = 0x0000000A => "Invalid Source Address",
+    ESME_RINVDSTADR = 0x0000000B => "Invalid Dest Addr",
+    ESME_RINVMSGID = 0x0000000C => "Message ID is invalid",
+    ESME_RBINDFAIL = 0x0000000D => "Bind Failed",
+    ESME_RINVPASWD = 0x0000000E => "Invalid Password",
+    ESME_RINVSYSID = 0x0000000F => "Invalid System ID",
+    ESME_RCANCELFAIL = 0x00000011 => "Cancel SM Failed",
+    ESME_RREPLACEFAIL = 0x00000013 => "Replace SM Failed",
+    ESME_RMSGQFUL = 0x00000014 => "Message Queue Full",
+    ESME_RINVSERTYP = 0x00000015 => "Invalid Service Type",
+    ESME_RINVNUMDESTS = 0x00000033 => "Invalid number of destinations",
+    ESME_RINVDLNAME = 0x00000034 => "Invalid Distribution List name",
+    ESME_RINVDESTFLAG = 0x00000040 => "Destination flag is invalid (submit_multi)",
+    ESME_RINVSUBREP = 0x00000042 => "Invalid 'submit with replace' request",
+    ESME_RINVESMCLASS = 0x00000043 => "Invalid esm_class field data",
+    ESME_RCNTSUBDL = 0x00000044 => "Cannot Submit to Distribution List",
+    ESME_RSUBMITFAIL = 0x00000045 => "submit_sm or submit_multi failed",
+    ESME_RINVSRCTON = 0x00000048 => "Invalid Source address TON",
+    ESME_RINVSRCNPI = 0x00000049 => "Invalid Source address NPI",
+    ESME_RINVDSTTON = 0x00000050 => "Invalid Destination address TON",
+    ESME_RINVDSTNPI = 0x00000051 => "Invalid Destination address NPI",
+    ESME_RINVSYSTYP = 0x00000053 => "Invalid system_type field",
+    ESME_RINVREPFLAG = 0x00000054 => "Invalid replace_if_present flag",
+    ESME_RINVNUMMSGS = 0x00000055 => "Invalid number of messages",
+    ESME_RTHROTTLED = 0x00000058 => "Throttling error (ESME has exceeded allowed message limits)",
+    ESME_RINVSCHED = 0x00000061 => "Invalid Scheduled Delivery Time",
+    ESME_RINVEXPIRY = 0x00000062 => "Invalid message validity period (Expiry time)",
+    ESME_RINVDFTMSGID = 0x00000063 => "Predefined Message Invalid or Not Found",
+    ESME_RX_T_APPN = 0x00000064 => "ESME Receiver Temporary App Error Code",
+    ESME_RX_P_APPN = 0x00000065 => "ESME Receiver Permanent App Error Code",
+    ESME_RX_R_APPN = 0x00000066 => "ESME Receiver Reject Message Error Code",
+    ESME_RQUERYFAIL = 0x00000067 => "query_sm request failed",
+    ESME_RINVOPTPARSTREAM = 0x000000C0 => "Error in the optional part of the PDU Body",
+    ESME_ROPTPARNOTALLWD = 0x000000C1 => "Optional Parameter not allowed",
+    ESME_RINVPARLEN = 0x000000C2 => "Invalid Parameter Length",
+    ESME_RMISSINGOPTPARAM = 0x000000C3 => "Expected Optional Parameter missing",
+    ESME_RINVOPTPARAMVAL = 0x000000C4 => "Invalid Optional Parameter Value",
+    ESME_RDELIVERYFAILURE = 0x000000FE => "Delivery Failure",
+    ESME_RUNKNOWNERR = 0x000000FF => "Unknown Error",
+}
+
+/// Is command_status in the range the spec reserves for SMSC
+/// vendor-specific errors?
+pub fn is_vendor_specific(command_status: u32) -> bool {
+    (0x00000400..=0x000004FF).contains(&command_status)
+}
+
+/// A status name like ESME_RTHROTTLED, or a number like 0x58 or 88
+pub fn parse_status(s: &str) -> Option<u32> {
+    status_value(s).or_else(|| match s.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    })
+}
+
+/// Displays a command_status as its name followed by its value in hex,
+/// e.g. "ESME_RINVCMDLEN (0x00000002)", or just the hex if it has no name.
+pub struct StatusName(pub u32);
+
+impl Display for StatusName {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match status_name(self.0) {
+            Some(name) => write!(formatter, "{} ({:#010X})", name, self.0),
+            None => write!(formatter, "{:#010X}", self.0),
+        }
+    }
+}
+
+/// Displays a command_status as StatusName does, followed by what it means,
+/// e.g. "ESME_RINVPASWD (0x0000000E): Invalid Password".
+pub struct StatusDescription(pub u32);
+
+impl Display for StatusDescription {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        StatusName(self.0).fmt(formatter)?;
+        match status_description(self.0) {
+            Some(description) => write!(formatter, ": {}", description),
+            None if is_vendor_specific(self.0) => {
+                formatter.write_str(": vendor-specific error")
+            }
+            None => Ok(()),
+        }
+    }
+}
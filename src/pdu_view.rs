@@ -0,0 +1,324 @@
+//! Reading submit_sm and deliver_sm straight from the bytes of a frame,
+//! borrowing each string and octet string where Pdu::parse() copies them
+//! into Vecs of their own.  For servers where those allocations add up.
+//! A bytes::Bytes derefs to the &[u8] that SmView::parse() takes, and a
+//! view can still be made into a SubmitSmPdu or DeliverSmPdu when one is
+//! needed.
+//!
+//! Frames are checked as Pdu::parse() checks them, and fail with the same
+//! errors.
+
+use ascii::AsciiStr;
+use num_traits::ToPrimitive;
+use smpp_pdu::pdu::formats::OctetStringCreationError;
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::{
+    DeliverSmPdu, PduParseError, PduParseErrorBody, SubmitSmPdu,
+};
+use std::io;
+
+use crate::command_id::CommandId;
+use crate::frame_body::{Header, HEADER_LENGTH};
+
+const MAX_LENGTH_SERVICE_TYPE: usize = 6;
+const MAX_LENGTH_ADDR: usize = 21;
+const MAX_LENGTH_TIME: usize = 17;
+const MAX_LENGTH_SHORT_MESSAGE: usize = 254;
+
+/// A submit_sm or deliver_sm, borrowed from its frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SmView<'a> {
+    pub command_id: u32,
+    pub sequence_number: u32,
+    pub service_type: &'a str,
+    pub source_addr_ton: u8,
+    pub source_addr_npi: u8,
+    pub source_addr: &'a str,
+    pub dest_addr_ton: u8,
+    pub dest_addr_npi: u8,
+    pub destination_addr: &'a str,
+    pub esm_class: u8,
+    pub protocol_id: u8,
+    pub priority_flag: u8,
+    pub schedule_delivery_time: &'a str,
+    pub validity_period: &'a str,
+    pub registered_delivery: u8,
+    pub replace_if_present_flag: u8,
+    pub data_coding: u8,
+    pub sm_default_msg_id: u8,
+    pub short_message: &'a [u8],
+    pub tlvs: TlvsView<'a>,
+}
+
+impl<'a> SmView<'a> {
+    /// frame must be one whole submit_sm or deliver_sm, header and all.
+    pub fn parse(frame: &'a [u8]) -> Result<Self, PduParseError> {
+        let expected_command_id = match Header::peek(frame) {
+            Some(header)
+                if header.command_id == CommandId::DeliverSm as u32 =>
+            {
+                CommandId::DeliverSm
+            }
+            _ => CommandId::SubmitSm,
+        };
+        let header = Header::parse(frame, expected_command_id as u32)?;
+        if header.command_status != 0 {
+            return Err(header.error(PduParseError::new(
+                PduParseErrorBody::StatusIsNotZero,
+            )));
+        }
+        let mut fields = Fields {
+            rest: &frame[HEADER_LENGTH..],
+        };
+        Self::parse_body(&header, &mut fields).map_err(|e| header.error(e))
+    }
+
+    fn parse_body(
+        header: &Header,
+        fields: &mut Fields<'a>,
+    ) -> Result<Self, PduParseError> {
+        let service_type =
+            fields.c_octet_string("service_type", MAX_LENGTH_SERVICE_TYPE)?;
+        let source_addr_ton = fields.u8("source_addr_ton")?;
+        let source_addr_npi = fields.u8("source_addr_npi")?;
+        let source_addr =
+            fields.c_octet_string("source_addr", MAX_LENGTH_ADDR)?;
+        let dest_addr_ton = fields.u8("dest_addr_ton")?;
+        let dest_addr_npi = fields.u8("dest_addr_npi")?;
+        let destination_addr =
+            fields.c_octet_string("destination_addr", MAX_LENGTH_ADDR)?;
+        let esm_class = fields.u8("esm_class")?;
+        let protocol_id = fields.u8("protocol_id")?;
+        let priority_flag = fields.u8("priority_flag")?;
+        let schedule_delivery_time =
+            fields.c_octet_string("schedule_delivery_time", MAX_LENGTH_TIME)?;
+        let validity_period =
+            fields.c_octet_string("validity_period", MAX_LENGTH_TIME)?;
+        let registered_delivery = fields.u8("registered_delivery")?;
+        let replace_if_present_flag = fields.u8("replace_if_present_flag")?;
+        let data_coding = fields.u8("data_coding")?;
+        let sm_default_msg_id = fields.u8("sm_default_msg_id")?;
+        let sm_length = fields.u8("sm_length")?;
+        let short_message =
+            fields.octet_string("short_message", sm_length.into())?;
+        let tlvs = TlvsView::parse(fields.rest)?;
+        check_time_length("schedule_delivery_time", schedule_delivery_time)?;
+        check_time_length("validity_period", validity_period)?;
+        Ok(Self {
+            command_id: header.command_id,
+            sequence_number: header.sequence_number,
+            service_type,
+            source_addr_ton,
+            source_addr_npi,
+            source_addr,
+            dest_addr_ton,
+            dest_addr_npi,
+            destination_addr,
+            esm_class,
+            protocol_id,
+            priority_flag,
+            schedule_delivery_time,
+            validity_period,
+            registered_delivery,
+            replace_if_present_flag,
+            data_coding,
+            sm_default_msg_id,
+            short_message,
+            tlvs,
+        })
+    }
+
+    /// An owned copy, whichever command_id the frame had.
+    pub fn to_submit_sm(&self) -> Result<SubmitSmPdu, PduParseError> {
+        SubmitSmPdu::new(
+            self.service_type,
+            self.source_addr_ton,
+            self.source_addr_npi,
+            self.source_addr,
+            self.dest_addr_ton,
+            self.dest_addr_npi,
+            self.destination_addr,
+            self.esm_class,
+            self.protocol_id,
+            self.priority_flag,
+            self.schedule_delivery_time,
+            self.validity_period,
+            self.registered_delivery,
+            self.replace_if_present_flag,
+            self.data_coding,
+            self.sm_default_msg_id,
+            self.short_message,
+            self.tlvs.to_tlvs(),
+        )
+    }
+
+    /// An owned copy, whichever command_id the frame had.
+    pub fn to_deliver_sm(&self) -> Result<DeliverSmPdu, PduParseError> {
+        DeliverSmPdu::new(
+            self.service_type,
+            self.source_addr_ton,
+            self.source_addr_npi,
+            self.source_addr,
+            self.dest_addr_ton,
+            self.dest_addr_npi,
+            self.destination_addr,
+            self.esm_class,
+            self.protocol_id,
+            self.priority_flag,
+            self.schedule_delivery_time,
+            self.validity_period,
+            self.registered_delivery,
+            self.replace_if_present_flag,
+            self.data_coding,
+            self.sm_default_msg_id,
+            self.short_message,
+            self.tlvs.to_tlvs(),
+        )
+    }
+}
+
+/// The TLVs at the end of a body, read as they are iterated.  Their
+/// lengths were checked by SmView::parse().
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TlvsView<'a>(&'a [u8]);
+
+impl<'a> TlvsView<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self, PduParseError> {
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            let length = match rest.get(2..4) {
+                Some(length) => {
+                    usize::from(u16::from_be_bytes([length[0], length[1]]))
+                }
+                None => return Err(unexpected_eof()),
+            };
+            rest = rest.get(4 + length..).ok_or_else(unexpected_eof)?;
+        }
+        Ok(Self(bytes))
+    }
+
+    /// The value of the first TLV with tag, as for Tlvs::get().
+    pub fn get(&self, tag: KnownTlvTag) -> Option<&'a [u8]> {
+        let raw_tag = tag.to_u16()?;
+        self.iter()
+            .find(|(tag, _)| *tag == raw_tag)
+            .map(|(_, value)| value)
+    }
+
+    /// Each raw tag and value, in order
+    pub fn iter(&self) -> TlvsIter<'a> {
+        TlvsIter(self.0)
+    }
+
+    pub fn to_tlvs(&self) -> Tlvs {
+        let tlvs: Vec<Tlv> = self
+            .iter()
+            .map(|(tag, value)| Tlv::new_unknown(tag, value))
+            .collect();
+        Tlvs::from(&tlvs)
+    }
+}
+
+pub struct TlvsIter<'a>(&'a [u8]);
+
+impl<'a> Iterator for TlvsIter<'a> {
+    type Item = (u16, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.len() < 4 {
+            return None;
+        }
+        let tag = u16::from_be_bytes([self.0[0], self.0[1]]);
+        let length = usize::from(u16::from_be_bytes([self.0[2], self.0[3]]));
+        let (value, rest) = self.0[4..].split_at(length);
+        self.0 = rest;
+        Some((tag, value))
+    }
+}
+
+/// What is left of a body, read a field at a time
+struct Fields<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    fn u8(&mut self, name: &str) -> Result<u8, PduParseError> {
+        let (first, rest) = self
+            .rest
+            .split_first()
+            .ok_or_else(|| unexpected_eof().into_with_field_name(name))?;
+        self.rest = rest;
+        Ok(*first)
+    }
+
+    /// As for COctetString::read(): the terminating NULL must come within
+    /// max_length bytes, and what comes before it must be ASCII.
+    fn c_octet_string(
+        &mut self,
+        name: &str,
+        max_length: usize,
+    ) -> Result<&'a str, PduParseError> {
+        let searched = &self.rest[..self.rest.len().min(max_length)];
+        let end = searched.iter().position(|b| *b == 0).ok_or_else(|| {
+            let e = if searched.len() == max_length {
+                OctetStringCreationError::TooLong(max_length)
+            } else {
+                OctetStringCreationError::DoesNotEndWithZeroByte
+            };
+            PduParseError::from(e).into_with_field_name(name)
+        })?;
+        let value = AsciiStr::from_ascii(&self.rest[..end]).map_err(|e| {
+            PduParseError::from(OctetStringCreationError::NotAscii(e))
+                .into_with_field_name(name)
+        })?;
+        self.rest = &self.rest[end + 1..];
+        Ok(value.as_str())
+    }
+
+    fn octet_string(
+        &mut self,
+        name: &str,
+        length: usize,
+    ) -> Result<&'a [u8], PduParseError> {
+        if length > MAX_LENGTH_SHORT_MESSAGE {
+            return Err(PduParseError::from(
+                OctetStringCreationError::TooLong(length),
+            )
+            .into_with_field_name(name));
+        }
+        if self.rest.len() < length {
+            return Err(PduParseError::from(
+                OctetStringCreationError::OtherIoError(eof()),
+            )
+            .into_with_field_name(name));
+        }
+        let (value, rest) = self.rest.split_at(length);
+        self.rest = rest;
+        Ok(value)
+    }
+}
+
+/// The error read_exact() gives, as Pdu::parse() would report it
+fn eof() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer")
+}
+
+fn unexpected_eof() -> PduParseError {
+    PduParseError::from(eof())
+}
+
+/// As SmData checks times: empty, or 16 characters plus the NULL
+fn check_time_length(name: &str, value: &str) -> Result<(), PduParseError> {
+    if value.is_empty() || value.len() == MAX_LENGTH_TIME - 1 {
+        Ok(())
+    } else {
+        Err(PduParseError::new(PduParseErrorBody::IncorrectLength(
+            value.len() as u32,
+            String::from(
+                "Must be either 1 or 17 characters, including the NULL \
+                character.",
+            ),
+        ))
+        .into_with_field_name(name))
+    }
+}
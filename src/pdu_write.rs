@@ -0,0 +1,86 @@
+//! Writing PDUs, including the ones smpp_pdu cannot write itself.
+//!
+//! BindData::write is unimplemented in smpp_pdu, so Pdu::write panics for
+//! bind_receiver, bind_transmitter and bind_transceiver.  write_pdu writes
+//! those from their fields and hands everything else to Pdu::write.  No
+//! other body's write is a stub (generic_nack really has no body).
+//!
+//! Each PDU is encoded in memory first, then written with one write_all(),
+//! so any AsyncWrite will do and the number of bytes written is known.
+//! With the sync-write feature, write_pdu_sync() does the same for a
+//! std::io::Write.
+
+use smpp_pdu::pdu::data::bind_data::BindData;
+use smpp_pdu::pdu::formats::WriteStream;
+use smpp_pdu::pdu::{Pdu, PduBody};
+use std::io;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// command_length, command_id, command_status and sequence_number
+const HEADER_LENGTH: usize = 16;
+
+/// Write pdu, header included, and return its length.  Use this instead
+/// of Pdu::write, which panics for bind PDUs.
+pub async fn write_pdu<W: AsyncWrite + Unpin + ?Sized>(
+    pdu: &Pdu,
+    stream: &mut W,
+) -> io::Result<usize> {
+    let bytes = encode_pdu(pdu).await?;
+    stream.write_all(&bytes).await?;
+    Ok(bytes.len())
+}
+
+/// As write_pdu(), for writers that block.
+#[cfg(feature = "sync-write")]
+pub fn write_pdu_sync<W: io::Write + ?Sized>(
+    pdu: &Pdu,
+    stream: &mut W,
+) -> io::Result<usize> {
+    use futures::FutureExt;
+
+    let bytes = encode_pdu(pdu)
+        .now_or_never()
+        .expect("Writing to a Vec should never wait")?;
+    stream.write_all(&bytes)?;
+    Ok(bytes.len())
+}
+
+async fn encode_pdu(pdu: &Pdu) -> io::Result<Vec<u8>> {
+    let mut bytes: Vec<u8> = Vec::new();
+    let bind_data = match pdu.body() {
+        PduBody::BindReceiver(body) => &body.0,
+        PduBody::BindTransceiver(body) => &body.0,
+        PduBody::BindTransmitter(body) => &body.0,
+        _ => {
+            pdu.write(&mut bytes).await?;
+            return Ok(bytes);
+        }
+    };
+    let mut body: Vec<u8> = Vec::new();
+    write_bind_data(bind_data, &mut body).await?;
+    let command_length = (HEADER_LENGTH + body.len()) as u32;
+    for value in &[
+        command_length,
+        pdu.command_id().value,
+        pdu.command_status.value,
+        pdu.sequence_number.value,
+    ] {
+        bytes.extend_from_slice(&value.to_be_bytes());
+    }
+    bytes.extend_from_slice(&body);
+    Ok(bytes)
+}
+
+/// The body of a bind_receiver, bind_transmitter or bind_transceiver.
+pub async fn write_bind_data(
+    data: &BindData,
+    stream: &mut WriteStream,
+) -> io::Result<()> {
+    data.system_id.write(stream).await?;
+    data.password.write(stream).await?;
+    data.system_type.write(stream).await?;
+    data.interface_version.write(stream).await?;
+    data.addr_ton.write(stream).await?;
+    data.addr_npi.write(stream).await?;
+    data.address_range.write(stream).await
+}
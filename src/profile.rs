@@ -0,0 +1,100 @@
+//! Named bundles of the client's leniency switches, for SMSCs known to
+//! bend the spec, so that callers can say which kind of SMSC they talk to
+//! instead of setting each switch.
+//!
+//! ```no_run
+//! # async fn example() -> std::io::Result<()> {
+//! use smpp::client::Client;
+//! use smpp::profile::Profile;
+//!
+//! let client = Client::connect("127.0.0.1:2775")
+//!     .await?
+//!     .with_profile(Profile::Legacy);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::parse_options::ParseMode;
+use crate::session_info::{SMPP_3_3, SMPP_3_4};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Profile {
+    /// Everything by the spec, as without a profile
+    Standard,
+    /// Sends text fields that are not ASCII, or leaves out their NULL
+    /// terminators, which ParseMode::Lenient repairs
+    LenientAscii,
+    /// Writes delivery receipt dates with seconds, "YYMMDDhhmmss"
+    ReceiptDatesWithSeconds,
+    /// Speaks SMPP 3.3, but accepts TLVs on submit_sm anyway
+    TlvsOnSmpp33,
+    /// An SMPP 3.3 SMSC that does all of the above
+    Legacy,
+}
+
+/// The switches a Profile sets.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Compatibility {
+    pub parse_mode: ParseMode,
+    /// The interface_version to bind with
+    pub interface_version: u8,
+    /// Whether to send TLVs on a session bound as SMPP 3.3, which does not
+    /// have them.  If not, they are left out.
+    pub tlvs_on_smpp_3_3: bool,
+    /// Whether receipt dates may have seconds, which are then dropped so
+    /// that DeliveryReceipt dates are always "YYMMDDhhmm"
+    pub receipt_dates_with_seconds: bool,
+}
+
+impl Default for Compatibility {
+    fn default() -> Self {
+        Profile::Standard.compatibility()
+    }
+}
+
+impl Profile {
+    pub fn compatibility(self) -> Compatibility {
+        let standard = Compatibility {
+            parse_mode: ParseMode::Strict,
+            interface_version: SMPP_3_4,
+            tlvs_on_smpp_3_3: false,
+            receipt_dates_with_seconds: false,
+        };
+        match self {
+            Self::Standard => standard,
+            Self::LenientAscii => Compatibility {
+                parse_mode: ParseMode::Lenient,
+                ..standard
+            },
+            Self::ReceiptDatesWithSeconds => Compatibility {
+                receipt_dates_with_seconds: true,
+                ..standard
+            },
+            Self::TlvsOnSmpp33 => Compatibility {
+                interface_version: SMPP_3_3,
+                tlvs_on_smpp_3_3: true,
+                ..standard
+            },
+            Self::Legacy => Compatibility {
+                parse_mode: ParseMode::Lenient,
+                interface_version: SMPP_3_3,
+                tlvs_on_smpp_3_3: true,
+                receipt_dates_with_seconds: true,
+            },
+        }
+    }
+}
+
+impl Compatibility {
+    /// date as "YYMMDDhhmm", if receipt dates may have seconds and it
+    /// does.
+    pub fn receipt_date(&self, date: String) -> String {
+        let with_seconds =
+            date.len() == 12 && date.bytes().all(|b| b.is_ascii_digit());
+        if self.receipt_dates_with_seconds && with_seconds {
+            String::from(&date[..10])
+        } else {
+            date
+        }
+    }
+}
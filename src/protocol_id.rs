@@ -0,0 +1,149 @@
+//! protocol_id values, which SMPP 3.4 (5.2.13) passes through as the GSM
+//! TP-Protocol-Identifier (3GPP TS 23.040 9.2.3.9).
+
+use std::convert::TryFrom;
+use std::error;
+use std::fmt::{Display, Formatter};
+
+/// A protocol_id.  Anything in a reserved range fails validate(), but the
+/// value is kept so that it can still be shown.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ProtocolId(pub u8);
+
+/// The value is in a range TS 23.040 reserves
+#[derive(Debug, PartialEq)]
+pub struct InvalidProtocolId(pub u8);
+
+impl Display for InvalidProtocolId {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "protocol_id {:#04X} is reserved", self.0)
+    }
+}
+
+impl error::Error for InvalidProtocolId {}
+
+impl ProtocolId {
+    /// A plain SME-to-SME message, what almost every submit_sm sends
+    pub const DEFAULT: Self = Self(0x00);
+    /// Shown to nobody: the handset acknowledges it and throws it away,
+    /// e.g. to check that a phone is reachable
+    pub const SHORT_MESSAGE_TYPE_0: Self = Self(0x40);
+    pub const RETURN_CALL_MESSAGE: Self = Self(0x5F);
+    pub const ANSI_136_R_DATA: Self = Self(0x7C);
+    pub const ME_DATA_DOWNLOAD: Self = Self(0x7D);
+    pub const ME_DEPERSONALIZATION: Self = Self(0x7E);
+    /// For the SIM rather than the user, e.g. an OTA update
+    pub const SIM_DATA_DOWNLOAD: Self = Self(0x7F);
+
+    /// Replaces the last message of the same type n from the same
+    /// source_addr on the handset.  None unless n is from 1 to 7.
+    pub fn replace_short_message_type(n: u8) -> Option<Self> {
+        if (1..=7).contains(&n) {
+            Some(Self(0x40 | n))
+        } else {
+            None
+        }
+    }
+
+    /// For a telematic device such as a fax or email gateway.  None unless
+    /// device_type is from 0 to 31.
+    pub fn telematic_interworking(device_type: u8) -> Option<Self> {
+        if device_type <= 0x1F {
+            Some(Self(0x20 | device_type))
+        } else {
+            None
+        }
+    }
+
+    /// n if this is replace short message type n
+    pub fn replace_type(self) -> Option<u8> {
+        match self.0 {
+            0x41..=0x47 => Some(self.0 & 0x07),
+            _ => None,
+        }
+    }
+
+    /// The telematic device type, if this is telematic interworking
+    pub fn telematic_device_type(self) -> Option<u8> {
+        match self.0 {
+            0x20..=0x3F => Some(self.0 & 0x1F),
+            _ => None,
+        }
+    }
+
+    /// Values from 0xC0 are for SMSCs to define
+    pub fn is_sc_specific(self) -> bool {
+        self.0 >= 0xC0
+    }
+
+    /// Fails if this is in a reserved range.
+    pub fn validate(self) -> Result<(), InvalidProtocolId> {
+        match self.0 {
+            0x48..=0x5E | 0x60..=0x7B | 0x80..=0xBF => {
+                Err(InvalidProtocolId(self.0))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for ProtocolId {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl TryFrom<u8> for ProtocolId {
+    type Error = InvalidProtocolId;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        let protocol_id = Self(value);
+        protocol_id.validate()?;
+        Ok(protocol_id)
+    }
+}
+
+impl From<ProtocolId> for u8 {
+    fn from(protocol_id: ProtocolId) -> u8 {
+        protocol_id.0
+    }
+}
+
+impl Display for ProtocolId {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match *self {
+            Self::DEFAULT => formatter.write_str("default"),
+            Self::SHORT_MESSAGE_TYPE_0 => {
+                formatter.write_str("short message type 0")
+            }
+            Self::RETURN_CALL_MESSAGE => {
+                formatter.write_str("return call message")
+            }
+            Self::ANSI_136_R_DATA => formatter.write_str("ANSI-136 R-DATA"),
+            Self::ME_DATA_DOWNLOAD => formatter.write_str("ME data download"),
+            Self::ME_DEPERSONALIZATION => {
+                formatter.write_str("ME de-personalization")
+            }
+            Self::SIM_DATA_DOWNLOAD => formatter.write_str("SIM data download"),
+            protocol_id => {
+                if let Some(n) = protocol_id.replace_type() {
+                    write!(formatter, "replace short message type {}", n)
+                } else if let Some(device_type) =
+                    protocol_id.telematic_device_type()
+                {
+                    write!(
+                        formatter,
+                        "telematic interworking ({:#04X})",
+                        device_type
+                    )
+                } else if protocol_id.is_sc_specific() {
+                    write!(formatter, "SC specific ({:#04X})", protocol_id.0)
+                } else if protocol_id.validate().is_err() {
+                    write!(formatter, "reserved ({:#04X})", protocol_id.0)
+                } else {
+                    write!(formatter, "SME-to-SME ({:#04X})", protocol_id.0)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! query_sm and query_sm_resp, with which an ESME asks after the state of a
+//! message it submitted earlier.
+//!
+//! Like data_sm, these are missing from smpp_pdu, so they are standalone
+//! types that read and write a whole frame, header included.
+
+use smpp_pdu::pdu::formats::{COctetString, WriteStream};
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody};
+use std::convert::TryFrom;
+use std::io::{self, Cursor};
+
+use crate::command_id::CommandId;
+use crate::frame_body::{
+    c_octet_string, c_octet_string_at, command_id, pdu_fields, read_bytes,
+    time, time_at, write_frame, Header, HEADER_LENGTH,
+};
+
+pub const QUERY_SM: u32 = CommandId::QuerySm as u32;
+pub const QUERY_SM_RESP: u32 = CommandId::QuerySmResp as u32;
+
+const MAX_LENGTH_MESSAGE_ID: usize = 65;
+const MAX_LENGTH_SOURCE_ADDR: usize = 21;
+
+/// The message_state of a query_sm_resp, per section 5.2.28 of the spec.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MessageState {
+    Enroute = 1,
+    Delivered = 2,
+    Expired = 3,
+    Deleted = 4,
+    Undeliverable = 5,
+    Accepted = 6,
+    Unknown = 7,
+    Rejected = 8,
+}
+
+impl TryFrom<u8> for MessageState {
+    type Error = u8;
+
+    /// The value itself is the error if it is not a known state.
+    fn try_from(value: u8) -> Result<Self, u8> {
+        Ok(match value {
+            1 => Self::Enroute,
+            2 => Self::Delivered,
+            3 => Self::Expired,
+            4 => Self::Deleted,
+            5 => Self::Undeliverable,
+            6 => Self::Accepted,
+            7 => Self::Unknown,
+            8 => Self::Rejected,
+            _ => return Err(value),
+        })
+    }
+}
+
+const STATS: [(MessageState, &str); 8] = [
+    (MessageState::Enroute, "ENROUTE"),
+    (MessageState::Delivered, "DELIVRD"),
+    (MessageState::Expired, "EXPIRED"),
+    (MessageState::Deleted, "DELETED"),
+    (MessageState::Undeliverable, "UNDELIV"),
+    (MessageState::Accepted, "ACCEPTD"),
+    (MessageState::Unknown, "UNKNOWN"),
+    (MessageState::Rejected, "REJECTD"),
+];
+
+impl MessageState {
+    /// How a delivery receipt's stat: field writes this state, e.g.
+    /// "DELIVRD"
+    pub fn stat(self) -> &'static str {
+        STATS
+            .iter()
+            .find(|(state, _)| *state == self)
+            .map(|(_, stat)| *stat)
+            .expect("Every MessageState has a stat")
+    }
+
+    /// The state a stat: field names, in any case.
+    pub fn from_stat(stat: &str) -> Option<Self> {
+        STATS
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(stat))
+            .map(|(state, _)| *state)
+    }
+}
+
+pdu_fields! {
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct QuerySmPdu {
+        pub message_id: COctetString(MAX_LENGTH_MESSAGE_ID),
+        pub source_addr_ton: u8,
+        pub source_addr_npi: u8,
+        /// Must match the source_addr the message was submitted with
+        pub source_addr: COctetString(MAX_LENGTH_SOURCE_ADDR),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuerySmRespPdu {
+    pub command_status: u32,
+    pub sequence_number: u32,
+    pub message_id: COctetString,
+    /// When the message reached a final state, as an absolute time, or
+    /// empty if it has not
+    pub final_date: COctetString,
+    pub message_state: u8,
+    /// A network-specific error code for why delivery failed, or 0
+    pub error_code: u8,
+}
+
+impl QuerySmPdu {
+    /// A query for message_id, submitted from source_addr with TON and NPI
+    /// 0.  Set those directly if they matter.
+    pub fn new(
+        sequence_number: u32,
+        message_id: &str,
+        source_addr: &str,
+    ) -> Result<Self, PduParseError> {
+        Ok(Self {
+            sequence_number,
+            message_id: c_octet_string(
+                "message_id",
+                message_id,
+                MAX_LENGTH_MESSAGE_ID,
+            )?,
+            source_addr_ton: 0,
+            source_addr_npi: 0,
+            source_addr: c_octet_string(
+                "source_addr",
+                source_addr,
+                MAX_LENGTH_SOURCE_ADDR,
+            )?,
+        })
+    }
+
+    /// Does this complete frame (as accepted by Pdu::check) hold a
+    /// query_sm?
+    pub fn is_query_sm(frame: &[u8]) -> bool {
+        command_id(frame) == Some(QUERY_SM)
+    }
+
+    pub fn parse(frame: &[u8]) -> Result<Self, PduParseError> {
+        let header = Header::parse(frame, QUERY_SM)?;
+        if header.command_status != 0 {
+            return Err(header.error(PduParseError::new(
+                PduParseErrorBody::StatusIsNotZero,
+            )));
+        }
+        Self::read_body(&header, &mut Cursor::new(&frame[HEADER_LENGTH..]))
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        let body = self.write_body().await?;
+        write_frame(stream, QUERY_SM, 0, self.sequence_number, &body).await
+    }
+}
+
+impl QuerySmRespPdu {
+    /// The answer to the query_sm with sequence_number.  final_date is
+    /// empty, or an absolute time in the 16-character SMPP format.
+    pub fn new(
+        sequence_number: u32,
+        message_id: &str,
+        final_date: &str,
+        message_state: MessageState,
+        error_code: u8,
+    ) -> Result<Self, PduParseError> {
+        Ok(Self {
+            command_status: 0,
+            sequence_number,
+            message_id: c_octet_string(
+                "message_id",
+                message_id,
+                MAX_LENGTH_MESSAGE_ID,
+            )?,
+            final_date: time("final_date", final_date)?,
+            message_state: message_state as u8,
+            error_code,
+        })
+    }
+
+    /// A rejection of the query_sm with sequence_number, e.g. with
+    /// ESME_RQUERYFAIL when the message is not known.
+    pub fn new_error(command_status: u32, sequence_number: u32) -> Self {
+        Self {
+            command_status,
+            sequence_number,
+            message_id: COctetString::new(),
+            final_date: COctetString::new(),
+            message_state: 0,
+            error_code: 0,
+        }
+    }
+
+    pub fn is_query_sm_resp(frame: &[u8]) -> bool {
+        command_id(frame) == Some(QUERY_SM_RESP)
+    }
+
+    /// message_state as a MessageState, or the raw value if it is not one.
+    pub fn message_state(&self) -> Result<MessageState, u8> {
+        MessageState::try_from(self.message_state)
+    }
+
+    /// Parse one complete frame.  A rejection may leave the body out
+    /// entirely, which is read as empty fields.
+    pub fn parse(frame: &[u8]) -> Result<Self, PduParseError> {
+        let header = Header::parse(frame, QUERY_SM_RESP)?;
+        if frame.len() == HEADER_LENGTH && header.command_status != 0 {
+            return Ok(Self::new_error(
+                header.command_status,
+                header.sequence_number,
+            ));
+        }
+        let mut body = Cursor::new(&frame[HEADER_LENGTH..]);
+        let message_id = c_octet_string_at(
+            &mut body,
+            &header,
+            "message_id",
+            MAX_LENGTH_MESSAGE_ID,
+        )?;
+        let final_date = time_at(&mut body, &header, "final_date")?;
+        let [message_state, error_code] =
+            read_bytes(&mut body, &header, "error_code")?;
+        Ok(Self {
+            command_status: header.command_status,
+            sequence_number: header.sequence_number,
+            message_id,
+            final_date,
+            message_state,
+            error_code,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        let mut body: Vec<u8> = Vec::new();
+        self.message_id.write(&mut body).await?;
+        self.final_date.write(&mut body).await?;
+        body.extend([self.message_state, self.error_code]);
+        write_frame(
+            stream,
+            QUERY_SM_RESP,
+            self.command_status,
+            self.sequence_number,
+            &body,
+        )
+        .await
+    }
+}
@@ -0,0 +1,128 @@
+//! Keeping message content out of logs.
+//!
+//! Build with the `redact-message-content` feature to guarantee that
+//! short_message and TLV values never appear in anything this crate logs.
+//! Without the feature, [`Redacted`] prints exactly what `{:?}` on the
+//! underlying [`Pdu`] would.
+//!
+//! Raw frames, as kept by session captures and logged when they fail to
+//! parse, go through [`frame_bytes`], and loose TLVs through
+//! [`RedactedTlvs`].
+
+use smpp_pdu::pdu::data::sm_data::SmData;
+use smpp_pdu::pdu::tlvs::Tlv;
+use smpp_pdu::pdu::{Pdu, PduBody};
+use std::fmt::{Debug, Formatter};
+
+use crate::frame_body::HEADER_LENGTH;
+
+/// The part of a raw frame that may be logged or kept: all of it, or only
+/// the header when the `redact-message-content` feature is enabled.
+pub fn frame_bytes(frame: &[u8]) -> &[u8] {
+    if cfg!(feature = "redact-message-content") {
+        &frame[..frame.len().min(HEADER_LENGTH)]
+    } else {
+        frame
+    }
+}
+
+/// Wrap a PDU before logging it, so that its message content is removed
+/// when the `redact-message-content` feature is enabled.
+pub struct Redacted<'a>(pub &'a Pdu);
+
+impl Debug for Redacted<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        if !cfg!(feature = "redact-message-content") {
+            return self.0.fmt(formatter);
+        }
+
+        let body: &dyn Debug = match self.0.body() {
+            PduBody::DeliverSm(body) => {
+                &RedactedBody("DeliverSm", RedactedSmData(&body.0))
+            }
+            PduBody::SubmitSm(body) => {
+                &RedactedBody("SubmitSm", RedactedSmData(&body.0))
+            }
+            body => body,
+        };
+
+        formatter
+            .debug_struct("Pdu")
+            .field("command_status", &self.0.command_status)
+            .field("sequence_number", &self.0.sequence_number)
+            .field("body", body)
+            .finish()
+    }
+}
+
+struct RedactedBody<'a>(&'static str, RedactedSmData<'a>);
+
+impl Debug for RedactedBody<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.debug_tuple(self.0).field(&self.1).finish()
+    }
+}
+
+struct RedactedSmData<'a>(&'a SmData);
+
+impl Debug for RedactedSmData<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        let sm = self.0;
+        formatter
+            .debug_struct("SmData")
+            .field("service_type", &sm.service_type)
+            .field("source_addr_ton", &sm.source_addr_ton)
+            .field("source_addr_npi", &sm.source_addr_npi)
+            .field("source_addr", &sm.source_addr)
+            .field("dest_addr_ton", &sm.dest_addr_ton)
+            .field("dest_addr_npi", &sm.dest_addr_npi)
+            .field("destination_addr", &sm.destination_addr)
+            .field("esm_class", &sm.esm_class)
+            .field("protocol_id", &sm.protocol_id)
+            .field("priority_flag", &sm.priority_flag)
+            .field("schedule_delivery_time", &sm.schedule_delivery_time)
+            .field("validity_period", &sm.validity_period)
+            .field("registered_delivery", &sm.registered_delivery)
+            .field("replace_if_present_flag", &sm.replace_if_present_flag)
+            .field("data_coding", &sm.data_coding)
+            .field("sm_default_msg_id", &sm.sm_default_msg_id)
+            .field(
+                "short_message",
+                &format_args!("<{} bytes redacted>", sm.short_message.len()),
+            )
+            // TLVs may carry message_payload, so they are dropped entirely
+            .field("tlvs", &format_args!("<redacted>"))
+            .finish()
+    }
+}
+
+/// Wrap TLVs before logging them, so that their values are removed when
+/// the `redact-message-content` feature is enabled.
+pub struct RedactedTlvs<'a>(pub &'a [Tlv]);
+
+impl Debug for RedactedTlvs<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        if !cfg!(feature = "redact-message-content") {
+            return self.0.fmt(formatter);
+        }
+        formatter
+            .debug_list()
+            .entries(self.0.iter().map(RedactedTlv))
+            .finish()
+    }
+}
+
+struct RedactedTlv<'a>(&'a Tlv);
+
+impl Debug for RedactedTlv<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter
+            .debug_struct("Tlv")
+            .field("raw_tag", &self.0.raw_tag)
+            .field(
+                "value",
+                &format_args!("<{} bytes redacted>", self.0.value.len()),
+            )
+            .finish()
+    }
+}
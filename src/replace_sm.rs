@@ -0,0 +1,247 @@
+//! replace_sm and replace_sm_resp, with which an ESME changes the content
+//! or schedule of a message it submitted earlier that has yet to be
+//! delivered.
+//!
+//! Like query_sm, these are missing from smpp_pdu, so they are standalone
+//! types that read and write a whole frame, header included.
+
+use smpp_pdu::pdu::formats::{COctetString, WriteStream};
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody};
+use std::fmt::{Debug, Formatter};
+use std::io::{self, Cursor, Read};
+
+use crate::command_id::CommandId;
+use crate::frame_body::{
+    c_octet_string, c_octet_string_at, command_id, read_bytes, time, time_at,
+    write_frame, Header, HEADER_LENGTH,
+};
+
+pub const REPLACE_SM: u32 = CommandId::ReplaceSm as u32;
+pub const REPLACE_SM_RESP: u32 = CommandId::ReplaceSmResp as u32;
+
+const MAX_LENGTH_MESSAGE_ID: usize = 65;
+const MAX_LENGTH_SOURCE_ADDR: usize = 21;
+const MAX_LENGTH_SHORT_MESSAGE: usize = 254;
+
+#[derive(Clone, PartialEq)]
+pub struct ReplaceSmPdu {
+    pub sequence_number: u32,
+    pub message_id: COctetString,
+    pub source_addr_ton: u8,
+    pub source_addr_npi: u8,
+    /// Must match the source_addr the message was submitted with
+    pub source_addr: COctetString,
+    /// Empty to leave unchanged
+    pub schedule_delivery_time: COctetString,
+    /// Empty to leave unchanged
+    pub validity_period: COctetString,
+    pub registered_delivery: u8,
+    pub sm_default_msg_id: u8,
+    /// Written with sm_length in front, so at most 254 bytes
+    pub short_message: Vec<u8>,
+}
+
+/// Just a header: whether the replace worked is in command_status.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplaceSmRespPdu {
+    pub command_status: u32,
+    pub sequence_number: u32,
+}
+
+impl ReplaceSmPdu {
+    /// Replace the content of message_id, submitted from source_addr with
+    /// TON and NPI 0, leaving its schedule alone.  Set other fields
+    /// directly if they matter.
+    pub fn new(
+        sequence_number: u32,
+        message_id: &str,
+        source_addr: &str,
+        short_message: &[u8],
+    ) -> Result<Self, PduParseError> {
+        let pdu = Self {
+            sequence_number,
+            message_id: c_octet_string(
+                "message_id",
+                message_id,
+                MAX_LENGTH_MESSAGE_ID,
+            )?,
+            source_addr_ton: 0,
+            source_addr_npi: 0,
+            source_addr: c_octet_string(
+                "source_addr",
+                source_addr,
+                MAX_LENGTH_SOURCE_ADDR,
+            )?,
+            schedule_delivery_time: COctetString::new(),
+            validity_period: COctetString::new(),
+            registered_delivery: 0,
+            sm_default_msg_id: 0,
+            short_message: Vec::from(short_message),
+        };
+        pdu.validate()?;
+        Ok(pdu)
+    }
+
+    /// A copy with a new schedule_delivery_time and validity_period, each
+    /// empty or in the 16-character SMPP time format.
+    pub fn with_schedule(
+        self,
+        schedule_delivery_time: &str,
+        validity_period: &str,
+    ) -> Result<Self, PduParseError> {
+        Ok(Self {
+            schedule_delivery_time: time(
+                "schedule_delivery_time",
+                schedule_delivery_time,
+            )?,
+            validity_period: time("validity_period", validity_period)?,
+            ..self
+        })
+    }
+
+    /// Does this complete frame (as accepted by Pdu::check) hold a
+    /// replace_sm?
+    pub fn is_replace_sm(frame: &[u8]) -> bool {
+        command_id(frame) == Some(REPLACE_SM)
+    }
+
+    /// Does this name a message, with a short_message that fits?
+    pub fn validate(&self) -> Result<(), PduParseError> {
+        if self.message_id.value.is_empty() {
+            return Err(PduParseError::new(
+                PduParseErrorBody::IncorrectLength(
+                    0,
+                    String::from("name the message to replace"),
+                ),
+            )
+            .into_with_field_name("message_id"));
+        }
+        if self.short_message.len() > MAX_LENGTH_SHORT_MESSAGE {
+            return Err(PduParseError::new(PduParseErrorBody::LengthTooLong(
+                self.short_message.len() as u32,
+            ))
+            .into_with_field_name("short_message"));
+        }
+        Ok(())
+    }
+
+    pub fn parse(frame: &[u8]) -> Result<Self, PduParseError> {
+        let header = Header::parse(frame, REPLACE_SM)?;
+        if header.command_status != 0 {
+            return Err(header.error(PduParseError::new(
+                PduParseErrorBody::StatusIsNotZero,
+            )));
+        }
+        let mut body = Cursor::new(&frame[HEADER_LENGTH..]);
+        let message_id = c_octet_string_at(
+            &mut body,
+            &header,
+            "message_id",
+            MAX_LENGTH_MESSAGE_ID,
+        )?;
+        let [source_addr_ton, source_addr_npi] =
+            read_bytes(&mut body, &header, "source_addr_npi")?;
+        let source_addr = c_octet_string_at(
+            &mut body,
+            &header,
+            "source_addr",
+            MAX_LENGTH_SOURCE_ADDR,
+        )?;
+        let schedule_delivery_time =
+            time_at(&mut body, &header, "schedule_delivery_time")?;
+        let validity_period = time_at(&mut body, &header, "validity_period")?;
+        let [registered_delivery, sm_default_msg_id, sm_length] =
+            read_bytes(&mut body, &header, "sm_length")?;
+        let mut short_message = vec![0; sm_length as usize];
+        body.read_exact(&mut short_message).map_err(|e| {
+            header.error(
+                PduParseError::from(e).into_with_field_name("short_message"),
+            )
+        })?;
+        let pdu = Self {
+            sequence_number: header.sequence_number,
+            message_id,
+            source_addr_ton,
+            source_addr_npi,
+            source_addr,
+            schedule_delivery_time,
+            validity_period,
+            registered_delivery,
+            sm_default_msg_id,
+            short_message,
+        };
+        pdu.validate().map_err(|e| header.error(e))?;
+        Ok(pdu)
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        let mut body: Vec<u8> = Vec::new();
+        self.message_id.write(&mut body).await?;
+        body.extend([self.source_addr_ton, self.source_addr_npi]);
+        self.source_addr.write(&mut body).await?;
+        self.schedule_delivery_time.write(&mut body).await?;
+        self.validity_period.write(&mut body).await?;
+        body.extend([
+            self.registered_delivery,
+            self.sm_default_msg_id,
+            self.short_message.len() as u8,
+        ]);
+        body.extend(&self.short_message);
+        write_frame(stream, REPLACE_SM, 0, self.sequence_number, &body).await
+    }
+}
+
+/// short_message is left out when building with the
+/// redact-message-content feature, like in crate::redact.
+impl Debug for ReplaceSmPdu {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        let short_message: &dyn Debug =
+            if cfg!(feature = "redact-message-content") {
+                &format_args!("<redacted>")
+            } else {
+                &self.short_message
+            };
+        formatter
+            .debug_struct("ReplaceSmPdu")
+            .field("sequence_number", &self.sequence_number)
+            .field("message_id", &self.message_id)
+            .field("source_addr_ton", &self.source_addr_ton)
+            .field("source_addr_npi", &self.source_addr_npi)
+            .field("source_addr", &self.source_addr)
+            .field("schedule_delivery_time", &self.schedule_delivery_time)
+            .field("validity_period", &self.validity_period)
+            .field("registered_delivery", &self.registered_delivery)
+            .field("sm_default_msg_id", &self.sm_default_msg_id)
+            .field("short_message", short_message)
+            .finish()
+    }
+}
+
+impl ReplaceSmRespPdu {
+    pub fn new(command_status: u32, sequence_number: u32) -> Self {
+        Self {
+            command_status,
+            sequence_number,
+        }
+    }
+
+    pub fn is_replace_sm_resp(frame: &[u8]) -> bool {
+        command_id(frame) == Some(REPLACE_SM_RESP)
+    }
+
+    pub fn parse(frame: &[u8]) -> Result<Self, PduParseError> {
+        let header = Header::parse(frame, REPLACE_SM_RESP)?;
+        Ok(Self::new(header.command_status, header.sequence_number))
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        write_frame(
+            stream,
+            REPLACE_SM_RESP,
+            self.command_status,
+            self.sequence_number,
+            &[],
+        )
+        .await
+    }
+}
@@ -0,0 +1,468 @@
+//! Sending messages at a time of the caller's choosing, e.g. a campaign
+//! that should reach handsets at 9am.  Messages are held until their send
+//! time, then submitted through a Client, no faster than a given rate.
+//!
+//! ```no_run
+//! # use smpp::async_result::AsyncResult;
+//! # use smpp::client::Client;
+//! # use smpp::scheduler::Scheduler;
+//! # use smpp::submit_sm_builder::SubmitSmBuilder;
+//! # use std::sync::Arc;
+//! # use std::time::SystemTime;
+//! # async fn example(client: Client) -> AsyncResult<()> {
+//! # let nine_am = SystemTime::now();
+//! # let submit_sm = SubmitSmBuilder::new().short_message(b"hi").build()?;
+//! let scheduler = Scheduler::new();
+//! scheduler.schedule(nine_am, submit_sm)?;
+//! let mut releases = scheduler.start(Arc::new(client), Some(50));
+//! while let Some(released) = releases.next().await {
+//!     println!("{}: {:?}", released.id, released.result);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A message that could not reach the SMSC, because the connection failed
+//! or it did not answer, waits RETRY_UNSENT_AFTER and is sent again.
+//!
+//! With a ScheduleSpool, messages not yet sent survive a restart.  The
+//! spool feature adds DirSpool, which keeps them in a directory.
+//!
+//! With SendingWindows, messages whose send time falls outside their
+//! country's sending hours are either refused or moved to when the window
+//! next opens; see OutsideWindow.
+
+use log::*;
+use smpp_pdu::pdu::SubmitSmPdu;
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{mpsc, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, sleep_until, Instant};
+
+use crate::client::{Client, ClientError, SubmitSmResp};
+use crate::pdu_accessors::SmAccessors;
+use crate::pdu_clone::PduClone;
+use crate::sending_window::SendingWindows;
+
+/// How long to wait before sending again a message that did not reach the
+/// SMSC
+pub const RETRY_UNSENT_AFTER: Duration = Duration::from_secs(10);
+
+/// A message waiting for its send time
+#[derive(Debug, PartialEq)]
+pub struct Scheduled {
+    pub id: u64,
+    pub send_at: SystemTime,
+    pub submit_sm: SubmitSmPdu,
+}
+
+/// Keeps scheduled messages somewhere that outlives the process.  Register
+/// with Scheduler::with_spool().
+pub trait ScheduleSpool {
+    fn save(&self, scheduled: &Scheduled) -> io::Result<()>;
+    /// Called once the SMSC has answered the message, or it is cancelled.
+    fn remove(&self, id: u64) -> io::Result<()>;
+    /// Everything saved and not removed
+    fn load(&self) -> io::Result<Vec<Scheduled>>;
+}
+
+/// What schedule() does with a message whose send time is outside its
+/// destination's SendingWindow
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutsideWindow {
+    /// Refuse it with ScheduleError::OutsideSendingWindow
+    Reject,
+    /// Send it when the window next opens instead
+    Defer,
+}
+
+#[derive(Debug)]
+pub enum ScheduleError {
+    /// The ScheduleSpool could not save the message
+    Spool(io::Error),
+    /// The send time is outside the destination's sending window, which
+    /// next opens at next_allowed
+    OutsideSendingWindow { next_allowed: SystemTime },
+}
+
+impl Display for ScheduleError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Self::Spool(e) => e.fmt(formatter),
+            Self::OutsideSendingWindow { next_allowed } => write!(
+                formatter,
+                "Outside the destination's sending window, which next opens \
+                {}s after the Unix epoch",
+                next_allowed
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+            ),
+        }
+    }
+}
+
+impl error::Error for ScheduleError {}
+
+impl From<io::Error> for ScheduleError {
+    fn from(e: io::Error) -> Self {
+        Self::Spool(e)
+    }
+}
+
+/// A message submitted at its send time, and answered by the SMSC
+#[derive(Debug)]
+pub struct Released {
+    pub id: u64,
+    pub send_at: SystemTime,
+    pub result: Result<SubmitSmResp, ClientError>,
+}
+
+/// Messages waiting for their send time.  Nothing is sent until start().
+pub struct Scheduler {
+    queue: Arc<Mutex<Queue>>,
+    changed: Arc<Notify>,
+    spool: Option<Arc<dyn ScheduleSpool + Send + Sync>>,
+    windows: SendingWindows,
+    outside_window: OutsideWindow,
+}
+
+#[derive(Default)]
+struct Queue {
+    next_id: u64,
+    /// Soonest first, then in the order they were scheduled
+    waiting: BTreeMap<(SystemTime, u64), SubmitSmPdu>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            queue: Arc::new(Mutex::new(Queue {
+                next_id: 1,
+                waiting: BTreeMap::new(),
+            })),
+            changed: Arc::new(Notify::new()),
+            spool: None,
+            windows: SendingWindows::default(),
+            outside_window: OutsideWindow::Defer,
+        }
+    }
+
+    /// A scheduler that saves each message to spool until it is sent,
+    /// starting with whatever spool already holds.
+    pub fn with_spool(
+        spool: Arc<dyn ScheduleSpool + Send + Sync>,
+    ) -> io::Result<Self> {
+        let mut queue = Queue::default();
+        for scheduled in spool.load()? {
+            queue.next_id = queue.next_id.max(scheduled.id);
+            queue
+                .waiting
+                .insert((scheduled.send_at, scheduled.id), scheduled.submit_sm);
+        }
+        queue.next_id += 1;
+        Ok(Self {
+            queue: Arc::new(Mutex::new(queue)),
+            changed: Arc::new(Notify::new()),
+            spool: Some(spool),
+            windows: SendingWindows::default(),
+            outside_window: OutsideWindow::Defer,
+        })
+    }
+
+    /// Check each message scheduled from now on against windows, doing
+    /// outside_window with those whose send time falls outside them.
+    /// Messages already waiting, including any loaded from a spool, are
+    /// left as they are.
+    pub fn with_sending_windows(
+        mut self,
+        windows: SendingWindows,
+        outside_window: OutsideWindow,
+    ) -> Self {
+        self.windows = windows;
+        self.outside_window = outside_window;
+        self
+    }
+
+    /// Hold submit_sm until send_at, or send it as soon as possible if that
+    /// has passed.  Returns an id for cancel().
+    pub fn schedule(
+        &self,
+        send_at: SystemTime,
+        submit_sm: SubmitSmPdu,
+    ) -> Result<u64, ScheduleError> {
+        // A send time already passed means now, so check now instead
+        let due = send_at.max(SystemTime::now());
+        let next_allowed = self
+            .windows
+            .next_allowed(SmAccessors::destination_addr(&submit_sm), due);
+        let send_at = if next_allowed == due {
+            send_at
+        } else if self.outside_window == OutsideWindow::Defer {
+            next_allowed
+        } else {
+            return Err(ScheduleError::OutsideSendingWindow { next_allowed });
+        };
+        let mut queue = self.queue.lock().unwrap();
+        let id = queue.next_id;
+        if let Some(spool) = &self.spool {
+            spool.save(&Scheduled {
+                id,
+                send_at,
+                submit_sm: submit_sm.pdu_clone(),
+            })?;
+        }
+        queue.next_id += 1;
+        queue.waiting.insert((send_at, id), submit_sm);
+        self.changed.notify_one();
+        Ok(id)
+    }
+
+    /// Stop id being sent.  False if it has already been, or never was.
+    pub fn cancel(&self, id: u64) -> io::Result<bool> {
+        let mut queue = self.queue.lock().unwrap();
+        let key = queue.waiting.keys().find(|(_, i)| *i == id).copied();
+        let key = match key {
+            Some(key) => key,
+            None => return Ok(false),
+        };
+        if let Some(spool) = &self.spool {
+            spool.remove(id)?;
+        }
+        queue.waiting.remove(&key);
+        self.changed.notify_one();
+        Ok(true)
+    }
+
+    /// Each waiting message's id and send time, soonest first
+    pub fn waiting(&self) -> Vec<(u64, SystemTime)> {
+        let queue = self.queue.lock().unwrap();
+        queue
+            .waiting
+            .keys()
+            .map(|(send_at, id)| (*id, *send_at))
+            .collect()
+    }
+
+    /// Submit each message through client when its send time comes, at
+    /// most max_per_sec a second.  Messages are still accepted by
+    /// schedule() while this runs.  Dropping the Releases stops it.
+    pub fn start(
+        &self,
+        client: Arc<Client>,
+        max_per_sec: Option<u32>,
+    ) -> Releases {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let task = tokio::spawn(release_loop(
+            Arc::clone(&self.queue),
+            Arc::clone(&self.changed),
+            self.spool.clone(),
+            client,
+            max_per_sec.map(|n| Duration::from_secs(1) / n.max(1)),
+            sender,
+        ));
+        Releases { receiver, task }
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What happened to each message, as it is sent
+pub struct Releases {
+    receiver: mpsc::UnboundedReceiver<Released>,
+    task: JoinHandle<()>,
+}
+
+impl Releases {
+    pub async fn next(&mut self) -> Option<Released> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for Releases {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn release_loop(
+    queue: Arc<Mutex<Queue>>,
+    changed: Arc<Notify>,
+    spool: Option<Arc<dyn ScheduleSpool + Send + Sync>>,
+    client: Arc<Client>,
+    min_interval: Option<Duration>,
+    released: mpsc::UnboundedSender<Released>,
+) {
+    let mut next_allowed = Instant::now();
+    loop {
+        let soonest = queue.lock().unwrap().waiting.keys().next().copied();
+        let (send_at, id) = match soonest {
+            Some(key) => key,
+            None => {
+                changed.notified().await;
+                continue;
+            }
+        };
+        if let Ok(wait) = send_at.duration_since(SystemTime::now()) {
+            // Something sooner may be scheduled while we wait
+            tokio::select! {
+                _ = sleep(wait) => {}
+                _ = changed.notified() => continue,
+            }
+        }
+        if let Some(min_interval) = min_interval {
+            sleep_until(next_allowed).await;
+            next_allowed = Instant::now().max(next_allowed) + min_interval;
+        }
+        // It may have been cancelled while we waited
+        let submit_sm =
+            match queue.lock().unwrap().waiting.remove(&(send_at, id)) {
+                Some(submit_sm) => submit_sm,
+                None => continue,
+            };
+        let result = client.submit_sm(submit_sm.pdu_clone()).await;
+        if let Err(e @ ClientError::Timeout)
+        | Err(e @ ClientError::Closed)
+        | Err(e @ ClientError::Io(_)) = &result
+        {
+            warn!(
+                "Could not send {}, retrying in {:?}: {}",
+                id, RETRY_UNSENT_AFTER, e
+            );
+            let retry_at = SystemTime::now() + RETRY_UNSENT_AFTER;
+            queue
+                .lock()
+                .unwrap()
+                .waiting
+                .insert((retry_at, id), submit_sm);
+            continue;
+        }
+        if let Some(spool) = &spool {
+            if let Err(e) = spool.remove(id) {
+                warn!("Could not remove {} from the spool: {}", id, e);
+            }
+        }
+        // Nobody may be listening, which is fine
+        let _ = released.send(Released {
+            id,
+            send_at,
+            result,
+        });
+    }
+}
+
+#[cfg(feature = "spool")]
+pub use dir_spool::DirSpool;
+
+#[cfg(feature = "spool")]
+mod dir_spool {
+    use futures::FutureExt;
+    use smpp_pdu::pdu::Pdu;
+    use std::convert::TryInto;
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{ScheduleSpool, Scheduled};
+    use crate::pdu_clone::PduClone;
+    use crate::pdu_view::SmView;
+    use crate::pdu_write::write_pdu;
+
+    const EXTENSION: &str = "scheduled";
+
+    /// One file per message in a directory: its send time in milliseconds
+    /// since the Unix epoch, as 8 big-endian bytes, then the submit_sm as
+    /// it would be sent.
+    pub struct DirSpool {
+        dir: PathBuf,
+    }
+
+    impl DirSpool {
+        /// Creates dir if it does not exist.
+        pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+            let dir = dir.into();
+            fs::create_dir_all(&dir)?;
+            Ok(Self { dir })
+        }
+
+        fn path(&self, id: u64) -> PathBuf {
+            self.dir.join(format!("{}.{}", id, EXTENSION))
+        }
+    }
+
+    impl ScheduleSpool for DirSpool {
+        fn save(&self, scheduled: &Scheduled) -> io::Result<()> {
+            let millis = scheduled
+                .send_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64;
+            let mut bytes = millis.to_be_bytes().to_vec();
+            let pdu = Pdu::new(0, 1, scheduled.submit_sm.pdu_clone().into())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            write_pdu(&pdu, &mut bytes)
+                .now_or_never()
+                .expect("Writing to a Vec should never wait")?;
+            // Write then rename, so that a crash never leaves half a file
+            let temp = self.path(scheduled.id).with_extension("tmp");
+            fs::write(&temp, bytes)?;
+            fs::rename(temp, self.path(scheduled.id))
+        }
+
+        fn remove(&self, id: u64) -> io::Result<()> {
+            match fs::remove_file(self.path(id)) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+                result => result,
+            }
+        }
+
+        fn load(&self) -> io::Result<Vec<Scheduled>> {
+            let mut ret = Vec::new();
+            for entry in fs::read_dir(&self.dir)? {
+                let path = entry?.path();
+                if path.extension().map_or(true, |e| e != EXTENSION) {
+                    continue;
+                }
+                let id = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str()?.parse().ok());
+                let bytes = fs::read(&path)?;
+                let invalid = || {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "{} is not a scheduled message",
+                            path.display()
+                        ),
+                    )
+                };
+                let id = id.ok_or_else(invalid)?;
+                if bytes.len() < 8 {
+                    return Err(invalid());
+                }
+                let (millis, frame) = bytes.split_at(8);
+                let millis = u64::from_be_bytes(millis.try_into().unwrap());
+                let submit_sm = SmView::parse(frame)
+                    .and_then(|view| view.to_submit_sm())
+                    .map_err(|_| invalid())?;
+                ret.push(Scheduled {
+                    id,
+                    send_at: UNIX_EPOCH + Duration::from_millis(millis),
+                    submit_sm,
+                });
+            }
+            ret.sort_by_key(|scheduled| scheduled.id);
+            Ok(ret)
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Who a message is from, and the source_addr_ton and source_addr_npi that
+//! go with each kind of sender, so that callers do not need to remember
+//! them.
+
+use smpp_pdu::pdu::SubmitSmPdu;
+use std::error;
+use std::fmt::{Display, Formatter};
+
+use crate::async_result::AsyncResult;
+use crate::msisdn::{is_international, is_short_code, normalize_msisdn};
+use crate::submit_sm_builder::SubmitSmBuilder;
+
+/// Type of Number (TON) values we use
+pub const TON_UNKNOWN: u8 = 0x00;
+pub const TON_INTERNATIONAL: u8 = 0x01;
+pub const TON_NETWORK_SPECIFIC: u8 = 0x03;
+pub const TON_ALPHANUMERIC: u8 = 0x05;
+
+/// Numbering Plan Indicator (NPI) values we use
+pub const NPI_UNKNOWN: u8 = 0x00;
+pub const NPI_ISDN: u8 = 0x01;
+
+/// Handsets show at most this many characters of an alphanumeric sender.
+pub const MAX_ALPHANUMERIC_LEN: usize = 11;
+
+/// E.164 numbers are at most this many digits long.
+pub const MAX_MSISDN_LEN: usize = 15;
+
+/// Printable ASCII characters that are not in the GSM 03.38 default
+/// alphabet's basic character set, so cannot be shown in a sender ID.
+const NOT_GSM_DEFAULT: &[char] =
+    &['[', ']', '{', '}', '\\', '^', '~', '|', '`'];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sender {
+    /// e.g. "88222"
+    Shortcode(String),
+    /// A phone number with its country code, e.g. "+447700900123" or
+    /// "00447700900123".  National numbers like "07700900123" are not
+    /// valid, since we could not send them as international numbers.
+    Msisdn(String),
+    /// e.g. "MyCompany"
+    Alphanumeric(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum InvalidSender {
+    NotAShortcode(String),
+    NotAnMsisdn(String),
+    NotInternational(String),
+    AlphanumericEmpty,
+    AlphanumericTooLong(String),
+    AlphanumericInvalidCharacter(char),
+}
+
+impl Display for InvalidSender {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Self::NotAShortcode(s) => {
+                write!(formatter, "'{}' is not a short code.", s)
+            }
+            Self::NotAnMsisdn(s) => {
+                write!(formatter, "'{}' is not a phone number.", s)
+            }
+            Self::NotInternational(s) => write!(
+                formatter,
+                "'{}' must start with '+' or '00' and a country code.",
+                s
+            ),
+            Self::AlphanumericEmpty => {
+                write!(formatter, "Alphanumeric senders may not be empty.")
+            }
+            Self::AlphanumericTooLong(s) => write!(
+                formatter,
+                "Alphanumeric sender '{}' is longer than {} characters.",
+                s, MAX_ALPHANUMERIC_LEN
+            ),
+            Self::AlphanumericInvalidCharacter(c) => write!(
+                formatter,
+                "Alphanumeric senders may not contain {:?}.",
+                c
+            ),
+        }
+    }
+}
+
+impl error::Error for InvalidSender {}
+
+impl Sender {
+    /// Check that this sender is one that SMSCs and handsets will accept.
+    pub fn validate(&self) -> Result<(), InvalidSender> {
+        match self {
+            Self::Shortcode(s) => {
+                if is_short_code(s) {
+                    Ok(())
+                } else {
+                    Err(InvalidSender::NotAShortcode(s.clone()))
+                }
+            }
+            Self::Msisdn(s) => {
+                let digits = normalize_msisdn(s, None);
+                if digits.is_empty()
+                    || digits.len() > MAX_MSISDN_LEN
+                    || !digits.chars().all(|c| c.is_ascii_digit())
+                {
+                    Err(InvalidSender::NotAnMsisdn(s.clone()))
+                } else if !is_international(s) {
+                    Err(InvalidSender::NotInternational(s.clone()))
+                } else {
+                    Ok(())
+                }
+            }
+            Self::Alphanumeric(s) => {
+                if s.is_empty() {
+                    return Err(InvalidSender::AlphanumericEmpty);
+                }
+                if let Some(c) = s.chars().find(|c| {
+                    !c.is_ascii()
+                        || c.is_ascii_control()
+                        || NOT_GSM_DEFAULT.contains(c)
+                }) {
+                    return Err(InvalidSender::AlphanumericInvalidCharacter(c));
+                }
+                if s.len() > MAX_ALPHANUMERIC_LEN {
+                    return Err(InvalidSender::AlphanumericTooLong(s.clone()));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// The address to send, with numbers in E.164 form.
+    pub fn addr(&self) -> String {
+        match self {
+            Self::Shortcode(s) | Self::Msisdn(s) => normalize_msisdn(s, None),
+            Self::Alphanumeric(s) => s.clone(),
+        }
+    }
+
+    pub fn ton(&self) -> u8 {
+        match self {
+            Self::Shortcode(_) => TON_NETWORK_SPECIFIC,
+            Self::Msisdn(_) => TON_INTERNATIONAL,
+            Self::Alphanumeric(_) => TON_ALPHANUMERIC,
+        }
+    }
+
+    pub fn npi(&self) -> u8 {
+        match self {
+            Self::Msisdn(_) => NPI_ISDN,
+            Self::Shortcode(_) | Self::Alphanumeric(_) => NPI_UNKNOWN,
+        }
+    }
+
+    /// destination_addr as a Sender, for the TON and NPI that go with it.
+    /// Check is_international() before treating a number as an Msisdn.
+    pub fn destination(destination_addr: &str) -> Self {
+        if is_short_code(destination_addr) {
+            Self::Shortcode(String::from(destination_addr))
+        } else {
+            Self::Msisdn(String::from(destination_addr))
+        }
+    }
+
+    /// A submit_sm of short_message from this sender to destination_addr,
+    /// with TON and NPI set to match both addresses.  Fails if this sender
+    /// is not valid.
+    pub fn submit_sm(
+        &self,
+        destination_addr: &str,
+        short_message: &[u8],
+    ) -> AsyncResult<SubmitSmPdu> {
+        self.validate()?;
+        Ok(SubmitSmBuilder::new()
+            .sender(self)
+            .destination(destination_addr)
+            .short_message(short_message)
+            .build()?)
+    }
+}
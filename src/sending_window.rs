@@ -0,0 +1,229 @@
+//! The hours of the day during which we may send to each country, e.g. no
+//! marketing before 8am or after 9pm in the handset's own time.  Give them
+//! to Scheduler::with_sending_windows() to reject or defer messages that
+//! would otherwise be sent outside them.
+//!
+//! **A window with an offset such as +01:00 keeps that offset all year: it
+//! does not follow summer time, so it is an hour out for half of it.**
+//! With the time-zones feature, give the country's time zone instead, e.g.
+//! "33=08:00-21:00@Europe/Paris", and its clocks changing are taken into
+//! account.
+
+use std::cmp::Reverse;
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "time-zones")]
+use chrono::{Offset, TimeZone as _};
+
+use crate::msisdn::normalize_msisdn;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// The hours during which we may send to destination addresses starting
+/// with prefix, given in international form without a '+'.  Written as
+/// PREFIX=HH:MM-HH:MM, optionally followed by the country's offset from
+/// UTC, e.g. "33=08:00-21:00@+01:00", or with the time-zones feature its
+/// time zone, e.g. "33=08:00-21:00@Europe/Paris".  Without either the
+/// times are UTC.  A window may cross midnight, e.g. "1=22:00-06:00", and
+/// one whose start and end are the same is open all day.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SendingWindow {
+    pub prefix: String,
+    /// Seconds after local midnight
+    pub opens: u32,
+    /// Seconds after local midnight
+    pub closes: u32,
+    /// Seconds ahead of UTC, all year round.  Ignored if time_zone is set.
+    pub utc_offset: i32,
+    pub time_zone: Option<TimeZone>,
+}
+
+/// A time zone from the IANA database, such as "Europe/Paris".  Only the
+/// time-zones feature can make one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TimeZone {
+    #[cfg(feature = "time-zones")]
+    tz: chrono_tz::Tz,
+}
+
+impl FromStr for TimeZone {
+    type Err = ();
+
+    #[cfg(feature = "time-zones")]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            tz: s.parse().map_err(|_| ())?,
+        })
+    }
+
+    #[cfg(not(feature = "time-zones"))]
+    fn from_str(_s: &str) -> Result<Self, Self::Err> {
+        Err(())
+    }
+}
+
+impl TimeZone {
+    /// Seconds ahead of UTC, seconds after the Unix epoch
+    #[cfg(feature = "time-zones")]
+    fn utc_offset_at(&self, unix_time: i64) -> i32 {
+        match chrono::DateTime::from_timestamp(unix_time, 0) {
+            Some(utc) => self
+                .tz
+                .offset_from_utc_datetime(&utc.naive_utc())
+                .fix()
+                .local_minus_utc(),
+            None => 0,
+        }
+    }
+
+    #[cfg(not(feature = "time-zones"))]
+    fn utc_offset_at(&self, _unix_time: i64) -> i32 {
+        0
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseSendingWindowError(String);
+
+impl Display for ParseSendingWindowError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "Invalid sending window '{}': expected \
+            PREFIX=HH:MM-HH:MM[@+HH:MM], e.g. 33=08:00-21:00@+01:00, or \
+            PREFIX=HH:MM-HH:MM@ZONE with the time-zones feature",
+            self.0
+        )
+    }
+}
+
+impl error::Error for ParseSendingWindowError {}
+
+impl FromStr for SendingWindow {
+    type Err = ParseSendingWindowError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseSendingWindowError(String::from(s));
+        let (prefix, hours) = s.split_once('=').ok_or_else(err)?;
+        let seconds = |offset: &str| {
+            time_of_day(offset)
+                .map(|seconds| seconds as i32)
+                .ok_or_else(err)
+        };
+        let (hours, utc_offset, time_zone) = match hours.split_once('@') {
+            Some((hours, zone)) => match (zone.get(..1), zone.get(1..)) {
+                (Some("+"), Some(offset)) => (hours, seconds(offset)?, None),
+                (Some("-"), Some(offset)) => (hours, -seconds(offset)?, None),
+                _ => (hours, 0, Some(zone.parse().map_err(|_| err())?)),
+            },
+            None => (hours, 0, None),
+        };
+        let (opens, closes) = hours.split_once('-').ok_or_else(err)?;
+        Ok(Self {
+            prefix: String::from(prefix),
+            opens: time_of_day(opens).ok_or_else(err)?,
+            closes: time_of_day(closes).ok_or_else(err)?,
+            utc_offset,
+            time_zone,
+        })
+    }
+}
+
+/// HH:MM as seconds after midnight
+fn time_of_day(s: &str) -> Option<u32> {
+    let (hours, minutes) = s.split_once(':')?;
+    if hours.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    let hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok()?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some((hours * 60 + minutes) * 60)
+}
+
+impl SendingWindow {
+    fn is_open(&self, seconds_after_midnight: u32) -> bool {
+        let s = seconds_after_midnight;
+        if self.opens < self.closes {
+            self.opens <= s && s < self.closes
+        } else if self.opens > self.closes {
+            s >= self.opens || s < self.closes
+        } else {
+            true
+        }
+    }
+
+    /// Seconds ahead of UTC, seconds after the Unix epoch
+    fn utc_offset_at(&self, unix_time: i64) -> i32 {
+        match &self.time_zone {
+            Some(time_zone) => time_zone.utc_offset_at(unix_time),
+            None => self.utc_offset,
+        }
+    }
+
+    /// at, if the window is open then, or else the next time it opens.
+    pub fn next_open(&self, at: SystemTime) -> SystemTime {
+        let since_epoch = at.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let unix_time = since_epoch.as_secs() as i64;
+        let offset = self.utc_offset_at(unix_time);
+        let seconds_after_midnight = |offset: i32| {
+            (unix_time + i64::from(offset)).rem_euclid(SECONDS_PER_DAY)
+        };
+        if self.is_open(seconds_after_midnight(offset) as u32) {
+            return at;
+        }
+        let wait = |offset: i32| {
+            (i64::from(self.opens) - seconds_after_midnight(offset))
+                .rem_euclid(SECONDS_PER_DAY)
+        };
+        let mut opens_at = unix_time + wait(offset);
+        // The clocks may change before then, e.g. when summer time starts
+        let offset_then = self.utc_offset_at(opens_at);
+        if offset_then != offset {
+            opens_at = unix_time + wait(offset_then);
+        }
+        UNIX_EPOCH + Duration::from_secs(opens_at as u64)
+    }
+}
+
+/// A SendingWindow for each country we restrict.  Destinations matching
+/// none of them may be sent to at any time.
+#[derive(Clone, Debug, Default)]
+pub struct SendingWindows {
+    /// Longest prefix first, so the most specific window applies
+    windows: Vec<SendingWindow>,
+}
+
+impl SendingWindows {
+    pub fn new(windows: &[SendingWindow]) -> Self {
+        let mut windows = Vec::from(windows);
+        windows.sort_by_key(|window| Reverse(window.prefix.len()));
+        Self { windows }
+    }
+
+    /// The window for destination_addr, once it is in international form,
+    /// e.g. "+33 6 12 34 56 78" is matched as "33612345678".
+    pub fn window_for(&self, destination_addr: &str) -> Option<&SendingWindow> {
+        let destination_addr = normalize_msisdn(destination_addr, None);
+        self.windows
+            .iter()
+            .find(|window| destination_addr.starts_with(&window.prefix))
+    }
+
+    /// The soonest time from at that we may send to destination_addr.
+    pub fn next_allowed(
+        &self,
+        destination_addr: &str,
+        at: SystemTime,
+    ) -> SystemTime {
+        match self.window_for(destination_addr) {
+            Some(window) => window.next_open(at),
+            None => at,
+        }
+    }
+}
@@ -0,0 +1,331 @@
+//! Recording the PDUs that pass over a connection, and printing them in
+//! the layout Wireshark's SMPP dissector uses, so that our view of a
+//! session can be compared line-by-line with a capture taken elsewhere.
+
+use smpp_pdu::pdu::data::bind_data::BindData;
+use smpp_pdu::pdu::data::sm_data::SmData;
+use smpp_pdu::pdu::formats::COctetString;
+use smpp_pdu::pdu::tlvs::Tlv;
+use smpp_pdu::pdu::{Pdu, PduBody};
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use crate::command_id::RESPONSE_BIT;
+use crate::encoded_len::EncodedLen;
+use crate::frame_body::{Header, HEADER_LENGTH};
+use crate::pdu_status::StatusName;
+use crate::redact::frame_bytes;
+use crate::smpp_connection::PeerAddr;
+
+/// How many PDUs a SessionCapture keeps.  Older ones are dropped, so that
+/// a long-lived connection does not grow without limit.
+pub const MAX_CAPTURED_PDUS: usize = 1000;
+
+/// Shown instead of the password in binds
+const PASSWORD_MASK: &str = "********";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Received,
+    Sent,
+}
+
+#[derive(Clone, Debug)]
+pub struct CapturedPdu {
+    /// Time since the capture started
+    pub elapsed: Duration,
+    pub direction: Direction,
+    /// The PDU exactly as it appeared on the wire.  When the
+    /// redact-message-content feature is enabled, only the 16-byte header
+    /// is kept.
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SessionCapture {
+    pub peer: PeerAddr,
+    started: Instant,
+    pdus: VecDeque<CapturedPdu>,
+    /// How many PDUs were dropped to stay within MAX_CAPTURED_PDUS
+    dropped: usize,
+}
+
+impl SessionCapture {
+    pub fn new(peer: PeerAddr) -> Self {
+        Self {
+            peer,
+            started: Instant::now(),
+            pdus: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    pub fn record(&mut self, direction: Direction, bytes: &[u8]) {
+        if self.pdus.len() == MAX_CAPTURED_PDUS {
+            self.pdus.pop_front();
+            self.dropped += 1;
+        }
+        self.pdus.push_back(CapturedPdu {
+            elapsed: self.started.elapsed(),
+            direction,
+            bytes: Vec::from(frame_bytes(bytes)),
+        });
+    }
+
+    /// The most recent PDUs, oldest first.  At most MAX_CAPTURED_PDUS are
+    /// kept.
+    pub fn pdus(&self) -> &VecDeque<CapturedPdu> {
+        &self.pdus
+    }
+
+    /// How many earlier PDUs were dropped to stay within MAX_CAPTURED_PDUS
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Print every captured PDU in the layout of Wireshark's "Packet
+    /// Details" view of its SMPP dissector.  Frames are numbered from the
+    /// start of the session, counting any that were dropped.
+    pub fn to_wireshark_text(&self) -> String {
+        let mut ret = String::new();
+        for (i, captured) in self.pdus.iter().enumerate() {
+            let _ = writeln!(
+                ret,
+                "Frame {}: {} bytes {} {} at {}.{:06}s",
+                self.dropped + i + 1,
+                captured.bytes.len(),
+                match captured.direction {
+                    Direction::Received => "received from",
+                    Direction::Sent => "sent to",
+                },
+                self.peer,
+                captured.elapsed.as_secs(),
+                captured.elapsed.subsec_micros(),
+            );
+            ret.push_str(&wireshark_text(&captured.bytes));
+            ret.push('\n');
+        }
+        ret
+    }
+}
+
+/// Print a single PDU in the layout Wireshark uses.  Bytes that do not
+/// parse are shown as a malformed packet after whatever header fields
+/// could be read.
+pub fn wireshark_text(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let header = match Header::peek(bytes) {
+        Some(header) => header,
+        None => {
+            out.push_str("Short Message Peer to Peer\n");
+            out.push_str("    [Malformed Packet: SMPP]\n");
+            return out;
+        }
+    };
+    let Header {
+        command_length,
+        command_id,
+        command_status,
+        sequence_number,
+    } = header;
+
+    let _ = writeln!(
+        out,
+        "Short Message Peer to Peer, Command: {}, Seq: {}, Len: {}",
+        operation_name(command_id),
+        sequence_number,
+        command_length
+    );
+    field(&mut out, "Length", command_length);
+    field(
+        &mut out,
+        "Operation",
+        format!("{} ({:#010x})", operation_name(command_id), command_id),
+    );
+    if command_id & RESPONSE_BIT != 0 {
+        field(&mut out, "Result", StatusName(command_status));
+    }
+    field(&mut out, "Sequence #", sequence_number);
+
+    if bytes.len() == HEADER_LENGTH && command_length as usize > HEADER_LENGTH {
+        out.push_str("    [Body not captured]\n");
+        return out;
+    }
+
+    match Pdu::parse(&mut Cursor::new(bytes)) {
+        Ok(pdu) => body_fields(&mut out, &pdu, &bytes[HEADER_LENGTH..]),
+        Err(e) => {
+            let _ = writeln!(out, "    [Malformed Packet: {}]", e);
+        }
+    }
+    out
+}
+
+fn operation_name(command_id: u32) -> &'static str {
+    match command_id {
+        0x80000000 => "Generic_nack",
+        0x00000001 => "Bind_receiver",
+        0x80000001 => "Bind_receiver - resp",
+        0x00000002 => "Bind_transmitter",
+        0x80000002 => "Bind_transmitter - resp",
+        0x00000004 => "Submit_sm",
+        0x80000004 => "Submit_sm - resp",
+        0x00000005 => "Deliver_sm",
+        0x80000005 => "Deliver_sm - resp",
+        0x00000009 => "Bind_transceiver",
+        0x80000009 => "Bind_transceiver - resp",
+        0x00000015 => "Enquire_link",
+        0x80000015 => "Enquire_link - resp",
+        _ => "Unknown",
+    }
+}
+
+fn field(out: &mut String, name: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "    {}: {}", name, value);
+}
+
+fn c_octet_string(value: &COctetString) -> String {
+    value.value.to_string()
+}
+
+fn body_fields(out: &mut String, pdu: &Pdu, body_bytes: &[u8]) {
+    match pdu.body() {
+        PduBody::BindReceiver(body) => bind_fields(out, body.bind_data()),
+        PduBody::BindTransceiver(body) => bind_fields(out, body.bind_data()),
+        PduBody::BindTransmitter(body) => bind_fields(out, body.bind_data()),
+        PduBody::BindReceiverResp(_)
+        | PduBody::BindTransceiverResp(_)
+        | PduBody::BindTransmitterResp(_) => {
+            // The system_id is not accessible on these types, so read it
+            // from the bytes instead.
+            if let Ok(system_id) =
+                COctetString::read(&mut Cursor::new(body_bytes), 16)
+            {
+                field(out, "System ID", c_octet_string(&system_id));
+            }
+        }
+        PduBody::SubmitSm(body) => sm_fields(out, &body.0, body_bytes),
+        PduBody::DeliverSm(body) => sm_fields(out, &body.0, body_bytes),
+        PduBody::SubmitSmResp(body) => {
+            if let Some(message_id) = &body.message_id {
+                field(out, "Message id.", c_octet_string(message_id));
+            }
+        }
+        PduBody::EnquireLink(_)
+        | PduBody::EnquireLinkResp(_)
+        | PduBody::GenericNack(_) => {}
+    }
+}
+
+fn bind_fields(out: &mut String, bind_data: &BindData) {
+    field(out, "System ID", c_octet_string(&bind_data.system_id));
+    field(out, "Password", PASSWORD_MASK);
+    field(out, "System type", c_octet_string(&bind_data.system_type));
+    field(
+        out,
+        "Version (if)",
+        format!("{:#04x}", bind_data.interface_version.value),
+    );
+    field(
+        out,
+        "Type of number",
+        format!("{:#04x}", bind_data.addr_ton.value),
+    );
+    field(
+        out,
+        "Numbering plan indicator",
+        format!("{:#04x}", bind_data.addr_npi.value),
+    );
+    field(
+        out,
+        "Address range",
+        c_octet_string(&bind_data.address_range),
+    );
+}
+
+fn sm_fields(out: &mut String, sm: &SmData, body_bytes: &[u8]) {
+    let hex = |v: u8| format!("{:#04x}", v);
+    field(out, "Service type", c_octet_string(&sm.service_type));
+    field(
+        out,
+        "Type of number (originator)",
+        hex(sm.source_addr_ton.value),
+    );
+    field(
+        out,
+        "Numbering plan indicator (originator)",
+        hex(sm.source_addr_npi.value),
+    );
+    field(out, "Originator address", c_octet_string(&sm.source_addr));
+    field(
+        out,
+        "Type of number (recipient)",
+        hex(sm.dest_addr_ton.value),
+    );
+    field(
+        out,
+        "Numbering plan indicator (recipient)",
+        hex(sm.dest_addr_npi.value),
+    );
+    field(
+        out,
+        "Recipient address",
+        c_octet_string(&sm.destination_addr),
+    );
+    field(out, "ESM class", hex(sm.esm_class.value));
+    field(out, "Protocol id.", hex(sm.protocol_id.value));
+    field(out, "Priority level", hex(sm.priority_flag.value));
+    field(
+        out,
+        "Scheduled delivery time",
+        c_octet_string(&sm.schedule_delivery_time),
+    );
+    field(out, "Validity period", c_octet_string(&sm.validity_period));
+    field(
+        out,
+        "Registered delivery",
+        hex(sm.registered_delivery.value),
+    );
+    field(out, "Replace", hex(sm.replace_if_present_flag.value));
+    field(out, "Data coding", hex(sm.data_coding.value));
+    field(out, "Predefined message", sm.sm_default_msg_id.value);
+    field(out, "Message length", sm.short_message.len());
+    if cfg!(feature = "redact-message-content") {
+        field(out, "Message bytes", "<redacted>");
+        return;
+    }
+    field(out, "Message bytes", hex_bytes(&sm.short_message.value));
+
+    // Tlvs does not let us iterate over its contents, so read them from
+    // the bytes that follow the mandatory fields.
+    let tlvs_start = sm.encoded_len() - sm.tlvs.encoded_len();
+    let mut tlv_bytes = Cursor::new(&body_bytes[tlvs_start..]);
+    let mut first = true;
+    while let Ok(Some(tlv)) = Tlv::read(&mut tlv_bytes) {
+        if first {
+            out.push_str("    Optional parameters\n");
+            first = false;
+        }
+        tlv_field(out, &tlv);
+    }
+}
+
+fn tlv_field(out: &mut String, tlv: &Tlv) {
+    let name = match tlv.tag() {
+        Ok(tag) => format!("{:?}", tag),
+        Err(_) => String::from("Unknown"),
+    };
+    let _ = writeln!(
+        out,
+        "        Optional parameter: {} ({:#06x})",
+        name, tlv.raw_tag
+    );
+    let _ = writeln!(out, "            Length: {}", tlv.value.len());
+    let _ = writeln!(out, "            Value: {}", hex_bytes(&tlv.value));
+}
+
+pub(crate) fn hex_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,65 @@
+//! What was agreed when a session was bound, so that logic can branch on
+//! what the session supports rather than guess.
+//!
+//! Both ends record it on their SmppConnection once a bind succeeds: the
+//! SMSC from the bind it accepted, and the client from the bind it sent.
+//! smpp_pdu cannot read TLVs from bind_resp, so an SMSC's
+//! sc_interface_version is not seen, and the interface_version is the one
+//! the ESME bound with.
+
+use std::fmt::{Display, Formatter};
+
+pub const SMPP_3_3: u8 = 0x33;
+pub const SMPP_3_4: u8 = 0x34;
+pub const SMPP_5_0: u8 = 0x50;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BindMode {
+    Transmitter,
+    Receiver,
+    Transceiver,
+}
+
+impl Display for BindMode {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str(match self {
+            Self::Transmitter => "transmitter",
+            Self::Receiver => "receiver",
+            Self::Transceiver => "transceiver",
+        })
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SessionInfo {
+    pub bind_mode: BindMode,
+    /// The interface_version in the bind
+    pub interface_version: u8,
+    /// The most requests we let ourselves have outstanding on this session
+    /// at once, if limited
+    pub window: Option<usize>,
+}
+
+impl SessionInfo {
+    /// TLVs arrived with SMPP 3.4, so must not be sent to older ESMEs.
+    pub fn supports_tlvs(&self) -> bool {
+        self.interface_version >= SMPP_3_4
+    }
+}
+
+/// Logged when a bind succeeds, e.g. "transceiver, SMPP 3.4, window 10".
+impl Display for SessionInfo {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "{}, SMPP {}.{}",
+            self.bind_mode,
+            self.interface_version >> 4,
+            self.interface_version & 0x0F
+        )?;
+        match self.window {
+            Some(window) => write!(formatter, ", window {}", window),
+            None => formatter.write_str(", no window"),
+        }
+    }
+}
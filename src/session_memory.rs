@@ -0,0 +1,81 @@
+//! Roughly how much memory one connection is holding, so that a peer that
+//! floods us, or stops reading, can be throttled or closed before it
+//! exhausts the memory of every other connection too.
+
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::Instant;
+
+/// What a connection is holding, in bytes.  Only the parts that grow with
+/// what the peer does are counted, and only approximately.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SessionMemory {
+    /// Allocated for bytes read but not yet parsed
+    pub read_buffer: usize,
+    /// PDUs waiting to be sent later, such as scheduled delivery receipts
+    pub queued: usize,
+    /// Requests received and not yet answered
+    pub pending_requests: usize,
+    /// The session capture, if any, and the bad frames kept
+    pub recorded: usize,
+}
+
+impl SessionMemory {
+    /// What we keep for each request until it is answered
+    pub const PENDING_REQUEST: usize = std::mem::size_of::<(u32, Instant)>();
+
+    pub fn total(&self) -> usize {
+        self.read_buffer + self.queued + self.pending_requests + self.recorded
+    }
+}
+
+impl Display for SessionMemory {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "{} bytes (read buffer {}, queued {}, pending requests {}, \
+            recorded {})",
+            self.total(),
+            self.read_buffer,
+            self.queued,
+            self.pending_requests,
+            self.recorded
+        )
+    }
+}
+
+/// What to do with a connection holding more than --max-session-memory
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SessionMemoryAction {
+    /// Reject each submit_sm with ESME_RTHROTTLED until usage falls
+    Throttle,
+    Close,
+}
+
+#[derive(Debug)]
+pub struct ParseSessionMemoryActionError(String);
+
+impl Display for ParseSessionMemoryActionError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "Invalid session memory action '{}': expected throttle or close",
+            self.0
+        )
+    }
+}
+
+impl error::Error for ParseSessionMemoryActionError {}
+
+impl FromStr for SessionMemoryAction {
+    type Err = ParseSessionMemoryActionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "throttle" => Ok(Self::Throttle),
+            "close" => Ok(Self::Close),
+            _ => Err(ParseSessionMemoryActionError(String::from(s))),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use smpp_pdu::pdu::{Pdu, PduBody};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, TokioClock};
+use crate::command_id::{CommandId, RESPONSE_BIT};
+use crate::deliver_sm_resp::DeliverSmRespPdu;
+use crate::frame_body::Header;
+
+/// How far back recent_responses() looks
+pub const RECENT: Duration = Duration::from_secs(60);
+
+/// Counters describing what has happened on one connection.
+#[derive(Clone, Debug, Default)]
+pub struct SessionStats {
+    /// submit_sm PDUs received from the ESME
+    pub submits: u64,
+    /// deliver_sm PDUs sent to the ESME
+    pub deliveries: u64,
+    /// How many responses we sent with each non-zero command_status
+    pub errors: BTreeMap<u32, u64>,
+    /// How many deliver_sm_resp PDUs the ESME sent with each non-zero
+    /// command_status
+    pub delivery_errors: BTreeMap<u32, u64>,
+    /// When a PDU was last sent or received
+    pub last_activity: Option<Instant>,
+    /// How long the peer took to answer the last enquire_link we sent
+    pub last_enquire_link_rtt: Option<Duration>,
+    responses: u64,
+    total_resp_latency: Duration,
+    awaiting_resp: HashMap<u32, Instant>,
+    enquire_link_sent: Option<(u32, Instant)>,
+    /// When we sent each response in the last RECENT, and its status
+    recent: VecDeque<(Instant, u32)>,
+}
+
+impl SessionStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_received(&mut self, pdu: &Pdu) {
+        let now = TokioClock.now();
+        self.last_activity = Some(now);
+        match pdu.body() {
+            PduBody::SubmitSm(_) => self.submits += 1,
+            PduBody::EnquireLinkResp(_) => {
+                if let Some((sequence_number, sent)) = self.enquire_link_sent {
+                    if sequence_number == pdu.sequence_number.value {
+                        self.last_enquire_link_rtt = Some(now - sent);
+                        self.enquire_link_sent = None;
+                    }
+                }
+            }
+            _ => {}
+        }
+        if !is_response(pdu) {
+            self.awaiting_resp.insert(pdu.sequence_number.value, now);
+        }
+    }
+
+    pub fn record_deliver_sm_resp(&mut self, resp: &DeliverSmRespPdu) {
+        self.last_activity = Some(TokioClock.now());
+        if resp.command_status != 0 {
+            *self.delivery_errors.entry(resp.command_status).or_insert(0) += 1;
+        }
+    }
+
+    pub fn record_sent(&mut self, pdu: &Pdu) {
+        self.record_sent_header(
+            pdu.command_id().value,
+            pdu.command_status.value,
+            pdu.sequence_number.value,
+        );
+    }
+
+    /// As record_sent(), for a frame smpp_pdu cannot represent, such as a
+    /// deliver_sm_resp, from its header.
+    pub(crate) fn record_sent_frame(&mut self, header: &Header) {
+        self.record_sent_header(
+            header.command_id,
+            header.command_status,
+            header.sequence_number,
+        );
+    }
+
+    fn record_sent_header(
+        &mut self,
+        command_id: u32,
+        command_status: u32,
+        sequence_number: u32,
+    ) {
+        let now = TokioClock.now();
+        self.last_activity = Some(now);
+        match CommandId::try_from(command_id) {
+            Ok(CommandId::DeliverSm) => self.deliveries += 1,
+            Ok(CommandId::EnquireLink) => {
+                self.enquire_link_sent = Some((sequence_number, now))
+            }
+            _ => {}
+        }
+        if command_id & RESPONSE_BIT != 0 {
+            if command_status != 0 {
+                *self.errors.entry(command_status).or_insert(0) += 1;
+            }
+            self.recent.push_back((now, command_status));
+            self.forget_old(now);
+            if let Some(received) = self.awaiting_resp.remove(&sequence_number)
+            {
+                self.responses += 1;
+                self.total_resp_latency += now - received;
+            }
+        }
+    }
+
+    /// Mean time between receiving a request and sending our response, or
+    /// None if we have not responded to anything yet.
+    pub fn average_resp_latency(&self) -> Option<Duration> {
+        if self.responses == 0 {
+            None
+        } else {
+            Some(self.total_resp_latency / self.responses as u32)
+        }
+    }
+
+    /// How many requests we have received but not yet responded to.
+    pub fn pending_responses(&self) -> usize {
+        self.awaiting_resp.len()
+    }
+
+    /// The command_status of each response we sent in the last RECENT.
+    pub fn recent_responses(&self) -> Vec<u32> {
+        let now = TokioClock.now();
+        self.recent
+            .iter()
+            .filter(|(sent, _)| now.saturating_duration_since(*sent) <= RECENT)
+            .map(|(_, status)| *status)
+            .collect()
+    }
+
+    fn forget_old(&mut self, now: Instant) {
+        while self.recent.front().is_some_and(|(sent, _)| {
+            now.saturating_duration_since(*sent) > RECENT
+        }) {
+            self.recent.pop_front();
+        }
+    }
+}
+
+fn is_response(pdu: &Pdu) -> bool {
+    pdu.command_id().value & RESPONSE_BIT != 0
+}
@@ -1,25 +1,117 @@
 use ascii::AsciiString;
 use bytes::{Buf, BytesMut};
 use log::*;
-use smpp_pdu::pdu::{CheckOutcome, Pdu, PduParseError, PduParseErrorBody};
+use smpp_pdu::pdu::formats::WriteStream;
+use smpp_pdu::pdu::tlvs::Tlv;
+use smpp_pdu::pdu::{
+    CheckOutcome, Pdu, PduBody, PduParseError, PduParseErrorBody,
+};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
 use std::io;
 use std::io::Cursor;
 use std::net::SocketAddr;
-use tokio::io::{split, AsyncReadExt, ReadHalf, WriteHalf};
+#[cfg(unix)]
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use tokio::io::{split, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::net::TcpStream;
-use tokio::sync::Mutex;
+#[cfg(unix)]
+use tokio::net::UnixStream;
+use tokio::sync::{Mutex, Notify};
 
-#[derive(Clone, Eq, Hash, PartialEq)]
+use crate::alert_notification::AlertNotificationPdu;
+use crate::cancel_sm::{CancelSmPdu, CancelSmRespPdu};
+use crate::clock::{Clock, TokioClock};
+use crate::command_id::CommandId;
+use crate::data_sm::{DataSmPdu, DataSmRespPdu};
+use crate::deliver_sm_resp::DeliverSmRespPdu;
+use crate::frame_body::Header;
+use crate::in_flight::SequenceNumbers;
+use crate::outbind::OutbindPdu;
+use crate::parse_options::{ParseOptions, Parsed};
+use crate::pdu_write::write_pdu;
+use crate::query_sm::{QuerySmPdu, QuerySmRespPdu};
+use crate::redact::{frame_bytes, Redacted, RedactedTlvs};
+use crate::replace_sm::{ReplaceSmPdu, ReplaceSmRespPdu};
+use crate::session_capture::{hex_bytes, Direction, SessionCapture};
+use crate::session_info::SessionInfo;
+use crate::session_memory::SessionMemory;
+use crate::session_stats::SessionStats;
+use crate::submit_multi::{SubmitMultiPdu, SubmitMultiRespPdu};
+use crate::text::DataCodingMap;
+use crate::unbind::{UnbindPdu, UnbindRespPdu};
+use crate::unique_id::SessionId;
+
+/// How many frames that failed to parse we remember per connection.
+pub const MAX_BAD_FRAMES: usize = 16;
+
+/// How much of a frame that failed to parse we keep.  A bad length can make
+/// a "frame" arbitrarily long, and the start is what matters.
+pub const MAX_BAD_FRAME_LENGTH: usize = 1024;
+
+const READ_BUFFER_CAPACITY: usize = 4096;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct EsmeId {
     pub system_id: AsciiString,
     pub system_type: AsciiString,
 }
 
+/// Where the other end of a connection is
+#[derive(Clone, Debug, PartialEq)]
+pub enum PeerAddr {
+    Tcp(SocketAddr),
+    /// Unix domain socket peers are usually unnamed, so this is the path of
+    /// the socket we listened or connected on.
+    Unix(PathBuf),
+}
+
+impl From<SocketAddr> for PeerAddr {
+    fn from(socket_addr: SocketAddr) -> Self {
+        Self::Tcp(socket_addr)
+    }
+}
+
+impl Display for PeerAddr {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Self::Tcp(socket_addr) => socket_addr.fmt(formatter),
+            Self::Unix(path) => write!(formatter, "unix:{}", path.display()),
+        }
+    }
+}
+
 pub struct SmppConnection {
-    pub socket_addr: SocketAddr,
+    pub peer_addr: PeerAddr,
+    session_id: SessionId,
     read: Mutex<Option<SmppRead>>,
     write: Mutex<Option<SmppWrite>>,
     bound_esme_id: std::sync::Mutex<Option<EsmeId>>,
+    capture: std::sync::Mutex<Option<SessionCapture>>,
+    bad_frames: std::sync::Mutex<VecDeque<Vec<u8>>>,
+    stats: std::sync::Mutex<SessionStats>,
+    idle_since: std::sync::Mutex<Instant>,
+    partial_pdu: std::sync::Mutex<Option<PartialPdu>>,
+    partial_pdu_started: Notify,
+    session_info: std::sync::Mutex<Option<SessionInfo>>,
+    sequence_numbers: SequenceNumbers,
+    close_requested: Notify,
+    data_coding_map: std::sync::Mutex<DataCodingMap>,
+    parse_options: std::sync::Mutex<ParseOptions>,
+    read_buffer_capacity: AtomicUsize,
+    queued_bytes: AtomicUsize,
+}
+
+/// Which connection a log line is about: the peer_addr, which may be
+/// shared over time, and the session_id, which is not.
+impl Display for SmppConnection {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "{} {}", self.peer_addr, self.session_id)
+    }
 }
 
 impl SmppConnection {
@@ -27,23 +119,218 @@ impl SmppConnection {
         tcp_stream: TcpStream,
         socket_addr: SocketAddr,
     ) -> SmppConnection {
-        let (read_stream, write_stream) = split(tcp_stream);
-        let buffer = BytesMut::with_capacity(4096);
+        Self::from_stream(tcp_stream, socket_addr.into())
+    }
+
+    /// Connect to an SMPP peer listening on a Unix domain socket.
+    #[cfg(unix)]
+    pub async fn connect_unix(path: &Path) -> io::Result<SmppConnection> {
+        let stream = UnixStream::connect(path).await?;
+        Ok(Self::from_stream(
+            stream,
+            PeerAddr::Unix(path.to_path_buf()),
+        ))
+    }
+
+    /// A connection over any byte stream, e.g. one tunnelled through another
+    /// protocol.
+    pub fn from_stream<S>(stream: S, peer_addr: PeerAddr) -> SmppConnection
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (read_stream, write_stream) = split(stream);
+        let buffer = BytesMut::with_capacity(READ_BUFFER_CAPACITY);
         let read = SmppRead {
-            stream: read_stream,
+            stream: Box::new(read_stream),
             buffer,
         };
         let write = SmppWrite {
-            stream: write_stream,
+            stream: Box::new(write_stream),
         };
         SmppConnection {
             read: Mutex::new(Some(read)),
             write: Mutex::new(Some(write)),
-            socket_addr,
+            peer_addr,
+            session_id: SessionId::next(),
             bound_esme_id: std::sync::Mutex::new(None),
+            capture: std::sync::Mutex::new(None),
+            bad_frames: std::sync::Mutex::new(VecDeque::new()),
+            stats: std::sync::Mutex::new(SessionStats::new()),
+            idle_since: std::sync::Mutex::new(TokioClock.now()),
+            partial_pdu: std::sync::Mutex::new(None),
+            partial_pdu_started: Notify::new(),
+            session_info: std::sync::Mutex::new(None),
+            sequence_numbers: SequenceNumbers::new(),
+            close_requested: Notify::new(),
+            data_coding_map: std::sync::Mutex::new(DataCodingMap::default()),
+            parse_options: std::sync::Mutex::new(ParseOptions::default()),
+            read_buffer_capacity: AtomicUsize::new(READ_BUFFER_CAPACITY),
+            queued_bytes: AtomicUsize::new(0),
         }
     }
 
+    /// Unique to this connection, unlike peer_addr.
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
+    /// What each data_coding value means to the peer.
+    pub fn data_coding_map(&self) -> DataCodingMap {
+        self.data_coding_map.lock().unwrap().clone()
+    }
+
+    /// Use map to encode and decode text for this peer, e.g. because it
+    /// treats data_coding 0 as Latin-1.
+    pub fn set_data_coding_map(&self, map: DataCodingMap) {
+        *self.data_coding_map.lock().unwrap() = map;
+    }
+
+    pub fn parse_options(&self) -> ParseOptions {
+        *self.parse_options.lock().unwrap()
+    }
+
+    /// Read frames from now on with options, e.g. a different
+    /// max_pdu_length.
+    pub fn set_parse_options(&self, options: ParseOptions) {
+        *self.parse_options.lock().unwrap() = options;
+    }
+
+    /// Begin recording every PDU sent or received on this connection.
+    pub fn start_capture(&self) {
+        self.capture
+            .lock()
+            .unwrap()
+            .replace(SessionCapture::new(self.peer_addr.clone()));
+    }
+
+    /// Everything recorded since start_capture() was called, or None if
+    /// it was never called.
+    pub fn capture(&self) -> Option<SessionCapture> {
+        self.capture.lock().unwrap().clone()
+    }
+
+    /// The raw bytes of the most recent frames that failed to parse, oldest
+    /// first.  At most MAX_BAD_FRAMES are kept, each cut to
+    /// MAX_BAD_FRAME_LENGTH bytes.
+    pub fn bad_frames(&self) -> Vec<Vec<u8>> {
+        self.bad_frames.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn record_bad_frame(&self, frame: &[u8], error: &PduParseError) {
+        let frame =
+            frame_bytes(&frame[..frame.len().min(MAX_BAD_FRAME_LENGTH)]);
+        warn!(
+            "<= {} failed to parse PDU ({}): {}",
+            self,
+            error,
+            hex_bytes(frame)
+        );
+
+        let mut bad_frames = self.bad_frames.lock().unwrap();
+        if bad_frames.len() == MAX_BAD_FRAMES {
+            bad_frames.pop_front();
+        }
+        bad_frames.push_back(Vec::from(frame));
+    }
+
+    pub fn stats(&self) -> SessionStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Roughly how much memory this connection is holding.
+    pub fn memory(&self) -> SessionMemory {
+        let captured =
+            self.capture.lock().unwrap().as_ref().map_or(0, |c| {
+                c.pdus().iter().map(|pdu| pdu.bytes.len()).sum()
+            });
+        let bad_frames: usize =
+            self.bad_frames.lock().unwrap().iter().map(Vec::len).sum();
+        SessionMemory {
+            read_buffer: self.read_buffer_capacity.load(Ordering::Relaxed),
+            queued: self.queued_bytes.load(Ordering::Relaxed),
+            pending_requests: self.stats.lock().unwrap().pending_responses()
+                * SessionMemory::PENDING_REQUEST,
+            recorded: captured + bad_frames,
+        }
+    }
+
+    /// Count bytes, e.g. of a PDU to be sent later, as held by this
+    /// connection until the returned guard is dropped.
+    pub fn queue(&self, bytes: usize) -> Queued<'_> {
+        self.queued_bytes.fetch_add(bytes, Ordering::Relaxed);
+        Queued {
+            queued_bytes: &self.queued_bytes,
+            bytes,
+        }
+    }
+
+    /// When a PDU other than enquire_link or enquire_link_resp was last sent
+    /// or received, or when we connected if there has been none.
+    pub fn idle_since(&self) -> Instant {
+        *self.idle_since.lock().unwrap()
+    }
+
+    /// The PDU we have received part of so far, if any.
+    pub fn partial_pdu(&self) -> Option<PartialPdu> {
+        *self.partial_pdu.lock().unwrap()
+    }
+
+    fn record_partial_pdu(&self, bytes: usize) {
+        let mut partial_pdu = self.partial_pdu.lock().unwrap();
+        match &mut *partial_pdu {
+            Some(partial_pdu) => partial_pdu.received += bytes,
+            None => {
+                *partial_pdu = Some(PartialPdu {
+                    started: TokioClock.now(),
+                    received: bytes,
+                });
+                self.partial_pdu_started.notify_waiters();
+            }
+        }
+    }
+
+    /// Completes when the first bytes of a PDU arrive, if we are already
+    /// waiting here then.
+    pub async fn partial_pdu_started(&self) {
+        self.partial_pdu_started.notified().await
+    }
+
+    /// We have dealt with a frame, so whatever is left in the buffer is the
+    /// start of the next one.
+    fn restart_partial_pdu(&self, buffered: usize) {
+        *self.partial_pdu.lock().unwrap() = if buffered == 0 {
+            None
+        } else {
+            Some(PartialPdu {
+                started: TokioClock.now(),
+                received: buffered,
+            })
+        };
+    }
+
+    fn record_activity(&self, pdu: &Pdu) {
+        match pdu.body() {
+            PduBody::EnquireLink(_) | PduBody::EnquireLinkResp(_) => {}
+            _ => *self.idle_since.lock().unwrap() = TokioClock.now(),
+        }
+    }
+
+    /// A sequence number for a request we are originating.  Counts up from
+    /// 1, wrapping back to 1 after 0x7FFFFFFF, the highest allowed.
+    pub fn next_sequence_number(&self) -> u32 {
+        self.sequence_numbers.next()
+    }
+
+    /// Ask whoever is reading from this connection to close it.
+    pub fn request_close(&self) {
+        self.close_requested.notify_one();
+    }
+
+    /// Completes when request_close() has been called.
+    pub async fn close_requested(&self) {
+        self.close_requested.notified().await
+    }
+
     pub fn bound_esme_id(&self) -> Option<EsmeId> {
         self.bound_esme_id.lock().unwrap().clone()
     }
@@ -55,36 +342,162 @@ impl SmppConnection {
         });
     }
 
+    /// What was agreed when this connection was bound, or None if it is
+    /// not bound.
+    pub fn session_info(&self) -> Option<SessionInfo> {
+        self.session_info.lock().unwrap().clone()
+    }
+
+    pub fn set_session_info(&self, session_info: SessionInfo) {
+        info!("Connection {} - bound as {}", self, session_info);
+        self.session_info.lock().unwrap().replace(session_info);
+    }
+
+    /// The next PDU smpp_pdu can represent, or None if the peer closed the
+    /// connection.  deliver_sm_resp, unbind and unbind_resp are skipped;
+    /// use read_frame() to see them.
     pub async fn read_pdu(&self) -> Result<Option<Pdu>, PduParseError> {
+        loop {
+            match self.read_frame().await? {
+                Some(Frame::Pdu(pdu)) => return Ok(Some(pdu)),
+                Some(_) => continue,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// The next frame, or None if the peer closed the connection.
+    pub async fn read_frame(&self) -> Result<Option<Frame>, PduParseError> {
         loop {
             let mut read = self.read.lock().await;
             if let Some(read) = &mut *read {
-                if let Some(pdu) = read.parse_pdu()? {
-                    return Ok(Some(pdu));
+                let options = *self.parse_options.lock().unwrap();
+                let parsed = read.parse_pdu(&self.capture, &options).map(|p| {
+                    p.map(|parsed| {
+                        for warning in &parsed.warnings {
+                            warn!(
+                                "Connection {} - accepted a PDU that breaks \
+                                the spec: {}",
+                                self, warning
+                            );
+                        }
+                        parsed.frame
+                    })
+                });
+                self.read_buffer_capacity
+                    .store(read.buffer.capacity(), Ordering::Relaxed);
+                if !matches!(parsed, Ok(None)) {
+                    self.restart_partial_pdu(read.buffer.len());
                 }
-
-                if 0 == read.read_own_buf().await? {
-                    if read.buffer.is_empty() {
-                        return Ok(None);
-                    } else {
-                        return Err(PduParseError::new(
-                            PduParseErrorBody::NotEnoughBytes,
-                        ));
+                match parsed {
+                    Ok(Some(Frame::Pdu(pdu))) => {
+                        self.stats.lock().unwrap().record_received(&pdu);
+                        self.record_activity(&pdu);
+                        return Ok(Some(Frame::Pdu(pdu)));
+                    }
+                    Ok(Some(frame)) => {
+                        info!("<= {} {:?}", self, frame);
+                        if let Frame::DeliverSmResp(resp) = &frame {
+                            self.stats
+                                .lock()
+                                .unwrap()
+                                .record_deliver_sm_resp(resp);
+                        }
+                        *self.idle_since.lock().unwrap() = TokioClock.now();
+                        return Ok(Some(frame));
+                    }
+                    Ok(None) => {}
+                    Err((e, frame)) => {
+                        self.record_bad_frame(&frame, &e);
+                        return Err(e);
                     }
                 }
+
+                let bytes = read.read_own_buf().await?;
+                self.read_buffer_capacity
+                    .store(read.buffer.capacity(), Ordering::Relaxed);
+                if bytes > 0 {
+                    self.record_partial_pdu(bytes);
+                } else if read.buffer.is_empty() {
+                    return Ok(None);
+                } else {
+                    let e =
+                        PduParseError::new(PduParseErrorBody::NotEnoughBytes);
+                    self.record_bad_frame(&read.buffer, &e);
+                    return Err(e);
+                }
             } else {
                 error!("Attempting to read from a closed connection!");
                 return Err(PduParseError::new(
-                    PduParseErrorBody::NotEnoughBytes.into(),
+                    PduParseErrorBody::NotEnoughBytes,
                 ));
             }
         }
     }
 
     pub async fn write_pdu(&self, pdu: &Pdu) -> io::Result<()> {
-        info!("=> {} {:?}", self.socket_addr, pdu);
+        self.write_pdu_with_tlvs(pdu, &[]).await
+    }
+
+    /// Write pdu with tlvs appended.  Useful for responses, which smpp_pdu
+    /// does not allow to have TLVs.
+    pub async fn write_pdu_with_tlvs(
+        &self,
+        pdu: &Pdu,
+        tlvs: &[Tlv],
+    ) -> io::Result<()> {
+        if tlvs.is_empty() {
+            info!("=> {} {:?}", self, Redacted(pdu));
+        } else {
+            info!("=> {} {:?} {:?}", self, Redacted(pdu), RedactedTlvs(tlvs));
+        }
+        if let Some(write) = &mut *self.write.lock().await {
+            let mut buf: Vec<u8> = Vec::new();
+            write_pdu(pdu, &mut buf).await?;
+            if !tlvs.is_empty() {
+                for tlv in tlvs {
+                    tlv.write(&mut buf).await?;
+                }
+                let command_length = buf.len() as u32;
+                buf[..4].copy_from_slice(&command_length.to_be_bytes());
+            }
+            if let Some(capture) = &mut *self.capture.lock().unwrap() {
+                capture.record(Direction::Sent, &buf);
+            }
+            self.stats.lock().unwrap().record_sent(pdu);
+            self.record_activity(pdu);
+            write.stream.write_all(&buf).await
+        } else {
+            error!("Attempting to write to a closed connection!");
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    /// Write an unbind_resp, which smpp_pdu cannot represent as a Pdu.
+    pub async fn write_unbind_resp(
+        &self,
+        resp: &UnbindRespPdu,
+    ) -> io::Result<()> {
+        self.write_frame(&Frame::UnbindResp(resp.clone())).await
+    }
+
+    /// Write any frame, including those smpp_pdu cannot represent.
+    pub async fn write_frame(&self, frame: &Frame) -> io::Result<()> {
+        if let Frame::Pdu(pdu) = frame {
+            return self.write_pdu(pdu).await;
+        }
+        let mut buf: Vec<u8> = Vec::new();
+        frame.write(&mut buf).await?;
+        info!("=> {} {:?}", self, frame);
         if let Some(write) = &mut *self.write.lock().await {
-            pdu.write(&mut write.stream).await
+            if let Some(capture) = &mut *self.capture.lock().unwrap() {
+                capture.record(Direction::Sent, &buf);
+            }
+            if let Some(header) = Header::peek(&buf) {
+                self.stats.lock().unwrap().record_sent_frame(&header);
+            }
+            *self.idle_since.lock().unwrap() = TokioClock.now();
+            write.stream.write_all(&buf).await
         } else {
             error!("Attempting to write to a closed connection!");
             Err(io::ErrorKind::BrokenPipe.into())
@@ -97,8 +510,201 @@ impl SmppConnection {
     }
 }
 
+/// The start of a PDU whose remaining bytes have yet to arrive.
+#[derive(Clone, Copy, Debug)]
+pub struct PartialPdu {
+    /// When its first byte arrived
+    pub started: Instant,
+    /// How many of its bytes have arrived so far
+    pub received: usize,
+}
+
+/// One PDU read from a connection.  smpp_pdu cannot parse deliver_sm_resp,
+/// unbind, data_sm, query_sm, cancel_sm, replace_sm or submit_multi, nor
+/// the responses to the last six, nor alert_notification or outbind, so
+/// they are read separately.
+#[derive(Debug)]
+pub enum Frame {
+    Pdu(Pdu),
+    DeliverSmResp(DeliverSmRespPdu),
+    Unbind(UnbindPdu),
+    UnbindResp(UnbindRespPdu),
+    DataSm(DataSmPdu),
+    DataSmResp(DataSmRespPdu),
+    QuerySm(QuerySmPdu),
+    QuerySmResp(QuerySmRespPdu),
+    CancelSm(CancelSmPdu),
+    CancelSmResp(CancelSmRespPdu),
+    ReplaceSm(ReplaceSmPdu),
+    ReplaceSmResp(ReplaceSmRespPdu),
+    SubmitMulti(SubmitMultiPdu),
+    SubmitMultiResp(SubmitMultiRespPdu),
+    AlertNotification(AlertNotificationPdu),
+    Outbind(OutbindPdu),
+}
+
+impl Frame {
+    /// Parse bytes, which Pdu::check found to be exactly one PDU.
+    pub fn parse(bytes: &[u8]) -> Result<Frame, PduParseError> {
+        let command_id = Header::peek(bytes)
+            .map(|header| CommandId::try_from(header.command_id));
+        match command_id {
+            Some(Ok(CommandId::DeliverSmResp)) => {
+                DeliverSmRespPdu::parse(bytes).map(Frame::DeliverSmResp)
+            }
+            Some(Ok(CommandId::Unbind)) => {
+                UnbindPdu::parse(bytes).map(Frame::Unbind)
+            }
+            Some(Ok(CommandId::UnbindResp)) => {
+                UnbindRespPdu::parse(bytes).map(Frame::UnbindResp)
+            }
+            Some(Ok(CommandId::DataSm)) => {
+                DataSmPdu::parse(bytes).map(Frame::DataSm)
+            }
+            Some(Ok(CommandId::DataSmResp)) => {
+                DataSmRespPdu::parse(bytes).map(Frame::DataSmResp)
+            }
+            Some(Ok(CommandId::QuerySm)) => {
+                QuerySmPdu::parse(bytes).map(Frame::QuerySm)
+            }
+            Some(Ok(CommandId::QuerySmResp)) => {
+                QuerySmRespPdu::parse(bytes).map(Frame::QuerySmResp)
+            }
+            Some(Ok(CommandId::CancelSm)) => {
+                CancelSmPdu::parse(bytes).map(Frame::CancelSm)
+            }
+            Some(Ok(CommandId::CancelSmResp)) => {
+                CancelSmRespPdu::parse(bytes).map(Frame::CancelSmResp)
+            }
+            Some(Ok(CommandId::ReplaceSm)) => {
+                ReplaceSmPdu::parse(bytes).map(Frame::ReplaceSm)
+            }
+            Some(Ok(CommandId::ReplaceSmResp)) => {
+                ReplaceSmRespPdu::parse(bytes).map(Frame::ReplaceSmResp)
+            }
+            Some(Ok(CommandId::SubmitMulti)) => {
+                SubmitMultiPdu::parse(bytes).map(Frame::SubmitMulti)
+            }
+            Some(Ok(CommandId::SubmitMultiResp)) => {
+                SubmitMultiRespPdu::parse(bytes).map(Frame::SubmitMultiResp)
+            }
+            Some(Ok(CommandId::AlertNotification)) => {
+                AlertNotificationPdu::parse(bytes).map(Frame::AlertNotification)
+            }
+            Some(Ok(CommandId::Outbind)) => {
+                OutbindPdu::parse(bytes).map(Frame::Outbind)
+            }
+            _ => Pdu::parse(&mut Cursor::new(bytes)).map(Frame::Pdu),
+        }
+    }
+
+    /// Write this frame, header included.
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        match self {
+            Frame::Pdu(pdu) => write_pdu(pdu, stream).await.map(drop),
+            Frame::DeliverSmResp(resp) => resp.write(stream).await,
+            Frame::Unbind(unbind) => unbind.write(stream).await,
+            Frame::UnbindResp(resp) => resp.write(stream).await,
+            Frame::DataSm(data_sm) => data_sm.write(stream).await,
+            Frame::DataSmResp(resp) => resp.write(stream).await,
+            Frame::QuerySm(query_sm) => query_sm.write(stream).await,
+            Frame::QuerySmResp(resp) => resp.write(stream).await,
+            Frame::CancelSm(cancel_sm) => cancel_sm.write(stream).await,
+            Frame::CancelSmResp(resp) => resp.write(stream).await,
+            Frame::ReplaceSm(replace_sm) => replace_sm.write(stream).await,
+            Frame::ReplaceSmResp(resp) => resp.write(stream).await,
+            Frame::SubmitMulti(submit_multi) => {
+                submit_multi.write(stream).await
+            }
+            Frame::SubmitMultiResp(resp) => resp.write(stream).await,
+            Frame::AlertNotification(alert) => alert.write(stream).await,
+            Frame::Outbind(outbind) => outbind.write(stream).await,
+        }
+    }
+
+    pub fn command_id(&self) -> CommandId {
+        match self {
+            Frame::Pdu(pdu) => CommandId::from(pdu.body()),
+            Frame::DeliverSmResp(_) => CommandId::DeliverSmResp,
+            Frame::Unbind(_) => CommandId::Unbind,
+            Frame::UnbindResp(_) => CommandId::UnbindResp,
+            Frame::DataSm(_) => CommandId::DataSm,
+            Frame::DataSmResp(_) => CommandId::DataSmResp,
+            Frame::QuerySm(_) => CommandId::QuerySm,
+            Frame::QuerySmResp(_) => CommandId::QuerySmResp,
+            Frame::CancelSm(_) => CommandId::CancelSm,
+            Frame::CancelSmResp(_) => CommandId::CancelSmResp,
+            Frame::ReplaceSm(_) => CommandId::ReplaceSm,
+            Frame::ReplaceSmResp(_) => CommandId::ReplaceSmResp,
+            Frame::SubmitMulti(_) => CommandId::SubmitMulti,
+            Frame::SubmitMultiResp(_) => CommandId::SubmitMultiResp,
+            Frame::AlertNotification(_) => CommandId::AlertNotification,
+            Frame::Outbind(_) => CommandId::Outbind,
+        }
+    }
+
+    pub fn sequence_number(&self) -> u32 {
+        match self {
+            Frame::Pdu(pdu) => pdu.sequence_number.value,
+            Frame::DeliverSmResp(pdu) => pdu.sequence_number,
+            Frame::Unbind(pdu) => pdu.sequence_number,
+            Frame::UnbindResp(pdu) => pdu.sequence_number,
+            Frame::DataSm(pdu) => pdu.sequence_number,
+            Frame::DataSmResp(pdu) => pdu.sequence_number,
+            Frame::QuerySm(pdu) => pdu.sequence_number,
+            Frame::QuerySmResp(pdu) => pdu.sequence_number,
+            Frame::CancelSm(pdu) => pdu.sequence_number,
+            Frame::CancelSmResp(pdu) => pdu.sequence_number,
+            Frame::ReplaceSm(pdu) => pdu.sequence_number,
+            Frame::ReplaceSmResp(pdu) => pdu.sequence_number,
+            Frame::SubmitMulti(pdu) => pdu.sequence_number,
+            Frame::SubmitMultiResp(pdu) => pdu.sequence_number,
+            Frame::AlertNotification(pdu) => pdu.sequence_number,
+            Frame::Outbind(pdu) => pdu.sequence_number,
+        }
+    }
+
+    /// Number this frame afresh, e.g. to send it again.
+    pub fn set_sequence_number(&mut self, sequence_number: u32) {
+        match self {
+            Frame::Pdu(pdu) => pdu.sequence_number.value = sequence_number,
+            Frame::DeliverSmResp(pdu) => pdu.sequence_number = sequence_number,
+            Frame::Unbind(pdu) => pdu.sequence_number = sequence_number,
+            Frame::UnbindResp(pdu) => pdu.sequence_number = sequence_number,
+            Frame::DataSm(pdu) => pdu.sequence_number = sequence_number,
+            Frame::DataSmResp(pdu) => pdu.sequence_number = sequence_number,
+            Frame::QuerySm(pdu) => pdu.sequence_number = sequence_number,
+            Frame::QuerySmResp(pdu) => pdu.sequence_number = sequence_number,
+            Frame::CancelSm(pdu) => pdu.sequence_number = sequence_number,
+            Frame::CancelSmResp(pdu) => pdu.sequence_number = sequence_number,
+            Frame::ReplaceSm(pdu) => pdu.sequence_number = sequence_number,
+            Frame::ReplaceSmResp(pdu) => pdu.sequence_number = sequence_number,
+            Frame::SubmitMulti(pdu) => pdu.sequence_number = sequence_number,
+            Frame::SubmitMultiResp(pdu) => {
+                pdu.sequence_number = sequence_number
+            }
+            Frame::AlertNotification(pdu) => {
+                pdu.sequence_number = sequence_number
+            }
+            Frame::Outbind(pdu) => pdu.sequence_number = sequence_number,
+        }
+    }
+}
+
+/// Bytes counted by SmppConnection::queue()
+pub struct Queued<'a> {
+    queued_bytes: &'a AtomicUsize,
+    bytes: usize,
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.queued_bytes.fetch_sub(self.bytes, Ordering::Relaxed);
+    }
+}
+
 struct SmppRead {
-    stream: ReadHalf<TcpStream>,
+    stream: Box<dyn AsyncRead + Send + Unpin>,
     buffer: BytesMut,
 }
 
@@ -107,36 +713,53 @@ impl SmppRead {
         self.stream.read_buf(&mut self.buffer).await
     }
 
-    fn parse_pdu(&mut self) -> Result<Option<Pdu>, PduParseError> {
+    /// On failure, also returns the bytes of the offending frame.
+    fn parse_pdu(
+        &mut self,
+        capture: &std::sync::Mutex<Option<SessionCapture>>,
+        options: &ParseOptions,
+    ) -> Result<Option<Parsed>, (PduParseError, Vec<u8>)> {
         let mut buf = Cursor::new(&self.buffer[..]);
-        match Pdu::check(&mut buf) {
+        match options.check(&mut buf) {
             Ok(CheckOutcome::Ready) => {
-                // Pdu::check moved us to the end, so position is length
+                // check moved us to the end, so position is length
                 let len = buf.position() as usize;
 
-                // Rewind and parse
-                buf.set_position(0);
-                let pdu = Pdu::parse(&mut buf)?;
+                if let Some(capture) = &mut *capture.lock().unwrap() {
+                    capture.record(Direction::Received, &self.buffer[..len]);
+                }
+
+                let frame = &self.buffer[..len];
+                let pdu = options
+                    .parse_frame_with_warnings(frame)
+                    .map(Some)
+                    .map_err(|e| (e, Vec::from(frame)));
 
-                // Parsing succeeded, so consume bytes from buffer and return
+                // We know where this PDU ends, so consume its bytes from the
+                // buffer whether or not parsing succeeded.  This allows
+                // callers to carry on reading after a bad PDU.
                 self.buffer.advance(len);
-                Ok(Some(pdu))
+                pdu
             }
             // Try again when we have more
             Ok(CheckOutcome::Incomplete) => Ok(None),
-            // Failed (e.g. too long)
-            Err(e) => Err(e.into()),
-            // Issue#1: it would be good to respond with a specific error here,
-            // instead of generic_nack.  That should be possible in some cases
-            // if we can read the PDU header before we reject it.  It's not
-            // too bad to do this though, because the PDU is actually
-            // malformed, so not knowing what type it is is forgivable.
+            // Failed (e.g. too long).  If the whole header has arrived, the
+            // error carries it, so that the response can be of the right
+            // type and sequence_number rather than a generic_nack.
+            Err(e) => {
+                let e = match Header::peek(&self.buffer) {
+                    Some(header) => header.error(e),
+                    None => e,
+                };
+                let end = self.buffer.len().min(MAX_BAD_FRAME_LENGTH);
+                Err((e, Vec::from(&self.buffer[..end])))
+            }
         }
     }
 }
 
 struct SmppWrite {
-    stream: WriteHalf<TcpStream>,
+    stream: Box<dyn AsyncWrite + Send + Unpin>,
 }
 
 impl SmppWrite {}
@@ -0,0 +1,303 @@
+//! A small HTTP endpoint for operating the SMSC from scripts and load
+//! balancers.  Enabled with the admin-http feature and --admin-address.
+//!
+//! GET    /health                  {"status":"ok"}, or 503 if unhealthy
+//! GET    /health/report           bound sessions, enquire_link round
+//!                                 trips, pending responses, error rates
+//! GET    /sessions                bound sessions
+//! DELETE /sessions/SYSTEM_ID      close every session bound as SYSTEM_ID
+//! GET    /stats                   per-session statistics
+//! GET    /routes                  paused routes
+//! POST   /routes/PREFIX/pause     reject submit_sm to PREFIX...
+//! POST   /routes/PREFIX/resume    ...until this is called
+//!
+//! There is no authentication: anyone who can reach the endpoint can kick
+//! sessions and pause routes.  Bind it to localhost (e.g.
+//! --admin-address 127.0.0.1:8081), or to an interface only operators can
+//! reach.
+
+use log::*;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+
+use crate::health::SmscHealth;
+use crate::json::json_string;
+use crate::smsc::Smsc;
+
+/// We only expect short requests with no bodies.
+const MAX_REQUEST_LENGTH: usize = 8192;
+
+/// How long a client has to send its request headers before we hang up.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+const HEALTHY: &str = r#"{"status":"ok"}"#;
+const UNHEALTHY: &str = r#"{"status":"unhealthy"}"#;
+
+pub async fn listen(address: &str, smsc: Arc<Mutex<Smsc>>) -> io::Result<()> {
+    let listener = TcpListener::bind(address).await?;
+    info!("Admin endpoint bound on {}", address);
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(serve(stream, Arc::clone(&smsc)));
+                }
+                Err(e) => error!("Admin connection failed: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn serve(mut stream: TcpStream, smsc: Arc<Mutex<Smsc>>) {
+    let request_line =
+        tokio::time::timeout(REQUEST_TIMEOUT, read_request_line(&mut stream))
+            .await;
+    let response = match request_line {
+        Ok(Ok(Some(request_line))) => {
+            let mut parts = request_line.split(' ');
+            let method = parts.next().unwrap_or("");
+            let path = parts.next().unwrap_or("");
+            handle(method, path, &smsc).await
+        }
+        Ok(Ok(None)) => {
+            Response::new(400, "Bad Request", error_json("Bad request"))
+        }
+        Ok(Err(e)) => {
+            error!("Failed to read admin request: {}", e);
+            return;
+        }
+        Err(_) => {
+            warn!("Admin request not received within {:?}", REQUEST_TIMEOUT);
+            Response::new(408, "Request Timeout", error_json("Request timeout"))
+        }
+    };
+    if let Err(e) = stream.write_all(&response.to_bytes()).await {
+        error!("Failed to write admin response: {}", e);
+    }
+}
+
+/// Read the request headers, and return the first line, or None if the
+/// request is too long or not valid.
+async fn read_request_line(
+    stream: &mut TcpStream,
+) -> io::Result<Option<String>> {
+    let mut request: Vec<u8> = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 || request.len() + n > MAX_REQUEST_LENGTH {
+            return Ok(None);
+        }
+        request.extend(&buf[..n]);
+    }
+    Ok(std::str::from_utf8(&request)
+        .ok()
+        .and_then(|r| r.lines().next())
+        .map(String::from))
+}
+
+async fn handle(method: &str, path: &str, smsc: &Mutex<Smsc>) -> Response {
+    let segments: Vec<&str> =
+        path.split('/').filter(|s| !s.is_empty()).collect();
+    match (method, segments.as_slice()) {
+        ("GET", ["health"]) => {
+            if smsc.lock().await.is_healthy() {
+                Response::ok(String::from(HEALTHY))
+            } else {
+                Response::new(
+                    503,
+                    "Service Unavailable",
+                    String::from(UNHEALTHY),
+                )
+            }
+        }
+        ("GET", ["health", "report"]) => {
+            Response::ok(health_json(&smsc.lock().await.health()))
+        }
+        ("GET", ["sessions"]) => {
+            Response::ok(sessions_json(&*smsc.lock().await))
+        }
+        ("DELETE", ["sessions", system_id]) => {
+            match smsc.lock().await.kick(system_id) {
+                0 => Response::not_found(),
+                n => Response::ok(format!(r#"{{"kicked":{}}}"#, n)),
+            }
+        }
+        ("GET", ["stats"]) => Response::ok(stats_json(&*smsc.lock().await)),
+        ("GET", ["routes"]) => Response::ok(routes_json(&*smsc.lock().await)),
+        ("POST", ["routes", prefix, "pause"]) => {
+            let mut smsc = smsc.lock().await;
+            smsc.pause_route(prefix);
+            Response::ok(routes_json(&smsc))
+        }
+        ("POST", ["routes", prefix, "resume"]) => {
+            let mut smsc = smsc.lock().await;
+            smsc.resume_route(prefix);
+            Response::ok(routes_json(&smsc))
+        }
+        _ => Response::not_found(),
+    }
+}
+
+struct Response {
+    status: u16,
+    reason: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn new(status: u16, reason: &'static str, body: String) -> Self {
+        Self {
+            status,
+            reason,
+            body,
+        }
+    }
+
+    fn ok(body: String) -> Self {
+        Self::new(200, "OK", body)
+    }
+
+    fn not_found() -> Self {
+        Self::new(404, "Not Found", error_json("Not found"))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "HTTP/1.1 {} {}\r\n\
+            Content-Type: application/json\r\n\
+            Content-Length: {}\r\n\
+            Connection: close\r\n\
+            \r\n\
+            {}",
+            self.status,
+            self.reason,
+            self.body.len(),
+            self.body
+        )
+        .into_bytes()
+    }
+}
+
+fn error_json(message: &str) -> String {
+    format!(r#"{{"error":{}}}"#, json_string(message))
+}
+
+fn sessions_json(smsc: &Smsc) -> String {
+    let sessions: Vec<String> = smsc
+        .connections()
+        .iter()
+        .filter_map(|connection| {
+            let esme_id = connection.bound_esme_id()?;
+            Some(format!(
+                concat!(
+                    r#"{{"system_id":{},"system_type":{},"peer":{},"#,
+                    r#""session_id":{}}}"#,
+                ),
+                json_string(esme_id.system_id.as_str()),
+                json_string(esme_id.system_type.as_str()),
+                json_string(&connection.peer_addr.to_string()),
+                connection.session_id().0,
+            ))
+        })
+        .collect();
+    format!("[{}]", sessions.join(","))
+}
+
+fn stats_json(smsc: &Smsc) -> String {
+    let sessions: Vec<String> = smsc
+        .connections()
+        .iter()
+        .filter_map(|connection| {
+            let esme_id = connection.bound_esme_id()?;
+            let stats = connection.stats();
+            let errors: Vec<String> = stats
+                .errors
+                .iter()
+                .map(|(status, count)| {
+                    format!(r#""{:#010x}":{}"#, status, count)
+                })
+                .collect();
+            let last_activity_secs_ago = stats
+                .last_activity
+                .map(|t| t.elapsed().as_secs().to_string())
+                .unwrap_or_else(|| String::from("null"));
+            let average_resp_latency_ms = stats
+                .average_resp_latency()
+                .map(|d| d.as_millis().to_string())
+                .unwrap_or_else(|| String::from("null"));
+            Some(format!(
+                concat!(
+                    r#"{{"system_id":{},"system_type":{},"submits":{},"#,
+                    r#""deliveries":{},"errors":{{{}}},"#,
+                    r#""last_activity_secs_ago":{},"#,
+                    r#""average_resp_latency_ms":{},"session_id":{}}}"#,
+                ),
+                json_string(esme_id.system_id.as_str()),
+                json_string(esme_id.system_type.as_str()),
+                stats.submits,
+                stats.deliveries,
+                errors.join(","),
+                last_activity_secs_ago,
+                average_resp_latency_ms,
+                connection.session_id().0,
+            ))
+        })
+        .collect();
+    format!("[{}]", sessions.join(","))
+}
+
+fn health_json(health: &SmscHealth) -> String {
+    let optional_ms = |d: Option<Duration>| {
+        d.map(|d| d.as_millis().to_string())
+            .unwrap_or_else(|| String::from("null"))
+    };
+    let sessions: Vec<String> = health
+        .sessions
+        .iter()
+        .map(|session| {
+            format!(
+                concat!(
+                    r#"{{"system_id":{},"system_type":{},"#,
+                    r#""last_enquire_link_rtt_ms":{},"#,
+                    r#""pending_responses":{},"recent_responses":{},"#,
+                    r#""recent_errors":{},"recent_system_errors":{}}}"#,
+                ),
+                json_string(&session.system_id),
+                json_string(&session.system_type),
+                optional_ms(session.last_enquire_link_rtt),
+                session.pending_responses,
+                session.recent_responses,
+                session.recent_errors,
+                session.recent_system_errors,
+            )
+        })
+        .collect();
+    format!(
+        concat!(
+            r#"{{"healthy":{},"bound_sessions":{},"pending_responses":{},"#,
+            r#""recent_error_rate":{},"recent_system_error_rate":{},"#,
+            r#""sessions":[{}]}}"#,
+        ),
+        health.is_healthy(),
+        health.bound_sessions(),
+        health.pending_responses(),
+        health.recent_error_rate(),
+        health.recent_system_error_rate(),
+        sessions.join(","),
+    )
+}
+
+fn routes_json(smsc: &Smsc) -> String {
+    let paused: Vec<String> = smsc
+        .paused_routes()
+        .iter()
+        .map(|p| json_string(p))
+        .collect();
+    format!(r#"{{"paused":[{}]}}"#, paused.join(","))
+}
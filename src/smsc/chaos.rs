@@ -0,0 +1,180 @@
+//! Random misbehaviour for soak-testing ESMEs against the simulator:
+//! response latency, bursts of ESME_RTHROTTLED and forced unbinds.
+
+use smpp_pdu::pdu::{Pdu, PduStatus, SubmitSmRespPdu};
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::command_id::CommandId;
+use crate::smsc::SmscConfig;
+
+/// How long to wait before sending each response, in milliseconds.
+/// Written fixed:MS, uniform:MIN-MAX or exponential:MEAN.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Latency {
+    Fixed(u64),
+    Uniform(u64, u64),
+    Exponential(u64),
+}
+
+#[derive(Debug)]
+pub struct ParseLatencyError(String);
+
+impl Display for ParseLatencyError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "Invalid latency '{}': expected fixed:MS, uniform:MIN-MAX or \
+            exponential:MEAN, e.g. uniform:50-200",
+            self.0
+        )
+    }
+}
+
+impl error::Error for ParseLatencyError {}
+
+impl FromStr for Latency {
+    type Err = ParseLatencyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseLatencyError(String::from(s));
+        let (kind, ms) = s.split_once(':').ok_or_else(err)?;
+        let parse = |ms: &str| ms.parse::<u64>().map_err(|_| err());
+        match kind {
+            "fixed" => Ok(Self::Fixed(parse(ms)?)),
+            "uniform" => {
+                let (min, max) = ms.split_once('-').ok_or_else(err)?;
+                let (min, max) = (parse(min)?, parse(max)?);
+                if min > max {
+                    return Err(err());
+                }
+                Ok(Self::Uniform(min, max))
+            }
+            "exponential" => Ok(Self::Exponential(parse(ms)?)),
+            _ => Err(err()),
+        }
+    }
+}
+
+impl Latency {
+    fn sample(&self, rng: &mut Rng) -> Duration {
+        let ms = match *self {
+            Self::Fixed(ms) => ms as f64,
+            Self::Uniform(min, max) => {
+                min as f64 + rng.next_f64() * (max - min) as f64
+            }
+            Self::Exponential(mean) => {
+                -(mean as f64) * (1.0 - rng.next_f64()).ln()
+            }
+        };
+        Duration::from_micros((ms * 1000.0) as u64)
+    }
+}
+
+/// xorshift64*, which is plenty for picking delays and failures.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // xorshift never leaves 0
+        Self(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    /// Between 0 (inclusive) and 1 (exclusive)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// The chaos options from an SmscConfig, and the random numbers that
+/// drive them, shared by every connection.
+pub struct Chaos {
+    latency: Option<Latency>,
+    throttle_burst_percent: f64,
+    throttle_burst_length: u32,
+    rng: Mutex<Rng>,
+}
+
+impl Chaos {
+    pub fn new(config: &SmscConfig) -> Self {
+        let seed = config.chaos_seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(1, |d| d.as_nanos() as u64)
+        });
+        Self {
+            latency: config.latency,
+            throttle_burst_percent: config.throttle_burst_percent,
+            throttle_burst_length: config.throttle_burst_length,
+            rng: Mutex::new(Rng::new(seed)),
+        }
+    }
+
+    /// How long to wait before sending the next response
+    pub fn latency(&self) -> Option<Duration> {
+        let latency = self.latency?;
+        Some(latency.sample(&mut self.rng.lock().unwrap()))
+    }
+
+    fn starts_burst(&self) -> bool {
+        self.throttle_burst_percent > 0.0
+            && self.rng.lock().unwrap().next_f64() * 100.0
+                < self.throttle_burst_percent
+    }
+}
+
+/// Where one connection has got to in a burst of ESME_RTHROTTLED.
+#[derive(Default)]
+pub struct ChaosSession {
+    throttled_remaining: u32,
+}
+
+impl ChaosSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An ESME_RTHROTTLED response to send instead of handling pdu, if it
+    /// is a submit_sm that falls in a burst.
+    pub fn receive(&mut self, chaos: &Chaos, pdu: &Pdu) -> Option<Pdu> {
+        let command_status =
+            self.receive_command(chaos, CommandId::from(pdu.body()))?;
+        Pdu::new(
+            command_status,
+            pdu.sequence_number.value,
+            SubmitSmRespPdu::new_error().into(),
+        )
+        .ok()
+    }
+
+    /// As receive(), for a request of any type, including data_sm and
+    /// submit_multi: ESME_RTHROTTLED if it is a submit that falls in a
+    /// burst.
+    pub fn receive_command(
+        &mut self,
+        chaos: &Chaos,
+        command_id: CommandId,
+    ) -> Option<u32> {
+        if !command_id.is_submit() {
+            return None;
+        }
+        if self.throttled_remaining == 0 && chaos.starts_burst() {
+            self.throttled_remaining = chaos.throttle_burst_length;
+        }
+        if self.throttled_remaining == 0 {
+            return None;
+        }
+        self.throttled_remaining -= 1;
+        Some(PduStatus::ESME_RTHROTTLED as u32)
+    }
+}
@@ -0,0 +1,129 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::Instant;
+
+use crate::clock::{Clock, TokioClock};
+
+/// A limit on how many messages per second we accept for destination
+/// addresses starting with prefix.  Written as PREFIX=RATE, e.g. "4477=50".
+#[derive(Clone, Debug, PartialEq)]
+pub struct DestinationLimit {
+    pub prefix: String,
+    pub per_second: u32,
+}
+
+#[derive(Debug)]
+pub struct ParseDestinationLimitError(String);
+
+impl Display for ParseDestinationLimitError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "Invalid destination limit '{}': expected PREFIX=RATE, \
+            e.g. 4477=50",
+            self.0
+        )
+    }
+}
+
+impl error::Error for ParseDestinationLimitError {}
+
+impl FromStr for DestinationLimit {
+    type Err = ParseDestinationLimitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseDestinationLimitError(String::from(s));
+        let (prefix, per_second) = s.split_once('=').ok_or_else(err)?;
+        let per_second = per_second.parse().map_err(|_| err())?;
+        if per_second == 0 {
+            return Err(err());
+        }
+        Ok(Self {
+            prefix: String::from(prefix),
+            per_second,
+        })
+    }
+}
+
+/// Token buckets, one per DestinationLimit, so that traffic to one
+/// congested network cannot use up all our capacity.
+pub struct DestinationThrottle {
+    /// Longest prefix first, so the most specific limit applies
+    limits: Vec<DestinationLimit>,
+    buckets: HashMap<String, Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl DestinationThrottle {
+    pub fn new(limits: &[DestinationLimit]) -> Self {
+        let mut limits = Vec::from(limits);
+        limits.sort_by_key(|limit| Reverse(limit.prefix.len()));
+        Self {
+            limits,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Record a message to destination_addr, returning false if that takes
+    /// its destination over its limit, in which case it should be rejected.
+    pub fn try_acquire(&mut self, destination_addr: &str) -> bool {
+        self.try_acquire_at(destination_addr, TokioClock.now())
+    }
+
+    pub fn try_acquire_at(
+        &mut self,
+        destination_addr: &str,
+        now: Instant,
+    ) -> bool {
+        let limit = match self
+            .limits
+            .iter()
+            .find(|limit| destination_addr.starts_with(&limit.prefix))
+        {
+            Some(limit) => limit,
+            None => return true,
+        };
+
+        let capacity = f64::from(limit.per_second);
+        let bucket =
+            self.buckets.entry(limit.prefix.clone()).or_insert(Bucket {
+                tokens: capacity,
+                updated: now,
+            });
+        let elapsed = now.saturating_duration_since(bucket.updated);
+        bucket.tokens =
+            (bucket.tokens + elapsed.as_secs_f64() * capacity).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Give back the token try_acquire took for a message to
+    /// destination_addr that was rejected for another reason.
+    pub fn release(&mut self, destination_addr: &str) {
+        let limit = match self
+            .limits
+            .iter()
+            .find(|limit| destination_addr.starts_with(&limit.prefix))
+        {
+            Some(limit) => limit,
+            None => return,
+        };
+        if let Some(bucket) = self.buckets.get_mut(&limit.prefix) {
+            bucket.tokens =
+                (bucket.tokens + 1.0).min(f64::from(limit.per_second));
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, TokioClock};
+
+/// What makes two submit_sm the same message: the ESME, the source and
+/// destination addresses, and a hash of the content and its data_coding.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SubmitFingerprint {
+    pub system_id: String,
+    pub source_addr: String,
+    pub destination_addr: String,
+    pub content_hash: u64,
+}
+
+impl SubmitFingerprint {
+    pub fn new(
+        system_id: &str,
+        source_addr: &str,
+        destination_addr: &str,
+        data_coding: u8,
+        message: &[u8],
+    ) -> Self {
+        let mut hasher = DefaultHasher::new();
+        data_coding.hash(&mut hasher);
+        message.hash(&mut hasher);
+        Self {
+            system_id: String::from(system_id),
+            source_addr: String::from(source_addr),
+            destination_addr: String::from(destination_addr),
+            content_hash: hasher.finish(),
+        }
+    }
+}
+
+/// The message_ids of submit_sm accepted within the last window, so that an
+/// ESME resubmitting one (usually a retry bug) can be spotted.
+pub struct DuplicateWindow {
+    window: Duration,
+    accepted: HashMap<SubmitFingerprint, Accepted>,
+    /// Oldest first, for expiry
+    order: VecDeque<(Instant, SubmitFingerprint)>,
+}
+
+struct Accepted {
+    at: Instant,
+    message_id: String,
+}
+
+impl DuplicateWindow {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            accepted: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// The message_id of a submit_sm with fingerprint accepted within the
+    /// window, if there was one.
+    pub fn duplicate_of(
+        &mut self,
+        fingerprint: &SubmitFingerprint,
+    ) -> Option<String> {
+        self.duplicate_of_at(fingerprint, TokioClock.now())
+    }
+
+    pub fn duplicate_of_at(
+        &mut self,
+        fingerprint: &SubmitFingerprint,
+        now: Instant,
+    ) -> Option<String> {
+        self.expire(now);
+        self.accepted
+            .get(fingerprint)
+            .map(|accepted| accepted.message_id.clone())
+    }
+
+    /// Remember that a submit_sm with fingerprint was accepted as
+    /// message_id.
+    pub fn record(&mut self, fingerprint: SubmitFingerprint, message_id: &str) {
+        self.record_at(fingerprint, message_id, TokioClock.now())
+    }
+
+    pub fn record_at(
+        &mut self,
+        fingerprint: SubmitFingerprint,
+        message_id: &str,
+        now: Instant,
+    ) {
+        self.expire(now);
+        self.order.push_back((now, fingerprint.clone()));
+        self.accepted.insert(
+            fingerprint,
+            Accepted {
+                at: now,
+                message_id: String::from(message_id),
+            },
+        );
+    }
+
+    /// How many submit_sm are remembered.
+    pub fn len(&self) -> usize {
+        self.accepted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accepted.is_empty()
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some((at, _)) = self.order.front() {
+            if now.saturating_duration_since(*at) < self.window {
+                break;
+            }
+            let (at, fingerprint) = self.order.pop_front().unwrap();
+            // Unless it was accepted again since
+            if self.accepted.get(&fingerprint).is_some_and(|a| a.at == at) {
+                self.accepted.remove(&fingerprint);
+            }
+        }
+    }
+}
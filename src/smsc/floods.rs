@@ -0,0 +1,155 @@
+//! Protection against chatty peers: ESMEs polling query_sm for the same
+//! message over and over, or sending enquire_link far more often than
+//! anyone needs to.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, TokioClock};
+use crate::query_sm::{QuerySmPdu, QuerySmRespPdu};
+
+/// What makes two query_sm the same question: the ESME asking, and the
+/// message they ask after.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct QueryKey {
+    pub system_id: String,
+    pub message_id: String,
+    pub source_addr_ton: u8,
+    pub source_addr_npi: u8,
+    pub source_addr: String,
+}
+
+impl QueryKey {
+    pub fn new(system_id: &str, query_sm: &QuerySmPdu) -> Self {
+        Self {
+            system_id: String::from(system_id),
+            message_id: String::from(query_sm.message_id.value.as_str()),
+            source_addr_ton: query_sm.source_addr_ton,
+            source_addr_npi: query_sm.source_addr_npi,
+            source_addr: String::from(query_sm.source_addr.value.as_str()),
+        }
+    }
+}
+
+/// Answers to query_sm from the last ttl, so that repeating one does not
+/// reach SmscLogic (and whatever store it looks in) again.
+pub struct QueryCache {
+    ttl: Duration,
+    answers: HashMap<QueryKey, QuerySmRespPdu>,
+    /// Oldest first, for expiry
+    order: VecDeque<(Instant, QueryKey)>,
+}
+
+impl QueryCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            answers: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// The answer to key given within the ttl, if there was one.  Its
+    /// sequence_number is that of the query it answered.
+    pub fn get(&mut self, key: &QueryKey) -> Option<QuerySmRespPdu> {
+        self.get_at(key, TokioClock.now())
+    }
+
+    pub fn get_at(
+        &mut self,
+        key: &QueryKey,
+        now: Instant,
+    ) -> Option<QuerySmRespPdu> {
+        self.expire(now);
+        self.answers.get(key).cloned()
+    }
+
+    pub fn insert(&mut self, key: QueryKey, resp: QuerySmRespPdu) {
+        self.insert_at(key, resp, TokioClock.now())
+    }
+
+    pub fn insert_at(
+        &mut self,
+        key: QueryKey,
+        resp: QuerySmRespPdu,
+        now: Instant,
+    ) {
+        self.expire(now);
+        if self.answers.insert(key.clone(), resp).is_none() {
+            self.order.push_back((now, key));
+        }
+    }
+
+    /// Drop every answer about message_id, e.g. because it was cancelled
+    /// or replaced.
+    pub fn forget(&mut self, message_id: &str) {
+        self.answers.retain(|key, _| key.message_id != message_id);
+        let answers = &self.answers;
+        self.order.retain(|(_, key)| answers.contains_key(key));
+    }
+
+    pub fn clear(&mut self) {
+        self.answers.clear();
+        self.order.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.answers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.answers.is_empty()
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some((at, _)) = self.order.front() {
+            if now.duration_since(*at) < self.ttl {
+                break;
+            }
+            if let Some((_, key)) = self.order.pop_front() {
+                self.answers.remove(&key);
+            }
+        }
+    }
+}
+
+/// Counts one connection's enquire_links in each second, allowing up to a
+/// limit.
+pub struct EnquireLinkLimit {
+    per_sec: u32,
+    second_started: Option<Instant>,
+    count: u32,
+}
+
+impl EnquireLinkLimit {
+    pub fn new(per_sec: u32) -> Self {
+        Self {
+            per_sec,
+            second_started: None,
+            count: 0,
+        }
+    }
+
+    /// Count an enquire_link, and return whether it is within the limit.
+    pub fn allow(&mut self) -> bool {
+        self.allow_at(TokioClock.now())
+    }
+
+    pub fn allow_at(&mut self, now: Instant) -> bool {
+        let in_this_second = self.second_started.is_some_and(|started| {
+            now.duration_since(started) < Duration::from_secs(1)
+        });
+        if !in_this_second {
+            self.second_started = Some(now);
+            self.count = 0;
+        }
+        self.count += 1;
+        self.count <= self.per_sec
+    }
+
+    /// Whether the enquire_link just counted was the first over the limit
+    /// this second, e.g. to warn once per flood.
+    pub fn just_exceeded(&self) -> bool {
+        self.count == self.per_sec + 1
+    }
+}
@@ -0,0 +1,79 @@
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::{DeliverSmPdu, Pdu, PduBody};
+
+use crate::pdu_clone::PduClone;
+use crate::typed_tlvs::TypedTlvs;
+
+/// Maps the message_ids SmscLogic::submit_sm returns to the ones ESMEs see,
+/// and back, e.g. so that ESMEs cannot tell how many messages we handle
+/// from sequential store IDs.  Register with Smsc::set_message_id_map().
+pub trait MessageIdMap {
+    /// The message_id to send in submit_sm_resp and delivery receipts
+    /// instead of internal.  At most 64 characters.
+    fn external(&self, internal: &str) -> String;
+
+    /// The internal message_id that external() turned into external, or
+    /// None if it did not come from external().  For operations where the
+    /// ESME names a message, such as query_sm.
+    fn internal(&self, external: &str) -> Option<String>;
+}
+
+/// A copy of a delivery receipt for internal with its message_id replaced
+/// by external, in both the receipted_message_id TLV and the "id:" field
+/// of the text, wherever they appear.
+pub(crate) fn replace_receipted_message_id(
+    pdu: &Pdu,
+    internal: &str,
+    external: &str,
+) -> Pdu {
+    let body = match pdu.body() {
+        PduBody::DeliverSm(body) => body,
+        _ => return pdu.pdu_clone(),
+    };
+    let mut sm_data = body.0.pdu_clone();
+
+    let tlvs: Vec<Tlv> = sm_data
+        .tlvs
+        .to_vec()
+        .into_iter()
+        .map(|tlv| {
+            if tlv.raw_tag == KnownTlvTag::receipted_message_id as u16 {
+                let mut value = Vec::from(external.as_bytes());
+                value.push(0);
+                Tlv::new(KnownTlvTag::receipted_message_id, &value)
+            } else {
+                tlv
+            }
+        })
+        .collect();
+    sm_data.tlvs = Tlvs::from(&tlvs);
+
+    let text = &sm_data.short_message.value;
+    if let Some(start) = id_field(text, internal) {
+        let mut replaced = Vec::from(&text[..start]);
+        replaced.extend(external.as_bytes());
+        replaced.extend(&text[start + internal.len()..]);
+        sm_data.short_message.value = replaced;
+    }
+
+    Pdu::new(
+        pdu.command_status.value,
+        pdu.sequence_number.value,
+        DeliverSmPdu(sm_data).into(),
+    )
+    .unwrap_or_else(|_| pdu.pdu_clone())
+}
+
+/// Where the value of an "id:" field equal to message_id starts in text
+fn id_field(text: &[u8], message_id: &str) -> Option<usize> {
+    let value = message_id.as_bytes();
+    let is_word = |c: u8| c.is_ascii_alphanumeric() || c == b'_';
+    (3..=text.len()).find(|&start| {
+        text[start - 3..start].eq_ignore_ascii_case(b"id:")
+            && (start == 3 || !is_word(text[start - 4]))
+            && text[start..].starts_with(value)
+            && text
+                .get(start + value.len())
+                .map_or(true, u8::is_ascii_whitespace)
+    })
+}
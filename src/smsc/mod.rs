@@ -1,9 +1,35 @@
+#[cfg(feature = "admin-http")]
+mod admin_http;
+pub mod chaos;
+pub mod destination_limits;
+pub mod duplicates;
+pub mod floods;
+pub mod message_id_map;
+pub mod replication;
+pub mod scenario;
+#[allow(clippy::module_inception)]
 pub mod smsc;
 pub mod smsc_config;
 pub mod smsc_logic;
+pub mod source_quotas;
+pub mod submit_sm_archive;
 
+pub use chaos::{Chaos, ChaosSession, Latency};
+pub use destination_limits::{DestinationLimit, DestinationThrottle};
+pub use duplicates::{DuplicateWindow, SubmitFingerprint};
+pub use floods::{EnquireLinkLimit, QueryCache, QueryKey};
+pub use message_id_map::MessageIdMap;
+pub use replication::{ParseReplicationStateError, ReplicationState};
+pub use scenario::{Scenario, ScenarioRule, ScenarioSession};
 pub use smpp_pdu::pdu::data::bind_data::BindData;
 pub use smpp_pdu::pdu::data::bind_resp_data::BindRespData;
 pub use smsc::{run, Smsc};
-pub use smsc_config::SmscConfig;
-pub use smsc_logic::{BindError, SmscLogic, SubmitSmError};
+pub use smsc_config::{SmscConfig, UnknownCommandAction};
+pub use smsc_logic::{
+    BindError, CancelSmError, QuerySmError, ReplaceSmError, SmscLogic,
+    SubmitSmError,
+};
+pub use source_quotas::{
+    QuotaExceeded, SourceQuota, SourceQuotaAlerts, SourceQuotas,
+};
+pub use submit_sm_archive::SubmitSmArchive;
@@ -0,0 +1,170 @@
+//! Just enough state to fail an SMSC over to a warm standby.
+//!
+//! Connections cannot move, so ESMEs must bind again to the standby.  What
+//! the standby needs from the primary is which ESME each message came from,
+//! so that their delivery receipts still reach the right place, and which
+//! routes were paused.  Message content and states belong to SmscLogic and
+//! any SubmitSmArchive or MessageIdMap, which must be replicated in their
+//! own way.  Receipts for messages accepted after the last export are lost.
+//!
+//! The text form has one record per line, fields separated by tabs:
+//!
+//! ```text
+//! bound   SYSTEM_ID   SYSTEM_TYPE
+//! message NAMESPACE_ID    MESSAGE_ID  DESTINATION_ADDR    SYSTEM_ID   SYSTEM_TYPE
+//! paused  PREFIX
+//! ```
+//!
+//! with backslash, tab and newline in fields written as \\, \t and \n.
+
+use ascii::AsciiString;
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::message_unique_key::MessageUniqueKey;
+use crate::smpp_connection::EsmeId;
+
+/// Exported by Smsc::export_state() and given to Smsc::import_state().
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplicationState {
+    /// Accounts bound when the state was exported, which should bind to
+    /// the standby
+    pub bound_accounts: Vec<EsmeId>,
+    /// Which ESME submitted each message, to route its receipt
+    pub messages: Vec<(MessageUniqueKey, EsmeId)>,
+    pub paused_routes: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ParseReplicationStateError {
+    /// Counting from 1
+    pub line_number: usize,
+    pub line: String,
+}
+
+impl Display for ParseReplicationStateError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "Invalid replication state on line {}: '{}'",
+            self.line_number, self.line
+        )
+    }
+}
+
+impl error::Error for ParseReplicationStateError {}
+
+impl Display for ReplicationState {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        for esme_id in &self.bound_accounts {
+            write_record(
+                formatter,
+                &[
+                    "bound",
+                    esme_id.system_id.as_str(),
+                    esme_id.system_type.as_str(),
+                ],
+            )?;
+        }
+        for (key, esme_id) in &self.messages {
+            write_record(
+                formatter,
+                &[
+                    "message",
+                    &key.namespace_id,
+                    &key.message_id,
+                    &key.destination_addr,
+                    esme_id.system_id.as_str(),
+                    esme_id.system_type.as_str(),
+                ],
+            )?;
+        }
+        for prefix in &self.paused_routes {
+            write_record(formatter, &["paused", prefix])?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ReplicationState {
+    type Err = ParseReplicationStateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut state = Self::default();
+        for (i, line) in s.lines().enumerate() {
+            let err = || ParseReplicationStateError {
+                line_number: i + 1,
+                line: String::from(line),
+            };
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<String> = line.split('\t').map(unescape).collect();
+            let esme_id = |system_id: &str, system_type: &str| {
+                Ok(EsmeId {
+                    system_id: AsciiString::from_ascii(system_id)
+                        .map_err(|_| err())?,
+                    system_type: AsciiString::from_ascii(system_type)
+                        .map_err(|_| err())?,
+                })
+            };
+            match &fields[..] {
+                [kind, system_id, system_type] if kind == "bound" => {
+                    state.bound_accounts.push(esme_id(system_id, system_type)?)
+                }
+                [kind, namespace_id, message_id, destination_addr, system_id, system_type]
+                    if kind == "message" =>
+                {
+                    state.messages.push((
+                        MessageUniqueKey::new(
+                            namespace_id.clone(),
+                            message_id.clone(),
+                            destination_addr.clone(),
+                        ),
+                        esme_id(system_id, system_type)?,
+                    ))
+                }
+                [kind, prefix] if kind == "paused" => {
+                    state.paused_routes.push(prefix.clone())
+                }
+                _ => return Err(err()),
+            }
+        }
+        Ok(state)
+    }
+}
+
+fn write_record(
+    formatter: &mut Formatter,
+    fields: &[&str],
+) -> std::fmt::Result {
+    let fields: Vec<String> = fields.iter().map(|f| escape(f)).collect();
+    writeln!(formatter, "{}", fields.join("\t"))
+}
+
+fn escape(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn unescape(field: &str) -> String {
+    let mut ret = String::new();
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        ret.push(match (c, chars.clone().next()) {
+            ('\\', Some(next @ ('\\' | 't' | 'n'))) => {
+                chars.next();
+                match next {
+                    't' => '\t',
+                    'n' => '\n',
+                    _ => '\\',
+                }
+            }
+            (c, _) => c,
+        });
+    }
+    ret
+}
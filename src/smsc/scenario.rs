@@ -0,0 +1,317 @@
+//! Scripted behaviour for the simulator, so that an ESME's error handling
+//! can be exercised deterministically.
+//!
+//! A scenario is a list of rules, one per line, each starting with the
+//! system_id of the account it applies to, or * for every account:
+//!
+//! ```text
+//! # Reject every third submit_sm from esme1
+//! esme1 status ESME_RTHROTTLED every 3
+//! # Wait before sending every response
+//! *     delay 250ms
+//! # Close the connection once it has sent 10 PDUs
+//! esme2 drop after 10
+//! # Send a delivery receipt 5 seconds after accepting each submit_sm
+//! esme2 dlr UNDELIV after 5s
+//! ```
+//!
+//! A rule for a named account replaces a * rule of the same kind.  Counts
+//! start again on each new connection.
+
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::{
+    DeliverEsmClass, DeliverSmPdu, Pdu, PduBody, SubmitSmPdu, SubmitSmRespPdu,
+};
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::mem::discriminant;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::command_id::CommandId;
+use crate::delivery_receipt::ReceiptText;
+use crate::pdu_status::parse_status;
+use crate::query_sm::MessageState;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScenarioRule {
+    /// Respond to every Nth submit_sm, data_sm or submit_multi with this
+    /// command_status, without passing it to the SmscLogic.
+    Status { command_status: u32, every: u32 },
+    /// Wait this long before sending each response
+    Delay(Duration),
+    /// Close the connection after receiving this many PDUs
+    DropAfter(u32),
+    /// Send a delivery receipt with stat:state this long after accepting
+    /// each submit_sm
+    Dlr { state: String, after: Duration },
+}
+
+#[derive(Debug)]
+pub struct ParseScenarioError(String);
+
+impl Display for ParseScenarioError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "Invalid scenario rule '{}': expected ACCOUNT followed by \
+            'status STATUS every N', 'delay DURATION', 'drop after N' or \
+            'dlr STATE after DURATION'",
+            self.0
+        )
+    }
+}
+
+impl error::Error for ParseScenarioError {}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Scenario {
+    rules: Vec<(String, ScenarioRule)>,
+}
+
+impl FromStr for Scenario {
+    type Err = ParseScenarioError;
+
+    /// Blank lines and lines starting with # are ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = Vec::new();
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = || ParseScenarioError(String::from(line));
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let rule = match words[1..] {
+                ["status", status, "every", every] => ScenarioRule::Status {
+                    command_status: parse_status(status).ok_or_else(err)?,
+                    every: parse_positive(every).ok_or_else(err)?,
+                },
+                ["delay", delay] => {
+                    ScenarioRule::Delay(parse_duration(delay).ok_or_else(err)?)
+                }
+                ["drop", "after", count] => ScenarioRule::DropAfter(
+                    parse_positive(count).ok_or_else(err)?,
+                ),
+                ["dlr", state, "after", after] => ScenarioRule::Dlr {
+                    state: MessageState::from_stat(state)
+                        .map(|_| state.to_ascii_uppercase())
+                        .ok_or_else(err)?,
+                    after: parse_duration(after).ok_or_else(err)?,
+                },
+                _ => return Err(err()),
+            };
+            rules.push((String::from(words[0]), rule));
+        }
+        Ok(Self { rules })
+    }
+}
+
+impl Scenario {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The rules that apply to an ESME bound as system_id, or only the *
+    /// rules if it is not bound yet.
+    pub fn rules_for(&self, system_id: Option<&str>) -> Vec<&ScenarioRule> {
+        let own: Vec<&ScenarioRule> = self
+            .rules
+            .iter()
+            .filter(|(account, _)| Some(account.as_str()) == system_id)
+            .map(|(_, rule)| rule)
+            .collect();
+        let replaced =
+            |rule: &ScenarioRule| own.iter().any(|r| same_kind(r, rule));
+        let mut ret: Vec<&ScenarioRule> = self
+            .rules
+            .iter()
+            .filter(|(account, rule)| account == "*" && !replaced(rule))
+            .map(|(_, rule)| rule)
+            .collect();
+        ret.extend(own.iter());
+        ret
+    }
+}
+
+/// Where one connection has got to in a Scenario.
+pub struct ScenarioSession {
+    scenario: Arc<Scenario>,
+    pdus_received: u32,
+    submits_received: u32,
+}
+
+impl ScenarioSession {
+    pub fn new(scenario: Arc<Scenario>) -> Self {
+        Self {
+            scenario,
+            pdus_received: 0,
+            submits_received: 0,
+        }
+    }
+
+    /// Count pdu, and return the response to send instead of handling it
+    /// normally, if a status rule says so.
+    pub fn receive(
+        &mut self,
+        system_id: Option<&str>,
+        pdu: &Pdu,
+    ) -> Option<Pdu> {
+        let command_status =
+            self.receive_command(system_id, CommandId::from(pdu.body()))?;
+        Pdu::new(
+            command_status,
+            pdu.sequence_number.value,
+            SubmitSmRespPdu::new_error().into(),
+        )
+        .ok()
+    }
+
+    /// As receive(), for a request of any type, including those smpp_pdu
+    /// cannot represent, such as data_sm: the command_status to respond
+    /// with instead of handling it, if a status rule says so.
+    pub fn receive_command(
+        &mut self,
+        system_id: Option<&str>,
+        command_id: CommandId,
+    ) -> Option<u32> {
+        self.pdus_received += 1;
+        if !command_id.is_submit() {
+            return None;
+        }
+        self.submits_received += 1;
+        self.scenario
+            .rules_for(system_id)
+            .into_iter()
+            .find_map(|rule| match rule {
+                ScenarioRule::Status {
+                    command_status,
+                    every,
+                } if self.submits_received % *every == 0 => {
+                    Some(*command_status)
+                }
+                _ => None,
+            })
+    }
+
+    /// How long to wait before sending a response
+    pub fn delay(&self, system_id: Option<&str>) -> Option<Duration> {
+        self.scenario
+            .rules_for(system_id)
+            .into_iter()
+            .find_map(|rule| match rule {
+                ScenarioRule::Delay(delay) => Some(*delay),
+                _ => None,
+            })
+    }
+
+    /// Have we received as many PDUs as we are allowed to before closing
+    /// the connection?
+    pub fn should_drop(&self, system_id: Option<&str>) -> bool {
+        self.scenario
+            .rules_for(system_id)
+            .into_iter()
+            .any(|rule| match rule {
+                ScenarioRule::DropAfter(count) => self.pdus_received >= *count,
+                _ => false,
+            })
+    }
+
+    /// The delivery receipts to send, and how long to wait before sending
+    /// each, after we sent response to request.
+    pub fn receipts(
+        &self,
+        system_id: Option<&str>,
+        request: &Pdu,
+        response: &Pdu,
+    ) -> Vec<(Duration, DeliverSmPdu)> {
+        let (submit_sm, message_id) = match (request.body(), response.body()) {
+            (PduBody::SubmitSm(submit_sm), PduBody::SubmitSmResp(resp))
+                if response.command_status.value == 0 =>
+            {
+                match resp.message_id() {
+                    Some(message_id) => (submit_sm, message_id),
+                    None => return vec![],
+                }
+            }
+            _ => return vec![],
+        };
+        self.scenario
+            .rules_for(system_id)
+            .into_iter()
+            .filter_map(|rule| match rule {
+                ScenarioRule::Dlr { state, after } => Some((
+                    *after,
+                    delivery_receipt(submit_sm, &message_id, state),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// A deliver_sm reporting that the message with message_id, sent as
+/// submit_sm, reached state.
+pub fn delivery_receipt(
+    submit_sm: &SubmitSmPdu,
+    message_id: &str,
+    state: &str,
+) -> DeliverSmPdu {
+    let delivered = if state == "DELIVRD" { "001" } else { "000" };
+    let text = ReceiptText {
+        id: Some(String::from(message_id)),
+        sub: Some(String::from("001")),
+        dlvrd: Some(String::from(delivered)),
+        stat: Some(String::from(state)),
+        err: Some(String::from("000")),
+        text: Some(String::new()),
+        ..ReceiptText::default()
+    };
+    let mut tlvs = text.tlvs();
+    if text.message_state().is_none() {
+        tlvs.push(Tlv::new(
+            KnownTlvTag::message_state,
+            &[MessageState::Unknown as u8],
+        ));
+    }
+    DeliverSmPdu::new(
+        "",
+        submit_sm.dest_addr_ton(),
+        submit_sm.dest_addr_npi(),
+        &submit_sm.destination_addr(),
+        submit_sm.source_addr_ton(),
+        submit_sm.source_addr_npi(),
+        &submit_sm.source_addr(),
+        DeliverEsmClass::SmscDeliveryReceipt as u8,
+        0,
+        0,
+        "",
+        "",
+        0,
+        0,
+        0,
+        0,
+        text.to_string().as_bytes(),
+        Tlvs::from(&tlvs),
+    )
+    .expect("Fields copied from a valid submit_sm should be valid")
+}
+
+fn same_kind(left: &ScenarioRule, right: &ScenarioRule) -> bool {
+    discriminant(left) == discriminant(right)
+}
+
+fn parse_positive(s: &str) -> Option<u32> {
+    s.parse().ok().filter(|n| *n > 0)
+}
+
+/// A number of milliseconds written like 250ms, or seconds like 5s
+fn parse_duration(s: &str) -> Option<Duration> {
+    if let Some(ms) = s.strip_suffix("ms") {
+        ms.parse().ok().map(Duration::from_millis)
+    } else if let Some(secs) = s.strip_suffix('s') {
+        secs.parse().ok().map(Duration::from_secs)
+    } else {
+        None
+    }
+}
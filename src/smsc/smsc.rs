@@ -1,24 +1,63 @@
 use log::*;
+use smpp_pdu::pdu::formats::COctetString;
+use smpp_pdu::pdu::tlvs::Tlv;
 use smpp_pdu::pdu::{
     BindReceiverRespPdu, BindTransceiverRespPdu, BindTransmitterRespPdu,
-    EnquireLinkRespPdu, GenericNackPdu, Pdu, PduBody, PduParseError, PduStatus,
-    SubmitSmPdu, SubmitSmRespPdu,
+    EnquireLinkPdu, EnquireLinkRespPdu, GenericNackPdu, Pdu, PduBody,
+    PduParseError, PduStatus, SubmitSmPdu, SubmitSmRespPdu,
 };
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::convert::TryFrom;
 use std::error;
 use std::fmt::{Display, Formatter};
 use std::io;
-use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::net::TcpListener;
-use tokio::sync::{Mutex, Semaphore, TryAcquireError};
-use tokio::time::sleep;
+use std::time::{Duration, Instant};
+use tokio::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc, oneshot, Mutex, Semaphore, TryAcquireError};
+use tokio::time::{sleep, timeout_at};
 
 use crate::async_result::AsyncResult;
+use crate::cancel_sm::{CancelSmPdu, CancelSmRespPdu};
+use crate::canned_messages::CannedMessages;
+use crate::clock::{Clock, TokioClock};
+use crate::command_id::{CommandId, CommandName};
+use crate::data_sm::{DataSmPdu, DataSmRespPdu};
+use crate::encoded_len::EncodedLen;
+use crate::frame_body::HEADER_LENGTH;
+use crate::health::{SessionHealth, SmscHealth};
+use crate::message_payload::MessageBytes;
 use crate::message_unique_key::MessageUniqueKey;
-use crate::smpp_connection::{EsmeId, SmppConnection};
-use crate::smsc::{SmscConfig, SmscLogic};
+use crate::msisdn;
+use crate::outbind::OutbindPdu;
+use crate::parse_error::{ErrorSeverity, RecommendedStatus, Severity};
+use crate::parse_options::{ParseMode, ParseOptions};
+use crate::pdu_status::{StatusDescription, StatusName};
+use crate::query_sm::{QuerySmPdu, QuerySmRespPdu};
+use crate::redact::Redacted;
+use crate::replace_sm::{ReplaceSmPdu, ReplaceSmRespPdu};
+use crate::session_info::{BindMode, SessionInfo};
+use crate::session_memory::SessionMemoryAction;
+use crate::session_stats::SessionStats;
+use crate::smpp_connection::{EsmeId, Frame, PeerAddr, SmppConnection};
+use crate::smsc::{
+    message_id_map, Chaos, ChaosSession, DestinationThrottle, DuplicateWindow,
+    EnquireLinkLimit, MessageIdMap, QueryCache, QueryKey, ReplicationState,
+    Scenario, ScenarioSession, SmscConfig, SmscLogic, SourceQuotaAlerts,
+    SourceQuotas, SubmitFingerprint, SubmitSmArchive, UnknownCommandAction,
+};
+use crate::socket_activation;
+use crate::submit_multi::{
+    DestAddress, SmeAddress, SubmitMultiPdu, SubmitMultiRespPdu, UnsuccessSme,
+};
+use crate::text::DataCodingMap;
+use crate::typed_tlvs;
+use crate::unbind::{UnbindPdu, UnbindRespPdu};
+use crate::unique_id::MessageUid;
 
 pub fn run<L: SmscLogic + Send + Sync + 'static>(
     config: SmscConfig,
@@ -28,9 +67,7 @@ pub fn run<L: SmscLogic + Send + Sync + 'static>(
     rt.block_on(async move {
         let smsc = Smsc::start(config, smsc_logic).await?;
         loop {
-            if let Err(e) = smsc.lock().await.stopped().await {
-                return Err(e);
-            }
+            smsc.lock().await.stopped().await?;
             sleep(Duration::from_millis(100)).await;
             // TODO: notify instead of poll?
         }
@@ -39,7 +76,36 @@ pub fn run<L: SmscLogic + Send + Sync + 'static>(
 
 pub struct Smsc {
     connections: HashMap<EsmeId, Arc<SmppConnection>>,
-    messages: HashMap<MessageUniqueKey, EsmeId>,
+    messages: HashMap<MessageUniqueKey, SubmittedMessage>,
+    destination_throttle: DestinationThrottle,
+    source_quotas: SourceQuotas,
+    source_quota_status: u32,
+    source_quota_alerts: Option<Arc<dyn SourceQuotaAlerts + Send + Sync>>,
+    duplicates: Option<DuplicateWindow>,
+    suppress_duplicates: bool,
+    query_cache: Option<QueryCache>,
+    paused_routes: BTreeSet<String>,
+    archive: Option<Arc<dyn SubmitSmArchive + Send + Sync>>,
+    message_id_map: Option<Arc<dyn MessageIdMap + Send + Sync>>,
+    canned_messages: CannedMessages,
+    default_country_code: Option<String>,
+    data_coding_map: DataCodingMap,
+    scenario: Arc<Scenario>,
+    chaos: Arc<Chaos>,
+    outbinds: mpsc::UnboundedSender<OutbindRequest>,
+}
+
+/// Where a delivery receipt for a message should go
+struct SubmittedMessage {
+    esme_id: EsmeId,
+    uid: MessageUid,
+}
+
+/// Asks the outbind task to connect to address and send outbind.
+struct OutbindRequest {
+    address: String,
+    outbind: OutbindPdu,
+    sent: oneshot::Sender<io::Result<()>>,
 }
 
 impl Smsc {
@@ -53,26 +119,149 @@ impl Smsc {
     ) -> AsyncResult<Arc<Mutex<Self>>> {
         info!("Starting SMSC");
 
+        let scenario = match &smsc_config.scenario {
+            Some(path) => std::fs::read_to_string(path)?.parse()?,
+            None => Scenario::default(),
+        };
+
+        let (outbinds, outbind_requests) = mpsc::unbounded_channel();
         let smsc = Smsc {
             connections: HashMap::new(),
             messages: HashMap::new(),
+            destination_throttle: DestinationThrottle::new(
+                &smsc_config.destination_limits,
+            ),
+            source_quotas: SourceQuotas::new(&smsc_config.source_quotas),
+            source_quota_status: smsc_config.source_quota_status,
+            source_quota_alerts: None,
+            duplicates: smsc_config
+                .duplicate_window_secs
+                .map(|secs| DuplicateWindow::new(Duration::from_secs(secs))),
+            suppress_duplicates: smsc_config.suppress_duplicates,
+            query_cache: smsc_config
+                .query_cache_ms
+                .map(|ms| QueryCache::new(Duration::from_millis(ms))),
+            paused_routes: BTreeSet::new(),
+            archive: None,
+            message_id_map: None,
+            canned_messages: CannedMessages::new(&smsc_config.canned_messages),
+            default_country_code: smsc_config.default_country_code.clone(),
+            data_coding_map: DataCodingMap::with_remaps(
+                &smsc_config.data_coding_remaps,
+            ),
+            scenario: Arc::new(scenario),
+            chaos: Arc::new(Chaos::new(&smsc_config)),
+            outbinds,
         };
         let smsc = Arc::new(Mutex::new(smsc));
 
-        let listener = TcpListener::bind(&smsc_config.bind_address).await?;
-        info!("Bound on {}", &smsc_config.bind_address);
+        let listener = match socket_activation::take_listener()? {
+            Some(listener) => {
+                info!("Listening on passed socket {}", listener.local_addr()?);
+                TcpListener::from_std(listener)?
+            }
+            None => {
+                let listener =
+                    TcpListener::bind(&smsc_config.bind_address).await?;
+                info!("Bound on {}", &smsc_config.bind_address);
+                listener
+            }
+        };
+
+        #[cfg(feature = "admin-http")]
+        if let Some(admin_address) = &smsc_config.admin_address {
+            crate::smsc::admin_http::listen(admin_address, Arc::clone(&smsc))
+                .await?;
+        }
+
+        if let Some(secs) = smsc_config.forced_unbind_secs {
+            tokio::spawn(force_unbinds(
+                Duration::from_secs(secs),
+                Arc::clone(&smsc),
+            ));
+        }
+
+        // Connections over every transport share one limit and one logic
+        let sem = Arc::new(Semaphore::new(smsc_config.max_open_sockets));
+        let logic = Arc::new(Mutex::new(smsc_logic));
+
+        #[cfg(feature = "websocket")]
+        if let Some(websocket_address) = &smsc_config.websocket_address {
+            let websocket_listener =
+                TcpListener::bind(websocket_address).await?;
+            info!("WebSocket endpoint bound on {}", websocket_address);
+            tokio::spawn(listen_loop(
+                websocket_listener,
+                Transport::WebSocket,
+                Arc::clone(&sem),
+                Arc::clone(&smsc),
+                smsc_config.clone(),
+                Arc::clone(&logic),
+            ));
+        }
+
+        #[cfg(unix)]
+        if let Some(path) = &smsc_config.unix_socket {
+            let unix_listener = bind_unix(path).await?;
+            info!("Bound on unix:{}", path.display());
+            tokio::spawn(unix_listen_loop(
+                unix_listener,
+                path.clone(),
+                Arc::clone(&sem),
+                Arc::clone(&smsc),
+                smsc_config.clone(),
+                Arc::clone(&logic),
+            ));
+        }
+
+        tokio::spawn(outbind_loop(
+            outbind_requests,
+            Arc::clone(&sem),
+            Arc::clone(&smsc),
+            smsc_config.clone(),
+            Arc::clone(&logic),
+        ));
 
         // Spawn off a task that deals with incoming connections
         tokio::spawn(listen_loop(
             listener,
+            Transport::Tcp,
+            sem,
             Arc::clone(&smsc),
             smsc_config,
-            smsc_logic,
+            logic,
         ));
 
         Ok(smsc)
     }
 
+    /// Connect to an ESME listening on address and send it outbind, asking
+    /// it to bind as a receiver with system_id, e.g. to collect receipts
+    /// held for it.  Returns once outbind is sent; the session then runs
+    /// like any other.  Takes the Mutex so that it is not held while
+    /// connecting.
+    pub async fn outbind(
+        smsc: &Mutex<Self>,
+        address: &str,
+        system_id: &str,
+        password: &str,
+    ) -> AsyncResult<()> {
+        // The sequence_number is filled in once connected
+        let outbind = OutbindPdu::new(0, system_id, password)?;
+        let (sent, result) = oneshot::channel();
+        smsc.lock()
+            .await
+            .outbinds
+            .send(OutbindRequest {
+                address: String::from(address),
+                outbind,
+                sent,
+            })
+            .map_err(|_| "The outbind task has stopped")?;
+        result.await.map_err(|_| "The outbind task has stopped")??;
+        Ok(())
+    }
+
     async fn stopped(&self) -> AsyncResult<()> {
         // TODO: check whether we are stopped and return an error if so
         Ok(())
@@ -85,7 +274,7 @@ impl Smsc {
     ) -> AsyncResult<()> {
         // Later: Issue#5: consider retrying after a delay if unable to match DR
         // Later: Issue#12: handle MOs
-        info!("<= receive_pdu() {:?}", pdu);
+        info!("<= receive_pdu() {:?}", Redacted(&pdu));
         match pdu.body() {
             PduBody::DeliverSm(body) => {
                 let k =
@@ -112,27 +301,296 @@ impl Smsc {
         pdu: Pdu,
         message_unique_key: MessageUniqueKey,
     ) -> AsyncResult<()> {
-        let conn = self.connection_for_message(message_unique_key).await?;
+        let pdu = match &self.message_id_map {
+            Some(map) => message_id_map::replace_receipted_message_id(
+                &pdu,
+                &message_unique_key.message_id,
+                &map.external(&message_unique_key.message_id),
+            ),
+            None => pdu,
+        };
+        let (conn, uid) =
+            self.connection_for_message(message_unique_key).await?;
+        info!("Connection {} - delivering receipt for {}", conn, uid);
         // Later: Issue#3: in order to support a window size to the client, we
         // will need to put this PDU into a queue rather than writing
         // it immediately here.
         tokio::spawn(async move {
             // We schedule the write here, as a sort-of 1-message queue,
             // so we return immediately, and the IO is done later.
-            conn.write_pdu(&pdu).await.map_err(
-                |e| error!("Failed to send PDU to client: {}", e), // TODO: give information about the client here
-            )
+            conn.write_pdu(&pdu).await.map_err(|e| {
+                error!(
+                    "Connection {} - failed to deliver receipt for {}: {}",
+                    conn, uid, e
+                )
+            })
         });
         Ok(())
     }
 
+    /// What was agreed when esme_id bound, or None if it is not bound.
+    pub fn session_info(&self, esme_id: &EsmeId) -> Option<SessionInfo> {
+        self.connections.get(esme_id)?.session_info()
+    }
+
+    /// Statistics for every currently-bound ESME.
+    pub fn session_stats(&self) -> HashMap<EsmeId, SessionStats> {
+        self.connections
+            .iter()
+            .map(|(esme_id, connection)| (esme_id.clone(), connection.stats()))
+            .collect()
+    }
+
+    /// A report on every bound session, ordered by system_id.
+    pub fn health(&self) -> SmscHealth {
+        let mut sessions: Vec<SessionHealth> = self
+            .session_stats()
+            .iter()
+            .map(|(esme_id, stats)| SessionHealth::new(esme_id, stats))
+            .collect();
+        sessions.sort_by(|a, b| a.system_id.cmp(&b.system_id));
+        SmscHealth { sessions }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.health().is_healthy()
+    }
+
+    /// Use map for text on connections opened from now on, e.g. to add
+    /// alphabets beyond those --data-coding can name.
+    pub fn set_data_coding_map(&mut self, map: DataCodingMap) {
+        self.data_coding_map = map;
+    }
+
+    /// Follow scenario on connections opened from now on.
+    pub fn set_scenario(&mut self, scenario: Scenario) {
+        self.scenario = Arc::new(scenario);
+    }
+
+    /// Pass every submit_sm we accept to archive, from now on.
+    pub fn set_archive(
+        &mut self,
+        archive: Arc<dyn SubmitSmArchive + Send + Sync>,
+    ) {
+        self.archive = Some(archive);
+    }
+
+    /// Show ESMEs the message_ids map makes of the ones SmscLogic returns,
+    /// from now on.
+    pub fn set_message_id_map(
+        &mut self,
+        map: Arc<dyn MessageIdMap + Send + Sync>,
+    ) {
+        self.message_id_map = Some(map);
+    }
+
+    /// Send these canned messages for submit_sm that name them, from now
+    /// on, instead of those from --canned-message.
+    pub fn set_canned_messages(&mut self, canned_messages: CannedMessages) {
+        self.canned_messages = canned_messages;
+    }
+
+    /// Tell alerts whenever a source address goes over a --source-quota,
+    /// from now on.
+    pub fn set_source_quota_alerts(
+        &mut self,
+        alerts: Arc<dyn SourceQuotaAlerts + Send + Sync>,
+    ) {
+        self.source_quota_alerts = Some(alerts);
+    }
+
+    /// How much each source address has sent, for accounting.
+    pub fn source_quotas(&self) -> &SourceQuotas {
+        &self.source_quotas
+    }
+
+    /// Every currently-bound connection.
+    pub fn connections(&self) -> Vec<Arc<SmppConnection>> {
+        self.connections.values().cloned().collect()
+    }
+
+    /// Close every connection bound with this system_id, returning how many
+    /// there were.
+    pub fn kick(&self, system_id: &str) -> usize {
+        let mut kicked = 0;
+        for (esme_id, connection) in &self.connections {
+            if esme_id.system_id.as_str() == system_id {
+                info!("Connection {} - kicked", connection);
+                connection.request_close();
+                kicked += 1;
+            }
+        }
+        kicked
+    }
+
+    /// Reject submit_sm to destination addresses starting with prefix, with
+    /// ESME_RTHROTTLED, until resume_route(prefix) is called.
+    pub fn pause_route(&mut self, prefix: &str) {
+        info!("Pausing route {}", prefix);
+        self.paused_routes.insert(String::from(prefix));
+    }
+
+    pub fn resume_route(&mut self, prefix: &str) {
+        info!("Resuming route {}", prefix);
+        self.paused_routes.remove(prefix);
+    }
+
+    pub fn paused_routes(&self) -> Vec<String> {
+        self.paused_routes.iter().cloned().collect()
+    }
+
+    /// What a warm standby needs to take over from us, to pass to its
+    /// import_state().  See crate::smsc::replication.
+    pub fn export_state(&self) -> ReplicationState {
+        let mut bound_accounts: Vec<EsmeId> =
+            self.connections.keys().cloned().collect();
+        bound_accounts.sort_by(|a, b| {
+            (&a.system_id, &a.system_type).cmp(&(&b.system_id, &b.system_type))
+        });
+        let mut messages: Vec<(MessageUniqueKey, EsmeId)> = self
+            .messages
+            .iter()
+            .map(|(key, message)| (key.clone(), message.esme_id.clone()))
+            .collect();
+        messages.sort_by(|(a, _), (b, _)| {
+            (&a.namespace_id, &a.message_id, &a.destination_addr).cmp(&(
+                &b.namespace_id,
+                &b.message_id,
+                &b.destination_addr,
+            ))
+        });
+        ReplicationState {
+            bound_accounts,
+            messages,
+            paused_routes: self.paused_routes(),
+        }
+    }
+
+    /// Take over from the SMSC that exported state: route receipts for its
+    /// messages, and pause its paused routes, as well as our own.  Its
+    /// bound accounts still need to bind to us.
+    pub fn import_state(&mut self, state: ReplicationState) {
+        info!(
+            "Importing {} messages and {} paused routes",
+            state.messages.len(),
+            state.paused_routes.len()
+        );
+        for (message_unique_key, esme_id) in state.messages {
+            self.add_message(message_unique_key, esme_id);
+        }
+        for prefix in &state.paused_routes {
+            self.pause_route(prefix);
+        }
+        for esme_id in &state.bound_accounts {
+            if !self.connections.contains_key(esme_id) {
+                info!(
+                    "Waiting for system_id='{}' system_type='{}' to bind",
+                    esme_id.system_id, esme_id.system_type
+                );
+            }
+        }
+    }
+
+    /// destination_addr in E.164 form, so that the same number always routes
+    /// and matches delivery receipts the same way.
+    fn normalize(&self, destination_addr: &str) -> String {
+        msisdn::normalize_msisdn(
+            destination_addr,
+            self.default_country_code.as_deref(),
+        )
+    }
+
+    /// Should we accept a submit_sm to destination_addr right now?
+    fn accepts_destination(&mut self, destination_addr: &str) -> bool {
+        let destination_addr = &self.normalize(destination_addr);
+        !self
+            .paused_routes
+            .iter()
+            .any(|prefix| destination_addr.starts_with(prefix.as_str()))
+            && self.destination_throttle.try_acquire(destination_addr)
+    }
+
+    /// What to remember body by for duplicate detection, or None if that
+    /// is turned off.
+    fn fingerprint(
+        &self,
+        esme_id: &EsmeId,
+        body: &SubmitSmPdu,
+        message: &[u8],
+    ) -> Option<SubmitFingerprint> {
+        self.duplicates.as_ref()?;
+        Some(SubmitFingerprint::new(
+            esme_id.system_id.as_str(),
+            &body.source_addr(),
+            &self.normalize(&body.destination_addr()),
+            body.0.data_coding.value,
+            message,
+        ))
+    }
+
+    /// The message_id to answer a duplicate of an earlier submit_sm with,
+    /// if we are suppressing duplicates.  Warns of any duplicate either
+    /// way.
+    fn suppress_duplicate(
+        &mut self,
+        connection: &SmppConnection,
+        fingerprint: &SubmitFingerprint,
+    ) -> Option<String> {
+        let message_id = self.duplicates.as_mut()?.duplicate_of(fingerprint)?;
+        warn!(
+            "Connection {} - submit_sm from {} to {} duplicates message {}",
+            connection,
+            fingerprint.source_addr,
+            fingerprint.destination_addr,
+            message_id
+        );
+        Some(message_id).filter(|_| self.suppress_duplicates)
+    }
+
+    /// Count a submit_sm from source_addr, or return the command_status to
+    /// reject it with if source_addr is over a quota.
+    fn accepts_source(&mut self, source_addr: &str) -> Result<(), u32> {
+        let exceeded = match self.source_quotas.try_acquire(source_addr) {
+            Ok(()) => return Ok(()),
+            Err(exceeded) => exceeded,
+        };
+        if exceeded.first {
+            warn!(
+                "Source address {} is over its quota of {}",
+                exceeded.source_addr, exceeded.quota
+            );
+            if let Some(alerts) = &self.source_quota_alerts {
+                alerts.quota_exceeded(&exceeded);
+            }
+        }
+        Err(self.source_quota_status)
+    }
+
+    /// Count a submit_sm from source_addr to destination_addr, or return
+    /// the command_status to reject it with.  A rejected submit_sm counts
+    /// against neither the destination's limit nor the source's quota.
+    fn accepts_submit(
+        &mut self,
+        source_addr: &str,
+        destination_addr: &str,
+    ) -> Result<(), u32> {
+        if !self.accepts_destination(destination_addr) {
+            return Err(PduStatus::ESME_RTHROTTLED as u32);
+        }
+        self.accepts_source(source_addr).map_err(|command_status| {
+            let destination_addr = &self.normalize(destination_addr);
+            self.destination_throttle.release(destination_addr);
+            command_status
+        })
+    }
+
     pub fn add_connection(&mut self, connection: Arc<SmppConnection>) {
         if let Some(esme_id) = connection.bound_esme_id() {
             self.connections.insert(esme_id, connection);
         } else {
             error!(
                 "Failed to add connection {} because it is not bound!",
-                connection.socket_addr
+                connection
             );
         }
     }
@@ -147,27 +605,48 @@ impl Smsc {
         }
     }
 
+    /// The MessageUid given to the message submitted with
+    /// message_unique_key, if we still know of it.  Messages taken over
+    /// with import_state() get new ones.
+    pub fn message_uid(
+        &self,
+        message_unique_key: &MessageUniqueKey,
+    ) -> Option<MessageUid> {
+        let mut message_unique_key = message_unique_key.clone();
+        message_unique_key.destination_addr =
+            self.normalize(&message_unique_key.destination_addr);
+        Some(self.messages.get(&message_unique_key)?.uid)
+    }
+
     fn add_message(
         &mut self,
-        message_unique_key: MessageUniqueKey,
+        mut message_unique_key: MessageUniqueKey,
         esme_id: EsmeId,
-    ) {
+    ) -> MessageUid {
+        message_unique_key.destination_addr =
+            self.normalize(&message_unique_key.destination_addr);
+        let uid = MessageUid::next();
         // Later: Issue#14: delete old entries in this map to keep size bounded
-        self.messages.insert(message_unique_key, esme_id);
+        self.messages
+            .insert(message_unique_key, SubmittedMessage { esme_id, uid });
+        uid
     }
 
     async fn connection_for_message(
         &mut self,
-        message_unique_key: MessageUniqueKey,
-    ) -> AsyncResult<Arc<SmppConnection>> {
-        if let Some(esme_id) = self.messages.get(&message_unique_key) {
+        mut message_unique_key: MessageUniqueKey,
+    ) -> AsyncResult<(Arc<SmppConnection>, MessageUid)> {
+        message_unique_key.destination_addr =
+            self.normalize(&message_unique_key.destination_addr);
+        if let Some(message) = self.messages.get(&message_unique_key) {
+            let esme_id = &message.esme_id;
             if let Some(connection) = self.connections.get(esme_id) {
-                Ok(Arc::clone(connection))
+                Ok((Arc::clone(connection), message.uid))
             } else {
                 Err(format!(
                     "No client connection found with \
-                system_id='{}' system_type='{}'.",
-                    esme_id.system_id, esme_id.system_type
+                system_id='{}' system_type='{}' for {}.",
+                    esme_id.system_id, esme_id.system_type, message.uid
                 )
                 .into())
             }
@@ -184,24 +663,198 @@ impl Smsc {
     }
 }
 
+/// How SMPP is carried over the connections a listener accepts
+#[derive(Clone, Copy)]
+enum Transport {
+    Tcp,
+    #[cfg(feature = "websocket")]
+    WebSocket,
+}
+
 /// Listen for clients connecting, and spawn a new task every time one does
 async fn listen_loop<L: SmscLogic + Send + Sync + 'static>(
     listener: TcpListener,
+    transport: Transport,
+    sem: Arc<Semaphore>,
     smsc: Arc<Mutex<Smsc>>,
     config: SmscConfig,
-    logic: L,
+    logic: Arc<Mutex<L>>,
 ) {
-    let sem = Arc::new(Semaphore::new(config.max_open_sockets));
-    let logic = Arc::new(Mutex::new(logic));
     loop {
         match listener.accept().await {
             Err(e) => {
                 error!("Client connection failed: {}", e);
             }
-            Ok((tcp_stream, socket_addr)) => {
+            Ok((tcp_stream, socket_addr)) => match transport {
+                #[cfg(feature = "compression")]
+                Transport::Tcp if config.compress => {
+                    tokio::spawn(process_stream(
+                        Arc::clone(&sem),
+                        SmppConnection::from_stream(
+                            crate::compression::compressed(tcp_stream),
+                            socket_addr.into(),
+                        ),
+                        config.clone(),
+                        Arc::clone(&logic),
+                        Arc::clone(&smsc),
+                    ));
+                }
+                Transport::Tcp => {
+                    tokio::spawn(process_stream(
+                        Arc::clone(&sem),
+                        SmppConnection::new(tcp_stream, socket_addr),
+                        config.clone(),
+                        Arc::clone(&logic),
+                        Arc::clone(&smsc),
+                    ));
+                }
+                #[cfg(feature = "websocket")]
+                Transport::WebSocket => {
+                    // Take the permit before the handshake, so that
+                    // half-open handshakes count towards max_open_sockets
+                    let permit = match Arc::clone(&sem).try_acquire_owned() {
+                        Ok(permit) => permit,
+                        Err(e) => {
+                            refuse(e, &socket_addr);
+                            continue;
+                        }
+                    };
+                    let config = config.clone();
+                    let logic = Arc::clone(&logic);
+                    let smsc = Arc::clone(&smsc);
+                    tokio::spawn(async move {
+                        let handshake_timeout = Duration::from_secs(
+                            config.websocket_handshake_timeout_secs,
+                        );
+                        let accepted = tokio::time::timeout(
+                            handshake_timeout,
+                            crate::websocket::accept(
+                                tcp_stream,
+                                config.max_pdu_length,
+                            ),
+                        )
+                        .await;
+                        match accepted {
+                            Ok(Ok(stream)) => {
+                                process_connection(
+                                    SmppConnection::from_stream(
+                                        stream,
+                                        socket_addr.into(),
+                                    ),
+                                    config,
+                                    logic,
+                                    smsc,
+                                )
+                                .await
+                            }
+                            Ok(Err(e)) => error!("{} from {}", e, socket_addr),
+                            Err(_) => error!(
+                                "WebSocket handshake timed out from {}",
+                                socket_addr
+                            ),
+                        }
+                        drop(permit);
+                    });
+                }
+            },
+        }
+    }
+}
+
+async fn outbind_loop<L: SmscLogic + Send + Sync + 'static>(
+    mut requests: mpsc::UnboundedReceiver<OutbindRequest>,
+    sem: Arc<Semaphore>,
+    smsc: Arc<Mutex<Smsc>>,
+    config: SmscConfig,
+    logic: Arc<Mutex<L>>,
+) {
+    while let Some(request) = requests.recv().await {
+        let sem = Arc::clone(&sem);
+        let smsc = Arc::clone(&smsc);
+        let config = config.clone();
+        let logic = Arc::clone(&logic);
+        tokio::spawn(async move {
+            let connection =
+                match send_outbind(request.address, request.outbind).await {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        // Nobody may be waiting any more, which is fine
+                        let _ = request.sent.send(Err(e));
+                        return;
+                    }
+                };
+            let _ = request.sent.send(Ok(()));
+            process_stream(sem, connection, config, logic, smsc).await;
+        });
+    }
+}
+
+async fn send_outbind(
+    address: String,
+    mut outbind: OutbindPdu,
+) -> io::Result<SmppConnection> {
+    let tcp_stream = TcpStream::connect(&address).await?;
+    let socket_addr = tcp_stream.peer_addr()?;
+    let connection = SmppConnection::new(tcp_stream, socket_addr);
+    outbind.sequence_number = connection.next_sequence_number();
+    info!("=> {} outbind for {}", connection, outbind.system_id.value);
+    connection.write_frame(&Frame::Outbind(outbind)).await?;
+    Ok(connection)
+}
+
+/// Every interval, close every bound connection, so that ESMEs have to
+/// rebind.
+async fn force_unbinds(interval: Duration, smsc: Arc<Mutex<Smsc>>) {
+    loop {
+        sleep(interval).await;
+        for connection in smsc.lock().await.connections() {
+            info!("Connection {} - forcing unbind", connection);
+            connection.request_close();
+        }
+    }
+}
+
+/// Bind to a Unix domain socket at path, replacing any socket file left
+/// behind by an SMSC that is no longer running.
+#[cfg(unix)]
+async fn bind_unix(path: &Path) -> io::Result<UnixListener> {
+    match UnixListener::bind(path) {
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+            // Only remove the file if nobody is listening on it
+            match UnixStream::connect(path).await {
+                Err(c) if c.kind() == io::ErrorKind::ConnectionRefused => {
+                    warn!("Removing stale socket {}", path.display());
+                    std::fs::remove_file(path)?;
+                    UnixListener::bind(path)
+                }
+                _ => Err(e),
+            }
+        }
+        result => result,
+    }
+}
+
+#[cfg(unix)]
+async fn unix_listen_loop<L: SmscLogic + Send + Sync + 'static>(
+    listener: UnixListener,
+    path: PathBuf,
+    sem: Arc<Semaphore>,
+    smsc: Arc<Mutex<Smsc>>,
+    config: SmscConfig,
+    logic: Arc<Mutex<L>>,
+) {
+    loop {
+        match listener.accept().await {
+            Err(e) => {
+                error!("Client connection failed: {}", e);
+            }
+            Ok((unix_stream, _)) => {
                 tokio::spawn(process_stream(
                     Arc::clone(&sem),
-                    SmppConnection::new(tcp_stream, socket_addr),
+                    SmppConnection::from_stream(
+                        unix_stream,
+                        PeerAddr::Unix(path.clone()),
+                    ),
                     config.clone(),
                     Arc::clone(&logic),
                     Arc::clone(&smsc),
@@ -218,27 +871,37 @@ async fn process_stream<L: SmscLogic + Send + Sync + 'static>(
     logic: Arc<Mutex<L>>,
     smsc: Arc<Mutex<Smsc>>,
 ) {
-    let socket_addr = connection.socket_addr.clone();
-    let aqu = sem.try_acquire();
-    match aqu {
-        Ok(_guard) => {
-            info!("Connection {} - opened", socket_addr);
-            let result = process(connection, config, logic, smsc).await;
-            log_result(result, socket_addr);
-        }
-        Err(TryAcquireError::NoPermits) => {
-            error!(
-                "Refused connection {} - too many open sockets",
-                connection.socket_addr
-            );
+    match sem.try_acquire() {
+        Ok(_guard) => process_connection(connection, config, logic, smsc).await,
+        Err(e) => refuse(e, &connection),
+    }
+}
+
+async fn process_connection<L: SmscLogic + Send + Sync + 'static>(
+    connection: SmppConnection,
+    config: SmscConfig,
+    logic: Arc<Mutex<L>>,
+    smsc: Arc<Mutex<Smsc>>,
+) {
+    // The connection moves into process()
+    let name = connection.to_string();
+    info!("Connection {} - opened", name);
+    let result = process(connection, config, logic, smsc).await;
+    log_result(result, &name);
+}
+
+fn refuse(e: TryAcquireError, connection: &dyn Display) {
+    match e {
+        TryAcquireError::NoPermits => {
+            error!("Refused connection {} - too many open sockets", connection);
         }
-        Err(TryAcquireError::Closed) => {
+        TryAcquireError::Closed => {
             error!("Unexpected error: semaphore closed");
         }
     }
 }
 
-fn log_result(closed_by_us: Result<bool, ProcessError>, addr: SocketAddr) {
+fn log_result(closed_by_us: Result<bool, ProcessError>, addr: &str) {
     match closed_by_us {
         Ok(true) => {
             info!("Connection {} - closed by us", addr)
@@ -283,6 +946,26 @@ impl ProcessError {
     fn new_connection_not_bound_as_transmitter() -> Self {
         ProcessError::ConnectionNotBoundAsTransmitter
     }
+
+    fn severity(&self) -> ErrorSeverity {
+        match self {
+            ProcessError::PduParseError(e) => e.severity(),
+            ProcessError::UnexpectedPduType(_) => {
+                ErrorSeverity::RequestRecoverable
+            }
+            ProcessError::ConnectionNotBoundAsTransmitter => {
+                ErrorSeverity::RequestRecoverable
+            }
+            ProcessError::IoError(_) => ErrorSeverity::SessionFatal,
+            ProcessError::InternalError(_) => ErrorSeverity::SessionFatal,
+        }
+    }
+
+    /// Unless we recover from bad PDUs, every error closes the connection.
+    fn is_fatal(&self, config: &SmscConfig) -> bool {
+        !config.recover_from_bad_pdus
+            || self.severity() == ErrorSeverity::SessionFatal
+    }
 }
 
 impl From<PduParseError> for ProcessError {
@@ -303,12 +986,19 @@ impl Display for ProcessError {
         formatter: &mut Formatter,
     ) -> std::result::Result<(), std::fmt::Error> {
         let s = match self {
-            ProcessError::PduParseError(e) => e.to_string(),
+            ProcessError::PduParseError(e) => {
+                format!(
+                    "{}  Response status: {}.",
+                    e,
+                    StatusName(e.recommended_status() as u32)
+                )
+            }
             ProcessError::UnexpectedPduType(e) => {
                 format!(
                     "Unexpected PDU type \
-                    (command_id={:#010X}, sequence_number={:#010X})",
-                    e.command_id, e.sequence_number
+                    (command_id={}, sequence_number={:#010X})",
+                    CommandName(e.command_id),
+                    e.sequence_number
                 )
             }
             ProcessError::ConnectionNotBoundAsTransmitter => String::from(
@@ -347,6 +1037,19 @@ async fn process<L: SmscLogic>(
         }
     }
 
+    if config.capture_sessions {
+        connection.start_capture();
+    }
+    connection.set_data_coding_map(smsc.lock().await.data_coding_map.clone());
+    connection.set_parse_options(ParseOptions {
+        max_pdu_length: config.max_pdu_length,
+        mode: if config.lenient_parsing {
+            ParseMode::Lenient
+        } else {
+            ParseMode::Strict
+        },
+    });
+
     // Ensure we disconnect connection when we leave this function,
     // even though we are wrapping it in an Arc so it can be accessed
     // from elsewhere.
@@ -355,13 +1058,23 @@ async fn process<L: SmscLogic>(
         connection: Arc::new(connection),
     };
 
-    process_loop(
+    let result = process_loop(
         Arc::clone(&disconnect_guard.connection),
         config,
         smsc_logic,
         smsc,
     )
-    .await
+    .await;
+
+    if let Some(capture) = disconnect_guard.connection.capture() {
+        info!(
+            "Connection {} - captured session:\n{}",
+            capture.peer,
+            capture.to_wireshark_text()
+        );
+    }
+
+    result
 }
 
 async fn process_loop<L: SmscLogic>(
@@ -370,74 +1083,678 @@ async fn process_loop<L: SmscLogic>(
     smsc_logic: Arc<Mutex<L>>,
     smsc: Arc<Mutex<Smsc>>,
 ) -> Result<bool, ProcessError> {
+    let mut keepalive = Keepalive::new();
+    let (scenario, chaos) = {
+        let smsc = smsc.lock().await;
+        (Arc::clone(&smsc.scenario), Arc::clone(&smsc.chaos))
+    };
+    let mut scenario = ScenarioSession::new(scenario);
+    let mut chaos_session = ChaosSession::new();
+    let mut enquire_link_limit =
+        config.max_enquire_links_per_sec.map(EnquireLinkLimit::new);
     loop {
-        let pdu = connection.read_pdu().await;
+        let incoming = match read_next_pdu(&connection, &config, &mut keepalive)
+            .await?
+        {
+            ReadOutcome::Read(pdu) => Incoming::Pdu(pdu),
+            ReadOutcome::Idle => {
+                warn!(
+                    "Connection {} - idle for more than {}s",
+                    connection,
+                    config.idle_timeout_secs.unwrap_or_default()
+                );
+                return Ok(true);
+            }
+            ReadOutcome::CloseRequested => return Ok(true),
+            ReadOutcome::DataSm(data_sm) => {
+                Incoming::Frame(Frame::DataSm(data_sm))
+            }
+            ReadOutcome::QuerySm(query_sm) => {
+                Incoming::Frame(Frame::QuerySm(query_sm))
+            }
+            ReadOutcome::CancelSm(cancel_sm) => {
+                Incoming::Frame(Frame::CancelSm(cancel_sm))
+            }
+            ReadOutcome::ReplaceSm(replace_sm) => {
+                Incoming::Frame(Frame::ReplaceSm(replace_sm))
+            }
+            ReadOutcome::SubmitMulti(submit_multi) => {
+                Incoming::Frame(Frame::SubmitMulti(submit_multi))
+            }
+            ReadOutcome::SlowPdu => {
+                warn!("Connection {} - PDU arriving too slowly", connection);
+                return Ok(true);
+            }
+            ReadOutcome::Unbind(unbind) => {
+                connection
+                    .write_unbind_resp(&UnbindRespPdu::new(
+                        PduStatus::ESME_ROK as u32,
+                        unbind.sequence_number,
+                    ))
+                    .await?;
+                info!("Connection {} - unbound", connection);
+                return Ok(true);
+            }
+            ReadOutcome::KeepaliveTimeout => {
+                warn!(
+                    "Connection {} - no enquire_link_resp within {}s",
+                    connection, config.enquire_link_timeout_secs
+                );
+                return Ok(true);
+            }
+        };
+        let pdu = match incoming {
+            Incoming::Pdu(pdu) => pdu,
+            Incoming::Frame(request) => {
+                // As for a PDU below: smpp_pdu cannot represent these
+                let over_memory = over_session_memory(&connection, &config);
+                if over_memory
+                    && config.session_memory_action
+                        == SessionMemoryAction::Close
+                {
+                    return Ok(true);
+                }
+                let command_id = request.command_id();
+                let system_id = bound_system_id(&connection);
+                let system_id = system_id.as_deref();
+                let command_status = if over_memory && command_id.is_submit() {
+                    Some(PduStatus::ESME_RTHROTTLED as u32)
+                } else {
+                    scenario.receive_command(system_id, command_id).or_else(
+                        || chaos_session.receive_command(&chaos, command_id),
+                    )
+                };
+                let response = match command_status
+                    .and_then(|s| error_response(&request, s))
+                {
+                    Some(response) => response,
+                    None => {
+                        handle_frame(
+                            request,
+                            &connection,
+                            &config,
+                            &smsc_logic,
+                            &smsc,
+                        )
+                        .await?
+                    }
+                };
+                if let Some(delay) = scenario.delay(system_id) {
+                    sleep(delay).await;
+                }
+                if let Some(latency) = chaos.latency() {
+                    sleep(latency).await;
+                }
+                connection.write_frame(&response).await?;
+                if scenario.should_drop(system_id) {
+                    info!("Connection {} - dropped by scenario", connection);
+                    return Ok(true);
+                }
+                continue;
+            }
+        };
         match pdu {
             Ok(pdu) => {
                 if let Some(pdu) = pdu {
+                    if keepalive.is_resp(&pdu) {
+                        keepalive.awaiting_resp = None;
+                        continue;
+                    }
+                    let over_memory = over_session_memory(&connection, &config);
+                    if over_memory
+                        && config.session_memory_action
+                            == SessionMemoryAction::Close
+                    {
+                        return Ok(true);
+                    }
                     let sequence_number = pdu.sequence_number.value;
-                    match handle_pdu(
-                        pdu,
-                        Arc::clone(&connection),
-                        &config,
-                        Arc::clone(&smsc_logic),
-                        Arc::clone(&smsc),
-                    )
-                    .await
+                    let result = match over_memory
+                        .then(|| throttled_submit_sm_resp(&pdu))
+                        .flatten()
+                        .or_else(|| {
+                            throttled_enquire_link_resp(
+                                &pdu,
+                                &connection,
+                                enquire_link_limit.as_mut()?,
+                            )
+                        })
+                        .or_else(|| {
+                            scenario.receive(
+                                bound_system_id(&connection).as_deref(),
+                                &pdu,
+                            )
+                        })
+                        .or_else(|| chaos_session.receive(&chaos, &pdu))
                     {
-                        Ok(response) => connection.write_pdu(&response).await?,
+                        Some(response) => Ok(response),
+                        None => {
+                            handle_pdu(
+                                &pdu,
+                                Arc::clone(&connection),
+                                &config,
+                                Arc::clone(&smsc_logic),
+                                Arc::clone(&smsc),
+                            )
+                            .await
+                        }
+                    };
+                    // Binding may have changed which rules apply
+                    let system_id = bound_system_id(&connection);
+                    let system_id = system_id.as_deref();
+                    if let Some(delay) = scenario.delay(system_id) {
+                        sleep(delay).await;
+                    }
+                    if let Some(latency) = chaos.latency() {
+                        sleep(latency).await;
+                    }
+                    match result {
+                        Ok(response) => {
+                            connection
+                                .write_pdu_with_tlvs(
+                                    &response,
+                                    &rejection_info(&config, &response),
+                                )
+                                .await?;
+                            for (after, receipt) in
+                                scenario.receipts(system_id, &pdu, &response)
+                            {
+                                send_later(
+                                    Arc::clone(&connection),
+                                    after,
+                                    receipt.into(),
+                                );
+                            }
+                        }
                         Err(e) => {
                             // Couldn't handle this PDU type.  Send a nack...
                             connection
-                                .write_pdu(
+                                .write_pdu_with_tlvs(
                                     &Pdu::new(
                                         PduStatus::ESME_RINVCMDID as u32,
                                         sequence_number,
                                         GenericNackPdu::new_error().into(),
                                     )
                                     .unwrap(),
+                                    &status_info(&config, &e),
                                 )
                                 .await?;
-                            // ...and Drop the connection.
-                            return Err(e);
+                            // ...and Drop the connection if we can't go on.
+                            if e.is_fatal(&config) {
+                                return Err(e);
+                            }
+                            warn!(
+                                "Connection {} - rejected PDU: {}",
+                                connection, e
+                            );
                         }
                     }
+                    if scenario.should_drop(system_id) {
+                        info!(
+                            "Connection {} - dropped by scenario",
+                            connection
+                        );
+                        return Ok(true);
+                    }
                 } else {
                     // Client closed the connection
                     return Ok(false);
                 }
             }
             Err(pdu_parse_error) => {
+                if is_unknown_command(&pdu_parse_error) {
+                    if let Some(frame) = connection.bad_frames().pop() {
+                        smsc_logic.lock().await.unknown_command(&frame).await;
+                    }
+                    match config.unknown_command {
+                        UnknownCommandAction::Nack => {}
+                        UnknownCommandAction::Ignore => {
+                            warn!(
+                                "Connection {} - ignored PDU: {}",
+                                connection, pdu_parse_error
+                            );
+                            continue;
+                        }
+                        UnknownCommandAction::Close => {
+                            return Err(ProcessError::from(pdu_parse_error));
+                        }
+                    }
+                }
+
                 // Respond with an error
                 let response = handle_pdu_parse_error(&pdu_parse_error);
-                connection.write_pdu(&response).await?;
+                let e = ProcessError::from(pdu_parse_error);
+                connection
+                    .write_pdu_with_tlvs(&response, &status_info(&config, &e))
+                    .await?;
+
+                // Then return the error if we need to drop the connection
+                if e.is_fatal(&config) {
+                    return Err(e);
+                }
+                warn!("Connection {} - rejected PDU: {}", connection, e);
+            }
+        }
+    }
+}
+
+/// What process_loop() read: a PDU, or a request smpp_pdu cannot represent
+enum Incoming {
+    Pdu(Result<Option<Pdu>, PduParseError>),
+    Frame(Frame),
+}
+
+/// Handle a request smpp_pdu cannot represent, returning its response.
+async fn handle_frame<L: SmscLogic>(
+    request: Frame,
+    connection: &Arc<SmppConnection>,
+    config: &SmscConfig,
+    smsc_logic: &Arc<Mutex<L>>,
+    smsc: &Arc<Mutex<Smsc>>,
+) -> Result<Frame, ProcessError> {
+    Ok(match request {
+        Frame::DataSm(data_sm) => Frame::DataSmResp(
+            handle_data_sm(
+                &data_sm,
+                Arc::clone(connection),
+                config,
+                Arc::clone(smsc_logic),
+                Arc::clone(smsc),
+            )
+            .await?,
+        ),
+        Frame::QuerySm(query_sm) => Frame::QuerySmResp(
+            handle_query_sm(
+                &query_sm,
+                connection,
+                Arc::clone(smsc_logic),
+                Arc::clone(smsc),
+            )
+            .await,
+        ),
+        Frame::CancelSm(cancel_sm) => Frame::CancelSmResp(
+            handle_cancel_sm(
+                &cancel_sm,
+                connection,
+                Arc::clone(smsc_logic),
+                Arc::clone(smsc),
+            )
+            .await,
+        ),
+        Frame::ReplaceSm(replace_sm) => Frame::ReplaceSmResp(
+            handle_replace_sm(
+                &replace_sm,
+                connection,
+                Arc::clone(smsc_logic),
+                Arc::clone(smsc),
+            )
+            .await,
+        ),
+        Frame::SubmitMulti(submit_multi) => Frame::SubmitMultiResp(
+            handle_submit_multi(
+                &submit_multi,
+                Arc::clone(connection),
+                config,
+                Arc::clone(smsc_logic),
+                Arc::clone(smsc),
+            )
+            .await?,
+        ),
+        frame => {
+            unreachable!("{} is not read as a request", frame.command_id())
+        }
+    })
+}
+
+/// A response to request with command_status and no body, for a request
+/// smpp_pdu cannot represent.
+fn error_response(request: &Frame, command_status: u32) -> Option<Frame> {
+    Some(match request {
+        Frame::DataSm(data_sm) => Frame::DataSmResp(DataSmRespPdu::new_error(
+            command_status,
+            data_sm.sequence_number,
+        )),
+        Frame::QuerySm(query_sm) => Frame::QuerySmResp(
+            QuerySmRespPdu::new_error(command_status, query_sm.sequence_number),
+        ),
+        Frame::CancelSm(cancel_sm) => Frame::CancelSmResp(
+            CancelSmRespPdu::new(command_status, cancel_sm.sequence_number),
+        ),
+        Frame::ReplaceSm(replace_sm) => Frame::ReplaceSmResp(
+            ReplaceSmRespPdu::new(command_status, replace_sm.sequence_number),
+        ),
+        Frame::SubmitMulti(submit_multi) => {
+            Frame::SubmitMultiResp(SubmitMultiRespPdu::new_error(
+                command_status,
+                submit_multi.sequence_number,
+            ))
+        }
+        _ => return None,
+    })
+}
+
+fn bound_system_id(connection: &SmppConnection) -> Option<String> {
+    connection
+        .bound_esme_id()
+        .map(|esme_id| esme_id.system_id.to_string())
+}
+
+/// Write body to connection after a delay, without holding up the caller.
+fn send_later(connection: Arc<SmppConnection>, after: Duration, body: PduBody) {
+    tokio::spawn(async move {
+        let _queued = connection.queue(HEADER_LENGTH + body.encoded_len());
+        sleep(after).await;
+        let sequence_number = connection.next_sequence_number();
+        let pdu = Pdu::new(PduStatus::ESME_ROK as u32, sequence_number, body)
+            .unwrap();
+        if let Err(e) = connection.write_pdu(&pdu).await {
+            error!(
+                "Connection {} - failed to send scheduled PDU: {}",
+                connection, e
+            );
+        }
+    });
+}
+
+/// Is connection holding more than config.max_session_memory?
+fn over_session_memory(
+    connection: &SmppConnection,
+    config: &SmscConfig,
+) -> bool {
+    let limit = match config.max_session_memory {
+        Some(limit) => limit,
+        None => return false,
+    };
+    let memory = connection.memory();
+    if memory.total() <= limit {
+        return false;
+    }
+    warn!(
+        "Connection {} - holding {}, over the limit of {}",
+        connection, memory, limit
+    );
+    true
+}
+
+/// An ESME_RTHROTTLED response if pdu is a submit_sm
+fn throttled_submit_sm_resp(pdu: &Pdu) -> Option<Pdu> {
+    match pdu.body() {
+        PduBody::SubmitSm(_) => Pdu::new(
+            PduStatus::ESME_RTHROTTLED as u32,
+            pdu.sequence_number.value,
+            SubmitSmRespPdu::new_error().into(),
+        )
+        .ok(),
+        _ => None,
+    }
+}
+
+/// A generic_nack with ESME_RTHROTTLED if pdu is an enquire_link over
+/// limit.  smpp_pdu will not give enquire_link_resp an error status.
+fn throttled_enquire_link_resp(
+    pdu: &Pdu,
+    connection: &SmppConnection,
+    limit: &mut EnquireLinkLimit,
+) -> Option<Pdu> {
+    if !matches!(pdu.body(), PduBody::EnquireLink(_)) || limit.allow() {
+        return None;
+    }
+    if limit.just_exceeded() {
+        warn!("Connection {} - too many enquire_links", connection);
+    }
+    Pdu::new(
+        PduStatus::ESME_RTHROTTLED as u32,
+        pdu.sequence_number.value,
+        GenericNackPdu::new_error().into(),
+    )
+    .ok()
+}
+
+/// TLVs explaining why we rejected a PDU, if configured to send them.
+fn status_info(config: &SmscConfig, e: &ProcessError) -> Vec<Tlv> {
+    if config.status_info_text {
+        vec![typed_tlvs::additional_status_info_text(&e.to_string())]
+    } else {
+        vec![]
+    }
+}
+
+/// TLVs explaining a negative response (e.g. a rejected submit_sm), if
+/// configured to send them.
+fn rejection_info(config: &SmscConfig, response: &Pdu) -> Vec<Tlv> {
+    let command_status = response.command_status.value;
+    if config.status_info_text && command_status != PduStatus::ESME_ROK as u32 {
+        let text = StatusDescription(command_status).to_string();
+        vec![typed_tlvs::additional_status_info_text(&text)]
+    } else {
+        vec![]
+    }
+}
+
+/// The enquire_links we originate (see config.enquire_link_interval_secs).
+struct Keepalive {
+    last_received: Instant,
+    awaiting_resp: Option<(u32, Instant)>,
+}
+
+impl Keepalive {
+    fn new() -> Self {
+        Self {
+            last_received: TokioClock.now(),
+            awaiting_resp: None,
+        }
+    }
 
-                // Then return the error, so we drop the connection
-                return Err(pdu_parse_error.into());
+    /// Is this the response to the enquire_link we are waiting on?
+    fn is_resp(&self, pdu: &Pdu) -> bool {
+        match (pdu.body(), self.awaiting_resp) {
+            (PduBody::EnquireLinkResp(_), Some((sequence_number, _))) => {
+                pdu.sequence_number.value == sequence_number
             }
+            _ => false,
         }
     }
+
+    /// When we must next send enquire_link, or give up waiting for its resp.
+    fn deadline(&self, config: &SmscConfig) -> Option<Instant> {
+        let interval = Duration::from_secs(config.enquire_link_interval_secs?);
+        Some(match self.awaiting_resp {
+            Some((_, sent)) => {
+                sent + Duration::from_secs(config.enquire_link_timeout_secs)
+            }
+            None => self.last_received + interval,
+        })
+    }
+}
+
+enum ReadOutcome {
+    Read(Result<Option<Pdu>, PduParseError>),
+    /// The peer asked to end the session
+    Unbind(UnbindPdu),
+    DataSm(DataSmPdu),
+    QuerySm(QuerySmPdu),
+    CancelSm(CancelSmPdu),
+    ReplaceSm(ReplaceSmPdu),
+    SubmitMulti(SubmitMultiPdu),
+    /// Nothing but enquire_links for longer than config.idle_timeout_secs
+    Idle,
+    /// The peer did not answer our enquire_link
+    KeepaliveTimeout,
+    /// Someone called connection.request_close()
+    CloseRequested,
+    /// Part of a PDU arrived, but the rest is too slow, per
+    /// config.partial_pdu_timeout_secs or config.min_bytes_per_sec
+    SlowPdu,
+}
+
+fn slow_pdu_deadline_configured(config: &SmscConfig) -> bool {
+    config.partial_pdu_timeout_secs.is_some()
+        || config.min_bytes_per_sec.is_some_and(|r| r > 0)
+}
+
+/// When the PDU we are part way through receiving will have been arriving
+/// too slowly, if no more of it arrives before then.
+fn slow_pdu_deadline(
+    connection: &SmppConnection,
+    config: &SmscConfig,
+) -> Option<Instant> {
+    let partial_pdu = connection.partial_pdu()?;
+    let timeout = config
+        .partial_pdu_timeout_secs
+        .map(|t| partial_pdu.started + Duration::from_secs(t));
+    // The first whole second in which the average falls below the minimum
+    let too_slow = config.min_bytes_per_sec.filter(|r| *r > 0).map(|rate| {
+        let secs = partial_pdu.received as u64 / rate + 1;
+        partial_pdu.started + Duration::from_secs(secs)
+    });
+    timeout.into_iter().chain(too_slow).min()
+}
+
+/// Read the next PDU, sending enquire_link if the peer goes quiet, and
+/// giving up if the connection is idle, the peer stops responding or sends
+/// too slowly, or someone asks us to close it.
+async fn read_next_pdu(
+    connection: &SmppConnection,
+    config: &SmscConfig,
+    keepalive: &mut Keepalive,
+) -> Result<ReadOutcome, ProcessError> {
+    let idle_timeout = config.idle_timeout_secs.map(Duration::from_secs);
+    loop {
+        let idle_deadline = idle_timeout.map(|t| connection.idle_since() + t);
+        let keepalive_deadline = keepalive.deadline(config);
+        let deadline = idle_deadline
+            .into_iter()
+            .chain(keepalive_deadline)
+            .chain(slow_pdu_deadline(connection, config))
+            .min();
+
+        let read = async {
+            match deadline {
+                None => Ok(connection.read_frame().await),
+                Some(deadline) => {
+                    timeout_at(deadline.into(), connection.read_frame()).await
+                }
+            }
+        };
+        let pdu = tokio::select! {
+            _ = connection.close_requested() => {
+                return Ok(ReadOutcome::CloseRequested);
+            }
+            // Start again, with a deadline for the rest of it
+            _ = connection.partial_pdu_started(),
+                if slow_pdu_deadline_configured(config) => continue,
+            pdu = read => pdu,
+        };
+        let pdu = match pdu {
+            Ok(pdu) => pdu,
+            Err(_) => {
+                let now = TokioClock.now();
+                // We may have sent something (e.g. a deliver_sm) while
+                // we were waiting, in which case we are not idle after all.
+                if idle_timeout
+                    .is_some_and(|t| connection.idle_since() + t <= now)
+                {
+                    return Ok(ReadOutcome::Idle);
+                }
+                if slow_pdu_deadline(connection, config)
+                    .is_some_and(|d| d <= now)
+                {
+                    return Ok(ReadOutcome::SlowPdu);
+                }
+                if keepalive_deadline.is_some_and(|d| d <= now) {
+                    if keepalive.awaiting_resp.is_some() {
+                        return Ok(ReadOutcome::KeepaliveTimeout);
+                    }
+                    let sequence_number = connection.next_sequence_number();
+                    connection
+                        .write_pdu(
+                            &Pdu::new(
+                                PduStatus::ESME_ROK as u32,
+                                sequence_number,
+                                EnquireLinkPdu::new().into(),
+                            )
+                            .unwrap(),
+                        )
+                        .await?;
+                    keepalive.awaiting_resp =
+                        Some((sequence_number, TokioClock.now()));
+                }
+                continue;
+            }
+        };
+        keepalive.last_received = TokioClock.now();
+        let outcome = match pdu {
+            Ok(Some(Frame::Pdu(pdu))) => ReadOutcome::Read(Ok(Some(pdu))),
+            Ok(Some(Frame::Unbind(unbind))) => ReadOutcome::Unbind(unbind),
+            Ok(Some(Frame::DataSm(data_sm))) => ReadOutcome::DataSm(data_sm),
+            Ok(Some(Frame::QuerySm(query_sm))) => {
+                ReadOutcome::QuerySm(query_sm)
+            }
+            Ok(Some(Frame::CancelSm(cancel_sm))) => {
+                ReadOutcome::CancelSm(cancel_sm)
+            }
+            Ok(Some(Frame::ReplaceSm(replace_sm))) => {
+                ReadOutcome::ReplaceSm(replace_sm)
+            }
+            Ok(Some(Frame::SubmitMulti(submit_multi))) => {
+                ReadOutcome::SubmitMulti(submit_multi)
+            }
+            Ok(Some(Frame::AlertNotification(_))) => {
+                warn!(
+                    "Connection {} - ignoring alert_notification, which only \
+                    an SMSC should send",
+                    connection
+                );
+                continue;
+            }
+            Ok(Some(Frame::Outbind(_))) => {
+                warn!(
+                    "Connection {} - ignoring outbind, which only an SMSC \
+                    should send",
+                    connection
+                );
+                continue;
+            }
+            // deliver_sm_resp and unbind_resp need nothing from us
+            Ok(Some(_)) => continue,
+            Ok(None) => ReadOutcome::Read(Ok(None)),
+            Err(e) => ReadOutcome::Read(Err(e)),
+        };
+        return Ok(outcome);
+    }
+}
+
+/// smpp_pdu does not let us see which PduParseErrorBody we got, but only
+/// UnknownCommandId is answered with ESME_RINVCMDID.
+fn is_unknown_command(error: &PduParseError) -> bool {
+    error.recommended_status() == PduStatus::ESME_RINVCMDID
 }
 
 fn handle_pdu_parse_error(error: &PduParseError) -> Pdu {
     let sequence_number = error.sequence_number.unwrap_or(1);
-    match error.command_id {
-        Some(0x00000002) => Pdu::new(
-            error.status(),
+    let command_status = error.recommended_status() as u32;
+    match error.command_id.map(CommandId::try_from) {
+        Some(Ok(CommandId::BindReceiver)) => Pdu::new(
+            command_status,
+            sequence_number,
+            BindReceiverRespPdu::new_error().into(),
+        )
+        .unwrap(),
+        Some(Ok(CommandId::BindTransmitter)) => Pdu::new(
+            command_status,
             sequence_number,
             BindTransmitterRespPdu::new_error().into(),
         )
         .unwrap(),
         // For any PDU type we're not set up for, send generic_nack
         Some(_) => Pdu::new(
-            error.status(),
+            command_status,
             sequence_number,
             GenericNackPdu::new_error().into(),
         )
         .unwrap(),
         // If we don't even know the PDU type, send generic_nack
         None => Pdu::new(
-            error.status(),
+            command_status,
             sequence_number,
             GenericNackPdu::new_error().into(),
         )
@@ -446,7 +1763,7 @@ fn handle_pdu_parse_error(error: &PduParseError) -> Pdu {
 }
 
 async fn handle_bind_pdu<L: SmscLogic>(
-    pdu: Pdu,
+    pdu: &Pdu,
     connection: Arc<SmppConnection>,
     config: &SmscConfig,
     smsc_logic: Arc<Mutex<L>>,
@@ -515,6 +1832,16 @@ async fn handle_bind_pdu<L: SmscLogic>(
                 bind_data.system_type.value.clone(),
             )
             .await;
+        connection.set_session_info(SessionInfo {
+            bind_mode: match pdu.body() {
+                PduBody::BindReceiver(_) => BindMode::Receiver,
+                PduBody::BindTransmitter(_) => BindMode::Transmitter,
+                _ => BindMode::Transceiver,
+            },
+            interface_version: bind_data.interface_version.value,
+            // We do not limit the deliver_sm we have outstanding
+            window: None,
+        });
         // TODO: we only need to know about this connection if it can transmit,
         // right?
         smsc.lock().await.add_connection(connection);
@@ -535,6 +1862,62 @@ async fn handle_submit_sm_pdu<L: SmscLogic>(
     // find out using connection.bound_esme_id
 
     if let Some(esme_id) = connection.bound_esme_id() {
+        let expanded = smsc.lock().await.canned_messages.expand(body);
+        let expanded = match expanded {
+            Ok(expanded) => expanded,
+            Err(_) => {
+                return Pdu::new(
+                    PduStatus::ESME_RINVDFTMSGID as u32,
+                    sequence_number,
+                    SubmitSmRespPdu::new_error().into(),
+                )
+                .map_err(|e| e.into());
+            }
+        };
+        let body = expanded.as_ref().unwrap_or(body);
+
+        let message = match body.message_bytes() {
+            Ok(message) => message,
+            Err(_) => {
+                return Pdu::new(
+                    PduStatus::ESME_ROPTPARNOTALLWD as u32,
+                    sequence_number,
+                    SubmitSmRespPdu::new_error().into(),
+                )
+                .map_err(|e| e.into());
+            }
+        };
+
+        let fingerprint =
+            smsc.lock().await.fingerprint(&esme_id, body, &message);
+        if let Some(fingerprint) = &fingerprint {
+            let original = smsc
+                .lock()
+                .await
+                .suppress_duplicate(&connection, fingerprint);
+            if let Some(message_id) = original {
+                return Pdu::new(
+                    PduStatus::ESME_ROK as u32,
+                    sequence_number,
+                    SubmitSmRespPdu::new(&message_id)?.into(),
+                )
+                .map_err(|e| e.into());
+            }
+        }
+
+        if let Err(command_status) = smsc
+            .lock()
+            .await
+            .accepts_submit(&body.source_addr(), &body.destination_addr())
+        {
+            return Pdu::new(
+                command_status,
+                sequence_number,
+                SubmitSmRespPdu::new_error().into(),
+            )
+            .map_err(|e| e.into());
+        }
+
         let mut command_status = PduStatus::ESME_ROK;
         let resp = match smsc_logic
             .lock()
@@ -543,12 +1926,34 @@ async fn handle_submit_sm_pdu<L: SmscLogic>(
             .await
         {
             Ok((resp, message_unique_key)) => {
-                smsc.lock().await.add_message(message_unique_key, esme_id);
+                let archive = smsc.lock().await.archive.clone();
+                if let Some(archive) = archive {
+                    archive.archive(&esme_id, body, &message_unique_key).await;
+                }
+                let mut smsc = smsc.lock().await;
+                let uid = smsc.add_message(message_unique_key, esme_id);
+                let resp = match (&smsc.message_id_map, resp.message_id()) {
+                    (Some(map), Some(message_id)) => {
+                        SubmitSmRespPdu::new(&map.external(&message_id))?
+                    }
+                    _ => resp,
+                };
+                info!(
+                    "Connection {} - accepted submit_sm as {} with message_id {}",
+                    connection,
+                    uid,
+                    resp.message_id().unwrap_or_default()
+                );
+                if let (Some(duplicates), Some(fingerprint), Some(message_id)) =
+                    (&mut smsc.duplicates, fingerprint, resp.message_id())
+                {
+                    duplicates.record(fingerprint, &message_id);
+                }
                 resp
             }
             Err(e) => {
                 command_status = e.into();
-                SubmitSmRespPdu::new_error().into()
+                SubmitSmRespPdu::new_error()
             }
         };
         Pdu::new(command_status as u32, sequence_number, resp.into())
@@ -559,30 +1964,329 @@ async fn handle_submit_sm_pdu<L: SmscLogic>(
     }
 }
 
+/// Answer data_sm as we would the equivalent submit_sm, so that SmscLogic
+/// only ever sees submit_sm.
+async fn handle_data_sm<L: SmscLogic>(
+    data_sm: &DataSmPdu,
+    connection: Arc<SmppConnection>,
+    config: &SmscConfig,
+    smsc_logic: Arc<Mutex<L>>,
+    smsc: Arc<Mutex<Smsc>>,
+) -> Result<DataSmRespPdu, ProcessError> {
+    info!("<= {} {:?}", connection, data_sm);
+    let sequence_number = data_sm.sequence_number;
+    let reject = |command_status: PduStatus| {
+        Ok(DataSmRespPdu::new_error(
+            command_status as u32,
+            sequence_number,
+        ))
+    };
+    if data_sm.message_payload().is_none() {
+        return reject(PduStatus::ESME_RMISSINGOPTPARAM);
+    }
+    let submit_sm = match data_sm.to_submit_sm() {
+        Ok(submit_sm) => submit_sm,
+        Err(e) => return reject(e.recommended_status()),
+    };
+    let name = connection.to_string();
+    match handle_submit_sm_pdu(
+        &submit_sm,
+        sequence_number,
+        connection,
+        smsc_logic,
+        smsc,
+    )
+    .await
+    {
+        Ok(resp) => match resp.body() {
+            PduBody::SubmitSmResp(body) if resp.command_status.value == 0 => {
+                let message_id = body.message_id().unwrap_or_default();
+                Ok(DataSmRespPdu::new(sequence_number, &message_id)?)
+            }
+            _ => Ok(DataSmRespPdu::new_error(
+                resp.command_status.value,
+                sequence_number,
+            )),
+        },
+        Err(ProcessError::ConnectionNotBoundAsTransmitter) => {
+            reject(PduStatus::ESME_RINVBNDSTS)
+        }
+        Err(e) if e.is_fatal(config) => Err(e),
+        Err(e) => {
+            warn!("Connection {} - rejected data_sm: {}", name, e);
+            reject(PduStatus::ESME_RSYSERR)
+        }
+    }
+}
+
+/// Answer submit_multi as we would a submit_sm to each SME address, so that
+/// SmscLogic only ever sees submit_sm.  The response's message_id is that
+/// of the first destination accepted, and the rest that were refused are
+/// listed in unsuccess_sme.  We hold no distribution lists, so each named
+/// one is listed too, as an address with TON and NPI 0, with
+/// ESME_RINVDLNAME.  If every destination is refused, so is the whole
+/// submit_multi, with the first refusal's status.
+async fn handle_submit_multi<L: SmscLogic>(
+    submit_multi: &SubmitMultiPdu,
+    connection: Arc<SmppConnection>,
+    config: &SmscConfig,
+    smsc_logic: Arc<Mutex<L>>,
+    smsc: Arc<Mutex<Smsc>>,
+) -> Result<SubmitMultiRespPdu, ProcessError> {
+    info!("<= {} {:?}", connection, submit_multi);
+    let sequence_number = submit_multi.sequence_number;
+    let mut message_id = None;
+    let mut unsuccess_smes = Vec::new();
+    for dest_address in &submit_multi.dest_addresses {
+        let address = match dest_address {
+            DestAddress::Sme(address) => address,
+            DestAddress::DistributionList(dl_name) => {
+                unsuccess_smes.push(UnsuccessSme {
+                    address: SmeAddress {
+                        dest_addr_ton: 0,
+                        dest_addr_npi: 0,
+                        destination_addr: dl_name.clone(),
+                    },
+                    error_status_code: PduStatus::ESME_RINVDLNAME as u32,
+                });
+                continue;
+            }
+        };
+        let refuse = |command_status: u32| UnsuccessSme {
+            address: address.clone(),
+            error_status_code: command_status,
+        };
+        let submit_sm = match submit_multi.to_submit_sm(address) {
+            Ok(submit_sm) => submit_sm,
+            Err(e) => {
+                unsuccess_smes.push(refuse(e.recommended_status() as u32));
+                continue;
+            }
+        };
+        match handle_submit_sm_pdu(
+            &submit_sm,
+            sequence_number,
+            Arc::clone(&connection),
+            Arc::clone(&smsc_logic),
+            Arc::clone(&smsc),
+        )
+        .await
+        {
+            Ok(resp) => match resp.body() {
+                PduBody::SubmitSmResp(body)
+                    if resp.command_status.value == 0 =>
+                {
+                    message_id
+                        .get_or_insert(body.message_id().unwrap_or_default());
+                }
+                _ => unsuccess_smes.push(refuse(resp.command_status.value)),
+            },
+            Err(ProcessError::ConnectionNotBoundAsTransmitter) => {
+                return Ok(SubmitMultiRespPdu::new_error(
+                    PduStatus::ESME_RINVBNDSTS as u32,
+                    sequence_number,
+                ));
+            }
+            Err(e) if e.is_fatal(config) => return Err(e),
+            Err(e) => {
+                warn!(
+                    "Connection {} - rejected submit_multi to {}: {}",
+                    connection, address.destination_addr.value, e
+                );
+                unsuccess_smes.push(refuse(PduStatus::ESME_RSYSERR as u32));
+            }
+        }
+    }
+    match message_id {
+        Some(message_id) => Ok(SubmitMultiRespPdu::new(
+            sequence_number,
+            &message_id,
+            unsuccess_smes,
+        )?),
+        None => Ok(SubmitMultiRespPdu::new_error(
+            unsuccess_smes[0].error_status_code,
+            sequence_number,
+        )),
+    }
+}
+
+/// The message_id SmscLogic gave out that an ESME knows as external, or
+/// None if there is a MessageIdMap and external did not come from it.
+async fn internal_message_id(
+    smsc: &Mutex<Smsc>,
+    external: &COctetString,
+) -> Option<COctetString> {
+    match &smsc.lock().await.message_id_map {
+        Some(map) => {
+            map.internal(external.value.as_str()).and_then(|internal| {
+                // As long as in submit_sm_resp
+                COctetString::from_str(&internal, 65).ok()
+            })
+        }
+        None => Some(external.clone()),
+    }
+}
+
+/// Ask SmscLogic after the message, translating its message_id through any
+/// MessageIdMap.
+async fn handle_query_sm<L: SmscLogic>(
+    query_sm: &QuerySmPdu,
+    connection: &SmppConnection,
+    smsc_logic: Arc<Mutex<L>>,
+    smsc: Arc<Mutex<Smsc>>,
+) -> QuerySmRespPdu {
+    info!("<= {} {:?}", connection, query_sm);
+    let sequence_number = query_sm.sequence_number;
+    let reject = |command_status: PduStatus| {
+        QuerySmRespPdu::new_error(command_status as u32, sequence_number)
+    };
+    let system_id = match bound_system_id(connection) {
+        Some(system_id) => system_id,
+        None => return reject(PduStatus::ESME_RINVBNDSTS),
+    };
+    let key = QueryKey::new(&system_id, query_sm);
+    let cached = |smsc: &mut Smsc| {
+        let resp = smsc.query_cache.as_mut()?.get(&key)?;
+        info!("Connection {} - answering query_sm from cache", connection);
+        Some(QuerySmRespPdu {
+            sequence_number,
+            ..resp
+        })
+    };
+    if let Some(resp) = cached(&mut *smsc.lock().await) {
+        return resp;
+    }
+    let internal_query =
+        match internal_message_id(&smsc, &query_sm.message_id).await {
+            Some(message_id) => QuerySmPdu {
+                message_id,
+                ..query_sm.clone()
+            },
+            None => return reject(PduStatus::ESME_RQUERYFAIL),
+        };
+    let mut smsc_logic = smsc_logic.lock().await;
+    // An identical query may have been answered while we waited
+    if let Some(resp) = cached(&mut *smsc.lock().await) {
+        return resp;
+    }
+    let resp = match smsc_logic
+        .query_sm(Arc::clone(&smsc), &internal_query)
+        .await
+    {
+        Ok(resp) => QuerySmRespPdu {
+            sequence_number,
+            message_id: query_sm.message_id.clone(),
+            ..resp
+        },
+        Err(e) => reject(e.into()),
+    };
+    if let Some(cache) = &mut smsc.lock().await.query_cache {
+        cache.insert(key, resp.clone());
+    }
+    resp
+}
+
+/// Ask SmscLogic to cancel, translating any message_id as for query_sm.
+async fn handle_cancel_sm<L: SmscLogic>(
+    cancel_sm: &CancelSmPdu,
+    connection: &SmppConnection,
+    smsc_logic: Arc<Mutex<L>>,
+    smsc: Arc<Mutex<Smsc>>,
+) -> CancelSmRespPdu {
+    info!("<= {} {:?}", connection, cancel_sm);
+    let respond = |command_status: PduStatus| {
+        CancelSmRespPdu::new(command_status as u32, cancel_sm.sequence_number)
+    };
+    if connection.bound_esme_id().is_none() {
+        return respond(PduStatus::ESME_RINVBNDSTS);
+    }
+    let internal_cancel = if cancel_sm.message_id.value.is_empty() {
+        cancel_sm.clone()
+    } else {
+        match internal_message_id(&smsc, &cancel_sm.message_id).await {
+            Some(message_id) => CancelSmPdu {
+                message_id,
+                ..cancel_sm.clone()
+            },
+            None => return respond(PduStatus::ESME_RCANCELFAIL),
+        }
+    };
+    match smsc_logic
+        .lock()
+        .await
+        .cancel_sm(Arc::clone(&smsc), &internal_cancel)
+        .await
+    {
+        Ok(()) => {
+            if let Some(cache) = &mut smsc.lock().await.query_cache {
+                if cancel_sm.message_id.value.is_empty() {
+                    cache.clear();
+                } else {
+                    cache.forget(cancel_sm.message_id.value.as_str());
+                }
+            }
+            respond(PduStatus::ESME_ROK)
+        }
+        Err(e) => respond(e.into()),
+    }
+}
+
+/// Ask SmscLogic to replace, translating the message_id as for query_sm.
+async fn handle_replace_sm<L: SmscLogic>(
+    replace_sm: &ReplaceSmPdu,
+    connection: &SmppConnection,
+    smsc_logic: Arc<Mutex<L>>,
+    smsc: Arc<Mutex<Smsc>>,
+) -> ReplaceSmRespPdu {
+    info!("<= {} {:?}", connection, replace_sm);
+    let respond = |command_status: PduStatus| {
+        ReplaceSmRespPdu::new(command_status as u32, replace_sm.sequence_number)
+    };
+    if connection.bound_esme_id().is_none() {
+        return respond(PduStatus::ESME_RINVBNDSTS);
+    }
+    let internal_replace =
+        match internal_message_id(&smsc, &replace_sm.message_id).await {
+            Some(message_id) => ReplaceSmPdu {
+                message_id,
+                ..replace_sm.clone()
+            },
+            None => return respond(PduStatus::ESME_RREPLACEFAIL),
+        };
+    match smsc_logic
+        .lock()
+        .await
+        .replace_sm(Arc::clone(&smsc), &internal_replace)
+        .await
+    {
+        Ok(()) => {
+            if let Some(cache) = &mut smsc.lock().await.query_cache {
+                cache.forget(replace_sm.message_id.value.as_str());
+            }
+            respond(PduStatus::ESME_ROK)
+        }
+        Err(e) => respond(e.into()),
+    }
+}
+
 async fn handle_pdu<L: SmscLogic>(
-    pdu: Pdu,
+    pdu: &Pdu,
     connection: Arc<SmppConnection>,
     config: &SmscConfig,
     smsc_logic: Arc<Mutex<L>>,
     smsc: Arc<Mutex<Smsc>>,
 ) -> Result<Pdu, ProcessError> {
-    info!("<= {} {:?}", connection.socket_addr, pdu);
+    info!("<= {} {:?}", connection, Redacted(pdu));
     let sequence_number = pdu.sequence_number.value;
     match pdu.body() {
         PduBody::BindReceiver(_body) => {
-            handle_bind_pdu(pdu, connection, config, smsc_logic, smsc)
-                .await
-                .map_err(|e| e.into())
+            handle_bind_pdu(pdu, connection, config, smsc_logic, smsc).await
         }
         PduBody::BindTransmitter(_body) => {
-            handle_bind_pdu(pdu, connection, config, smsc_logic, smsc)
-                .await
-                .map_err(|e| e.into())
+            handle_bind_pdu(pdu, connection, config, smsc_logic, smsc).await
         }
         PduBody::BindTransceiver(_body) => {
-            handle_bind_pdu(pdu, connection, config, smsc_logic, smsc)
-                .await
-                .map_err(|e| e.into())
+            handle_bind_pdu(pdu, connection, config, smsc_logic, smsc).await
         }
 
         PduBody::EnquireLink(_body) => Pdu::new(
@@ -1,10 +1,21 @@
 use clap::Clap;
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::canned_messages::CannedMessage;
+use crate::pdu_status::parse_status;
+use crate::session_memory::SessionMemoryAction;
+use crate::smsc::{DestinationLimit, Latency, SourceQuota};
+use crate::text::DataCodingRemap;
 
 /// Short Message Service Center (SMSC) in Rust
 #[derive(Clap, Clone, Debug)]
 #[clap(name = "smsc")]
 pub struct SmscConfig {
-    /// Address to bind on
+    /// Address to bind on, unless systemd passes us a listening socket
+    /// (LISTEN_FDS)
     #[clap(short, long, default_value = "0.0.0.0:8080", env = "BIND_ADDRESS")]
     pub bind_address: String,
 
@@ -15,4 +26,248 @@ pub struct SmscConfig {
     /// system_id used as an identifier of the SMSC
     #[clap(short, long, default_value = "rust_smpp", env = "SYSTEM_ID")]
     pub system_id: String,
+
+    /// When a client sends a PDU we can't handle, reject that PDU and carry
+    /// on reading where we can find where it ended, instead of closing the
+    /// connection
+    #[clap(long, env = "RECOVER_FROM_BAD_PDUS")]
+    pub recover_from_bad_pdus: bool,
+
+    /// What to do when a client sends a command_id we do not know: nack
+    /// (respond generic_nack with ESME_RINVCMDID), ignore, or close
+    #[clap(long, default_value = "nack", env = "UNKNOWN_COMMAND")]
+    pub unknown_command: UnknownCommandAction,
+
+    /// Record the PDUs on each connection, up to the last 1000, and log them
+    /// in the layout Wireshark uses when the connection closes.  Bind
+    /// passwords are masked.
+    #[clap(long)]
+    pub capture_sessions: bool,
+
+    /// Close connections that have sent and received nothing except
+    /// enquire_link and enquire_link_resp for this many seconds
+    #[clap(long, env = "IDLE_TIMEOUT_SECS")]
+    pub idle_timeout_secs: Option<u64>,
+
+    /// Send enquire_link to a connection when we have received nothing from
+    /// it for this many seconds
+    #[clap(long, env = "ENQUIRE_LINK_INTERVAL_SECS")]
+    pub enquire_link_interval_secs: Option<u64>,
+
+    /// Close the connection if enquire_link_resp does not arrive this many
+    /// seconds after we sent enquire_link
+    #[clap(long, default_value = "10", env = "ENQUIRE_LINK_TIMEOUT_SECS")]
+    pub enquire_link_timeout_secs: u64,
+
+    /// Close the connection if a PDU takes longer than this many seconds to
+    /// arrive after its first byte, so that clients trickling bytes cannot
+    /// tie up connections (slowloris)
+    #[clap(long, env = "PARTIAL_PDU_TIMEOUT_SECS")]
+    pub partial_pdu_timeout_secs: Option<u64>,
+
+    /// Close the connection if, while part of a PDU has arrived, the rest
+    /// arrives at fewer than this many bytes per second on average
+    #[clap(long, env = "MIN_BYTES_PER_SEC")]
+    pub min_bytes_per_sec: Option<u64>,
+
+    /// Throttle or close (see --session-memory-action) a connection while
+    /// it holds more than this many bytes in its read buffer, queued PDUs
+    /// and unanswered requests, so that one ESME cannot exhaust our memory
+    #[clap(long, env = "MAX_SESSION_MEMORY")]
+    pub max_session_memory: Option<usize>,
+
+    /// Reject PDUs whose command_length is over this many bytes.  Lower it
+    /// to limit what one PDU can make us buffer, or raise it for ESMEs that
+    /// send submit_sm with a large message_payload
+    #[clap(long, default_value = "70000", env = "MAX_PDU_LENGTH")]
+    pub max_pdu_length: usize,
+
+    /// Accept PDUs that break the spec in common ways (non-ASCII or
+    /// unterminated strings, bodies on error responses), logging a warning
+    /// for each, instead of rejecting them
+    #[clap(long)]
+    pub lenient_parsing: bool,
+
+    /// What to do with a connection over --max-session-memory: throttle
+    /// (reject submit_sm with ESME_RTHROTTLED) or close
+    #[clap(long, default_value = "throttle", env = "SESSION_MEMORY_ACTION")]
+    pub session_memory_action: SessionMemoryAction,
+
+    /// Country code for destination addresses written in national format,
+    /// e.g. 44 to treat 07700900123 as 447700900123
+    #[clap(long, env = "DEFAULT_COUNTRY_CODE")]
+    pub default_country_code: Option<String>,
+
+    /// Reject submit_sm with ESME_RTHROTTLED when more than RATE per second
+    /// are sent to destination addresses starting with PREFIX.  Written
+    /// PREFIX=RATE, and may be repeated.  The longest matching prefix applies.
+    /// Prefixes match addresses in E.164 form, without '+'
+    #[clap(long = "destination-limit")]
+    pub destination_limits: Vec<DestinationLimit>,
+
+    /// Send TEXT for a submit_sm with sm_default_msg_id ID and no message
+    /// of its own.  Written ID=TEXT, with ID from 1 to 254, and may be
+    /// repeated.  Other ids are rejected with ESME_RINVDFTMSGID
+    #[clap(long = "canned-message")]
+    pub canned_messages: Vec<CannedMessage>,
+
+    /// Reject submit_sm from a source address that has already sent LIMIT
+    /// messages in the last PERIOD.  Written LIMIT/PERIOD, where PERIOD is
+    /// minute, hour or day, e.g. 100/minute.  May be repeated
+    #[clap(long = "source-quota")]
+    pub source_quotas: Vec<SourceQuota>,
+
+    /// The command_status for submit_sm over a --source-quota, as a name
+    /// like ESME_RTHROTTLED or a number
+    #[clap(
+        long,
+        default_value = "ESME_RTHROTTLED",
+        parse(try_from_str = parse_command_status),
+        env = "SOURCE_QUOTA_STATUS"
+    )]
+    pub source_quota_status: u32,
+
+    /// Log a warning for each submit_sm with the same ESME, source,
+    /// destination and content as one accepted this many seconds ago or
+    /// less
+    #[clap(long, env = "DUPLICATE_WINDOW_SECS")]
+    pub duplicate_window_secs: Option<u64>,
+
+    /// As well as warning of a duplicate submit_sm, answer it with the
+    /// message_id of the original instead of submitting it again.  Needs
+    /// --duplicate-window-secs
+    #[clap(long)]
+    pub suppress_duplicates: bool,
+
+    /// Answer a query_sm the same as an identical one from the same ESME
+    /// answered this many milliseconds ago or less, without asking
+    /// SmscLogic again
+    #[clap(long, env = "QUERY_CACHE_MS")]
+    pub query_cache_ms: Option<u64>,
+
+    /// Answer enquire_link with generic_nack and ESME_RTHROTTLED after this
+    /// many from one connection in the same second
+    #[clap(long, env = "MAX_ENQUIRE_LINKS_PER_SEC")]
+    pub max_enquire_links_per_sec: Option<u32>,
+
+    /// What a data_coding value means to our ESMEs, when it differs from the
+    /// SMPP specification.  Written DATA_CODING=CHARSET, where CHARSET is
+    /// gsm7, ascii, latin1 or ucs2, e.g. 0=latin1.  May be repeated
+    #[clap(long = "data-coding")]
+    pub data_coding_remaps: Vec<DataCodingRemap>,
+
+    /// Explain negative responses in an additional_status_info_text TLV.
+    /// This is allowed by SMPP 5.0, but SMPP 3.4 ESMEs may not expect it
+    #[clap(long)]
+    pub status_info_text: bool,
+
+    /// Script of per-account rules for simulating an awkward SMSC, e.g.
+    /// rejecting every Nth submit_sm or delaying responses.  See the
+    /// scenario module for the format
+    #[clap(long, env = "SCENARIO")]
+    pub scenario: Option<PathBuf>,
+
+    /// Wait a random time before sending each response, in milliseconds.
+    /// Written fixed:MS, uniform:MIN-MAX or exponential:MEAN
+    #[clap(long, env = "LATENCY")]
+    pub latency: Option<Latency>,
+
+    /// Percentage chance that a submit_sm, data_sm or submit_multi starts a
+    /// burst of ESME_RTHROTTLED responses on its connection
+    #[clap(long, default_value = "0", env = "THROTTLE_BURST_PERCENT")]
+    pub throttle_burst_percent: f64,
+
+    /// How many submits in a row a burst of ESME_RTHROTTLED rejects
+    #[clap(long, default_value = "10", env = "THROTTLE_BURST_LENGTH")]
+    pub throttle_burst_length: u32,
+
+    /// Close every bound connection this often, in seconds, forcing ESMEs
+    /// to rebind
+    #[clap(long, env = "FORCED_UNBIND_SECS")]
+    pub forced_unbind_secs: Option<u64>,
+
+    /// Seed for --latency and --throttle-burst-percent, to repeat a run.
+    /// Taken from the clock if omitted
+    #[clap(long, env = "CHAOS_SEED")]
+    pub chaos_seed: Option<u64>,
+
+    /// Address to serve the HTTP admin endpoint on.  Not served if omitted.
+    /// The endpoint has no authentication, so bind it to localhost (e.g.
+    /// 127.0.0.1:8081) unless only operators can reach the interface
+    #[cfg(feature = "admin-http")]
+    #[clap(long, env = "ADMIN_ADDRESS")]
+    pub admin_address: Option<String>,
+
+    /// Address to accept SMPP over WebSocket on.  Not accepted if omitted
+    #[cfg(feature = "websocket")]
+    #[clap(long, env = "WEBSOCKET_ADDRESS")]
+    pub websocket_address: Option<String>,
+
+    /// Close WebSocket connections that have not finished the opening
+    /// handshake after this many seconds
+    #[cfg(feature = "websocket")]
+    #[clap(
+        long,
+        default_value = "10",
+        env = "WEBSOCKET_HANDSHAKE_TIMEOUT_SECS"
+    )]
+    pub websocket_handshake_timeout_secs: u64,
+
+    /// zlib-compress SMPP on --bind-address.  Only for ESMEs that compress
+    /// too, since nothing on the wire says it is in use
+    #[cfg(feature = "compression")]
+    #[clap(long)]
+    pub compress: bool,
+
+    /// Path of a Unix domain socket to listen on as well, for applications
+    /// on the same host.  Not listened on if omitted
+    #[cfg(unix)]
+    #[clap(long, env = "UNIX_SOCKET")]
+    pub unix_socket: Option<PathBuf>,
+}
+
+fn parse_command_status(s: &str) -> Result<u32, String> {
+    parse_status(s).ok_or_else(|| {
+        format!(
+            "Invalid command_status '{}': expected a name like \
+            ESME_RTHROTTLED or a number",
+            s
+        )
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnknownCommandAction {
+    Nack,
+    Ignore,
+    Close,
+}
+
+#[derive(Debug)]
+pub struct ParseUnknownCommandActionError(String);
+
+impl Display for ParseUnknownCommandActionError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "Invalid unknown command action '{}': expected nack, ignore or \
+            close",
+            self.0
+        )
+    }
+}
+
+impl error::Error for ParseUnknownCommandActionError {}
+
+impl FromStr for UnknownCommandAction {
+    type Err = ParseUnknownCommandActionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nack" => Ok(Self::Nack),
+            "ignore" => Ok(Self::Ignore),
+            "close" => Ok(Self::Close),
+            _ => Err(ParseUnknownCommandActionError(String::from(s))),
+        }
+    }
 }
@@ -5,7 +5,10 @@ use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::cancel_sm::CancelSmPdu;
 use crate::message_unique_key::MessageUniqueKey;
+use crate::query_sm::{QuerySmPdu, QuerySmRespPdu};
+use crate::replace_sm::ReplaceSmPdu;
 use crate::smsc::Smsc;
 
 pub enum BindError {
@@ -23,19 +26,70 @@ impl From<BindError> for PduStatus {
 }
 
 pub enum SubmitSmError {
+    /// We have no way to reach destination_addr
+    InvalidDestination,
+    /// Try again later
+    Throttled,
     InternalError,
 }
 
 impl From<SubmitSmError> for PduStatus {
     fn from(e: SubmitSmError) -> PduStatus {
         match e {
+            SubmitSmError::InvalidDestination => PduStatus::ESME_RINVDSTADR,
+            SubmitSmError::Throttled => PduStatus::ESME_RTHROTTLED,
             SubmitSmError::InternalError => PduStatus::ESME_RSYSERR,
         }
     }
 }
 
+pub enum QuerySmError {
+    /// We know of no such message from this source_addr
+    UnknownMessage,
+    InternalError,
+}
+
+impl From<QuerySmError> for PduStatus {
+    fn from(e: QuerySmError) -> PduStatus {
+        match e {
+            QuerySmError::UnknownMessage => PduStatus::ESME_RQUERYFAIL,
+            QuerySmError::InternalError => PduStatus::ESME_RSYSERR,
+        }
+    }
+}
+
+pub enum CancelSmError {
+    /// No such message, or it can no longer be cancelled
+    CancelFailed,
+    InternalError,
+}
+
+impl From<CancelSmError> for PduStatus {
+    fn from(e: CancelSmError) -> PduStatus {
+        match e {
+            CancelSmError::CancelFailed => PduStatus::ESME_RCANCELFAIL,
+            CancelSmError::InternalError => PduStatus::ESME_RSYSERR,
+        }
+    }
+}
+
+pub enum ReplaceSmError {
+    /// No such message, or it can no longer be replaced
+    ReplaceFailed,
+    InternalError,
+}
+
+impl From<ReplaceSmError> for PduStatus {
+    fn from(e: ReplaceSmError) -> PduStatus {
+        match e {
+            ReplaceSmError::ReplaceFailed => PduStatus::ESME_RREPLACEFAIL,
+            ReplaceSmError::InternalError => PduStatus::ESME_RSYSERR,
+        }
+    }
+}
+
 #[async_trait]
-pub trait SmscLogic {
+pub trait SmscLogic: Send {
     async fn bind(&mut self, bind_data: &BindData) -> Result<(), BindError>;
     async fn submit_sm(
         &mut self,
@@ -43,4 +97,41 @@ pub trait SmscLogic {
         pdu: &SubmitSmPdu,
         sequence_number: u32,
     ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError>;
+
+    /// Called with the raw bytes of each PDU whose command_id we do not
+    /// know, whatever --unknown-command says to do with it.  frame is cut
+    /// short as for SmppConnection::bad_frames().
+    async fn unknown_command(&mut self, _frame: &[u8]) {}
+
+    /// The state of the message pdu asks after.  Its message_id is the one
+    /// submit_sm returned, before any MessageIdMap.  The Smsc fills in the
+    /// response's sequence_number and message_id.  By default, every
+    /// message is unknown.
+    async fn query_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        _pdu: &QuerySmPdu,
+    ) -> Result<QuerySmRespPdu, QuerySmError> {
+        Err(QuerySmError::UnknownMessage)
+    }
+
+    /// Withdraw the messages pdu names.  Its message_id, if any, is as for
+    /// query_sm.  By default, nothing can be cancelled.
+    async fn cancel_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        _pdu: &CancelSmPdu,
+    ) -> Result<(), CancelSmError> {
+        Err(CancelSmError::CancelFailed)
+    }
+
+    /// Change the message pdu names.  Its message_id is as for query_sm.
+    /// By default, nothing can be replaced.
+    async fn replace_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        _pdu: &ReplaceSmPdu,
+    ) -> Result<(), ReplaceSmError> {
+        Err(ReplaceSmError::ReplaceFailed)
+    }
 }
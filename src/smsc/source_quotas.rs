@@ -0,0 +1,220 @@
+use std::collections::{HashMap, VecDeque};
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, TokioClock};
+
+/// How often SourceQuotas forgets idle source addresses
+pub const EXPIRE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum QuotaPeriod {
+    Minute,
+    Hour,
+    Day,
+}
+
+impl QuotaPeriod {
+    const NAMES: &'static [(&'static str, QuotaPeriod)] = &[
+        ("minute", Self::Minute),
+        ("hour", Self::Hour),
+        ("day", Self::Day),
+    ];
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(match self {
+            Self::Minute => 60,
+            Self::Hour => 60 * 60,
+            Self::Day => 24 * 60 * 60,
+        })
+    }
+}
+
+/// At most limit messages from each source address in any rolling period.
+/// Written LIMIT/PERIOD, e.g. "100/minute" or "5000/day".
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SourceQuota {
+    pub limit: u32,
+    pub period: QuotaPeriod,
+}
+
+impl Display for SourceQuota {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        let (name, _) = QuotaPeriod::NAMES
+            .iter()
+            .find(|(_, period)| *period == self.period)
+            .unwrap();
+        write!(formatter, "{}/{}", self.limit, name)
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseSourceQuotaError(String);
+
+impl Display for ParseSourceQuotaError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "Invalid source quota '{}': expected LIMIT/PERIOD, where PERIOD \
+            is minute, hour or day, e.g. 100/minute",
+            self.0
+        )
+    }
+}
+
+impl error::Error for ParseSourceQuotaError {}
+
+impl FromStr for SourceQuota {
+    type Err = ParseSourceQuotaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseSourceQuotaError(String::from(s));
+        let (limit, period) = s.split_once('/').ok_or_else(err)?;
+        let limit = limit.parse().map_err(|_| err())?;
+        if limit == 0 {
+            return Err(err());
+        }
+        let (_, period) = QuotaPeriod::NAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(period))
+            .ok_or_else(err)?;
+        Ok(Self {
+            limit,
+            period: *period,
+        })
+    }
+}
+
+/// A submit_sm rejected because its source address is over a quota.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QuotaExceeded {
+    pub source_addr: String,
+    pub quota: SourceQuota,
+    /// True for the first rejection since source_addr was last within its
+    /// quotas, so that alerts need not fire for every one
+    pub first: bool,
+}
+
+/// Told when a source address goes over a quota, e.g. to raise an alert.
+/// Register with Smsc::set_source_quota_alerts().
+pub trait SourceQuotaAlerts {
+    /// Called for the first rejection of each run, not for every one.
+    fn quota_exceeded(&self, event: &QuotaExceeded);
+}
+
+/// How many messages each source address has sent within each quota's
+/// period, counted from the times of the most recent ones.
+pub struct SourceQuotas {
+    quotas: Vec<SourceQuota>,
+    senders: HashMap<String, Sender>,
+    last_expired: Option<Instant>,
+}
+
+#[derive(Default)]
+struct Sender {
+    /// Times of recent messages, oldest first.  Only as many as the largest
+    /// limit are kept, since older ones cannot matter.
+    sent: VecDeque<Instant>,
+    over_quota: bool,
+}
+
+impl SourceQuotas {
+    pub fn new(quotas: &[SourceQuota]) -> Self {
+        Self {
+            quotas: Vec::from(quotas),
+            senders: HashMap::new(),
+            last_expired: None,
+        }
+    }
+
+    /// Record a message from source_addr, or refuse it if that would take
+    /// source_addr over a quota.  Refused messages do not count.
+    pub fn try_acquire(
+        &mut self,
+        source_addr: &str,
+    ) -> Result<(), QuotaExceeded> {
+        self.try_acquire_at(source_addr, TokioClock.now())
+    }
+
+    pub fn try_acquire_at(
+        &mut self,
+        source_addr: &str,
+        now: Instant,
+    ) -> Result<(), QuotaExceeded> {
+        let max_limit = match self.quotas.iter().map(|q| q.limit).max() {
+            Some(max_limit) => max_limit as usize,
+            None => return Ok(()),
+        };
+        let expire_due = self.last_expired.map_or(true, |last| {
+            now.saturating_duration_since(last) >= EXPIRE_INTERVAL
+        });
+        if expire_due {
+            self.expire(now);
+        }
+        let sender = self.senders.entry(String::from(source_addr)).or_default();
+        let exceeded = self
+            .quotas
+            .iter()
+            .find(|quota| count_since(sender, quota, now) >= quota.limit);
+        if let Some(quota) = exceeded {
+            let first = !sender.over_quota;
+            sender.over_quota = true;
+            return Err(QuotaExceeded {
+                source_addr: String::from(source_addr),
+                quota: *quota,
+                first,
+            });
+        }
+        sender.over_quota = false;
+        sender.sent.push_back(now);
+        if sender.sent.len() > max_limit {
+            sender.sent.pop_front();
+        }
+        Ok(())
+    }
+
+    /// How many messages source_addr has sent within quota's period.
+    pub fn count(&self, source_addr: &str, quota: &SourceQuota) -> u32 {
+        self.count_at(source_addr, quota, TokioClock.now())
+    }
+
+    pub fn count_at(
+        &self,
+        source_addr: &str,
+        quota: &SourceQuota,
+        now: Instant,
+    ) -> u32 {
+        self.senders
+            .get(source_addr)
+            .map_or(0, |sender| count_since(sender, quota, now))
+    }
+
+    /// Forget source addresses with nothing sent within any period, to
+    /// bound memory when there are many of them.  try_acquire() does this
+    /// every EXPIRE_INTERVAL.
+    pub fn expire(&mut self, now: Instant) {
+        self.last_expired = Some(now);
+        let longest =
+            match self.quotas.iter().map(|q| q.period.duration()).max() {
+                Some(longest) => longest,
+                None => return,
+            };
+        self.senders.retain(|_, sender| {
+            sender.sent.back().is_some_and(|last| {
+                now.saturating_duration_since(*last) < longest
+            })
+        });
+    }
+}
+
+fn count_since(sender: &Sender, quota: &SourceQuota, now: Instant) -> u32 {
+    let period = quota.period.duration();
+    sender
+        .sent
+        .iter()
+        .rev()
+        .take_while(|sent| now.saturating_duration_since(**sent) < period)
+        .count() as u32
+}
@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+use smpp_pdu::pdu::SubmitSmPdu;
+
+use crate::message_unique_key::MessageUniqueKey;
+use crate::smpp_connection::EsmeId;
+
+/// Receives a copy of every submit_sm the SMSC accepts, e.g. to keep it in
+/// long-term storage for compliance.  Register with Smsc::set_archive().
+#[async_trait]
+pub trait SubmitSmArchive {
+    /// Called after SmscLogic::submit_sm accepts a message, before we send
+    /// submit_sm_resp.
+    async fn archive(
+        &self,
+        esme_id: &EsmeId,
+        pdu: &SubmitSmPdu,
+        message_unique_key: &MessageUniqueKey,
+    );
+}
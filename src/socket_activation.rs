@@ -0,0 +1,73 @@
+//! Accept a listening socket passed in by systemd (or anything else that
+//! follows the sd_listen_fds(3) protocol), so the SMSC can use socket
+//! activation and be restarted without refusing connections.
+
+use std::env;
+use std::io;
+use std::net::TcpListener;
+
+/// The first file descriptor passed to us.  (0-2 are stdin, stdout and
+/// stderr.)
+pub const SD_LISTEN_FDS_START: i32 = 3;
+
+/// How many file descriptors were passed to process `pid`, given the values
+/// of the LISTEN_PID and LISTEN_FDS environment variables.  LISTEN_PID
+/// guards against us using sockets that were intended for our parent.
+pub fn passed_fd_count(
+    listen_pid: Option<&str>,
+    listen_fds: Option<&str>,
+    pid: u32,
+) -> usize {
+    match (listen_pid, listen_fds) {
+        (Some(listen_pid), Some(listen_fds))
+            if listen_pid.parse() == Ok(pid) =>
+        {
+            listen_fds.parse().unwrap_or(0)
+        }
+        _ => 0,
+    }
+}
+
+/// If we were passed a listening socket, take it and return it, removing
+/// the environment variables so that child processes do not try to use it
+/// too.  Returns None if no socket was passed.
+pub fn take_listener() -> io::Result<Option<TcpListener>> {
+    let count = passed_fd_count(
+        env::var("LISTEN_PID").ok().as_deref(),
+        env::var("LISTEN_FDS").ok().as_deref(),
+        std::process::id(),
+    );
+    if count == 0 {
+        return Ok(None);
+    }
+    env::remove_var("LISTEN_PID");
+    env::remove_var("LISTEN_FDS");
+    env::remove_var("LISTEN_FDNAMES");
+
+    if count > 1 {
+        log::warn!(
+            "Passed {} sockets, but we only listen on the first.",
+            count
+        );
+    }
+    listener_from_fd(SD_LISTEN_FDS_START).map(Some)
+}
+
+#[cfg(unix)]
+fn listener_from_fd(fd: i32) -> io::Result<TcpListener> {
+    use std::os::unix::io::FromRawFd;
+
+    // Safety: sd_listen_fds(3) guarantees this fd is open and ours, and we
+    // only take it once because we removed LISTEN_FDS above.
+    let listener = unsafe { TcpListener::from_raw_fd(fd) };
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+#[cfg(not(unix))]
+fn listener_from_fd(_fd: i32) -> io::Result<TcpListener> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "Socket activation is only supported on Unix.",
+    ))
+}
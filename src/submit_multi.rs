@@ -0,0 +1,533 @@
+//! submit_multi and submit_multi_resp, with which an ESME sends one message
+//! to several destinations at once.  Each destination is an SME address or
+//! the name of a distribution list held by the SMSC, and the response lists
+//! the SME addresses the message could not be submitted to.
+//!
+//! Like query_sm, these are missing from smpp_pdu, so they are standalone
+//! types that read and write a whole frame, header included.
+
+use smpp_pdu::pdu::formats::{COctetString, WriteStream};
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody, SubmitSmPdu};
+use std::fmt::{Debug, Formatter};
+use std::io::{self, Cursor, Read};
+
+use crate::command_id::CommandId;
+use crate::frame_body::{
+    c_octet_string, c_octet_string_at, command_id, read_bytes, repeated_at,
+    time, time_at, u32_at, write_frame, Header, HEADER_LENGTH,
+};
+use crate::pdu_clone::PduClone;
+
+pub const SUBMIT_MULTI: u32 = CommandId::SubmitMulti as u32;
+pub const SUBMIT_MULTI_RESP: u32 = CommandId::SubmitMultiResp as u32;
+
+/// The most destinations one submit_multi may have, per section 5.2.5 of
+/// the spec
+pub const MAX_NUMBER_OF_DESTS: usize = 254;
+
+const DEST_FLAG_SME_ADDRESS: u8 = 1;
+const DEST_FLAG_DISTRIBUTION_LIST: u8 = 2;
+
+const MAX_LENGTH_SERVICE_TYPE: usize = 6;
+const MAX_LENGTH_ADDR: usize = 21;
+const MAX_LENGTH_DL_NAME: usize = 21;
+const MAX_LENGTH_MESSAGE_ID: usize = 65;
+const MAX_LENGTH_SHORT_MESSAGE: usize = 254;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SmeAddress {
+    pub dest_addr_ton: u8,
+    pub dest_addr_npi: u8,
+    pub destination_addr: COctetString,
+}
+
+/// One entry of submit_multi's dest_address list
+#[derive(Clone, Debug, PartialEq)]
+pub enum DestAddress {
+    Sme(SmeAddress),
+    /// The name of a list of addresses the SMSC holds for this ESME
+    DistributionList(COctetString),
+}
+
+/// One entry of submit_multi_resp's unsuccess_sme list
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsuccessSme {
+    pub address: SmeAddress,
+    /// Why the message could not be submitted to address, as a
+    /// command_status
+    pub error_status_code: u32,
+}
+
+#[derive(PartialEq)]
+pub struct SubmitMultiPdu {
+    pub sequence_number: u32,
+    pub service_type: COctetString,
+    pub source_addr_ton: u8,
+    pub source_addr_npi: u8,
+    pub source_addr: COctetString,
+    /// Between 1 and MAX_NUMBER_OF_DESTS of them
+    pub dest_addresses: Vec<DestAddress>,
+    pub esm_class: u8,
+    pub protocol_id: u8,
+    pub priority_flag: u8,
+    pub schedule_delivery_time: COctetString,
+    pub validity_period: COctetString,
+    pub registered_delivery: u8,
+    pub replace_if_present_flag: u8,
+    pub data_coding: u8,
+    pub sm_default_msg_id: u8,
+    /// Written with sm_length in front, so at most 254 bytes
+    pub short_message: Vec<u8>,
+    pub tlvs: Tlvs,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SubmitMultiRespPdu {
+    pub command_status: u32,
+    pub sequence_number: u32,
+    pub message_id: COctetString,
+    /// The SME addresses the message was not submitted to
+    pub unsuccess_smes: Vec<UnsuccessSme>,
+}
+
+impl SmeAddress {
+    /// destination_addr with TON and NPI 0.  Set those directly if they
+    /// matter.
+    pub fn new(destination_addr: &str) -> Result<Self, PduParseError> {
+        Ok(Self {
+            dest_addr_ton: 0,
+            dest_addr_npi: 0,
+            destination_addr: c_octet_string(
+                "destination_addr",
+                destination_addr,
+                MAX_LENGTH_ADDR,
+            )?,
+        })
+    }
+
+    fn read(
+        body: &mut Cursor<&[u8]>,
+        header: &Header,
+    ) -> Result<Self, PduParseError> {
+        let [dest_addr_ton, dest_addr_npi] =
+            read_bytes(body, header, "dest_addr_npi")?;
+        let destination_addr = c_octet_string_at(
+            body,
+            header,
+            "destination_addr",
+            MAX_LENGTH_ADDR,
+        )?;
+        Ok(Self {
+            dest_addr_ton,
+            dest_addr_npi,
+            destination_addr,
+        })
+    }
+
+    async fn write(&self, body: &mut Vec<u8>) -> io::Result<()> {
+        body.extend([self.dest_addr_ton, self.dest_addr_npi]);
+        self.destination_addr.write(body).await
+    }
+}
+
+impl DestAddress {
+    /// An SME address with TON and NPI 0
+    pub fn sme(destination_addr: &str) -> Result<Self, PduParseError> {
+        SmeAddress::new(destination_addr).map(Self::Sme)
+    }
+
+    pub fn distribution_list(dl_name: &str) -> Result<Self, PduParseError> {
+        c_octet_string("dl_name", dl_name, MAX_LENGTH_DL_NAME)
+            .map(Self::DistributionList)
+    }
+
+    fn read(
+        body: &mut Cursor<&[u8]>,
+        header: &Header,
+    ) -> Result<Self, PduParseError> {
+        let [dest_flag] = read_bytes(body, header, "dest_flag")?;
+        match dest_flag {
+            DEST_FLAG_SME_ADDRESS => {
+                SmeAddress::read(body, header).map(Self::Sme)
+            }
+            DEST_FLAG_DISTRIBUTION_LIST => {
+                c_octet_string_at(body, header, "dl_name", MAX_LENGTH_DL_NAME)
+                    .map(Self::DistributionList)
+            }
+            _ => Err(header.error(
+                PduParseError::new(PduParseErrorBody::IncorrectLength(
+                    dest_flag as u32,
+                    String::from(
+                        "dest_flag must be 1 (SME address) or 2 \
+                        (distribution list)",
+                    ),
+                ))
+                .into_with_field_name("dest_flag"),
+            )),
+        }
+    }
+
+    async fn write(&self, body: &mut Vec<u8>) -> io::Result<()> {
+        match self {
+            Self::Sme(address) => {
+                body.push(DEST_FLAG_SME_ADDRESS);
+                address.write(body).await
+            }
+            Self::DistributionList(dl_name) => {
+                body.push(DEST_FLAG_DISTRIBUTION_LIST);
+                dl_name.write(body).await
+            }
+        }
+    }
+}
+
+impl UnsuccessSme {
+    fn read(
+        body: &mut Cursor<&[u8]>,
+        header: &Header,
+    ) -> Result<Self, PduParseError> {
+        let address = SmeAddress::read(body, header)?;
+        let error_status_code = u32_at(body, header, "error_status_code")?;
+        Ok(Self {
+            address,
+            error_status_code,
+        })
+    }
+
+    async fn write(&self, body: &mut Vec<u8>) -> io::Result<()> {
+        self.address.write(body).await?;
+        body.extend(self.error_status_code.to_be_bytes());
+        Ok(())
+    }
+}
+
+impl SubmitMultiPdu {
+    /// short_message from source_addr (TON and NPI 0) to each of
+    /// dest_addresses, with every field not given here zero or empty.  Set
+    /// those directly if they matter.
+    pub fn new(
+        sequence_number: u32,
+        source_addr: &str,
+        dest_addresses: Vec<DestAddress>,
+        data_coding: u8,
+        short_message: &[u8],
+    ) -> Result<Self, PduParseError> {
+        let pdu = Self {
+            sequence_number,
+            service_type: COctetString::new(),
+            source_addr_ton: 0,
+            source_addr_npi: 0,
+            source_addr: c_octet_string(
+                "source_addr",
+                source_addr,
+                MAX_LENGTH_ADDR,
+            )?,
+            dest_addresses,
+            esm_class: 0,
+            protocol_id: 0,
+            priority_flag: 0,
+            schedule_delivery_time: COctetString::new(),
+            validity_period: COctetString::new(),
+            registered_delivery: 0,
+            replace_if_present_flag: 0,
+            data_coding,
+            sm_default_msg_id: 0,
+            short_message: Vec::from(short_message),
+            tlvs: Tlvs::new(),
+        };
+        pdu.validate()?;
+        Ok(pdu)
+    }
+
+    /// A copy with a new schedule_delivery_time and validity_period, each
+    /// empty or in the 16-character SMPP time format.
+    pub fn with_schedule(
+        self,
+        schedule_delivery_time: &str,
+        validity_period: &str,
+    ) -> Result<Self, PduParseError> {
+        Ok(Self {
+            schedule_delivery_time: time(
+                "schedule_delivery_time",
+                schedule_delivery_time,
+            )?,
+            validity_period: time("validity_period", validity_period)?,
+            ..self
+        })
+    }
+
+    /// Does this complete frame (as accepted by Pdu::check) hold a
+    /// submit_multi?
+    pub fn is_submit_multi(frame: &[u8]) -> bool {
+        command_id(frame) == Some(SUBMIT_MULTI)
+    }
+
+    /// Are there between 1 and MAX_NUMBER_OF_DESTS destinations, and a
+    /// short_message that fits?
+    pub fn validate(&self) -> Result<(), PduParseError> {
+        let number_of_dests = self.dest_addresses.len();
+        if number_of_dests == 0 || number_of_dests > MAX_NUMBER_OF_DESTS {
+            return Err(PduParseError::new(
+                PduParseErrorBody::IncorrectLength(
+                    number_of_dests as u32,
+                    format!(
+                        "number_of_dests must be between 1 and {}",
+                        MAX_NUMBER_OF_DESTS
+                    ),
+                ),
+            )
+            .into_with_field_name("number_of_dests"));
+        }
+        if self.short_message.len() > MAX_LENGTH_SHORT_MESSAGE {
+            return Err(PduParseError::new(PduParseErrorBody::LengthTooLong(
+                self.short_message.len() as u32,
+            ))
+            .into_with_field_name("short_message"));
+        }
+        Ok(())
+    }
+
+    /// The submit_sm that asks for the same thing for just address.
+    pub fn to_submit_sm(
+        &self,
+        address: &SmeAddress,
+    ) -> Result<SubmitSmPdu, PduParseError> {
+        SubmitSmPdu::new(
+            self.service_type.value.as_str(),
+            self.source_addr_ton,
+            self.source_addr_npi,
+            self.source_addr.value.as_str(),
+            address.dest_addr_ton,
+            address.dest_addr_npi,
+            address.destination_addr.value.as_str(),
+            self.esm_class,
+            self.protocol_id,
+            self.priority_flag,
+            self.schedule_delivery_time.value.as_str(),
+            self.validity_period.value.as_str(),
+            self.registered_delivery,
+            self.replace_if_present_flag,
+            self.data_coding,
+            self.sm_default_msg_id,
+            &self.short_message,
+            self.tlvs.pdu_clone(),
+        )
+    }
+
+    pub fn parse(frame: &[u8]) -> Result<Self, PduParseError> {
+        let header = Header::parse(frame, SUBMIT_MULTI)?;
+        if header.command_status != 0 {
+            return Err(header.error(PduParseError::new(
+                PduParseErrorBody::StatusIsNotZero,
+            )));
+        }
+        let mut body = Cursor::new(&frame[HEADER_LENGTH..]);
+        let service_type = c_octet_string_at(
+            &mut body,
+            &header,
+            "service_type",
+            MAX_LENGTH_SERVICE_TYPE,
+        )?;
+        let [source_addr_ton, source_addr_npi] =
+            read_bytes(&mut body, &header, "source_addr_npi")?;
+        let source_addr = c_octet_string_at(
+            &mut body,
+            &header,
+            "source_addr",
+            MAX_LENGTH_ADDR,
+        )?;
+        let [number_of_dests] =
+            read_bytes(&mut body, &header, "number_of_dests")?;
+        let dest_addresses = repeated_at(
+            &mut body,
+            &header,
+            number_of_dests,
+            DestAddress::read,
+        )?;
+        let [esm_class, protocol_id, priority_flag] =
+            read_bytes(&mut body, &header, "priority_flag")?;
+        let schedule_delivery_time =
+            time_at(&mut body, &header, "schedule_delivery_time")?;
+        let validity_period = time_at(&mut body, &header, "validity_period")?;
+        let [registered_delivery, replace_if_present_flag, data_coding, sm_default_msg_id, sm_length] =
+            read_bytes(&mut body, &header, "sm_length")?;
+        let mut short_message = vec![0; sm_length as usize];
+        body.read_exact(&mut short_message).map_err(|e| {
+            header.error(
+                PduParseError::from(e).into_with_field_name("short_message"),
+            )
+        })?;
+        let tlvs = Tlvs::read(&mut body).map_err(|e| {
+            header.error(PduParseError::from(e).into_with_field_name("tlvs"))
+        })?;
+        let pdu = Self {
+            sequence_number: header.sequence_number,
+            service_type,
+            source_addr_ton,
+            source_addr_npi,
+            source_addr,
+            dest_addresses,
+            esm_class,
+            protocol_id,
+            priority_flag,
+            schedule_delivery_time,
+            validity_period,
+            registered_delivery,
+            replace_if_present_flag,
+            data_coding,
+            sm_default_msg_id,
+            short_message,
+            tlvs,
+        };
+        pdu.validate().map_err(|e| header.error(e))?;
+        Ok(pdu)
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        let mut body: Vec<u8> = Vec::new();
+        self.service_type.write(&mut body).await?;
+        body.extend([self.source_addr_ton, self.source_addr_npi]);
+        self.source_addr.write(&mut body).await?;
+        body.push(self.dest_addresses.len() as u8);
+        for dest_address in &self.dest_addresses {
+            dest_address.write(&mut body).await?;
+        }
+        body.extend([self.esm_class, self.protocol_id, self.priority_flag]);
+        self.schedule_delivery_time.write(&mut body).await?;
+        self.validity_period.write(&mut body).await?;
+        body.extend([
+            self.registered_delivery,
+            self.replace_if_present_flag,
+            self.data_coding,
+            self.sm_default_msg_id,
+            self.short_message.len() as u8,
+        ]);
+        body.extend(&self.short_message);
+        self.tlvs.write(&mut body).await?;
+        write_frame(stream, SUBMIT_MULTI, 0, self.sequence_number, &body).await
+    }
+}
+
+/// short_message and the TLVs are left out when building with the
+/// redact-message-content feature, like in crate::redact.
+impl Debug for SubmitMultiPdu {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        let redacted = cfg!(feature = "redact-message-content");
+        let short_message: &dyn Debug = if redacted {
+            &format_args!("<redacted>")
+        } else {
+            &self.short_message
+        };
+        let tlvs: &dyn Debug = if redacted {
+            &format_args!("<redacted>")
+        } else {
+            &self.tlvs
+        };
+        formatter
+            .debug_struct("SubmitMultiPdu")
+            .field("sequence_number", &self.sequence_number)
+            .field("service_type", &self.service_type)
+            .field("source_addr_ton", &self.source_addr_ton)
+            .field("source_addr_npi", &self.source_addr_npi)
+            .field("source_addr", &self.source_addr)
+            .field("dest_addresses", &self.dest_addresses)
+            .field("esm_class", &self.esm_class)
+            .field("protocol_id", &self.protocol_id)
+            .field("priority_flag", &self.priority_flag)
+            .field("schedule_delivery_time", &self.schedule_delivery_time)
+            .field("validity_period", &self.validity_period)
+            .field("registered_delivery", &self.registered_delivery)
+            .field("replace_if_present_flag", &self.replace_if_present_flag)
+            .field("data_coding", &self.data_coding)
+            .field("sm_default_msg_id", &self.sm_default_msg_id)
+            .field("short_message", short_message)
+            .field("tlvs", tlvs)
+            .finish()
+    }
+}
+
+impl SubmitMultiRespPdu {
+    pub fn new(
+        sequence_number: u32,
+        message_id: &str,
+        unsuccess_smes: Vec<UnsuccessSme>,
+    ) -> Result<Self, PduParseError> {
+        if unsuccess_smes.len() > MAX_NUMBER_OF_DESTS {
+            return Err(PduParseError::new(PduParseErrorBody::LengthTooLong(
+                unsuccess_smes.len() as u32,
+            ))
+            .into_with_field_name("no_unsuccess"));
+        }
+        Ok(Self {
+            command_status: 0,
+            sequence_number,
+            message_id: c_octet_string(
+                "message_id",
+                message_id,
+                MAX_LENGTH_MESSAGE_ID,
+            )?,
+            unsuccess_smes,
+        })
+    }
+
+    /// A rejection of the whole submit_multi with sequence_number.
+    pub fn new_error(command_status: u32, sequence_number: u32) -> Self {
+        Self {
+            command_status,
+            sequence_number,
+            message_id: COctetString::new(),
+            unsuccess_smes: Vec::new(),
+        }
+    }
+
+    pub fn is_submit_multi_resp(frame: &[u8]) -> bool {
+        command_id(frame) == Some(SUBMIT_MULTI_RESP)
+    }
+
+    /// Parse one complete frame.  Like data_sm_resp, a rejection may leave
+    /// the body out entirely, which is read as no message_id and no
+    /// unsuccess_smes.
+    pub fn parse(frame: &[u8]) -> Result<Self, PduParseError> {
+        let header = Header::parse(frame, SUBMIT_MULTI_RESP)?;
+        if frame.len() == HEADER_LENGTH {
+            return Ok(Self::new_error(
+                header.command_status,
+                header.sequence_number,
+            ));
+        }
+        let mut body = Cursor::new(&frame[HEADER_LENGTH..]);
+        let message_id = c_octet_string_at(
+            &mut body,
+            &header,
+            "message_id",
+            MAX_LENGTH_MESSAGE_ID,
+        )?;
+        let [no_unsuccess] = read_bytes(&mut body, &header, "no_unsuccess")?;
+        let unsuccess_smes =
+            repeated_at(&mut body, &header, no_unsuccess, UnsuccessSme::read)?;
+        Ok(Self {
+            command_status: header.command_status,
+            sequence_number: header.sequence_number,
+            message_id,
+            unsuccess_smes,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        let mut body: Vec<u8> = Vec::new();
+        self.message_id.write(&mut body).await?;
+        body.push(self.unsuccess_smes.len() as u8);
+        for unsuccess_sme in &self.unsuccess_smes {
+            unsuccess_sme.write(&mut body).await?;
+        }
+        write_frame(
+            stream,
+            SUBMIT_MULTI_RESP,
+            self.command_status,
+            self.sequence_number,
+            &body,
+        )
+        .await
+    }
+}
@@ -0,0 +1,250 @@
+//! Building a submit_sm by naming the fields to set, instead of passing all
+//! of SubmitSmPdu::new()'s arguments in order.
+
+use smpp_pdu::pdu::tlvs::{Tlv, Tlvs};
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody, SubmitSmPdu};
+
+use crate::canned_messages::check_sm_default_msg_id;
+use crate::frame_body::{c_octet_string, time};
+use crate::msisdn::{is_international, normalize_msisdn};
+use crate::protocol_id::ProtocolId;
+use crate::sender::{Sender, NPI_ISDN, TON_UNKNOWN};
+use crate::text::DataCodingMap;
+
+const MAX_LENGTH_SERVICE_TYPE: usize = 6;
+const MAX_LENGTH_ADDR: usize = 21;
+
+/// Every field starts empty or 0, so only the destination and message
+/// usually need setting.  Nothing is checked until build().
+#[derive(Clone, Debug, Default)]
+pub struct SubmitSmBuilder {
+    service_type: String,
+    source_addr_ton: u8,
+    source_addr_npi: u8,
+    source_addr: String,
+    dest_addr_ton: u8,
+    dest_addr_npi: u8,
+    destination_addr: String,
+    esm_class: u8,
+    protocol_id: ProtocolId,
+    priority_flag: u8,
+    schedule_delivery_time: String,
+    validity_period: String,
+    registered_delivery: u8,
+    replace_if_present_flag: u8,
+    data_coding: u8,
+    sm_default_msg_id: u8,
+    short_message: Vec<u8>,
+    message_text: Option<String>,
+    data_coding_map: DataCodingMap,
+    tlvs: Vec<Tlv>,
+}
+
+impl SubmitSmBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn service_type(mut self, service_type: &str) -> Self {
+        self.service_type = String::from(service_type);
+        self
+    }
+
+    pub fn source_addr_ton(mut self, source_addr_ton: u8) -> Self {
+        self.source_addr_ton = source_addr_ton;
+        self
+    }
+
+    pub fn source_addr_npi(mut self, source_addr_npi: u8) -> Self {
+        self.source_addr_npi = source_addr_npi;
+        self
+    }
+
+    pub fn source_addr(mut self, source_addr: &str) -> Self {
+        self.source_addr = String::from(source_addr);
+        self
+    }
+
+    /// source_addr, with the TON and NPI that go with it
+    pub fn sender(self, sender: &Sender) -> Self {
+        self.source_addr_ton(sender.ton())
+            .source_addr_npi(sender.npi())
+            .source_addr(&sender.addr())
+    }
+
+    pub fn dest_addr_ton(mut self, dest_addr_ton: u8) -> Self {
+        self.dest_addr_ton = dest_addr_ton;
+        self
+    }
+
+    pub fn dest_addr_npi(mut self, dest_addr_npi: u8) -> Self {
+        self.dest_addr_npi = dest_addr_npi;
+        self
+    }
+
+    pub fn destination_addr(mut self, destination_addr: &str) -> Self {
+        self.destination_addr = String::from(destination_addr);
+        self
+    }
+
+    /// destination_addr, with the TON and NPI that go with it.  Numbers
+    /// without a '+' or "00" prefix are sent as written with an unknown
+    /// TON, and left for the SMSC to interpret.
+    pub fn destination(self, destination_addr: &str) -> Self {
+        let destination = Sender::destination(destination_addr);
+        if let Sender::Msisdn(_) = destination {
+            if !is_international(destination_addr) {
+                return self
+                    .dest_addr_ton(TON_UNKNOWN)
+                    .dest_addr_npi(NPI_ISDN)
+                    .destination_addr(&normalize_msisdn(
+                        destination_addr,
+                        None,
+                    ));
+            }
+        }
+        self.dest_addr_ton(destination.ton())
+            .dest_addr_npi(destination.npi())
+            .destination_addr(&destination.addr())
+    }
+
+    pub fn esm_class(mut self, esm_class: u8) -> Self {
+        self.esm_class = esm_class;
+        self
+    }
+
+    /// Reserved values are rejected by build()
+    pub fn protocol_id(mut self, protocol_id: ProtocolId) -> Self {
+        self.protocol_id = protocol_id;
+        self
+    }
+
+    pub fn priority_flag(mut self, priority_flag: u8) -> Self {
+        self.priority_flag = priority_flag;
+        self
+    }
+
+    /// In SMPP time format, e.g. "000000001000000R" for ten minutes from now
+    pub fn schedule_delivery_time(
+        mut self,
+        schedule_delivery_time: &str,
+    ) -> Self {
+        self.schedule_delivery_time = String::from(schedule_delivery_time);
+        self
+    }
+
+    /// In SMPP time format, e.g. "000001000000000R" for one day from now
+    pub fn validity_period(mut self, validity_period: &str) -> Self {
+        self.validity_period = String::from(validity_period);
+        self
+    }
+
+    pub fn registered_delivery(mut self, registered_delivery: u8) -> Self {
+        self.registered_delivery = registered_delivery;
+        self
+    }
+
+    pub fn replace_if_present_flag(
+        mut self,
+        replace_if_present_flag: u8,
+    ) -> Self {
+        self.replace_if_present_flag = replace_if_present_flag;
+        self
+    }
+
+    pub fn data_coding(mut self, data_coding: u8) -> Self {
+        self.data_coding = data_coding;
+        self
+    }
+
+    pub fn sm_default_msg_id(mut self, sm_default_msg_id: u8) -> Self {
+        self.sm_default_msg_id = sm_default_msg_id;
+        self
+    }
+
+    pub fn short_message(mut self, short_message: &[u8]) -> Self {
+        self.short_message = Vec::from(short_message);
+        self
+    }
+
+    /// Send text in the first alphabet of the data_coding_map that can
+    /// write it, setting short_message and data_coding at build().
+    pub fn message_text(mut self, text: &str) -> Self {
+        self.message_text = Some(String::from(text));
+        self
+    }
+
+    /// Which alphabets message_text() may use, and in what order, e.g. a
+    /// connection's data_coding_map().  The default tries GSM 03.38, then
+    /// Latin-1, then UCS-2.
+    pub fn data_coding_map(mut self, data_coding_map: &DataCodingMap) -> Self {
+        self.data_coding_map = data_coding_map.clone();
+        self
+    }
+
+    /// Add tlv after any already added.
+    pub fn tlv(mut self, tlv: Tlv) -> Self {
+        self.tlvs.push(tlv);
+        self
+    }
+
+    /// The submit_sm, or a PduParseError naming the first invalid field.
+    pub fn build(&self) -> Result<SubmitSmPdu, PduParseError> {
+        c_octet_string(
+            "service_type",
+            &self.service_type,
+            MAX_LENGTH_SERVICE_TYPE,
+        )?;
+        c_octet_string("source_addr", &self.source_addr, MAX_LENGTH_ADDR)?;
+        c_octet_string(
+            "destination_addr",
+            &self.destination_addr,
+            MAX_LENGTH_ADDR,
+        )?;
+        time("schedule_delivery_time", &self.schedule_delivery_time)?;
+        time("validity_period", &self.validity_period)?;
+        self.protocol_id.validate().map_err(|e| {
+            PduParseError::new(PduParseErrorBody::IncorrectLength(
+                u32::from(e.0),
+                e.to_string(),
+            ))
+            .into_with_field_name("protocol_id")
+        })?;
+        check_sm_default_msg_id(self.sm_default_msg_id)?;
+        let (data_coding, short_message) = match &self.message_text {
+            Some(text) => {
+                self.data_coding_map.select(text).ok_or_else(|| {
+                    PduParseError::new(PduParseErrorBody::IncorrectLength(
+                        0,
+                        String::from(
+                            "message_text cannot be written in any preferred \
+                        data_coding",
+                        ),
+                    ))
+                    .into_with_field_name("short_message")
+                })?
+            }
+            None => (self.data_coding, self.short_message.clone()),
+        };
+        SubmitSmPdu::new(
+            &self.service_type,
+            self.source_addr_ton,
+            self.source_addr_npi,
+            &self.source_addr,
+            self.dest_addr_ton,
+            self.dest_addr_npi,
+            &self.destination_addr,
+            self.esm_class,
+            self.protocol_id.into(),
+            self.priority_flag,
+            &self.schedule_delivery_time,
+            &self.validity_period,
+            self.registered_delivery,
+            self.replace_if_present_flag,
+            data_coding,
+            self.sm_default_msg_id,
+            &short_message,
+            Tlvs::from(&self.tlvs),
+        )
+    }
+}
@@ -0,0 +1,80 @@
+//! Values a client fills in for whichever submit_sm fields the caller left
+//! unset, so that most submit_sm need only a destination and a message.
+
+use smpp_pdu::pdu::formats::Integer1;
+use smpp_pdu::pdu::{PduParseError, SubmitSmPdu};
+
+use crate::frame_body::{c_octet_string, time};
+use crate::sender::Sender;
+
+const MAX_LENGTH_SERVICE_TYPE: usize = 6;
+const MAX_LENGTH_SOURCE_ADDR: usize = 21;
+
+/// Unset means empty, or 0 for registered_delivery.  The source address,
+/// TON and NPI go together: they are filled in only if source_addr is
+/// empty, so that a caller's own source_addr keeps the TON and NPI they
+/// gave with it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SubmitSmDefaults {
+    pub service_type: Option<String>,
+    pub source_addr_ton: Option<u8>,
+    pub source_addr_npi: Option<u8>,
+    pub source_addr: Option<String>,
+    pub registered_delivery: Option<u8>,
+    /// In SMPP time format, e.g. "000001000000000R" for one day from now
+    pub validity_period: Option<String>,
+}
+
+impl SubmitSmDefaults {
+    /// Defaults with the address, TON and NPI of sender.
+    pub fn with_sender(mut self, sender: &Sender) -> Self {
+        self.source_addr_ton = Some(sender.ton());
+        self.source_addr_npi = Some(sender.npi());
+        self.source_addr = Some(sender.addr());
+        self
+    }
+
+    /// Fill in each field of submit_sm that is unset and has a default.
+    /// Fails if a default is not a valid value for its field.
+    pub fn apply(
+        &self,
+        submit_sm: &mut SubmitSmPdu,
+    ) -> Result<(), PduParseError> {
+        let sm = &mut submit_sm.0;
+        if let Some(service_type) = &self.service_type {
+            if sm.service_type.value.is_empty() {
+                sm.service_type = c_octet_string(
+                    "service_type",
+                    service_type,
+                    MAX_LENGTH_SERVICE_TYPE,
+                )?;
+            }
+        }
+        if let Some(source_addr) = &self.source_addr {
+            if sm.source_addr.value.is_empty() {
+                sm.source_addr = c_octet_string(
+                    "source_addr",
+                    source_addr,
+                    MAX_LENGTH_SOURCE_ADDR,
+                )?;
+                if let Some(ton) = self.source_addr_ton {
+                    sm.source_addr_ton = Integer1::new(ton);
+                }
+                if let Some(npi) = self.source_addr_npi {
+                    sm.source_addr_npi = Integer1::new(npi);
+                }
+            }
+        }
+        if let Some(registered_delivery) = self.registered_delivery {
+            if sm.registered_delivery.value == 0 {
+                sm.registered_delivery = Integer1::new(registered_delivery);
+            }
+        }
+        if let Some(validity_period) = &self.validity_period {
+            if sm.validity_period.value.is_empty() {
+                sm.validity_period = time("validity_period", validity_period)?;
+            }
+        }
+        Ok(())
+    }
+}
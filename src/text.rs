@@ -0,0 +1,397 @@
+//! Turning text into short_message bytes and back.  Which character set a
+//! data_coding value means is supposed to be fixed by the SMPP
+//! specification, but carriers disagree (particularly about 0, the "SMSC
+//! default alphabet"), so each connection has its own DataCodingMap.
+//!
+//! Alphabets beyond the built-in Charsets, e.g. Cyrillic for data_coding 6
+//! or a carrier's proprietary table, can be added by implementing Codec and
+//! registering it in a DataCodingMap.
+
+use smpp_pdu::pdu::data::sm_data::SmData;
+use smpp_pdu::pdu::{DeliverSmPdu, SubmitSmPdu};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::message_payload::MessageBytes;
+
+/// The GSM 03.38 default alphabet, indexed by septet.  0x1B is the escape
+/// to GSM_EXTENSION, and never decodes to itself.
+const GSM_BASIC: &str = concat!(
+    "@£$¥èéùìòÇ\nØø\rÅå",
+    "Δ_ΦΓΛΩΠΨΣΘΞ\u{1b}ÆæßÉ",
+    " !\"#¤%&'()*+,-./",
+    "0123456789:;<=>?",
+    "¡ABCDEFGHIJKLMNO",
+    "PQRSTUVWXYZÄÖÑÜ§",
+    "¿abcdefghijklmno",
+    "pqrstuvwxyzäöñüà",
+);
+
+const GSM_ESCAPE: u8 = 0x1B;
+
+/// Characters written as GSM_ESCAPE followed by a second septet
+const GSM_EXTENSION: &[(u8, char)] = &[
+    (0x0A, '\u{c}'),
+    (0x14, '^'),
+    (0x28, '{'),
+    (0x29, '}'),
+    (0x2F, '\\'),
+    (0x3C, '['),
+    (0x3D, '~'),
+    (0x3E, ']'),
+    (0x40, '|'),
+    (0x65, '€'),
+];
+
+/// Whether data_coding means 8-bit binary data rather than text: 0x02 or
+/// 0x04, or a GSM message class (0xF0 to 0xFF) with bit 2 set.
+pub fn is_binary(data_coding: u8) -> bool {
+    matches!(data_coding, 0x02 | 0x04 | 0xF4..=0xF7 | 0xFC..=0xFF)
+}
+
+/// A way of writing text as short_message bytes.
+pub trait Codec: Send + Sync {
+    /// A short name, e.g. "gsm7"
+    fn name(&self) -> &str;
+
+    /// text in this alphabet, or None if it contains characters that this
+    /// alphabet cannot represent.
+    fn encode(&self, text: &str) -> Option<Vec<u8>>;
+
+    /// The text in bytes, replacing anything that is not valid in this
+    /// alphabet.
+    fn decode(&self, bytes: &[u8]) -> String;
+
+    /// Whether each byte encode() writes is a GSM septet, which the SMSC
+    /// packs so that 160 fit in a 140-octet message.
+    fn septets(&self) -> bool {
+        false
+    }
+}
+
+/// The alphabets the SMPP specification names, which we support built in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Charset {
+    /// The GSM 03.38 default alphabet, one septet per octet (unpacked), as
+    /// SMPP carries it
+    Gsm7,
+    /// IA5, i.e. ASCII
+    Ascii,
+    /// ISO-8859-1
+    Latin1,
+    /// UCS-2, big-endian.  Characters outside the BMP are written as UTF-16
+    /// surrogate pairs, which most handsets display correctly.
+    Ucs2,
+}
+
+impl Codec for Charset {
+    fn name(&self) -> &str {
+        match self {
+            Self::Gsm7 => "gsm7",
+            Self::Ascii => "ascii",
+            Self::Latin1 => "latin1",
+            Self::Ucs2 => "ucs2",
+        }
+    }
+
+    fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        match self {
+            Self::Gsm7 => text.chars().try_fold(Vec::new(), |mut ret, c| {
+                match gsm_septet(c)? {
+                    (None, septet) => ret.push(septet),
+                    (Some(escape), septet) => ret.extend(&[escape, septet]),
+                }
+                Some(ret)
+            }),
+            Self::Ascii => text
+                .chars()
+                .map(|c| if c.is_ascii() { Some(c as u8) } else { None })
+                .collect(),
+            Self::Latin1 => text
+                .chars()
+                .map(|c| u8::try_from(u32::from(c)).ok())
+                .collect(),
+            Self::Ucs2 => Some(
+                text.encode_utf16().flat_map(|u| u.to_be_bytes()).collect(),
+            ),
+        }
+    }
+
+    /// Invalid characters become '?' (or U+FFFD for UCS-2).
+    fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            Self::Gsm7 => {
+                let mut ret = String::new();
+                let mut iter = bytes.iter();
+                while let Some(&b) = iter.next() {
+                    if b == GSM_ESCAPE {
+                        let next = iter.next().copied();
+                        ret.push(
+                            GSM_EXTENSION
+                                .iter()
+                                .find(|(septet, _)| Some(*septet) == next)
+                                .map(|(_, c)| *c)
+                                // Escape then unknown means a space
+                                .unwrap_or(' '),
+                        );
+                    } else {
+                        ret.push(
+                            GSM_BASIC.chars().nth(b.into()).unwrap_or('?'),
+                        );
+                    }
+                }
+                ret
+            }
+            Self::Ascii => bytes
+                .iter()
+                .map(|&b| if b.is_ascii() { b as char } else { '?' })
+                .collect(),
+            Self::Latin1 => bytes.iter().map(|&b| char::from(b)).collect(),
+            Self::Ucs2 => String::from_utf16_lossy(
+                &bytes
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                    .collect::<Vec<u16>>(),
+            ),
+        }
+    }
+
+    fn septets(&self) -> bool {
+        *self == Self::Gsm7
+    }
+}
+
+/// The septet for c, preceded by an escape if c is in the extension table.
+fn gsm_septet(c: char) -> Option<(Option<u8>, u8)> {
+    if c != '\u{1b}' {
+        if let Some(septet) = GSM_BASIC.chars().position(|g| g == c) {
+            return Some((None, septet as u8));
+        }
+    }
+    GSM_EXTENSION
+        .iter()
+        .find(|(_, g)| *g == c)
+        .map(|(septet, _)| (Some(GSM_ESCAPE), *septet))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseCharsetError(String);
+
+impl Display for ParseCharsetError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "Unknown charset '{}': expected gsm7, ascii, latin1 or ucs2",
+            self.0
+        )
+    }
+}
+
+impl error::Error for ParseCharsetError {}
+
+impl FromStr for Charset {
+    type Err = ParseCharsetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "gsm7" => Ok(Self::Gsm7),
+            "ascii" | "ia5" => Ok(Self::Ascii),
+            "latin1" | "iso-8859-1" => Ok(Self::Latin1),
+            "ucs2" => Ok(Self::Ucs2),
+            _ => Err(ParseCharsetError(String::from(s))),
+        }
+    }
+}
+
+/// A carrier's meaning for one data_coding value, written as
+/// DATA_CODING=CHARSET, e.g. "0=latin1".
+#[derive(Clone, Debug, PartialEq)]
+pub struct DataCodingRemap {
+    pub data_coding: u8,
+    pub charset: Charset,
+}
+
+#[derive(Debug)]
+pub struct ParseDataCodingRemapError(String);
+
+impl Display for ParseDataCodingRemapError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "Invalid data_coding mapping '{}': expected DATA_CODING=CHARSET, \
+            e.g. 0=latin1",
+            self.0
+        )
+    }
+}
+
+impl error::Error for ParseDataCodingRemapError {}
+
+impl FromStr for DataCodingRemap {
+    type Err = ParseDataCodingRemapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseDataCodingRemapError(String::from(s));
+        let (data_coding, charset) = s.split_once('=').ok_or_else(err)?;
+        Ok(Self {
+            data_coding: data_coding.parse().map_err(|_| err())?,
+            charset: charset.parse().map_err(|_| err())?,
+        })
+    }
+}
+
+/// Which alphabet each data_coding value means, and which we prefer to
+/// send text in.
+#[derive(Clone)]
+pub struct DataCodingMap {
+    codecs: BTreeMap<u8, Arc<dyn Codec>>,
+    preference: Vec<u8>,
+}
+
+impl Default for DataCodingMap {
+    /// The meanings in the SMPP specification, taking the SMSC default
+    /// alphabet to be GSM 03.38
+    fn default() -> Self {
+        Self::with_remaps(&[])
+    }
+}
+
+impl Debug for DataCodingMap {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter
+            .debug_map()
+            .entries(self.codecs.iter().map(|(dc, codec)| (dc, codec.name())))
+            .finish()
+    }
+}
+
+impl DataCodingMap {
+    /// The default map, with each of remaps applied.  Text is sent in GSM
+    /// 03.38 if possible, then Latin-1, then UCS-2.
+    pub fn with_remaps(remaps: &[DataCodingRemap]) -> Self {
+        let mut ret = Self {
+            codecs: BTreeMap::new(),
+            preference: vec![0x00, 0x03, 0x08],
+        };
+        ret.register(0x00, Arc::new(Charset::Gsm7));
+        ret.register(0x01, Arc::new(Charset::Ascii));
+        ret.register(0x03, Arc::new(Charset::Latin1));
+        ret.register(0x08, Arc::new(Charset::Ucs2));
+        // GSM message classes 0 to 3 in the default alphabet
+        for data_coding in (0xF0..=0xF3).chain(0xF8..=0xFB) {
+            ret.register(data_coding, Arc::new(Charset::Gsm7));
+        }
+        for remap in remaps {
+            ret.register(remap.data_coding, Arc::new(remap.charset));
+        }
+        ret
+    }
+
+    /// Use codec for data_coding, replacing any codec it had.  To send text
+    /// with it, include data_coding in set_preference too.
+    pub fn register(&mut self, data_coding: u8, codec: Arc<dyn Codec>) {
+        self.codecs.insert(data_coding, codec);
+    }
+
+    /// Which data_coding values select tries, most preferred first.
+    pub fn set_preference(&mut self, data_codings: &[u8]) {
+        self.preference = Vec::from(data_codings);
+    }
+
+    pub fn codec(&self, data_coding: u8) -> Option<&dyn Codec> {
+        self.codecs.get(&data_coding).map(|codec| &**codec)
+    }
+
+    /// The most preferred data_coding whose codec can write text, and the
+    /// short_message bytes, or None if none of them can.
+    pub fn select(&self, text: &str) -> Option<(u8, Vec<u8>)> {
+        self.preference.iter().find_map(|data_coding| {
+            Some((*data_coding, self.codec(*data_coding)?.encode(text)?))
+        })
+    }
+
+    /// The text in a short_message, or None if we do not know what
+    /// data_coding means.
+    pub fn decode(&self, data_coding: u8, bytes: &[u8]) -> Option<String> {
+        Some(self.codec(data_coding)?.decode(bytes))
+    }
+}
+
+/// Why a message's text could not be read
+#[derive(Debug, PartialEq)]
+pub enum DecodeTextError {
+    /// Both short_message and a message_payload TLV were given
+    MessageInBothFields,
+    /// The data_coding means binary data, not text
+    Binary(u8),
+    /// The DataCodingMap has no codec for the data_coding
+    UnknownDataCoding(u8),
+}
+
+impl Display for DecodeTextError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Self::MessageInBothFields => formatter.write_str(
+                "PDU has both a short_message and a message_payload TLV",
+            ),
+            Self::Binary(data_coding) => {
+                write!(formatter, "data_coding {:#04X} is binary", data_coding)
+            }
+            Self::UnknownDataCoding(data_coding) => {
+                write!(formatter, "Unknown data_coding {:#04X}", data_coding)
+            }
+        }
+    }
+}
+
+impl error::Error for DecodeTextError {}
+
+pub trait MessageText {
+    /// The text of the message, from short_message or message_payload,
+    /// in the alphabet its data_coding means in map.
+    fn decode_text(
+        &self,
+        map: &DataCodingMap,
+    ) -> Result<String, DecodeTextError>;
+}
+
+impl MessageText for SmData {
+    fn decode_text(
+        &self,
+        map: &DataCodingMap,
+    ) -> Result<String, DecodeTextError> {
+        let bytes = self
+            .message_bytes()
+            .map_err(|_| DecodeTextError::MessageInBothFields)?;
+        let data_coding = self.data_coding.value;
+        map.decode(data_coding, &bytes).ok_or({
+            if is_binary(data_coding) {
+                DecodeTextError::Binary(data_coding)
+            } else {
+                DecodeTextError::UnknownDataCoding(data_coding)
+            }
+        })
+    }
+}
+
+impl MessageText for SubmitSmPdu {
+    fn decode_text(
+        &self,
+        map: &DataCodingMap,
+    ) -> Result<String, DecodeTextError> {
+        self.0.decode_text(map)
+    }
+}
+
+impl MessageText for DeliverSmPdu {
+    fn decode_text(
+        &self,
+        map: &DataCodingMap,
+    ) -> Result<String, DecodeTextError> {
+        self.0.decode_text(map)
+    }
+}
@@ -0,0 +1,368 @@
+//! Typed access to the values of some TLVs, which smpp_pdu only gives us as
+//! raw bytes.
+
+use futures::FutureExt;
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use std::convert::TryFrom;
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::io::Cursor;
+
+/// additional_status_info_text may be at most 256 bytes, including its
+/// terminating NULL.
+pub const MAX_STATUS_INFO_TEXT_LEN: usize = 255;
+
+/// The most messages number_of_messages may report.
+pub const MAX_NUMBER_OF_MESSAGES: u8 = 99;
+
+/// The shortest and longest a subaddress may be, including its type byte.
+pub const MIN_SUBADDRESS_LEN: usize = 2;
+pub const MAX_SUBADDRESS_LEN: usize = 23;
+
+#[derive(Debug, PartialEq)]
+pub struct TlvValueOutOfRange {
+    pub name: &'static str,
+    pub value: u32,
+    pub min: u32,
+    pub max: u32,
+}
+
+impl TlvValueOutOfRange {
+    fn check(
+        name: &'static str,
+        value: usize,
+        min: usize,
+        max: usize,
+    ) -> Result<(), Self> {
+        if (min..=max).contains(&value) {
+            Ok(())
+        } else {
+            Err(Self {
+                name,
+                value: value as u32,
+                min: min as u32,
+                max: max as u32,
+            })
+        }
+    }
+}
+
+impl Display for TlvValueOutOfRange {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "{} must be between {} and {}, but was {}.",
+            self.name, self.min, self.max, self.value
+        )
+    }
+}
+
+impl error::Error for TlvValueOutOfRange {}
+
+/// The value of a delivery_failure_reason TLV
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeliveryFailureReason {
+    DestinationUnavailable,
+    DestinationAddressInvalid,
+    PermanentNetworkError,
+    TemporaryNetworkError,
+    Reserved(u8),
+}
+
+impl From<u8> for DeliveryFailureReason {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::DestinationUnavailable,
+            1 => Self::DestinationAddressInvalid,
+            2 => Self::PermanentNetworkError,
+            3 => Self::TemporaryNetworkError,
+            v => Self::Reserved(v),
+        }
+    }
+}
+
+impl From<DeliveryFailureReason> for u8 {
+    fn from(reason: DeliveryFailureReason) -> u8 {
+        match reason {
+            DeliveryFailureReason::DestinationUnavailable => 0,
+            DeliveryFailureReason::DestinationAddressInvalid => 1,
+            DeliveryFailureReason::PermanentNetworkError => 2,
+            DeliveryFailureReason::TemporaryNetworkError => 3,
+            DeliveryFailureReason::Reserved(v) => v,
+        }
+    }
+}
+
+/// An additional_status_info_text TLV containing text.  Characters that are
+/// not printable ASCII become '?', and text is cut to
+/// MAX_STATUS_INFO_TEXT_LEN characters.
+pub fn additional_status_info_text(text: &str) -> Tlv {
+    let mut value: Vec<u8> = text
+        .chars()
+        .take(MAX_STATUS_INFO_TEXT_LEN)
+        .map(|c| {
+            if c.is_ascii() && !c.is_ascii_control() {
+                c as u8
+            } else {
+                b'?'
+            }
+        })
+        .collect();
+    value.push(0);
+    Tlv::new(KnownTlvTag::additional_status_info_text, &value)
+}
+
+pub fn delivery_failure_reason(reason: DeliveryFailureReason) -> Tlv {
+    Tlv::new(KnownTlvTag::delivery_failure_reason, &[reason.into()])
+}
+
+/// The first byte of a source_subaddress or dest_subaddress
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SubaddressType {
+    /// NSAP (Even) [ITU-T X.213]
+    NsapEven,
+    /// NSAP (Odd) [ITU-T X.213]
+    NsapOdd,
+    UserSpecified,
+    Reserved(u8),
+}
+
+impl From<u8> for SubaddressType {
+    fn from(value: u8) -> Self {
+        match value {
+            0x80 => Self::NsapEven,
+            0x88 => Self::NsapOdd,
+            0xA0 => Self::UserSpecified,
+            v => Self::Reserved(v),
+        }
+    }
+}
+
+impl From<SubaddressType> for u8 {
+    fn from(subaddress_type: SubaddressType) -> u8 {
+        match subaddress_type {
+            SubaddressType::NsapEven => 0x80,
+            SubaddressType::NsapOdd => 0x88,
+            SubaddressType::UserSpecified => 0xA0,
+            SubaddressType::Reserved(v) => v,
+        }
+    }
+}
+
+/// The value of a source_subaddress or dest_subaddress TLV
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subaddress {
+    pub subaddress_type: SubaddressType,
+    pub subaddress: Vec<u8>,
+}
+
+impl Subaddress {
+    fn to_tlv(
+        &self,
+        tag: KnownTlvTag,
+        name: &'static str,
+    ) -> Result<Tlv, TlvValueOutOfRange> {
+        let mut value = vec![u8::from(self.subaddress_type)];
+        value.extend(&self.subaddress);
+        TlvValueOutOfRange::check(
+            name,
+            value.len(),
+            MIN_SUBADDRESS_LEN,
+            MAX_SUBADDRESS_LEN,
+        )?;
+        Ok(Tlv::new(tag, &value))
+    }
+
+    fn from_tlv(tlv: Tlv) -> Option<Self> {
+        if !(MIN_SUBADDRESS_LEN..=MAX_SUBADDRESS_LEN).contains(&tlv.value.len())
+        {
+            return None;
+        }
+        Some(Self {
+            subaddress_type: tlv.value[0].into(),
+            subaddress: Vec::from(&tlv.value[1..]),
+        })
+    }
+}
+
+pub fn source_subaddress(
+    subaddress: &Subaddress,
+) -> Result<Tlv, TlvValueOutOfRange> {
+    subaddress
+        .to_tlv(KnownTlvTag::source_subaddress, "source_subaddress length")
+}
+
+pub fn dest_subaddress(
+    subaddress: &Subaddress,
+) -> Result<Tlv, TlvValueOutOfRange> {
+    subaddress.to_tlv(KnownTlvTag::dest_subaddress, "dest_subaddress length")
+}
+
+/// The value of a privacy_indicator TLV
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PrivacyIndicator {
+    NotRestricted = 0,
+    Restricted = 1,
+    Confidential = 2,
+    Secret = 3,
+}
+
+impl TryFrom<u8> for PrivacyIndicator {
+    type Error = TlvValueOutOfRange;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::NotRestricted),
+            1 => Ok(Self::Restricted),
+            2 => Ok(Self::Confidential),
+            3 => Ok(Self::Secret),
+            v => Err(TlvValueOutOfRange {
+                name: "privacy_indicator",
+                value: v.into(),
+                min: 0,
+                max: 3,
+            }),
+        }
+    }
+}
+
+pub fn privacy_indicator(privacy_indicator: PrivacyIndicator) -> Tlv {
+    Tlv::new(KnownTlvTag::privacy_indicator, &[privacy_indicator as u8])
+}
+
+/// A user_response_code TLV.  The meaning of the code is up to the
+/// application, e.g. which item of a menu was chosen.
+pub fn user_response_code(code: u8) -> Tlv {
+    Tlv::new(KnownTlvTag::user_response_code, &[code])
+}
+
+/// A number_of_messages TLV, e.g. for the number of voicemails waiting.
+pub fn number_of_messages(count: u8) -> Result<Tlv, TlvValueOutOfRange> {
+    TlvValueOutOfRange::check(
+        "number_of_messages",
+        count.into(),
+        0,
+        MAX_NUMBER_OF_MESSAGES.into(),
+    )?;
+    Ok(Tlv::new(KnownTlvTag::number_of_messages, &[count]))
+}
+
+/// An sms_signal TLV, the alerting signal to use when paging.
+pub fn sms_signal(signal: u16) -> Tlv {
+    Tlv::new(KnownTlvTag::sms_signal, &signal.to_be_bytes())
+}
+
+/// The value of a network_error_code TLV
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NetworkErrorCode {
+    /// 1 for ANSI-136, 2 for IS-95, 3 for GSM, etc.
+    pub network_type: u8,
+    /// The network's own code, e.g. a GSM MAP error
+    pub error_code: u16,
+}
+
+/// The network_type of a NetworkErrorCode from a GSM network
+pub const NETWORK_TYPE_GSM: u8 = 3;
+
+pub fn network_error_code(code: NetworkErrorCode) -> Tlv {
+    let [hi, lo] = code.error_code.to_be_bytes();
+    Tlv::new(
+        KnownTlvTag::network_error_code,
+        &[code.network_type, hi, lo],
+    )
+}
+
+/// Typed getters for TLVs we understand.  Each returns None if the TLV is
+/// absent or its value is the wrong length.
+pub trait TypedTlvs {
+    /// Every TLV, in the order they will be written.
+    fn to_vec(&self) -> Vec<Tlv>;
+    fn additional_status_info_text(&self) -> Option<String>;
+    fn delivery_failure_reason(&self) -> Option<DeliveryFailureReason>;
+    /// None if the value is over MAX_NUMBER_OF_MESSAGES, too
+    fn number_of_messages(&self) -> Option<u8>;
+    fn sms_signal(&self) -> Option<u16>;
+    fn source_subaddress(&self) -> Option<Subaddress>;
+    fn dest_subaddress(&self) -> Option<Subaddress>;
+    /// None if the value is reserved, too
+    fn privacy_indicator(&self) -> Option<PrivacyIndicator>;
+    fn user_response_code(&self) -> Option<u8>;
+    fn network_error_code(&self) -> Option<NetworkErrorCode>;
+}
+
+impl TypedTlvs for Tlvs {
+    fn to_vec(&self) -> Vec<Tlv> {
+        // Tlvs does not let us iterate over its contents, so write them out
+        // and read them back one at a time.
+        let mut buf = Vec::new();
+        self.write(&mut buf)
+            .now_or_never()
+            .expect("Writing to a Vec should never wait")
+            .expect("Writing to a Vec should never fail");
+        let mut bytes = Cursor::new(&buf[..]);
+        let mut ret = Vec::new();
+        while let Ok(Some(tlv)) = Tlv::read(&mut bytes) {
+            ret.push(tlv);
+        }
+        ret
+    }
+
+    fn additional_status_info_text(&self) -> Option<String> {
+        let tlv = self.get(KnownTlvTag::additional_status_info_text)?;
+        let text = tlv.value.split(|b| *b == 0).next().unwrap_or(&[]);
+        Some(String::from_utf8_lossy(text).into_owned())
+    }
+
+    fn delivery_failure_reason(&self) -> Option<DeliveryFailureReason> {
+        match self.get(KnownTlvTag::delivery_failure_reason)?.value[..] {
+            [value] => Some(value.into()),
+            _ => None,
+        }
+    }
+
+    fn number_of_messages(&self) -> Option<u8> {
+        match self.get(KnownTlvTag::number_of_messages)?.value[..] {
+            [count] if count <= MAX_NUMBER_OF_MESSAGES => Some(count),
+            _ => None,
+        }
+    }
+
+    fn sms_signal(&self) -> Option<u16> {
+        match self.get(KnownTlvTag::sms_signal)?.value[..] {
+            [hi, lo] => Some(u16::from_be_bytes([hi, lo])),
+            _ => None,
+        }
+    }
+
+    fn source_subaddress(&self) -> Option<Subaddress> {
+        Subaddress::from_tlv(self.get(KnownTlvTag::source_subaddress)?)
+    }
+
+    fn dest_subaddress(&self) -> Option<Subaddress> {
+        Subaddress::from_tlv(self.get(KnownTlvTag::dest_subaddress)?)
+    }
+
+    fn privacy_indicator(&self) -> Option<PrivacyIndicator> {
+        match self.get(KnownTlvTag::privacy_indicator)?.value[..] {
+            [value] => PrivacyIndicator::try_from(value).ok(),
+            _ => None,
+        }
+    }
+
+    fn user_response_code(&self) -> Option<u8> {
+        match self.get(KnownTlvTag::user_response_code)?.value[..] {
+            [code] => Some(code),
+            _ => None,
+        }
+    }
+
+    fn network_error_code(&self) -> Option<NetworkErrorCode> {
+        match self.get(KnownTlvTag::network_error_code)?.value[..] {
+            [network_type, hi, lo] => Some(NetworkErrorCode {
+                network_type,
+                error_code: u16::from_be_bytes([hi, lo]),
+            }),
+            _ => None,
+        }
+    }
+}
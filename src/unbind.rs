@@ -0,0 +1,128 @@
+//! unbind and unbind_resp, for ending a session cleanly.
+//!
+//! Like deliver_sm_resp, these are missing from smpp_pdu, so they are
+//! standalone types that read and write a whole frame.  Both are just a
+//! header.
+
+use smpp_pdu::pdu::formats::WriteStream;
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody};
+use std::io;
+use tokio::io::AsyncWriteExt;
+
+use crate::command_id::CommandId;
+use crate::frame_body::{command_id, Header, HEADER_LENGTH};
+
+pub const UNBIND: u32 = CommandId::Unbind as u32;
+pub const UNBIND_RESP: u32 = CommandId::UnbindResp as u32;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnbindPdu {
+    pub sequence_number: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnbindRespPdu {
+    pub command_status: u32,
+    pub sequence_number: u32,
+}
+
+impl UnbindPdu {
+    pub fn new(sequence_number: u32) -> Self {
+        Self { sequence_number }
+    }
+
+    /// Does this complete frame (as accepted by Pdu::check) hold an
+    /// unbind?
+    pub fn is_unbind(frame: &[u8]) -> bool {
+        command_id(frame) == Some(UNBIND)
+    }
+
+    pub fn parse(frame: &[u8]) -> Result<Self, PduParseError> {
+        let (command_status, sequence_number) = parse_header(frame, UNBIND)?;
+        if command_status != 0 {
+            return Err(PduParseError::new(PduParseErrorBody::StatusIsNotZero)
+                .into_with_header(
+                    Some(UNBIND),
+                    Some(command_status),
+                    Some(sequence_number),
+                ));
+        }
+        Ok(Self { sequence_number })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        write_header(stream, UNBIND, 0, self.sequence_number).await
+    }
+}
+
+impl UnbindRespPdu {
+    pub fn new(command_status: u32, sequence_number: u32) -> Self {
+        Self {
+            command_status,
+            sequence_number,
+        }
+    }
+
+    pub fn is_unbind_resp(frame: &[u8]) -> bool {
+        command_id(frame) == Some(UNBIND_RESP)
+    }
+
+    pub fn parse(frame: &[u8]) -> Result<Self, PduParseError> {
+        let (command_status, sequence_number) =
+            parse_header(frame, UNBIND_RESP)?;
+        Ok(Self {
+            command_status,
+            sequence_number,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        write_header(
+            stream,
+            UNBIND_RESP,
+            self.command_status,
+            self.sequence_number,
+        )
+        .await
+    }
+}
+
+/// The command_status and sequence_number of a frame that should be
+/// exactly a header with command_id expected_command_id.
+fn parse_header(
+    frame: &[u8],
+    expected_command_id: u32,
+) -> Result<(u32, u32), PduParseError> {
+    let header = Header::peek(frame)
+        .ok_or_else(|| PduParseError::new(PduParseErrorBody::NotEnoughBytes))?;
+    let error = |body| Err(header.error(PduParseError::new(body)));
+    if header.command_id != expected_command_id {
+        return error(PduParseErrorBody::UnknownCommandId);
+    }
+    if header.command_length as usize != HEADER_LENGTH
+        || frame.len() != HEADER_LENGTH
+    {
+        return error(PduParseErrorBody::IncorrectLength(
+            header.command_length,
+            String::from("Expected a header with no body"),
+        ));
+    }
+    Ok((header.command_status, header.sequence_number))
+}
+
+async fn write_header(
+    stream: &mut WriteStream,
+    command_id: u32,
+    command_status: u32,
+    sequence_number: u32,
+) -> io::Result<()> {
+    for value in &[
+        HEADER_LENGTH as u32,
+        command_id,
+        command_status,
+        sequence_number,
+    ] {
+        stream.write_all(&value.to_be_bytes()).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,45 @@
+//! IDs for following one session or message through the logs.
+//!
+//! Each is unique within the process, counting up from 1.  A message's
+//! message_id comes from SmscLogic and may be reused, or rewritten by a
+//! MessageIdMap, so MessageUid is what ties its submit_sm, what SmscLogic
+//! stored, and its delivery receipt together.
+
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+static NEXT_MESSAGE_UID: AtomicU64 = AtomicU64::new(1);
+
+/// Identifies one SmppConnection, from connecting until it closes.
+/// Displayed as "session-N".
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SessionId(pub u64);
+
+impl SessionId {
+    pub fn next() -> Self {
+        Self(NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Display for SessionId {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "session-{}", self.0)
+    }
+}
+
+/// Identifies one message the SMSC accepted.  Displayed as "message-N".
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct MessageUid(pub u64);
+
+impl MessageUid {
+    pub fn next() -> Self {
+        Self(NEXT_MESSAGE_UID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Display for MessageUid {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "message-{}", self.0)
+    }
+}
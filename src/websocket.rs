@@ -0,0 +1,180 @@
+//! SMPP over WebSocket (RFC 6455).  Each PDU we send is one binary message,
+//! and the payloads of the binary messages we receive are treated as one
+//! stream of bytes, so a peer may split or combine PDUs however it likes.
+//!
+//! accept and connect perform the opening handshake on a stream, and return
+//! the other end of an in-memory pipe carrying the SMPP bytes, which can be
+//! used anywhere a TCP stream would be, e.g. SmppConnection::from_stream.
+//! The WebSocket protocol itself is handled by tokio-tungstenite.  Enabled
+//! with the websocket feature.
+//!
+//! Both take the longest PDU to allow, usually ParseOptions::max_pdu_length
+//! or smpp_pdu's MAX_PDU_LENGTH.  No message or frame may be longer.
+
+use futures::stream::{SplitSink, SplitStream};
+use futures::{SinkExt, StreamExt};
+use log::*;
+use std::io;
+use tokio::io::{
+    duplex, split, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+    DuplexStream, ReadHalf, WriteHalf,
+};
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::{Error, Message};
+use tokio_tungstenite::WebSocketStream;
+
+/// How much SMPP data may be waiting in the pipe in each direction.
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+/// Perform the server side of the opening handshake on stream, and return
+/// the SMPP bytes sent and received over it.
+pub async fn accept<S>(
+    stream: S,
+    max_pdu_length: usize,
+) -> io::Result<DuplexStream>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let websocket = tokio_tungstenite::accept_async_with_config(
+        stream,
+        Some(config(max_pdu_length)),
+    )
+    .await
+    .map_err(handshake_error)?;
+    Ok(pump(websocket, max_pdu_length))
+}
+
+/// Perform the client side of the opening handshake on stream, asking for
+/// path on host, and return the SMPP bytes sent and received over it.
+pub async fn connect<S>(
+    stream: S,
+    host: &str,
+    path: &str,
+    max_pdu_length: usize,
+) -> io::Result<DuplexStream>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let url = format!("ws://{}{}", host, path);
+    let (websocket, _response) = tokio_tungstenite::client_async_with_config(
+        url.as_str(),
+        stream,
+        Some(config(max_pdu_length)),
+    )
+    .await
+    .map_err(handshake_error)?;
+    Ok(pump(websocket, max_pdu_length))
+}
+
+/// The Sec-WebSocket-Accept value a server should reply with when a client
+/// sends key as its Sec-WebSocket-Key.
+pub fn accept_key(key: &str) -> String {
+    derive_accept_key(key.as_bytes())
+}
+
+/// No message or frame may be longer than a PDU may be.
+fn config(max_pdu_length: usize) -> WebSocketConfig {
+    WebSocketConfig {
+        max_message_size: Some(max_pdu_length),
+        max_frame_size: Some(max_pdu_length),
+        ..WebSocketConfig::default()
+    }
+}
+
+/// Spawn tasks copying between the WebSocket and one end of a pipe, and
+/// return the other end.
+fn pump<S>(websocket: WebSocketStream<S>, max_pdu_length: usize) -> DuplexStream
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (ours, theirs) = duplex(PIPE_CAPACITY);
+    let (pipe_read, pipe_write) = split(theirs);
+    let (sink, stream) = websocket.split();
+    tokio::spawn(receive_loop(stream, pipe_write));
+    tokio::spawn(send_loop(pipe_read, sink, max_pdu_length));
+    ours
+}
+
+/// Write the payloads of binary messages into the pipe until the peer
+/// closes the WebSocket.  Pings and closes are answered by tungstenite.
+async fn receive_loop<S>(
+    mut stream: SplitStream<WebSocketStream<S>>,
+    mut pipe: WriteHalf<DuplexStream>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    while let Some(message) = stream.next().await {
+        let result = match message {
+            Ok(Message::Binary(payload)) => pipe.write_all(&payload).await,
+            Ok(Message::Ping(_)) | Ok(Message::Pong(_)) => Ok(()),
+            Ok(Message::Close(_)) => break,
+            Ok(Message::Text(_)) => {
+                error!("Closing WebSocket: SMPP must be sent as binary.");
+                break;
+            }
+            Ok(Message::Frame(_)) => Ok(()),
+            Err(Error::ConnectionClosed) => break,
+            Err(e) => {
+                error!("WebSocket receive failed: {}", e);
+                break;
+            }
+        };
+        if let Err(e) = result {
+            error!("WebSocket receive failed: {}", e);
+            break;
+        }
+    }
+    let _ = pipe.shutdown().await;
+}
+
+/// Send each PDU written into the pipe as a binary message, and close the
+/// WebSocket when the pipe is closed.
+async fn send_loop<S>(
+    mut pipe: ReadHalf<DuplexStream>,
+    mut sink: SplitSink<WebSocketStream<S>, Message>,
+    max_pdu_length: usize,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    loop {
+        let pdu = match read_pdu(&mut pipe, max_pdu_length).await {
+            Ok(pdu) => pdu,
+            Err(e) => {
+                if e.kind() != io::ErrorKind::UnexpectedEof {
+                    error!("WebSocket send failed: {}", e);
+                }
+                break;
+            }
+        };
+        if let Err(e) = sink.send(Message::Binary(pdu)).await {
+            error!("WebSocket send failed: {}", e);
+            return;
+        }
+    }
+    // Errors are expected if the peer closed first.
+    let _ = sink.close().await;
+}
+
+/// Read the bytes of one PDU, using its command_length.  If the length is
+/// nonsense we pass on just the length, and let the peer reject it.
+async fn read_pdu<R>(read: &mut R, max_pdu_length: usize) -> io::Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut pdu = vec![0; 4];
+    read.read_exact(&mut pdu).await?;
+    let len = u32::from_be_bytes([pdu[0], pdu[1], pdu[2], pdu[3]]) as usize;
+    if (4..=max_pdu_length).contains(&len) {
+        pdu.resize(len, 0);
+        read.read_exact(&mut pdu[4..]).await?;
+    }
+    Ok(pdu)
+}
+
+fn handshake_error(e: Error) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("WebSocket handshake failed: {}", e),
+    )
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "admin-http")]
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+mod test_utils;
+
+use test_utils::{next_port, DefaultLogic, TestClient, TestServer};
+
+async fn start() -> (TestServer, String) {
+    let admin_address = format!("127.0.0.1:{}", next_port());
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.admin_address = Some(admin_address.clone())
+    })
+    .await
+    .unwrap();
+    (server, admin_address)
+}
+
+async fn request(admin_address: &str, method: &str, path: &str) -> String {
+    let mut stream = TcpStream::connect(admin_address).await.unwrap();
+    stream
+        .write_all(
+            format!("{} {} HTTP/1.1\r\nHost: smsc\r\n\r\n", method, path)
+                .as_bytes(),
+        )
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+fn body(response: &str) -> &str {
+    response.split("\r\n\r\n").nth(1).unwrap()
+}
+
+#[tokio::test]
+async fn health_check_responds_ok() {
+    let (_server, admin) = start().await;
+    let response = request(&admin, "GET", "/health").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("Content-Length: 15\r\n"));
+    assert_eq!(body(&response), r#"{"status":"ok"}"#);
+}
+
+#[tokio::test]
+async fn health_report_describes_bound_sessions() {
+    let (server, admin) = start().await;
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transceiver().await;
+
+    let response = request(&admin, "GET", "/health/report").await;
+    assert_eq!(
+        body(&response),
+        concat!(
+            r#"{"healthy":true,"bound_sessions":1,"pending_responses":0,"#,
+            r#""recent_error_rate":0,"recent_system_error_rate":0,"#,
+            r#""sessions":[{"system_id":"esmeid","system_type":"type","#,
+            r#""last_enquire_link_rtt_ms":null,"pending_responses":0,"#,
+            r#""recent_responses":1,"recent_errors":0,"#,
+            r#""recent_system_errors":0}]}"#,
+        )
+    );
+}
+
+#[tokio::test]
+async fn sessions_can_be_listed_and_kicked() {
+    let (server, admin) = start().await;
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transceiver_as("my\"esme").await;
+
+    let response = request(&admin, "GET", "/sessions").await;
+    let expected_start = r#"[{"system_id":"my\"esme","system_type":"type","#;
+    assert!(body(&response).starts_with(expected_start), "{}", response);
+
+    let response = request(&admin, "GET", "/stats").await;
+    assert!(body(&response).contains(r#""submits":0,"#), "{}", response);
+
+    let response = request(&admin, "DELETE", "/sessions/my\"esme").await;
+    assert_eq!(body(&response), r#"{"kicked":1}"#);
+
+    let response = request(&admin, "DELETE", "/sessions/nobody").await;
+    assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+}
+
+#[tokio::test]
+async fn routes_can_be_paused_and_resumed() {
+    let (_server, admin) = start().await;
+
+    let response = request(&admin, "POST", "/routes/4477/pause").await;
+    assert_eq!(body(&response), r#"{"paused":["4477"]}"#);
+    let response = request(&admin, "GET", "/routes").await;
+    assert_eq!(body(&response), r#"{"paused":["4477"]}"#);
+    let response = request(&admin, "POST", "/routes/4477/resume").await;
+    assert_eq!(body(&response), r#"{"paused":[]}"#);
+}
+
+#[tokio::test]
+async fn unknown_paths_are_not_found() {
+    let (_server, admin) = start().await;
+    let response = request(&admin, "GET", "/nothing").await;
+    assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    assert_eq!(body(&response), r#"{"error":"Not found"}"#);
+}
@@ -0,0 +1,83 @@
+use smpp::alert_notification::{AlertNotificationPdu, MsAvailabilityStatus};
+use smpp::client::{BindMode, Client};
+use smpp::smpp_connection::Frame;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+async fn written(frame: Frame) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    frame.write(&mut bytes).await.unwrap();
+    bytes
+}
+
+#[tokio::test]
+async fn alert_notification_is_written_and_parsed() {
+    let alert = AlertNotificationPdu::new(
+        1,
+        "447700900123",
+        "esme1",
+        Some(MsAvailabilityStatus::Available),
+    )
+    .unwrap();
+
+    let bytes = written(Frame::AlertNotification(alert)).await;
+
+    assert_eq!(
+        bytes,
+        b"\x00\x00\x00\x2c\x00\x00\x01\x02\x00\x00\x00\x00\x00\x00\x00\x01\
+          \x00\x00447700900123\x00\x00\x00esme1\x00\
+          \x04\x22\x00\x01\x00"
+    );
+    let parsed = AlertNotificationPdu::parse(&bytes).unwrap();
+    assert_eq!(parsed.source_addr.value.as_str(), "447700900123");
+    assert_eq!(parsed.esme_addr.value.as_str(), "esme1");
+    assert_eq!(
+        parsed.ms_availability_status(),
+        Some(Ok(MsAvailabilityStatus::Available))
+    );
+}
+
+#[test]
+fn ms_availability_status_is_optional() {
+    let alert = AlertNotificationPdu::parse(
+        b"\x00\x00\x00\x1a\x00\x00\x01\x02\x00\x00\x00\x00\x00\x00\x00\x01\
+          \x00\x00123\x00\x00\x00e\x00",
+    )
+    .unwrap();
+
+    assert_eq!(alert.ms_availability_status(), None);
+}
+
+#[tokio::test]
+async fn a_receiver_consumes_alert_notifications() {
+    let server = TestServer::start().await.unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Receiver, "esme1", "secret", "")
+        .await
+        .unwrap();
+
+    let connection = server.smsc.lock().await.connections().remove(0);
+    let alert = AlertNotificationPdu::new(
+        1,
+        "447700900123",
+        "esme1",
+        Some(MsAvailabilityStatus::Unavailable),
+    )
+    .unwrap();
+    connection
+        .write_frame(&Frame::AlertNotification(alert))
+        .await
+        .unwrap();
+
+    let received = client.next_alert_notification().await.unwrap();
+    assert_eq!(received.source_addr.value.as_str(), "447700900123");
+    assert_eq!(
+        received.ms_availability_status(),
+        Some(Ok(MsAvailabilityStatus::Unavailable))
+    );
+    // The session carries on
+    client.unbind().await.unwrap();
+}
@@ -0,0 +1,96 @@
+use smpp::smpp_connection::{SmppConnection, MAX_BAD_FRAMES};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+
+async fn connect() -> (TcpStream, SmppConnection) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (server_stream, addr) = listener.accept().await.unwrap();
+    (client, SmppConnection::new(server_stream, addr))
+}
+
+fn unknown_command(seq: u8) -> Vec<u8> {
+    let mut pdu = Vec::from(
+        &b"\x00\x00\x00\x10\xff\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00"[..],
+    );
+    //           invalid ^^^^^^^^^^^^^^^
+    pdu[15] = seq;
+    pdu
+}
+
+#[tokio::test]
+async fn frames_that_fail_to_parse_are_kept() {
+    const ENQUIRE_LINK: &[u8; 0x10] =
+        b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12";
+
+    let (mut client, connection) = connect().await;
+    let mut bytes = unknown_command(0x22);
+    bytes.extend(ENQUIRE_LINK);
+    client.write_all(&bytes).await.unwrap();
+
+    assert!(connection.read_pdu().await.is_err());
+    assert_eq!(connection.bad_frames(), vec![unknown_command(0x22)]);
+
+    // Good frames are not kept
+    assert!(connection.read_pdu().await.unwrap().is_some());
+    assert_eq!(connection.bad_frames().len(), 1);
+}
+
+#[tokio::test]
+async fn frames_with_bad_lengths_are_kept() {
+    let (mut client, connection) = connect().await;
+    client.write_all(b"\x00\x00\x00\x01").await.unwrap();
+    //                          length is 1! ^^
+
+    assert!(connection.read_pdu().await.is_err());
+    assert_eq!(connection.bad_frames(), vec![b"\x00\x00\x00\x01".to_vec()]);
+}
+
+#[tokio::test]
+async fn bad_lengths_are_reported_with_the_rest_of_the_header() {
+    let (mut client, connection) = connect().await;
+    client
+        .write_all(
+            b"\x00\xff\xff\xff\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x07",
+        )
+        //   ^^^^^^^^^^^^^^^^ too long
+        .await
+        .unwrap();
+
+    let e = connection.read_pdu().await.unwrap_err();
+
+    assert_eq!(e.command_id, Some(0x00000002));
+    assert_eq!(e.sequence_number, Some(7));
+}
+
+#[tokio::test]
+async fn partial_frames_are_kept_when_the_peer_disconnects() {
+    let (mut client, connection) = connect().await;
+    client.write_all(b"\x00\x00\x00\x29\x00\x00").await.unwrap();
+    client.shutdown().await.unwrap();
+
+    assert!(connection.read_pdu().await.is_err());
+    assert_eq!(
+        connection.bad_frames(),
+        vec![b"\x00\x00\x00\x29\x00\x00".to_vec()]
+    );
+}
+
+#[tokio::test]
+async fn only_the_most_recent_bad_frames_are_kept() {
+    let (mut client, connection) = connect().await;
+    for seq in 0..=MAX_BAD_FRAMES as u8 {
+        client.write_all(&unknown_command(seq)).await.unwrap();
+        assert!(connection.read_pdu().await.is_err());
+    }
+
+    let bad_frames = connection.bad_frames();
+    assert_eq!(bad_frames.len(), MAX_BAD_FRAMES);
+    assert_eq!(bad_frames[0], unknown_command(1));
+    assert_eq!(
+        bad_frames[MAX_BAD_FRAMES - 1],
+        unknown_command(MAX_BAD_FRAMES as u8)
+    );
+}
@@ -0,0 +1,163 @@
+use async_trait::async_trait;
+use smpp::bridge::{Bridge, ClientPool, Upstream};
+use smpp::client::{BindMode, Client, ClientState};
+use smpp::examples::smsc_drs_after_1_sec::DrsAfter1Sec;
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
+use smpp::submit_sm_builder::SubmitSmBuilder;
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::{sleep, timeout};
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+/// Lets anyone bind, and sends everything through one Bridge
+struct Forward(Arc<Bridge>);
+
+#[async_trait]
+impl SmscLogic for Forward {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        self.0.submit_sm(pdu).await
+    }
+}
+
+fn upstream(server: &TestServer) -> Upstream {
+    Upstream::new("up", &server.bind_address, "gw", "gw")
+}
+
+async fn bridged_client() -> (TestServer, TestServer, Client) {
+    let upstream_server = TestServer::start_with_logic(DrsAfter1Sec::new())
+        .await
+        .unwrap();
+    let bridge =
+        Arc::new(Bridge::connect(&upstream(&upstream_server)).await.unwrap());
+    let server = TestServer::start_with_logic(Forward(Arc::clone(&bridge)))
+        .await
+        .unwrap();
+    bridge.relay_receipts(&server.smsc, None);
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transceiver, "esme1", "secret", "")
+        .await
+        .unwrap();
+    (upstream_server, server, client)
+}
+
+async fn submit(client: &Client, registered_delivery: u8) -> Option<String> {
+    let submit_sm = SubmitSmBuilder::new()
+        .destination_addr("447700900123")
+        .registered_delivery(registered_delivery)
+        .short_message(b"hello")
+        .build()
+        .unwrap();
+    let resp = client.submit_sm(submit_sm).await.unwrap();
+    assert_eq!(resp.command_status, 0);
+    resp.message_id
+}
+
+#[tokio::test]
+async fn esmes_see_our_message_ids_in_responses_and_receipts() {
+    let (_upstream, _server, client) = bridged_client().await;
+
+    let message_id = submit(&client, 1).await;
+    assert!(message_id.is_some());
+    assert_ne!(message_id.as_deref(), Some("abc"));
+
+    let receipt = timeout(Duration::from_secs(5), client.next_receipt())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(receipt.message_id, message_id);
+}
+
+#[tokio::test]
+async fn receipts_nobody_asked_for_are_not_passed_on() {
+    let (_upstream, _server, client) = bridged_client().await;
+
+    submit(&client, 0).await;
+
+    assert!(timeout(Duration::from_millis(1500), client.next_receipt())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn pools_use_each_session_in_turn() {
+    let upstream_server =
+        TestServer::start_with_logic_and_config(DrsAfter1Sec::new(), 3)
+            .await
+            .unwrap();
+    let pool = ClientPool::connect(&Upstream {
+        connections: 2,
+        ..upstream(&upstream_server)
+    })
+    .await
+    .unwrap();
+
+    let clients = pool.clients();
+    assert_eq!(clients.len(), 2);
+    assert!(Arc::ptr_eq(&pool.next(), &clients[0]));
+    assert!(Arc::ptr_eq(&pool.next(), &clients[1]));
+    assert!(Arc::ptr_eq(&pool.next(), &clients[0]));
+}
+
+#[tokio::test]
+async fn closed_sessions_are_reconnected() {
+    let upstream_server = TestServer::start_with_logic(DrsAfter1Sec::new())
+        .await
+        .unwrap();
+    // Each session closes after its bind and one submit_sm
+    upstream_server
+        .smsc
+        .lock()
+        .await
+        .set_scenario("gw drop after 2".parse().unwrap());
+    let pool = ClientPool::connect(&upstream(&upstream_server))
+        .await
+        .unwrap();
+    let mut state_changes = pool.next().state_changes();
+    let states = tokio::spawn(async move {
+        let mut states = Vec::new();
+        while state_changes.changed().await.is_ok() {
+            let state = state_changes.borrow().state;
+            states.push(state);
+            if state == ClientState::Bound {
+                return states;
+            }
+        }
+        states
+    });
+    let bridge = Bridge::new("up", pool);
+    let submit_sm = || {
+        SubmitSmBuilder::new()
+            .destination_addr("447700900123")
+            .short_message(b"hello")
+            .build()
+            .unwrap()
+    };
+
+    assert!(bridge.submit_sm(&submit_sm()).await.is_ok());
+    sleep(Duration::from_millis(100)).await;
+    assert!(bridge.submit_sm(&submit_sm()).await.is_err());
+    assert!(bridge.submit_sm(&submit_sm()).await.is_ok());
+
+    let states = timeout(Duration::from_secs(5), states)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(states.contains(&ClientState::Reconnecting), "{:?}", states);
+    assert_eq!(states.last(), Some(&ClientState::Bound));
+}
@@ -0,0 +1,67 @@
+use smpp::c_octet_string::{read, validate, COctetStringFormat};
+use smpp_pdu::pdu::formats::COctetString;
+use std::io::Cursor;
+
+fn s(value: &str) -> COctetString {
+    COctetString::from_str(value, 65).unwrap()
+}
+
+#[test]
+fn decimal_strings_may_only_contain_digits() {
+    assert!(
+        validate("f", &s("0123456789"), COctetStringFormat::Decimal).is_ok()
+    );
+    assert!(validate("f", &s(""), COctetStringFormat::Decimal).is_ok());
+
+    let e = validate("message_id", &s("12a4"), COctetStringFormat::Decimal)
+        .unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "message_id must be a C-Octet String (Decimal), \
+        but it contains 'a' at position 2."
+    );
+}
+
+#[test]
+fn hex_strings_may_only_contain_hex_digits() {
+    assert!(validate(
+        "f",
+        &s("0123456789abcdefABCDEF"),
+        COctetStringFormat::Hex
+    )
+    .is_ok());
+
+    let e = validate("message_id", &s("ff0g"), COctetStringFormat::Hex)
+        .unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "message_id must be a C-Octet String (Hex), \
+        but it contains 'g' at position 3."
+    );
+}
+
+#[test]
+fn any_format_allows_any_ascii() {
+    assert!(validate("f", &s("a b-c!"), COctetStringFormat::Any).is_ok());
+}
+
+#[test]
+fn read_checks_format_after_reading() {
+    let mut bytes = Cursor::new(&b"1234\0rest"[..]);
+    assert_eq!(
+        read("id", &mut bytes, 9, COctetStringFormat::Decimal).unwrap(),
+        s("1234")
+    );
+
+    let mut bytes = Cursor::new(&b"12x4\0"[..]);
+    assert!(read("id", &mut bytes, 9, COctetStringFormat::Decimal).is_err());
+
+    let mut bytes = Cursor::new(&b"1234"[..]);
+    assert_eq!(
+        read("id", &mut bytes, 9, COctetStringFormat::Decimal)
+            .unwrap_err()
+            .to_string(),
+        "Error reading id: C-Octet String does not end with the NULL \
+        character."
+    );
+}
@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use smpp::cancel_sm::{CancelSmPdu, CancelSmRespPdu};
+use smpp::client::{BindMode, Client};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smpp_connection::Frame;
+use smpp::smsc::{
+    BindData, BindError, CancelSmError, Smsc, SmscLogic, SubmitSmError,
+};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+/// Can cancel "1234", and nothing else
+struct Logic {}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        _pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Err(SubmitSmError::InternalError)
+    }
+
+    async fn cancel_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &CancelSmPdu,
+    ) -> Result<(), CancelSmError> {
+        if pdu.message_id.value == "1234" {
+            Ok(())
+        } else {
+            Err(CancelSmError::CancelFailed)
+        }
+    }
+}
+
+async fn written(frame: Frame) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    frame.write(&mut bytes).await.unwrap();
+    bytes
+}
+
+#[tokio::test]
+async fn cancel_sm_is_written_and_parsed() {
+    let cancel_sm = CancelSmPdu::new(7, "1234", "MyCompany").unwrap();
+
+    let bytes = written(Frame::CancelSm(cancel_sm.clone())).await;
+
+    assert_eq!(
+        bytes,
+        b"\x00\x00\x00\x25\x00\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00\x07\
+          \x001234\x00\x00\x00MyCompany\x00\x00\x00\x00"
+    );
+    assert_eq!(CancelSmPdu::parse(&bytes).unwrap(), cancel_sm);
+}
+
+#[tokio::test]
+async fn cancel_sm_resp_is_just_a_header() {
+    let bytes =
+        written(Frame::CancelSmResp(CancelSmRespPdu::new(0x11, 7))).await;
+
+    assert_eq!(
+        bytes,
+        b"\x00\x00\x00\x10\x80\x00\x00\x08\x00\x00\x00\x11\x00\x00\x00\x07"
+    );
+    assert_eq!(
+        CancelSmRespPdu::parse(&bytes).unwrap(),
+        CancelSmRespPdu::new(0x11, 7)
+    );
+}
+
+#[test]
+fn without_a_message_id_a_destination_is_required() {
+    assert!(CancelSmPdu::new(7, "", "MyCompany").is_err());
+    assert!(CancelSmPdu::new_by_address(7, "", "MyCompany", "").is_err());
+    assert!(
+        CancelSmPdu::new_by_address(7, "", "MyCompany", "447700900123").is_ok()
+    );
+
+    let err = CancelSmPdu::parse(
+        b"\x00\x00\x00\x21\x00\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00\x07\
+          \x00\x00\x00\x00MyCompany\x00\x00\x00\x00",
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("destination_addr"), "{}", err);
+}
+
+#[test]
+fn a_truncated_body_names_the_missing_field() {
+    let err = CancelSmPdu::parse(
+        b"\x00\x00\x00\x16\x00\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00\x07\
+          \x001234\x00",
+    )
+    .unwrap_err();
+
+    assert!(err.to_string().contains("source_addr_ton"), "{}", err);
+}
+
+#[tokio::test]
+async fn the_smsc_answers_cancel_sm_from_its_logic() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transmitter, "esme1", "secret", "")
+        .await
+        .unwrap();
+
+    let resp = client
+        .cancel_sm(CancelSmPdu::new(0, "1234", "MyCompany").unwrap())
+        .await
+        .unwrap();
+    assert_eq!(resp.command_status, 0);
+
+    let resp = client
+        .cancel_sm(CancelSmPdu::new(0, "9999", "MyCompany").unwrap())
+        .await
+        .unwrap();
+    // ESME_RCANCELFAIL
+    assert_eq!(resp.command_status, 0x11);
+}
@@ -0,0 +1,116 @@
+use async_trait::async_trait;
+use smpp::canned_messages::{CannedMessage, CannedMessages};
+use smpp::client::{BindMode, Client, ClientError};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
+use smpp_pdu::pdu::{PduStatus, SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::{submit_sm_to, TestServer};
+
+fn submit_sm(sm_default_msg_id: u8, short_message: &[u8]) -> SubmitSmPdu {
+    let mut submit_sm = submit_sm_to("447700900123");
+    submit_sm.0.sm_default_msg_id.value = sm_default_msg_id;
+    submit_sm.0.short_message.value = Vec::from(short_message);
+    submit_sm
+}
+
+#[test]
+fn canned_messages_are_written_id_equals_text() {
+    assert_eq!(
+        "7=Running late".parse::<CannedMessage>().unwrap(),
+        CannedMessage::new(7, b"Running late").unwrap()
+    );
+    assert!("0=Running late".parse::<CannedMessage>().is_err());
+    assert!("255=Running late".parse::<CannedMessage>().is_err());
+    assert!("Running late".parse::<CannedMessage>().is_err());
+    assert!(CannedMessage::new(1, &[b'a'; 255]).is_err());
+}
+
+#[test]
+fn only_a_submit_sm_without_a_message_is_expanded() {
+    let canned = CannedMessages::new(&[CannedMessage::new(1, b"Hi").unwrap()]);
+
+    let expanded = canned.expand(&submit_sm(1, b"")).unwrap().unwrap();
+
+    assert_eq!(expanded.0.short_message.value, b"Hi");
+    assert_eq!(expanded.0.sm_default_msg_id.value, 1);
+    assert!(canned.expand(&submit_sm(1, b"Hello")).unwrap().is_none());
+    assert!(canned.expand(&submit_sm(0, b"")).unwrap().is_none());
+    assert!(canned.expand(&submit_sm(2, b"")).is_err());
+}
+
+#[tokio::test]
+async fn the_smsc_sends_the_canned_message_an_id_names() {
+    let submitted = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let server = TestServer::start_with_smsc_config(
+        Logic {
+            submitted: Arc::clone(&submitted),
+        },
+        |config| {
+            config.canned_messages = vec!["1=Running late".parse().unwrap()]
+        },
+    )
+    .await
+    .unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transmitter, "esme1", "secret", "")
+        .await
+        .unwrap();
+
+    client.submit_sm(submit_sm(1, b"")).await.unwrap();
+    let resp = client.submit_sm(submit_sm(2, b"")).await.unwrap();
+
+    assert_eq!(*submitted.lock().unwrap(), vec![b"Running late".to_vec()]);
+    assert!(matches!(
+        resp.pdu_status(),
+        Some(PduStatus::ESME_RINVDFTMSGID)
+    ));
+}
+
+#[tokio::test]
+async fn the_client_refuses_ids_out_of_range() {
+    let server = TestServer::start().await.unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+
+    let err = client.submit_sm(submit_sm(255, b"")).await.unwrap_err();
+
+    assert!(matches!(err, ClientError::Pdu(_)), "{}", err);
+    assert!(err.to_string().contains("sm_default_msg_id"), "{}", err);
+}
+
+/// Records the short_message of each submit_sm.
+struct Logic {
+    submitted: Arc<std::sync::Mutex<Vec<Vec<u8>>>>,
+}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        self.submitted
+            .lock()
+            .unwrap()
+            .push(pdu.0.short_message.value.clone());
+        Ok((
+            SubmitSmRespPdu::new("1234").unwrap(),
+            MessageUniqueKey::new(
+                String::from("testsystem"),
+                String::from("1234"),
+                pdu.destination_addr(),
+            ),
+        ))
+    }
+}
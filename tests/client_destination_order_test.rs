@@ -0,0 +1,99 @@
+use async_trait::async_trait;
+use smpp::client::{BindMode, Client, DestinationOrder};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
+use smpp_pdu::pdu::{PduStatus, SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+/// Throttles the first message "otp", and remembers the text of each
+/// message it accepts
+struct Logic {
+    throttled: bool,
+    accepted: Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        let text = String::from_utf8_lossy(&pdu.0.short_message.value);
+        if text == "otp" && !self.throttled {
+            self.throttled = true;
+            return Err(SubmitSmError::Throttled);
+        }
+        self.accepted.lock().unwrap().push(text.to_string());
+        let msgid = pdu.destination_addr();
+        Ok((
+            SubmitSmRespPdu::new(&msgid).unwrap(),
+            MessageUniqueKey::new(String::from("esme1"), msgid.clone(), msgid),
+        ))
+    }
+}
+
+/// What the SMSC accepted after an OTP and a follow-up were submitted to
+/// the same destination one straight after the other
+async fn accepted(order: Option<DestinationOrder>) -> Vec<String> {
+    let accepted = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let server = TestServer::start_with_logic(Logic {
+        throttled: false,
+        accepted: accepted.clone(),
+    })
+    .await
+    .unwrap();
+    let mut client = Client::connect(&server.bind_address).await.unwrap();
+    client.set_destination_order(order);
+    client
+        .bind(BindMode::Transmitter, "esme1", "", "")
+        .await
+        .unwrap();
+    let client = Arc::new(client);
+
+    let submit = |text: &'static [u8]| {
+        let client = Arc::clone(&client);
+        tokio::spawn(async move {
+            client.submit_text("+447700900123", text).await.unwrap()
+        })
+    };
+    let otp = submit(b"otp");
+    sleep(Duration::from_millis(5)).await;
+    let follow_up = submit(b"follow-up");
+    let otp = otp.await.unwrap();
+    follow_up.await.unwrap();
+
+    if order.is_some() {
+        assert_eq!(otp.command_status, 0);
+    } else {
+        assert_eq!(otp.command_status, PduStatus::ESME_RTHROTTLED as u32);
+    }
+    let accepted = accepted.lock().unwrap().clone();
+    accepted
+}
+
+#[tokio::test]
+async fn messages_to_a_destination_are_submitted_and_retried_in_order() {
+    let order = DestinationOrder {
+        retries: 3,
+        retry_delay: Duration::from_millis(10),
+    };
+    assert_eq!(accepted(Some(order)).await, vec!["otp", "follow-up"]);
+}
+
+#[tokio::test]
+async fn without_destination_order_a_follow_up_can_overtake() {
+    assert_eq!(accepted(None).await, vec!["follow-up"]);
+}
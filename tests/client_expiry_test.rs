@@ -0,0 +1,160 @@
+use smpp::client::{
+    Client, ClientError, ExpiredRequest, ExpiryAction, ExpiryPolicy,
+};
+use smpp::command_id::CommandId;
+use std::convert::TryInto;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// An SMSC that ignores the first `ignored` requests and answers the rest
+/// with a submit_sm_resp, and the sequence numbers it received
+async fn smsc_ignoring(ignored: usize) -> (SocketAddr, Arc<Mutex<Vec<u32>>>) {
+    let smsc = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let smsc_address = smsc.local_addr().unwrap();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let received_by_smsc = Arc::clone(&received);
+    tokio::spawn(async move {
+        let (mut stream, _) = smsc.accept().await.unwrap();
+        loop {
+            let mut header = [0; 16];
+            if stream.read_exact(&mut header).await.is_err() {
+                return;
+            }
+            let command_length =
+                u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+            let mut body = vec![0; command_length - 16];
+            stream.read_exact(&mut body).await.unwrap();
+            let sequence_number =
+                u32::from_be_bytes(header[12..].try_into().unwrap());
+            let count = {
+                let mut received = received_by_smsc.lock().unwrap();
+                received.push(sequence_number);
+                received.len()
+            };
+            if count > ignored {
+                let mut resp = Vec::from(
+                    &b"\x00\x00\x00\x14\x80\x00\x00\x04\x00\x00\x00\x00"[..],
+                );
+                resp.extend(&header[12..]);
+                resp.extend(b"abc\x00");
+                stream.write_all(&resp).await.unwrap();
+            }
+        }
+    });
+    (smsc_address, received)
+}
+
+async fn client_with(address: SocketAddr, policy: ExpiryPolicy) -> Client {
+    let mut client = Client::connect(address).await.unwrap();
+    client.set_response_timeout(Duration::from_millis(100));
+    client.set_expiry_policy(policy);
+    client
+}
+
+#[tokio::test]
+async fn by_default_an_unanswered_request_fails() {
+    let (address, received) = smsc_ignoring(usize::MAX).await;
+    let client = client_with(address, ExpiryPolicy::default()).await;
+
+    let result = client.submit_text("447700900123", b"hello").await;
+
+    assert!(matches!(result, Err(ClientError::Timeout)), "{:?}", result);
+    assert_eq!(received.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn an_unanswered_request_can_be_resent_once() {
+    let (address, received) = smsc_ignoring(1).await;
+    let client = client_with(address, ExpiryPolicy::ResendOnce).await;
+
+    let resp = client.submit_text("447700900123", b"hello").await.unwrap();
+
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 2);
+    assert_ne!(received[0], received[1]);
+    assert_eq!(resp.sequence_number, received[1]);
+    assert_eq!(resp.message_id.as_deref(), Some("abc"));
+}
+
+#[tokio::test]
+async fn a_resent_request_that_is_not_answered_either_fails() {
+    let (address, received) = smsc_ignoring(usize::MAX).await;
+    let client = client_with(address, ExpiryPolicy::ResendOnce).await;
+
+    let result = client.submit_text("447700900123", b"hello").await;
+
+    assert!(matches!(result, Err(ClientError::Timeout)), "{:?}", result);
+    assert_eq!(received.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn a_callback_decides_what_to_do_about_each_expiry() {
+    let (address, received) = smsc_ignoring(2).await;
+    let expired = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&expired);
+    let policy = ExpiryPolicy::Callback(Arc::new(move |request| {
+        seen.lock().unwrap().push(*request);
+        ExpiryAction::Resend
+    }));
+    let client = client_with(address, policy).await;
+
+    client.submit_text("447700900123", b"hello").await.unwrap();
+
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 3);
+    assert_eq!(
+        *expired.lock().unwrap(),
+        vec![
+            ExpiredRequest {
+                command_id: CommandId::SubmitSm,
+                sequence_number: received[0],
+                resent: 0,
+            },
+            ExpiredRequest {
+                command_id: CommandId::SubmitSm,
+                sequence_number: received[1],
+                resent: 1,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn a_callback_can_fail_the_request() {
+    let (address, received) = smsc_ignoring(usize::MAX).await;
+    let policy = ExpiryPolicy::Callback(Arc::new(|_: &ExpiredRequest| {
+        ExpiryAction::Fail
+    }));
+    let client = client_with(address, policy).await;
+
+    let result = client.submit_text("447700900123", b"hello").await;
+
+    assert!(matches!(result, Err(ClientError::Timeout)), "{:?}", result);
+    assert_eq!(received.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn outstanding_sequence_numbers_are_listed() {
+    let (address, received) = smsc_ignoring(usize::MAX).await;
+    let mut client = Client::connect(address).await.unwrap();
+    client.set_response_timeout(Duration::from_secs(5));
+    let client = Arc::new(client);
+    assert!(client.outstanding_sequence_numbers().is_empty());
+
+    for _ in 0..2 {
+        let client = Arc::clone(&client);
+        tokio::spawn(async move {
+            let _ = client.submit_text("447700900123", b"hello").await;
+        });
+    }
+    while received.lock().unwrap().len() < 2 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let mut received = received.lock().unwrap().clone();
+    received.sort_unstable();
+    assert_eq!(client.outstanding_sequence_numbers(), received);
+}
@@ -0,0 +1,105 @@
+use async_trait::async_trait;
+use smpp::client::{BindMode, Client};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smpp_connection::SmppConnection;
+use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+/// Accepts each submit_sm after a while, one at a time
+struct SlowLogic {}
+
+#[async_trait]
+impl SmscLogic for SlowLogic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        sleep(Duration::from_millis(50)).await;
+        let msgid = pdu.destination_addr();
+        Ok((
+            SubmitSmRespPdu::new(&msgid).unwrap(),
+            MessageUniqueKey::new(String::from("esme1"), msgid.clone(), msgid),
+        ))
+    }
+}
+
+async fn bound_client(
+    producer_window: Option<usize>,
+) -> (TestServer, Arc<Client>) {
+    let server = TestServer::start_with_logic(SlowLogic {}).await.unwrap();
+    let stream = TcpStream::connect(&server.bind_address).await.unwrap();
+    let peer_addr = stream.peer_addr().unwrap();
+    let mut client = Client::from_connection_with_window(
+        SmppConnection::new(stream, peer_addr),
+        4,
+    );
+    if let Some(max_in_flight) = producer_window {
+        client.set_producer_window(max_in_flight);
+    }
+    client
+        .bind(BindMode::Transmitter, "esme1", "", "")
+        .await
+        .unwrap();
+    (server, Arc::new(client))
+}
+
+/// Where, among the answers, the one producer "otp" got comes after
+/// "bulk" has queued six messages.
+async fn place_of_otp_answer(client: Arc<Client>) -> usize {
+    let answered = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let submit = |producer: &'static str, destination: String| {
+        let client = Arc::clone(&client);
+        let answered = Arc::clone(&answered);
+        tokio::spawn(async move {
+            client
+                .as_producer(
+                    producer,
+                    client.submit_text(&destination, b"hello"),
+                )
+                .await
+                .unwrap();
+            answered.lock().unwrap().push(producer);
+        })
+    };
+    let mut tasks: Vec<_> = (0..6)
+        .map(|i| submit("bulk", format!("+4477009001{:02}", i)))
+        .collect();
+    sleep(Duration::from_millis(10)).await;
+    tasks.push(submit("otp", String::from("+447700900999")));
+
+    sleep(Duration::from_millis(10)).await;
+    assert!(client.producer_in_flight("bulk") <= 2);
+    for task in tasks {
+        task.await.unwrap();
+    }
+    let answered = answered.lock().unwrap();
+    answered.iter().position(|p| *p == "otp").unwrap()
+}
+
+#[tokio::test]
+async fn one_producer_cannot_take_the_whole_window() {
+    let (_server, client) = bound_client(Some(2)).await;
+    assert!(place_of_otp_answer(Arc::clone(&client)).await <= 2);
+    assert_eq!(client.producer_in_flight("bulk"), 0);
+}
+
+#[tokio::test]
+async fn without_a_producer_window_producers_share_the_window() {
+    let (_server, client) = bound_client(None).await;
+    assert_eq!(place_of_otp_answer(client).await, 6);
+}
@@ -0,0 +1,100 @@
+use async_trait::async_trait;
+use smpp::client::{BindMode, Client, ClientError, ClientState};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+/// Accepts each submit_sm after a while
+struct SlowLogic {
+    delay: Duration,
+}
+
+#[async_trait]
+impl SmscLogic for SlowLogic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        sleep(self.delay).await;
+        let msgid = pdu.destination_addr();
+        Ok((
+            SubmitSmRespPdu::new(&msgid).unwrap(),
+            MessageUniqueKey::new(String::from("esme1"), msgid.clone(), msgid),
+        ))
+    }
+}
+
+async fn bound_client(delay: Duration) -> (TestServer, Arc<Client>) {
+    let server = TestServer::start_with_logic(SlowLogic { delay })
+        .await
+        .unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transmitter, "esme1", "", "")
+        .await
+        .unwrap();
+    (server, Arc::new(client))
+}
+
+#[tokio::test]
+async fn quiescing_waits_for_submits_in_flight_then_unbinds() {
+    let (_server, client) = bound_client(Duration::from_millis(200)).await;
+    let in_flight = {
+        let client = Arc::clone(&client);
+        tokio::spawn(async move {
+            client.submit_text("+447700900123", b"hello").await
+        })
+    };
+    sleep(Duration::from_millis(50)).await;
+
+    let quiesced = {
+        let client = Arc::clone(&client);
+        tokio::spawn(
+            async move { client.quiesce(Duration::from_secs(5)).await },
+        )
+    };
+    sleep(Duration::from_millis(10)).await;
+    assert!(matches!(
+        client.submit_text("+447700900456", b"too late").await,
+        Err(ClientError::Quiescing)
+    ));
+
+    quiesced.await.unwrap().unwrap();
+    let resp = in_flight.await.unwrap().unwrap();
+    assert_eq!(resp.message_id.as_deref(), Some("447700900123"));
+    assert_eq!(client.state(), ClientState::Closed);
+}
+
+#[tokio::test]
+async fn quiescing_unbinds_anyway_after_the_drain_timeout() {
+    let (_server, client) = bound_client(Duration::from_millis(500)).await;
+    let in_flight = {
+        let client = Arc::clone(&client);
+        tokio::spawn(async move {
+            client.submit_text("+447700900123", b"hello").await
+        })
+    };
+    sleep(Duration::from_millis(50)).await;
+
+    assert!(matches!(
+        client.quiesce(Duration::from_millis(50)).await,
+        Err(ClientError::Timeout)
+    ));
+    // The SMSC answered it before our unbind
+    assert!(in_flight.await.unwrap().is_ok());
+    assert_eq!(client.state(), ClientState::Closed);
+}
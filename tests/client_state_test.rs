@@ -0,0 +1,100 @@
+use async_trait::async_trait;
+use smpp::client::{BindMode, Client, ClientState};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
+use smpp::submit_sm_builder::SubmitSmBuilder;
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+mod test_utils;
+
+use test_utils::{DefaultLogic, TestServer};
+
+/// Takes longer to accept a submit_sm than the client will wait
+struct SlowLogic {}
+
+#[async_trait]
+impl SmscLogic for SlowLogic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        _pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        sleep(Duration::from_millis(300)).await;
+        Err(SubmitSmError::InternalError)
+    }
+}
+
+#[tokio::test]
+async fn binding_and_unbinding_are_published() {
+    let server = TestServer::start_with_logic(DefaultLogic {}).await.unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    let mut state_changes = client.state_changes();
+    assert_eq!(state_changes.borrow().state, ClientState::Connecting);
+
+    client
+        .bind(BindMode::Transmitter, "esme1", "", "")
+        .await
+        .unwrap();
+    state_changes.changed().await.unwrap();
+    assert_eq!(state_changes.borrow().state, ClientState::Bound);
+    assert_eq!(state_changes.borrow().cause, None);
+
+    client.unbind().await.unwrap();
+    state_changes.changed().await.unwrap();
+    let change = state_changes.borrow().clone();
+    assert_eq!(change.state, ClientState::Closed);
+    assert_eq!(change.cause.as_deref(), Some("Unbound"));
+    assert_eq!(client.state(), ClientState::Closed);
+}
+
+#[tokio::test]
+async fn the_smsc_closing_the_connection_is_published() {
+    let server = TestServer::start_with_logic(DefaultLogic {}).await.unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transmitter, "esme1", "", "")
+        .await
+        .unwrap();
+    let mut state_changes = client.state_changes();
+
+    server.smsc.lock().await.kick("esme1");
+
+    state_changes.changed().await.unwrap();
+    let change = state_changes.borrow().clone();
+    assert_eq!(change.state, ClientState::Closed);
+    assert_eq!(change.cause.as_deref(), Some("Closed by the SMSC"));
+}
+
+#[tokio::test]
+async fn a_client_is_degraded_until_the_smsc_answers_in_time() {
+    let server = TestServer::start_with_logic(SlowLogic {}).await.unwrap();
+    let mut client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transmitter, "esme1", "", "")
+        .await
+        .unwrap();
+
+    client.set_response_timeout(Duration::from_millis(50));
+    let submit_sm = SubmitSmBuilder::new()
+        .destination("+447700900123")
+        .short_message(b"hello")
+        .build()
+        .unwrap();
+    assert!(client.submit_sm(submit_sm).await.is_err());
+    let change = client.state_changes().borrow().clone();
+    assert_eq!(change.state, ClientState::Degraded);
+    assert_eq!(change.cause.as_deref(), Some("No response from SMSC"));
+
+    client.set_response_timeout(Duration::from_secs(5));
+    client.enquire_link().await.unwrap();
+    assert_eq!(client.state(), ClientState::Bound);
+}
@@ -0,0 +1,120 @@
+use smpp::client::{Client, ClientError, ClientState, StrayResponsePolicy};
+use std::convert::TryInto;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::time::{sleep, timeout};
+
+/// An SMSC that answers each request with `times` submit_sm_resps, after
+/// delay
+async fn smsc_answering(times: usize, delay: Duration) -> SocketAddr {
+    let smsc = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let smsc_address = smsc.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut stream, _) = smsc.accept().await.unwrap();
+        loop {
+            let mut header = [0; 16];
+            if stream.read_exact(&mut header).await.is_err() {
+                return;
+            }
+            let command_length =
+                u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+            let mut body = vec![0; command_length - 16];
+            stream.read_exact(&mut body).await.unwrap();
+            sleep(delay).await;
+            let mut resp = Vec::from(
+                &b"\x00\x00\x00\x14\x80\x00\x00\x04\x00\x00\x00\x00"[..],
+            );
+            resp.extend(&header[12..]);
+            resp.extend(b"abc\x00");
+            for _ in 0..times {
+                if stream.write_all(&resp).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    smsc_address
+}
+
+async fn client_with(
+    address: SocketAddr,
+    policy: StrayResponsePolicy,
+) -> Client {
+    let client = Client::connect(address).await.unwrap();
+    client.set_stray_response_policy(policy);
+    client
+}
+
+/// Wait until health() satisfies counted
+async fn counted(client: &Client, counted: impl Fn(usize, usize) -> bool) {
+    timeout(Duration::from_secs(5), async {
+        loop {
+            let health = client.health();
+            if counted(health.late_responses, health.duplicate_responses) {
+                return;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn stray_responses_are_logged_by_default() {
+    let client = Client::connect(smsc_answering(2, Duration::ZERO).await)
+        .await
+        .unwrap();
+    assert_eq!(client.stray_response_policy(), StrayResponsePolicy::Log);
+
+    client.submit_text("447700900123", b"hello").await.unwrap();
+
+    counted(&client, |late, duplicate| late == 0 && duplicate == 1).await;
+    assert_eq!(client.state(), ClientState::Connecting);
+}
+
+#[tokio::test]
+async fn ignored_stray_responses_are_still_counted() {
+    let address = smsc_answering(3, Duration::ZERO).await;
+    let client = client_with(address, StrayResponsePolicy::Ignore).await;
+
+    client.submit_text("447700900123", b"hello").await.unwrap();
+
+    counted(&client, |late, duplicate| late == 0 && duplicate == 2).await;
+    assert_eq!(client.state(), ClientState::Connecting);
+}
+
+#[tokio::test]
+async fn a_response_after_the_request_timed_out_counts_as_late() {
+    let address = smsc_answering(1, Duration::from_millis(200)).await;
+    let mut client = client_with(address, StrayResponsePolicy::Log).await;
+    client.set_response_timeout(Duration::from_millis(50));
+
+    let result = client.submit_text("447700900123", b"hello").await;
+    assert!(matches!(result, Err(ClientError::Timeout)), "{:?}", result);
+
+    counted(&client, |late, duplicate| late == 1 && duplicate == 0).await;
+}
+
+#[tokio::test]
+async fn a_stray_response_can_close_the_session() {
+    let address = smsc_answering(2, Duration::ZERO).await;
+    let client = client_with(address, StrayResponsePolicy::Error).await;
+    let mut state_changes = client.state_changes();
+
+    let resp = client.submit_text("447700900123", b"hello").await.unwrap();
+
+    state_changes.changed().await.unwrap();
+    let change = state_changes.borrow().clone();
+    assert_eq!(change.state, ClientState::Closed);
+    assert_eq!(
+        change.cause,
+        Some(format!(
+            "Duplicate response to sequence_number {}",
+            resp.sequence_number
+        ))
+    );
+    assert_eq!(client.health().duplicate_responses, 1);
+}
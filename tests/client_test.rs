@@ -0,0 +1,375 @@
+use async_trait::async_trait;
+use smpp::client::{BindMode, Client, ClientError, Metadata};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{
+    DeliverEsmClass, DeliverSmPdu, Pdu, SubmitSmPdu, SubmitSmRespPdu,
+};
+use std::convert::TryInto;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use tokio::time::timeout;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+struct Logic {}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, bind_data: &BindData) -> Result<(), BindError> {
+        if bind_data.password.value == "secret" {
+            Ok(())
+        } else {
+            Err(BindError::IncorrectPassword)
+        }
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        // Use the destination as the message ID, so each response can be
+        // matched to its request
+        let msgid = pdu.destination_addr();
+        Ok((
+            SubmitSmRespPdu::new(&msgid).unwrap(),
+            MessageUniqueKey::new(
+                String::from("testsystem"),
+                msgid.clone(),
+                msgid,
+            ),
+        ))
+    }
+}
+
+fn submit_sm(destination_addr: &str) -> SubmitSmPdu {
+    SubmitSmPdu::new(
+        "",
+        5,
+        0,
+        "MyCompany",
+        1,
+        1,
+        destination_addr,
+        0,
+        0,
+        0,
+        "",
+        "",
+        1,
+        0,
+        0,
+        0,
+        b"hello",
+        Tlvs::new(),
+    )
+    .unwrap()
+}
+
+async fn bound_client(server: &TestServer, mode: BindMode) -> Client {
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client.bind(mode, "esme1", "secret", "").await.unwrap();
+    client
+}
+
+#[tokio::test]
+async fn a_client_can_bind_submit_and_unbind() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = bound_client(&server, BindMode::Transmitter).await;
+
+    let resp = client.submit_sm(submit_sm("447700900123")).await.unwrap();
+    assert_eq!(resp.command_status, 0);
+    assert_eq!(resp.message_id.as_deref(), Some("447700900123"));
+
+    client.enquire_link().await.unwrap();
+    client.unbind().await.unwrap();
+}
+
+#[tokio::test]
+async fn a_rejected_bind_is_an_error_with_its_status() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+
+    let e = client
+        .bind(BindMode::Receiver, "esme1", "guess", "")
+        .await
+        .unwrap_err();
+
+    assert!(matches!(e, ClientError::Status(0x0000000E)), "{:?}", e);
+    assert_eq!(
+        e.to_string(),
+        "SMSC responded with ESME_RINVPASWD (0x0000000E): Invalid Password"
+    );
+}
+
+#[tokio::test]
+async fn concurrent_submits_each_get_their_own_response() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = bound_client(&server, BindMode::Transceiver).await;
+
+    let destinations: Vec<String> =
+        (0..10).map(|i| format!("4477009001{:02}", i)).collect();
+    let responses = futures::future::join_all(
+        destinations.iter().map(|d| client.submit_sm(submit_sm(d))),
+    )
+    .await;
+
+    let mut sequence_numbers = Vec::new();
+    for (destination, resp) in destinations.iter().zip(responses) {
+        let resp = resp.unwrap();
+        assert_eq!(resp.message_id.as_ref(), Some(destination));
+        sequence_numbers.push(resp.sequence_number);
+    }
+    sequence_numbers.sort_unstable();
+    sequence_numbers.dedup();
+    assert_eq!(sequence_numbers.len(), 10);
+}
+
+#[tokio::test]
+async fn delivery_receipts_are_acknowledged_and_handed_over() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = bound_client(&server, BindMode::Transceiver).await;
+    client.submit_sm(submit_sm("447700900123")).await.unwrap();
+
+    let receipt = DeliverSmPdu::new(
+        "",
+        1,
+        1,
+        "447700900123",
+        5,
+        0,
+        "MyCompany",
+        DeliverEsmClass::SmscDeliveryReceipt as u8,
+        0,
+        0,
+        "",
+        "",
+        0,
+        0,
+        0,
+        0,
+        b"id:447700900123 stat:DELIVRD err:000",
+        Tlvs::new(),
+    )
+    .unwrap();
+    server
+        .receive_pdu("testsystem", Pdu::new(0, 9, receipt.into()).unwrap())
+        .await
+        .unwrap();
+
+    let receipt = client.next_receipt().await.unwrap();
+    assert_eq!(receipt.message_id.as_deref(), Some("447700900123"));
+    assert_eq!(receipt.stat.as_deref(), Some("DELIVRD"));
+    assert_eq!(
+        receipt.pdu.0.short_message.value,
+        b"id:447700900123 stat:DELIVRD err:000"
+    );
+    client.enquire_link().await.unwrap();
+    // Our deliver_sm_resp counts as answering the deliver_sm
+    assert_eq!(client.connection().stats().pending_responses(), 0);
+}
+
+#[tokio::test]
+async fn metadata_comes_back_with_the_response_and_the_receipt() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = bound_client(&server, BindMode::Transceiver).await;
+    let mut metadata = Metadata::new();
+    metadata.insert(String::from("campaign"), String::from("spring"));
+    metadata.insert(String::from("tenant"), String::from("acme"));
+
+    let resp = client
+        .submit_sm_with_metadata(submit_sm("447700900123"), metadata.clone())
+        .await
+        .unwrap();
+    assert_eq!(resp.metadata, metadata);
+    let resp = client.submit_sm(submit_sm("447700900124")).await.unwrap();
+    assert!(resp.metadata.is_empty());
+
+    for destination_addr in ["447700900124", "447700900123"] {
+        let receipt = DeliverSmPdu::new(
+            "",
+            1,
+            1,
+            destination_addr,
+            5,
+            0,
+            "MyCompany",
+            DeliverEsmClass::SmscDeliveryReceipt as u8,
+            0,
+            0,
+            "",
+            "",
+            0,
+            0,
+            0,
+            0,
+            format!("id:{} stat:DELIVRD err:000", destination_addr).as_bytes(),
+            Tlvs::new(),
+        )
+        .unwrap();
+        server
+            .receive_pdu("testsystem", Pdu::new(0, 9, receipt.into()).unwrap())
+            .await
+            .unwrap();
+    }
+
+    let receipt = client.next_receipt().await.unwrap();
+    assert_eq!(receipt.message_id.as_deref(), Some("447700900124"));
+    assert!(receipt.metadata.is_empty());
+    let receipt = client.next_receipt().await.unwrap();
+    assert_eq!(receipt.message_id.as_deref(), Some("447700900123"));
+    assert_eq!(receipt.metadata, metadata);
+}
+
+#[tokio::test]
+async fn metadata_reaches_a_receipt_sent_straight_after_the_response() {
+    let smsc = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let smsc_address = smsc.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut stream, _) = smsc.accept().await.unwrap();
+        let mut header = [0; 16];
+        stream.read_exact(&mut header).await.unwrap();
+        let command_length =
+            u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+        let mut body = vec![0; command_length - 16];
+        stream.read_exact(&mut body).await.unwrap();
+        // The submit_sm_resp and the receipt in one write, so that the
+        // client reads both before the submitter wakes
+        let mut bytes =
+            Vec::from(&b"\x00\x00\x00\x14\x80\x00\x00\x04\x00\x00\x00\x00"[..]);
+        bytes.extend(&header[12..]);
+        bytes.extend(b"abc\x00");
+        let receipt = DeliverSmPdu::new(
+            "",
+            1,
+            1,
+            "447700900123",
+            5,
+            0,
+            "MyCompany",
+            DeliverEsmClass::SmscDeliveryReceipt as u8,
+            0,
+            0,
+            "",
+            "",
+            0,
+            0,
+            0,
+            0,
+            b"id:abc stat:DELIVRD err:000",
+            Tlvs::new(),
+        )
+        .unwrap();
+        Pdu::new(0, 1, receipt.into())
+            .unwrap()
+            .write(&mut bytes)
+            .await
+            .unwrap();
+        stream.write_all(&bytes).await.unwrap();
+        // Stay connected
+        let _ = stream.read(&mut header).await;
+    });
+    let client = Client::connect(smsc_address).await.unwrap();
+    let mut metadata = Metadata::new();
+    metadata.insert(String::from("campaign"), String::from("spring"));
+
+    client
+        .submit_sm_with_metadata(submit_sm("447700900123"), metadata.clone())
+        .await
+        .unwrap();
+
+    let receipt = client.next_receipt().await.unwrap();
+    assert_eq!(receipt.message_id.as_deref(), Some("abc"));
+    assert_eq!(receipt.metadata, metadata);
+}
+
+#[tokio::test]
+async fn deliveries_are_split_by_esm_class() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = bound_client(&server, BindMode::Transceiver).await;
+    client.submit_sm(submit_sm("447700900123")).await.unwrap();
+
+    for (esm_class, text) in [
+        // Smsc::receive_pdu() can only route by id:, even for MOs
+        (0x00, &b"id:447700900123 hello"[..]),
+        (0x20, b"id:447700900123 stat:ENROUTE"),
+        (0x04, b"id:447700900123 stat:DELIVRD"),
+    ] {
+        let body = DeliverSmPdu::new(
+            "",
+            1,
+            1,
+            "447700900123",
+            5,
+            0,
+            "MyCompany",
+            esm_class,
+            0,
+            0,
+            "",
+            "",
+            0,
+            0,
+            0,
+            0,
+            text,
+            Tlvs::new(),
+        )
+        .unwrap();
+        server
+            .receive_pdu("testsystem", Pdu::new(0, 9, body.into()).unwrap())
+            .await
+            .unwrap();
+    }
+
+    let message = client.next_message().await.unwrap();
+    assert_eq!(message.0.short_message.value, b"id:447700900123 hello");
+    let notification = client.next_notification().await.unwrap();
+    assert_eq!(
+        notification.0.short_message.value,
+        b"id:447700900123 stat:ENROUTE"
+    );
+    let receipt = client.next_receipt().await.unwrap();
+    assert_eq!(receipt.stat.as_deref(), Some("DELIVRD"));
+}
+
+#[tokio::test]
+async fn a_response_that_cannot_be_parsed_fails_its_request() {
+    let smsc = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let smsc_address = smsc.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut stream, _) = smsc.accept().await.unwrap();
+        let mut header = [0; 16];
+        stream.read_exact(&mut header).await.unwrap();
+        let command_length =
+            u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+        let mut body = vec![0; command_length - 16];
+        stream.read_exact(&mut body).await.unwrap();
+        // A submit_sm_resp with both ESME_RTHROTTLED and a message_id
+        let mut resp =
+            Vec::from(&b"\x00\x00\x00\x14\x80\x00\x00\x04\x00\x00\x00\x58"[..]);
+        resp.extend(&header[12..]);
+        resp.extend(b"abc\x00");
+        stream.write_all(&resp).await.unwrap();
+        // Stay connected
+        let _ = stream.read(&mut header).await;
+    });
+    let client = Client::connect(smsc_address).await.unwrap();
+
+    let result = timeout(
+        Duration::from_secs(5),
+        client.submit_text("+447700900123", b"hello"),
+    )
+    .await
+    .unwrap();
+
+    assert!(matches!(result, Err(ClientError::Pdu(_))), "{:?}", result);
+}
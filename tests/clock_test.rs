@@ -0,0 +1,57 @@
+use smpp::clock::{Clock, ManualClock, TokioClock};
+use smpp::dlr_batch::{DlrBatches, DlrOutcome};
+use smpp::session_stats::SessionStats;
+use smpp_pdu::pdu::{EnquireLinkPdu, EnquireLinkRespPdu, Pdu};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn manual_clock_only_moves_when_advanced() {
+    let clock = ManualClock::new();
+    let start = clock.now();
+    std::thread::sleep(Duration::from_millis(5));
+    assert_eq!(clock.now(), start);
+
+    clock.advance(Duration::from_secs(30));
+    assert_eq!(clock.now() - start, Duration::from_secs(30));
+}
+
+#[tokio::test(start_paused = true)]
+async fn tokio_clock_follows_paused_time() {
+    let start = TokioClock.now();
+    tokio::time::advance(Duration::from_secs(90)).await;
+    assert_eq!(TokioClock.now() - start, Duration::from_secs(90));
+}
+
+#[test]
+fn dlr_batches_time_out_by_their_clock() {
+    let clock = Arc::new(ManualClock::new());
+    let mut batches =
+        DlrBatches::with_clock(Duration::from_secs(60), clock.clone());
+    batches.start("campaign", 2);
+    batches.record(&"campaign", DlrOutcome::Delivered);
+
+    clock.advance(Duration::from_secs(59));
+    assert!(batches.expire(clock.now()).is_empty());
+
+    clock.advance(Duration::from_secs(1));
+    let expired = batches.expire(clock.now());
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].delivered, 1);
+    assert_eq!(expired[0].missing, 1);
+    assert!(expired[0].timed_out);
+}
+
+#[tokio::test(start_paused = true)]
+async fn enquire_link_round_trips_are_measured_in_tokio_time() {
+    let mut stats = SessionStats::new();
+    stats.record_sent(&Pdu::new(0, 7, EnquireLinkPdu::new().into()).unwrap());
+    tokio::time::advance(Duration::from_millis(250)).await;
+    stats.record_received(
+        &Pdu::new(0, 7, EnquireLinkRespPdu::new().into()).unwrap(),
+    );
+    assert_eq!(
+        stats.last_enquire_link_rtt,
+        Some(Duration::from_millis(250))
+    );
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "codec")]
+
+use futures::{SinkExt, StreamExt};
+use smpp::codec::{SmppCodec, SmppCodecError};
+use smpp::smpp_connection::Frame;
+use smpp::unbind::UnbindPdu;
+use smpp_pdu::pdu::{BindTransmitterPdu, Pdu, PduBody};
+use tokio::io::duplex;
+use tokio_util::codec::{Decoder, Framed};
+
+const ENQUIRE_LINK: &[u8] = b"\x00\x00\x00\x10\x00\x00\x00\x15\
+    \x00\x00\x00\x00\x00\x00\x00\x07";
+
+#[test]
+fn a_partial_pdu_waits_for_the_rest() {
+    let mut codec = SmppCodec::new();
+    let mut buf = bytes::BytesMut::from(&ENQUIRE_LINK[..10]);
+
+    assert!(codec.decode(&mut buf).unwrap().is_none());
+    buf.extend_from_slice(&ENQUIRE_LINK[10..]);
+    buf.extend_from_slice(&ENQUIRE_LINK[..3]);
+
+    let frame = codec.decode(&mut buf).unwrap().unwrap();
+    assert!(matches!(
+        frame,
+        Frame::Pdu(pdu) if pdu.sequence_number.value == 7
+    ));
+    assert_eq!(&buf[..], &ENQUIRE_LINK[..3]);
+}
+
+#[tokio::test]
+async fn pdus_sent_through_framed_arrive_as_frames() {
+    let (a, b) = duplex(1024);
+    let mut a = Framed::new(a, SmppCodec::new());
+    let mut b = Framed::new(b, SmppCodec::new());
+    let bind =
+        BindTransmitterPdu::new("esme1", "secret", "", 0x34, 0, 0, "").unwrap();
+
+    a.send(Pdu::new(0, 1, bind.into()).unwrap()).await.unwrap();
+    a.send(Frame::Unbind(UnbindPdu::new(2))).await.unwrap();
+
+    match b.next().await.unwrap().unwrap() {
+        Frame::Pdu(pdu) => match pdu.body() {
+            PduBody::BindTransmitter(body) => {
+                assert_eq!(body.0.system_id.value, "esme1")
+            }
+            body => panic!("Unexpected body {:?}", body),
+        },
+        frame => panic!("Unexpected frame {:?}", frame),
+    }
+    assert!(matches!(
+        b.next().await.unwrap().unwrap(),
+        Frame::Unbind(unbind) if unbind.sequence_number == 2
+    ));
+}
+
+#[test]
+fn decoding_carries_on_after_a_bad_pdu() {
+    let mut codec = SmppCodec::new();
+    // submit_sm whose body is too short, then an enquire_link
+    let mut buf = bytes::BytesMut::from(
+        &b"\x00\x00\x00\x11\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x01\x00"[..],
+    );
+    buf.extend_from_slice(ENQUIRE_LINK);
+
+    let e = codec.decode(&mut buf).unwrap_err();
+    assert!(matches!(e, SmppCodecError::Pdu(_)), "{:?}", e);
+
+    assert!(matches!(
+        codec.decode(&mut buf).unwrap().unwrap(),
+        Frame::Pdu(pdu) if pdu.sequence_number.value == 7
+    ));
+}
+
+#[test]
+fn an_oversized_frame_is_skipped_without_being_buffered() {
+    let mut codec = SmppCodec::with_max_frame_length(100);
+    let mut buf =
+        bytes::BytesMut::from(&b"\x00\x00\x03\xE8\x00\x00\x00\x04"[..]);
+
+    let e = codec.decode(&mut buf).unwrap_err();
+    assert!(matches!(e, SmppCodecError::Pdu(_)), "{:?}", e);
+    assert!(buf.is_empty());
+
+    buf.extend_from_slice(&[0; 500]);
+    assert!(codec.decode(&mut buf).unwrap().is_none());
+    assert!(buf.is_empty());
+
+    buf.extend_from_slice(&[0; 492]);
+    buf.extend_from_slice(ENQUIRE_LINK);
+    assert!(matches!(
+        codec.decode(&mut buf).unwrap().unwrap(),
+        Frame::Pdu(pdu) if pdu.sequence_number.value == 7
+    ));
+}
@@ -0,0 +1,36 @@
+use smpp::client::ClientError;
+use smpp::command_id::{CommandId, CommandName};
+use smpp::submit_multi::SUBMIT_MULTI_RESP;
+use std::convert::TryFrom;
+
+#[test]
+fn command_ids_convert_to_and_from_u32() {
+    assert_eq!(CommandId::try_from(0x00000004), Ok(CommandId::SubmitSm));
+    assert_eq!(
+        CommandId::try_from(SUBMIT_MULTI_RESP),
+        Ok(CommandId::SubmitMultiResp)
+    );
+    assert_eq!(u32::from(CommandId::DataSmResp), 0x80000103);
+    assert_eq!(CommandId::try_from(0x00000022), Err(0x00000022));
+}
+
+#[test]
+fn responses_have_the_response_bit_set() {
+    assert!(CommandId::GenericNack.is_response());
+    assert!(CommandId::EnquireLinkResp.is_response());
+    assert!(!CommandId::Outbind.is_response());
+}
+
+#[test]
+fn command_ids_are_displayed_by_name() {
+    assert_eq!(CommandId::SubmitSmResp.to_string(), "submit_sm_resp");
+    assert_eq!(
+        CommandName(0x80000004).to_string(),
+        "submit_sm_resp (0x80000004)"
+    );
+    assert_eq!(CommandName(0x00000022).to_string(), "0x00000022");
+    assert_eq!(
+        ClientError::UnexpectedResponse(CommandId::DeliverSmResp).to_string(),
+        "SMSC responded with unexpected deliver_sm_resp (0x80000005)"
+    );
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "compression")]
+
+use smpp::client::{BindMode, Client};
+use smpp::compression::compressed;
+use smpp::smpp_connection::SmppConnection;
+use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+mod test_utils;
+
+use test_utils::{DefaultLogic, TestServer};
+
+const RECEIPT: &[u8] =
+    b"id:0123456789 sub:001 dlvrd:001 submit date:2103301649 \
+    done date:2103301650 stat:DELIVRD err:000 text:Hello";
+
+#[tokio::test]
+async fn bytes_written_to_one_end_come_out_of_the_other() {
+    let (a, b) = duplex(1024);
+    let mut a = compressed(a);
+    let mut b = compressed(b);
+    let sent: Vec<u8> = (0..200u32).flat_map(|i| i.to_be_bytes()).collect();
+
+    a.write_all(&sent).await.unwrap();
+    let mut received = vec![0; sent.len()];
+    b.read_exact(&mut received).await.unwrap();
+
+    assert_eq!(received, sent);
+}
+
+#[tokio::test]
+async fn repetitive_traffic_is_sent_smaller() {
+    let (ours, mut raw) = duplex(64 * 1024);
+    let mut ours = compressed(ours);
+    let sent = RECEIPT.repeat(50);
+
+    ours.write_all(&sent).await.unwrap();
+    ours.shutdown().await.unwrap();
+    let mut on_the_wire = Vec::new();
+    raw.read_to_end(&mut on_the_wire).await.unwrap();
+
+    assert!(
+        on_the_wire.len() < sent.len() / 10,
+        "{} bytes compressed to {}",
+        sent.len(),
+        on_the_wire.len()
+    );
+}
+
+#[tokio::test]
+async fn frames_compressed_by_other_zlib_implementations_are_understood() {
+    // Python's zlib.compress(bytes(reversed(RECEIPT)) + RECEIPT, 9), which
+    // uses dynamic Huffman codes
+    const ZLIB: &[u8] = b"\
+        \x78\xda\x55\x8e\xbd\x0a\x84\x30\x10\x84\x5f\x65\x1f\x61\xa3\xf1\
+        \x27\x5b\x47\x38\xc1\xca\xc2\x5e\xd9\x2d\x02\x51\x21\xae\xe2\xe3\
+        \x5f\xce\xab\xec\x86\x6f\x98\x8f\xd9\x63\x94\x0f\xe9\x2d\x0a\x88\
+        \x48\x29\x09\xf8\x71\xea\x87\xce\x93\xce\x7a\x00\x56\xb5\xc1\xb2\
+        \x44\x53\x90\xe8\xcc\x20\xdb\xce\xe0\xec\x1b\x6a\x58\x97\xf3\x00\
+        \x93\x05\x9c\xae\xc8\x4f\xfa\x11\xd7\x36\x75\x65\xcb\xc2\xe4\x22\
+        \x04\xa6\xbc\x28\x6d\x55\x37\xad\x83\xe3\x5c\x08\xd1\x00\xc7\x2b\
+        \xf1\x93\x32\x59\x83\x02\xcf\x2a\x54\xfc\xfd\xb5\x75\xc0\xfb\x26\
+        \x6f\x58\x21\x1c\xf9\x1c\xf9\x6e\xe8\xa7\xd1\x83\xa4\x94\x05\x08\
+        \x2a\xb7\xd2\x47\x62\xdc\xbf\x1a\x7a\x3e\x25";
+    let (ours, mut raw) = duplex(1024);
+    let mut ours = compressed(ours);
+
+    raw.write_u32(ZLIB.len() as u32).await.unwrap();
+    raw.write_all(ZLIB).await.unwrap();
+
+    let mut expected: Vec<u8> = RECEIPT.iter().rev().copied().collect();
+    expected.extend(RECEIPT);
+    let mut received = vec![0; expected.len()];
+    ours.read_exact(&mut received).await.unwrap();
+    assert_eq!(received, expected);
+}
+
+#[tokio::test]
+async fn a_corrupt_frame_closes_the_stream() {
+    let (ours, mut raw) = duplex(1024);
+    let mut ours = compressed(ours);
+
+    raw.write_all(b"\x00\x00\x00\x08\x78\x01garbage")
+        .await
+        .unwrap();
+
+    let mut received = Vec::new();
+    ours.read_to_end(&mut received).await.unwrap();
+    assert!(received.is_empty());
+}
+
+#[tokio::test]
+async fn an_smsc_with_compress_serves_compressing_clients() {
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.compress = true
+    })
+    .await
+    .unwrap();
+    let stream = TcpStream::connect(&server.bind_address).await.unwrap();
+    let peer_addr = stream.peer_addr().unwrap();
+    let client = Client::from_connection(SmppConnection::from_stream(
+        compressed(stream),
+        peer_addr.into(),
+    ));
+
+    client
+        .bind(BindMode::Transceiver, "esme1", "secret", "")
+        .await
+        .unwrap();
+    client.enquire_link().await.unwrap();
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "conformance")]
+
+use smpp::conformance::{run, ConformanceTarget};
+use std::time::Duration;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+#[tokio::test]
+async fn our_smsc_is_checked_against_the_spec() {
+    let server = TestServer::start().await.unwrap();
+    let mut target =
+        ConformanceTarget::new(&server.bind_address, "esmeid", "password");
+    target.timeout = Duration::from_secs(2);
+
+    let report = run(&target).await;
+    let failures: Vec<&str> =
+        report.failures().iter().map(|check| check.name).collect();
+    // We reject submit_sm before bind, but with ESME_RINVCMDID.
+    assert_eq!(failures, vec!["submit_sm_when_open"]);
+    assert!(!report.passed());
+    assert_eq!(report.checks.len(), 6);
+}
+
+#[tokio::test]
+async fn report_is_written_as_json() {
+    let server = TestServer::start().await.unwrap();
+    let target =
+        ConformanceTarget::new(&server.bind_address, "esmeid", "password");
+    let json = run(&target).await.to_json();
+
+    assert!(json.starts_with(
+        r#"{"passed":false,"checks":[{"name":"bind_transmitter","#
+    ));
+    assert!(json.contains(concat!(
+        r#"{"name":"unknown_command_id","description":"an unknown "#,
+        r#"command_id is answered by generic_nack with ESME_RINVCMDID","#,
+        r#""passed":true,"detail":null}"#
+    )));
+    assert!(json.contains(r#""name":"submit_sm_when_open""#));
+    assert!(json.contains(concat!(
+        r#""passed":false,"detail":"expected ESME_RINVBNDSTS and "#,
+        r#"sequence_number 1, got command_id 0x80000000 with "#,
+        r#"ESME_RINVCMDID and sequence_number 1"}"#
+    )));
+}
+
+#[tokio::test]
+async fn unreachable_smscs_fail_every_check() {
+    let target = ConformanceTarget::new("127.0.0.1:1", "esmeid", "password");
+    let report = run(&target).await;
+    assert_eq!(report.failures().len(), report.checks.len());
+}
@@ -0,0 +1,136 @@
+use async_trait::async_trait;
+use smpp::client::{BindMode, Client};
+use smpp::data_sm::{DataSmPdu, DataSmRespPdu};
+use smpp::message_payload::MessageBytes;
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smpp_connection::Frame;
+use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+/// Answers each submit_sm with its message as the message_id
+struct Logic {}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        let message = pdu.message_bytes().unwrap().to_vec();
+        let msgid = String::from_utf8(message).unwrap();
+        Ok((
+            SubmitSmRespPdu::new(&msgid).unwrap(),
+            MessageUniqueKey::new(
+                String::from("testsystem"),
+                msgid,
+                pdu.destination_addr(),
+            ),
+        ))
+    }
+}
+
+async fn written(frame: Frame) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    frame.write(&mut bytes).await.unwrap();
+    bytes
+}
+
+#[tokio::test]
+async fn data_sm_is_written_and_parsed_with_its_message_payload() {
+    let data_sm =
+        DataSmPdu::new(7, "MyCompany", "447700900123", 8, b"\x00h\x00i")
+            .unwrap();
+
+    let bytes = written(Frame::DataSm(data_sm)).await;
+
+    assert_eq!(
+        &bytes[4..16],
+        b"\x00\x00\x01\x03\x00\x00\x00\x00\x00\x00\x00\x07"
+    );
+    let parsed = match Frame::parse(&bytes).unwrap() {
+        Frame::DataSm(data_sm) => data_sm,
+        frame => panic!("Unexpected frame {:?}", frame),
+    };
+    assert_eq!(parsed.source_addr.value, "MyCompany");
+    assert_eq!(parsed.destination_addr.value, "447700900123");
+    assert_eq!(parsed.data_coding, 8);
+    assert_eq!(parsed.message_payload().unwrap(), b"\x00h\x00i");
+}
+
+#[tokio::test]
+async fn data_sm_resp_is_written_and_parsed() {
+    let resp = DataSmRespPdu::new(7, "abc").unwrap();
+
+    let bytes = written(Frame::DataSmResp(resp)).await;
+
+    assert_eq!(
+        bytes,
+        b"\x00\x00\x00\x14\x80\x00\x01\x03\x00\x00\x00\x00\x00\x00\x00\x07abc\x00"
+    );
+    assert_eq!(
+        DataSmRespPdu::parse(&bytes).unwrap(),
+        DataSmRespPdu::new(7, "abc").unwrap()
+    );
+}
+
+#[test]
+fn a_rejection_without_a_body_is_understood() {
+    let resp = DataSmRespPdu::parse(
+        b"\x00\x00\x00\x10\x80\x00\x01\x03\x00\x00\x00\x58\x00\x00\x00\x07",
+    )
+    .unwrap();
+
+    assert_eq!(resp, DataSmRespPdu::new_error(0x58, 7));
+}
+
+#[tokio::test]
+async fn the_smsc_answers_data_sm_as_the_equivalent_submit_sm() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transmitter, "esme1", "secret", "")
+        .await
+        .unwrap();
+
+    let resp = client
+        .data_sm(
+            DataSmPdu::new(0, "MyCompany", "447700900123", 0, b"hello")
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(resp.command_status, 0);
+    assert_eq!(resp.message_id.value, "hello");
+}
+
+#[tokio::test]
+async fn data_sm_without_message_payload_is_rejected() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transmitter, "esme1", "secret", "")
+        .await
+        .unwrap();
+    let mut data_sm =
+        DataSmPdu::new(0, "MyCompany", "447700900123", 0, b"").unwrap();
+    data_sm.tlvs = Tlvs::new();
+
+    let resp = client.data_sm(data_sm).await.unwrap();
+
+    // ESME_RMISSINGOPTPARAM
+    assert_eq!(resp.command_status, 0xC3);
+}
@@ -0,0 +1,90 @@
+use smpp::deliver_sm_resp::DeliverSmRespPdu;
+use smpp::smpp_connection::EsmeId;
+use tokio::io::AsyncWriteExt;
+
+mod test_utils;
+
+use test_utils::TestSetup;
+
+const DELIVER_SM_RESP: &[u8] =
+    b"\x00\x00\x00\x11\x80\x00\x00\x05\x00\x00\x00\x00\x00\x00\x00\x07\x00";
+
+const ENQUIRE_LINK: &[u8] =
+    b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x08";
+
+const ENQUIRE_LINK_RESP: &[u8] =
+    b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x08";
+
+#[tokio::test]
+async fn deliver_sm_resp_is_written_with_an_empty_message_id() {
+    let mut written = Vec::new();
+    DeliverSmRespPdu::new(0, 7)
+        .write(&mut written)
+        .await
+        .unwrap();
+    assert_eq!(written, DELIVER_SM_RESP);
+}
+
+#[test]
+fn deliver_sm_resp_is_parsed() {
+    assert!(DeliverSmRespPdu::is_deliver_sm_resp(DELIVER_SM_RESP));
+    assert!(!DeliverSmRespPdu::is_deliver_sm_resp(ENQUIRE_LINK));
+    assert_eq!(
+        DeliverSmRespPdu::parse(DELIVER_SM_RESP).unwrap(),
+        DeliverSmRespPdu::new(0, 7)
+    );
+}
+
+#[test]
+fn deliver_sm_resp_with_no_body_is_parsed() {
+    let resp = DeliverSmRespPdu::parse(
+        b"\x00\x00\x00\x10\x80\x00\x00\x05\x00\x00\x00\x45\x00\x00\x00\x07",
+    )
+    .unwrap();
+    assert_eq!(resp, DeliverSmRespPdu::new(0x45, 7));
+}
+
+#[test]
+fn malformed_deliver_sm_resp_is_rejected() {
+    for frame in &[
+        // Not a deliver_sm_resp
+        ENQUIRE_LINK,
+        // Shorter than its command_length
+        &b"\x00\x00\x00\x12\x80\x00\x00\x05\x00\x00\x00\x00\x00\x00\x00\x07\x00"[..],
+        // message_id not terminated
+        &b"\x00\x00\x00\x11\x80\x00\x00\x05\x00\x00\x00\x00\x00\x00\x00\x07a"[..],
+        // Bytes after message_id
+        &b"\x00\x00\x00\x12\x80\x00\x00\x05\x00\x00\x00\x00\x00\x00\x00\x07\x00\x00"[..],
+    ] {
+        assert!(DeliverSmRespPdu::parse(frame).is_err());
+    }
+}
+
+#[tokio::test]
+async fn smsc_accepts_deliver_sm_resp_without_responding() {
+    let mut t = TestSetup::new().await;
+    t.client.bind_transceiver().await;
+
+    // An ESME rejecting a deliver_sm, then a good one
+    t.client
+        .stream
+        .write_all(
+            b"\x00\x00\x00\x10\x80\x00\x00\x05\x00\x00\x00\x64\x00\x00\x00\x06",
+        )
+        .await
+        .unwrap();
+    t.client.stream.write_all(DELIVER_SM_RESP).await.unwrap();
+
+    // No generic_nack comes back before the enquire_link_resp
+    t.client
+        .send_and_expect_response(ENQUIRE_LINK, ENQUIRE_LINK_RESP)
+        .await;
+
+    let all_stats = t.server.smsc.lock().await.session_stats();
+    let stats = &all_stats[&EsmeId {
+        system_id: "esmeid".parse().unwrap(),
+        system_type: "type".parse().unwrap(),
+    }];
+    assert_eq!(stats.delivery_errors.get(&0x64), Some(&1));
+    assert_eq!(stats.delivery_errors.len(), 1);
+}
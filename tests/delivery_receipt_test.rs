@@ -0,0 +1,143 @@
+use smpp::delivery_receipt::{
+    DeliveryKind, DeliveryReceipt, NotAReceipt, ReceiptText,
+};
+use smpp::dlr_batch::DlrOutcome;
+use smpp::dlr_errors::DlrErrorMap;
+use smpp::query_sm::MessageState;
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::DeliverSmPdu;
+
+fn deliver_sm(esm_class: u8, text: &[u8], tlvs: &[Tlv]) -> DeliverSmPdu {
+    DeliverSmPdu::new(
+        "",
+        1,
+        1,
+        "447700900123",
+        5,
+        0,
+        "MyCompany",
+        esm_class,
+        0,
+        0,
+        "",
+        "",
+        0,
+        0,
+        0,
+        0,
+        text,
+        Tlvs::from(tlvs),
+    )
+    .unwrap()
+}
+
+#[test]
+fn the_kind_comes_from_the_message_type_bits_of_esm_class() {
+    assert_eq!(DeliveryKind::from_esm_class(0x00), DeliveryKind::Message);
+    assert_eq!(DeliveryKind::from_esm_class(0x04), DeliveryKind::Receipt);
+    assert_eq!(
+        DeliveryKind::from_esm_class(0x20),
+        DeliveryKind::Notification
+    );
+    // UDHI and reply path do not change the message type
+    assert_eq!(DeliveryKind::from_esm_class(0xC4), DeliveryKind::Receipt);
+    // SME delivery acknowledgement
+    assert_eq!(DeliveryKind::from_esm_class(0x08), DeliveryKind::Message);
+}
+
+#[test]
+fn receipt_text_fields_are_read() {
+    let receipt = DeliveryReceipt::new(deliver_sm(
+        0x04,
+        b"id:123 sub:001 dlvrd:000 submit date:2101020304 \
+          done date:2101020305 stat:UNDELIV err:027 text:hello",
+        &[],
+    ));
+
+    assert_eq!(receipt.message_id.as_deref(), Some("123"));
+    assert_eq!(receipt.stat.as_deref(), Some("UNDELIV"));
+    assert_eq!(receipt.err.as_deref(), Some("027"));
+    assert_eq!(receipt.submit_date.as_deref(), Some("2101020304"));
+    assert_eq!(receipt.done_date.as_deref(), Some("2101020305"));
+    assert_eq!(receipt.message_state, None);
+    assert!(matches!(
+        receipt.outcome(&DlrErrorMap::default()),
+        Some(DlrOutcome::Failed(_))
+    ));
+}
+
+#[test]
+fn receipt_tlvs_are_preferred_to_the_text() {
+    let receipt = DeliveryReceipt::new(deliver_sm(
+        0x04,
+        b"id:123 stat:DELIVRD",
+        &[
+            Tlv::new(KnownTlvTag::receipted_message_id, b"abc\0"),
+            Tlv::new(KnownTlvTag::message_state, &[2]),
+        ],
+    ));
+
+    assert_eq!(receipt.message_id.as_deref(), Some("abc"));
+    assert_eq!(receipt.message_state, Some(2));
+}
+
+#[test]
+fn receipt_text_is_parsed_and_written_back() {
+    const TEXT: &str = "id:123 sub:001 dlvrd:001 submit date:2101020304 \
+        done date:2101020305 stat:DELIVRD err:000 text:see you at 5 id:9";
+
+    let parsed = ReceiptText::parse(TEXT.as_bytes()).unwrap();
+
+    assert_eq!(
+        parsed,
+        ReceiptText {
+            id: Some(String::from("123")),
+            sub: Some(String::from("001")),
+            dlvrd: Some(String::from("001")),
+            submit_date: Some(String::from("2101020304")),
+            done_date: Some(String::from("2101020305")),
+            stat: Some(String::from("DELIVRD")),
+            err: Some(String::from("000")),
+            text: Some(String::from("see you at 5 id:9")),
+        }
+    );
+    assert_eq!(parsed.to_string(), TEXT);
+    assert_eq!(parsed.message_state(), Some(MessageState::Delivered));
+}
+
+#[test]
+fn receipt_text_fields_may_be_missing_or_out_of_order() {
+    let parsed = ReceiptText::parse(b"STAT:undeliv ID:abc").unwrap();
+
+    assert_eq!(parsed.id.as_deref(), Some("abc"));
+    assert_eq!(parsed.message_state(), Some(MessageState::Undeliverable));
+    assert_eq!(parsed.text, None);
+    assert_eq!(parsed.to_string(), "id:abc stat:undeliv");
+    assert_eq!(ReceiptText::parse(b"Hello there"), Err(NotAReceipt));
+}
+
+#[test]
+fn receipt_text_gives_the_matching_tlvs() {
+    let text = ReceiptText {
+        id: Some(String::from("abc")),
+        stat: Some(String::from("EXPIRED")),
+        ..ReceiptText::default()
+    };
+
+    let receipt = DeliveryReceipt::new(deliver_sm(
+        0x04,
+        text.to_string().as_bytes(),
+        &text.tlvs(),
+    ));
+
+    assert_eq!(
+        text.tlvs(),
+        vec![
+            Tlv::new(KnownTlvTag::receipted_message_id, b"abc\0"),
+            Tlv::new(KnownTlvTag::message_state, &[3]),
+        ]
+    );
+    assert_eq!(receipt.message_id.as_deref(), Some("abc"));
+    assert_eq!(receipt.message_state, Some(3));
+    assert_eq!(receipt.stat.as_deref(), Some("EXPIRED"));
+}
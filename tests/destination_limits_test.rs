@@ -0,0 +1,217 @@
+use async_trait::async_trait;
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smsc::{
+    BindData, BindError, DestinationLimit, DestinationThrottle, Smsc,
+    SmscLogic, SubmitSmError,
+};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::{new_submit_sm, TestClient, TestServer};
+
+fn limit(s: &str) -> DestinationLimit {
+    s.parse().unwrap()
+}
+
+#[test]
+fn destination_limits_are_written_prefix_equals_rate() {
+    assert_eq!(
+        limit("4477=50"),
+        DestinationLimit {
+            prefix: String::from("4477"),
+            per_second: 50
+        }
+    );
+    assert!("4477".parse::<DestinationLimit>().is_err());
+    assert!("4477=x".parse::<DestinationLimit>().is_err());
+    assert!("4477=0".parse::<DestinationLimit>().is_err());
+}
+
+#[test]
+fn destinations_over_their_limit_are_throttled_until_tokens_refill() {
+    let mut throttle = DestinationThrottle::new(&[limit("4477=2")]);
+    let start = Instant::now();
+
+    assert!(throttle.try_acquire_at("447700900123", start));
+    assert!(throttle.try_acquire_at("447700900456", start));
+    assert!(!throttle.try_acquire_at("447700900789", start));
+
+    // Other destinations are unaffected
+    assert!(throttle.try_acquire_at("447800900123", start));
+
+    // Half a second later, one more is allowed
+    let later = start + Duration::from_millis(500);
+    assert!(throttle.try_acquire_at("447700900789", later));
+    assert!(!throttle.try_acquire_at("447700900789", later));
+}
+
+#[test]
+fn the_longest_matching_prefix_applies() {
+    let mut throttle =
+        DestinationThrottle::new(&[limit("44=1"), limit("4477=2")]);
+    let now = Instant::now();
+
+    assert!(throttle.try_acquire_at("447700900123", now));
+    assert!(throttle.try_acquire_at("447700900123", now));
+    assert!(!throttle.try_acquire_at("447700900123", now));
+
+    assert!(throttle.try_acquire_at("447800900123", now));
+    assert!(!throttle.try_acquire_at("447800900123", now));
+}
+
+#[test]
+fn a_released_token_can_be_used_again() {
+    let mut throttle = DestinationThrottle::new(&[limit("4477=1")]);
+    let now = Instant::now();
+
+    assert!(throttle.try_acquire_at("447700900123", now));
+    throttle.release("447700900123");
+    assert!(throttle.try_acquire_at("447700900123", now));
+    assert!(!throttle.try_acquire_at("447700900123", now));
+
+    // Releasing never takes a bucket over its limit
+    throttle.release("447700900123");
+    throttle.release("447700900123");
+    assert!(throttle.try_acquire_at("447700900123", now));
+    assert!(!throttle.try_acquire_at("447700900123", now));
+}
+
+#[tokio::test]
+async fn when_a_destination_is_over_its_limit_we_respond_throttled() {
+    let server = TestServer::start_with_smsc_config(Logic {}, |c| {
+        c.destination_limits = vec![limit("4477=1")]
+    })
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transmitter().await;
+
+    client
+        .send_and_expect_response(
+            &new_submit_sm(0x10, "447700900123").await,
+            b"\x00\x00\x00\x13\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x10\
+            id\0",
+        )
+        .await;
+    client
+        .send_and_expect_response(
+            &new_submit_sm(0x11, "447700900123").await,
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x58\x00\x00\x00\x11",
+            //                        ESME_RTHROTTLED ^^^^
+        )
+        .await;
+    client
+        .send_and_expect_response(
+            &new_submit_sm(0x12, "447800900123").await,
+            b"\x00\x00\x00\x13\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x12\
+            id\0",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn a_throttled_submit_sm_does_not_count_against_its_source_quota() {
+    let server = TestServer::start_with_smsc_config(Logic {}, |c| {
+        c.destination_limits = vec![limit("4477=1")];
+        c.source_quotas = vec!["2/minute".parse().unwrap()];
+    })
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transmitter().await;
+
+    client
+        .send_and_expect_response(
+            &new_submit_sm(0x10, "447700900123").await,
+            b"\x00\x00\x00\x13\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x10\
+            id\0",
+        )
+        .await;
+    client
+        .send_and_expect_response(
+            &new_submit_sm(0x11, "447700900123").await,
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x58\x00\x00\x00\x11",
+            //                        ESME_RTHROTTLED ^^^^
+        )
+        .await;
+    // The source has sent one of its two
+    client
+        .send_and_expect_response(
+            &new_submit_sm(0x12, "447800900123").await,
+            b"\x00\x00\x00\x13\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x12\
+            id\0",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn a_submit_sm_over_its_source_quota_does_not_count_against_its_destination(
+) {
+    let server = TestServer::start_with_smsc_config(Logic {}, |c| {
+        c.destination_limits = vec![limit("4477=1")];
+        c.source_quotas = vec!["1/minute".parse().unwrap()];
+        c.source_quota_status = 0x14; // ESME_RMSGQFUL
+    })
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transmitter().await;
+
+    client
+        .send_and_expect_response(
+            &new_submit_sm(0x10, "447800900123").await,
+            b"\x00\x00\x00\x13\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x10\
+            id\0",
+        )
+        .await;
+    client
+        .send_and_expect_response(
+            &new_submit_sm(0x11, "447700900123").await,
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x14\x00\x00\x00\x11",
+            //                         ESME_RMSGQFUL ^^^^
+        )
+        .await;
+    // 4477 still has its one token for another source
+    let mut submit_sm = new_submit_sm(0x12, "447700900123").await;
+    let source_addr = submit_sm
+        .windows(9)
+        .position(|w| w == b"MyCompany")
+        .unwrap();
+    submit_sm[source_addr..source_addr + 9].copy_from_slice(b"OtherComp");
+    client
+        .send_and_expect_response(
+            &submit_sm,
+            b"\x00\x00\x00\x13\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x12\
+            id\0",
+        )
+        .await;
+}
+
+struct Logic {}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Ok((
+            SubmitSmRespPdu::new("id").unwrap(),
+            MessageUniqueKey::new(
+                String::from("limits"),
+                format!("{}", sequence_number),
+                pdu.destination_addr(),
+            ),
+        ))
+    }
+}
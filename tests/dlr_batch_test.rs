@@ -0,0 +1,121 @@
+use smpp::dlr_batch::{BatchSummary, DlrBatches, DlrOutcome};
+use smpp::dlr_errors::{DeliveryError, DlrErrorMap};
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::DeliverSmPdu;
+use std::time::{Duration, Instant};
+
+fn dr(text: &str) -> DeliverSmPdu {
+    DeliverSmPdu::new(
+        "",
+        0,
+        0,
+        "447700900123",
+        0,
+        0,
+        "MyCompany",
+        0x04,
+        0,
+        0,
+        "",
+        "",
+        0,
+        0,
+        0,
+        0,
+        text.as_bytes(),
+        Tlvs::new(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn receipts_give_final_outcomes() {
+    let map = DlrErrorMap::default();
+    let outcome = |text| DlrOutcome::from_receipt(&dr(text), &map);
+    assert_eq!(
+        outcome("id:1 stat:DELIVRD err:000"),
+        Some(DlrOutcome::Delivered)
+    );
+    assert_eq!(
+        outcome("id:1 stat:UNDELIV err:027"),
+        Some(DlrOutcome::Failed(Some(DeliveryError::AbsentSubscriber)))
+    );
+    assert_eq!(
+        outcome("id:1 stat:REJECTD err:000"),
+        Some(DlrOutcome::Failed(None))
+    );
+    assert_eq!(outcome("id:1 stat:ENROUTE err:000"), None);
+    assert_eq!(outcome("not a receipt"), None);
+}
+
+#[test]
+fn a_summary_is_returned_when_every_outcome_is_in() {
+    let map = DlrErrorMap::default();
+    let mut batches = DlrBatches::new(Duration::from_secs(60));
+    batches.start("campaign", 3);
+
+    assert_eq!(
+        batches.record_receipt(&"campaign", &dr("stat:DELIVRD"), &map),
+        None
+    );
+    // Not final, so not counted
+    assert_eq!(
+        batches.record_receipt(&"campaign", &dr("stat:ENROUTE"), &map),
+        None
+    );
+    assert_eq!(
+        batches.record_receipt(&"campaign", &dr("stat:UNDELIV err:1"), &map),
+        None
+    );
+    assert_eq!(
+        batches.record_receipt(&"campaign", &dr("stat:EXPIRED"), &map),
+        Some(BatchSummary {
+            key: "campaign",
+            delivered: 1,
+            failed: 2,
+            missing: 0,
+            first_failure: Some(DeliveryError::UnknownSubscriber),
+            timed_out: false,
+        })
+    );
+    assert!(batches.is_empty());
+
+    // Once summarised, later outcomes are ignored
+    assert_eq!(batches.record(&"campaign", DlrOutcome::Delivered), None);
+}
+
+#[test]
+fn incomplete_batches_are_summarised_when_they_time_out() {
+    let mut batches = DlrBatches::new(Duration::from_secs(60));
+    batches.start(1, 2);
+    batches.start(2, 5);
+    assert_eq!(batches.record(&2, DlrOutcome::Delivered), None);
+
+    assert!(batches.expire(Instant::now()).is_empty());
+    assert_eq!(batches.len(), 2);
+
+    let mut expired = batches.expire(Instant::now() + Duration::from_secs(61));
+    expired.sort_by_key(|summary| summary.key);
+    assert_eq!(
+        expired,
+        vec![
+            BatchSummary {
+                key: 1,
+                delivered: 0,
+                failed: 0,
+                missing: 2,
+                first_failure: None,
+                timed_out: true,
+            },
+            BatchSummary {
+                key: 2,
+                delivered: 1,
+                failed: 0,
+                missing: 4,
+                first_failure: None,
+                timed_out: true,
+            },
+        ]
+    );
+    assert!(batches.is_empty());
+}
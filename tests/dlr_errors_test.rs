@@ -0,0 +1,128 @@
+use smpp::dlr_errors::{DeliveryError, DlrErrorMap};
+use smpp::typed_tlvs::{network_error_code, NetworkErrorCode};
+use smpp_pdu::pdu::tlvs::{Tlv, Tlvs};
+use smpp_pdu::pdu::DeliverSmPdu;
+
+fn dr(text: &str, tlvs: &[Tlv]) -> DeliverSmPdu {
+    DeliverSmPdu::new(
+        "",
+        0,
+        0,
+        "447700900123",
+        0,
+        0,
+        "MyCompany",
+        0x04,
+        0,
+        0,
+        "",
+        "",
+        0,
+        0,
+        0,
+        0,
+        text.as_bytes(),
+        Tlvs::from(tlvs),
+    )
+    .unwrap()
+}
+
+fn gsm(error_code: u16) -> Tlv {
+    network_error_code(NetworkErrorCode {
+        network_type: 3,
+        error_code,
+    })
+}
+
+#[test]
+fn err_codes_map_to_gsm_meanings_however_they_are_written() {
+    let map = DlrErrorMap::default();
+    assert_eq!(map.from_err("027"), DeliveryError::AbsentSubscriber);
+    assert_eq!(map.from_err("27"), DeliveryError::AbsentSubscriber);
+    assert_eq!(map.from_err("001"), DeliveryError::UnknownSubscriber);
+    assert_eq!(map.from_err("013"), DeliveryError::Barred);
+    assert_eq!(map.from_err("032"), DeliveryError::HandsetMemoryFull);
+    assert_eq!(map.from_err("999"), DeliveryError::Unknown);
+    assert_eq!(map.from_err("X"), DeliveryError::Unknown);
+}
+
+#[test]
+fn receipts_are_classified_from_err_stat_and_network_error_code() {
+    let map = DlrErrorMap::default();
+    let text = "id:1 sub:001 dlvrd:000 submit date:2101011200 \
+        done date:2101011201 stat:UNDELIV err:027 text:hi";
+    assert_eq!(
+        map.classify(&dr(text, &[])),
+        Some(DeliveryError::AbsentSubscriber)
+    );
+
+    // network_error_code wins over err:
+    assert_eq!(
+        map.classify(&dr(text, &[gsm(13)])),
+        Some(DeliveryError::Barred)
+    );
+
+    let expired = "id:1 sub:001 dlvrd:000 stat:EXPIRED err:000 text:hi";
+    assert_eq!(
+        map.classify(&dr(expired, &[])),
+        Some(DeliveryError::Expired)
+    );
+
+    let delivered = "id:1 sub:001 dlvrd:001 stat:DELIVRD err:000 text:hi";
+    assert_eq!(map.classify(&dr(delivered, &[gsm(0)])), None);
+
+    let strange = "id:1 stat:UNDELIV err:777";
+    assert_eq!(
+        map.classify(&dr(strange, &[])),
+        Some(DeliveryError::Unknown)
+    );
+}
+
+#[test]
+fn carriers_can_override_the_mapping() {
+    let mut map = DlrErrorMap::default();
+    map.load_overrides(
+        "# MyCarrier uses its own codes\n\
+        err:069 = handset_memory_full\n\
+        \n\
+        network:3:27=network_failure\n\
+        err:ABC=barred\n",
+    )
+    .unwrap();
+
+    assert_eq!(map.from_err("69"), DeliveryError::HandsetMemoryFull);
+    assert_eq!(map.from_err("abc"), DeliveryError::Barred);
+    assert_eq!(map.from_err("027"), DeliveryError::AbsentSubscriber);
+    assert_eq!(
+        map.from_network_error_code(NetworkErrorCode {
+            network_type: 3,
+            error_code: 27
+        }),
+        DeliveryError::NetworkFailure
+    );
+}
+
+#[test]
+fn bad_overrides_are_rejected() {
+    let mut map = DlrErrorMap::default();
+    assert_eq!(
+        map.load_overrides("err:1=lost").unwrap_err().to_string(),
+        "Invalid DLR error override 'err:1=lost': expected err:CODE=ERROR \
+        or network:TYPE:CODE=ERROR, e.g. err:027=absent_subscriber"
+    );
+    assert!(map.load_overrides("network:3=barred").is_err());
+    assert!(map.load_overrides("network:3:70000=barred").is_err());
+    assert!(map.load_overrides("barred").is_err());
+}
+
+#[test]
+fn delivery_errors_have_snake_case_names() {
+    assert_eq!(
+        DeliveryError::HandsetMemoryFull.to_string(),
+        "handset_memory_full"
+    );
+    assert_eq!(
+        "expired".parse::<DeliveryError>().unwrap(),
+        DeliveryError::Expired
+    );
+}
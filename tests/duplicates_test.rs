@@ -0,0 +1,164 @@
+use async_trait::async_trait;
+use smpp::client::{BindMode, Client};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smsc::{
+    BindData, BindError, DuplicateWindow, Smsc, SmscConfig, SmscLogic,
+    SubmitFingerprint, SubmitSmError,
+};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::{submit_sm_to, TestServer};
+
+fn fingerprint(destination_addr: &str, message: &[u8]) -> SubmitFingerprint {
+    SubmitFingerprint::new("esme1", "MyCompany", destination_addr, 0, message)
+}
+
+#[test]
+fn a_repeat_within_the_window_is_a_duplicate_of_the_original() {
+    let mut window = DuplicateWindow::new(Duration::from_secs(10));
+    let start = Instant::now();
+
+    assert_eq!(
+        window.duplicate_of_at(&fingerprint("447700900123", b"hi"), start),
+        None
+    );
+    window.record_at(fingerprint("447700900123", b"hi"), "id-1", start);
+
+    let later = start + Duration::from_secs(9);
+    assert_eq!(
+        window.duplicate_of_at(&fingerprint("447700900123", b"hi"), later),
+        Some(String::from("id-1"))
+    );
+    // A different destination or content is not a duplicate
+    assert_eq!(
+        window.duplicate_of_at(&fingerprint("447700900456", b"hi"), later),
+        None
+    );
+    assert_eq!(
+        window.duplicate_of_at(&fingerprint("447700900123", b"ho"), later),
+        None
+    );
+
+    // Once the window has passed, it is forgotten
+    let after = start + Duration::from_secs(10);
+    assert_eq!(
+        window.duplicate_of_at(&fingerprint("447700900123", b"hi"), after),
+        None
+    );
+    assert!(window.is_empty());
+}
+
+#[test]
+fn the_window_runs_from_the_latest_acceptance() {
+    let mut window = DuplicateWindow::new(Duration::from_secs(10));
+    let start = Instant::now();
+
+    window.record_at(fingerprint("447700900123", b"hi"), "id-1", start);
+    let later = start + Duration::from_secs(5);
+    window.record_at(fingerprint("447700900123", b"hi"), "id-2", later);
+
+    let after_first = start + Duration::from_secs(12);
+    assert_eq!(
+        window
+            .duplicate_of_at(&fingerprint("447700900123", b"hi"), after_first),
+        Some(String::from("id-2"))
+    );
+    assert_eq!(window.len(), 1);
+}
+
+fn submit_sm(destination_addr: &str, message: &[u8]) -> SubmitSmPdu {
+    let mut submit_sm = submit_sm_to(destination_addr);
+    submit_sm.0.short_message.value = Vec::from(message);
+    submit_sm
+}
+
+async fn message_ids(
+    configure: impl FnOnce(&mut SmscConfig),
+    submits: &[(&str, &[u8])],
+) -> Vec<String> {
+    let server =
+        TestServer::start_with_smsc_config(Logic::default(), configure)
+            .await
+            .unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transmitter, "esme1", "secret", "")
+        .await
+        .unwrap();
+    let mut message_ids = Vec::new();
+    for (destination_addr, message) in submits {
+        let resp = client
+            .submit_sm(submit_sm(destination_addr, message))
+            .await
+            .unwrap();
+        assert_eq!(resp.command_status, 0);
+        message_ids.push(resp.message_id.unwrap());
+    }
+    message_ids
+}
+
+#[tokio::test]
+async fn suppressed_duplicates_are_answered_with_the_original_message_id() {
+    let ids = message_ids(
+        |c| {
+            c.duplicate_window_secs = Some(60);
+            c.suppress_duplicates = true;
+        },
+        &[
+            ("447700900123", b"hi"),
+            ("447700900123", b"hi"),
+            ("447700900123", b"ho"),
+            ("447700900456", b"hi"),
+        ],
+    )
+    .await;
+
+    assert_eq!(ids, vec!["id-1", "id-1", "id-2", "id-3"]);
+}
+
+#[tokio::test]
+async fn without_suppression_duplicates_are_submitted_again() {
+    let ids = message_ids(
+        |c| c.duplicate_window_secs = Some(60),
+        &[("447700900123", b"hi"), ("447700900123", b"hi")],
+    )
+    .await;
+
+    assert_eq!(ids, vec!["id-1", "id-2"]);
+}
+
+/// Numbers each message it accepts
+#[derive(Default)]
+struct Logic {
+    submitted: u32,
+}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        self.submitted += 1;
+        let message_id = format!("id-{}", self.submitted);
+        Ok((
+            SubmitSmRespPdu::new(&message_id).unwrap(),
+            MessageUniqueKey::new(
+                String::from("duplicates"),
+                message_id,
+                pdu.destination_addr(),
+            ),
+        ))
+    }
+}
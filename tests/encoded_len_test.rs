@@ -0,0 +1,140 @@
+use smpp::encoded_len::EncodedLen;
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::{
+    BindTransmitterRespPdu, DeliverSmPdu, EnquireLinkPdu, GenericNackPdu, Pdu,
+    SubmitSmPdu, SubmitSmRespPdu,
+};
+
+async fn written_len(pdu: &Pdu) -> usize {
+    let mut buf: Vec<u8> = Vec::new();
+    pdu.write(&mut buf).await.unwrap();
+    buf.len()
+}
+
+async fn assert_encoded_len_is_correct(pdu: Pdu) {
+    assert_eq!(pdu.encoded_len(), written_len(&pdu).await);
+}
+
+#[tokio::test]
+async fn encoded_len_of_pdus_without_bodies_is_header_length() {
+    let pdu = Pdu::new(0, 1, EnquireLinkPdu::new().into()).unwrap();
+    assert_eq!(pdu.encoded_len(), 16);
+    assert_encoded_len_is_correct(pdu).await;
+
+    assert_encoded_len_is_correct(
+        Pdu::new(3, 1, GenericNackPdu::new_error().into()).unwrap(),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn encoded_len_matches_written_length_for_responses() {
+    assert_encoded_len_is_correct(
+        Pdu::new(0, 2, BindTransmitterRespPdu::new("sys").unwrap().into())
+            .unwrap(),
+    )
+    .await;
+    assert_encoded_len_is_correct(
+        Pdu::new(8, 2, BindTransmitterRespPdu::new_error().into()).unwrap(),
+    )
+    .await;
+    assert_encoded_len_is_correct(
+        Pdu::new(0, 3, SubmitSmRespPdu::new("abc123").unwrap().into()).unwrap(),
+    )
+    .await;
+    assert_encoded_len_is_correct(
+        Pdu::new(8, 3, SubmitSmRespPdu::new_error().into()).unwrap(),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn encoded_len_matches_written_length_for_messages_with_tlvs() {
+    assert_encoded_len_is_correct(
+        Pdu::new(
+            0,
+            4,
+            SubmitSmPdu::new(
+                "CMT",
+                1,
+                1,
+                "447000123123",
+                1,
+                1,
+                "447111222222",
+                0,
+                0,
+                0,
+                "",
+                "",
+                1,
+                0,
+                0,
+                0,
+                b"hello there",
+                Tlvs::from(&[Tlv::new(
+                    KnownTlvTag::user_message_reference,
+                    b"\x00\x07",
+                )]),
+            )
+            .unwrap()
+            .into(),
+        )
+        .unwrap(),
+    )
+    .await;
+
+    assert_encoded_len_is_correct(
+        Pdu::new(
+            0,
+            5,
+            DeliverSmPdu::new(
+                "",
+                0,
+                0,
+                "447111222222",
+                0,
+                0,
+                "447000123123",
+                4,
+                0,
+                0,
+                "",
+                "",
+                0,
+                0,
+                0,
+                0,
+                b"",
+                Tlvs::from(&[
+                    Tlv::new(KnownTlvTag::receipted_message_id, b"abc\0"),
+                    Tlv::new_unknown(0x1401, b"vendor"),
+                ]),
+            )
+            .unwrap()
+            .into(),
+        )
+        .unwrap(),
+    )
+    .await;
+}
+
+#[test]
+fn encoded_len_counts_each_tlvs_header_and_value() {
+    assert_eq!(Tlvs::new().encoded_len(), 0);
+    assert_eq!(Tlv::new_unknown(0x1401, b"").encoded_len(), 4);
+    assert_eq!(
+        Tlvs::from(&[
+            Tlv::new(KnownTlvTag::receipted_message_id, b"abc\0"),
+            Tlv::new_unknown(0x1401, b"vendor"),
+        ])
+        .encoded_len(),
+        (4 + 4) + (4 + 6)
+    );
+}
+
+#[test]
+fn encoded_len_of_a_bind_resp_counts_the_system_id_and_its_null() {
+    assert_eq!(BindTransmitterRespPdu::new("sys").unwrap().encoded_len(), 4);
+    assert_eq!(BindTransmitterRespPdu::new_error().encoded_len(), 0);
+}
@@ -0,0 +1,210 @@
+use async_trait::async_trait;
+use smpp::cancel_sm::CancelSmPdu;
+use smpp::client::{BindMode, Client};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::query_sm::{MessageState, QuerySmPdu, QuerySmRespPdu};
+use smpp::smsc::{
+    BindData, BindError, CancelSmError, EnquireLinkLimit, QueryCache, QueryKey,
+    QuerySmError, Smsc, SmscLogic, SubmitSmError,
+};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::{DefaultLogic, TestClient, TestServer};
+
+fn key(message_id: &str) -> QueryKey {
+    QueryKey::new(
+        "esme1",
+        &QuerySmPdu::new(0, message_id, "MyCompany").unwrap(),
+    )
+}
+
+fn delivered(message_id: &str) -> QuerySmRespPdu {
+    QuerySmRespPdu::new(0, message_id, "", MessageState::Delivered, 0).unwrap()
+}
+
+#[test]
+fn answers_are_cached_for_the_ttl() {
+    let mut cache = QueryCache::new(Duration::from_millis(500));
+    let start = Instant::now();
+
+    assert_eq!(cache.get_at(&key("1"), start), None);
+    cache.insert_at(key("1"), delivered("1"), start);
+
+    let later = start + Duration::from_millis(499);
+    assert_eq!(cache.get_at(&key("1"), later), Some(delivered("1")));
+    assert_eq!(cache.get_at(&key("2"), later), None);
+
+    let after = start + Duration::from_millis(500);
+    assert_eq!(cache.get_at(&key("1"), after), None);
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn forgotten_messages_are_no_longer_cached() {
+    let mut cache = QueryCache::new(Duration::from_secs(10));
+    cache.insert(key("1"), delivered("1"));
+    cache.insert(key("2"), delivered("2"));
+
+    cache.forget("1");
+
+    assert_eq!(cache.get(&key("1")), None);
+    assert_eq!(cache.get(&key("2")), Some(delivered("2")));
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn enquire_links_are_limited_per_second() {
+    let mut limit = EnquireLinkLimit::new(2);
+    let start = Instant::now();
+
+    assert!(limit.allow_at(start));
+    assert!(limit.allow_at(start + Duration::from_millis(100)));
+    assert!(!limit.allow_at(start + Duration::from_millis(200)));
+    assert!(limit.just_exceeded());
+    assert!(!limit.allow_at(start + Duration::from_millis(300)));
+    assert!(!limit.just_exceeded());
+
+    assert!(limit.allow_at(start + Duration::from_secs(1)));
+}
+
+/// Knows every message is delivered, and counts how often it is asked
+struct CountingLogic {
+    queries: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl SmscLogic for CountingLogic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        _pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Err(SubmitSmError::InternalError)
+    }
+
+    async fn query_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &QuerySmPdu,
+    ) -> Result<QuerySmRespPdu, QuerySmError> {
+        self.queries.fetch_add(1, Ordering::SeqCst);
+        Ok(delivered(pdu.message_id.value.as_str()))
+    }
+
+    async fn cancel_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        _pdu: &CancelSmPdu,
+    ) -> Result<(), CancelSmError> {
+        Ok(())
+    }
+}
+
+async fn query_cache_server() -> (TestServer, Arc<AtomicUsize>) {
+    let queries = Arc::new(AtomicUsize::new(0));
+    let logic = CountingLogic {
+        queries: Arc::clone(&queries),
+    };
+    let server = TestServer::start_with_smsc_config(logic, |c| {
+        c.query_cache_ms = Some(10_000);
+    })
+    .await
+    .unwrap();
+    (server, queries)
+}
+
+async fn bound_client(server: &TestServer) -> Client {
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transceiver, "esme1", "secret", "")
+        .await
+        .unwrap();
+    client
+}
+
+async fn query(client: &Client, message_id: &str) -> QuerySmRespPdu {
+    client
+        .query_sm(QuerySmPdu::new(0, message_id, "MyCompany").unwrap())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn repeated_queries_are_answered_from_the_cache() {
+    let (server, queries) = query_cache_server().await;
+    let client = bound_client(&server).await;
+
+    for _ in 0..3 {
+        let resp = query(&client, "1234").await;
+        assert_eq!(resp.command_status, 0);
+        assert_eq!(resp.message_id.value, "1234");
+    }
+    assert_eq!(queries.load(Ordering::SeqCst), 1);
+
+    query(&client, "5678").await;
+    assert_eq!(queries.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn cancelling_a_message_drops_its_cached_answer() {
+    let (server, queries) = query_cache_server().await;
+    let client = bound_client(&server).await;
+    query(&client, "1234").await;
+
+    let cancel_sm = CancelSmPdu::new(0, "1234", "MyCompany").unwrap();
+    assert_eq!(client.cancel_sm(cancel_sm).await.unwrap().command_status, 0);
+    query(&client, "1234").await;
+
+    assert_eq!(queries.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn enquire_link_floods_are_throttled() {
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.max_enquire_links_per_sec = Some(2);
+    })
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+
+    for seq in 1..=2 {
+        client
+            .send_and_expect_response(
+                &enquire_link(seq),
+                &enquire_link_resp(seq),
+            )
+            .await;
+    }
+    // generic_nack with ESME_RTHROTTLED
+    client
+        .send_and_expect_response(
+            &enquire_link(3),
+            b"\x00\x00\x00\x10\x80\x00\x00\x00\x00\x00\x00\x58\x00\x00\x00\x03",
+        )
+        .await;
+}
+
+fn enquire_link(sequence_number: u8) -> [u8; 16] {
+    let mut bytes =
+        *b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x00";
+    bytes[15] = sequence_number;
+    bytes
+}
+
+fn enquire_link_resp(sequence_number: u8) -> [u8; 16] {
+    let mut bytes =
+        *b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x00";
+    bytes[15] = sequence_number;
+    bytes
+}
@@ -0,0 +1,223 @@
+use clap::Clap;
+use smpp::client::{BindMode, Client};
+use smpp::delivery_receipt::DeliveryReceipt;
+use smpp::examples::smsc_drs_after_1_sec::DrsAfter1Sec;
+use smpp::gateway::{
+    Cdr, CdrSink, Gateway, GatewayBuilder, ReceiptSink, Upstream,
+};
+use smpp::smsc::SmscConfig;
+use smpp::submit_sm_builder::SubmitSmBuilder;
+use smpp_pdu::pdu::PduStatus;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::timeout;
+
+mod test_utils;
+
+use test_utils::{next_port, DefaultLogic, TestServer};
+
+#[derive(Default)]
+struct Receipts(Mutex<Vec<(String, Option<String>)>>);
+
+impl ReceiptSink for Receipts {
+    fn receipt(&self, upstream: &str, receipt: &DeliveryReceipt) {
+        self.0
+            .lock()
+            .unwrap()
+            .push((String::from(upstream), receipt.message_id.clone()));
+    }
+}
+
+#[derive(Default)]
+struct Cdrs(Mutex<Vec<Cdr>>);
+
+impl CdrSink for Cdrs {
+    fn record(&self, cdr: &Cdr) {
+        self.0.lock().unwrap().push(cdr.clone());
+    }
+}
+
+fn builder() -> (GatewayBuilder, String) {
+    let address = format!("127.0.0.1:{}", next_port());
+    let config = SmscConfig::parse_from(["smsc", "--bind-address", &address]);
+    (
+        GatewayBuilder::new(config).account("esme1", "secret"),
+        address,
+    )
+}
+
+async fn gateway_to(
+    upstream: &TestServer,
+    sink: Arc<Receipts>,
+) -> (Gateway, Client) {
+    let (builder, address) = builder();
+    let gateway = builder
+        .upstream(Upstream::new("up", &upstream.bind_address, "gw", "gw"))
+        .route("44", "up")
+        .receipt_sink(sink)
+        .start()
+        .await
+        .unwrap();
+    (gateway, bind(&address).await)
+}
+
+async fn bind(address: &str) -> Client {
+    let client = Client::connect(address).await.unwrap();
+    client
+        .bind(BindMode::Transceiver, "esme1", "secret", "")
+        .await
+        .unwrap();
+    client
+}
+
+async fn submit(
+    client: &Client,
+    destination_addr: &str,
+) -> (u32, Option<String>) {
+    let submit_sm = SubmitSmBuilder::new()
+        .destination_addr(destination_addr)
+        .registered_delivery(1)
+        .short_message(b"hello")
+        .build()
+        .unwrap();
+    let resp = client.submit_sm(submit_sm).await.unwrap();
+    (resp.command_status, resp.message_id)
+}
+
+#[tokio::test]
+async fn submits_go_upstream_and_receipts_come_back() {
+    let upstream = TestServer::start_with_logic(DrsAfter1Sec::new())
+        .await
+        .unwrap();
+    let sink = Arc::new(Receipts::default());
+    let (_gateway, client) = gateway_to(&upstream, Arc::clone(&sink)).await;
+
+    let (command_status, message_id) = submit(&client, "447700900123").await;
+    assert_eq!(command_status, 0);
+    assert_ne!(message_id, Some(String::from("abc")));
+
+    let receipt = timeout(Duration::from_secs(5), client.next_receipt())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(receipt.message_id, message_id);
+    assert_eq!(
+        *sink.0.lock().unwrap(),
+        vec![(String::from("up"), Some(String::from("abc")))]
+    );
+}
+
+#[tokio::test]
+async fn destinations_without_a_route_are_rejected() {
+    let upstream = TestServer::start_with_logic(DrsAfter1Sec::new())
+        .await
+        .unwrap();
+    let (_gateway, client) =
+        gateway_to(&upstream, Arc::new(Receipts::default())).await;
+
+    assert_eq!(
+        submit(&client, "33612345678").await,
+        (PduStatus::ESME_RINVDSTADR as u32, None)
+    );
+}
+
+#[tokio::test]
+async fn only_accounts_may_bind() {
+    let (builder, address) = builder();
+    let _gateway = builder.start().await.unwrap();
+    let client = Client::connect(&address).await.unwrap();
+
+    let err = client
+        .bind(BindMode::Transceiver, "esme1", "wrong", "")
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err.pdu_status(), Some(PduStatus::ESME_RINVPASWD)));
+}
+
+#[tokio::test]
+async fn routes_must_name_an_upstream() {
+    let (builder, _) = builder();
+
+    let err = builder.route("44", "nowhere").start().await.err().unwrap();
+
+    assert_eq!(err.to_string(), "No upstream called 'nowhere'");
+}
+
+#[tokio::test]
+async fn the_cheapest_route_is_chosen() {
+    let cheap = TestServer::start_with_logic(DrsAfter1Sec::new())
+        .await
+        .unwrap();
+    let dear = TestServer::start_with_logic(DrsAfter1Sec::new())
+        .await
+        .unwrap();
+    let cdrs = Arc::new(Cdrs::default());
+    let (builder, address) = builder();
+    let _gateway = builder
+        .upstream(Upstream::new("dear", &dear.bind_address, "gw", "gw"))
+        .upstream(Upstream::new("cheap", &cheap.bind_address, "gw", "gw"))
+        .route_with_cost("447", "dear", 10)
+        .route_with_cost("", "cheap", 2)
+        .cdr_sink(Arc::clone(&cdrs) as Arc<dyn CdrSink + Send + Sync>)
+        .start()
+        .await
+        .unwrap();
+    let client = bind(&address).await;
+
+    let (command_status, message_id) = submit(&client, "447700900123").await;
+
+    assert_eq!(command_status, 0);
+    assert_eq!(
+        *cdrs.0.lock().unwrap(),
+        vec![Cdr {
+            message_id: message_id.unwrap(),
+            destination_addr: String::from("447700900123"),
+            upstream: String::from("cheap"),
+            cost: 2,
+            attempts: 1,
+        }]
+    );
+}
+
+#[tokio::test]
+async fn failing_upstreams_are_failed_over_then_avoided() {
+    let broken = TestServer::start_with_logic(DefaultLogic {}).await.unwrap();
+    let working = TestServer::start_with_logic(DrsAfter1Sec::new())
+        .await
+        .unwrap();
+    let cdrs = Arc::new(Cdrs::default());
+    let (builder, address) = builder();
+    let _gateway = builder
+        .upstream(Upstream::new("broken", &broken.bind_address, "gw", "gw"))
+        .upstream(Upstream::new("working", &working.bind_address, "gw", "gw"))
+        .route_with_cost("", "broken", 1)
+        .route_with_cost("", "working", 5)
+        .cdr_sink(Arc::clone(&cdrs) as Arc<dyn CdrSink + Send + Sync>)
+        .start()
+        .await
+        .unwrap();
+    let client = bind(&address).await;
+
+    for _ in 0..4 {
+        assert_eq!(submit(&client, "447700900123").await.0, 0);
+    }
+
+    let routes: Vec<(String, usize)> = cdrs
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|cdr| (cdr.upstream.clone(), cdr.attempts))
+        .collect();
+    let working = String::from("working");
+    assert_eq!(
+        routes,
+        vec![
+            (working.clone(), 2),
+            (working.clone(), 2),
+            (working.clone(), 2),
+            (working, 1),
+        ]
+    );
+}
@@ -0,0 +1,106 @@
+use smpp::client::{BindMode, Client, ClientState};
+use smpp::session_stats::SessionStats;
+use smpp_pdu::pdu::{EnquireLinkPdu, EnquireLinkRespPdu, Pdu};
+use std::time::Duration;
+
+mod test_utils;
+
+use test_utils::{new_submit_sm, DefaultLogic, TestServer, TestSetup};
+
+#[tokio::test]
+async fn an_smsc_with_no_sessions_is_healthy() {
+    let t = TestSetup::new().await;
+    let health = t.server.smsc.lock().await.health();
+    assert_eq!(health.bound_sessions(), 0);
+    assert_eq!(health.recent_error_rate(), 0.0);
+    assert!(health.is_healthy());
+}
+
+#[tokio::test]
+async fn system_errors_make_the_smsc_unhealthy() {
+    let mut t = TestSetup::new().await;
+    t.client.bind_transmitter().await;
+    assert!(t.server.smsc.lock().await.is_healthy());
+
+    // DefaultLogic rejects every submit_sm with ESME_RSYSERR
+    t.client
+        .send_and_expect_response(
+            &new_submit_sm(3, "447700900123").await,
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x08\x00\x00\x00\x03",
+        )
+        .await;
+
+    let health = t.server.smsc.lock().await.health();
+    assert_eq!(health.bound_sessions(), 1);
+    let session = &health.sessions[0];
+    assert_eq!(session.system_id, "esmeid");
+    assert_eq!(session.pending_responses, 0);
+    // The bind_transmitter_resp and the submit_sm_resp
+    assert_eq!(session.recent_responses, 2);
+    assert_eq!(session.recent_errors, 1);
+    assert_eq!(session.recent_system_errors, 1);
+    assert_eq!(session.recent_error_rate(), 0.5);
+    assert!(health.is_healthy());
+
+    t.client
+        .send_and_expect_response(
+            &new_submit_sm(4, "447700900123").await,
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x08\x00\x00\x00\x04",
+        )
+        .await;
+    assert!(!t.server.smsc.lock().await.is_healthy());
+}
+
+#[test]
+fn enquire_link_round_trip_is_measured_from_our_enquire_link() {
+    let mut stats = SessionStats::new();
+    stats.record_sent(&Pdu::new(0, 7, EnquireLinkPdu::new().into()).unwrap());
+    assert_eq!(stats.last_enquire_link_rtt, None);
+
+    // A response to something else does not count
+    stats.record_received(
+        &Pdu::new(0, 6, EnquireLinkRespPdu::new().into()).unwrap(),
+    );
+    assert_eq!(stats.last_enquire_link_rtt, None);
+
+    std::thread::sleep(Duration::from_millis(5));
+    stats.record_received(
+        &Pdu::new(0, 7, EnquireLinkRespPdu::new().into()).unwrap(),
+    );
+    assert!(stats.last_enquire_link_rtt.unwrap() >= Duration::from_millis(5));
+}
+
+#[tokio::test]
+async fn a_client_is_healthy_while_bound_and_answered() {
+    let server = TestServer::start_with_logic(DefaultLogic {}).await.unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    assert!(!client.is_healthy());
+
+    client
+        .bind(BindMode::Transmitter, "esme1", "", "")
+        .await
+        .unwrap();
+    client.enquire_link().await.unwrap();
+    let health = client.health();
+    assert_eq!(health.state, ClientState::Bound);
+    assert!(health.last_enquire_link_rtt.is_some());
+    assert_eq!(health.pending_requests, 0);
+    assert_eq!(health.queued_deliveries, 0);
+    assert_eq!(health.recent_requests, 2);
+    assert_eq!(health.recent_errors, 0);
+    assert!(health.is_healthy());
+
+    // DefaultLogic rejects every submit_sm with ESME_RSYSERR
+    for _ in 0..2 {
+        let resp = client.submit_text("+447700900123", b"hi").await.unwrap();
+        assert_eq!(resp.command_status, 0x00000008);
+    }
+    let health = client.health();
+    assert_eq!(health.recent_requests, 4);
+    assert_eq!(health.recent_system_errors, 2);
+    assert_eq!(health.recent_error_rate(), 0.5);
+    assert!(health.is_healthy());
+
+    client.submit_text("+447700900123", b"hi").await.unwrap();
+    assert!(!client.is_healthy());
+}
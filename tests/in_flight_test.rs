@@ -0,0 +1,93 @@
+use smpp::in_flight::{InFlight, InFlightError, SequenceNumbers, Stray};
+use std::time::Duration;
+use tokio::time::timeout;
+
+const LONG: Duration = Duration::from_secs(10);
+
+#[test]
+fn sequence_numbers_count_up_from_1() {
+    let sequence_numbers = SequenceNumbers::new();
+    let numbers: Vec<u32> = (0..3).map(|_| sequence_numbers.next()).collect();
+    assert_eq!(numbers, [1, 2, 3]);
+}
+
+#[tokio::test]
+async fn each_response_goes_to_the_request_with_its_sequence_number() {
+    let in_flight = InFlight::new(LONG);
+    let first = in_flight.start(1).await;
+    let second = in_flight.start(2).await;
+
+    assert!(in_flight.respond(2, "second"));
+    assert!(in_flight.respond(1, "first"));
+    assert!(!in_flight.respond(3, "nobody asked"));
+
+    assert_eq!(first.response().await, Ok("first"));
+    assert_eq!(second.response().await, Ok("second"));
+    assert!(in_flight.is_empty());
+}
+
+#[tokio::test]
+async fn a_request_without_a_response_times_out_and_is_forgotten() {
+    let in_flight: InFlight<()> = InFlight::new(Duration::from_millis(50));
+
+    let pending = in_flight.start(1).await;
+    assert_eq!(in_flight.len(), 1);
+
+    assert_eq!(pending.response().await, Err(InFlightError::Timeout));
+    assert!(!in_flight.respond(1, ()));
+}
+
+#[tokio::test]
+async fn clearing_fails_every_outstanding_request() {
+    let in_flight: InFlight<()> = InFlight::new(LONG);
+    let pending = in_flight.start(1).await;
+
+    in_flight.clear();
+
+    assert_eq!(pending.response().await, Err(InFlightError::Closed));
+}
+
+#[tokio::test]
+async fn requests_beyond_the_window_wait_for_a_response() {
+    let in_flight = InFlight::with_window(LONG, 2);
+    let first = in_flight.start(1).await;
+    let _second = in_flight.start(2).await;
+
+    assert!(timeout(Duration::from_millis(50), in_flight.start(3))
+        .await
+        .is_err());
+
+    in_flight.respond(1, ());
+    first.response().await.unwrap();
+    let third = timeout(Duration::from_millis(50), in_flight.start(3))
+        .await
+        .unwrap();
+    assert_eq!(third.sequence_number(), 3);
+}
+
+#[tokio::test]
+async fn outstanding_sequence_numbers_are_listed_lowest_first() {
+    let in_flight = InFlight::new(LONG);
+    let _third = in_flight.start(7).await;
+    let _first = in_flight.start(2).await;
+    let _second = in_flight.start(5).await;
+
+    in_flight.respond(5, ());
+
+    assert_eq!(in_flight.sequence_numbers(), [2, 7]);
+}
+
+#[tokio::test]
+async fn a_stray_response_is_a_duplicate_if_its_request_was_answered() {
+    let in_flight: InFlight<()> = InFlight::new(Duration::from_millis(50));
+    let answered = in_flight.start(1).await;
+    let expired = in_flight.start(2).await;
+    in_flight.respond(1, ());
+    drop(answered);
+    assert_eq!(expired.response().await, Err(InFlightError::Timeout));
+
+    assert!(!in_flight.respond(1, ()));
+    assert_eq!(in_flight.stray(1), Stray::Duplicate);
+    assert!(!in_flight.respond(2, ()));
+    assert_eq!(in_flight.stray(2), Stray::Late);
+}
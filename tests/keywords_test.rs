@@ -0,0 +1,164 @@
+use async_trait::async_trait;
+use smpp::keywords::{
+    KeywordHandler, KeywordRouter, MemoryStopList, MoMessage, Routed, StopList,
+};
+use smpp::text::{DataCodingMap, DecodeTextError};
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::DeliverSmPdu;
+use std::sync::{Arc, Mutex};
+
+const HANDSET: &str = "447700900123";
+const SHORT_CODE: &str = "60123";
+
+/// Remembers every message it handles
+#[derive(Default)]
+struct Handled(Mutex<Vec<MoMessage>>);
+
+#[async_trait]
+impl KeywordHandler for Handled {
+    async fn handle(&self, message: &MoMessage) {
+        self.0.lock().unwrap().push(message.clone());
+    }
+}
+
+impl Handled {
+    fn keywords(&self) -> Vec<String> {
+        let handled = self.0.lock().unwrap();
+        handled.iter().map(|m| m.keyword.clone()).collect()
+    }
+}
+
+fn deliver_sm(data_coding: u8, short_message: &[u8]) -> DeliverSmPdu {
+    DeliverSmPdu::new(
+        "",
+        1,
+        1,
+        HANDSET,
+        0,
+        0,
+        SHORT_CODE,
+        0,
+        0,
+        0,
+        "",
+        "",
+        0,
+        0,
+        data_coding,
+        0,
+        short_message,
+        Tlvs::new(),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn the_first_word_is_matched_case_insensitively() {
+    let join = Arc::new(Handled::default());
+    let router = KeywordRouter::new().on("join", join.clone());
+
+    for text in ["JOIN", "join  Pizza Club ", "  Join! please"] {
+        assert_eq!(
+            router.route_text(HANDSET, SHORT_CODE, text).await,
+            Routed::Keyword(String::from("JOIN"))
+        );
+    }
+    assert_eq!(
+        router.route_text(HANDSET, SHORT_CODE, "joining").await,
+        Routed::Unmatched
+    );
+
+    let handled = join.0.lock().unwrap();
+    assert_eq!(handled.len(), 3);
+    assert_eq!(
+        handled[1],
+        MoMessage {
+            source_addr: String::from(HANDSET),
+            destination_addr: String::from(SHORT_CODE),
+            keyword: String::from("JOIN"),
+            rest: String::from("Pizza Club"),
+            text: String::from("join  Pizza Club "),
+        }
+    );
+}
+
+#[tokio::test]
+async fn unmatched_messages_go_to_the_fallback() {
+    let help = Arc::new(Handled::default());
+    let other = Arc::new(Handled::default());
+    let router = KeywordRouter::new()
+        .on("HELP", help.clone())
+        .otherwise(other.clone());
+
+    router.route_text(HANDSET, SHORT_CODE, "help").await;
+    router.route_text(HANDSET, SHORT_CODE, "Thanks!").await;
+    router.route_text(HANDSET, SHORT_CODE, "").await;
+
+    assert_eq!(help.keywords(), vec!["HELP"]);
+    assert_eq!(other.keywords(), vec!["THANKS", ""]);
+}
+
+#[tokio::test]
+async fn stop_and_start_maintain_the_stop_list() {
+    let stop_list = Arc::new(MemoryStopList::new());
+    let stopped = Arc::new(Handled::default());
+    let other = Arc::new(Handled::default());
+    let router = KeywordRouter::new()
+        .with_stop_list(stop_list.clone())
+        .on("STOP", stopped.clone())
+        .otherwise(other.clone());
+
+    assert_eq!(
+        router.route_text(HANDSET, SHORT_CODE, "Stop.").await,
+        Routed::Keyword(String::from("STOP"))
+    );
+    assert!(stop_list.contains(HANDSET).await);
+    assert_eq!(stopped.keywords(), vec!["STOP"]);
+
+    router.route_text(HANDSET, SHORT_CODE, "start").await;
+    assert!(!stop_list.contains(HANDSET).await);
+
+    // Built in keywords need no handler of their own
+    assert_eq!(
+        router.route_text(HANDSET, SHORT_CODE, "UNSUBSCRIBE").await,
+        Routed::Keyword(String::from("UNSUBSCRIBE"))
+    );
+    assert!(stop_list.contains(HANDSET).await);
+    assert!(other.keywords().is_empty());
+}
+
+#[tokio::test]
+async fn the_stop_list_keeps_numbers_in_international_form() {
+    let stop_list = Arc::new(MemoryStopList::new());
+    let router = KeywordRouter::new().with_stop_list(stop_list.clone());
+
+    router
+        .route_text("+44 7700 900123", SHORT_CODE, "STOP")
+        .await;
+    assert!(stop_list.contains(HANDSET).await);
+    assert!(stop_list.contains("0044 7700900123").await);
+
+    router.route_text(HANDSET, SHORT_CODE, "START").await;
+    assert!(!stop_list.contains("+447700900123").await);
+}
+
+#[tokio::test]
+async fn deliver_sm_is_decoded_before_routing() {
+    let stop_list = Arc::new(MemoryStopList::new());
+    let router = KeywordRouter::new().with_stop_list(stop_list.clone());
+    let map = DataCodingMap::default();
+
+    // UCS-2
+    let stop = deliver_sm(0x08, b"\x00S\x00T\x00O\x00P");
+    assert_eq!(
+        router.route(&stop, &map).await,
+        Routed::Keyword(String::from("STOP"))
+    );
+    assert!(stop_list.contains(HANDSET).await);
+
+    let binary = deliver_sm(0x04, b"\x01\x02");
+    assert_eq!(
+        router.route(&binary, &map).await,
+        Routed::NotText(DecodeTextError::Binary(0x04))
+    );
+}
@@ -0,0 +1,172 @@
+use smpp::long_message::{
+    Concatenation, LongMessage, LongMessageError, Part, Reassembler,
+    ESM_CLASS_UDHI,
+};
+use smpp::pdu_accessors::SmAccessors;
+use smpp::submit_sm_builder::SubmitSmBuilder;
+use smpp::text::DataCodingMap;
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlvs};
+
+fn template() -> SubmitSmBuilder {
+    SubmitSmBuilder::new()
+        .source_addr("MyCompany")
+        .destination_addr("447700900123")
+}
+
+fn split(text: &str, concatenation: Concatenation) -> LongMessage {
+    LongMessage::from_text(text, &DataCodingMap::default(), concatenation)
+        .unwrap()
+}
+
+fn part_lengths(message: &LongMessage) -> Vec<usize> {
+    message.parts().iter().map(Vec::len).collect()
+}
+
+#[test]
+fn a_message_that_fits_is_sent_whole() {
+    let text = "a".repeat(160);
+
+    let pdus = split(&text, Concatenation::Udh8)
+        .submit_sms(&template(), 1)
+        .unwrap();
+
+    assert_eq!(pdus.len(), 1);
+    assert_eq!(pdus[0].esm_class(), 0);
+    assert_eq!(pdus[0].short_message(), text.as_bytes());
+    assert_eq!(pdus[0].tlvs(), &Tlvs::new());
+}
+
+#[test]
+fn each_concatenation_leaves_room_for_its_header() {
+    let text = "a".repeat(400);
+
+    assert_eq!(
+        part_lengths(&split(&text, Concatenation::Udh8)),
+        vec![153, 153, 94]
+    );
+    assert_eq!(
+        part_lengths(&split(&text, Concatenation::Udh16)),
+        vec![152, 152, 96]
+    );
+    assert_eq!(
+        part_lengths(&split(&text, Concatenation::Sar)),
+        vec![160, 160, 80]
+    );
+    assert_eq!(
+        part_lengths(&split(&"â".repeat(141), Concatenation::Udh8)),
+        vec![134, 7]
+    );
+}
+
+#[test]
+fn characters_are_never_split_between_parts() {
+    // A GSM escape and the septet after it
+    let gsm = split(
+        &format!("{}€{}", "a".repeat(152), "a".repeat(10)),
+        Concatenation::Udh8,
+    );
+    assert_eq!(part_lengths(&gsm), vec![152, 12]);
+
+    // A UTF-16 surrogate pair
+    let ucs2 = split(&"😀".repeat(36), Concatenation::Udh8);
+    assert_eq!(ucs2.data_coding(), 0x08);
+    assert_eq!(part_lengths(&ucs2), vec![132, 12]);
+}
+
+#[test]
+fn udh_parts_start_with_the_concatenation_header() {
+    let pdus = split(&"a".repeat(200), Concatenation::Udh16)
+        .submit_sms(&template().esm_class(0x03), 0x1234)
+        .unwrap();
+
+    assert_eq!(pdus.len(), 2);
+    assert_eq!(pdus[1].esm_class(), 0x03 | ESM_CLASS_UDHI);
+    assert_eq!(
+        &pdus[1].short_message()[..7],
+        &[6, 0x08, 4, 0x12, 0x34, 2, 2]
+    );
+    assert_eq!(
+        Part::of(&pdus[0]),
+        Some((
+            Part {
+                reference: 0x1234,
+                total: 2,
+                seqnum: 1,
+            },
+            &[b'a'; 152][..]
+        ))
+    );
+}
+
+#[test]
+fn sar_parts_carry_tlvs() {
+    let pdus = split(&"a".repeat(200), Concatenation::Sar)
+        .submit_sms(&template(), 0x1234)
+        .unwrap();
+
+    assert_eq!(pdus[1].esm_class(), 0);
+    let value = |tag| pdus[1].tlvs().get(tag).map(|tlv| tlv.value);
+    assert_eq!(value(KnownTlvTag::sar_msg_ref_num), Some(vec![0x12, 0x34]));
+    assert_eq!(value(KnownTlvTag::sar_total_segments), Some(vec![2]));
+    assert_eq!(value(KnownTlvTag::sar_segment_seqnum), Some(vec![2]));
+}
+
+#[test]
+fn at_most_255_parts_are_allowed() {
+    assert_eq!(
+        LongMessage::from_bytes(&[0; 134 * 255 + 1], 0x04, Concatenation::Udh8),
+        Err(LongMessageError::TooManyParts(256))
+    );
+}
+
+#[test]
+fn parts_are_reassembled_in_any_order() {
+    let map = DataCodingMap::default();
+    let text = "Ünïcödé ".repeat(40);
+    for concatenation in &[
+        Concatenation::Udh8,
+        Concatenation::Udh16,
+        Concatenation::Sar,
+    ] {
+        let message =
+            LongMessage::from_text(&text, &map, *concatenation).unwrap();
+        let pdus = message.submit_sms(&template(), 7).unwrap();
+        assert!(pdus.len() > 2);
+        let mut reassembler = Reassembler::new();
+
+        let mut whole = None;
+        for pdu in pdus.iter().rev() {
+            assert_eq!(whole, None);
+            whole = reassembler.add(pdu);
+        }
+
+        let whole = whole.unwrap();
+        assert_eq!(map.decode(message.data_coding(), &whole).unwrap(), text);
+        assert_eq!(reassembler.pending(), 0);
+    }
+}
+
+#[test]
+fn messages_with_the_same_reference_are_kept_apart() {
+    let message = split(&"a".repeat(200), Concatenation::Udh8);
+    let to_one = message.submit_sms(&template(), 1).unwrap();
+    let to_other = message
+        .submit_sms(&template().destination_addr("447700900999"), 1)
+        .unwrap();
+    let mut reassembler = Reassembler::new();
+
+    assert_eq!(reassembler.add(&to_one[0]), None);
+    assert_eq!(reassembler.add(&to_other[1]), None);
+    assert_eq!(reassembler.add(&to_one[0]), None);
+    assert_eq!(reassembler.pending(), 2);
+    assert!(reassembler.add(&to_one[1]).is_some());
+    assert_eq!(reassembler.pending(), 1);
+}
+
+#[test]
+fn a_whole_message_passes_straight_through() {
+    let pdu = template().short_message(b"hello").build().unwrap();
+
+    assert_eq!(Part::of(&pdu), None);
+    assert_eq!(Reassembler::new().add(&pdu), Some(b"hello".to_vec()));
+}
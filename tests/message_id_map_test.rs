@@ -0,0 +1,172 @@
+use async_trait::async_trait;
+use smpp::client::{BindMode, Client};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smsc::{
+    BindData, BindError, MessageIdMap, Smsc, SmscLogic, SubmitSmError,
+};
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::{
+    DeliverEsmClass, DeliverSmPdu, Pdu, SubmitSmPdu, SubmitSmRespPdu,
+};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::{submit_sm_to, TestServer};
+
+struct Logic {}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        _pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Ok((
+            SubmitSmRespPdu::new("1234").unwrap(),
+            MessageUniqueKey::new(
+                String::from("testsystem"),
+                String::from("1234"),
+                String::from("447700900123"),
+            ),
+        ))
+    }
+}
+
+struct Reversed {}
+
+impl MessageIdMap for Reversed {
+    fn external(&self, internal: &str) -> String {
+        format!("x{}", internal.chars().rev().collect::<String>())
+    }
+
+    fn internal(&self, external: &str) -> Option<String> {
+        external
+            .strip_prefix('x')
+            .map(|id| id.chars().rev().collect())
+    }
+}
+
+fn receipt(text: &[u8], tlvs: &[Tlv]) -> Pdu {
+    let body = DeliverSmPdu::new(
+        "",
+        1,
+        1,
+        "447700900123",
+        5,
+        0,
+        "MyCompany",
+        DeliverEsmClass::SmscDeliveryReceipt as u8,
+        0,
+        0,
+        "",
+        "",
+        0,
+        0,
+        0,
+        0,
+        text,
+        Tlvs::from(tlvs),
+    )
+    .unwrap();
+    Pdu::new(0, 9, body.into()).unwrap()
+}
+
+async fn server_with_map() -> TestServer {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    server
+        .smsc
+        .lock()
+        .await
+        .set_message_id_map(Arc::new(Reversed {}));
+    server
+}
+
+#[tokio::test]
+async fn submit_sm_resp_carries_the_external_message_id() {
+    let server = server_with_map().await;
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transmitter, "esme1", "secret", "")
+        .await
+        .unwrap();
+
+    let resp = client
+        .submit_sm(submit_sm_to("447700900123"))
+        .await
+        .unwrap();
+
+    assert_eq!(resp.message_id.as_deref(), Some("x4321"));
+    assert_eq!(Reversed {}.internal("x4321").as_deref(), Some("1234"));
+}
+
+#[tokio::test]
+async fn delivery_receipts_carry_the_external_message_id() {
+    let server = server_with_map().await;
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transceiver, "esme1", "secret", "")
+        .await
+        .unwrap();
+    client
+        .submit_sm(submit_sm_to("447700900123"))
+        .await
+        .unwrap();
+
+    server
+        .receive_pdu(
+            "testsystem",
+            receipt(
+                b"id:1234 stat:DELIVRD err:000 text:id:1234",
+                &[Tlv::new(KnownTlvTag::receipted_message_id, b"1234\0")],
+            ),
+        )
+        .await
+        .unwrap();
+
+    let receipt = client.next_receipt().await.unwrap();
+    assert_eq!(receipt.message_id.as_deref(), Some("x4321"));
+    let delivered = receipt.pdu;
+    assert_eq!(
+        delivered.0.short_message.value,
+        b"id:x4321 stat:DELIVRD err:000 text:id:1234"
+    );
+    assert_eq!(
+        delivered
+            .0
+            .tlvs
+            .get(KnownTlvTag::receipted_message_id)
+            .unwrap()
+            .value,
+        b"x4321\0"
+    );
+}
+
+#[tokio::test]
+async fn receipts_without_a_tlv_are_rewritten_in_their_text() {
+    let server = server_with_map().await;
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transceiver, "esme1", "secret", "")
+        .await
+        .unwrap();
+    client
+        .submit_sm(submit_sm_to("447700900123"))
+        .await
+        .unwrap();
+
+    server
+        .receive_pdu("testsystem", receipt(b"ID:1234 stat:DELIVRD", &[]))
+        .await
+        .unwrap();
+
+    let delivered = client.next_receipt().await.unwrap().pdu;
+    assert_eq!(delivered.0.short_message.value, b"ID:x4321 stat:DELIVRD");
+}
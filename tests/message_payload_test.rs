@@ -0,0 +1,110 @@
+use smpp::message_payload::{MessageBytes, MessageInBothFields};
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::{Pdu, SubmitSmPdu};
+use std::io::Cursor;
+use tokio::io::AsyncWriteExt;
+
+mod test_utils;
+
+use test_utils::{
+    submit_sm_to, DefaultLogic, TestClient, TestServer, TestSetup,
+};
+
+fn submit_sm(short_message: &[u8], tlvs: &[Tlv]) -> SubmitSmPdu {
+    let mut submit_sm = submit_sm_to("447700900123");
+    submit_sm.0.short_message.value = Vec::from(short_message);
+    submit_sm.0.tlvs = Tlvs::from(tlvs);
+    submit_sm
+}
+
+fn payload(value: &[u8]) -> Tlv {
+    Tlv::new(KnownTlvTag::message_payload, value)
+}
+
+async fn bytes(sequence_number: u32, body: SubmitSmPdu) -> Vec<u8> {
+    let mut ret = Vec::new();
+    Pdu::new(0, sequence_number, body.into())
+        .unwrap()
+        .write(&mut ret)
+        .await
+        .unwrap();
+    ret
+}
+
+#[test]
+fn message_bytes_come_from_short_message_or_message_payload() {
+    assert_eq!(submit_sm(b"hi", &[]).message_bytes().unwrap(), b"hi");
+
+    let long = vec![b'x'; 300];
+    assert_eq!(
+        submit_sm(b"", &[payload(&long)]).message_bytes().unwrap(),
+        long
+    );
+
+    assert_eq!(submit_sm(b"", &[]).message_bytes().unwrap(), b"");
+}
+
+#[test]
+fn message_in_both_fields_is_an_error() {
+    assert_eq!(
+        submit_sm(b"hi", &[payload(b"there")]).message_bytes(),
+        Err(MessageInBothFields)
+    );
+}
+
+#[tokio::test]
+async fn submit_sm_with_only_message_payload_is_parsed() {
+    let long = vec![b'x'; 300];
+    let written = bytes(3, submit_sm(b"", &[payload(&long)])).await;
+    let parsed = Pdu::parse(&mut Cursor::new(&written)).unwrap();
+    match parsed.body() {
+        smpp_pdu::pdu::PduBody::SubmitSm(body) => {
+            assert_eq!(body.message_bytes().unwrap(), long)
+        }
+        _ => panic!("Not a submit_sm"),
+    }
+}
+
+#[tokio::test]
+async fn smsc_rejects_submit_sm_with_message_in_both_fields() {
+    let mut t = TestSetup::new().await;
+    t.client.bind_transmitter().await;
+    t.client
+        .send_and_expect_response(
+            &bytes(3, submit_sm(b"hi", &[payload(b"there")])).await,
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\xc1\x00\x00\x00\x03",
+            //         submit_sm_resp ^^^^ ESME_ROPTPARNOTALLWD ^^^^
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn smsc_explains_rejected_submit_sm_when_configured() {
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.status_info_text = true
+    })
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transmitter().await;
+
+    client
+        .stream
+        .write_all(&bytes(3, submit_sm(b"hi", &[payload(b"there")])).await)
+        .await
+        .unwrap();
+
+    let header = client.read_n(16).await;
+    assert_eq!(
+        &header[4..],
+        b"\x80\x00\x00\x04\x00\x00\x00\xc1\x00\x00\x00\x03" // submit_sm_resp ^^^^ ESME_ROPTPARNOTALLWD ^^^^
+    );
+    let length =
+        u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+    let tlv = client.read_n(length as usize - 16).await;
+    assert_eq!(&tlv[..2], b"\x00\x1d"); // additional_status_info_text
+    assert_eq!(
+        std::str::from_utf8(&tlv[4..tlv.len() - 1]).unwrap(),
+        "ESME_ROPTPARNOTALLWD (0x000000C1): Optional Parameter not allowed"
+    );
+}
@@ -0,0 +1,67 @@
+use smpp::msisdn::{is_short_code, normalize_msisdn};
+
+mod test_utils;
+
+use test_utils::{new_submit_sm, DefaultLogic, TestClient, TestServer};
+
+#[test]
+fn international_numbers_lose_their_prefix_and_separators() {
+    assert_eq!(normalize_msisdn("+44 7700 900123", None), "447700900123");
+    assert_eq!(normalize_msisdn("0044-7700-900123", None), "447700900123");
+    assert_eq!(normalize_msisdn("447700900123", None), "447700900123");
+    assert_eq!(normalize_msisdn("+1 (555) 010.0199", None), "15550100199");
+}
+
+#[test]
+fn national_numbers_get_the_default_country_code() {
+    assert_eq!(normalize_msisdn("07700900123", Some("44")), "447700900123");
+    assert_eq!(normalize_msisdn("07700900123", Some("+44")), "447700900123");
+}
+
+#[test]
+fn national_numbers_are_unchanged_without_a_default_country_code() {
+    assert_eq!(normalize_msisdn("07700900123", None), "07700900123");
+    assert_eq!(normalize_msisdn("07700 900123", None), "07700900123");
+}
+
+#[test]
+fn short_codes_and_alphanumerics_are_unchanged() {
+    assert!(is_short_code("88222"));
+    assert!(is_short_code("882 22"));
+    assert!(!is_short_code("12"));
+    assert!(!is_short_code("4477009"));
+    assert!(!is_short_code("+88222"));
+    assert!(!is_short_code("MyCo"));
+
+    assert_eq!(normalize_msisdn("88222", Some("44")), "88222");
+    assert_eq!(normalize_msisdn("MyCompany", Some("44")), "MyCompany");
+    assert_eq!(normalize_msisdn("", Some("44")), "");
+}
+
+#[tokio::test]
+async fn routes_match_whichever_way_the_number_is_written() {
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.default_country_code = Some(String::from("44"))
+    })
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transmitter().await;
+
+    server.smsc.lock().await.pause_route("4477");
+
+    for (sequence_number, destination_addr) in
+        [(0x10, "+447700900123"), (0x11, "07700 900123")]
+    {
+        let mut expected =
+            Vec::from(&b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x58"[..]);
+        //                                           ESME_RTHROTTLED ^^^^
+        expected.extend(&u32::to_be_bytes(sequence_number));
+        client
+            .send_and_expect_response(
+                &new_submit_sm(sequence_number, destination_addr).await,
+                &expected,
+            )
+            .await;
+    }
+}
@@ -0,0 +1,118 @@
+use smpp::client::{BindMode, Client, ClientError};
+use smpp::command_id::CommandId;
+use smpp::outbind::OutbindPdu;
+use smpp::session_info::SessionInfo;
+use smpp::smpp_connection::{EsmeId, Frame, SmppConnection};
+use smpp::smsc::Smsc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::time::timeout;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+async fn written(frame: Frame) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    frame.write(&mut bytes).await.unwrap();
+    bytes
+}
+
+#[tokio::test]
+async fn outbind_is_written_and_parsed() {
+    let outbind = OutbindPdu::new(1, "esme1", "pw").unwrap();
+
+    let bytes = written(Frame::Outbind(outbind.clone())).await;
+
+    assert_eq!(
+        bytes,
+        b"\x00\x00\x00\x19\x00\x00\x00\x0b\x00\x00\x00\x00\x00\x00\x00\x01\
+          esme1\x00pw\x00"
+    );
+    assert_eq!(OutbindPdu::parse(&bytes).unwrap(), outbind);
+    assert_eq!(
+        Frame::parse(&bytes).unwrap().command_id(),
+        CommandId::Outbind
+    );
+}
+
+#[test]
+fn a_password_that_is_too_long_is_refused() {
+    assert!(OutbindPdu::new(1, "esme1", "much too long").is_err());
+}
+
+#[tokio::test]
+async fn an_esme_binds_as_a_receiver_when_outbound() {
+    let server = TestServer::start().await.unwrap();
+    let esme = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let esme_address = esme.local_addr().unwrap().to_string();
+    let accepted = tokio::spawn(async move {
+        let (stream, peer_addr) = esme.accept().await.unwrap();
+        Client::accept_outbind(
+            SmppConnection::new(stream, peer_addr),
+            "secret",
+            "type",
+        )
+        .await
+    });
+
+    Smsc::outbind(&server.smsc, &esme_address, "esme1", "secret")
+        .await
+        .unwrap();
+    let client = accepted.await.unwrap().unwrap();
+
+    assert_eq!(
+        client.session_info().map(|info| info.bind_mode),
+        Some(BindMode::Receiver)
+    );
+    let esme_id = EsmeId {
+        system_id: "esme1".parse().unwrap(),
+        system_type: "type".parse().unwrap(),
+    };
+    assert!(matches!(
+        server.smsc.lock().await.session_info(&esme_id),
+        Some(SessionInfo {
+            bind_mode: BindMode::Receiver,
+            ..
+        })
+    ));
+    client.unbind().await.unwrap();
+}
+
+#[tokio::test]
+async fn accept_outbind_refuses_a_connection_that_starts_otherwise() {
+    let esme = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let esme_address = esme.local_addr().unwrap();
+    let accepted = tokio::spawn(async move {
+        let (stream, peer_addr) = esme.accept().await.unwrap();
+        Client::accept_outbind(
+            SmppConnection::new(stream, peer_addr),
+            "secret",
+            "",
+        )
+        .await
+    });
+
+    // Something that is not an SMSC sends enquire_link instead
+    let other = Client::connect(esme_address).await.unwrap();
+    let _ = timeout(Duration::from_millis(100), other.enquire_link()).await;
+
+    assert!(matches!(
+        accepted.await.unwrap(),
+        Err(ClientError::UnexpectedResponse(CommandId::EnquireLink))
+    ));
+}
+
+#[tokio::test]
+async fn outbind_fails_if_the_esme_is_not_listening() {
+    let server = TestServer::start().await.unwrap();
+    let esme = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let esme_address = esme.local_addr().unwrap().to_string();
+    drop(esme);
+
+    assert!(
+        Smsc::outbind(&server.smsc, &esme_address, "esme1", "secret")
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,80 @@
+use smpp::parse_error::{ErrorSeverity, RecommendedStatus, Severity};
+use smpp::smpp_connection::Frame;
+use smpp_pdu::pdu::{
+    OctetStringCreationError, PduParseError, PduParseErrorBody, PduStatus,
+};
+
+#[test]
+fn bad_lengths_are_reported_as_invalid_command_length() {
+    for body in [
+        PduParseErrorBody::LengthTooLong(100_000),
+        PduParseErrorBody::LengthTooShort(4),
+        PduParseErrorBody::LengthLongerThanPdu(30),
+        PduParseErrorBody::NotEnoughBytes,
+    ] {
+        assert!(body.recommended_status() == PduStatus::ESME_RINVCMDLEN);
+    }
+}
+
+#[test]
+fn bad_fields_are_reported_as_invalid_message_length() {
+    assert!(
+        PduParseErrorBody::IncorrectLength(300, String::from("too long"))
+            .recommended_status()
+            == PduStatus::ESME_RINVMSGLEN
+    );
+    assert!(
+        PduParseErrorBody::OctetStringCreationError(
+            OctetStringCreationError::TooLong(16)
+        )
+        .recommended_status()
+            == PduStatus::ESME_RINVMSGLEN
+    );
+}
+
+#[test]
+fn unknown_command_id_is_reported_as_invalid_command_id() {
+    assert!(
+        PduParseErrorBody::UnknownCommandId.recommended_status()
+            == PduStatus::ESME_RINVCMDID
+    );
+    assert!(
+        PduParseError::new(PduParseErrorBody::UnknownCommandId)
+            .recommended_status()
+            == PduStatus::ESME_RINVCMDID
+    );
+}
+
+#[test]
+fn other_errors_are_reported_as_system_errors() {
+    assert!(
+        PduParseErrorBody::InvalidSequenceNumber.recommended_status()
+            == PduStatus::ESME_RSYSERR
+    );
+    assert!(
+        PduParseErrorBody::StatusIsZero.recommended_status()
+            == PduStatus::ESME_RSYSERR
+    );
+}
+
+#[test]
+fn malformed_frames_are_answered_with_a_length_error() {
+    // A submit_sm whose service_type runs to the end without a NULL
+    let unterminated =
+        b"\x00\x00\x00\x15\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x01abcde";
+    // A submit_sm that ends before esm_class
+    let truncated = b"\x00\x00\x00\x17\x00\x00\x00\x04\x00\x00\x00\x00\
+        \x00\x00\x00\x01\x00\x01\x01\x00\x01\x01\x00";
+    // A submit_sm whose service_type is not ASCII
+    let not_ascii = b"\x00\x00\x00\x15\x00\x00\x00\x04\x00\x00\x00\x00\
+        \x00\x00\x00\x01\xff\xfe\x00\x01\x01";
+
+    let status = |frame: &[u8]| {
+        let e = Frame::parse(frame).unwrap_err();
+        assert_eq!(e.severity(), ErrorSeverity::RequestRecoverable);
+        e.recommended_status()
+    };
+    assert!(status(unterminated) == PduStatus::ESME_RINVMSGLEN);
+    assert!(status(not_ascii) == PduStatus::ESME_RINVMSGLEN);
+    assert!(status(truncated) == PduStatus::ESME_RINVCMDLEN);
+}
@@ -0,0 +1,271 @@
+use futures::FutureExt;
+use smpp::parse_options::{ParseMode, ParseOptions, ParseWarning};
+use smpp::pdu_write::write_pdu;
+use smpp::smpp_connection::{Frame, SmppConnection};
+use smpp::submit_sm_builder::SubmitSmBuilder;
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv};
+use smpp_pdu::pdu::{CheckOutcome, Pdu, PduBody};
+use std::io::Cursor;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+
+mod test_utils;
+
+use test_utils::{DefaultLogic, TestClient, TestServer};
+
+const ENQUIRE_LINK: &[u8; 0x10] =
+    b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12";
+
+fn tight() -> ParseOptions {
+    ParseOptions {
+        max_pdu_length: 100,
+        ..ParseOptions::default()
+    }
+}
+
+fn relaxed() -> ParseOptions {
+    ParseOptions {
+        max_pdu_length: 200_000,
+        ..ParseOptions::default()
+    }
+}
+
+/// A submit_sm too long for smpp_pdu, with two large TLVs
+fn huge_submit_sm() -> Vec<u8> {
+    let submit_sm = SubmitSmBuilder::new()
+        .destination_addr("447700900123")
+        .tlv(Tlv::new(KnownTlvTag::message_payload, &[b'a'; 60_000]))
+        .tlv(Tlv::new(
+            KnownTlvTag::user_message_reference,
+            &[b'b'; 20_000],
+        ))
+        .build()
+        .unwrap();
+    let pdu = Pdu::new(0, 5, submit_sm.into()).unwrap();
+    let mut bytes = Vec::new();
+    write_pdu(&pdu, &mut bytes).now_or_never().unwrap().unwrap();
+    assert!(bytes.len() > 80_000);
+    bytes
+}
+
+fn lenient() -> ParseOptions {
+    ParseOptions {
+        mode: ParseMode::Lenient,
+        ..ParseOptions::default()
+    }
+}
+
+// bind_transmitter_resp whose system_id runs to the end without a NULL
+const BIND_RESP_WITHOUT_NULL: &[u8] =
+    b"\x00\x00\x00\x14\x80\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x02SMSC";
+
+// bind_transmitter with a Latin-1 e-acute in its system_id
+const BIND_WITH_NON_ASCII: &[u8] =
+    b"\x00\x00\x00\x29\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x07\
+    esm\xe9id\0password\0type\0\x34\x00\x00\0";
+
+// submit_sm_resp with ESME_RSUBMITFAIL and a message_id anyway
+const ERROR_RESP_WITH_BODY: &[u8] =
+    b"\x00\x00\x00\x14\x80\x00\x00\x04\x00\x00\x00\x45\x00\x00\x00\x02abc\0";
+
+fn written(frame: Frame) -> Vec<u8> {
+    match frame {
+        Frame::Pdu(pdu) => {
+            let mut written = Vec::new();
+            write_pdu(&pdu, &mut written)
+                .now_or_never()
+                .unwrap()
+                .unwrap();
+            written
+        }
+        frame => panic!("Unexpected {:?}", frame),
+    }
+}
+
+async fn connect() -> (TcpStream, SmppConnection) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (server_stream, addr) = listener.accept().await.unwrap();
+    (client, SmppConnection::new(server_stream, addr))
+}
+
+#[test]
+fn lengths_over_the_maximum_are_rejected_as_soon_as_they_arrive() {
+    let err = tight()
+        .check(&mut Cursor::new(&b"\x00\x00\x00\x65"[..]))
+        .unwrap_err();
+    assert!(err.to_string().contains("101"), "{}", err);
+
+    let err = ParseOptions::default()
+        .check(&mut Cursor::new(&b"\x00\x01\x11\x71"[..]))
+        .unwrap_err();
+    assert!(err.to_string().contains("70001"), "{}", err);
+
+    let mut cursor = Cursor::new(&b"\x00\x00\x00\x04"[..]);
+    assert!(tight().check(&mut cursor).is_err());
+}
+
+#[test]
+fn check_moves_to_the_end_of_a_whole_frame() {
+    let mut bytes = ENQUIRE_LINK.to_vec();
+    bytes.extend(&ENQUIRE_LINK[..5]);
+
+    let mut cursor = Cursor::new(&bytes[..]);
+    assert_eq!(tight().check(&mut cursor).unwrap(), CheckOutcome::Ready);
+    assert_eq!(cursor.position(), 16);
+    assert_eq!(
+        tight().check(&mut cursor).unwrap(),
+        CheckOutcome::Incomplete
+    );
+    assert_eq!(cursor.position(), 16);
+}
+
+#[test]
+fn submit_sm_longer_than_smpp_pdu_allows_can_be_parsed() {
+    let bytes = huge_submit_sm();
+
+    assert!(ParseOptions::default()
+        .check(&mut Cursor::new(&bytes[..]))
+        .is_err());
+    assert!(Frame::parse(&bytes).is_err());
+
+    assert_eq!(
+        relaxed().check(&mut Cursor::new(&bytes[..])).unwrap(),
+        CheckOutcome::Ready
+    );
+    match relaxed().parse_frame(&bytes).unwrap() {
+        Frame::Pdu(pdu) => {
+            assert_eq!(pdu.sequence_number.value, 5);
+            assert!(matches!(pdu.body(), PduBody::SubmitSm(_)));
+            let mut written = Vec::new();
+            write_pdu(&pdu, &mut written)
+                .now_or_never()
+                .unwrap()
+                .unwrap();
+            assert_eq!(written, bytes);
+        }
+        frame => panic!("Unexpected {:?}", frame),
+    }
+}
+
+#[test]
+fn strict_parsing_rejects_common_violations() {
+    for bytes in &[
+        BIND_RESP_WITHOUT_NULL,
+        BIND_WITH_NON_ASCII,
+        ERROR_RESP_WITH_BODY,
+    ] {
+        assert!(ParseOptions::default().parse_frame(bytes).is_err());
+    }
+}
+
+#[test]
+fn lenient_parsing_repairs_common_violations_with_warnings() {
+    let parsed = lenient()
+        .parse_frame_with_warnings(BIND_RESP_WITHOUT_NULL)
+        .unwrap();
+    assert_eq!(
+        parsed.warnings,
+        vec![ParseWarning::MissingNull { field: "system_id" }]
+    );
+    assert_eq!(
+        written(parsed.frame),
+        b"\x00\x00\x00\x15\x80\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x02SMSC\0"
+    );
+
+    let parsed = lenient()
+        .parse_frame_with_warnings(BIND_WITH_NON_ASCII)
+        .unwrap();
+    assert_eq!(
+        parsed.warnings,
+        vec![ParseWarning::NonAscii { field: "system_id" }]
+    );
+    let mut expected = BIND_WITH_NON_ASCII.to_vec();
+    expected[0x13] = b'?';
+    assert_eq!(written(parsed.frame), expected);
+
+    let parsed = lenient()
+        .parse_frame_with_warnings(ERROR_RESP_WITH_BODY)
+        .unwrap();
+    assert_eq!(
+        parsed.warnings,
+        vec![ParseWarning::BodyWithErrorStatus {
+            command_status: 0x45
+        }]
+    );
+    assert_eq!(
+        written(parsed.frame),
+        b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x45\x00\x00\x00\x02"
+    );
+}
+
+#[test]
+fn lenient_parsing_warns_only_about_violations() {
+    let parsed = lenient().parse_frame_with_warnings(ENQUIRE_LINK).unwrap();
+    assert!(parsed.warnings.is_empty());
+    assert_eq!(written(parsed.frame), ENQUIRE_LINK);
+}
+
+#[test]
+fn lenient_parsing_gives_the_original_error_for_what_it_cannot_repair() {
+    // bind_transmitter cut off after its password
+    let bytes: &[u8] = b"\x00\x00\x00\x19\x00\x00\x00\x02\x00\x00\x00\x00\
+        \x00\x00\x00\x07id\0pass\0";
+    let strict = ParseOptions::default().parse_frame(bytes).unwrap_err();
+    let lenient = lenient().parse_frame(bytes).unwrap_err();
+    assert_eq!(lenient.to_string(), strict.to_string());
+}
+
+#[tokio::test]
+async fn connections_read_with_their_parse_options() {
+    let (mut client, connection) = connect().await;
+    connection.set_parse_options(relaxed());
+    client.write_all(&huge_submit_sm()).await.unwrap();
+    assert!(connection.read_pdu().await.unwrap().is_some());
+
+    connection.set_parse_options(tight());
+    client.write_all(&huge_submit_sm()[..4]).await.unwrap();
+    let err = connection.read_pdu().await.unwrap_err();
+    assert!(err.to_string().contains("too long"), "{}", err);
+}
+
+#[tokio::test]
+async fn the_smsc_rejects_pdus_over_its_max_pdu_length() {
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.max_pdu_length = 40
+    })
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+
+    // bind_transmitter is 0x29 bytes long
+    client
+        .send_and_expect_error_response(
+            b"\x00\x00\x00\x29\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x07\
+            esmeid\0password\0type\0\x34\x00\x00\0",
+            b"\x00\x00\x00\x10\x80\x00\x00\x02\x00\x00\x00\x02\x00\x00\x00\x07",
+            //                                 ESME_RINVCMDLEN ^^^^^^^^^^^^^^^^
+            "unexpected end of file",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn the_smsc_accepts_non_compliant_pdus_when_lenient() {
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.lenient_parsing = true
+    })
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+
+    client
+        .send_and_expect_response(
+            BIND_WITH_NON_ASCII,
+            b"\x00\x00\x00\x1b\x80\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x07\
+            TestServer\0",
+        )
+        .await;
+}
@@ -0,0 +1,156 @@
+#![cfg(feature = "pcap")]
+
+use smpp::pcap::{tcp_segments, TcpSegment};
+use std::process::Command;
+use std::time::Duration;
+
+const ENQUIRE_LINK: &[u8; 16] =
+    b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x01";
+const ENQUIRE_LINK_RESP: &[u8; 16] =
+    b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x01";
+
+const ESME: ([u8; 4], u16) = ([10, 0, 0, 1], 40000);
+const SMSC: ([u8; 4], u16) = ([10, 0, 0, 2], 2775);
+
+struct Packet {
+    micros: u32,
+    from: ([u8; 4], u16),
+    to: ([u8; 4], u16),
+    seq: u32,
+    syn: bool,
+    payload: &'static [u8],
+}
+
+/// A little-endian, microsecond pcap of Ethernet frames
+fn pcap(packets: &[Packet]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+    out.extend_from_slice(&[2, 0, 4, 0]);
+    out.extend_from_slice(&[0; 8]);
+    out.extend_from_slice(&65535u32.to_le_bytes());
+    out.extend_from_slice(&1u32.to_le_bytes());
+    for packet in packets {
+        let mut frame = vec![0; 12];
+        frame.extend_from_slice(&[0x08, 0x00]);
+        let total_length = (20 + 20 + packet.payload.len()) as u16;
+        frame.extend_from_slice(&[0x45, 0]);
+        frame.extend_from_slice(&total_length.to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0x40, 0, 64, 6, 0, 0]);
+        frame.extend_from_slice(&packet.from.0);
+        frame.extend_from_slice(&packet.to.0);
+        frame.extend_from_slice(&packet.from.1.to_be_bytes());
+        frame.extend_from_slice(&packet.to.1.to_be_bytes());
+        frame.extend_from_slice(&packet.seq.to_be_bytes());
+        frame.extend_from_slice(&[0; 4]);
+        frame.extend_from_slice(&[0x50, if packet.syn { 0x02 } else { 0x18 }]);
+        frame.extend_from_slice(&[0xff, 0xff, 0, 0, 0, 0]);
+        frame.extend_from_slice(packet.payload);
+
+        out.extend_from_slice(&1_600_000_000u32.to_le_bytes());
+        out.extend_from_slice(&packet.micros.to_le_bytes());
+        out.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        out.extend_from_slice(&frame);
+    }
+    out
+}
+
+/// The ESME sends enquire_link in two pieces then again in full, as a
+/// retransmission, and the SMSC responds.
+fn enquire_link_capture() -> Vec<u8> {
+    let packet = |micros, from, to, seq, syn, payload| Packet {
+        micros,
+        from,
+        to,
+        seq,
+        syn,
+        payload,
+    };
+    pcap(&[
+        packet(0, ESME, SMSC, 1000, true, b""),
+        packet(1000, ESME, SMSC, 1001, false, &ENQUIRE_LINK[..8]),
+        packet(2000, ESME, SMSC, 1009, false, &ENQUIRE_LINK[8..]),
+        packet(3000, ESME, SMSC, 1001, false, &ENQUIRE_LINK[..]),
+        packet(4000, SMSC, ESME, 5000, false, &ENQUIRE_LINK_RESP[..]),
+    ])
+}
+
+#[test]
+fn tcp_payloads_are_read_without_retransmissions() {
+    let segments = tcp_segments(&enquire_link_capture()).unwrap();
+
+    let segment =
+        |micros, from: ([u8; 4], u16), to: ([u8; 4], u16), payload: &[u8]| {
+            TcpSegment {
+                timestamp: Duration::from_secs(1_600_000_000)
+                    + Duration::from_micros(micros),
+                source: (from.0, from.1).into(),
+                destination: (to.0, to.1).into(),
+                payload: payload.to_vec(),
+            }
+        };
+    assert_eq!(
+        segments,
+        vec![
+            segment(1000, ESME, SMSC, &ENQUIRE_LINK[..8]),
+            segment(2000, ESME, SMSC, &ENQUIRE_LINK[8..]),
+            segment(4000, SMSC, ESME, &ENQUIRE_LINK_RESP[..]),
+        ]
+    );
+}
+
+#[test]
+fn files_that_are_not_pcap_are_rejected() {
+    let err = tcp_segments(
+        b"\x0a\x0d\x0d\x0a\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "pcapng is not supported: save the capture as pcap"
+    );
+
+    let err = tcp_segments(&[0; 24]).unwrap_err();
+    assert_eq!(err.to_string(), "Not a pcap file (magic 0x00000000)");
+
+    let mut truncated = enquire_link_capture();
+    truncated.pop();
+    assert!(tcp_segments(&truncated).is_err());
+}
+
+#[test]
+fn smpp_dump_prints_the_pdus_in_each_stream() {
+    let path = std::env::temp_dir()
+        .join(format!("smpp-dump-test-{}.pcap", std::process::id()));
+    std::fs::write(&path, enquire_link_capture()).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_smpp-dump"))
+        .arg("--pcap")
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with(
+            "PDU 1 10.0.0.1:40000 -> 10.0.0.2:2775 at 0.001000s, \
+            stream offset 0:\n\
+            Short Message Peer to Peer, Command: Enquire_link, Seq: 1"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(
+            "PDU 2 10.0.0.2:2775 -> 10.0.0.1:40000 at 0.003000s, \
+            stream offset 0:\n\
+            Short Message Peer to Peer, \
+            Command: Enquire_link - resp, Seq: 1"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("PDU 3"), "{}", stdout);
+}
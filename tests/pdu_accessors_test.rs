@@ -0,0 +1,48 @@
+use smpp::pdu_accessors::{BindAccessors, BindRespAccessors, SmAccessors};
+use smpp::submit_sm_builder::SubmitSmBuilder;
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{BindTransmitterPdu, BindTransmitterRespPdu};
+
+#[test]
+fn bind_fields_can_be_read() {
+    let bind =
+        BindTransmitterPdu::new("esme1", "secret", "VMS", 0x34, 1, 2, "^44")
+            .unwrap();
+
+    assert_eq!(bind.system_id(), "esme1");
+    assert_eq!(bind.password(), "secret");
+    assert_eq!(bind.system_type(), "VMS");
+    assert_eq!(bind.interface_version(), 0x34);
+    assert_eq!(bind.addr_ton(), 1);
+    assert_eq!(bind.addr_npi(), 2);
+    assert_eq!(bind.address_range(), "^44");
+}
+
+#[test]
+fn a_bind_resp_has_a_system_id_only_if_it_succeeded() {
+    let resp = BindTransmitterRespPdu::new("smsc").unwrap();
+
+    assert_eq!(resp.system_id(), Some(String::from("smsc")));
+    assert_eq!(BindTransmitterRespPdu::new_error().system_id(), None);
+}
+
+#[test]
+fn submit_sm_fields_can_be_read() {
+    let submit_sm = SubmitSmBuilder::new()
+        .service_type("CMT")
+        .source_addr("MyCompany")
+        .destination_addr("447700900123")
+        .validity_period("000001000000000R")
+        .data_coding(8)
+        .short_message(b"hello")
+        .build()
+        .unwrap();
+
+    assert_eq!(submit_sm.service_type(), "CMT");
+    assert_eq!(SmAccessors::source_addr(&submit_sm), "MyCompany");
+    assert_eq!(SmAccessors::destination_addr(&submit_sm), "447700900123");
+    assert_eq!(submit_sm.validity_period(), "000001000000000R");
+    assert_eq!(submit_sm.data_coding(), 8);
+    assert_eq!(submit_sm.short_message(), b"hello");
+    assert_eq!(submit_sm.tlvs(), &Tlvs::new());
+}
@@ -0,0 +1,81 @@
+use smpp::pdu_clone::PduClone;
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::{
+    BindTransceiverPdu, BindTransceiverRespPdu, BindTransmitterRespPdu,
+    DeliverSmPdu, EnquireLinkPdu, GenericNackPdu, Pdu, SubmitSmPdu,
+    SubmitSmRespPdu,
+};
+
+fn assert_clone_is_equal(pdu: Pdu) {
+    assert_eq!(pdu.pdu_clone(), pdu);
+}
+
+#[test]
+fn pdus_without_bodies_can_be_cloned() {
+    assert_clone_is_equal(
+        Pdu::new(0, 1, EnquireLinkPdu::new().into()).unwrap(),
+    );
+    assert_clone_is_equal(
+        Pdu::new(3, 2, GenericNackPdu::new_error().into()).unwrap(),
+    );
+}
+
+#[test]
+fn binds_and_their_responses_can_be_cloned() {
+    assert_clone_is_equal(
+        Pdu::new(
+            0,
+            1,
+            BindTransceiverPdu::new("sys", "pass", "type", 0x34, 1, 1, "44")
+                .unwrap()
+                .into(),
+        )
+        .unwrap(),
+    );
+    assert_clone_is_equal(
+        Pdu::new(0, 1, BindTransceiverRespPdu::new("smsc").unwrap().into())
+            .unwrap(),
+    );
+    assert_clone_is_equal(
+        Pdu::new(0x0e, 1, BindTransmitterRespPdu::new_error().into()).unwrap(),
+    );
+}
+
+#[test]
+fn short_messages_and_their_tlvs_can_be_cloned() {
+    let tlvs = Tlvs::from(&[
+        Tlv::new(KnownTlvTag::user_message_reference, &[0, 1]),
+        Tlv::new_unknown(0x1400, &[3]),
+    ]);
+    let submit_sm = SubmitSmPdu::new(
+        "",
+        1,
+        1,
+        "447700900123",
+        1,
+        1,
+        "447700900456",
+        0,
+        0,
+        0,
+        "",
+        "",
+        1,
+        0,
+        0,
+        0,
+        b"hello",
+        tlvs,
+    )
+    .unwrap();
+    let deliver_sm = DeliverSmPdu(submit_sm.0.pdu_clone());
+
+    assert_clone_is_equal(Pdu::new(0, 3, submit_sm.into()).unwrap());
+    assert_clone_is_equal(Pdu::new(0, 4, deliver_sm.into()).unwrap());
+    assert_clone_is_equal(
+        Pdu::new(0, 3, SubmitSmRespPdu::new("abc").unwrap().into()).unwrap(),
+    );
+    assert_clone_is_equal(
+        Pdu::new(0x45, 3, SubmitSmRespPdu::new_error().into()).unwrap(),
+    );
+}
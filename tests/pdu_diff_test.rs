@@ -0,0 +1,117 @@
+use smpp::pdu_diff::{diff, FieldDiff};
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::{
+    BindTransmitterRespPdu, EnquireLinkPdu, Pdu, SubmitSmPdu, SubmitSmRespPdu,
+};
+
+fn submit_sm(dest_addr_npi: u8, tlvs: &[Tlv]) -> Pdu {
+    Pdu::new(
+        0,
+        7,
+        SubmitSmPdu::new(
+            "",
+            1,
+            1,
+            "447700900123",
+            1,
+            dest_addr_npi,
+            "447700900456",
+            0,
+            0,
+            0,
+            "",
+            "",
+            1,
+            0,
+            0,
+            0,
+            b"hello",
+            Tlvs::from(tlvs),
+        )
+        .unwrap()
+        .into(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn identical_pdus_have_no_differences() {
+    let tlvs = [Tlv::new(KnownTlvTag::user_message_reference, &[0, 1])];
+    let differences = diff(&submit_sm(1, &tlvs), &submit_sm(1, &tlvs));
+    assert!(differences.is_empty());
+    assert_eq!(differences.to_string(), "");
+}
+
+#[test]
+fn differing_fields_are_named_with_both_values() {
+    let differences = diff(&submit_sm(1, &[]), &submit_sm(0, &[]));
+    assert_eq!(
+        differences.0,
+        vec![FieldDiff {
+            field: String::from("dest_addr_npi"),
+            left: String::from("1"),
+            right: String::from("0"),
+        }]
+    );
+    assert_eq!(differences.to_string(), "dest_addr_npi: 1 != 0\n");
+}
+
+#[test]
+fn header_fields_are_compared() {
+    let left =
+        Pdu::new(0, 1, SubmitSmRespPdu::new("abc").unwrap().into()).unwrap();
+    let right = Pdu::new(0x45, 2, SubmitSmRespPdu::new_error().into()).unwrap();
+    assert_eq!(
+        diff(&left, &right).to_string(),
+        "command_status: ESME_ROK (0x00000000) != ESME_RSUBMITFAIL (0x00000045)\n\
+         sequence_number: 1 != 2\n\
+         message_id: \"abc\" != <absent>\n"
+    );
+}
+
+#[test]
+fn different_operations_compare_only_the_header() {
+    let left = Pdu::new(0, 1, EnquireLinkPdu::new().into()).unwrap();
+    let right = submit_sm(1, &[]);
+    assert_eq!(
+        diff(&left, &right).to_string(),
+        "command_id: 0x00000015 != 0x00000004\nsequence_number: 1 != 7\n"
+    );
+}
+
+#[test]
+fn bind_resp_system_ids_are_compared() {
+    let left = Pdu::new(0, 1, BindTransmitterRespPdu::new("a").unwrap().into())
+        .unwrap();
+    let right =
+        Pdu::new(0, 1, BindTransmitterRespPdu::new("b").unwrap().into())
+            .unwrap();
+    assert_eq!(
+        diff(&left, &right).to_string(),
+        "system_id: \"a\" != \"b\"\n"
+    );
+}
+
+#[cfg(not(feature = "redact-message-content"))]
+#[test]
+fn tlvs_are_compared_by_tag_regardless_of_order() {
+    let reference = Tlv::new(KnownTlvTag::user_message_reference, &[0, 1]);
+    let port = Tlv::new(KnownTlvTag::source_port, &[0, 2]);
+    let unknown = Tlv::new_unknown(0x1400, &[3]);
+
+    let left = submit_sm(1, &[reference.clone(), port.clone()]);
+    let right = submit_sm(1, &[port, reference, unknown]);
+    assert_eq!(
+        diff(&left, &right).to_string(),
+        "tlv 0x1400: <absent> != 03\n"
+    );
+
+    let left =
+        submit_sm(1, &[Tlv::new(KnownTlvTag::user_message_reference, &[0, 1])]);
+    let right =
+        submit_sm(1, &[Tlv::new(KnownTlvTag::user_message_reference, &[0, 9])]);
+    assert_eq!(
+        diff(&left, &right).to_string(),
+        "tlv user_message_reference: 0001 != 0009\n"
+    );
+}
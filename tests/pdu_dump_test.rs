@@ -0,0 +1,63 @@
+use smpp::pdu_dump::{hex_dump, Dump, PduDump};
+use smpp_pdu::pdu::{BindTransmitterPdu, EnquireLinkPdu, Pdu};
+
+#[test]
+fn bytes_are_dumped_16_to_a_line_with_ascii() {
+    let bytes =
+        b"\x00\x00\x00\x1b\x80\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x02\
+        TestServer\0";
+
+    assert_eq!(
+        hex_dump(bytes),
+        "0000  00 00 00 1b 80 00 00 02  00 00 00 00 00 00 00 02   \
+            ........ ........\n\
+        0010  54 65 73 74 53 65 72 76  65 72 00                  \
+            TestServ er.\n"
+    );
+}
+
+#[test]
+fn a_pdu_dump_shows_its_fields_then_its_bytes() {
+    let pdu = Pdu::new(0, 18, EnquireLinkPdu::new().into()).unwrap();
+
+    assert_eq!(
+        pdu.dump().to_string(),
+        "Short Message Peer to Peer, Command: Enquire_link, Seq: 18, Len: 16\n\
+        \x20   Length: 16\n\
+        \x20   Operation: Enquire_link (0x00000015)\n\
+        \x20   Sequence #: 18\n\
+        \n\
+        0000  00 00 00 10 00 00 00 15  00 00 00 00 00 00 00 12   \
+            ........ ........\n"
+    );
+}
+
+#[test]
+fn binds_can_be_dumped() {
+    let bind =
+        BindTransmitterPdu::new("esme1", "pw", "", 0x34, 0, 0, "").unwrap();
+    let pdu = Pdu::new(0, 1, bind.into()).unwrap();
+
+    let text = pdu.dump().to_string();
+
+    assert!(text.contains("    System ID: esme1\n"), "{}", text);
+    if cfg!(feature = "redact-message-content") {
+        assert!(text.ends_with("[Body redacted]\n"), "{}", text);
+    } else {
+        assert!(text.contains("0010  65 73 6d 65 31 00"), "{}", text);
+    }
+}
+
+#[test]
+fn bytes_that_do_not_parse_are_still_dumped() {
+    let text = PduDump::from_bytes(b"\x00\x00\x00\x10\x00\x00").to_string();
+
+    assert_eq!(
+        text,
+        "Short Message Peer to Peer\n\
+        \x20   [Malformed Packet: SMPP]\n\
+        \n\
+        0000  00 00 00 10 00 00                                  \
+            ......\n"
+    );
+}
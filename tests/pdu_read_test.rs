@@ -0,0 +1,78 @@
+use smpp::pdu_read::{read_frame, read_pdu};
+use smpp::smpp_connection::Frame;
+use smpp_pdu::pdu::{EnquireLinkPdu, Pdu};
+use tokio::io::{AsyncWriteExt, BufReader};
+
+const ENQUIRE_LINK: &[u8; 0x10] =
+    b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12";
+const UNBIND: &[u8; 0x10] =
+    b"\x00\x00\x00\x10\x00\x00\x00\x06\x00\x00\x00\x00\x00\x00\x00\x13";
+
+fn enquire_link(sequence_number: u32) -> Pdu {
+    Pdu::new(0, sequence_number, EnquireLinkPdu::new().into()).unwrap()
+}
+
+#[tokio::test]
+async fn pdus_are_read_one_at_a_time_until_the_end() {
+    let bytes = [&ENQUIRE_LINK[..], &ENQUIRE_LINK[..]].concat();
+    let mut reader = BufReader::new(&bytes[..]);
+
+    assert_eq!(
+        read_pdu(&mut reader).await.unwrap(),
+        Some(enquire_link(0x12))
+    );
+    assert_eq!(
+        read_pdu(&mut reader).await.unwrap(),
+        Some(enquire_link(0x12))
+    );
+    assert_eq!(read_pdu(&mut reader).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn reading_waits_for_the_whole_pdu() {
+    let (mut writer, reader) = tokio::io::duplex(64);
+    tokio::spawn(async move {
+        for byte in ENQUIRE_LINK {
+            writer.write_all(&[*byte]).await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        }
+    });
+
+    let pdu = read_pdu(&mut BufReader::new(reader)).await.unwrap();
+
+    assert_eq!(pdu, Some(enquire_link(0x12)));
+}
+
+#[tokio::test]
+async fn frames_smpp_pdu_lacks_can_be_read() {
+    let mut reader = BufReader::new(&UNBIND[..]);
+
+    let frame = read_frame(&mut reader).await.unwrap();
+
+    assert!(matches!(frame, Some(Frame::Unbind(_))));
+}
+
+#[tokio::test]
+async fn ending_part_way_through_a_pdu_is_an_error() {
+    for length in &[2, 10] {
+        let mut reader = BufReader::new(&ENQUIRE_LINK[..*length]);
+
+        let err = read_pdu(&mut reader).await.unwrap_err();
+
+        assert!(
+            err.to_string().contains("before finding all fields"),
+            "{}",
+            err
+        );
+    }
+}
+
+#[tokio::test]
+async fn bad_lengths_fail_before_the_body_is_read() {
+    let mut reader = BufReader::new(&b"\x00\x01\x11\x71\x00\x00\x00\x15"[..]);
+    //                                  ^^^^^^^^^^^^^^^^ 70001 bytes
+
+    let err = read_pdu(&mut reader).await.unwrap_err();
+
+    assert!(err.to_string().contains("70001"), "{}", err);
+}
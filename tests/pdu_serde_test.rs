@@ -0,0 +1,127 @@
+#![cfg(feature = "serde")]
+
+use serde_json::json;
+use smpp::pdu_serde::SerdePdu;
+use smpp::submit_sm_builder::SubmitSmBuilder;
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv};
+use smpp_pdu::pdu::{
+    BindTransmitterPdu, EnquireLinkPdu, GenericNackPdu, Pdu, SubmitSmRespPdu,
+};
+
+fn round_trip(pdu: Pdu) -> serde_json::Value {
+    let value = serde_json::to_value(SerdePdu(pdu)).unwrap();
+    let SerdePdu(parsed) = serde_json::from_value(value.clone()).unwrap();
+    assert_eq!(serde_json::to_value(SerdePdu(parsed)).unwrap(), value);
+    value
+}
+
+#[test]
+fn submit_sm_is_written_field_by_field() {
+    let submit_sm = SubmitSmBuilder::new()
+        .source_addr("MyCompany")
+        .destination_addr("447700900123")
+        .registered_delivery(1)
+        .short_message(b"hi")
+        .tlv(Tlv::new(KnownTlvTag::user_message_reference, &[0, 7]))
+        .build()
+        .unwrap();
+
+    let value = round_trip(Pdu::new(0, 3, submit_sm.into()).unwrap());
+
+    assert_eq!(value["command_id"], "0x00000004");
+    assert_eq!(value["command_status"], "ESME_ROK");
+    assert_eq!(value["sequence_number"], 3);
+    let sm = &value["body"]["sm"];
+    assert_eq!(sm["source_addr"], "MyCompany");
+    assert_eq!(sm["destination_addr"], "447700900123");
+    assert_eq!(sm["registered_delivery"], 1);
+    assert_eq!(sm["short_message"], "6869");
+    assert_eq!(sm["tlvs"], json!([{"tag": "0x0204", "value": "0007"}]));
+}
+
+#[test]
+fn binds_and_responses_are_written_field_by_field() {
+    let bind =
+        BindTransmitterPdu::new("esme1", "secret", "", 0x34, 1, 1, "").unwrap();
+    let value = round_trip(Pdu::new(0, 1, bind.into()).unwrap());
+    assert_eq!(value["body"]["bind"]["system_id"], "esme1");
+    assert_eq!(value["body"]["bind"]["interface_version"], 0x34);
+
+    let resp = SubmitSmRespPdu::new("abc").unwrap();
+    let value = round_trip(Pdu::new(0, 2, resp.into()).unwrap());
+    assert_eq!(
+        value["body"],
+        json!({"submit_sm_resp": {"message_id": "abc"}})
+    );
+}
+
+#[test]
+fn other_bodies_are_kept_as_hex() {
+    let value =
+        round_trip(Pdu::new(0, 4, EnquireLinkPdu::new().into()).unwrap());
+    assert_eq!(value["command_id"], "0x00000015");
+    assert_eq!(value["body"], json!({"raw": ""}));
+
+    let nack = GenericNackPdu::new_error();
+    let value = round_trip(Pdu::new(0x58, 5, nack.into()).unwrap());
+    assert_eq!(value["command_status"], "ESME_RTHROTTLED");
+}
+
+#[test]
+fn statuses_without_a_name_are_hex() {
+    let nack = GenericNackPdu::new_error();
+
+    let value = round_trip(Pdu::new(0x400, 6, nack.into()).unwrap());
+
+    assert_eq!(value["command_status"], "0x00000400");
+}
+
+#[test]
+fn pdus_that_would_not_parse_are_not_deserialized() {
+    let err = serde_json::from_value::<SerdePdu>(json!({
+        "command_id": "0x00000004",
+        "command_status": "ESME_ROK",
+        "sequence_number": 1,
+        "body": {"sm": {
+            "service_type": "",
+            "source_addr_ton": 0,
+            "source_addr_npi": 0,
+            "source_addr": "",
+            "dest_addr_ton": 0,
+            "dest_addr_npi": 0,
+            "destination_addr": "447700900123",
+            "esm_class": 0,
+            "protocol_id": 0,
+            "priority_flag": 0,
+            "schedule_delivery_time": "tomorrow",
+            "validity_period": "",
+            "registered_delivery": 0,
+            "replace_if_present_flag": 0,
+            "data_coding": 0,
+            "sm_default_msg_id": 0,
+            "short_message": "6869",
+            "tlvs": []
+        }}
+    }))
+    .unwrap_err();
+
+    assert!(
+        err.to_string().contains("schedule_delivery_time"),
+        "{}",
+        err
+    );
+
+    let err = serde_json::from_value::<SerdePdu>(json!({
+        "command_id": "4",
+        "command_status": "ESME_ROK",
+        "sequence_number": 1,
+        "body": {"raw": ""}
+    }))
+    .unwrap_err();
+
+    assert!(
+        err.to_string().starts_with("Invalid command_id '4'"),
+        "{}",
+        err
+    );
+}
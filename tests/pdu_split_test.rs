@@ -0,0 +1,67 @@
+use smpp::pdu_split::{PduSplitter, StreamBytes};
+
+const ENQUIRE_LINK: &[u8; 16] =
+    b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x01";
+const BIND_TRANSMITTER_RESP: &[u8; 0x1b] =
+    b"\x00\x00\x00\x1b\x80\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x02\
+    TestServer\0";
+
+fn pdu(offset: usize, bytes: &[u8]) -> StreamBytes {
+    StreamBytes {
+        offset,
+        bytes: bytes.to_vec(),
+    }
+}
+
+#[test]
+fn pdus_pushed_together_are_split_apart() {
+    let mut splitter = PduSplitter::new();
+    splitter.push(&[&ENQUIRE_LINK[..], &BIND_TRANSMITTER_RESP[..]].concat());
+
+    assert_eq!(splitter.next_pdu().unwrap().unwrap(), pdu(0, ENQUIRE_LINK));
+    assert_eq!(
+        splitter.next_pdu().unwrap().unwrap(),
+        pdu(16, BIND_TRANSMITTER_RESP)
+    );
+    assert!(splitter.next_pdu().is_none());
+    assert!(splitter.finish().is_none());
+}
+
+#[test]
+fn pdus_pushed_in_pieces_are_joined() {
+    let mut splitter = PduSplitter::new();
+    splitter.push(&BIND_TRANSMITTER_RESP[..3]);
+    assert!(splitter.next_pdu().is_none());
+    splitter.push(&BIND_TRANSMITTER_RESP[3..20]);
+    assert!(splitter.next_pdu().is_none());
+    splitter.push(&BIND_TRANSMITTER_RESP[20..]);
+
+    assert_eq!(
+        splitter.next_pdu().unwrap().unwrap(),
+        pdu(0, BIND_TRANSMITTER_RESP)
+    );
+}
+
+#[test]
+fn a_pdu_that_never_finishes_is_left_over() {
+    let mut splitter = PduSplitter::new();
+    splitter.push(&[&ENQUIRE_LINK[..], &ENQUIRE_LINK[..10]].concat());
+
+    assert!(splitter.next_pdu().unwrap().is_ok());
+    assert!(splitter.next_pdu().is_none());
+    assert_eq!(splitter.finish(), Some(pdu(16, &ENQUIRE_LINK[..10])));
+}
+
+#[test]
+fn an_invalid_length_stops_the_split() {
+    let mut splitter = PduSplitter::new();
+    splitter.push(ENQUIRE_LINK);
+    splitter.push(b"\x00\x00\x00\x02\x00\x00\x00\x15");
+
+    assert!(splitter.next_pdu().unwrap().is_ok());
+    let err = splitter.next_pdu().unwrap().unwrap_err();
+    assert_eq!(err.rest, pdu(16, b"\x00\x00\x00\x02\x00\x00\x00\x15"));
+
+    splitter.push(ENQUIRE_LINK);
+    assert!(splitter.next_pdu().is_none());
+}
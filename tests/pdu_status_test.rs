@@ -0,0 +1,69 @@
+use smpp::client::ClientError;
+use smpp::pdu_status::{
+    is_vendor_specific, pdu_status, status_description, status_name,
+    StatusDescription, StatusName,
+};
+use smpp_pdu::pdu::PduStatus;
+
+#[test]
+fn known_statuses_have_names() {
+    assert_eq!(status_name(PduStatus::ESME_ROK as u32), Some("ESME_ROK"));
+    assert_eq!(status_name(0x00000003), Some("ESME_RINVCMDID"));
+    assert_eq!(status_name(0x000000FF), Some("ESME_RUNKNOWNERR"));
+}
+
+#[test]
+fn reserved_and_vendor_statuses_have_no_names() {
+    assert_eq!(status_name(0x00000009), None);
+    assert_eq!(status_name(0x00000077), None);
+    assert_eq!(status_name(0x00000401), None);
+}
+
+#[test]
+fn status_names_display_with_hex_value() {
+    assert_eq!(
+        StatusName(0x00000002).to_string(),
+        "ESME_RINVCMDLEN (0x00000002)"
+    );
+    assert_eq!(StatusName(0x00000077).to_string(), "0x00000077");
+}
+
+#[test]
+fn statuses_convert_from_wire_values() {
+    assert!(matches!(
+        pdu_status(0x00000058),
+        Some(PduStatus::ESME_RTHROTTLED)
+    ));
+    assert!(pdu_status(0x00000401).is_none());
+    assert!(matches!(
+        ClientError::Status(0x0000000E).pdu_status(),
+        Some(PduStatus::ESME_RINVPASWD)
+    ));
+    assert!(ClientError::Timeout.pdu_status().is_none());
+}
+
+#[test]
+fn vendor_specific_statuses_are_recognised() {
+    assert!(is_vendor_specific(0x00000400));
+    assert!(is_vendor_specific(0x000004FF));
+    assert!(!is_vendor_specific(0x000003FF));
+    assert!(!is_vendor_specific(0x00000500));
+}
+
+#[test]
+fn status_descriptions_come_from_the_spec() {
+    assert_eq!(
+        status_description(0x00000045),
+        Some("submit_sm or submit_multi failed")
+    );
+    assert_eq!(
+        StatusDescription(0x00000058).to_string(),
+        "ESME_RTHROTTLED (0x00000058): Throttling error \
+        (ESME has exceeded allowed message limits)"
+    );
+    assert_eq!(
+        StatusDescription(0x00000401).to_string(),
+        "0x00000401: vendor-specific error"
+    );
+    assert_eq!(StatusDescription(0x00000077).to_string(), "0x00000077");
+}
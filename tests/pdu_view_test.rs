@@ -0,0 +1,119 @@
+use smpp::pdu_view::SmView;
+use smpp::pdu_write::write_pdu;
+use smpp::submit_sm_builder::SubmitSmBuilder;
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv};
+use smpp_pdu::pdu::{Pdu, PduBody, SubmitSmPdu};
+use std::io::Cursor;
+
+fn submit_sm() -> SubmitSmPdu {
+    SubmitSmBuilder::new()
+        .service_type("CMT")
+        .source_addr("MyCompany")
+        .destination_addr("447700900123")
+        .esm_class(0x40)
+        .validity_period("000001000000000R")
+        .registered_delivery(1)
+        .short_message(b"\x05\x00\x03\x01\x02\x01hello")
+        .tlv(Tlv::new(KnownTlvTag::user_message_reference, &[0, 7]))
+        .tlv(Tlv::new_unknown(0x1401, b"vendor"))
+        .build()
+        .unwrap()
+}
+
+async fn frame(command_status: u32, body: PduBody) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_pdu(&Pdu::new(command_status, 9, body).unwrap(), &mut bytes)
+        .await
+        .unwrap();
+    bytes
+}
+
+/// frame with its body cut to body_length, and command_length to match
+fn truncated(frame: &[u8], body_length: usize) -> Vec<u8> {
+    let mut bytes = Vec::from(&frame[..16 + body_length]);
+    let command_length = (bytes.len() as u32).to_be_bytes();
+    bytes[..4].copy_from_slice(&command_length);
+    bytes
+}
+
+fn assert_same_error(frame: &[u8]) {
+    let expected = Pdu::parse(&mut Cursor::new(frame)).unwrap_err();
+    let actual = SmView::parse(frame).unwrap_err();
+    assert_eq!(actual.to_string(), expected.to_string());
+}
+
+#[tokio::test]
+async fn fields_are_borrowed_from_the_frame() {
+    let bytes = frame(0, submit_sm().into()).await;
+
+    let view = SmView::parse(&bytes).unwrap();
+
+    assert_eq!(view.command_id, 0x04);
+    assert_eq!(view.sequence_number, 9);
+    assert_eq!(view.service_type, "CMT");
+    assert_eq!(view.source_addr, "MyCompany");
+    assert_eq!(view.destination_addr, "447700900123");
+    assert_eq!(view.esm_class, 0x40);
+    assert_eq!(view.schedule_delivery_time, "");
+    assert_eq!(view.validity_period, "000001000000000R");
+    assert_eq!(view.registered_delivery, 1);
+    assert_eq!(view.short_message, b"\x05\x00\x03\x01\x02\x01hello");
+    assert!(bytes.as_ptr_range().contains(&view.short_message.as_ptr()));
+    assert_eq!(
+        view.tlvs.get(KnownTlvTag::user_message_reference),
+        Some(&[0, 7][..])
+    );
+    assert_eq!(
+        view.tlvs.iter().collect::<Vec<_>>(),
+        vec![(0x0204, &[0, 7][..]), (0x1401, &b"vendor"[..])]
+    );
+}
+
+#[tokio::test]
+async fn views_convert_to_the_pdus_parse_would_give() {
+    let bytes = frame(0, submit_sm().into()).await;
+    let view = SmView::parse(&bytes).unwrap();
+
+    assert_eq!(view.to_submit_sm().unwrap(), submit_sm());
+
+    let deliver_sm = view.to_deliver_sm().unwrap();
+    let bytes = frame(0, deliver_sm.into()).await;
+    let view = SmView::parse(&bytes).unwrap();
+    assert_eq!(view.command_id, 0x05);
+    match Pdu::parse(&mut Cursor::new(&bytes)).unwrap().body() {
+        PduBody::DeliverSm(body) => {
+            assert_eq!(body, &view.to_deliver_sm().unwrap())
+        }
+        _ => panic!("Expected deliver_sm"),
+    }
+}
+
+#[tokio::test]
+async fn bad_frames_fail_as_they_do_for_parse() {
+    let bytes = frame(0, submit_sm().into()).await;
+
+    // Cut short in a string, the short_message and a TLV
+    assert_same_error(&truncated(&bytes, 10));
+    assert_same_error(&truncated(&bytes, 60));
+    assert_same_error(&truncated(&bytes, bytes.len() - 18));
+
+    // Not ASCII
+    let mut not_ascii = bytes.clone();
+    not_ascii[16] = 0xC3;
+    assert_same_error(&not_ascii);
+
+    // A validity_period of the wrong length
+    let mut short_time = bytes.clone();
+    short_time[54] = 0;
+    assert_same_error(&short_time);
+
+    // Not a submit_sm or deliver_sm at all
+    let mut enquire_link = truncated(&bytes, 0);
+    enquire_link[7] = 0x15;
+    assert!(SmView::parse(&enquire_link).is_err());
+
+    // A non-zero command_status
+    let mut failed = bytes;
+    failed[11] = 0x08;
+    assert!(SmView::parse(&failed).is_err());
+}
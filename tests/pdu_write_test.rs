@@ -0,0 +1,111 @@
+use smpp::encoded_len::EncodedLen;
+use smpp::pdu_write::write_pdu;
+use smpp_pdu::pdu::{
+    BindReceiverPdu, BindTransceiverPdu, BindTransmitterPdu, EnquireLinkPdu,
+    GenericNackPdu, Pdu, SubmitSmRespPdu,
+};
+use std::io::Cursor;
+use tokio::io::AsyncReadExt;
+
+async fn written(pdu: &Pdu) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_pdu(pdu, &mut buf).await.unwrap();
+    buf
+}
+
+async fn assert_round_trips(bytes: &[u8]) {
+    let pdu = Pdu::parse(&mut Cursor::new(bytes)).unwrap();
+    let rewritten = written(&pdu).await;
+    assert_eq!(rewritten, bytes);
+    assert_eq!(pdu.encoded_len(), bytes.len());
+    assert_eq!(Pdu::parse(&mut Cursor::new(&rewritten)).unwrap(), pdu);
+}
+
+#[tokio::test]
+async fn bind_transmitter_parses_writes_and_parses_again() {
+    assert_round_trips(
+        b"\x00\x00\x00\x2e\x00\x00\x00\x02\x00\x00\x00\x00\x01\x02\x03\x44\
+        mysystem_ID\0pw$xx\0t_p_\0\x34\x13\x50rng\0",
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn bind_receiver_and_transceiver_parse_write_and_parse_again() {
+    assert_round_trips(
+        b"\x00\x00\x00\x2e\x00\x00\x00\x01\x00\x00\x00\x00\x01\x02\x03\x45\
+        mysystem_ID\0pw$xx\0t_p_\0\x34\x13\x50rng\0",
+    )
+    .await;
+    assert_round_trips(
+        b"\x00\x00\x00\x2e\x00\x00\x00\x09\x00\x00\x00\x00\x01\x02\x03\x45\
+        mysystem_ID\0pw$xx\0t_p_\0\x34\x13\x50rng\0",
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn binds_built_from_fields_can_be_written() {
+    let bind_transmitter =
+        BindTransmitterPdu::new("esme1", "secret", "", 0x34, 1, 1, "").unwrap();
+    let bind_receiver =
+        BindReceiverPdu::new("esme1", "secret", "", 0x34, 0, 0, "^44").unwrap();
+    let bind_transceiver =
+        BindTransceiverPdu::new("e", "", "VMA", 0x34, 2, 3, "").unwrap();
+    for pdu in [
+        Pdu::new(0, 1, bind_transmitter.into()).unwrap(),
+        Pdu::new(0, 2, bind_receiver.into()).unwrap(),
+        Pdu::new(0, 3, bind_transceiver.into()).unwrap(),
+    ] {
+        let bytes = written(&pdu).await;
+        assert_eq!(Pdu::parse(&mut Cursor::new(&bytes)).unwrap(), pdu);
+    }
+}
+
+#[tokio::test]
+async fn other_pdus_are_written_as_smpp_pdu_writes_them() {
+    for pdu in [
+        Pdu::new(0, 4, EnquireLinkPdu::new().into()).unwrap(),
+        Pdu::new(3, 5, GenericNackPdu::new_error().into()).unwrap(),
+        Pdu::new(0, 6, SubmitSmRespPdu::new("abc").unwrap().into()).unwrap(),
+    ] {
+        let mut expected = Vec::new();
+        pdu.write(&mut expected).await.unwrap();
+        assert_eq!(written(&pdu).await, expected);
+    }
+}
+
+#[tokio::test]
+async fn the_number_of_bytes_written_is_returned() {
+    let pdu =
+        Pdu::new(0, 7, SubmitSmRespPdu::new("abc").unwrap().into()).unwrap();
+    let (mut writer, mut reader) = tokio::io::duplex(64);
+
+    let length = write_pdu(&pdu, &mut writer).await.unwrap();
+
+    let mut bytes = vec![0; length];
+    reader.read_exact(&mut bytes).await.unwrap();
+    assert_eq!(length, pdu.encoded_len());
+    assert_eq!(Pdu::parse(&mut Cursor::new(&bytes)).unwrap(), pdu);
+}
+
+#[cfg(feature = "sync-write")]
+#[tokio::test]
+async fn sync_writers_get_the_same_bytes() {
+    use smpp::pdu_write::write_pdu_sync;
+
+    let pdu = Pdu::new(
+        0,
+        8,
+        BindTransceiverPdu::new("sys", "pw", "", 0x34, 0, 0, "")
+            .unwrap()
+            .into(),
+    )
+    .unwrap();
+    let mut bytes = Vec::new();
+
+    let length = write_pdu_sync(&pdu, &mut bytes).unwrap();
+
+    assert_eq!(length, bytes.len());
+    assert_eq!(bytes, written(&pdu).await);
+}
@@ -0,0 +1,179 @@
+use async_trait::async_trait;
+use smpp::client::{BindMode, Client};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::parse_options::ParseMode;
+use smpp::profile::{Compatibility, Profile};
+use smpp::session_info::SMPP_3_3;
+use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
+use smpp::submit_sm_builder::SubmitSmBuilder;
+use smpp::typed_tlvs::TypedTlvs;
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::{
+    DeliverEsmClass, DeliverSmPdu, Pdu, SubmitSmPdu, SubmitSmRespPdu,
+};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+/// Accepts every submit_sm with message_id "abc", and remembers how many
+/// TLVs each had
+struct Logic {
+    tlv_counts: Arc<std::sync::Mutex<Vec<usize>>>,
+}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        self.tlv_counts
+            .lock()
+            .unwrap()
+            .push(pdu.0.tlvs.to_vec().len());
+        Ok((
+            SubmitSmRespPdu::new("abc").unwrap(),
+            MessageUniqueKey::new(
+                String::from("esme1"),
+                String::from("abc"),
+                pdu.destination_addr(),
+            ),
+        ))
+    }
+}
+
+async fn start() -> (TestServer, Arc<std::sync::Mutex<Vec<usize>>>) {
+    let tlv_counts = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let server = TestServer::start_with_logic(Logic {
+        tlv_counts: tlv_counts.clone(),
+    })
+    .await
+    .unwrap();
+    (server, tlv_counts)
+}
+
+fn submit_sm_with_a_tlv() -> SubmitSmPdu {
+    SubmitSmBuilder::new()
+        .destination("+447700900123")
+        .short_message(b"hello")
+        .tlv(Tlv::new(KnownTlvTag::user_message_reference, &[0, 1]))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn legacy_turns_on_every_switch() {
+    assert_eq!(
+        Profile::Legacy.compatibility(),
+        Compatibility {
+            parse_mode: ParseMode::Lenient,
+            interface_version: SMPP_3_3,
+            tlvs_on_smpp_3_3: true,
+            receipt_dates_with_seconds: true,
+        }
+    );
+    assert_eq!(Profile::Standard.compatibility(), Compatibility::default());
+}
+
+#[test]
+fn receipt_dates_lose_their_seconds_only_when_allowed() {
+    let standard = Profile::Standard.compatibility();
+    let seconds = Profile::ReceiptDatesWithSeconds.compatibility();
+    let date = || String::from("210102030459");
+
+    assert_eq!(standard.receipt_date(date()), "210102030459");
+    assert_eq!(seconds.receipt_date(date()), "2101020304");
+    assert_eq!(
+        seconds.receipt_date(String::from("2101020304")),
+        "2101020304"
+    );
+}
+
+#[tokio::test]
+async fn a_profile_chooses_the_interface_version_and_parse_mode() {
+    let (server, _) = start().await;
+    let client = Client::connect(&server.bind_address)
+        .await
+        .unwrap()
+        .with_profile(Profile::Legacy);
+    client
+        .bind(BindMode::Transmitter, "esme1", "", "")
+        .await
+        .unwrap();
+
+    let session_info = client.session_info().unwrap();
+    assert_eq!(session_info.interface_version, SMPP_3_3);
+    assert_eq!(client.connection().parse_options().mode, ParseMode::Lenient);
+}
+
+#[tokio::test]
+async fn tlvs_are_left_out_on_smpp_3_3_unless_the_profile_allows_them() {
+    let (server, tlv_counts) = start().await;
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client.set_compatibility(Compatibility {
+        interface_version: SMPP_3_3,
+        ..Compatibility::default()
+    });
+    client
+        .bind(BindMode::Transmitter, "esme1", "", "")
+        .await
+        .unwrap();
+    client.submit_sm(submit_sm_with_a_tlv()).await.unwrap();
+    client.set_compatibility(Profile::TlvsOnSmpp33.compatibility());
+    client.submit_sm(submit_sm_with_a_tlv()).await.unwrap();
+
+    assert_eq!(*tlv_counts.lock().unwrap(), vec![0, 1]);
+}
+
+#[tokio::test]
+async fn receipt_dates_with_seconds_are_shortened() {
+    let (server, _) = start().await;
+    let client = Client::connect(&server.bind_address)
+        .await
+        .unwrap()
+        .with_profile(Profile::ReceiptDatesWithSeconds);
+    client
+        .bind(BindMode::Transceiver, "esme1", "", "")
+        .await
+        .unwrap();
+    client.submit_sm(submit_sm_with_a_tlv()).await.unwrap();
+
+    let receipt = DeliverSmPdu::new(
+        "",
+        1,
+        1,
+        "447700900123",
+        5,
+        0,
+        "MyCompany",
+        DeliverEsmClass::SmscDeliveryReceipt as u8,
+        0,
+        0,
+        "",
+        "",
+        0,
+        0,
+        0,
+        0,
+        b"id:abc submit date:210102030401 done date:210102030559 stat:DELIVRD",
+        Tlvs::new(),
+    )
+    .unwrap();
+    server
+        .receive_pdu("esme1", Pdu::new(0, 9, receipt.into()).unwrap())
+        .await
+        .unwrap();
+
+    let receipt = client.next_receipt().await.unwrap();
+    assert_eq!(receipt.submit_date.as_deref(), Some("2101020304"));
+    assert_eq!(receipt.done_date.as_deref(), Some("2101020305"));
+}
@@ -0,0 +1,53 @@
+use smpp::protocol_id::{InvalidProtocolId, ProtocolId};
+use smpp::submit_sm_builder::SubmitSmBuilder;
+use std::convert::TryFrom;
+
+#[test]
+fn common_values_have_names() {
+    assert_eq!(ProtocolId::DEFAULT.to_string(), "default");
+    assert_eq!(
+        ProtocolId::SIM_DATA_DOWNLOAD.to_string(),
+        "SIM data download"
+    );
+    assert_eq!(ProtocolId(0x43).to_string(), "replace short message type 3");
+    assert_eq!(ProtocolId(0xC5).to_string(), "SC specific (0xC5)");
+    assert_eq!(ProtocolId(0x50).to_string(), "reserved (0x50)");
+}
+
+#[test]
+fn replace_short_message_types_go_from_1_to_7() {
+    assert_eq!(
+        ProtocolId::replace_short_message_type(7),
+        Some(ProtocolId(0x47))
+    );
+    assert_eq!(ProtocolId::replace_short_message_type(0), None);
+    assert_eq!(ProtocolId::replace_short_message_type(8), None);
+    assert_eq!(ProtocolId(0x47).replace_type(), Some(7));
+    assert_eq!(ProtocolId::SHORT_MESSAGE_TYPE_0.replace_type(), None);
+}
+
+#[test]
+fn telematic_device_types_go_from_0_to_31() {
+    assert_eq!(
+        ProtocolId::telematic_interworking(0x02),
+        Some(ProtocolId(0x22))
+    );
+    assert_eq!(ProtocolId::telematic_interworking(0x20), None);
+    assert_eq!(ProtocolId(0x22).telematic_device_type(), Some(0x02));
+}
+
+#[test]
+fn reserved_values_are_rejected() {
+    assert_eq!(
+        ProtocolId::try_from(0x7F),
+        Ok(ProtocolId::SIM_DATA_DOWNLOAD)
+    );
+    assert_eq!(ProtocolId::try_from(0x48), Err(InvalidProtocolId(0x48)));
+    assert_eq!(ProtocolId::try_from(0x80), Err(InvalidProtocolId(0x80)));
+
+    let err = SubmitSmBuilder::new()
+        .protocol_id(ProtocolId(0x60))
+        .build()
+        .unwrap_err();
+    assert!(err.to_string().contains("protocol_id"), "{}", err);
+}
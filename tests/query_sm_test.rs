@@ -0,0 +1,214 @@
+use async_trait::async_trait;
+use smpp::client::{BindMode, Client};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::query_sm::{MessageState, QuerySmPdu, QuerySmRespPdu};
+use smpp::smpp_connection::Frame;
+use smpp::smsc::{
+    BindData, BindError, MessageIdMap, QuerySmError, Smsc, SmscLogic,
+    SubmitSmError,
+};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+/// Knows of one delivered message, "1234" from "MyCompany"
+struct Logic {}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        _pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Err(SubmitSmError::InternalError)
+    }
+
+    async fn query_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &QuerySmPdu,
+    ) -> Result<QuerySmRespPdu, QuerySmError> {
+        if pdu.message_id.value != "1234"
+            || pdu.source_addr.value != "MyCompany"
+        {
+            return Err(QuerySmError::UnknownMessage);
+        }
+        Ok(QuerySmRespPdu::new(
+            0,
+            "",
+            "210102030405000+",
+            MessageState::Delivered,
+            0,
+        )
+        .unwrap())
+    }
+}
+
+/// "1234" is known to ESMEs as "x4321"
+struct Reversed {}
+
+impl MessageIdMap for Reversed {
+    fn external(&self, internal: &str) -> String {
+        format!("x{}", internal.chars().rev().collect::<String>())
+    }
+
+    fn internal(&self, external: &str) -> Option<String> {
+        external
+            .strip_prefix('x')
+            .map(|id| id.chars().rev().collect())
+    }
+}
+
+async fn written(frame: Frame) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    frame.write(&mut bytes).await.unwrap();
+    bytes
+}
+
+async fn bound_client(server: &TestServer) -> Client {
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transmitter, "esme1", "secret", "")
+        .await
+        .unwrap();
+    client
+}
+
+#[tokio::test]
+async fn query_sm_is_written_and_parsed() {
+    let query_sm = QuerySmPdu::new(7, "1234", "MyCompany").unwrap();
+
+    let bytes = written(Frame::QuerySm(query_sm)).await;
+
+    assert_eq!(
+        bytes,
+        b"\x00\x00\x00\x21\x00\x00\x00\x03\x00\x00\x00\x00\x00\x00\x00\x07\
+          1234\x00\x00\x00MyCompany\x00"
+    );
+    match Frame::parse(&bytes).unwrap() {
+        Frame::QuerySm(parsed) => {
+            assert_eq!(parsed, QuerySmPdu::new(7, "1234", "MyCompany").unwrap())
+        }
+        frame => panic!("Unexpected frame {:?}", frame),
+    }
+}
+
+#[tokio::test]
+async fn query_sm_resp_is_written_and_parsed() {
+    let resp = QuerySmRespPdu::new(
+        7,
+        "1234",
+        "210102030405000+",
+        MessageState::Delivered,
+        0,
+    )
+    .unwrap();
+
+    let bytes = written(Frame::QuerySmResp(resp)).await;
+
+    assert_eq!(
+        bytes,
+        b"\x00\x00\x00\x28\x80\x00\x00\x03\x00\x00\x00\x00\x00\x00\x00\x07\
+          1234\x00210102030405000+\x00\x02\x00"
+    );
+    let parsed = QuerySmRespPdu::parse(&bytes).unwrap();
+    assert_eq!(parsed.final_date.value, "210102030405000+");
+    assert_eq!(parsed.message_state(), Ok(MessageState::Delivered));
+}
+
+#[test]
+fn final_date_must_be_empty_or_a_whole_time() {
+    let resp = |final_date| {
+        QuerySmRespPdu::new(7, "1234", final_date, MessageState::Enroute, 0)
+    };
+    assert!(resp("").is_ok());
+    assert!(resp("210102030405000+").is_ok());
+    assert!(resp("2101020304").is_err());
+}
+
+#[test]
+fn a_rejection_without_a_body_is_understood() {
+    let resp = QuerySmRespPdu::parse(
+        b"\x00\x00\x00\x10\x80\x00\x00\x03\x00\x00\x00\x67\x00\x00\x00\x07",
+    )
+    .unwrap();
+
+    assert_eq!(resp, QuerySmRespPdu::new_error(0x67, 7));
+}
+
+#[tokio::test]
+async fn the_smsc_answers_query_sm_from_its_logic() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = bound_client(&server).await;
+
+    let resp = client
+        .query_sm(QuerySmPdu::new(0, "1234", "MyCompany").unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(resp.command_status, 0);
+    assert_eq!(resp.message_id.value, "1234");
+    assert_eq!(resp.message_state(), Ok(MessageState::Delivered));
+}
+
+#[tokio::test]
+async fn unknown_messages_fail_the_query() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = bound_client(&server).await;
+
+    let resp = client
+        .query_sm(QuerySmPdu::new(0, "9999", "MyCompany").unwrap())
+        .await
+        .unwrap();
+
+    // ESME_RQUERYFAIL
+    assert_eq!(resp.command_status, 0x67);
+}
+
+#[tokio::test]
+async fn queries_name_messages_by_their_external_message_id() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    server
+        .smsc
+        .lock()
+        .await
+        .set_message_id_map(Arc::new(Reversed {}));
+    let client = bound_client(&server).await;
+
+    let resp = client
+        .query_sm(QuerySmPdu::new(0, "x4321", "MyCompany").unwrap())
+        .await
+        .unwrap();
+    assert_eq!(resp.command_status, 0);
+    assert_eq!(resp.message_id.value, "x4321");
+
+    let resp = client
+        .query_sm(QuerySmPdu::new(0, "1234", "MyCompany").unwrap())
+        .await
+        .unwrap();
+    assert_eq!(resp.command_status, 0x67);
+}
+
+#[tokio::test]
+async fn query_sm_before_bind_is_rejected() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+
+    let resp = client
+        .query_sm(QuerySmPdu::new(0, "1234", "MyCompany").unwrap())
+        .await
+        .unwrap();
+
+    // ESME_RINVBNDSTS
+    assert_eq!(resp.command_status, 0x04);
+}
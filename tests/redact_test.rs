@@ -0,0 +1,81 @@
+use smpp::redact::{frame_bytes, Redacted, RedactedTlvs};
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::{Pdu, SubmitSmPdu};
+
+fn submit_sm(short_message: &[u8]) -> Pdu {
+    Pdu::new(
+        0x00,
+        0x12,
+        SubmitSmPdu::new(
+            "",
+            0,
+            0,
+            "447000123123",
+            0,
+            0,
+            "447111222222",
+            0,
+            0,
+            0,
+            "",
+            "",
+            1,
+            0,
+            0,
+            0,
+            short_message,
+            Tlvs::new(),
+        )
+        .unwrap()
+        .into(),
+    )
+    .unwrap()
+}
+
+#[cfg(not(feature = "redact-message-content"))]
+#[test]
+fn without_redaction_pdu_is_logged_unchanged() {
+    let pdu = submit_sm(b"secret words");
+    assert_eq!(format!("{:?}", Redacted(&pdu)), format!("{:?}", pdu));
+}
+
+#[cfg(feature = "redact-message-content")]
+#[test]
+fn with_redaction_message_content_is_not_logged() {
+    let pdu = submit_sm(b"secret words");
+    let logged = format!("{:?}", Redacted(&pdu));
+
+    assert!(
+        !logged.contains("115, 101, 99, 114, 101, 116"),
+        "{}",
+        logged
+    );
+    assert!(logged.contains("<12 bytes redacted>"), "{}", logged);
+    // Addressing information is still there for debugging
+    assert!(logged.contains("447111222222"), "{}", logged);
+}
+
+// submit_sm_resp with message_id "abc"
+const FRAME: &[u8] =
+    b"\x00\x00\x00\x14\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x02abc\0";
+
+#[cfg(not(feature = "redact-message-content"))]
+#[test]
+fn without_redaction_frames_and_tlvs_are_logged_unchanged() {
+    assert_eq!(frame_bytes(FRAME), FRAME);
+
+    let tlvs = [Tlv::new(KnownTlvTag::message_payload, b"secret words")];
+    assert_eq!(format!("{:?}", RedactedTlvs(&tlvs)), format!("{:?}", tlvs));
+}
+
+#[cfg(feature = "redact-message-content")]
+#[test]
+fn with_redaction_frames_and_tlvs_lose_their_content() {
+    assert_eq!(frame_bytes(FRAME), &FRAME[..16]);
+    assert_eq!(frame_bytes(&FRAME[..4]), &FRAME[..4]);
+
+    let tlvs = [Tlv::new(KnownTlvTag::message_payload, b"secret words")];
+    let logged = format!("{:?}", RedactedTlvs(&tlvs));
+    assert!(!logged.contains("115, 101, 99"), "{}", logged);
+    assert!(logged.contains("<12 bytes redacted>"), "{}", logged);
+}
@@ -0,0 +1,75 @@
+use smpp::client::{BindMode, Client};
+use smpp::replace_sm::{ReplaceSmPdu, ReplaceSmRespPdu};
+use smpp::smpp_connection::Frame;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+async fn written(frame: Frame) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    frame.write(&mut bytes).await.unwrap();
+    bytes
+}
+
+#[tokio::test]
+async fn replace_sm_is_written_and_parsed() {
+    let replace_sm = ReplaceSmPdu::new(7, "1234", "MyCompany", b"hi")
+        .unwrap()
+        .with_schedule("210102030405000+", "")
+        .unwrap();
+
+    let bytes = written(Frame::ReplaceSm(replace_sm.clone())).await;
+
+    assert_eq!(
+        bytes,
+        b"\x00\x00\x00\x38\x00\x00\x00\x07\x00\x00\x00\x00\x00\x00\x00\x07\
+          1234\x00\x00\x00MyCompany\x00210102030405000+\x00\x00\
+          \x00\x00\x02hi"
+    );
+    assert_eq!(ReplaceSmPdu::parse(&bytes).unwrap(), replace_sm);
+    assert_eq!(
+        ReplaceSmRespPdu::parse(
+            b"\x00\x00\x00\x10\x80\x00\x00\x07\x00\x00\x00\x00\x00\x00\x00\x07"
+        )
+        .unwrap(),
+        ReplaceSmRespPdu::new(0, 7)
+    );
+}
+
+#[test]
+fn replace_sm_fields_are_validated() {
+    assert!(ReplaceSmPdu::new(7, "", "MyCompany", b"hi").is_err());
+    assert!(ReplaceSmPdu::new(7, "1234", "MyCompany", &[b'x'; 255]).is_err());
+    let replace_sm = ReplaceSmPdu::new(7, "1234", "MyCompany", b"hi").unwrap();
+    assert!(replace_sm.with_schedule("tomorrow", "").is_err());
+}
+
+#[test]
+fn a_short_message_longer_than_the_frame_is_rejected() {
+    let err = ReplaceSmPdu::parse(
+        b"\x00\x00\x00\x28\x00\x00\x00\x07\x00\x00\x00\x00\x00\x00\x00\x07\
+          1234\x00\x00\x00MyCompany\x00\x00\x00\x00\x00\x09hi",
+    )
+    .unwrap_err();
+
+    assert!(err.to_string().contains("short_message"), "{}", err);
+}
+
+#[tokio::test]
+async fn by_default_the_smsc_cannot_replace() {
+    let server = TestServer::start().await.unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transmitter, "esme1", "secret", "")
+        .await
+        .unwrap();
+
+    let resp = client
+        .replace_sm(ReplaceSmPdu::new(0, "1234", "MyCompany", b"hi").unwrap())
+        .await
+        .unwrap();
+
+    // ESME_RREPLACEFAIL
+    assert_eq!(resp.command_status, 0x13);
+}
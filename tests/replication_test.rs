@@ -0,0 +1,154 @@
+use ascii::AsciiString;
+use async_trait::async_trait;
+use smpp::client::{BindMode, Client};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smpp_connection::EsmeId;
+use smpp::smsc::{
+    BindData, BindError, ReplicationState, Smsc, SmscLogic, SubmitSmError,
+};
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{
+    DeliverEsmClass, DeliverSmPdu, Pdu, SubmitSmPdu, SubmitSmRespPdu,
+};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::{submit_sm_to, TestServer};
+
+fn esme_id(system_id: &str, system_type: &str) -> EsmeId {
+    EsmeId {
+        system_id: AsciiString::from_ascii(system_id).unwrap(),
+        system_type: AsciiString::from_ascii(system_type).unwrap(),
+    }
+}
+
+#[test]
+fn replication_state_is_written_one_record_per_line() {
+    let state = ReplicationState {
+        bound_accounts: vec![esme_id("esme1", "")],
+        messages: vec![(
+            MessageUniqueKey::new(
+                String::from("ns"),
+                String::from("a\tb\\c"),
+                String::from("447700900123"),
+            ),
+            esme_id("esme1", ""),
+        )],
+        paused_routes: vec![String::from("4477")],
+    };
+
+    let text = state.to_string();
+
+    assert_eq!(
+        text,
+        "bound\tesme1\t\n\
+        message\tns\ta\\tb\\\\c\t447700900123\tesme1\t\n\
+        paused\t4477\n"
+    );
+    assert_eq!(text.parse::<ReplicationState>().unwrap(), state);
+}
+
+#[test]
+fn unknown_records_are_rejected_with_their_line_number() {
+    let err = "paused\t4477\nbogus\tx\n"
+        .parse::<ReplicationState>()
+        .unwrap_err();
+
+    assert_eq!(err.line_number, 2);
+    assert!(err.to_string().contains("bogus"), "{}", err);
+}
+
+#[tokio::test]
+async fn a_standby_routes_receipts_for_messages_accepted_by_the_primary() {
+    let primary = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = Client::connect(&primary.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transceiver, "esme1", "secret", "")
+        .await
+        .unwrap();
+    client
+        .submit_sm(submit_sm_to("447700900123"))
+        .await
+        .unwrap();
+    primary.smsc.lock().await.pause_route("4478");
+
+    let exported = primary.smsc.lock().await.export_state().to_string();
+    assert_eq!(
+        exported.parse::<ReplicationState>().unwrap().bound_accounts,
+        vec![esme_id("esme1", "")]
+    );
+
+    let standby = TestServer::start_with_logic(Logic {}).await.unwrap();
+    standby
+        .smsc
+        .lock()
+        .await
+        .import_state(exported.parse().unwrap());
+    assert_eq!(standby.smsc.lock().await.paused_routes(), vec!["4478"]);
+
+    // The ESME fails over by binding to the standby
+    let client = Client::connect(&standby.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transceiver, "esme1", "secret", "")
+        .await
+        .unwrap();
+    standby
+        .receive_pdu("testsystem", receipt(b"id:1234 stat:DELIVRD"))
+        .await
+        .unwrap();
+
+    let receipt = client.next_receipt().await.unwrap();
+    assert_eq!(receipt.message_id.as_deref(), Some("1234"));
+}
+
+struct Logic {}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Ok((
+            SubmitSmRespPdu::new("1234").unwrap(),
+            MessageUniqueKey::new(
+                String::from("testsystem"),
+                String::from("1234"),
+                pdu.destination_addr(),
+            ),
+        ))
+    }
+}
+
+fn receipt(text: &[u8]) -> Pdu {
+    let body = DeliverSmPdu::new(
+        "",
+        1,
+        1,
+        "447700900123",
+        5,
+        0,
+        "MyCompany",
+        DeliverEsmClass::SmscDeliveryReceipt as u8,
+        0,
+        0,
+        "",
+        "",
+        0,
+        0,
+        0,
+        0,
+        text,
+        Tlvs::new(),
+    )
+    .unwrap();
+    Pdu::new(0, 9, body.into()).unwrap()
+}
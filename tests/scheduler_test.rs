@@ -0,0 +1,251 @@
+use smpp::client::{BindMode, Client};
+use smpp::examples::smsc_drs_after_1_sec::DrsAfter1Sec;
+use smpp::scheduler::{OutsideWindow, ScheduleError, Scheduler};
+use smpp::sending_window::{SendingWindow, SendingWindows};
+use smpp::submit_sm_builder::SubmitSmBuilder;
+use smpp_pdu::pdu::SubmitSmPdu;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::net::TcpListener;
+use tokio::time::timeout;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+async fn bound_client() -> (TestServer, Arc<Client>) {
+    let server = TestServer::start_with_logic(DrsAfter1Sec::new())
+        .await
+        .unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transceiver, "esme1", "esme1", "")
+        .await
+        .unwrap();
+    (server, Arc::new(client))
+}
+
+fn submit_sm(destination_addr: &str) -> SubmitSmPdu {
+    SubmitSmBuilder::new()
+        .destination_addr(destination_addr)
+        .short_message(b"hello")
+        .build()
+        .unwrap()
+}
+
+fn in_millis(millis: u64) -> SystemTime {
+    SystemTime::now() + Duration::from_millis(millis)
+}
+
+#[tokio::test]
+async fn messages_are_sent_at_their_send_time_soonest_first() {
+    let (_server, client) = bound_client().await;
+    let scheduler = Scheduler::new();
+    let start = Instant::now();
+    let later = scheduler
+        .schedule(in_millis(300), submit_sm("447700900001"))
+        .unwrap();
+    let sooner = scheduler
+        .schedule(in_millis(100), submit_sm("447700900002"))
+        .unwrap();
+
+    let mut releases = scheduler.start(client, None);
+
+    let released = releases.next().await.unwrap();
+    assert_eq!(released.id, sooner);
+    assert_eq!(
+        released.result.unwrap().message_id,
+        Some(String::from("abc"))
+    );
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert_eq!(scheduler.waiting().len(), 1);
+
+    assert_eq!(releases.next().await.unwrap().id, later);
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    assert!(scheduler.waiting().is_empty());
+}
+
+#[tokio::test]
+async fn messages_scheduled_while_running_are_sent_in_order() {
+    let (_server, client) = bound_client().await;
+    let scheduler = Scheduler::new();
+    let later = scheduler
+        .schedule(in_millis(500), submit_sm("447700900001"))
+        .unwrap();
+    let mut releases = scheduler.start(client, None);
+
+    let sooner = scheduler
+        .schedule(in_millis(50), submit_sm("447700900002"))
+        .unwrap();
+
+    assert_eq!(releases.next().await.unwrap().id, sooner);
+    assert_eq!(releases.next().await.unwrap().id, later);
+}
+
+#[tokio::test]
+async fn messages_that_do_not_reach_the_smsc_wait_to_be_sent_again() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = Client::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    drop(listener.accept().await.unwrap());
+    let scheduler = Scheduler::new();
+    let id = scheduler
+        .schedule(SystemTime::now(), submit_sm("447700900001"))
+        .unwrap();
+
+    let mut releases = scheduler.start(Arc::new(client), None);
+
+    assert!(timeout(Duration::from_millis(200), releases.next())
+        .await
+        .is_err());
+    let waiting = scheduler.waiting();
+    assert_eq!(waiting.len(), 1);
+    assert_eq!(waiting[0].0, id);
+    assert!(waiting[0].1 > SystemTime::now());
+}
+
+#[tokio::test]
+async fn cancelled_messages_are_not_sent() {
+    let (_server, client) = bound_client().await;
+    let scheduler = Scheduler::new();
+    let cancelled = scheduler
+        .schedule(in_millis(50), submit_sm("447700900001"))
+        .unwrap();
+    let kept = scheduler
+        .schedule(in_millis(100), submit_sm("447700900002"))
+        .unwrap();
+
+    assert!(scheduler.cancel(cancelled).unwrap());
+    assert!(!scheduler.cancel(cancelled).unwrap());
+    let mut releases = scheduler.start(client, None);
+
+    assert_eq!(releases.next().await.unwrap().id, kept);
+}
+
+#[tokio::test]
+async fn due_messages_are_sent_no_faster_than_the_rate() {
+    let (_server, client) = bound_client().await;
+    let scheduler = Scheduler::new();
+    for _ in 0..3 {
+        scheduler
+            .schedule(SystemTime::now(), submit_sm("447700900001"))
+            .unwrap();
+    }
+    let start = Instant::now();
+
+    let mut releases = scheduler.start(client, Some(10));
+    for _ in 0..3 {
+        releases.next().await.unwrap().result.unwrap();
+    }
+
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
+
+#[cfg(feature = "spool")]
+#[tokio::test]
+async fn spooled_messages_survive_a_restart() {
+    use smpp::scheduler::DirSpool;
+
+    let dir = std::env::temp_dir().join(format!(
+        "smpp-spool-test-{}-{}",
+        std::process::id(),
+        test_utils::next_port()
+    ));
+    let send_at = in_millis(100);
+    let id = {
+        let spool = Arc::new(DirSpool::new(&dir).unwrap());
+        let scheduler = Scheduler::with_spool(spool).unwrap();
+        scheduler
+            .schedule(send_at, submit_sm("447700900001"))
+            .unwrap()
+    };
+
+    let (_server, client) = bound_client().await;
+    let scheduler =
+        Scheduler::with_spool(Arc::new(DirSpool::new(&dir).unwrap())).unwrap();
+    assert_eq!(scheduler.waiting().len(), 1);
+    assert_eq!(scheduler.waiting()[0].0, id);
+    let mut releases = scheduler.start(client, None);
+
+    assert_eq!(releases.next().await.unwrap().id, id);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    std::fs::remove_dir(&dir).unwrap();
+}
+
+/// A window for prefix that opened an hour ago and closes an hour from now,
+/// or the other way round if open is false
+fn window_around_now(prefix: &str, open: bool) -> SendingWindow {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let hour_ago = (now.as_secs() + 23 * 60 * 60) % (24 * 60 * 60);
+    let hour_ahead = (now.as_secs() + 60 * 60) % (24 * 60 * 60);
+    let (opens, closes) = if open {
+        (hour_ago, hour_ahead)
+    } else {
+        (hour_ahead, hour_ago)
+    };
+    SendingWindow {
+        prefix: String::from(prefix),
+        opens: opens as u32,
+        closes: closes as u32,
+        utc_offset: 0,
+        time_zone: None,
+    }
+}
+
+#[tokio::test]
+async fn messages_outside_their_window_are_deferred_until_it_opens() {
+    let (_server, client) = bound_client().await;
+    let closed = window_around_now("44", false);
+    let scheduler = Scheduler::new().with_sending_windows(
+        SendingWindows::new(&[closed.clone(), window_around_now("33", true)]),
+        OutsideWindow::Defer,
+    );
+
+    let deferred = scheduler
+        .schedule(SystemTime::now(), submit_sm("447700900001"))
+        .unwrap();
+    let allowed = scheduler
+        .schedule(SystemTime::now(), submit_sm("33612345678"))
+        .unwrap();
+    let unrestricted = scheduler
+        .schedule(in_millis(50), submit_sm("4915112345678"))
+        .unwrap();
+
+    let waiting = scheduler.waiting();
+    assert_eq!(waiting[2].0, deferred);
+    assert_eq!(waiting[2].1, closed.next_open(SystemTime::now()));
+    assert!(waiting[2].1 > in_millis(59 * 60 * 1000));
+
+    let mut releases = scheduler.start(client, None);
+    assert_eq!(releases.next().await.unwrap().id, allowed);
+    assert_eq!(releases.next().await.unwrap().id, unrestricted);
+    assert_eq!(scheduler.waiting().len(), 1);
+}
+
+#[tokio::test]
+async fn messages_outside_their_window_can_be_rejected() {
+    let closed = window_around_now("44", false);
+    let scheduler = Scheduler::new().with_sending_windows(
+        SendingWindows::new(std::slice::from_ref(&closed)),
+        OutsideWindow::Reject,
+    );
+
+    let err = scheduler
+        .schedule(SystemTime::now(), submit_sm("447700900001"))
+        .unwrap_err();
+
+    match err {
+        ScheduleError::OutsideSendingWindow { next_allowed } => {
+            assert_eq!(next_allowed, closed.next_open(SystemTime::now()))
+        }
+        e => panic!("Unexpected error {}", e),
+    }
+    assert!(scheduler.waiting().is_empty());
+
+    // A send time inside the window is fine
+    let later = closed.next_open(SystemTime::now()) + Duration::from_secs(60);
+    scheduler
+        .schedule(later, submit_sm("447700900001"))
+        .unwrap();
+}
@@ -0,0 +1,138 @@
+use smpp::sender::{
+    InvalidSender, Sender, NPI_ISDN, NPI_UNKNOWN, TON_ALPHANUMERIC,
+    TON_INTERNATIONAL, TON_NETWORK_SPECIFIC, TON_UNKNOWN,
+};
+
+fn alphanumeric(s: &str) -> Sender {
+    Sender::Alphanumeric(String::from(s))
+}
+
+#[test]
+fn alphanumeric_senders_are_at_most_11_characters() {
+    assert_eq!(alphanumeric("MyCompany").validate(), Ok(()));
+    assert_eq!(alphanumeric("Shop & Co.!").validate(), Ok(()));
+    assert_eq!(
+        alphanumeric("MyCompanyLtd").validate(),
+        Err(InvalidSender::AlphanumericTooLong(String::from(
+            "MyCompanyLtd"
+        )))
+    );
+    assert_eq!(
+        alphanumeric("").validate(),
+        Err(InvalidSender::AlphanumericEmpty)
+    );
+}
+
+#[test]
+fn alphanumeric_senders_use_the_gsm_default_alphabet() {
+    assert_eq!(
+        alphanumeric("My{Co}").validate(),
+        Err(InvalidSender::AlphanumericInvalidCharacter('{'))
+    );
+    assert_eq!(
+        alphanumeric("Café").validate(),
+        Err(InvalidSender::AlphanumericInvalidCharacter('é'))
+    );
+    assert_eq!(
+        alphanumeric("My\nCo").validate().unwrap_err().to_string(),
+        "Alphanumeric senders may not contain '\\n'."
+    );
+}
+
+#[test]
+fn numeric_senders_must_be_numbers() {
+    assert!(Sender::Shortcode(String::from("88222")).validate().is_ok());
+    assert_eq!(
+        Sender::Shortcode(String::from("447700900123")).validate(),
+        Err(InvalidSender::NotAShortcode(String::from("447700900123")))
+    );
+    assert!(Sender::Msisdn(String::from("+44 7700 900123"))
+        .validate()
+        .is_ok());
+    assert_eq!(
+        Sender::Msisdn(String::from("MyCompany"))
+            .validate()
+            .unwrap_err()
+            .to_string(),
+        "'MyCompany' is not a phone number."
+    );
+    assert!(Sender::Msisdn(String::from("+1234567890123456"))
+        .validate()
+        .is_err());
+}
+
+#[test]
+fn msisdn_senders_must_have_a_country_code() {
+    assert!(Sender::Msisdn(String::from("0044 7700 900123"))
+        .validate()
+        .is_ok());
+    assert_eq!(
+        Sender::Msisdn(String::from("07700900123")).validate(),
+        Err(InvalidSender::NotInternational(String::from("07700900123")))
+    );
+    assert_eq!(
+        Sender::Msisdn(String::from("447700900123"))
+            .validate()
+            .unwrap_err()
+            .to_string(),
+        "'447700900123' must start with '+' or '00' and a country code."
+    );
+}
+
+#[test]
+fn destinations_without_a_country_code_have_an_unknown_ton() {
+    let pdu = alphanumeric("MyCompany")
+        .submit_sm("07700 900123", b"hi")
+        .unwrap();
+    assert_eq!(pdu.destination_addr(), "07700900123");
+    assert_eq!(pdu.dest_addr_ton(), TON_UNKNOWN);
+    assert_eq!(pdu.dest_addr_npi(), NPI_ISDN);
+}
+
+#[test]
+fn ton_and_npi_follow_the_kind_of_sender() {
+    let msisdn = Sender::Msisdn(String::from("+44 7700 900123"));
+    assert_eq!(msisdn.addr(), "447700900123");
+    assert_eq!((msisdn.ton(), msisdn.npi()), (TON_INTERNATIONAL, NPI_ISDN));
+
+    let shortcode = Sender::Shortcode(String::from("882 22"));
+    assert_eq!(shortcode.addr(), "88222");
+    assert_eq!(
+        (shortcode.ton(), shortcode.npi()),
+        (TON_NETWORK_SPECIFIC, NPI_UNKNOWN)
+    );
+
+    let name = alphanumeric("MyCompany");
+    assert_eq!(name.addr(), "MyCompany");
+    assert_eq!((name.ton(), name.npi()), (TON_ALPHANUMERIC, NPI_UNKNOWN));
+}
+
+#[test]
+fn submit_sm_sets_addresses_ton_and_npi() {
+    let pdu = alphanumeric("MyCompany")
+        .submit_sm("+44 7700 900123", b"hi")
+        .unwrap();
+    assert_eq!(pdu.source_addr(), "MyCompany");
+    assert_eq!(pdu.source_addr_ton(), TON_ALPHANUMERIC);
+    assert_eq!(pdu.source_addr_npi(), NPI_UNKNOWN);
+    assert_eq!(pdu.destination_addr(), "447700900123");
+    assert_eq!(pdu.dest_addr_ton(), TON_INTERNATIONAL);
+    assert_eq!(pdu.dest_addr_npi(), NPI_ISDN);
+
+    let pdu = Sender::Msisdn(String::from("+447700900123"))
+        .submit_sm("88222", b"STOP")
+        .unwrap();
+    assert_eq!(pdu.source_addr_ton(), TON_INTERNATIONAL);
+    assert_eq!(pdu.dest_addr_ton(), TON_NETWORK_SPECIFIC);
+}
+
+#[test]
+fn submit_sm_fails_for_invalid_senders() {
+    let err = alphanumeric("WayTooLongName")
+        .submit_sm("447700900123", b"hi")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Alphanumeric sender 'WayTooLongName' is longer than 11 characters."
+    );
+}
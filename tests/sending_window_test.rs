@@ -0,0 +1,139 @@
+use smpp::sending_window::{SendingWindow, SendingWindows};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const HOUR: u64 = 60 * 60;
+
+/// A time on 1 January 1970, hours and minutes after midnight UTC
+fn utc(hours: u64, minutes: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(hours * HOUR + minutes * 60)
+}
+
+fn window(s: &str) -> SendingWindow {
+    s.parse().unwrap()
+}
+
+#[test]
+fn windows_are_parsed_with_an_optional_utc_offset() {
+    assert_eq!(
+        window("33=08:00-21:30@+01:00"),
+        SendingWindow {
+            prefix: String::from("33"),
+            opens: 8 * 3600,
+            closes: 21 * 3600 + 30 * 60,
+            utc_offset: 3600,
+            time_zone: None,
+        }
+    );
+    assert_eq!(window("1=09:00-20:00@-05:00").utc_offset, -5 * 3600);
+    assert_eq!(window("44=08:00-21:00").utc_offset, 0);
+
+    for invalid in [
+        "44",
+        "44=08:00",
+        "44=8:00-21:00",
+        "44=08:00-24:00",
+        "44=08:00-21:00@01:00",
+        "44=08:00-21:00@+1",
+        "44=08:00-21:00@Europe/Nowhere",
+    ] {
+        let err = invalid.parse::<SendingWindow>().unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with(&format!("Invalid sending window '{}'", invalid)),
+            "{}",
+            err
+        );
+    }
+}
+
+#[test]
+fn times_inside_a_window_are_allowed_as_they_are() {
+    let window = window("44=08:00-21:00");
+    let at = utc(12, 0) + Duration::from_millis(250);
+
+    assert_eq!(window.next_open(at), at);
+    assert_eq!(window.next_open(utc(8, 0)), utc(8, 0));
+}
+
+#[test]
+fn times_outside_a_window_move_to_when_it_next_opens() {
+    let window = window("44=08:00-21:00");
+
+    assert_eq!(window.next_open(utc(6, 30)), utc(8, 0));
+    assert_eq!(window.next_open(utc(21, 0)), utc(24 + 8, 0));
+    assert_eq!(
+        window.next_open(utc(23, 15) + Duration::from_millis(500)),
+        utc(24 + 8, 0)
+    );
+}
+
+#[test]
+fn windows_are_in_local_time() {
+    // 08:00-21:00 in UTC+01:00 is 07:00-20:00 UTC
+    let window = window("33=08:00-21:00@+01:00");
+
+    assert_eq!(window.next_open(utc(7, 30)), utc(7, 30));
+    assert_eq!(window.next_open(utc(6, 0)), utc(7, 0));
+    assert_eq!(window.next_open(utc(20, 0)), utc(24 + 7, 0));
+}
+
+#[test]
+fn windows_may_cross_midnight() {
+    let window = window("1=22:00-06:00");
+
+    assert_eq!(window.next_open(utc(23, 0)), utc(23, 0));
+    assert_eq!(window.next_open(utc(24 + 5, 0)), utc(24 + 5, 0));
+    assert_eq!(window.next_open(utc(12, 0)), utc(22, 0));
+}
+
+#[test]
+fn the_longest_matching_prefix_applies() {
+    let windows = SendingWindows::new(&[
+        window("44=08:00-21:00"),
+        window("4477=10:00-12:00"),
+    ]);
+
+    assert_eq!(windows.next_allowed("447700900123", utc(9, 0)), utc(10, 0));
+    assert_eq!(windows.next_allowed("447900900123", utc(9, 0)), utc(9, 0));
+    assert_eq!(windows.next_allowed("33612345678", utc(3, 0)), utc(3, 0));
+    assert!(windows.window_for("33612345678").is_none());
+}
+
+#[test]
+fn destinations_are_matched_in_international_form() {
+    let windows = SendingWindows::new(&[window("44=08:00-21:00")]);
+
+    assert_eq!(
+        windows.next_allowed("+44 7700 900123", utc(6, 0)),
+        utc(8, 0)
+    );
+    assert_eq!(
+        windows.next_allowed("0044 7700900123", utc(6, 0)),
+        utc(8, 0)
+    );
+}
+
+#[cfg(feature = "time-zones")]
+#[test]
+fn windows_in_a_time_zone_follow_its_summer_time() {
+    /// Midnight UTC on 15 January 2021, 28 March 2021, when Paris moves
+    /// to summer time at 01:00 UTC, and 15 July 2021
+    const JANUARY: u64 = 1_610_668_800;
+    const MARCH: u64 = 1_616_889_600;
+    const JULY: u64 = 1_626_307_200;
+    let at = |day: u64, hours: u64| utc(hours, 0) + Duration::from_secs(day);
+    let window = window("33=08:00-21:00@Europe/Paris");
+
+    assert!(window.time_zone.is_some());
+    assert_eq!(window.next_open(at(JANUARY, 6)), at(JANUARY, 7));
+    assert_eq!(window.next_open(at(JULY, 5)), at(JULY, 6));
+    assert_eq!(window.next_open(at(MARCH, 0)), at(MARCH, 6));
+}
+
+#[cfg(not(feature = "time-zones"))]
+#[test]
+fn time_zones_need_the_time_zones_feature() {
+    assert!("33=08:00-21:00@Europe/Paris"
+        .parse::<SendingWindow>()
+        .is_err());
+}
@@ -0,0 +1,160 @@
+use smpp::session_capture::{
+    wireshark_text, Direction, SessionCapture, MAX_CAPTURED_PDUS,
+};
+use smpp::smpp_connection::{PeerAddr, SmppConnection};
+use smpp_pdu::pdu::{EnquireLinkRespPdu, Pdu};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const ENQUIRE_LINK: &[u8; 0x10] =
+    b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12";
+
+const BIND_TRANSMITTER_RESP: &[u8; 0x1b] =
+    b"\x00\x00\x00\x1b\x80\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x02\
+    TestServer\0";
+
+#[test]
+fn bind_resp_is_shown_with_result_and_system_id() {
+    assert_eq!(
+        wireshark_text(BIND_TRANSMITTER_RESP),
+        "Short Message Peer to Peer, \
+            Command: Bind_transmitter - resp, Seq: 2, Len: 27\n\
+        \x20   Length: 27\n\
+        \x20   Operation: Bind_transmitter - resp (0x80000002)\n\
+        \x20   Result: ESME_ROK (0x00000000)\n\
+        \x20   Sequence #: 2\n\
+        \x20   System ID: TestServer\n"
+    );
+}
+
+#[test]
+fn submit_sm_is_shown_with_all_fields_and_tlvs() {
+    let mut pdu: Vec<u8> = Vec::new();
+    pdu.extend(
+        b"\x00\x00\x00\x43\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x03",
+    );
+    pdu.extend(b"\x00\x01\x01447000123123\x00\x01\x01447111222222\x00");
+    pdu.extend(b"\x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi");
+    pdu.extend(b"\x02\x04\x00\x02\x00\x07"); // user_message_reference = 7
+    assert_eq!(pdu.len(), 0x43);
+
+    let text = wireshark_text(&pdu);
+    for line in &[
+        "Short Message Peer to Peer, Command: Submit_sm, Seq: 3, Len: 67\n",
+        "    Operation: Submit_sm (0x00000004)\n",
+        "    Originator address: 447000123123\n",
+        "    Type of number (recipient): 0x01\n",
+        "    Recipient address: 447111222222\n",
+        "    Data coding: 0x03\n",
+        "    Message length: 4\n",
+    ] {
+        assert!(text.contains(line), "Missing {:?} in:\n{}", line, text);
+    }
+    if cfg!(feature = "redact-message-content") {
+        assert!(text.contains("    Message bytes: <redacted>\n"));
+        assert!(!text.contains("Optional parameter"));
+    } else {
+        assert!(text.contains("    Message bytes: 68696869\n"));
+        assert!(text.contains(
+            "    Optional parameters\n\
+            \x20       Optional parameter: user_message_reference (0x0204)\n\
+            \x20           Length: 2\n\
+            \x20           Value: 0007\n"
+        ));
+    }
+    // Requests have no Result
+    assert!(!text.contains("Result"));
+}
+
+#[test]
+fn bad_pdus_are_shown_as_malformed() {
+    let text = wireshark_text(
+        b"\x00\x00\x00\x10\xff\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x22",
+    );
+    assert!(text.starts_with(
+        "Short Message Peer to Peer, Command: Unknown, Seq: 34, Len: 16\n"
+    ));
+    assert!(text.contains("[Malformed Packet: "));
+
+    assert_eq!(
+        wireshark_text(b"\x00\x00"),
+        "Short Message Peer to Peer\n    [Malformed Packet: SMPP]\n"
+    );
+}
+
+#[test]
+fn captured_sessions_are_numbered_frames() {
+    let mut capture =
+        SessionCapture::new(PeerAddr::Tcp("127.0.0.1:2775".parse().unwrap()));
+    capture.record(Direction::Received, ENQUIRE_LINK);
+    capture.record(Direction::Sent, BIND_TRANSMITTER_RESP);
+
+    let text = capture.to_wireshark_text();
+    assert!(text.starts_with("Frame 1: 16 bytes received from 127.0.0.1:2775"));
+    // Only headers are kept when redacting
+    let sent_len = if cfg!(feature = "redact-message-content") {
+        16
+    } else {
+        27
+    };
+    assert!(text.contains(&format!(
+        "\nFrame 2: {} bytes sent to 127.0.0.1:2775",
+        sent_len
+    )));
+}
+
+#[test]
+fn bind_passwords_are_masked() {
+    let text = wireshark_text(
+        b"\x00\x00\x00\x29\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x07\
+        esmeid\0password\0type\0\x34\x00\x00\0",
+    );
+    assert!(text.contains("    Password: ********\n"), "{}", text);
+    assert!(!text.contains("password"), "{}", text);
+}
+
+#[test]
+fn captures_keep_only_the_most_recent_pdus() {
+    let mut capture =
+        SessionCapture::new(PeerAddr::Tcp("127.0.0.1:2775".parse().unwrap()));
+    for _ in 0..MAX_CAPTURED_PDUS + 2 {
+        capture.record(Direction::Received, ENQUIRE_LINK);
+    }
+
+    assert_eq!(capture.pdus().len(), MAX_CAPTURED_PDUS);
+    assert_eq!(capture.dropped(), 2);
+    let text = capture.to_wireshark_text();
+    assert!(text.starts_with("Frame 3: "), "{}", &text[..40]);
+}
+
+#[tokio::test]
+async fn connections_record_what_they_send_and_receive() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut client = TcpStream::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (server_stream, addr) = listener.accept().await.unwrap();
+    let connection = SmppConnection::new(server_stream, addr);
+
+    assert!(connection.capture().is_none());
+    connection.start_capture();
+
+    client.write_all(ENQUIRE_LINK).await.unwrap();
+    connection.read_pdu().await.unwrap().unwrap();
+    connection
+        .write_pdu(
+            &Pdu::new(0, 0x12, EnquireLinkRespPdu::new().into()).unwrap(),
+        )
+        .await
+        .unwrap();
+    let mut resp = [0; 16];
+    client.read_exact(&mut resp).await.unwrap();
+
+    let capture = connection.capture().unwrap();
+    let pdus = capture.pdus();
+    assert_eq!(pdus.len(), 2);
+    assert_eq!(pdus[0].direction, Direction::Received);
+    assert_eq!(pdus[0].bytes, ENQUIRE_LINK);
+    assert_eq!(pdus[1].direction, Direction::Sent);
+    assert_eq!(pdus[1].bytes, resp);
+}
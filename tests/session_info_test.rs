@@ -0,0 +1,72 @@
+use smpp::client::{BindMode, Client};
+use smpp::session_info::{SessionInfo, SMPP_3_3, SMPP_3_4};
+use smpp::smpp_connection::{EsmeId, SmppConnection};
+use tokio::net::TcpStream;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+#[tokio::test]
+async fn both_ends_record_what_was_agreed_at_bind() {
+    let server = TestServer::start().await.unwrap();
+    let stream = TcpStream::connect(&server.bind_address).await.unwrap();
+    let peer_addr = stream.peer_addr().unwrap();
+    let client = Client::from_connection_with_window(
+        SmppConnection::new(stream, peer_addr),
+        10,
+    );
+    assert_eq!(client.session_info(), None);
+
+    client
+        .bind(BindMode::Transceiver, "esme1", "secret", "type")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        client.session_info(),
+        Some(SessionInfo {
+            bind_mode: BindMode::Transceiver,
+            interface_version: SMPP_3_4,
+            window: Some(10),
+        })
+    );
+    let esme_id = EsmeId {
+        system_id: "esme1".parse().unwrap(),
+        system_type: "type".parse().unwrap(),
+    };
+    assert_eq!(
+        server.smsc.lock().await.session_info(&esme_id),
+        Some(SessionInfo {
+            bind_mode: BindMode::Transceiver,
+            interface_version: SMPP_3_4,
+            window: None,
+        })
+    );
+}
+
+#[test]
+fn tlvs_are_supported_from_smpp_3_4() {
+    let mut info = SessionInfo {
+        bind_mode: BindMode::Receiver,
+        interface_version: SMPP_3_4,
+        window: None,
+    };
+    assert!(info.supports_tlvs());
+
+    info.interface_version = SMPP_3_3;
+    assert!(!info.supports_tlvs());
+}
+
+#[test]
+fn session_info_displays_as_a_banner() {
+    let mut info = SessionInfo {
+        bind_mode: BindMode::Transceiver,
+        interface_version: SMPP_3_4,
+        window: Some(10),
+    };
+    assert_eq!(info.to_string(), "transceiver, SMPP 3.4, window 10");
+
+    info.window = None;
+    assert_eq!(info.to_string(), "transceiver, SMPP 3.4, no window");
+}
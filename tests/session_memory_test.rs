@@ -0,0 +1,140 @@
+use smpp::session_memory::{SessionMemory, SessionMemoryAction};
+use smpp::smpp_connection::SmppConnection;
+use smpp::smsc::SmscConfig;
+use smpp_pdu::pdu::{Pdu, PduStatus, SubmitSmRespPdu};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+
+mod test_utils;
+
+use test_utils::{new_submit_sm, DefaultLogic, TestClient, TestServer};
+
+const ENQUIRE_LINK: &[u8; 0x10] =
+    b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12";
+const ENQUIRE_LINK_RESP: &[u8; 0x10] =
+    b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12";
+
+async fn connect() -> (TcpStream, SmppConnection) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (server_stream, addr) = listener.accept().await.unwrap();
+    (client, SmppConnection::new(server_stream, addr))
+}
+
+async fn client_with(configure: impl FnOnce(&mut SmscConfig)) -> TestClient {
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, configure)
+        .await
+        .unwrap();
+    TestClient::connect_to(&server).await.unwrap()
+}
+
+#[test]
+fn actions_are_throttle_or_close() {
+    assert_eq!(
+        "throttle".parse::<SessionMemoryAction>().unwrap(),
+        SessionMemoryAction::Throttle
+    );
+    assert_eq!(
+        "close".parse::<SessionMemoryAction>().unwrap(),
+        SessionMemoryAction::Close
+    );
+    assert!("drop".parse::<SessionMemoryAction>().is_err());
+}
+
+#[test]
+fn the_total_adds_up_every_part() {
+    let memory = SessionMemory {
+        read_buffer: 1,
+        queued: 2,
+        pending_requests: 4,
+        recorded: 8,
+    };
+
+    assert_eq!(memory.total(), 15);
+    assert_eq!(
+        memory.to_string(),
+        "15 bytes (read buffer 1, queued 2, pending requests 4, recorded 8)"
+    );
+}
+
+#[tokio::test]
+async fn queued_bytes_are_counted_until_released() {
+    let (_client, connection) = connect().await;
+    let before = connection.memory();
+
+    let queued = connection.queue(100);
+    assert_eq!(connection.memory().queued, before.queued + 100);
+
+    drop(queued);
+    assert_eq!(connection.memory(), before);
+}
+
+#[tokio::test]
+async fn bad_frames_are_counted_as_recorded() {
+    let (mut client, connection) = connect().await;
+    client
+        .write_all(
+            b"\x00\x00\x00\x10\xff\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01",
+        )
+        .await
+        .unwrap();
+
+    assert!(connection.read_pdu().await.is_err());
+    assert_eq!(connection.memory().recorded, 0x10);
+}
+
+#[tokio::test]
+async fn submits_are_throttled_over_the_limit() {
+    let mut client = client_with(|c| c.max_session_memory = Some(1)).await;
+    let mut throttled = Vec::new();
+    Pdu::new(
+        PduStatus::ESME_RTHROTTLED as u32,
+        2,
+        SubmitSmRespPdu::new_error().into(),
+    )
+    .unwrap()
+    .write(&mut throttled)
+    .await
+    .unwrap();
+
+    client
+        .send_and_expect_response(
+            &new_submit_sm(2, "447700900123").await,
+            &throttled,
+        )
+        .await;
+
+    // Everything else is still answered
+    client
+        .send_and_expect_response(ENQUIRE_LINK, ENQUIRE_LINK_RESP)
+        .await;
+}
+
+#[tokio::test]
+async fn connections_are_closed_over_the_limit_if_asked() {
+    let mut client = client_with(|c| {
+        c.max_session_memory = Some(1);
+        c.session_memory_action = SessionMemoryAction::Close;
+    })
+    .await;
+
+    client.stream.write_all(ENQUIRE_LINK).await.unwrap();
+
+    assert!(client.read_n_maybe(1).await.is_err());
+}
+
+#[tokio::test]
+async fn nothing_is_limited_under_the_limit() {
+    let mut client =
+        client_with(|c| c.max_session_memory = Some(1 << 20)).await;
+    client.bind_transceiver().await;
+
+    client
+        .send_and_expect_response(
+            &new_submit_sm(2, "447700900123").await,
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x08\x00\x00\x00\x02",
+        )
+        .await;
+}
@@ -0,0 +1,58 @@
+use smpp::smpp_connection::EsmeId;
+
+mod test_utils;
+
+use test_utils::TestSetup;
+
+#[tokio::test]
+async fn bound_sessions_count_submits_and_errors() {
+    let mut submit_sm: Vec<u8> = Vec::new();
+    submit_sm.extend(b"\x00\x00\x00\x3d"); //   command_length = 61
+    submit_sm.extend(b"\x00\x00\x00\x04"); //       command_id = submit_sm
+    submit_sm.extend(b"\x00\x00\x00\x00"); //   command_status = NULL
+    submit_sm.extend(b"\x00\x00\x00\x03"); //  sequence_number = 3
+    submit_sm.extend(b"\x00\x00\x00"); // service_type, source_addr_ton, npi
+    submit_sm.extend(b"447000123123\x00"); //      source_addr
+    submit_sm.extend(b"\x00\x00"); //        dest_addr_ton, dest_addr_npi
+    submit_sm.extend(b"447111222222\x00"); // destination_addr
+    submit_sm.extend(b"\x00\x01\x01\x00\x00\x01\x00\x03\x00"); // esm_class...
+    submit_sm.extend(b"\x04hihi"); //       sm_length, short_message
+    assert_eq!(submit_sm.len(), 0x3d);
+
+    let mut t = TestSetup::new().await;
+    t.client.bind_transmitter().await;
+
+    // DefaultLogic rejects every submit_sm
+    t.client
+        .send_and_expect_response(
+            &submit_sm,
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x08\x00\x00\x00\x03",
+            //         submit_sm_resp ^^^^     system error ^^^^
+        )
+        .await;
+    t.client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x04",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x04",
+        )
+        .await;
+
+    let all_stats = t.server.smsc.lock().await.session_stats();
+    assert_eq!(all_stats.len(), 1);
+    let stats = &all_stats[&EsmeId {
+        system_id: "esmeid".parse().unwrap(),
+        system_type: "type".parse().unwrap(),
+    }];
+    assert_eq!(stats.submits, 1);
+    assert_eq!(stats.deliveries, 0);
+    assert_eq!(stats.errors.get(&0x08), Some(&1));
+    assert_eq!(stats.errors.len(), 1);
+    assert!(stats.last_activity.is_some());
+    assert!(stats.average_resp_latency().is_some());
+}
+
+#[tokio::test]
+async fn unbound_sessions_have_no_stats() {
+    let t = TestSetup::new().await;
+    assert!(t.server.smsc.lock().await.session_stats().is_empty());
+}
@@ -0,0 +1,112 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+const ENQUIRE_LINK: &[u8; 16] =
+    b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x01";
+/// A submit_sm whose body stops after service_type
+const SHORT_SUBMIT_SM: &[u8; 17] =
+    b"\x00\x00\x00\x11\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x02\0";
+
+fn smpp_dump(args: &[&str], input: &[u8]) -> (Output, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_smpp-dump"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8(output.stdout.clone()).unwrap();
+    (output, stdout)
+}
+
+#[test]
+fn each_pdu_on_stdin_is_printed() {
+    let (output, stdout) =
+        smpp_dump(&[], &[&ENQUIRE_LINK[..], &ENQUIRE_LINK[..]].concat());
+
+    assert!(output.status.success());
+    assert_eq!(
+        stdout,
+        "PDU 1 at offset 0 (0x0):\n\
+        Short Message Peer to Peer, Command: Enquire_link, Seq: 1, Len: 16\n\
+        \x20   Length: 16\n\
+        \x20   Operation: Enquire_link (0x00000015)\n\
+        \x20   Sequence #: 1\n\
+        \n\
+        0000  00 00 00 10 00 00 00 15  00 00 00 00 00 00 00 01   \
+            ........ ........\n\
+        \n\
+        PDU 2 at offset 16 (0x10):\n\
+        Short Message Peer to Peer, Command: Enquire_link, Seq: 1, Len: 16\n\
+        \x20   Length: 16\n\
+        \x20   Operation: Enquire_link (0x00000015)\n\
+        \x20   Sequence #: 1\n\
+        \n\
+        0000  00 00 00 10 00 00 00 15  00 00 00 00 00 00 00 01   \
+            ........ ........\n\
+        \n"
+    );
+}
+
+#[test]
+fn pdus_that_do_not_parse_say_why() {
+    let (output, stdout) = smpp_dump(
+        &["-"],
+        &[&SHORT_SUBMIT_SM[..], &ENQUIRE_LINK[..], &ENQUIRE_LINK[..6]].concat(),
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout.contains("PDU 1 at offset 0 (0x0):\n"), "{}", stdout);
+    assert!(stdout.contains("\nParse error: "), "{}", stdout);
+    assert!(
+        stdout.contains(
+            "\nAn SMSC would respond with ESME_RINVCMDLEN (0x00000002), \
+            then carry on\n"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("PDU 2 at offset 17 (0x11):\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.ends_with(
+            "Incomplete PDU at offset 33 (0x21): only 6 bytes\n\
+            0000  00 00 00 10 00 00                                  \
+                ......\n"
+        ),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn an_invalid_length_ends_the_dump() {
+    let (output, stdout) =
+        smpp_dump(&[], &[&ENQUIRE_LINK[..], b"\x00\x00\x00\x03"].concat());
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stdout.contains("Cannot find any more PDUs at offset 16 (0x10): "),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("0000  00 00 00 03 "), "{}", stdout);
+}
+
+#[test]
+fn files_that_cannot_be_read_are_reported() {
+    let (output, _) = smpp_dump(&["/nonexistent/capture"], b"");
+
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.starts_with("smpp-dump: /nonexistent/capture: "),
+        "{}",
+        stderr
+    );
+}
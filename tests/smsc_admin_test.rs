@@ -0,0 +1,62 @@
+use std::io;
+use tokio::io::AsyncReadExt;
+use tokio::time::{sleep, Duration};
+
+mod test_utils;
+
+use test_utils::{new_submit_sm, TestClient, TestSetup};
+
+#[tokio::test]
+async fn kicked_sessions_are_disconnected_and_forgotten() {
+    let mut t = TestSetup::new().await;
+    t.client.bind_transceiver_as("kickme").await;
+    let mut other = TestClient::connect_to(&t.server).await.unwrap();
+    other.bind_transceiver_as("other").await;
+    assert_eq!(t.server.smsc.lock().await.connections().len(), 2);
+
+    assert_eq!(t.server.smsc.lock().await.kick("kickme"), 1);
+
+    assert_eq!(
+        t.client.stream.read_u8().await.unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+    // Give the server a moment to tidy up after the disconnect
+    sleep(Duration::from_millis(10)).await;
+    let connections = t.server.smsc.lock().await.connections();
+    assert_eq!(connections.len(), 1);
+    assert_eq!(
+        connections[0].bound_esme_id().unwrap().system_id.as_str(),
+        "other"
+    );
+
+    assert_eq!(t.server.smsc.lock().await.kick("nobody"), 0);
+}
+
+#[tokio::test]
+async fn submit_sm_to_paused_routes_is_throttled_until_resumed() {
+    let mut t = TestSetup::new().await;
+    t.client.bind_transmitter().await;
+
+    t.server.smsc.lock().await.pause_route("4477");
+    assert_eq!(t.server.smsc.lock().await.paused_routes(), vec!["4477"]);
+
+    t.client
+        .send_and_expect_response(
+            &new_submit_sm(0x10, "447700900123").await,
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x58\x00\x00\x00\x10",
+            //                        ESME_RTHROTTLED ^^^^
+        )
+        .await;
+
+    t.server.smsc.lock().await.resume_route("4477");
+    assert!(t.server.smsc.lock().await.paused_routes().is_empty());
+
+    // DefaultLogic rejects everything, but it gets the chance to now
+    t.client
+        .send_and_expect_response(
+            &new_submit_sm(0x11, "447700900123").await,
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x08\x00\x00\x00\x11",
+            //                           ESME_RSYSERR ^^^^
+        )
+        .await;
+}
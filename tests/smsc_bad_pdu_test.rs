@@ -4,7 +4,9 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 mod test_utils;
 
-use test_utils::{bytes_as_string, TestClient, TestServer, TestSetup};
+use test_utils::{
+    bytes_as_string, DefaultLogic, TestClient, TestServer, TestSetup,
+};
 
 #[tokio::test]
 async fn when_we_receive_a_bad_pdu_we_respond_with_failure_resp_pdu() {
@@ -15,14 +17,30 @@ async fn when_we_receive_a_bad_pdu_we_respond_with_failure_resp_pdu() {
             b"\x00\x00\x00\x29\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x14\
         e\xf0\x9f\x92\xa9d\0password\0type\0\x34\x00\x00\0",
             //  ^^^^ non-ascii
-            b"\x00\x00\x00\x10\x80\x00\x00\x02\x00\x00\x00\x08\x00\x00\x00\x14",
-            //                               system error ^^^^        seq ^^^^
+            b"\x00\x00\x00\x10\x80\x00\x00\x02\x00\x00\x00\x01\x00\x00\x00\x14",
+            //                     invalid message length ^^^^        seq ^^^^
             // Note: no body part because this is an error response
             "unexpected end of file",
         )
         .await;
 }
 
+#[tokio::test]
+async fn when_we_receive_a_bad_bind_receiver_we_respond_with_its_resp() {
+    TestSetup::new()
+        .await
+        .client
+        .send_and_expect_error_response(
+            b"\x00\x00\x00\x29\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x14\
+        e\xf0\x9f\x92\xa9d\0password\0type\0\x34\x00\x00\0",
+            //  ^^^^ non-ascii
+            b"\x00\x00\x00\x10\x80\x00\x00\x01\x00\x00\x00\x01\x00\x00\x00\x14",
+            //     bind_receiver_resp ^^^^   invalid msg len ^^^^
+            "unexpected end of file",
+        )
+        .await;
+}
+
 #[tokio::test]
 async fn when_client_disconnects_within_pdu_we_continue_accepting_new_connections(
 ) {
@@ -33,7 +51,7 @@ async fn when_client_disconnects_within_pdu_we_continue_accepting_new_connection
     let server = TestServer::start().await.unwrap();
     // When ESME sends partial data then disconnects
     let mut client1 = TestClient::connect_to(&server).await.unwrap();
-    client1.stream.write(PDU).await.unwrap();
+    client1.stream.write_all(PDU).await.unwrap();
     client1.stream.shutdown().await.unwrap();
 
     // Another client is free to connect afterwards
@@ -98,20 +116,19 @@ async fn when_we_receive_nontlv_pdu_with_too_long_length_return_an_error() {
 }
 
 #[tokio::test]
-async fn when_we_receive_a_pdu_with_very_long_length_we_respond_generic_nack() {
+async fn when_we_receive_a_pdu_with_very_long_length_we_respond_with_its_resp()
+{
     const PDU: &[u8; 0x1b] =
         b"\x00\xff\xff\xff\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x02\
         e\0pd\0t\0\x34\x00\x00\0";
     // very long length
 
     const RESP: &[u8; 0x10] =
-        b"\x00\x00\x00\x10\x80\x00\x00\x00\x00\x00\x00\x02\x00\x00\x00\x01";
-    //       generic_nack ^^^^        cmd len invalid ^^^^        seq ^^^^
+        b"\x00\x00\x00\x10\x80\x00\x00\x02\x00\x00\x00\x02\x00\x00\x00\x02";
+    //  bind_transmitter_resp ^^^^        cmd len invalid ^^^^        seq ^^^^
 
-    // Note: we don't provide the correct sequence number here: we could, but
-    // we would have to read the PDU header before we notice the invalid
-    // PDU length.  Since a huge length is likely to indicate a catastrophic
-    // error, or malicious traffic, we are not too bothered.
+    // The length is rejected before the body is read, but the rest of the
+    // header has arrived by then, so the response can still match it.
 
     let many_bytes: Vec<u8> = PDU
         .iter()
@@ -124,7 +141,8 @@ async fn when_we_receive_a_pdu_with_very_long_length_we_respond_generic_nack() {
     let server = TestServer::start().await.unwrap();
     // When we send a huge PDU with huge length
     let mut client = TestClient::connect_to(&server).await.unwrap();
-    client.stream.write(&many_bytes).await.unwrap();
+    // (The SMSC may stop reading before it has all of them.)
+    let _written = client.stream.write(&many_bytes).await.unwrap();
 
     // Then SMSC either ...
     let resp = client.read_n_maybe(RESP.len()).await;
@@ -155,7 +173,7 @@ async fn when_receive_pdu_with_short_length_but_long_string_we_respond_with_erro
     // Our PDU will contain 100,000 letter 'e's within a COctetString
     let mut many_bytes: Vec<u8> = vec![];
     many_bytes.extend(BEGIN.iter());
-    many_bytes.extend(iter::repeat('e' as u8).take(100_000));
+    many_bytes.extend(iter::repeat(b'e').take(100_000));
     many_bytes.extend(END.iter());
 
     TestSetup::new()
@@ -163,8 +181,8 @@ async fn when_receive_pdu_with_short_length_but_long_string_we_respond_with_erro
         .client
         .send_and_expect_error_response(
             &many_bytes,
-            b"\x00\x00\x00\x10\x80\x00\x00\x02\x00\x00\x00\x08\x00\x00\x00\x02",
-            //      bind_transmitter_resp ^^^^ system error ^^
+            b"\x00\x00\x00\x10\x80\x00\x00\x02\x00\x00\x00\x01\x00\x00\x00\x02",
+            //      bind_transmitter_resp ^^^^ invalid msg len ^^
             "Connection reset by peer (os error 104)",
         )
         .await;
@@ -184,3 +202,96 @@ async fn when_we_receive_invalid_pdu_type_we_respond_with_error() {
         )
         .await;
 }
+
+#[tokio::test]
+async fn when_recovering_we_reject_pdus_with_bad_fields_and_carry_on() {
+    let mut t = TestSetup::new_recovering().await;
+    t.client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x29\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x14\
+        e\xf0\x9f\x92\xa9d\0password\0type\0\x34\x00\x00\0",
+            //  ^^^^ non-ascii
+            b"\x00\x00\x00\x10\x80\x00\x00\x02\x00\x00\x00\x01\x00\x00\x00\x14",
+        )
+        .await;
+
+    // The connection is still usable
+    t.client.bind_transmitter().await;
+}
+
+#[tokio::test]
+async fn when_recovering_we_nack_unknown_pdus_and_carry_on() {
+    let mut t = TestSetup::new_recovering().await;
+    t.client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\xff\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x22",
+            //    this is invalid! ^^^^^^^^^^^^^^^                    seq ^^^^
+            b"\x00\x00\x00\x10\x80\x00\x00\x00\x00\x00\x00\x03\x00\x00\x00\x22",
+        )
+        .await;
+    t.client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x1b\x80\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x23\
+        TestServer\0",
+            // bind_transmitter_resp ^^^^^^^^^^^^^ - doesn't make sense
+            b"\x00\x00\x00\x10\x80\x00\x00\x00\x00\x00\x00\x03\x00\x00\x00\x23",
+        )
+        .await;
+
+    // The connection is still usable
+    t.client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x24",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x24",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn when_recovering_we_still_disconnect_after_bad_lengths() {
+    TestSetup::new_recovering()
+        .await
+        .client
+        .send_and_expect_error_response(
+            b"\x00\x00\x00\x01",
+            b"\x00\x00\x00\x10\x80\x00\x00\x00\x00\x00\x00\x02\x00\x00\x00\x01",
+            "unexpected end of file",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn when_configured_we_explain_rejections_in_status_info_text() {
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.status_info_text = true
+    })
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+
+    client
+        .stream
+        .write_all(
+            b"\x00\x00\x00\x10\xff\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x22",
+        )
+        .await
+        .unwrap();
+
+    let header = client.read_n(16).await;
+    assert_eq!(
+        bytes_as_string(&header[4..]),
+        bytes_as_string(
+            b"\x80\x00\x00\x00\x00\x00\x00\x03\x00\x00\x00\x22" // generic_nack   invalid cmdid          seq
+        )
+    );
+    let length =
+        u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+    let tlv = client.read_n(length as usize - 16).await;
+    assert_eq!(&tlv[..2], b"\x00\x1d"); // additional_status_info_text
+    let text = std::str::from_utf8(&tlv[4..tlv.len() - 1]).unwrap();
+    assert!(
+        text.ends_with("Response status: ESME_RINVCMDID (0x00000003)."),
+        "{}",
+        text
+    );
+}
@@ -71,6 +71,7 @@ async fn when_we_bind_with_incorrect_password_we_receive_error() {
             Err(BindError::IncorrectPassword)
         }
 
+        #[allow(clippy::diverging_sub_expression)]
         async fn submit_sm(
             &mut self,
             _smsc: Arc<Mutex<Smsc>>,
@@ -148,6 +149,7 @@ async fn when_we_receive_multiple_binds_we_can_keep_track() {
             Ok(())
         }
 
+        #[allow(clippy::diverging_sub_expression)]
         async fn submit_sm(
             &mut self,
             _smsc: Arc<Mutex<Smsc>>,
@@ -0,0 +1,97 @@
+use clap::Clap;
+use smpp::smsc::{Chaos, Latency, SmscConfig};
+use smpp_pdu::pdu::{Pdu, PduStatus, SubmitSmRespPdu};
+use std::io;
+use std::time::{Duration, Instant};
+
+mod test_utils;
+
+use test_utils::{new_submit_sm, DefaultLogic, TestClient, TestServer};
+
+fn chaos(args: &[&str]) -> Chaos {
+    let args = std::iter::once("smsc").chain(args.iter().copied());
+    Chaos::new(&SmscConfig::parse_from(args))
+}
+
+async fn client_with(configure: impl FnOnce(&mut SmscConfig)) -> TestClient {
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, configure)
+        .await
+        .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transceiver().await;
+    client
+}
+
+#[test]
+fn latencies_are_written_kind_colon_milliseconds() {
+    assert_eq!("fixed:20".parse::<Latency>().unwrap(), Latency::Fixed(20));
+    assert_eq!(
+        "uniform:50-200".parse::<Latency>().unwrap(),
+        Latency::Uniform(50, 200)
+    );
+    assert_eq!(
+        "exponential:100".parse::<Latency>().unwrap(),
+        Latency::Exponential(100)
+    );
+    assert!("uniform:200-50".parse::<Latency>().is_err());
+    assert!("normal:100".parse::<Latency>().is_err());
+    assert!("100".parse::<Latency>().is_err());
+}
+
+#[test]
+fn uniform_latencies_stay_in_range_and_repeat_with_the_same_seed() {
+    let args = ["--latency", "uniform:10-20", "--chaos-seed", "42"];
+    let first = chaos(&args);
+    let second = chaos(&args);
+    for _ in 0..100 {
+        let latency = first.latency().unwrap();
+        assert!(latency >= Duration::from_millis(10));
+        assert!(latency <= Duration::from_millis(20));
+        assert_eq!(second.latency().unwrap(), latency);
+    }
+    assert_eq!(chaos(&[]).latency(), None);
+}
+
+#[tokio::test]
+async fn responses_wait_for_the_latency() {
+    let mut client =
+        client_with(|c| c.latency = Some(Latency::Fixed(200))).await;
+    let start = Instant::now();
+    client
+        .send_and_expect_response(
+            &new_submit_sm(2, "447700900123").await,
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x08\x00\x00\x00\x02",
+        )
+        .await;
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
+
+#[tokio::test]
+async fn submits_in_a_burst_are_throttled() {
+    let mut client = client_with(|c| c.throttle_burst_percent = 100.0).await;
+    for sequence_number in 2..5 {
+        let mut throttled = Vec::new();
+        Pdu::new(
+            PduStatus::ESME_RTHROTTLED as u32,
+            sequence_number,
+            SubmitSmRespPdu::new_error().into(),
+        )
+        .unwrap()
+        .write(&mut throttled)
+        .await
+        .unwrap();
+        client
+            .send_and_expect_response(
+                &new_submit_sm(sequence_number, "447700900123").await,
+                &throttled,
+            )
+            .await;
+    }
+}
+
+#[tokio::test]
+async fn bound_connections_are_closed_periodically() {
+    let mut client = client_with(|c| c.forced_unbind_secs = Some(1)).await;
+    let e = client.read_n_maybe(1).await.unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+}
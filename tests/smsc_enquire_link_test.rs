@@ -0,0 +1,61 @@
+use std::io;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::time::{Duration, Instant};
+
+mod test_utils;
+
+use test_utils::{DefaultLogic, TestClient, TestServer};
+
+const ENQUIRE_LINK_1: &[u8; 0x10] =
+    b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x01";
+//      enquire_link ^^^^^^^^^^^^^^^                    seq ^^^^^^^^^^^^^^^
+
+async fn start_server() -> TestServer {
+    TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.enquire_link_interval_secs = Some(1);
+        c.enquire_link_timeout_secs = 1;
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn when_client_is_quiet_we_send_enquire_link() {
+    let server = start_server().await;
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    let start = Instant::now();
+    client.bind_transmitter().await;
+
+    client.expect_to_receive(ENQUIRE_LINK_1).await;
+    assert!(start.elapsed() >= Duration::from_secs(1));
+
+    // When we respond, the connection stays open and we get another later
+    client
+        .stream
+        .write_all(
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x01",
+        )
+        .await
+        .unwrap();
+    client
+        .expect_to_receive(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x02",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn when_client_does_not_answer_enquire_link_we_disconnect() {
+    let server = start_server().await;
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transmitter().await;
+
+    client.expect_to_receive(ENQUIRE_LINK_1).await;
+    let sent = Instant::now();
+
+    assert_eq!(
+        client.stream.read_u8().await.unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+    assert!(sent.elapsed() >= Duration::from_millis(900));
+}
@@ -0,0 +1,60 @@
+use std::io;
+use tokio::io::AsyncReadExt;
+use tokio::time::{sleep, Duration, Instant};
+
+mod test_utils;
+
+use test_utils::{DefaultLogic, TestClient, TestServer};
+
+#[tokio::test]
+async fn when_only_enquire_links_are_sent_we_close_idle_connections() {
+    // Given an SMSC with one slot that closes connections after 1 second idle
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.max_open_sockets = 1;
+        c.idle_timeout_secs = Some(1);
+    })
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    let start = Instant::now();
+    client.bind_transmitter().await;
+
+    // When the client sends only enquire_links
+    sleep(Duration::from_millis(600)).await;
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+        )
+        .await;
+
+    // Then the SMSC closes the connection 1 second after the bind
+    assert_eq!(
+        client.stream.read_u8().await.unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+    assert!(start.elapsed() >= Duration::from_secs(1));
+
+    // And the slot is free for someone else
+    TestClient::connect_to(&server)
+        .await
+        .unwrap()
+        .bind_transmitter()
+        .await;
+}
+
+#[tokio::test]
+async fn without_idle_timeout_we_keep_idle_connections() {
+    let server = TestServer::start().await.unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transmitter().await;
+
+    sleep(Duration::from_millis(1100)).await;
+
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+        )
+        .await;
+}
@@ -0,0 +1,211 @@
+use async_trait::async_trait;
+use smpp::data_sm::{DataSmPdu, DataSmRespPdu};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smpp_connection::Frame;
+use smpp::smsc::scenario::delivery_receipt;
+use smpp::smsc::{
+    BindData, BindError, Scenario, ScenarioRule, Smsc, SmscLogic, SubmitSmError,
+};
+use smpp_pdu::pdu::{Pdu, PduStatus, SubmitSmPdu, SubmitSmRespPdu};
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::{new_submit_sm, TestClient, TestServer};
+
+struct AcceptAll {}
+
+#[async_trait]
+impl SmscLogic for AcceptAll {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Ok((
+            SubmitSmRespPdu::new("msg1").unwrap(),
+            MessageUniqueKey::new(
+                String::from("testsystem"),
+                String::from("msg1"),
+                pdu.destination_addr(),
+            ),
+        ))
+    }
+}
+
+async fn client_following(script: &str) -> TestClient {
+    let server = TestServer::start_with_logic(AcceptAll {}).await.unwrap();
+    server
+        .smsc
+        .lock()
+        .await
+        .set_scenario(script.parse().unwrap());
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transceiver().await;
+    client
+}
+
+async fn bytes(pdu: Pdu) -> Vec<u8> {
+    let mut ret = Vec::new();
+    pdu.write(&mut ret).await.unwrap();
+    ret
+}
+
+async fn submit_sm_resp(sequence_number: u32, status: u32) -> Vec<u8> {
+    let body = if status == 0 {
+        SubmitSmRespPdu::new("msg1").unwrap()
+    } else {
+        SubmitSmRespPdu::new_error()
+    };
+    bytes(Pdu::new(status, sequence_number, body.into()).unwrap()).await
+}
+
+#[test]
+fn scenarios_are_parsed_one_rule_per_line() {
+    let scenario: Scenario = "\
+        # comment\n\
+        esmeid status ESME_RTHROTTLED every 3\n\
+        \n\
+        * delay 250ms\n\
+        * status 0x45 every 2\n\
+        other drop after 10\n\
+        other dlr undeliv after 5s\n"
+        .parse()
+        .unwrap();
+
+    assert_eq!(
+        scenario.rules_for(Some("esmeid")),
+        vec![
+            &ScenarioRule::Delay(Duration::from_millis(250)),
+            &ScenarioRule::Status {
+                command_status: 0x58,
+                every: 3
+            },
+        ]
+    );
+    assert_eq!(
+        scenario.rules_for(None),
+        vec![
+            &ScenarioRule::Delay(Duration::from_millis(250)),
+            &ScenarioRule::Status {
+                command_status: 0x45,
+                every: 2
+            },
+        ]
+    );
+    assert_eq!(scenario.rules_for(Some("other")).len(), 4);
+    assert!(scenario
+        .rules_for(Some("other"))
+        .contains(&&ScenarioRule::Dlr {
+            state: String::from("UNDELIV"),
+            after: Duration::from_secs(5),
+        }));
+}
+
+#[test]
+fn invalid_rules_are_rejected() {
+    for rule in &[
+        "esmeid",
+        "esmeid status ESME_RNOTHING every 3",
+        "esmeid status ESME_RTHROTTLED every 0",
+        "esmeid delay 5",
+        "esmeid drop after x",
+        "esmeid dlr NOTASTATE after 1s",
+    ] {
+        assert!(rule.parse::<Scenario>().is_err(), "{}", rule);
+    }
+}
+
+#[tokio::test]
+async fn every_nth_submit_sm_gets_the_scripted_status() {
+    let mut client =
+        client_following("esmeid status ESME_RTHROTTLED every 2").await;
+    let throttled = PduStatus::ESME_RTHROTTLED as u32;
+
+    for (sequence_number, status) in
+        &[(2, 0), (3, throttled), (4, 0), (5, throttled)]
+    {
+        client
+            .send_and_expect_response(
+                &new_submit_sm(*sequence_number, "447700900123").await,
+                &submit_sm_resp(*sequence_number, *status).await,
+            )
+            .await;
+    }
+}
+
+#[tokio::test]
+async fn data_sm_gets_the_scripted_status_too() {
+    let mut client =
+        client_following("esmeid status ESME_RTHROTTLED every 1").await;
+    let data_sm =
+        DataSmPdu::new(2, "MyCompany", "447700900123", 0, b"hello").unwrap();
+    let mut request = Vec::new();
+    Frame::DataSm(data_sm).write(&mut request).await.unwrap();
+    let mut response = Vec::new();
+    Frame::DataSmResp(DataSmRespPdu::new_error(
+        PduStatus::ESME_RTHROTTLED as u32,
+        2,
+    ))
+    .write(&mut response)
+    .await
+    .unwrap();
+
+    client.send_and_expect_response(&request, &response).await;
+}
+
+#[tokio::test]
+async fn responses_are_delayed() {
+    let mut client = client_following("* delay 200ms").await;
+    let start = Instant::now();
+    client
+        .send_and_expect_response(
+            &new_submit_sm(2, "447700900123").await,
+            &submit_sm_resp(2, 0).await,
+        )
+        .await;
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
+
+#[tokio::test]
+async fn connection_is_dropped_after_enough_pdus() {
+    // The bind counts as the first PDU
+    let mut client = client_following("esmeid drop after 2").await;
+    client
+        .send_and_expect_response(
+            &new_submit_sm(2, "447700900123").await,
+            &submit_sm_resp(2, 0).await,
+        )
+        .await;
+    let e = client.read_n_maybe(1).await.unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[tokio::test]
+async fn delivery_receipts_are_sent_after_accepted_submits() {
+    let mut client = client_following("esmeid dlr UNDELIV after 0s").await;
+    let submit_sm = new_submit_sm(2, "447700900123").await;
+    client
+        .send_and_expect_response(&submit_sm, &submit_sm_resp(2, 0).await)
+        .await;
+
+    let submit_sm =
+        match Pdu::parse(&mut io::Cursor::new(&submit_sm)).unwrap().body() {
+            smpp_pdu::pdu::PduBody::SubmitSm(body) => {
+                delivery_receipt(body, "msg1", "UNDELIV")
+            }
+            _ => panic!("Not a submit_sm"),
+        };
+    let expected = bytes(Pdu::new(0, 1, submit_sm.into()).unwrap()).await;
+    client.expect_to_receive(&expected).await;
+    assert!(String::from_utf8_lossy(&expected)
+        .contains("id:msg1 sub:001 dlvrd:000 stat:UNDELIV err:000 text:"));
+}
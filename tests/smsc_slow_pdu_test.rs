@@ -0,0 +1,82 @@
+use std::io;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::time::{sleep, Duration, Instant};
+
+mod test_utils;
+
+use test_utils::{DefaultLogic, TestClient, TestServer};
+
+const ENQUIRE_LINK: &[u8] =
+    b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12";
+const ENQUIRE_LINK_RESP: &[u8] =
+    b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12";
+
+#[tokio::test]
+async fn a_pdu_that_never_finishes_arriving_closes_the_connection() {
+    // Given an SMSC that allows 1 second for a PDU to arrive
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.partial_pdu_timeout_secs = Some(1);
+    })
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transmitter().await;
+
+    // When the client sends half an enquire_link, then a byte at a time
+    let start = Instant::now();
+    client.stream.write_all(&ENQUIRE_LINK[..8]).await.unwrap();
+    for byte in &ENQUIRE_LINK[8..12] {
+        sleep(Duration::from_millis(300)).await;
+        client.stream.write_u8(*byte).await.unwrap();
+    }
+
+    // Then the SMSC closes the connection 1 second after the first byte
+    assert_eq!(
+        client.stream.read_u8().await.unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+    assert!(start.elapsed() >= Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn a_pdu_arriving_below_the_minimum_rate_closes_the_connection() {
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.min_bytes_per_sec = Some(100);
+    })
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transmitter().await;
+
+    client.stream.write_all(&ENQUIRE_LINK[..8]).await.unwrap();
+
+    assert_eq!(
+        client.stream.read_u8().await.unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+}
+
+#[tokio::test]
+async fn pdus_split_across_writes_are_allowed_within_the_limits() {
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.partial_pdu_timeout_secs = Some(1);
+        c.min_bytes_per_sec = Some(10);
+    })
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transmitter().await;
+
+    // Slow, but 16 bytes in 0.6s is over 10 bytes per second
+    client.stream.write_all(&ENQUIRE_LINK[..8]).await.unwrap();
+    sleep(Duration::from_millis(600)).await;
+    client
+        .send_and_expect_response(&ENQUIRE_LINK[8..], ENQUIRE_LINK_RESP)
+        .await;
+
+    // And waiting between PDUs is not waiting within one
+    sleep(Duration::from_millis(1100)).await;
+    client
+        .send_and_expect_response(ENQUIRE_LINK, ENQUIRE_LINK_RESP)
+        .await;
+}
@@ -0,0 +1,118 @@
+use async_trait::async_trait;
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smsc::{
+    BindData, BindError, Smsc, SmscLogic, SubmitSmError, UnknownCommandAction,
+};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::io;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::{TestClient, TestServer};
+
+const UNKNOWN_COMMAND: &[u8] =
+    b"\x00\x00\x00\x10\x00\x00\x00\x22\x00\x00\x00\x00\x00\x00\x00\x02";
+//                 unknown ^^^^^^^^^^
+
+const ENQUIRE_LINK: &[u8] =
+    b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x03";
+
+const ENQUIRE_LINK_RESP: &[u8] =
+    b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x03";
+
+/// Remembers every unknown command it is told about
+struct Recorder {
+    frames: Arc<std::sync::Mutex<Vec<Vec<u8>>>>,
+}
+
+#[async_trait]
+impl SmscLogic for Recorder {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        _pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Err(SubmitSmError::InternalError)
+    }
+
+    async fn unknown_command(&mut self, frame: &[u8]) {
+        self.frames.lock().unwrap().push(Vec::from(frame));
+    }
+}
+
+async fn client_with(
+    action: UnknownCommandAction,
+) -> (TestClient, Arc<std::sync::Mutex<Vec<Vec<u8>>>>) {
+    let frames = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let logic = Recorder {
+        frames: Arc::clone(&frames),
+    };
+    let server = TestServer::start_with_smsc_config(logic, |c| {
+        c.unknown_command = action
+    })
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transceiver().await;
+    (client, frames)
+}
+
+#[test]
+fn actions_are_parsed() {
+    assert_eq!(
+        "nack".parse::<UnknownCommandAction>().unwrap(),
+        UnknownCommandAction::Nack
+    );
+    assert_eq!(
+        "ignore".parse::<UnknownCommandAction>().unwrap(),
+        UnknownCommandAction::Ignore
+    );
+    assert_eq!(
+        "close".parse::<UnknownCommandAction>().unwrap(),
+        UnknownCommandAction::Close
+    );
+    assert!("drop".parse::<UnknownCommandAction>().is_err());
+}
+
+#[tokio::test]
+async fn unknown_commands_are_nacked_by_default() {
+    let (mut client, frames) = client_with(UnknownCommandAction::Nack).await;
+    client
+        .send_and_expect_response(
+            UNKNOWN_COMMAND,
+            b"\x00\x00\x00\x10\x80\x00\x00\x00\x00\x00\x00\x03\x00\x00\x00\x02",
+            //          generic_nack ^^^^   ESME_RINVCMDID ^^^^
+        )
+        .await;
+    assert_eq!(*frames.lock().unwrap(), vec![Vec::from(UNKNOWN_COMMAND)]);
+}
+
+#[tokio::test]
+async fn unknown_commands_can_be_ignored() {
+    let (mut client, frames) = client_with(UnknownCommandAction::Ignore).await;
+    client.stream.write_all(UNKNOWN_COMMAND).await.unwrap();
+
+    // The next thing we receive is the enquire_link_resp
+    client
+        .send_and_expect_response(ENQUIRE_LINK, ENQUIRE_LINK_RESP)
+        .await;
+    assert_eq!(*frames.lock().unwrap(), vec![Vec::from(UNKNOWN_COMMAND)]);
+}
+
+#[tokio::test]
+async fn unknown_commands_can_close_the_connection() {
+    let (mut client, frames) = client_with(UnknownCommandAction::Close).await;
+    client.stream.write_all(UNKNOWN_COMMAND).await.unwrap();
+
+    let e = client.read_n_maybe(1).await.unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+    assert_eq!(*frames.lock().unwrap(), vec![Vec::from(UNKNOWN_COMMAND)]);
+}
@@ -0,0 +1,21 @@
+use smpp::socket_activation::passed_fd_count;
+
+#[test]
+fn sockets_passed_to_us_are_counted() {
+    assert_eq!(passed_fd_count(Some("1234"), Some("1"), 1234), 1);
+    assert_eq!(passed_fd_count(Some("1234"), Some("3"), 1234), 3);
+}
+
+#[test]
+fn sockets_passed_to_another_process_are_ignored() {
+    assert_eq!(passed_fd_count(Some("1233"), Some("1"), 1234), 0);
+}
+
+#[test]
+fn missing_or_invalid_variables_mean_no_sockets() {
+    assert_eq!(passed_fd_count(None, None, 1234), 0);
+    assert_eq!(passed_fd_count(None, Some("1"), 1234), 0);
+    assert_eq!(passed_fd_count(Some("1234"), None, 1234), 0);
+    assert_eq!(passed_fd_count(Some("x"), Some("1"), 1234), 0);
+    assert_eq!(passed_fd_count(Some("1234"), Some("x"), 1234), 0);
+}
@@ -0,0 +1,174 @@
+use async_trait::async_trait;
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smsc::{
+    BindData, BindError, QuotaExceeded, Smsc, SmscLogic, SourceQuota,
+    SourceQuotaAlerts, SourceQuotas, SubmitSmError,
+};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::{new_submit_sm, TestClient, TestServer};
+
+fn quota(s: &str) -> SourceQuota {
+    s.parse().unwrap()
+}
+
+#[test]
+fn source_quotas_are_written_limit_slash_period() {
+    assert_eq!(quota("100/minute").to_string(), "100/minute");
+    assert_eq!(quota("5000/Day").to_string(), "5000/day");
+    assert!("100".parse::<SourceQuota>().is_err());
+    assert!("100/week".parse::<SourceQuota>().is_err());
+    assert!("x/hour".parse::<SourceQuota>().is_err());
+    assert!("0/hour".parse::<SourceQuota>().is_err());
+}
+
+#[test]
+fn sources_over_a_quota_are_refused_until_the_window_rolls_on() {
+    let two_a_minute = quota("2/minute");
+    let mut quotas = SourceQuotas::new(&[two_a_minute]);
+    let start = Instant::now();
+
+    assert!(quotas.try_acquire_at("Acme", start).is_ok());
+    let later = start + Duration::from_secs(30);
+    assert!(quotas.try_acquire_at("Acme", later).is_ok());
+    assert_eq!(
+        quotas.try_acquire_at("Acme", later).unwrap_err().quota,
+        two_a_minute
+    );
+    assert_eq!(quotas.count_at("Acme", &two_a_minute, later), 2);
+
+    // Other sources are unaffected
+    assert!(quotas.try_acquire_at("Other", later).is_ok());
+
+    // Once the first message is a minute old, there is room for one more
+    let after_a_minute = start + Duration::from_secs(60);
+    assert_eq!(quotas.count_at("Acme", &two_a_minute, after_a_minute), 1);
+    assert!(quotas.try_acquire_at("Acme", after_a_minute).is_ok());
+    assert!(quotas.try_acquire_at("Acme", after_a_minute).is_err());
+}
+
+#[test]
+fn every_quota_applies() {
+    let mut quotas = SourceQuotas::new(&[quota("2/minute"), quota("3/hour")]);
+    let start = Instant::now();
+
+    assert!(quotas.try_acquire_at("Acme", start).is_ok());
+    assert!(quotas.try_acquire_at("Acme", start).is_ok());
+    let later = start + Duration::from_secs(120);
+    assert!(quotas.try_acquire_at("Acme", later).is_ok());
+    let exceeded = quotas.try_acquire_at("Acme", later).unwrap_err();
+    assert_eq!(exceeded.quota, quota("3/hour"));
+}
+
+#[test]
+fn only_the_first_refusal_of_a_run_is_marked_first() {
+    let mut quotas = SourceQuotas::new(&[quota("1/minute")]);
+    let start = Instant::now();
+
+    assert!(quotas.try_acquire_at("Acme", start).is_ok());
+    assert!(quotas.try_acquire_at("Acme", start).unwrap_err().first);
+    assert!(!quotas.try_acquire_at("Acme", start).unwrap_err().first);
+
+    let later = start + Duration::from_secs(60);
+    assert!(quotas.try_acquire_at("Acme", later).is_ok());
+    assert!(quotas.try_acquire_at("Acme", later).unwrap_err().first);
+}
+
+#[tokio::test]
+async fn when_a_source_is_over_its_quota_we_respond_with_the_chosen_status() {
+    let server = TestServer::start_with_smsc_config(Logic {}, |c| {
+        c.source_quotas = vec![quota("1/minute")];
+        c.source_quota_status = 0x14; // ESME_RMSGQFUL
+    })
+    .await
+    .unwrap();
+    let alerts = Arc::new(Alerts::default());
+    server
+        .smsc
+        .lock()
+        .await
+        .set_source_quota_alerts(alerts.clone());
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transmitter().await;
+
+    client
+        .send_and_expect_response(
+            &new_submit_sm(0x10, "447700900123").await,
+            b"\x00\x00\x00\x13\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x10\
+            id\0",
+        )
+        .await;
+    for sequence_number in [0x11, 0x12] {
+        client
+            .send_and_expect_response(
+                &new_submit_sm(sequence_number, "447700900456").await,
+                &[
+                    b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x14\x00\x00\x00"
+                        .as_ref(),
+                    //                        ESME_RMSGQFUL ^^^^
+                    &[sequence_number as u8],
+                ]
+                .concat(),
+            )
+            .await;
+    }
+
+    assert_eq!(
+        *alerts.events.lock().unwrap(),
+        vec![QuotaExceeded {
+            source_addr: String::from("MyCompany"),
+            quota: quota("1/minute"),
+            first: true,
+        }]
+    );
+    assert_eq!(
+        server
+            .smsc
+            .lock()
+            .await
+            .source_quotas()
+            .count("MyCompany", &quota("1/minute")),
+        1
+    );
+}
+
+#[derive(Default)]
+struct Alerts {
+    events: std::sync::Mutex<Vec<QuotaExceeded>>,
+}
+
+impl SourceQuotaAlerts for Alerts {
+    fn quota_exceeded(&self, event: &QuotaExceeded) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
+
+struct Logic {}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Ok((
+            SubmitSmRespPdu::new("id").unwrap(),
+            MessageUniqueKey::new(
+                String::from("quotas"),
+                format!("{}", sequence_number),
+                pdu.destination_addr(),
+            ),
+        ))
+    }
+}
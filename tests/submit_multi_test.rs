@@ -0,0 +1,219 @@
+use async_trait::async_trait;
+use smpp::client::{BindMode, Client};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smpp_connection::Frame;
+use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
+use smpp::submit_multi::{
+    DestAddress, SmeAddress, SubmitMultiPdu, SubmitMultiRespPdu, UnsuccessSme,
+};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+async fn written(frame: Frame) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    frame.write(&mut bytes).await.unwrap();
+    bytes
+}
+
+fn dests(smes: &[&str], dl_names: &[&str]) -> Vec<DestAddress> {
+    let smes = smes.iter().map(|addr| DestAddress::sme(addr).unwrap());
+    let dl_names = dl_names
+        .iter()
+        .map(|name| DestAddress::distribution_list(name).unwrap());
+    smes.chain(dl_names).collect()
+}
+
+#[tokio::test]
+async fn submit_multi_is_written_and_parsed_with_every_destination() {
+    let submit_multi = SubmitMultiPdu::new(
+        7,
+        "Me",
+        dests(&["447700900123"], &["friends"]),
+        0,
+        b"hi",
+    )
+    .unwrap();
+
+    let bytes = written(Frame::SubmitMulti(submit_multi)).await;
+
+    assert_eq!(
+        bytes,
+        b"\x00\x00\x00\x3c\x00\x00\x00\x21\x00\x00\x00\x00\x00\x00\x00\x07\
+          \x00\x00\x00Me\x00\x02\
+          \x01\x00\x00447700900123\x00\
+          \x02friends\x00\
+          \x00\x00\x00\x00\x00\x00\x00\x00\x00\x02hi"
+    );
+    let parsed = SubmitMultiPdu::parse(&bytes).unwrap();
+    assert_eq!(
+        parsed.dest_addresses,
+        dests(&["447700900123"], &["friends"])
+    );
+    assert_eq!(parsed.short_message, b"hi");
+}
+
+#[tokio::test]
+async fn submit_multi_resp_lists_the_unsuccessful_smes() {
+    let unsuccess_sme = UnsuccessSme {
+        address: SmeAddress::new("447700900999").unwrap(),
+        error_status_code: 0x0B,
+    };
+    let resp =
+        SubmitMultiRespPdu::new(7, "id", vec![unsuccess_sme.clone()]).unwrap();
+
+    let bytes = written(Frame::SubmitMultiResp(resp)).await;
+
+    assert_eq!(
+        bytes,
+        b"\x00\x00\x00\x27\x80\x00\x00\x21\x00\x00\x00\x00\x00\x00\x00\x07\
+          id\x00\x01\
+          \x00\x00447700900999\x00\x00\x00\x00\x0b"
+    );
+    let parsed = SubmitMultiRespPdu::parse(&bytes).unwrap();
+    assert_eq!(parsed.message_id.value.as_str(), "id");
+    assert_eq!(parsed.unsuccess_smes, vec![unsuccess_sme]);
+}
+
+#[test]
+fn a_rejection_may_have_no_body() {
+    let resp = SubmitMultiRespPdu::parse(
+        b"\x00\x00\x00\x10\x80\x00\x00\x21\x00\x00\x00\x33\x00\x00\x00\x07",
+    )
+    .unwrap();
+
+    assert_eq!(resp, SubmitMultiRespPdu::new_error(0x33, 7));
+}
+
+#[test]
+fn submit_multi_fields_are_validated() {
+    assert!(SubmitMultiPdu::new(7, "Me", Vec::new(), 0, b"hi").is_err());
+    assert!(
+        SubmitMultiPdu::new(7, "Me", dests(&["1"; 255], &[]), 0, b"hi")
+            .is_err()
+    );
+    assert!(
+        SubmitMultiPdu::new(7, "Me", dests(&["1"], &[]), 0, &[b'x'; 255])
+            .is_err()
+    );
+    assert!(DestAddress::distribution_list(&"x".repeat(21)).is_err());
+}
+
+#[test]
+fn an_unknown_dest_flag_is_rejected() {
+    let err = SubmitMultiPdu::parse(
+        b"\x00\x00\x00\x1a\x00\x00\x00\x21\x00\x00\x00\x00\x00\x00\x00\x07\
+          \x00\x00\x00Me\x00\x01\
+          \x03x\x00",
+    )
+    .unwrap_err();
+
+    assert!(err.to_string().contains("dest_flag"), "{}", err);
+}
+
+#[tokio::test]
+async fn the_smsc_submits_to_each_sme_and_lists_the_failures() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transmitter, "esme1", "secret", "")
+        .await
+        .unwrap();
+
+    let resp = client
+        .submit_multi(
+            SubmitMultiPdu::new(
+                0,
+                "Me",
+                dests(
+                    &["447700900123", "447700900999", "447700900456"],
+                    &["friends"],
+                ),
+                0,
+                b"hi",
+            )
+            .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(resp.command_status, 0);
+    assert_eq!(resp.message_id.value.as_str(), "id-447700900123");
+    assert_eq!(
+        resp.unsuccess_smes,
+        vec![
+            UnsuccessSme {
+                address: SmeAddress::new("447700900999").unwrap(),
+                // ESME_RSYSERR
+                error_status_code: 0x08,
+            },
+            UnsuccessSme {
+                address: SmeAddress::new("friends").unwrap(),
+                // ESME_RINVDLNAME
+                error_status_code: 0x34,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn when_every_destination_fails_so_does_the_submit_multi() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transmitter, "esme1", "secret", "")
+        .await
+        .unwrap();
+
+    let resp = client
+        .submit_multi(
+            SubmitMultiPdu::new(
+                0,
+                "Me",
+                dests(&["447700900999"], &[]),
+                0,
+                b"hi",
+            )
+            .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // ESME_RSYSERR
+    assert_eq!(resp.command_status, 0x08);
+    assert!(resp.unsuccess_smes.is_empty());
+}
+
+/// Accepts every destination but 447700900999
+struct Logic {}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        let destination_addr = pdu.destination_addr();
+        if destination_addr == "447700900999" {
+            return Err(SubmitSmError::InternalError);
+        }
+        Ok((
+            SubmitSmRespPdu::new(&format!("id-{}", destination_addr)).unwrap(),
+            MessageUniqueKey::new(
+                String::from("multi"),
+                format!("{}", sequence_number),
+                destination_addr,
+            ),
+        ))
+    }
+}
@@ -0,0 +1,111 @@
+use async_trait::async_trait;
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smpp_connection::EsmeId;
+use smpp::smsc::{
+    BindData, BindError, Smsc, SmscLogic, SubmitSmArchive, SubmitSmError,
+};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::{new_submit_sm, TestSetup};
+
+#[tokio::test]
+async fn accepted_messages_are_archived_before_we_respond() {
+    let archive = Arc::new(Archive::default());
+    let mut t = TestSetup::new_with_logic(Logic {}).await;
+    t.server
+        .smsc
+        .lock()
+        .await
+        .set_archive(Arc::clone(&archive) as _);
+    t.client.bind_transmitter().await;
+
+    t.client
+        .send_and_expect_response(
+            &new_submit_sm(0x10, "447700900123").await,
+            b"\x00\x00\x00\x13\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x10\
+            id\0",
+        )
+        .await;
+
+    assert_eq!(
+        *archive.archived.lock().await,
+        vec![(
+            String::from("esmeid"),
+            String::from("447700900123"),
+            String::from("16")
+        )]
+    );
+}
+
+#[tokio::test]
+async fn rejected_messages_are_not_archived() {
+    let archive = Arc::new(Archive::default());
+    let mut t = TestSetup::new().await;
+    t.server
+        .smsc
+        .lock()
+        .await
+        .set_archive(Arc::clone(&archive) as _);
+    t.client.bind_transmitter().await;
+
+    // DefaultLogic rejects every submit_sm
+    t.client
+        .send_and_expect_response(
+            &new_submit_sm(0x10, "447700900123").await,
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x08\x00\x00\x00\x10",
+        )
+        .await;
+
+    assert!(archive.archived.lock().await.is_empty());
+}
+
+/// Remembers (system_id, destination_addr, message_id) of each message
+#[derive(Default)]
+struct Archive {
+    archived: Mutex<Vec<(String, String, String)>>,
+}
+
+#[async_trait]
+impl SubmitSmArchive for Archive {
+    async fn archive(
+        &self,
+        esme_id: &EsmeId,
+        pdu: &SubmitSmPdu,
+        message_unique_key: &MessageUniqueKey,
+    ) {
+        self.archived.lock().await.push((
+            esme_id.system_id.to_string(),
+            pdu.destination_addr(),
+            message_unique_key.message_id.clone(),
+        ));
+    }
+}
+
+struct Logic {}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Ok((
+            SubmitSmRespPdu::new("id").unwrap(),
+            MessageUniqueKey::new(
+                String::from("archive"),
+                format!("{}", sequence_number),
+                pdu.destination_addr(),
+            ),
+        ))
+    }
+}
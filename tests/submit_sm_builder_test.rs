@@ -0,0 +1,102 @@
+use smpp::protocol_id::ProtocolId;
+use smpp::sender::Sender;
+use smpp::submit_sm_builder::SubmitSmBuilder;
+use smpp::typed_tlvs::user_response_code;
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::SubmitSmPdu;
+
+#[test]
+fn unset_fields_are_empty_or_zero() {
+    let built = SubmitSmBuilder::new()
+        .destination_addr("447700900123")
+        .short_message(b"hello")
+        .build()
+        .unwrap();
+
+    let expected = SubmitSmPdu::new(
+        "",
+        0,
+        0,
+        "",
+        0,
+        0,
+        "447700900123",
+        0,
+        0,
+        0,
+        "",
+        "",
+        0,
+        0,
+        0,
+        0,
+        b"hello",
+        Tlvs::new(),
+    )
+    .unwrap();
+    assert_eq!(built, expected);
+}
+
+#[test]
+fn every_field_can_be_set_by_name() {
+    let built = SubmitSmBuilder::new()
+        .service_type("CMT")
+        .sender(&Sender::Alphanumeric(String::from("MyCompany")))
+        .destination("+447700900123")
+        .esm_class(0x40)
+        .protocol_id(ProtocolId::replace_short_message_type(1).unwrap())
+        .priority_flag(2)
+        .schedule_delivery_time("000000001000000R")
+        .validity_period("000001000000000R")
+        .registered_delivery(1)
+        .replace_if_present_flag(1)
+        .data_coding(8)
+        .sm_default_msg_id(3)
+        .short_message(b"hello")
+        .tlv(user_response_code(7))
+        .build()
+        .unwrap();
+
+    let expected = SubmitSmPdu::new(
+        "CMT",
+        5,
+        0,
+        "MyCompany",
+        1,
+        1,
+        "447700900123",
+        0x40,
+        0x41,
+        2,
+        "000000001000000R",
+        "000001000000000R",
+        1,
+        1,
+        8,
+        3,
+        b"hello",
+        Tlvs::from(&[user_response_code(7)]),
+    )
+    .unwrap();
+    assert_eq!(built, expected);
+}
+
+#[test]
+fn invalid_fields_are_reported_by_build() {
+    let err = SubmitSmBuilder::new()
+        .validity_period("tomorrow")
+        .build()
+        .unwrap_err();
+    assert!(err.to_string().contains("validity_period"), "{}", err);
+
+    let err = SubmitSmBuilder::new()
+        .source_addr("a source address far too long to send")
+        .build()
+        .unwrap_err();
+    assert!(err.to_string().contains("source_addr"), "{}", err);
+
+    assert!(SubmitSmBuilder::new()
+        .short_message(&[b'a'; 255])
+        .build()
+        .is_err());
+}
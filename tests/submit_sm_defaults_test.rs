@@ -0,0 +1,151 @@
+use async_trait::async_trait;
+use smpp::client::{BindMode, Client};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::sender::Sender;
+use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
+use smpp::submit_sm_defaults::SubmitSmDefaults;
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+fn defaults() -> SubmitSmDefaults {
+    SubmitSmDefaults {
+        service_type: Some(String::from("CMT")),
+        registered_delivery: Some(1),
+        validity_period: Some(String::from("000001000000000R")),
+        ..Default::default()
+    }
+    .with_sender(&Sender::Alphanumeric(String::from("MyCompany")))
+}
+
+fn bare_submit_sm(source_addr: &str, registered_delivery: u8) -> SubmitSmPdu {
+    SubmitSmPdu::new(
+        "",
+        1,
+        1,
+        source_addr,
+        1,
+        1,
+        "447700900123",
+        0,
+        0,
+        0,
+        "",
+        "",
+        registered_delivery,
+        0,
+        0,
+        0,
+        b"hello",
+        Tlvs::new(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn unset_fields_are_filled_in() {
+    let mut submit_sm = bare_submit_sm("", 0);
+
+    defaults().apply(&mut submit_sm).unwrap();
+
+    let sm = &submit_sm.0;
+    assert_eq!(sm.service_type.value.as_str(), "CMT");
+    assert_eq!(sm.source_addr.value.as_str(), "MyCompany");
+    assert_eq!(sm.source_addr_ton.value, 0x05);
+    assert_eq!(sm.source_addr_npi.value, 0x00);
+    assert_eq!(sm.registered_delivery.value, 1);
+    assert_eq!(sm.validity_period.value.as_str(), "000001000000000R");
+}
+
+#[test]
+fn fields_the_caller_set_are_kept() {
+    let mut submit_sm = bare_submit_sm("447700900999", 2);
+
+    defaults().apply(&mut submit_sm).unwrap();
+
+    let sm = &submit_sm.0;
+    assert_eq!(sm.source_addr.value.as_str(), "447700900999");
+    // The TON and NPI stay with the caller's source_addr
+    assert_eq!(sm.source_addr_ton.value, 1);
+    assert_eq!(sm.source_addr_npi.value, 1);
+    assert_eq!(sm.registered_delivery.value, 2);
+}
+
+#[test]
+fn invalid_defaults_are_reported() {
+    let defaults = SubmitSmDefaults {
+        validity_period: Some(String::from("tomorrow")),
+        ..Default::default()
+    };
+
+    let err = defaults.apply(&mut bare_submit_sm("", 0)).unwrap_err();
+
+    assert!(err.to_string().contains("validity_period"), "{}", err);
+}
+
+#[tokio::test]
+async fn submit_text_needs_only_a_destination_and_a_message() {
+    let submitted = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let server = TestServer::start_with_logic(Logic {
+        submitted: Arc::clone(&submitted),
+    })
+    .await
+    .unwrap();
+    let mut client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transmitter, "esme1", "secret", "")
+        .await
+        .unwrap();
+    client.set_submit_sm_defaults(defaults());
+
+    let resp = client.submit_text("447700900123", b"hello").await.unwrap();
+
+    assert_eq!(resp.command_status, 0);
+    assert_eq!(
+        *submitted.lock().unwrap(),
+        vec![(
+            String::from("MyCompany"),
+            String::from("447700900123"),
+            String::from("000001000000000R"),
+        )]
+    );
+}
+
+/// Records the source_addr, destination_addr and validity_period of each
+/// submit_sm.
+struct Logic {
+    submitted: Arc<std::sync::Mutex<Vec<(String, String, String)>>>,
+}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        self.submitted.lock().unwrap().push((
+            pdu.source_addr(),
+            pdu.destination_addr(),
+            pdu.0.validity_period.value.to_string(),
+        ));
+        Ok((
+            SubmitSmRespPdu::new("1234").unwrap(),
+            MessageUniqueKey::new(
+                String::from("testsystem"),
+                String::from("1234"),
+                pdu.destination_addr(),
+            ),
+        ))
+    }
+}
@@ -15,9 +15,9 @@ async fn listens_on_tcp_port() {
     // When we connect
     let mut client = TestClient::connect_to(&server).await.unwrap();
     // Then we can write and read to it
-    client.stream.write(BIND_TRANSMITTER_PDU).await.unwrap();
+    client.stream.write_all(BIND_TRANSMITTER_PDU).await.unwrap();
     let resp = client.read_string().await.unwrap();
-    assert!(resp.len() > 0);
+    assert!(!resp.is_empty());
 }
 
 #[tokio::test]
@@ -27,12 +27,12 @@ async fn stays_connected() {
     // When we connect
     let mut client = TestClient::connect_to(&server).await.unwrap();
     // Then we can write and read to it multiple times
-    client.stream.write(BIND_TRANSMITTER_PDU).await.unwrap();
+    client.stream.write_all(BIND_TRANSMITTER_PDU).await.unwrap();
     let resp = client.read_string().await.unwrap();
-    assert!(resp.len() > 0);
-    client.stream.write(BIND_TRANSMITTER_PDU).await.unwrap();
+    assert!(!resp.is_empty());
+    client.stream.write_all(BIND_TRANSMITTER_PDU).await.unwrap();
     let resp = client.read_string().await.unwrap();
-    assert!(resp.len() > 0);
+    assert!(!resp.is_empty());
 }
 
 #[tokio::test]
@@ -43,16 +43,28 @@ async fn disconnects_clients_when_overloaded() {
     let mut client1 = TestClient::connect_to(&server).await.unwrap();
     let mut client2 = TestClient::connect_to(&server).await.unwrap();
     let mut client3 = TestClient::connect_to(&server).await.unwrap();
-    client1.stream.write(BIND_TRANSMITTER_PDU).await.unwrap();
-    client2.stream.write(BIND_TRANSMITTER_PDU).await.unwrap();
-    client3.stream.write(BIND_TRANSMITTER_PDU).await.unwrap();
+    client1
+        .stream
+        .write_all(BIND_TRANSMITTER_PDU)
+        .await
+        .unwrap();
+    client2
+        .stream
+        .write_all(BIND_TRANSMITTER_PDU)
+        .await
+        .unwrap();
+    client3
+        .stream
+        .write_all(BIND_TRANSMITTER_PDU)
+        .await
+        .unwrap();
     let resp1 = client1.read_string().await.unwrap();
     let resp2 = client2.read_string().await.unwrap();
     let resp3_or_err = client3.read_string().await;
 
     // Then two of them are able to stay connected
-    assert!(resp1.len() > 0);
-    assert!(resp2.len() > 0);
+    assert!(!resp1.is_empty());
+    assert!(!resp2.is_empty());
 
     // And the third gets immediately disconnected
     let resp3 = resp3_or_err.unwrap_or(String::from(""));
@@ -2,9 +2,12 @@ use async_trait::async_trait;
 use once_cell::sync::Lazy;
 use smpp::async_result::AsyncResult;
 use smpp::message_unique_key::MessageUniqueKey;
+use smpp::session_memory::SessionMemoryAction;
 use smpp::smsc::{
     BindData, BindError, Smsc, SmscConfig, SmscLogic, SubmitSmError,
+    UnknownCommandAction,
 };
+use smpp_pdu::pdu::tlvs::Tlvs;
 use smpp_pdu::pdu::{Pdu, SubmitSmPdu, SubmitSmRespPdu};
 use std::io;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -59,13 +62,24 @@ impl TestSetup {
         Self { server, client }
     }
 
+    pub async fn new_recovering() -> Self {
+        let server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+            c.recover_from_bad_pdus = true
+        })
+        .await
+        .unwrap();
+        let client = TestClient::connect_to(&server).await.unwrap();
+        Self { server, client }
+    }
+
     pub async fn new_client(&mut self) {
         self.client = TestClient::connect_to(&self.server).await.unwrap();
     }
 }
 
-fn next_port() -> usize {
-    return PORT.fetch_add(1, Ordering::Relaxed);
+#[allow(dead_code)]
+pub fn next_port() -> usize {
+    PORT.fetch_add(1, Ordering::Relaxed)
 }
 
 /// A test server listening on the test port
@@ -92,6 +106,18 @@ impl TestServer {
     >(
         smsc_logic: L,
         max_open_sockets: usize,
+    ) -> AsyncResult<Self> {
+        TestServer::start_with_smsc_config(smsc_logic, |c| {
+            c.max_open_sockets = max_open_sockets
+        })
+        .await
+    }
+
+    pub async fn start_with_smsc_config<
+        L: SmscLogic + Send + Sync + 'static,
+    >(
+        smsc_logic: L,
+        configure: impl FnOnce(&mut SmscConfig),
     ) -> AsyncResult<Self> {
         let _ = env_logger::builder()
             .filter_level(log::LevelFilter::Trace)
@@ -100,13 +126,53 @@ impl TestServer {
 
         let bind_address = format!("{}:{}", TEST_BIND_URL, next_port());
 
-        let smsc_config = SmscConfig {
+        let mut smsc_config = SmscConfig {
             bind_address: String::from(&bind_address),
-            max_open_sockets,
+            max_open_sockets: 2,
             system_id: String::from("TestServer"),
+            recover_from_bad_pdus: false,
+            capture_sessions: false,
+            idle_timeout_secs: None,
+            enquire_link_interval_secs: None,
+            enquire_link_timeout_secs: 10,
+            partial_pdu_timeout_secs: None,
+            min_bytes_per_sec: None,
+            max_session_memory: None,
+            max_pdu_length: 70000,
+            lenient_parsing: false,
+            session_memory_action: SessionMemoryAction::Throttle,
+            default_country_code: None,
+            destination_limits: Vec::new(),
+            canned_messages: Vec::new(),
+            source_quotas: Vec::new(),
+            source_quota_status: 0x58,
+            duplicate_window_secs: None,
+            suppress_duplicates: false,
+            query_cache_ms: None,
+            max_enquire_links_per_sec: None,
+            data_coding_remaps: Vec::new(),
+            status_info_text: false,
+            scenario: None,
+            latency: None,
+            throttle_burst_percent: 0.0,
+            throttle_burst_length: 10,
+            forced_unbind_secs: None,
+            chaos_seed: None,
+            unknown_command: UnknownCommandAction::Nack,
+            #[cfg(feature = "admin-http")]
+            admin_address: None,
+            #[cfg(feature = "websocket")]
+            websocket_address: None,
+            #[cfg(feature = "websocket")]
+            websocket_handshake_timeout_secs: 10,
+            #[cfg(feature = "compression")]
+            compress: false,
+            #[cfg(unix)]
+            unix_socket: None,
         };
+        configure(&mut smsc_config);
 
-        let smsc = Smsc::start(smsc_config, smsc_logic).await.unwrap();
+        let smsc = Smsc::start(smsc_config, smsc_logic).await?;
 
         let server = TestServer { smsc, bind_address };
 
@@ -208,7 +274,7 @@ impl TestClient {
     }
 
     async fn send_exp(&mut self, input: &[u8], expected_output: &[u8]) {
-        self.stream.write(input).await.unwrap();
+        self.stream.write_all(input).await.unwrap();
         self.expect_to_receive(expected_output).await;
     }
 
@@ -281,3 +347,70 @@ pub fn bytes_as_string(arr: &[u8]) -> String {
         .collect::<Vec<String>>()
         .join("")
 }
+
+/// A submit_sm from "MyCompany" to destination_addr saying "hello" and
+/// asking for a delivery receipt.  Tests change whichever fields they
+/// need.
+#[allow(dead_code)]
+pub fn submit_sm_to(destination_addr: &str) -> SubmitSmPdu {
+    SubmitSmPdu::new(
+        "",
+        5,
+        0,
+        "MyCompany",
+        1,
+        1,
+        destination_addr,
+        0,
+        0,
+        0,
+        "",
+        "",
+        1,
+        0,
+        0,
+        0,
+        b"hello",
+        Tlvs::new(),
+    )
+    .unwrap()
+}
+
+/// The bytes of a simple submit_sm PDU
+#[allow(dead_code)]
+pub async fn new_submit_sm(
+    sequence_number: u32,
+    destination_addr: &str,
+) -> Vec<u8> {
+    let pdu = Pdu::new(
+        0,
+        sequence_number,
+        SubmitSmPdu::new(
+            "",
+            0,
+            0,
+            "MyCompany",
+            0,
+            0,
+            destination_addr,
+            0,
+            0x34,
+            1,
+            "",
+            "",
+            1,
+            0,
+            3,
+            0,
+            b"hi",
+            Tlvs::new(),
+        )
+        .unwrap()
+        .into(),
+    )
+    .unwrap();
+
+    let mut ret: Vec<u8> = Vec::new();
+    pdu.write(&mut ret).await.unwrap();
+    ret
+}
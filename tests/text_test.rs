@@ -0,0 +1,222 @@
+use smpp::submit_sm_builder::SubmitSmBuilder;
+use smpp::text::{
+    is_binary, Charset, Codec, DataCodingMap, DataCodingRemap, DecodeTextError,
+    MessageText,
+};
+use std::sync::Arc;
+
+mod test_utils;
+
+use test_utils::{DefaultLogic, TestClient, TestServer};
+
+#[test]
+fn gsm7_uses_the_default_alphabet_and_its_extension() {
+    assert_eq!(Charset::Gsm7.encode("@Hi £5!").unwrap(), b"\x00Hi \x015!");
+    assert_eq!(
+        Charset::Gsm7.encode("€[x]").unwrap(),
+        b"\x1b\x65\x1b\x3cx\x1b\x3e"
+    );
+    assert_eq!(Charset::Gsm7.decode(b"\x1b\x65\x1b\x3cx\x1b\x3e"), "€[x]");
+    assert_eq!(Charset::Gsm7.encode("Привет"), None);
+    assert_eq!(Charset::Gsm7.decode(b"\x00\x80"), "@?");
+}
+
+#[test]
+fn ascii_latin1_and_ucs2_round_trip() {
+    assert_eq!(Charset::Ascii.encode("café"), None);
+    assert_eq!(Charset::Latin1.encode("café").unwrap(), b"caf\xe9");
+    assert_eq!(Charset::Latin1.decode(b"caf\xe9"), "café");
+    assert_eq!(Charset::Ucs2.encode("Пр").unwrap(), b"\x04\x1f\x04\x40");
+    assert_eq!(Charset::Ucs2.decode(b"\x04\x1f\x04\x40"), "Пр");
+}
+
+#[test]
+fn the_default_map_follows_the_specification() {
+    let map = DataCodingMap::default();
+    assert_eq!(map.codec(0).map(Codec::name), Some("gsm7"));
+    assert_eq!(map.codec(1).map(Codec::name), Some("ascii"));
+    assert_eq!(map.codec(3).map(Codec::name), Some("latin1"));
+    assert_eq!(map.codec(8).map(Codec::name), Some("ucs2"));
+    assert!(map.codec(2).is_none());
+    assert_eq!(map.decode(3, b"\xe9"), Some(String::from("é")));
+    assert_eq!(map.decode(2, b"\xe9"), None);
+}
+
+#[test]
+fn carriers_can_remap_data_coding_values() {
+    let remap: DataCodingRemap = "0=latin1".parse().unwrap();
+    assert_eq!(
+        remap,
+        DataCodingRemap {
+            data_coding: 0,
+            charset: Charset::Latin1
+        }
+    );
+
+    let map = DataCodingMap::with_remaps(&[remap]);
+    assert_eq!(map.decode(0, b"caf\xe9"), Some(String::from("café")));
+    assert_eq!(map.select("é"), Some((0, vec![0xe9])));
+    // Nothing means GSM 03.38 any more, so this needs UCS-2
+    assert_eq!(map.select("Δ"), Some((8, vec![0x03, 0x94])));
+}
+
+#[test]
+fn text_is_sent_in_the_most_preferred_codec_that_can_write_it() {
+    let map = DataCodingMap::default();
+    assert_eq!(map.select("£1"), Some((0, vec![0x01, b'1'])));
+    assert_eq!(map.select("ô"), Some((3, vec![0xf4])));
+    assert_eq!(map.select("Пр"), Some((8, vec![0x04, 0x1f, 0x04, 0x40])));
+}
+
+/// Enough of ISO-8859-5 to write Russian
+struct Cyrillic;
+
+impl Codec for Cyrillic {
+    fn name(&self) -> &str {
+        "cyrillic"
+    }
+
+    fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        text.chars()
+            .map(|c| match c {
+                c if c.is_ascii() => Some(c as u8),
+                'А'..='я' => Some((c as u32 - 'А' as u32 + 0xB0) as u8),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> String {
+        bytes
+            .iter()
+            .map(|&b| match b {
+                0x00..=0x7F => b as char,
+                0xB0..=0xEF => {
+                    std::char::from_u32(u32::from(b) - 0xB0 + 'А' as u32)
+                        .unwrap()
+                }
+                _ => '?',
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn custom_codecs_can_be_registered_and_preferred() {
+    let mut map = DataCodingMap::default();
+    map.register(6, Arc::new(Cyrillic));
+    map.set_preference(&[0, 6, 8]);
+
+    assert_eq!(map.select("Hi"), Some((0, Vec::from(&b"Hi"[..]))));
+    assert_eq!(
+        map.select("Привет"),
+        Some((6, vec![0xbf, 0xe0, 0xd8, 0xd2, 0xd5, 0xe2]))
+    );
+    assert_eq!(
+        map.decode(6, b"\xbf\xe0\xd8\xd2\xd5\xe2"),
+        Some(String::from("Привет"))
+    );
+    // Not Cyrillic, so fall back to UCS-2
+    assert_eq!(map.select("Ж€ô").unwrap().0, 8);
+}
+
+#[test]
+fn bad_remaps_are_rejected() {
+    assert_eq!(
+        "0=klingon"
+            .parse::<DataCodingRemap>()
+            .unwrap_err()
+            .to_string(),
+        "Invalid data_coding mapping '0=klingon': expected \
+        DATA_CODING=CHARSET, e.g. 0=latin1"
+    );
+    assert!("256=gsm7".parse::<DataCodingRemap>().is_err());
+    assert!("latin1".parse::<DataCodingRemap>().is_err());
+}
+
+#[tokio::test]
+async fn smsc_connections_use_the_configured_remaps() {
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.data_coding_remaps = vec!["0=latin1".parse().unwrap()]
+    })
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transmitter().await;
+
+    let connections = server.smsc.lock().await.connections();
+    assert_eq!(
+        connections[0].data_coding_map().codec(0).map(Codec::name),
+        Some("latin1")
+    );
+}
+
+#[tokio::test]
+async fn smsc_connections_can_use_custom_codecs() {
+    let server = TestServer::start().await.unwrap();
+    let mut map = DataCodingMap::default();
+    map.register(6, Arc::new(Cyrillic));
+    server.smsc.lock().await.set_data_coding_map(map);
+
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transmitter().await;
+
+    let connections = server.smsc.lock().await.connections();
+    assert_eq!(
+        connections[0].data_coding_map().decode(6, b"\xb0"),
+        Some(String::from("А"))
+    );
+}
+
+#[test]
+fn binary_data_codings_are_recognised() {
+    assert!(is_binary(0x04));
+    assert!(is_binary(0xF5));
+    assert!(!is_binary(0x08));
+    assert!(!is_binary(0xF1));
+}
+
+#[test]
+fn message_text_picks_the_data_coding() {
+    let map = DataCodingMap::default();
+
+    let gsm7 = SubmitSmBuilder::new().message_text("€5").build().unwrap();
+    let ucs2 = SubmitSmBuilder::new()
+        .message_text("Привет")
+        .build()
+        .unwrap();
+
+    assert_eq!(gsm7.0.data_coding.value, 0x00);
+    assert_eq!(gsm7.0.short_message.value, b"\x1b\x655");
+    assert_eq!(gsm7.decode_text(&map).unwrap(), "€5");
+    assert_eq!(ucs2.0.data_coding.value, 0x08);
+    assert_eq!(ucs2.decode_text(&map).unwrap(), "Привет");
+}
+
+#[test]
+fn message_text_that_no_alphabet_can_write_is_an_error() {
+    let mut map = DataCodingMap::default();
+    map.set_preference(&[0x00]);
+
+    let err = SubmitSmBuilder::new()
+        .data_coding_map(&map)
+        .message_text("Привет")
+        .build()
+        .unwrap_err();
+
+    assert!(err.to_string().contains("short_message"), "{}", err);
+}
+
+#[test]
+fn binary_messages_have_no_text() {
+    let submit_sm = SubmitSmBuilder::new()
+        .data_coding(0xF6)
+        .short_message(b"\x02\x70")
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        submit_sm.decode_text(&DataCodingMap::default()),
+        Err(DecodeTextError::Binary(0xF6))
+    );
+}
@@ -0,0 +1,181 @@
+use smpp::typed_tlvs::{
+    additional_status_info_text, delivery_failure_reason, dest_subaddress,
+    network_error_code, number_of_messages, privacy_indicator, sms_signal,
+    source_subaddress, user_response_code, DeliveryFailureReason,
+    NetworkErrorCode, PrivacyIndicator, Subaddress, SubaddressType,
+    TlvValueOutOfRange, TypedTlvs, MAX_STATUS_INFO_TEXT_LEN, NETWORK_TYPE_GSM,
+};
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use std::convert::TryFrom;
+
+#[test]
+fn additional_status_info_text_is_a_c_octet_string() {
+    let tlv = additional_status_info_text("Bad address");
+    assert_eq!(tlv.raw_tag, 0x001D);
+    assert_eq!(tlv.value, b"Bad address\0");
+    assert_eq!(
+        Tlvs::from(&[tlv]).additional_status_info_text(),
+        Some(String::from("Bad address"))
+    );
+}
+
+#[test]
+fn additional_status_info_text_is_ascii_and_limited_in_length() {
+    assert_eq!(additional_status_info_text("café\n").value, b"caf??\0");
+
+    let long = "x".repeat(300);
+    let tlv = additional_status_info_text(&long);
+    assert_eq!(tlv.value.len(), MAX_STATUS_INFO_TEXT_LEN + 1);
+}
+
+#[test]
+fn delivery_failure_reason_is_one_byte() {
+    let tlv =
+        delivery_failure_reason(DeliveryFailureReason::TemporaryNetworkError);
+    assert_eq!(tlv.raw_tag, 0x0425);
+    assert_eq!(tlv.value, [3]);
+
+    let tlvs = Tlvs::from(&[
+        tlv,
+        Tlv::new(KnownTlvTag::additional_status_info_text, b"\0"),
+    ]);
+    assert_eq!(
+        tlvs.delivery_failure_reason(),
+        Some(DeliveryFailureReason::TemporaryNetworkError)
+    );
+    assert_eq!(tlvs.additional_status_info_text(), Some(String::new()));
+}
+
+#[test]
+fn missing_or_malformed_tlvs_are_none() {
+    assert_eq!(Tlvs::new().delivery_failure_reason(), None);
+    assert_eq!(Tlvs::new().additional_status_info_text(), None);
+
+    let tlvs =
+        Tlvs::from(&[Tlv::new(KnownTlvTag::delivery_failure_reason, &[1, 2])]);
+    assert_eq!(tlvs.delivery_failure_reason(), None);
+    assert_eq!(
+        DeliveryFailureReason::from(9),
+        DeliveryFailureReason::Reserved(9)
+    );
+}
+
+#[test]
+fn number_of_messages_is_at_most_99() {
+    let tlv = number_of_messages(3).unwrap();
+    assert_eq!(tlv.raw_tag, 0x0304);
+    assert_eq!(tlv.value, [3]);
+    assert_eq!(Tlvs::from(&[tlv]).number_of_messages(), Some(3));
+
+    let err = number_of_messages(100).unwrap_err();
+    assert_eq!(
+        err,
+        TlvValueOutOfRange {
+            name: "number_of_messages",
+            value: 100,
+            min: 0,
+            max: 99
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "number_of_messages must be between 0 and 99, but was 100."
+    );
+
+    let tlvs = Tlvs::from(&[Tlv::new(KnownTlvTag::number_of_messages, &[100])]);
+    assert_eq!(tlvs.number_of_messages(), None);
+}
+
+#[test]
+fn sms_signal_is_two_bytes() {
+    let tlv = sms_signal(0x0102);
+    assert_eq!(tlv.raw_tag, 0x1203);
+    assert_eq!(tlv.value, [1, 2]);
+    assert_eq!(Tlvs::from(&[tlv]).sms_signal(), Some(0x0102));
+
+    let tlvs = Tlvs::from(&[Tlv::new(KnownTlvTag::sms_signal, &[89])]);
+    assert_eq!(tlvs.sms_signal(), None);
+}
+
+#[test]
+fn subaddresses_start_with_their_type() {
+    let subaddress = Subaddress {
+        subaddress_type: SubaddressType::UserSpecified,
+        subaddress: Vec::from(&b"1234"[..]),
+    };
+    let tlv = source_subaddress(&subaddress).unwrap();
+    assert_eq!(tlv.raw_tag, 0x0202);
+    assert_eq!(tlv.value, b"\xa01234");
+    assert_eq!(Tlvs::from(&[tlv]).source_subaddress(), Some(subaddress));
+
+    let tlv = Tlv::new(KnownTlvTag::dest_subaddress, b"\x88\x01\x02");
+    assert_eq!(
+        Tlvs::from(&[tlv]).dest_subaddress(),
+        Some(Subaddress {
+            subaddress_type: SubaddressType::NsapOdd,
+            subaddress: vec![1, 2],
+        })
+    );
+}
+
+#[test]
+fn subaddresses_must_be_2_to_23_bytes_long() {
+    let subaddress = |len| Subaddress {
+        subaddress_type: SubaddressType::NsapEven,
+        subaddress: vec![0; len],
+    };
+    assert!(dest_subaddress(&subaddress(1)).is_ok());
+    assert!(dest_subaddress(&subaddress(22)).is_ok());
+    assert_eq!(
+        dest_subaddress(&subaddress(0)).unwrap_err().to_string(),
+        "dest_subaddress length must be between 2 and 23, but was 1."
+    );
+    assert!(dest_subaddress(&subaddress(23)).is_err());
+
+    let tlv = Tlv::new(KnownTlvTag::source_subaddress, b"\x80");
+    assert_eq!(Tlvs::from(&[tlv]).source_subaddress(), None);
+}
+
+#[test]
+fn privacy_indicator_must_be_0_to_3() {
+    let tlv = privacy_indicator(PrivacyIndicator::Confidential);
+    assert_eq!(tlv.raw_tag, 0x0201);
+    assert_eq!(tlv.value, [2]);
+    assert_eq!(
+        Tlvs::from(&[tlv]).privacy_indicator(),
+        Some(PrivacyIndicator::Confidential)
+    );
+
+    assert_eq!(
+        PrivacyIndicator::try_from(4).unwrap_err().to_string(),
+        "privacy_indicator must be between 0 and 3, but was 4."
+    );
+    let tlv = Tlv::new(KnownTlvTag::privacy_indicator, &[4]);
+    assert_eq!(Tlvs::from(&[tlv]).privacy_indicator(), None);
+}
+
+#[test]
+fn user_response_code_is_one_byte() {
+    let tlv = user_response_code(255);
+    assert_eq!(tlv.raw_tag, 0x0205);
+    assert_eq!(tlv.value, [255]);
+    assert_eq!(Tlvs::from(&[tlv]).user_response_code(), Some(255));
+
+    let tlv = Tlv::new(KnownTlvTag::user_response_code, &[]);
+    assert_eq!(Tlvs::from(&[tlv]).user_response_code(), None);
+}
+
+#[test]
+fn network_error_code_is_type_then_code() {
+    let code = NetworkErrorCode {
+        network_type: NETWORK_TYPE_GSM,
+        error_code: 27,
+    };
+    let tlv = network_error_code(code);
+    assert_eq!(tlv.raw_tag, 0x0423);
+    assert_eq!(tlv.value, [3, 0, 27]);
+    assert_eq!(Tlvs::from(&[tlv]).network_error_code(), Some(code));
+
+    let tlv = Tlv::new(KnownTlvTag::network_error_code, &[3, 27]);
+    assert_eq!(Tlvs::from(&[tlv]).network_error_code(), None);
+}
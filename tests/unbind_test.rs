@@ -0,0 +1,74 @@
+use smpp::unbind::{UnbindPdu, UnbindRespPdu};
+use std::io;
+use tokio::io::AsyncWriteExt;
+
+mod test_utils;
+
+use test_utils::TestSetup;
+
+const UNBIND: &[u8] =
+    b"\x00\x00\x00\x10\x00\x00\x00\x06\x00\x00\x00\x00\x00\x00\x00\x05";
+
+const UNBIND_RESP: &[u8] =
+    b"\x00\x00\x00\x10\x80\x00\x00\x06\x00\x00\x00\x00\x00\x00\x00\x05";
+
+#[tokio::test]
+async fn unbind_round_trips() {
+    let unbind = UnbindPdu::parse(UNBIND).unwrap();
+    assert_eq!(unbind, UnbindPdu::new(5));
+    assert!(UnbindPdu::is_unbind(UNBIND));
+    assert!(!UnbindPdu::is_unbind(UNBIND_RESP));
+
+    let mut written = Vec::new();
+    unbind.write(&mut written).await.unwrap();
+    assert_eq!(written, UNBIND);
+}
+
+#[tokio::test]
+async fn unbind_resp_round_trips() {
+    let resp = UnbindRespPdu::parse(UNBIND_RESP).unwrap();
+    assert_eq!(resp, UnbindRespPdu::new(0, 5));
+    assert!(UnbindRespPdu::is_unbind_resp(UNBIND_RESP));
+
+    let mut written = Vec::new();
+    resp.write(&mut written).await.unwrap();
+    assert_eq!(written, UNBIND_RESP);
+}
+
+#[test]
+fn malformed_unbinds_are_rejected() {
+    for frame in &[
+        // With a body
+        &b"\x00\x00\x00\x11\x00\x00\x00\x06\x00\x00\x00\x00\x00\x00\x00\x05\x00"
+            [..],
+        // With a command_status
+        &b"\x00\x00\x00\x10\x00\x00\x00\x06\x00\x00\x00\x08\x00\x00\x00\x05"[..],
+        // Not an unbind
+        UNBIND_RESP,
+    ] {
+        assert!(UnbindPdu::parse(frame).is_err());
+    }
+}
+
+#[tokio::test]
+async fn smsc_responds_to_unbind_and_closes_the_connection() {
+    let mut t = TestSetup::new().await;
+    t.client.bind_transceiver().await;
+    t.client.send_and_expect_response(UNBIND, UNBIND_RESP).await;
+
+    let e = t.client.read_n_maybe(1).await.unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[tokio::test]
+async fn unbind_resp_needs_no_response() {
+    let mut t = TestSetup::new().await;
+    t.client.bind_transceiver().await;
+    t.client.stream.write_all(UNBIND_RESP).await.unwrap();
+    t.client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x06",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x06",
+        )
+        .await;
+}
@@ -0,0 +1,88 @@
+use async_trait::async_trait;
+use smpp::client::{BindMode, Client};
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
+use smpp::unique_id::{MessageUid, SessionId};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::{submit_sm_to, TestServer};
+
+#[test]
+fn ids_are_displayed_with_what_they_identify() {
+    assert_eq!(SessionId(3).to_string(), "session-3");
+    assert_eq!(MessageUid(4).to_string(), "message-4");
+}
+
+#[tokio::test]
+async fn each_connection_has_its_own_session_id() {
+    let server = TestServer::start().await.unwrap();
+    let client1 = Client::connect(&server.bind_address).await.unwrap();
+    let client2 = Client::connect(&server.bind_address).await.unwrap();
+
+    let id1 = client1.connection().session_id();
+    let id2 = client2.connection().session_id();
+
+    assert_ne!(id1, id2);
+    assert!(client1
+        .connection()
+        .to_string()
+        .ends_with(&format!(" {}", id1)));
+}
+
+#[tokio::test]
+async fn each_accepted_message_gets_a_message_uid() {
+    let server = TestServer::start_with_logic(Logic {}).await.unwrap();
+    let client = Client::connect(&server.bind_address).await.unwrap();
+    client
+        .bind(BindMode::Transmitter, "esme1", "secret", "")
+        .await
+        .unwrap();
+
+    client
+        .submit_sm(submit_sm_to("447700900123"))
+        .await
+        .unwrap();
+    client
+        .submit_sm(submit_sm_to("447700900124"))
+        .await
+        .unwrap();
+
+    let smsc = server.smsc.lock().await;
+    let uid1 = smsc.message_uid(&key("447700900123")).unwrap();
+    let uid2 = smsc.message_uid(&key("447700900124")).unwrap();
+    assert_ne!(uid1, uid2);
+    assert_eq!(smsc.message_uid(&key("447700900125")), None);
+}
+
+struct Logic {}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Ok((
+            SubmitSmRespPdu::new("1234").unwrap(),
+            key(&pdu.destination_addr()),
+        ))
+    }
+}
+
+fn key(destination_addr: &str) -> MessageUniqueKey {
+    MessageUniqueKey::new(
+        String::from("testsystem"),
+        String::from("1234"),
+        String::from(destination_addr),
+    )
+}
@@ -0,0 +1,84 @@
+#![cfg(unix)]
+
+use smpp::smpp_connection::{PeerAddr, SmppConnection};
+use smpp_pdu::pdu::{EnquireLinkPdu, Pdu, PduBody, PduStatus};
+use std::path::{Path, PathBuf};
+
+mod test_utils;
+
+use test_utils::{next_port, DefaultLogic, TestServer};
+
+fn socket_path() -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "smpp-test-{}-{}.sock",
+        std::process::id(),
+        next_port()
+    ))
+}
+
+async fn start(path: &Path) -> TestServer {
+    TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.unix_socket = Some(path.to_path_buf())
+    })
+    .await
+    .unwrap()
+}
+
+async fn enquire_link(connection: &SmppConnection) -> Pdu {
+    let pdu = Pdu::new(
+        PduStatus::ESME_ROK as u32,
+        0x2a,
+        EnquireLinkPdu::new().into(),
+    )
+    .unwrap();
+    connection.write_pdu(&pdu).await.unwrap();
+    connection.read_pdu().await.unwrap().unwrap()
+}
+
+#[tokio::test]
+async fn clients_can_talk_smpp_over_a_unix_socket() {
+    let path = socket_path();
+    let _server = start(&path).await;
+
+    let connection = SmppConnection::connect_unix(&path).await.unwrap();
+    assert_eq!(connection.peer_addr, PeerAddr::Unix(path.clone()));
+    assert_eq!(
+        connection.peer_addr.to_string(),
+        format!("unix:{}", path.display())
+    );
+
+    let response = enquire_link(&connection).await;
+    assert!(matches!(response.body(), PduBody::EnquireLinkResp(_)));
+    assert_eq!(response.sequence_number.value, 0x2a);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn stale_socket_files_are_replaced() {
+    let path = socket_path();
+    // Leaves the socket file behind when dropped, like a crashed SMSC
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+    let _server = start(&path).await;
+
+    let connection = SmppConnection::connect_unix(&path).await.unwrap();
+    let response = enquire_link(&connection).await;
+    assert!(matches!(response.body(), PduBody::EnquireLinkResp(_)));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn sockets_in_use_are_not_replaced() {
+    let path = socket_path();
+    let _server = start(&path).await;
+
+    let second = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.unix_socket = Some(path.clone())
+    })
+    .await;
+    assert!(second.is_err());
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,234 @@
+#![cfg(feature = "websocket")]
+
+use smpp::websocket;
+use smpp_pdu::pdu::MAX_PDU_LENGTH;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+mod test_utils;
+
+use test_utils::{next_port, DefaultLogic, TestServer};
+
+const ENQUIRE_LINK: &[u8; 16] =
+    b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x2a";
+//    length=16       enquire_link    status          sequence_number
+
+const ENQUIRE_LINK_RESP: &[u8; 16] =
+    b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x2a";
+//    length=16       enquire_link_resp
+
+async fn start() -> (TestServer, String) {
+    start_with_max_pdu_length(MAX_PDU_LENGTH).await
+}
+
+async fn start_with_max_pdu_length(
+    max_pdu_length: usize,
+) -> (TestServer, String) {
+    let websocket_address = format!("127.0.0.1:{}", next_port());
+    let server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.websocket_address = Some(websocket_address.clone());
+        c.max_pdu_length = max_pdu_length;
+    })
+    .await
+    .unwrap();
+    (server, websocket_address)
+}
+
+/// Send a handshake by hand, and return the response headers.
+async fn handshake(stream: &mut TcpStream) -> String {
+    stream
+        .write_all(
+            b"GET /smpp HTTP/1.1\r\n\
+            Host: smsc\r\n\
+            Upgrade: websocket\r\n\
+            Connection: Upgrade\r\n\
+            Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+            Sec-WebSocket-Version: 13\r\n\
+            \r\n",
+        )
+        .await
+        .unwrap();
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        response.push(stream.read_u8().await.unwrap());
+    }
+    String::from_utf8(response).unwrap()
+}
+
+/// The value of the named header, ignoring case in its name.
+fn header<'a>(response: &'a str, name: &str) -> Option<&'a str> {
+    response.lines().skip(1).find_map(|line| {
+        let (n, value) = line.split_once(':')?;
+        if n.trim().eq_ignore_ascii_case(name) {
+            Some(value.trim())
+        } else {
+            None
+        }
+    })
+}
+
+/// A client frame, masked with a zero mask so the payload is readable.
+fn masked_frame(first_byte: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![first_byte, 0x80 | payload.len() as u8, 0, 0, 0, 0];
+    frame.extend(payload);
+    frame
+}
+
+#[test]
+fn accept_key_matches_the_rfc_example() {
+    assert_eq!(
+        websocket::accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+        "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+    );
+}
+
+#[tokio::test]
+async fn smpp_works_over_a_websocket() {
+    let (_server, address) = start().await;
+    let tcp_stream = TcpStream::connect(&address).await.unwrap();
+    let mut stream =
+        websocket::connect(tcp_stream, &address, "/smpp", MAX_PDU_LENGTH)
+            .await
+            .unwrap();
+
+    stream.write_all(ENQUIRE_LINK).await.unwrap();
+    let mut response = [0; 16];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(&response, ENQUIRE_LINK_RESP);
+}
+
+#[tokio::test]
+async fn pdus_may_be_split_across_frames_but_responses_are_one_message() {
+    let (_server, address) = start().await;
+    let mut stream = TcpStream::connect(&address).await.unwrap();
+
+    let response = handshake(&mut stream).await;
+    assert!(response.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+    assert_eq!(
+        header(&response, "Sec-WebSocket-Accept"),
+        Some("s3pPLMBiTxaQ9kYGzzhZRbK+xOo=")
+    );
+
+    // binary, not final, then continuation, final
+    stream
+        .write_all(&masked_frame(0x02, &ENQUIRE_LINK[..5]))
+        .await
+        .unwrap();
+    stream
+        .write_all(&masked_frame(0x80, &ENQUIRE_LINK[5..]))
+        .await
+        .unwrap();
+
+    let mut response = [0; 18];
+    stream.read_exact(&mut response).await.unwrap();
+    // final binary, unmasked, length 16
+    assert_eq!(&response[..2], b"\x82\x10");
+    assert_eq!(&response[2..], ENQUIRE_LINK_RESP);
+}
+
+#[tokio::test]
+async fn pings_are_answered() {
+    let (_server, address) = start().await;
+    let mut stream = TcpStream::connect(&address).await.unwrap();
+    handshake(&mut stream).await;
+
+    stream.write_all(&masked_frame(0x89, b"hi")).await.unwrap();
+
+    let mut response = [0; 4];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(&response, b"\x8a\x02hi");
+}
+
+#[tokio::test]
+async fn requests_that_are_not_upgrades_are_rejected() {
+    let (_server, address) = start().await;
+    let mut stream = TcpStream::connect(&address).await.unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: smsc\r\n\r\n")
+        .await
+        .unwrap();
+
+    // Closed without switching protocols
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert_eq!(response, "");
+}
+
+#[tokio::test]
+async fn unmasked_client_frames_close_the_websocket() {
+    let (_server, address) = start().await;
+    let mut stream = TcpStream::connect(&address).await.unwrap();
+    handshake(&mut stream).await;
+
+    let mut frame = vec![0x82, 0x10];
+    frame.extend(ENQUIRE_LINK);
+    stream.write_all(&frame).await.unwrap();
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await.unwrap();
+    // close, with no reason
+    assert_eq!(response, b"\x88\x00");
+}
+
+#[tokio::test]
+async fn connections_that_never_finish_the_handshake_are_closed() {
+    let websocket_address = format!("127.0.0.1:{}", next_port());
+    let _server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.websocket_address = Some(websocket_address.clone());
+        c.websocket_handshake_timeout_secs = 1;
+    })
+    .await
+    .unwrap();
+    let mut stream = TcpStream::connect(&websocket_address).await.unwrap();
+    stream.write_all(b"GET /smpp HTTP/1.1\r\n").await.unwrap();
+
+    let mut response = Vec::new();
+    tokio::time::timeout(
+        Duration::from_secs(5),
+        stream.read_to_end(&mut response),
+    )
+    .await
+    .expect("Connection was not closed")
+    .unwrap();
+    assert_eq!(response, b"");
+}
+
+#[tokio::test]
+async fn handshakes_in_progress_count_towards_max_open_sockets() {
+    let websocket_address = format!("127.0.0.1:{}", next_port());
+    let _server = TestServer::start_with_smsc_config(DefaultLogic {}, |c| {
+        c.websocket_address = Some(websocket_address.clone());
+        c.max_open_sockets = 1;
+    })
+    .await
+    .unwrap();
+    let _idle = TcpStream::connect(&websocket_address).await.unwrap();
+
+    let mut stream = TcpStream::connect(&websocket_address).await.unwrap();
+    let mut response = Vec::new();
+    tokio::time::timeout(
+        Duration::from_secs(5),
+        stream.read_to_end(&mut response),
+    )
+    .await
+    .expect("Connection was not refused")
+    .unwrap();
+    assert_eq!(response, b"");
+}
+
+#[tokio::test]
+async fn messages_longer_than_the_max_pdu_length_close_the_websocket() {
+    let (_server, address) = start_with_max_pdu_length(64).await;
+    let mut stream = TcpStream::connect(&address).await.unwrap();
+    handshake(&mut stream).await;
+
+    // Six whole PDUs, but 96 bytes in one message
+    stream
+        .write_all(&masked_frame(0x82, &ENQUIRE_LINK.repeat(6)))
+        .await
+        .unwrap();
+
+    // A close frame, not enquire_link_resps
+    assert_eq!(stream.read_u8().await.unwrap(), 0x88);
+}